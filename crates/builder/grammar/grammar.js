@@ -71,7 +71,7 @@ module.exports = grammar({
       optional(field("doc_comment", $.doc_comment)),
       repeat(field("annotation", $.annotation)),
       optional(field("is_exported", "export")),
-      field("method_type", choice("query", "mutation")),
+      field("method_type", choice("query", "mutation", "subscription")),
       field("name", $.term),
       "(",
       optional(commaSep(field("args", $.argument))),
@@ -277,6 +277,7 @@ module.exports = grammar({
     projection_atom: $ => choice(
       seq("/", field("name", $.term)),                                     // /basic (self-projection)
       prec(1, seq(field("relation", $.term), "/", field("name", $.term))), // owner/basic (relation projection)
+      prec(1, seq(field("qualifier", $.term), ".", field("name", $.term))), // AuthContext.id (context field, used by sessionContext)
       field("field", $.term),                                               // id (field name)
     ),
     term: $ => /[a-zA-Z_][a-zA-Z0-9_]*/,
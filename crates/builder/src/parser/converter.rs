@@ -629,7 +629,8 @@ fn convert_annotation_params(
 /// The node is an `access_expr`, `object_literal`, or `projection_expr` (from annotation_map_param grammar).
 /// - `access_expr` containing a `literal` → `AstAnnotationParam::Literal`
 /// - `object_literal` → `AstAnnotationParam::ObjectLiteral`
-/// - `projection_expr` → `AstAnnotationParam::Projection`
+/// - `projection_expr` of context fields (such as `[AuthContext.id]`) → `AstAnnotationParam::StringList`
+/// - other `projection_expr` → `AstAnnotationParam::Projection`
 /// - other `access_expr` → `AstAnnotationParam::AccessExpr`
 fn convert_annotation_value(
     node: Node,
@@ -647,6 +648,9 @@ fn convert_annotation_value(
             }
         }
         "object_literal" => convert_object_literal_param(node, source, source_span),
+        "projection_expr" if is_context_field_list(node) => {
+            convert_context_field_list(node, source, source_span)
+        }
         "projection_expr" => {
             AstAnnotationParam::Projection(convert_projection_expr(node, source, source_span))
         }
@@ -654,6 +658,39 @@ fn convert_annotation_value(
     }
 }
 
+/// Does the list contain a context field (such as `AuthContext.id`)?
+fn is_context_field_list(node: Node) -> bool {
+    let mut cursor = node.walk();
+    node.children_by_field_name("element", &mut cursor)
+        .any(|child| child.child_by_field_name("qualifier").is_some())
+}
+
+/// Convert a list such as `[AuthContext.id, AuthContext.tenantId]` to a string list (each
+/// element as written), leaving it to the consumer to validate the elements
+fn convert_context_field_list(
+    node: Node,
+    source: &[u8],
+    source_span: Span,
+) -> AstAnnotationParam<Untyped> {
+    let mut cursor = node.walk();
+    let (elements, spans) = node
+        .children_by_field_name("element", &mut cursor)
+        .map(|child| {
+            let element = match child.child_by_field_name("qualifier") {
+                Some(_) => format!(
+                    "{}.{}",
+                    text_child(child, source, "qualifier"),
+                    text_child(child, source, "name")
+                ),
+                None => child.utf8_text(source).unwrap().to_string(),
+            };
+            (element, span_from_node(source_span, child))
+        })
+        .unzip();
+
+    AstAnnotationParam::StringList(elements, spans)
+}
+
 fn convert_projection_expr(node: Node, source: &[u8], source_span: Span) -> AstProjectionExpr {
     let mut cursor = node.walk();
     let elements: Vec<AstProjectionExpr> = node
@@ -121,6 +121,37 @@ fn populate_annotation_env(
                 ]),
             },
         ),
+        (
+            "cache",
+            AnnotationSpec {
+                targets: &[AnnotationTarget::Type, AnnotationTarget::Method],
+                no_params: false,
+                single_params: true,
+                mapped_params: MappedAnnotationParams::Specific(&[
+                    MappedAnnotationParamSpec {
+                        name: "maxAge",
+                        optional: true,
+                    },
+                    MappedAnnotationParamSpec {
+                        name: "scope",
+                        optional: true,
+                    },
+                    MappedAnnotationParamSpec {
+                        name: "ttl",
+                        optional: true,
+                    },
+                ]),
+            },
+        ),
+        (
+            "cost",
+            AnnotationSpec {
+                targets: &[AnnotationTarget::Type, AnnotationTarget::Method],
+                no_params: false,
+                single_params: true,
+                mapped_params: MappedAnnotationParams::None,
+            },
+        ),
         (
             "cookie",
             AnnotationSpec {
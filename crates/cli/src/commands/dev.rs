@@ -15,7 +15,7 @@ use common::env_const::EXO_POSTGRES_READ_WRITE;
 use common::env_processing::EnvProcessing;
 use exo_env::{Environment, MapEnvironment};
 use exo_sql_pg_connect::{DatabaseClient, TransactionMode};
-use exo_sql_pg_schema::{Migration, VerificationErrors};
+use exo_sql_pg_schema::{Migration, MigrationMode, VerificationErrors};
 use futures::FutureExt;
 use std::{path::PathBuf, sync::Arc};
 
@@ -135,7 +135,7 @@ impl CommandDefinition for DevCommandDefinition {
 
                     match verification_result {
                         Err(e @ VerificationErrors::ModelNotCompatible(_)) => {
-                            let migrations = Migration::from_db_and_model(&db_client, &database, &migration_scope).await?;
+                            let migrations = Migration::from_db_and_model(&db_client, &database, &migration_scope, MigrationMode::Standard).await?;
 
                             // If migrations are safe to apply, let's go ahead with those
                             if !migrations.has_destructive_changes() {
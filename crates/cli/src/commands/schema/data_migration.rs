@@ -0,0 +1,223 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Run data migrations (mutations, typically implemented by Deno modules) in the middle of a
+//! schema migration.
+
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::{Result, anyhow};
+use colored::Colorize;
+use serde_json::Value;
+use tokio::sync::Mutex;
+
+use common::{
+    context::RequestContext,
+    env_const::{
+        EXO_CHECK_CONNECTION_ON_STARTUP, EXO_CONNECTION_POOL_SIZE, EXO_DATA_MIGRATION,
+        EXO_POSTGRES_URL, get_graphql_http_path,
+    },
+    http::{MemoryRequestHead, MemoryRequestPayload},
+    operation_payload::OperationsPayload,
+    router::{PlainRequestPayload, Router},
+};
+use core_plugin_shared::serializable_system::SerializableSystem;
+use exo_env::{Environment, MapEnvironment};
+use exo_sql_pg_connect::{DatabaseClient, TransactionHolder};
+use exo_sql_pg_schema::{DataMigration, StagedMigration};
+use system_router::{SystemRouter, create_system_router_from_system};
+
+/// Apply a staged migration in a single transaction: the schema changes before the data
+/// migrations, the data migrations, and the remaining schema changes.
+pub(super) async fn apply_staged_migration(
+    client: DatabaseClient,
+    migration: &StagedMigration,
+    allow_destructive_changes: bool,
+    system: SerializableSystem,
+    database_url: Option<&str>,
+    env: Arc<dyn Environment>,
+) -> Result<()> {
+    // Check both parts before running anything, since the statements that run before the
+    // transaction can't be rolled back
+    for part in [&migration.before, &migration.after] {
+        part.check_allowed(allow_destructive_changes)?;
+    }
+
+    for part in [&migration.before, &migration.after] {
+        part.execute_before_transaction(&client, allow_destructive_changes)
+            .await?;
+    }
+
+    client.batch_execute("BEGIN").await?;
+
+    if let Err(e) = migration
+        .before
+        .execute_in_transaction(&client, allow_destructive_changes)
+        .await
+    {
+        client.batch_execute("ROLLBACK").await?;
+        return Err(e.into());
+    }
+
+    let (client, result) = run_data_migrations(
+        client,
+        migration.data_migrations.clone(),
+        system,
+        router_env(database_url, env),
+    )
+    .await?;
+
+    let result = match result {
+        Ok(()) => migration
+            .after
+            .execute_in_transaction(&client, allow_destructive_changes)
+            .await
+            .map_err(|e| e.into()),
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(()) => {
+            client.batch_execute("COMMIT").await?;
+            Ok(())
+        }
+        Err(e) => {
+            client.batch_execute("ROLLBACK").await?;
+            Err(e)
+        }
+    }
+}
+
+/// Run the data migrations on the client (which is in the migration transaction), returning the
+/// client back along with the result.
+async fn run_data_migrations(
+    client: DatabaseClient,
+    data_migrations: Vec<DataMigration>,
+    system: SerializableSystem,
+    env: Arc<dyn Environment>,
+) -> Result<(DatabaseClient, Result<()>)> {
+    tokio::task::spawn_blocking(move || {
+        // Deno's async ops require a current-thread runtime (see `get_introspection_result` in the
+        // testing crate)
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let local = tokio::task::LocalSet::new();
+
+        local.block_on(&runtime, async move {
+            // Route all database work through the migration transaction (including queries that
+            // the mutations make through the `Exograph` API)
+            let transaction_holder = Arc::new(Mutex::new(
+                TransactionHolder::with_transaction(client).await,
+            ));
+
+            let result = match create_system_router_from_system(
+                system,
+                server_common::create_static_loaders(),
+                env.clone(),
+            )
+            .await
+            {
+                Ok(router) => {
+                    run_with_router(&router, &data_migrations, &transaction_holder, env.as_ref())
+                        .await
+                }
+                Err(e) => Err(anyhow!("Failed to load the model for data migrations: {e}")),
+            };
+
+            let client = transaction_holder
+                .lock()
+                .await
+                .take_client()
+                .await
+                .ok_or_else(|| anyhow!("The database client was lost during data migrations"))?;
+
+            Ok((client, result))
+        })
+    })
+    .await?
+}
+
+async fn run_with_router(
+    router: &SystemRouter,
+    data_migrations: &[DataMigration],
+    transaction_holder: &Arc<Mutex<TransactionHolder>>,
+    env: &dyn Environment,
+) -> Result<()> {
+    for data_migration in data_migrations {
+        println!(
+            "{}",
+            format!(
+                "Populating {}.\"{}\" with the `{}` mutation...",
+                data_migration.table.sql_name(),
+                data_migration.column,
+                data_migration.mutation
+            )
+            .blue()
+        );
+
+        let request_head = MemoryRequestHead::new(
+            HashMap::new(),
+            HashMap::new(),
+            http::Method::POST,
+            get_graphql_http_path(env),
+            Value::default(),
+            None,
+        );
+
+        let operations_payload = OperationsPayload {
+            operation_name: None,
+            query: Some(format!("mutation {{ {} }}", data_migration.mutation)),
+            variables: None,
+            query_hash: None,
+            uploads: vec![],
+        };
+        let request = MemoryRequestPayload::new(operations_payload.to_json()?, request_head);
+
+        let jwt_authenticator = None;
+        let mut base_context =
+            RequestContext::new(&request, vec![], router, &jwt_authenticator, env);
+        base_context.system_context.transaction_holder = transaction_holder.clone();
+
+        // Run the mutation as an internal request (like those made through the `Exograph` API),
+        // which isn't subject to trusted documents and leaves the transaction to the migration
+        let request_context = base_context.with_request(&request);
+
+        let response = router
+            .route(&PlainRequestPayload::internal(&request_context))
+            .await
+            .ok_or_else(|| anyhow!("No response for the `{}` mutation", data_migration.mutation))?;
+
+        let body = response.body.to_json().await?;
+
+        if let Some(errors) = body.get("errors") {
+            return Err(anyhow!(
+                "The data migration `{}` failed: {errors}",
+                data_migration.mutation
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn router_env(database_url: Option<&str>, env: Arc<dyn Environment>) -> Arc<dyn Environment> {
+    let mut router_env = MapEnvironment::new_with_fallback(env);
+
+    if let Some(database_url) = database_url {
+        router_env.set(EXO_POSTGRES_URL, database_url);
+    }
+    // The data migrations use the migration's connection, so the router needs no connections of
+    // its own
+    router_env.set(EXO_CONNECTION_POOL_SIZE, "1");
+    router_env.set(EXO_CHECK_CONNECTION_ON_STARTUP, "false");
+    router_env.set(EXO_DATA_MIGRATION, "true");
+
+    Arc::new(router_env)
+}
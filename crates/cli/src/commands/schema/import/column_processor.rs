@@ -47,7 +47,7 @@ fn column_default_to_model(default: &ColumnDefault) -> Option<String> {
 
 use super::{
     ImportContext,
-    traits::{ImportWriter, ModelImporter},
+    traits::{ImportWriter, ModelImporter, write_doc_comments},
 };
 
 #[derive(Debug)]
@@ -59,6 +59,7 @@ pub struct FieldImport {
     pub is_nullable: bool,
     pub annotations: Vec<String>,
     pub default_value: Option<String>,
+    pub doc_comments: Option<String>,
 }
 
 #[derive(Debug)]
@@ -117,6 +118,7 @@ impl ModelImporter<TableSpec, FieldImport> for ColumnSpec {
             is_nullable: self.is_nullable,
             annotations: all_annotations,
             default_value,
+            doc_comments: self.comment.clone(),
         })
     }
 }
@@ -174,6 +176,12 @@ impl ImportWriter for FieldImport {
     fn write_to(self, writer: &mut (dyn Write + Send)) -> Result<()> {
         const INDENT: &str = "  ";
 
+        write_doc_comments(
+            writer,
+            &format!("{INDENT}{INDENT}"),
+            self.doc_comments.as_deref(),
+        )?;
+
         write!(writer, "{INDENT}{INDENT}")?;
 
         // [@pk] [type-annotations] [name]: [data-type] = [default-value]
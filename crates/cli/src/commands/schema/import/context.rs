@@ -156,19 +156,53 @@ impl<'a> ImportContext<'a> {
         (field_name, needs_column_annotation)
     }
 
-    /// Converts the name of a SQL table to a exograph model name (for example, concert_artist -> ConcertArtist).
-    pub(super) fn add_table(&mut self, table_name: &SchemaObjectName) {
-        if let Some(schema) = &table_name.schema {
-            self.schemas.insert(schema.clone());
-        } else {
-            self.schemas.insert("public".to_string());
+    /// Converts the names of SQL tables to exograph model names. Tables with the same name in
+    /// different schemas get a model name qualified with the schema (`auth.users` and `billing.users`
+    /// -> `AuthUser` and `BillingUser`), except for the one in the public schema (which keeps `User`).
+    pub(super) fn add_tables(&mut self, table_names: &[SchemaObjectName]) {
+        let mut schemas_by_model_name: HashMap<String, HashSet<Option<&str>>> = HashMap::new();
+        for table_name in table_names {
+            schemas_by_model_name
+                .entry(self.standard_model_name(table_name))
+                .or_default()
+                .insert(table_name.schema.as_deref());
         }
 
+        for table_name in table_names {
+            let model_name = self.standard_model_name(table_name);
+
+            let model_name = match &table_name.schema {
+                Some(schema) if schemas_by_model_name[&model_name].len() > 1 => {
+                    qualified_model_name(schema, &model_name)
+                }
+                _ => model_name,
+            };
+
+            self.add_table_with_proposed_name(table_name, model_name);
+        }
+    }
+
+    /// Converts the name of a SQL table to a exograph model name (for example, concert_artist -> ConcertArtist).
+    pub(super) fn add_table(&mut self, table_name: &SchemaObjectName) {
         let model_name = self.standard_model_name(table_name);
 
+        // If a table in another schema already took the model name, qualify it with the schema
+        let model_name = match &table_name.schema {
+            Some(schema) if self.is_model_name_taken(&model_name) => {
+                qualified_model_name(schema, &model_name)
+            }
+            _ => model_name,
+        };
+
+        self.add_table_with_proposed_name(table_name, model_name);
+    }
+
+    fn add_table_with_proposed_name(&mut self, table_name: &SchemaObjectName, model_name: String) {
+        self.add_schema(table_name);
+
         // If the model name is already taken, try adding a number to the end.
         fn create_unique_model_name(
-            table_name_to_model_name: &HashMap<SchemaObjectName, String>,
+            context: &ImportContext,
             model_name: &str,
             attempt: u32,
         ) -> String {
@@ -178,20 +212,42 @@ impl<'a> ImportContext<'a> {
                 format!("{}{}", model_name, attempt)
             };
 
-            if table_name_to_model_name
-                .values()
-                .any(|name| name == &name_proposal)
-            {
-                create_unique_model_name(table_name_to_model_name, model_name, attempt + 1)
+            if context.is_model_name_taken(&name_proposal) {
+                create_unique_model_name(context, model_name, attempt + 1)
             } else {
                 name_proposal
             }
         }
 
-        self.table_name_to_model_name.insert(
-            table_name.clone(),
-            create_unique_model_name(&self.table_name_to_model_name, &model_name, 0),
-        );
+        let model_name = create_unique_model_name(self, &model_name, 0);
+        self.table_name_to_model_name
+            .insert(table_name.clone(), model_name);
+    }
+
+    fn is_model_name_taken(&self, model_name: &str) -> bool {
+        self.table_name_to_model_name
+            .values()
+            .any(|name| name == model_name)
+    }
+
+    /// Keeps the model name that an existing model uses for the table (when merging into it)
+    pub(super) fn add_table_with_model_name(
+        &mut self,
+        table_name: &SchemaObjectName,
+        model_name: &str,
+    ) {
+        self.add_schema(table_name);
+
+        self.table_name_to_model_name
+            .insert(table_name.clone(), model_name.to_string());
+    }
+
+    fn add_schema(&mut self, table_name: &SchemaObjectName) {
+        if let Some(schema) = &table_name.schema {
+            self.schemas.insert(schema.clone());
+        } else {
+            self.schemas.insert("public".to_string());
+        }
     }
 
     pub(super) fn referenced_columns(
@@ -301,6 +357,11 @@ impl<'a> ImportContext<'a> {
     }
 }
 
+/// The model name for a table in a non-public schema (`billing`, `User` -> `BillingUser`)
+fn qualified_model_name(schema: &str, model_name: &str) -> String {
+    format!("{}{model_name}", schema.to_upper_camel_case())
+}
+
 fn reference_field_name(column_name: &str, reference: &ColumnReferenceSpec) -> String {
     if column_name.ends_with(&format!("_{}", reference.foreign_pk_column_name)) {
         // Drop the trailing underscore and the foreign key column name
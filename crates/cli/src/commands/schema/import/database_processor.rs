@@ -19,6 +19,7 @@ pub struct ModuleImport {
     pub schema: Option<String>,
     pub module_name: String,
     pub is_fragment: bool,
+    pub validation_checks: bool,
     pub tables: Vec<super::table_processor::TableImport>,
     pub enums: Vec<super::enum_processor::EnumImport>,
 }
@@ -74,6 +75,7 @@ impl ModelImporter<(), DatabaseImport> for DatabaseSpec {
                 schema: schema.clone(),
                 module_name,
                 is_fragment: context.generate_fragments,
+                validation_checks: tables.iter().any(|table| table.validation_checks),
                 tables,
                 enums,
             });
@@ -101,11 +103,19 @@ impl ImportWriter for DatabaseImport {
 impl ImportWriter for ModuleImport {
     fn write_to(self, writer: &mut (dyn Write + Send)) -> Result<()> {
         // Write @postgres annotation
-        write!(writer, "@postgres")?;
+        let mut params = vec![];
         if let Some(schema) = &self.schema
             && !self.is_fragment
         {
-            write!(writer, "(schema=\"{schema}\")")?;
+            params.push(format!("schema=\"{schema}\""));
+        }
+        if self.validation_checks {
+            params.push("validationChecks=true".to_string());
+        }
+
+        write!(writer, "@postgres")?;
+        if !params.is_empty() {
+            write!(writer, "({})", params.join(", "))?;
         }
         writeln!(writer)?;
 
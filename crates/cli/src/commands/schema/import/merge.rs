@@ -0,0 +1,554 @@
+//! Merge an import into an existing model.
+//!
+//! Instead of regenerating the model, the merge keeps the existing source (along with any changes
+//! made to it since the import, such as access rules, doc comments, and projections) and inserts
+//! only the tables and columns that the model doesn't have yet. Tables and columns that are no
+//! longer in the database are flagged with a comment (and a warning), so that the user can decide
+//! how to handle them.
+
+use std::path::Path;
+
+use anyhow::{Result, anyhow};
+use codemap::{CodeMap, Pos};
+use colored::Colorize;
+use heck::ToSnakeCase;
+
+use core_model_builder::ast::ast_types::{
+    AstAnnotation, AstAnnotationParam, AstAnnotationParams, AstField, AstFieldType, AstLiteral,
+    AstModel, AstModelKind, AstModule, Untyped,
+};
+use exo_sql_pg::SchemaObjectName;
+use exo_sql_pg_schema::{DatabaseSpec, MigrationScope};
+use postgres_core_builder::naming::ToTableName;
+
+use super::{
+    ImportContext,
+    column_processor::{FieldImport, FieldImportKind},
+    database_processor::ModuleImport,
+    traits::{ImportWriter, ModelImporter},
+};
+
+pub(super) fn merge_exo_model(
+    existing_source: &str,
+    existing_file: &Path,
+    database_spec: &DatabaseSpec,
+    scope: &MigrationScope,
+    query_access: bool,
+    mutation_access: bool,
+) -> Result<String> {
+    let mut codemap = CodeMap::new();
+    let system =
+        builder::parser::parse_str(existing_source, &mut codemap, existing_file).map_err(|e| {
+            anyhow!(
+                "Failed to parse `{}` ({e}). Fix the model before merging into it.",
+                existing_file.display()
+            )
+        })?;
+
+    let source = ExistingSource {
+        source: existing_source,
+        codemap: &codemap,
+    };
+
+    let modules = system
+        .modules
+        .iter()
+        .filter(|module| find_annotation(&module.annotations, "postgres").is_some())
+        .map(|module| ExistingModule::new(module, &source))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut context = ImportContext::new(database_spec, query_access, mutation_access, false);
+
+    // Keep the model names of the existing types (so that the new fields refer to them) and name
+    // only the new tables
+    for existing_type in modules.iter().flat_map(|module| &module.types) {
+        if database_spec
+            .tables
+            .iter()
+            .any(|table| table.name == existing_type.table_name)
+        {
+            context.add_table_with_model_name(&existing_type.table_name, &existing_type.name);
+        }
+    }
+    for table in &database_spec.tables {
+        if context.model_name(&table.name).is_none() {
+            context.add_table(&table.name);
+        }
+    }
+
+    let database_import = database_spec.to_import(&(), &context)?;
+
+    let existing_enum_names = system
+        .modules
+        .iter()
+        .flat_map(|module| &module.enums)
+        .map(|enum_| enum_.name.as_str())
+        .collect::<Vec<_>>();
+
+    let mut edits = Edits::default();
+    let mut new_modules = vec![];
+
+    for module_import in database_import.modules {
+        let mut new_tables = vec![];
+
+        for table_import in module_import.tables {
+            let existing_type =
+                modules
+                    .iter()
+                    .flat_map(|module| &module.types)
+                    .find(|existing_type| {
+                        existing_type.name == table_import.name
+                            && context.model_name(&existing_type.table_name)
+                                == Some(existing_type.name.as_str())
+                    });
+
+            let Some(existing_type) = existing_type else {
+                new_tables.push(table_import);
+                continue;
+            };
+
+            for existing_field in &existing_type.fields {
+                if !table_import
+                    .fields
+                    .iter()
+                    .any(|field| existing_field.matches(field))
+                {
+                    edits.flag(
+                        existing_source,
+                        existing_field.start,
+                        &format!(
+                            "The database no longer has the column for `{}.{}`",
+                            existing_type.name, existing_field.name
+                        ),
+                    );
+                }
+            }
+
+            for field in table_import.fields {
+                if !existing_type
+                    .fields
+                    .iter()
+                    .any(|existing_field| existing_field.matches(&field))
+                {
+                    println!(
+                        "Adding field {}",
+                        format!("{}.{}", existing_type.name, field.name).cyan()
+                    );
+                    edits.insert_before_closing_brace(
+                        existing_source,
+                        existing_type.closing_brace,
+                        render(field)?,
+                    );
+                }
+            }
+        }
+
+        let new_enums = module_import
+            .enums
+            .into_iter()
+            .filter(|enum_| !existing_enum_names.contains(&enum_.name.as_str()))
+            .collect::<Vec<_>>();
+
+        let existing_module = modules
+            .iter()
+            .find(|module| module.schema == module_import.schema);
+
+        match existing_module {
+            Some(existing_module) => {
+                for table in new_tables {
+                    println!("Adding type {}", table.name.cyan());
+                    edits.insert_before_closing_brace(
+                        existing_source,
+                        existing_module.closing_brace,
+                        format!("\n{}", render(table)?),
+                    );
+                }
+
+                for enum_ in new_enums {
+                    println!("Adding enum {}", enum_.name.cyan());
+                    edits.insert_before_closing_brace(
+                        existing_source,
+                        existing_module.closing_brace,
+                        format!("\n{}", render(enum_)?),
+                    );
+                }
+            }
+            None => {
+                if !new_tables.is_empty() || !new_enums.is_empty() {
+                    println!("Adding module {}", module_import.module_name.cyan());
+                    new_modules.push(ModuleImport {
+                        tables: new_tables,
+                        enums: new_enums,
+                        ..module_import
+                    });
+                }
+            }
+        }
+    }
+
+    for existing_type in modules.iter().flat_map(|module| &module.types) {
+        let in_scope = match scope {
+            MigrationScope::Specified(scope_matches) => {
+                scope_matches.matches(&existing_type.table_name)
+            }
+            MigrationScope::FromNewSpec => true,
+        };
+
+        if in_scope
+            && !database_spec
+                .tables
+                .iter()
+                .any(|table| table.name == existing_type.table_name)
+        {
+            edits.flag(
+                existing_source,
+                existing_type.start,
+                &format!(
+                    "The database no longer has the table for `{}` ({})",
+                    existing_type.name,
+                    existing_type.table_name.sql_name()
+                ),
+            );
+        }
+    }
+
+    for module in new_modules {
+        let separator = if existing_source.ends_with('\n') {
+            "\n"
+        } else {
+            "\n\n"
+        };
+        edits.insert(
+            existing_source.len(),
+            format!("{separator}{}", render(module)?),
+        );
+    }
+
+    Ok(edits.apply(existing_source))
+}
+
+struct ExistingSource<'a> {
+    source: &'a str,
+    codemap: &'a CodeMap,
+}
+
+impl ExistingSource<'_> {
+    /// The byte offset of the position in the source
+    fn offset(&self, pos: Pos) -> usize {
+        let file = self.codemap.find_file(pos);
+        (pos - file.span.low()) as usize
+    }
+}
+
+/// A `@postgres` module in the existing model
+struct ExistingModule {
+    schema: Option<String>,
+    types: Vec<ExistingType>,
+    closing_brace: usize,
+}
+
+impl ExistingModule {
+    fn new(module: &AstModule<Untyped>, source: &ExistingSource) -> Result<Self> {
+        let schema = find_annotation(&module.annotations, "postgres")
+            .and_then(|annotation| string_param(annotation, Some("schema")))
+            .filter(|schema| *schema != "public")
+            .map(|schema| schema.to_string());
+
+        let types = module
+            .types
+            .iter()
+            .filter(|typ| typ.kind == AstModelKind::Type)
+            .map(|typ| ExistingType::new(typ, &schema, source))
+            .collect::<Result<Vec<_>>>()?;
+
+        let module_end = source.offset(module.span.high());
+        let closing_brace = source.source[..module_end]
+            .rfind('}')
+            .ok_or_else(|| anyhow!("Could not find the end of the module `{}`", module.name))?;
+
+        Ok(Self {
+            schema,
+            types,
+            closing_brace,
+        })
+    }
+}
+
+/// A type in a `@postgres` module of the existing model
+struct ExistingType {
+    name: String,
+    table_name: SchemaObjectName,
+    fields: Vec<ExistingField>,
+    /// The offset of the declaration (including its annotations)
+    start: usize,
+    closing_brace: usize,
+}
+
+impl ExistingType {
+    fn new(
+        typ: &AstModel<Untyped>,
+        module_schema: &Option<String>,
+        source: &ExistingSource,
+    ) -> Result<Self> {
+        let table_annotation = find_annotation(&typ.annotations, "table");
+
+        let table_name = table_annotation
+            .and_then(|annotation| {
+                string_param(annotation, None).or_else(|| string_param(annotation, Some("name")))
+            })
+            .map(|name| name.to_string())
+            .unwrap_or_else(|| {
+                let plural_name = find_annotation(&typ.annotations, "plural")
+                    .and_then(|annotation| string_param(annotation, None))
+                    .map(|name| name.to_string());
+                typ.name.table_name(plural_name)
+            });
+
+        let schema = table_annotation
+            .and_then(|annotation| string_param(annotation, Some("schema")))
+            .map(|schema| schema.to_string())
+            .or_else(|| module_schema.clone());
+
+        let start = typ
+            .annotations
+            .iter()
+            .map(|annotation| annotation.span.low())
+            .chain(std::iter::once(typ.span.low()))
+            .min()
+            .map(|pos| source.offset(pos))
+            .unwrap_or_default();
+
+        let closing_brace =
+            matching_closing_brace(source.source, source.offset(typ.span.high()))
+                .ok_or_else(|| anyhow!("Could not find the end of the type `{}`", typ.name))?;
+
+        Ok(Self {
+            name: typ.name.clone(),
+            table_name: SchemaObjectName::new(table_name, schema.as_deref()),
+            fields: typ
+                .fields
+                .iter()
+                .map(|field| ExistingField::new(field, source))
+                .collect(),
+            start,
+            closing_brace,
+        })
+    }
+}
+
+struct ExistingField {
+    name: String,
+    column_name: String,
+    type_name: String,
+    relation_name: Option<String>,
+    start: usize,
+}
+
+impl ExistingField {
+    fn new(field: &AstField<Untyped>, source: &ExistingSource) -> Self {
+        let column_name = find_annotation(&field.annotations, "column")
+            .and_then(|annotation| string_param(annotation, None))
+            .map(|name| name.to_string())
+            .unwrap_or_else(|| field.name.to_snake_case());
+
+        let relation_name = find_annotation(&field.annotations, "relation")
+            .and_then(|annotation| string_param(annotation, None))
+            .map(|name| name.to_string());
+
+        Self {
+            name: field.name.clone(),
+            column_name,
+            type_name: type_name(&field.typ),
+            relation_name,
+            start: source.offset(field.span.low()),
+        }
+    }
+
+    /// Does the field represent the same column (or relation) as the imported field? A field
+    /// renamed since the import still matches through its `@column` (or type, for relations
+    /// without a column).
+    fn matches(&self, field: &FieldImport) -> bool {
+        if self.name == field.name {
+            return true;
+        }
+
+        match field.field_kind {
+            FieldImportKind::BackReference { .. } => {
+                self.type_name == field.data_type
+                    && self.relation_name.as_deref()
+                        == quoted_annotation_value(&field.annotations, "@relation")
+            }
+            _ => {
+                let column_name = quoted_annotation_value(&field.annotations, "@column")
+                    .map(|name| name.to_string())
+                    .unwrap_or_else(|| field.name.to_snake_case());
+                self.column_name == column_name
+            }
+        }
+    }
+}
+
+/// Insertions into the existing source
+#[derive(Default)]
+struct Edits(Vec<(usize, String)>);
+
+impl Edits {
+    fn insert(&mut self, offset: usize, text: String) {
+        self.0.push((offset, text));
+    }
+
+    /// Insert lines (each ending with a newline) just before a closing brace
+    fn insert_before_closing_brace(&mut self, source: &str, closing_brace: usize, lines: String) {
+        let line_start = line_start(source, closing_brace);
+
+        if source[line_start..closing_brace].trim().is_empty() {
+            self.insert(line_start, lines);
+        } else {
+            self.insert(closing_brace, format!("\n{lines}"));
+        }
+    }
+
+    /// Add a comment above the line with the offset (unless an earlier merge added it already)
+    fn flag(&mut self, source: &str, offset: usize, message: &str) {
+        eprintln!("{}", message.yellow());
+
+        let line_start = line_start(source, offset);
+        let indent = source[line_start..]
+            .chars()
+            .take_while(|c| *c == ' ' || *c == '\t')
+            .collect::<String>();
+        let comment = format!("// {message}");
+
+        let previous_line = source[..line_start]
+            .trim_end_matches('\n')
+            .rsplit('\n')
+            .next()
+            .unwrap_or_default();
+
+        if previous_line.trim() != comment {
+            self.insert(line_start, format!("{indent}{comment}\n"));
+        }
+    }
+
+    fn apply(mut self, source: &str) -> String {
+        // Stable sort to keep the insertions at the same offset in order
+        self.0.sort_by_key(|(offset, _)| *offset);
+
+        let mut result = String::with_capacity(source.len());
+        let mut copied = 0;
+        for (offset, text) in self.0 {
+            result.push_str(&source[copied..offset]);
+            result.push_str(&text);
+            copied = offset;
+        }
+        result.push_str(&source[copied..]);
+
+        result
+    }
+}
+
+fn render(import: impl ImportWriter) -> Result<String> {
+    let mut buffer = Vec::new();
+    import.write_to(&mut buffer)?;
+    Ok(String::from_utf8(buffer)?)
+}
+
+fn line_start(source: &str, offset: usize) -> usize {
+    source[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0)
+}
+
+/// Find the brace that closes the first block opening after the offset (skipping strings and
+/// comments)
+fn matching_closing_brace(source: &str, from: usize) -> Option<usize> {
+    let bytes = source.as_bytes();
+    let mut depth = 0;
+    let mut i = from;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    if bytes[i] == b'\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i + 1 < bytes.len() && !(bytes[i] == b'*' && bytes[i + 1] == b'/') {
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    None
+}
+
+fn find_annotation<'a>(
+    annotations: &'a [AstAnnotation<Untyped>],
+    name: &str,
+) -> Option<&'a AstAnnotation<Untyped>> {
+    annotations
+        .iter()
+        .find(|annotation| annotation.name == name)
+}
+
+/// The string value of the single parameter (if `key` is `None`) or the named parameter of an
+/// annotation
+fn string_param<'a>(annotation: &'a AstAnnotation<Untyped>, key: Option<&str>) -> Option<&'a str> {
+    let param = match (&annotation.params, key) {
+        (AstAnnotationParams::Single(param, _), None) => Some(param),
+        (AstAnnotationParams::Map(params, _), Some(key)) => params.get(key),
+        _ => None,
+    };
+
+    match param {
+        Some(AstAnnotationParam::Literal(AstLiteral::String(value, _))) => Some(value),
+        _ => None,
+    }
+}
+
+/// The value of an imported annotation such as `@column("venue_id")`
+fn quoted_annotation_value<'a>(annotations: &'a [String], name: &str) -> Option<&'a str> {
+    annotations.iter().find_map(|annotation| {
+        annotation
+            .strip_prefix(name)?
+            .strip_prefix("(\"")?
+            .strip_suffix("\")")
+    })
+}
+
+/// The name of the type as the importer writes it (for example, `Set<Concert>`), ignoring
+/// optionality
+fn type_name(typ: &AstFieldType<Untyped>) -> String {
+    match typ {
+        AstFieldType::Plain(_, name, type_params, _, _) if type_params.is_empty() => name.clone(),
+        AstFieldType::Plain(_, name, type_params, _, _) => format!(
+            "{name}<{}>",
+            type_params
+                .iter()
+                .map(type_name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        AstFieldType::Optional(underlying) => type_name(underlying),
+    }
+}
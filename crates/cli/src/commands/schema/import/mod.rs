@@ -7,7 +7,7 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use async_trait::async_trait;
 use clap::{Arg, Command};
 use colored::Colorize;
@@ -19,7 +19,7 @@ use exo_sql_pg_schema::WithIssues;
 use exo_sql_pg_schema::{MigrationScope, MigrationScopeMatches};
 
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use crate::commands::command::{
@@ -39,6 +39,7 @@ mod traits;
 mod column_processor;
 pub mod database_processor;
 pub mod enum_processor;
+mod merge;
 pub mod table_processor;
 
 use context::ImportContext;
@@ -53,6 +54,16 @@ impl CommandDefinition for ImportCommandDefinition {
             .arg(database_arg())
             .arg(output_arg())
             .arg(migration_scope_arg())
+            .arg(
+                Arg::new("schemas")
+                    .help("The schemas to import (comma-separated)")
+                    .long_help("The schemas to import (comma-separated, for example `billing,auth,public`). Each schema becomes a separate module. A shorthand for `--scope billing.*,auth.*,public.*`.")
+                    .long("schemas")
+                    .required(false)
+                    .value_parser(clap::value_parser!(String))
+                    .conflicts_with("scope")
+                    .num_args(1),
+            )
             .arg(query_access_arg())
             .arg(mutation_access_arg())
             .arg(
@@ -62,6 +73,14 @@ impl CommandDefinition for ImportCommandDefinition {
                     .required(false)
                     .num_args(0),
             )
+            .arg(
+                Arg::new("merge")
+                    .help("Merge into the existing model file specified by --output")
+                    .long_help("Merge into the existing model file specified by --output instead of overwriting it. Adds the tables and columns that the model doesn't have yet, flags the ones no longer in the database with a comment, and keeps the rest of the model (including annotations, doc comments, and ordering) as is.")
+                    .long("merge")
+                    .required(false)
+                    .num_args(0),
+            )
             .arg(yes_arg())
     }
 
@@ -77,8 +96,40 @@ impl CommandDefinition for ImportCommandDefinition {
         let query_access: bool = query_access_value(matches);
         let mutation_access: bool = mutation_access_value(matches);
         let generate_fragments: bool = matches.get_flag("generate-fragments");
-        let scope: Option<String> = migration_scope_value(matches);
+        let schemas: Option<String> = get(matches, "schemas");
+        let scope: Option<String> = migration_scope_value(matches).or(schemas.map(schemas_scope));
         let yes: bool = yes_value(matches);
+        let merge: bool = matches.get_flag("merge");
+
+        if merge {
+            let Some(output) = &output else {
+                return Err(anyhow!("--merge requires --output"));
+            };
+            if generate_fragments {
+                return Err(anyhow!(
+                    "Cannot specify both --merge and --generate-fragments"
+                ));
+            }
+
+            // Without an existing model, there is nothing to merge into
+            if output.exists() {
+                let db_client = open_database(
+                    database_url.as_deref(),
+                    TransactionMode::ReadOnly,
+                    env.as_ref(),
+                )
+                .await?;
+                let db_client = db_client.get_client().await?;
+
+                let merged =
+                    merge_exo_model(output, &db_client, query_access, mutation_access, scope)
+                        .await?;
+                std::fs::write(output, merged)?;
+
+                println!("\nExograph model merged into `{}`", output.display());
+                return Ok(());
+            }
+        }
 
         let mut writer = open_file_for_output(output.as_deref(), yes)?;
         let db_client = open_database(
@@ -133,9 +184,7 @@ pub(crate) async fn create_exo_model(
         .map(|table| table.name.clone())
         .collect::<Vec<_>>();
 
-    for table in &schema.value.tables {
-        context.add_table(&table.name);
-    }
+    context.add_tables(&table_names);
 
     let database_import = schema.value.to_import(&(), &context)?;
     database_import.write_to(&mut writer)?;
@@ -147,6 +196,44 @@ pub(crate) async fn create_exo_model(
     Ok(table_names)
 }
 
+/// Merge the database schema into an existing model (see the `merge` module)
+pub(crate) async fn merge_exo_model(
+    model_file: &Path,
+    db_client: &DatabaseClient,
+    query_access: bool,
+    mutation_access: bool,
+    scope: Option<String>,
+) -> Result<String> {
+    let existing_source = std::fs::read_to_string(model_file)?;
+
+    let scope = compute_migration_scope(scope);
+    let schema = import_schema(db_client, &scope).await?;
+
+    let merged = merge::merge_exo_model(
+        &existing_source,
+        model_file,
+        &schema.value,
+        &scope,
+        query_access,
+        mutation_access,
+    )?;
+
+    for issue in &schema.issues {
+        eprintln!("{issue}");
+    }
+
+    Ok(merged)
+}
+
+/// The scope that imports all tables in the (comma-separated) schemas
+fn schemas_scope(schemas: String) -> String {
+    schemas
+        .split(',')
+        .map(|schema| format!("{}.*", schema.trim()))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
 async fn import_schema(
     client: &DatabaseClient,
     scope: &MigrationScope,
@@ -156,7 +243,11 @@ async fn import_schema(
         MigrationScope::FromNewSpec => &MigrationScopeMatches::all_schemas(),
     };
 
-    let database = DatabaseSpec::from_live_database(client, scope_matches).await?;
+    let mut database = DatabaseSpec::from_live_database(client, scope_matches).await?;
+
+    let index_issues = table_processor::unexpressible_index_issues(&database.value);
+    database.issues.extend(index_issues);
+
     Ok(database)
 }
 
@@ -236,6 +327,32 @@ mod tests {
         let _ = std::fs::remove_dir_all("generated");
     }
 
+    #[tokio::test]
+    async fn test_unexpressible_index_issues() {
+        with_init_script(
+            r#"
+                CREATE TABLE "subscribers" ("id" SERIAL PRIMARY KEY, "email" TEXT NOT NULL);
+                CREATE INDEX "subscribers_email_idx" ON "subscribers" ("email");
+                CREATE INDEX "subscribers_email_lower_idx" ON "subscribers" (lower("email"));
+            "#,
+            |client| async move {
+                let schema = import_schema(&client, &MigrationScope::all_schemas())
+                    .await
+                    .unwrap();
+
+                let issues = schema
+                    .issues
+                    .iter()
+                    .map(|issue| issue.to_string())
+                    .collect::<Vec<_>>();
+
+                // Other issues with the schema (if any) don't matter here
+                assert!(issues.contains(&"warning: The model leaves out the index 'subscribers_email_lower_idx' on 'subscribers', since a field with other indices can't specify an expression. Add it by hand, or a migration will drop it.".to_string()));
+            },
+        )
+        .await;
+    }
+
     async fn single_test(
         test_name: String,
         test_path: PathBuf,
@@ -243,12 +360,21 @@ mod tests {
         let schema = read_relative_file(&test_path, "schema.sql").unwrap();
 
         with_init_script(&schema, |client| async move {
-            let mut writer = BufWriter::new(Vec::new());
-            create_exo_model(&mut writer, &client, true, false, false, None)
-                .await
-                .unwrap();
+            // Tests with an existing model merge into it (instead of importing afresh)
+            let existing_model_file = test_path.join("index.existing.exo");
 
-            let output = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+            let output = if existing_model_file.exists() {
+                merge_exo_model(&existing_model_file, &client, true, false, None)
+                    .await
+                    .unwrap()
+            } else {
+                let mut writer = BufWriter::new(Vec::new());
+                create_exo_model(&mut writer, &client, true, false, false, None)
+                    .await
+                    .unwrap();
+
+                String::from_utf8(writer.into_inner().unwrap()).unwrap()
+            };
             assert_file_content(&test_path, "index.expected.exo", &output, &test_name)?;
 
             let expected_model_file = test_path.join("index.expected.exo");
@@ -1,5 +1,6 @@
 use anyhow::Result;
-use exo_sql_pg_schema::{ColumnSpec, DatabaseSpec, TableSpec};
+use exo_sql_pg::{FloatColumnType, IndexTarget, IntColumnType, Ordering, StringColumnType};
+use exo_sql_pg_schema::{ColumnSpec, DatabaseSpec, Issue, SimpleCheck, TableSpec};
 use std::collections::{HashMap, HashSet};
 use std::io::Write;
 
@@ -7,7 +8,7 @@ use super::column_processor::FieldImportKind;
 use super::{
     ImportContext,
     column_processor::FieldImport,
-    traits::{ImportWriter, ModelImporter},
+    traits::{ImportWriter, ModelImporter, write_doc_comments},
 };
 
 use heck::{ToLowerCamelCase, ToSnakeCase, ToUpperCamelCase};
@@ -21,6 +22,12 @@ pub struct TableImport {
     pub access_annotation: Option<AccessAnnotation>,
     pub table_annotation: Option<String>,
     pub fields: Vec<FieldImport>,
+    /// The expressions of `CHECK` constraints that no field annotation expresses
+    pub checks: Vec<String>,
+    /// Does any field have a `@range` or `@maxLength` for a `CHECK` constraint (so the module must
+    /// enable `validationChecks`)?
+    pub validation_checks: bool,
+    pub doc_comments: Option<String>,
 }
 
 #[derive(Debug)]
@@ -77,10 +84,19 @@ impl ModelImporter<DatabaseSpec, TableImport> for TableSpec {
         // Categorize columns to determine which should be written as scalars vs consumed by FK references
         let column_categories = self.categorize_columns(context);
 
+        // Checks that `@range` or `@maxLength` can express become annotations on their fields
+        let (validation_annotations, checks) = self.validation_annotations(&column_categories);
+        let validation_checks = !validation_annotations.is_empty();
+
         let mut fields = Vec::new();
 
         // Add scalar fields and foreign key references (these columns exist in this table)
-        self.add_scalar_fields(&mut fields, context, &column_categories)?;
+        self.add_scalar_fields(
+            &mut fields,
+            context,
+            &column_categories,
+            &validation_annotations,
+        )?;
         self.add_foreign_key_references(&mut fields, context, parent)?;
 
         // Add back-references (such as Set<User>, User?, etc.) for which this table is the target
@@ -92,12 +108,21 @@ impl ModelImporter<DatabaseSpec, TableImport> for TableSpec {
             access_annotation,
             table_annotation,
             fields,
+            checks: if context.generate_fragments {
+                vec![]
+            } else {
+                checks
+            },
+            validation_checks: validation_checks && !context.generate_fragments,
+            doc_comments: self.comment.clone(),
         })
     }
 }
 
 impl ImportWriter for TableImport {
     fn write_to(self, writer: &mut (dyn Write + Send)) -> Result<()> {
+        write_doc_comments(writer, INDENT, self.doc_comments.as_deref())?;
+
         // Write access annotation
         if let Some(access) = &self.access_annotation {
             writeln!(
@@ -112,6 +137,16 @@ impl ImportWriter for TableImport {
             writeln!(writer, "{INDENT}{}", table_annotation)?;
         }
 
+        if !self.checks.is_empty() {
+            let checks = self
+                .checks
+                .iter()
+                .map(|check| format!("\"{}\"", escape_string(check)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            writeln!(writer, "{INDENT}@check({checks})")?;
+        }
+
         // Write type/fragment declaration
         let keyword = if self.is_fragment { "fragment" } else { "type" };
         writeln!(writer, "{INDENT}{keyword} {} {{", self.name)?;
@@ -201,6 +236,7 @@ trait TableSpecImportNaming {
         fields: &mut Vec<FieldImport>,
         context: &ImportContext,
         column_categories: &ColumnCategories,
+        validation_annotations: &HashMap<String, Vec<String>>,
     ) -> Result<()>;
 
     /// Add foreign key reference fields to the field list
@@ -211,14 +247,96 @@ trait TableSpecImportNaming {
         database_spec: &DatabaseSpec,
     ) -> Result<()>;
 
-    /// Get all index names that contain the specified column
+    /// Get all index names that contain the specified column (directly or through an expression)
     fn get_indices_for_column(&self, column_name: &str) -> Vec<&str>;
 
     /// Generate index annotation for a column, handling both single and multi-column indices
     fn generate_index_annotation(&self, column_name: &str) -> Option<String>;
+
+    /// The column whose field carries an expression index column: the first column the
+    /// expression refers to
+    fn expression_anchor_column(&self, expression: &str) -> Option<&str>;
+
+    /// The names of the expression indices that no field annotation can express: only a field
+    /// with a single index can specify an expression, so an expression index is left out if any
+    /// field it spans has other indices
+    fn unexpressible_indices(&self) -> Vec<&str>;
+
+    /// Split `CHECK` constraints into `@range` and `@maxLength` annotations (keyed by the column
+    /// name) and the expressions of the remaining constraints
+    fn validation_annotations(
+        &self,
+        column_categories: &ColumnCategories,
+    ) -> (HashMap<String, Vec<String>>, Vec<String>);
 }
 
 impl TableSpecImportNaming for TableSpec {
+    fn validation_annotations(
+        &self,
+        column_categories: &ColumnCategories,
+    ) -> (HashMap<String, Vec<String>>, Vec<String>) {
+        let mut annotations: HashMap<String, Vec<String>> = HashMap::new();
+        let mut remaining_checks = vec![];
+
+        for check in &self.checks {
+            let annotation = check.simple_check().and_then(|simple_check| {
+                let (column_name, annotation) = match &simple_check {
+                    SimpleCheck::Range { column, min, max } => {
+                        let params = min
+                            .iter()
+                            .map(|min| format!("min={min}"))
+                            .chain(max.iter().map(|max| format!("max={max}")))
+                            .collect::<Vec<_>>();
+                        (column.clone(), format!("@range({})", params.join(", ")))
+                    }
+                    SimpleCheck::MaxLength { column, max_length } => {
+                        (column.clone(), format!("@maxLength({max_length})"))
+                    }
+                };
+
+                // The column must be a scalar field of a type that supports the annotation (and
+                // not have the annotation already)
+                let column = self
+                    .columns
+                    .iter()
+                    .find(|column| column.name == column_name)
+                    .filter(|column| {
+                        column_categories
+                            .scalar_columns
+                            .contains(column.name.as_str())
+                    })?;
+                let typ = column.typ.as_any();
+                let applicable = match &simple_check {
+                    SimpleCheck::Range { .. } => {
+                        typ.is::<IntColumnType>() || typ.is::<FloatColumnType>()
+                    }
+                    // `@maxLength` makes the column a `VARCHAR` of that length, so a length check
+                    // on any other column (such as a `TEXT` one) must stay a `@check`
+                    SimpleCheck::MaxLength { max_length, .. } => typ
+                        .downcast_ref::<StringColumnType>()
+                        .is_some_and(|typ| typ.max_length == Some(*max_length)),
+                };
+                let annotation_name = &annotation[..annotation.find('(').unwrap()];
+                let duplicate = annotations.get(&column_name).is_some_and(|annotations| {
+                    annotations
+                        .iter()
+                        .any(|existing| existing.starts_with(annotation_name))
+                });
+
+                (applicable && !duplicate).then_some((column_name, annotation))
+            });
+
+            match annotation {
+                Some((column_name, annotation)) => {
+                    annotations.entry(column_name).or_default().push(annotation)
+                }
+                None => remaining_checks.push(check.expression.clone()),
+            }
+        }
+
+        (annotations, remaining_checks)
+    }
+
     fn categorize_columns<'a>(&'a self, context: &ImportContext) -> ColumnCategories<'a> {
         let pk_columns: HashSet<&str> = self
             .columns
@@ -346,6 +464,7 @@ impl TableSpecImportNaming for TableSpec {
         fields: &mut Vec<FieldImport>,
         context: &ImportContext,
         column_categories: &ColumnCategories,
+        validation_annotations: &HashMap<String, Vec<String>>,
     ) -> Result<()> {
         for column in &self.columns {
             // Add this column as a scalar field if:
@@ -362,6 +481,17 @@ impl TableSpecImportNaming for TableSpec {
                     field_import.annotations.push(index_annotation);
                 }
 
+                // The column's type already gives a `VARCHAR` column its `@maxLength`
+                for annotation in validation_annotations
+                    .get(&column.name)
+                    .into_iter()
+                    .flatten()
+                {
+                    if !field_import.annotations.contains(annotation) {
+                        field_import.annotations.push(annotation.clone());
+                    }
+                }
+
                 fields.push(field_import);
             }
         }
@@ -486,6 +616,10 @@ impl TableSpecImportNaming for TableSpec {
                     is_nullable,
                     annotations,
                     default_value: None, // Foreign key references don't have default values
+                    doc_comments: match &references[..] {
+                        [(column, _)] => column.comment.clone(),
+                        _ => None,
+                    },
                 });
             }
         }
@@ -496,7 +630,17 @@ impl TableSpecImportNaming for TableSpec {
     fn get_indices_for_column(&self, column_name: &str) -> Vec<&str> {
         self.indices
             .iter()
-            .filter(|index| index.columns.contains(column_name))
+            .filter(|index| {
+                index
+                    .columns
+                    .iter()
+                    .any(|index_column| match &index_column.target {
+                        IndexTarget::Column(name) => name == column_name,
+                        IndexTarget::Expression(expression) => {
+                            self.expression_anchor_column(expression) == Some(column_name)
+                        }
+                    })
+            })
             .map(|index| index.name.as_str())
             .collect()
     }
@@ -507,19 +651,60 @@ impl TableSpecImportNaming for TableSpec {
             return None;
         }
 
+        // The import reports these indices (see `unexpressible_index_issues`)
+        let unexpressible_indices = self.unexpressible_indices();
+
         // If there's only one index and it's a single-column index, use simple @index annotation
-        if index_names.len() == 1 {
+        if index_names.len() == 1 && !unexpressible_indices.contains(&index_names[0]) {
             let index_name = index_names[0];
             let index = self
                 .indices
                 .iter()
                 .find(|idx| idx.name == index_name)
                 .unwrap();
+            let index_column =
+                index
+                    .columns
+                    .iter()
+                    .find(|index_column| match &index_column.target {
+                        IndexTarget::Column(name) => name == column_name,
+                        IndexTarget::Expression(expression) => {
+                            self.expression_anchor_column(expression) == Some(column_name)
+                        }
+                    })?;
+            let descending = index_column.ordering == Ordering::Desc;
+            let expression = match &index_column.target {
+                IndexTarget::Expression(expression) => Some(expression),
+                IndexTarget::Column(_) => None,
+            };
+
+            // Partial, descending, and expression indices need the parameters that a name list
+            // can't express
+            if descending || index.predicate.is_some() || expression.is_some() {
+                let mut params = vec![format!("name=\"{}\"", index_name)];
+                if descending {
+                    params.push("order=\"desc\"".to_string());
+                }
+                if let Some(expression) = expression {
+                    params.push(format!("expression=\"{}\"", escape_string(expression)));
+                }
+                if let Some(predicate) = &index.predicate {
+                    params.push(format!("where=\"{}\"", escape_string(predicate)));
+                }
+                return Some(format!("@index({})", params.join(", ")));
+            }
+
             if index.columns.len() == 1 {
                 return Some("@index".to_string());
             }
         }
 
+        // A name list would turn an expression index into an index on the column
+        index_names.retain(|name| !unexpressible_indices.contains(name));
+        if index_names.is_empty() {
+            return None;
+        }
+
         // Sort indices: single-column indices first, then multi-column indices
         index_names.sort_by_key(|&name| {
             let index = self.indices.iter().find(|idx| idx.name == name).unwrap();
@@ -535,6 +720,124 @@ impl TableSpecImportNaming for TableSpec {
 
         Some(format!("@index({})", index_list))
     }
+
+    fn expression_anchor_column(&self, expression: &str) -> Option<&str> {
+        expression_identifiers(expression)
+            .into_iter()
+            .find_map(|identifier| {
+                self.columns
+                    .iter()
+                    .find(|column| column.name == identifier)
+                    .map(|column| column.name.as_str())
+            })
+    }
+
+    fn unexpressible_indices(&self) -> Vec<&str> {
+        self.indices
+            .iter()
+            .filter(|index| {
+                let has_expression = index
+                    .columns
+                    .iter()
+                    .any(|index_column| index_column.column_name().is_none());
+
+                has_expression
+                    && self.columns.iter().any(|column| {
+                        let column_indices = self.get_indices_for_column(&column.name);
+                        column_indices.len() > 1 && column_indices.contains(&index.name.as_str())
+                    })
+            })
+            .map(|index| index.name.as_str())
+            .collect()
+    }
+}
+
+/// Warnings for the indices that the imported model leaves out (see
+/// `TableSpecImportNaming::unexpressible_indices`), which the user needs to add by hand
+pub(super) fn unexpressible_index_issues(database: &DatabaseSpec) -> Vec<Issue> {
+    database
+        .tables
+        .iter()
+        .flat_map(|table| {
+            table
+                .unexpressible_indices()
+                .into_iter()
+                .map(|index_name| {
+                    Issue::Warning(format!(
+                        "The model leaves out the index '{index_name}' on '{}', since a field with other indices can't specify an expression. Add it by hand, or a migration will drop it.",
+                        table.name.fully_qualified_name()
+                    ))
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn escape_string(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// The identifiers (in the order of appearance) in a SQL expression, skipping string literals and
+/// unquoting quoted identifiers
+fn expression_identifiers(expression: &str) -> Vec<String> {
+    let mut identifiers = vec![];
+    let mut chars = expression.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                while let Some(c) = chars.next() {
+                    if c == '\'' {
+                        if chars.peek() == Some(&'\'') {
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                }
+            }
+            '"' => {
+                let mut identifier = String::new();
+                while let Some(c) = chars.next() {
+                    if c == '"' {
+                        if chars.peek() == Some(&'"') {
+                            identifier.push(chars.next().unwrap());
+                        } else {
+                            break;
+                        }
+                    } else {
+                        identifier.push(c);
+                    }
+                }
+                identifiers.push(identifier);
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut identifier = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_alphanumeric() || c == '_' || c == '$' {
+                        identifier.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                identifiers.push(identifier.to_lowercase());
+            }
+            c if c.is_ascii_digit() => {
+                // Skip the rest of a number (so that `1e5` doesn't produce the `e5` identifier)
+                while let Some(&c) = chars.peek() {
+                    if c.is_alphanumeric() || c == '.' {
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    identifiers
 }
 
 /// Extract relation name from column name by removing common ID suffixes
@@ -685,6 +988,7 @@ fn add_back_references(
             is_nullable: back_ref.is_nullable,
             annotations,
             default_value: None,
+            doc_comments: None,
         });
     }
 
@@ -0,0 +1,14 @@
+CREATE TABLE "events" (
+        "id" SERIAL PRIMARY KEY,
+        "title" TEXT NOT NULL CHECK (char_length(title) <= 100),
+        "code" VARCHAR(10) NOT NULL CHECK (char_length(code) <= 10),
+        "slug" TEXT NOT NULL CHECK (slug <> '\'),
+        "capacity" INT NOT NULL CHECK (capacity >= 0 AND capacity <= 1000),
+        "start_at" INT NOT NULL,
+        "end_at" INT NOT NULL,
+        CHECK (start_at < end_at)
+);
+
+COMMENT ON TABLE "events" IS 'An event that attendees can register for';
+
+COMMENT ON COLUMN "events"."title" IS 'The title shown on the event page';
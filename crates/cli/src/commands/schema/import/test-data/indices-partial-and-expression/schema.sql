@@ -0,0 +1,23 @@
+CREATE TABLE "events" (
+	"id" SERIAL PRIMARY KEY,
+	"name" TEXT NOT NULL
+);
+
+CREATE TABLE "members" (
+	"id" SERIAL PRIMARY KEY,
+	"email" TEXT NOT NULL
+);
+
+CREATE TABLE "subscribers" (
+	"id" SERIAL PRIMARY KEY,
+	"email" TEXT NOT NULL
+);
+
+CREATE INDEX "events_name_idx" ON "events" ("name" DESC);
+
+CREATE INDEX "members_email_lower_idx" ON "members" (lower("email")) WHERE "email" <> '';
+
+-- The field can't carry the expression index along with another index, so the import leaves it out
+CREATE INDEX "subscribers_email_idx" ON "subscribers" ("email");
+
+CREATE INDEX "subscribers_email_lower_idx" ON "subscribers" (lower("email"));
@@ -0,0 +1,19 @@
+CREATE TABLE "concerts" (
+        "id" SERIAL PRIMARY KEY,
+        "title" TEXT NOT NULL,
+        "venue_id" INT NOT NULL,
+        "price" INT
+);
+
+CREATE TABLE "venues" (
+        "id" SERIAL PRIMARY KEY,
+        "name" TEXT NOT NULL,
+        "city" TEXT
+);
+
+CREATE TABLE "artists" (
+        "id" SERIAL PRIMARY KEY,
+        "name" TEXT NOT NULL
+);
+
+ALTER TABLE "concerts" ADD CONSTRAINT "concerts_venue_fk" FOREIGN KEY ("venue_id") REFERENCES "venues";
@@ -0,0 +1,21 @@
+CREATE SCHEMA "auth";
+
+CREATE SCHEMA "billing";
+
+CREATE TABLE "users" (
+        "id" SERIAL PRIMARY KEY,
+        "name" TEXT NOT NULL
+);
+
+CREATE TABLE "auth"."users" (
+        "id" SERIAL PRIMARY KEY,
+        "email" TEXT NOT NULL
+);
+
+CREATE TABLE "billing"."invoices" (
+        "id" SERIAL PRIMARY KEY,
+        "amount" INT NOT NULL,
+        "user_id" INT NOT NULL
+);
+
+ALTER TABLE "billing"."invoices" ADD CONSTRAINT "invoices_user_fk" FOREIGN KEY ("user_id") REFERENCES "auth"."users";
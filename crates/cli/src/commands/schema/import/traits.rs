@@ -20,3 +20,18 @@ pub(super) trait ModelImporter<P, O> {
 pub(super) trait ImportWriter {
     fn write_to(self, writer: &mut (dyn Write + Send)) -> Result<()>;
 }
+
+/// Write doc comments (one `///` line for each line of the comment)
+pub(super) fn write_doc_comments(
+    writer: &mut (dyn Write + Send),
+    indent: &str,
+    doc_comments: Option<&str>,
+) -> Result<()> {
+    for line in doc_comments
+        .into_iter()
+        .flat_map(|doc_comments| doc_comments.lines())
+    {
+        writeln!(writer, "{}", format!("{indent}/// {line}").trim_end())?;
+    }
+    Ok(())
+}
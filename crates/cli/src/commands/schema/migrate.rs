@@ -8,7 +8,8 @@
 // by the Apache License, Version 2.0.
 
 use std::sync::Arc;
-use std::{io, path::PathBuf};
+use std::time::Duration;
+use std::{io, io::Write, path::PathBuf};
 
 use anyhow::anyhow;
 use colored::Colorize;
@@ -18,8 +19,9 @@ use exo_sql_pg_connect::{DatabaseClient, TransactionMode};
 use exo_sql_pg_schema::DatabaseSpec;
 use exo_sql_pg_schema::MigrationScope;
 use exo_sql_pg_schema::{
-    InteractionError, Migration, MigrationError, MigrationInteraction,
-    PredefinedMigrationInteraction, TableAction, migrate_interactively,
+    ColumnAction, DataMigration, EnumValueAction, InteractionError, Migration, MigrationError,
+    MigrationInteraction, MigrationMode, PredefinedMigrationInteraction, StagedMigration,
+    TableAction, migrate_interactively,
 };
 
 use crate::commands::command::{
@@ -34,7 +36,8 @@ use crate::{
     util::open_file_for_output,
 };
 
-use super::util::{self, open_database};
+use super::data_migration::apply_staged_migration;
+use super::util::{self, PlanFormat, open_database, plan_format_arg};
 use anyhow::Result;
 use async_trait::async_trait;
 use clap::{Arg, Command};
@@ -76,6 +79,40 @@ impl CommandDefinition for MigrateCommandDefinition {
                 .long("interactions")
                 .required(false)
         )
+        .arg(
+            Arg::new("online")
+                .help("Produce a migration that can run against a database serving traffic")
+                .long_help("Build indexes concurrently, add foreign keys and check constraints as NOT VALID and validate them separately, and (with --apply-to-database) apply each statement with a lock timeout, retrying if the lock isn't acquired in time")
+                .long("online")
+                .required(false)
+                .num_args(0),
+        )
+        .arg(
+            Arg::new("lock-timeout")
+                .help("With --online, how long (in milliseconds) a statement may wait for a lock before retrying")
+                .long("lock-timeout")
+                .required(false)
+                .value_parser(clap::value_parser!(u64))
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("lock-retries")
+                .help("With --online, how many times to retry a statement that timed out waiting for a lock")
+                .long("lock-retries")
+                .required(false)
+                .value_parser(clap::value_parser!(u32))
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("data-migrations")
+                .help("Path to a file listing data migrations to run between schema changes")
+                .long_help("Path to a file listing data migrations (mutations that populate columns) to run between schema changes. Implies --non-interactive.")
+                .long("data-migrations")
+                .required(false)
+                .value_parser(clap::value_parser!(PathBuf))
+                .num_args(1),
+        )
+        .arg(plan_format_arg())
         .arg(use_ir_arg())
         .arg(yes_arg())
     }
@@ -97,6 +134,17 @@ impl CommandDefinition for MigrateCommandDefinition {
         let interaction_file: Option<String> = get(matches, "interactions");
         let scope: Option<String> = migration_scope_value(matches);
         let yes: bool = yes_value(matches);
+        let online: bool = matches.get_flag("online");
+        let lock_timeout: u64 = get(matches, "lock-timeout").unwrap_or(DEFAULT_LOCK_TIMEOUT_MS);
+        let lock_retries: u32 = get(matches, "lock-retries").unwrap_or(DEFAULT_LOCK_RETRIES);
+        let format: PlanFormat = get(matches, "format").unwrap_or(PlanFormat::Text);
+        let data_migrations_file: Option<PathBuf> = get(matches, "data-migrations");
+
+        let mode = if online {
+            MigrationMode::Online
+        } else {
+            MigrationMode::Standard
+        };
 
         if output.is_some() && apply_to_database {
             return Err(anyhow!(
@@ -104,7 +152,20 @@ impl CommandDefinition for MigrateCommandDefinition {
             ));
         }
 
-        let database = util::extract_postgres_database(&model, None, use_ir).await?;
+        if format == PlanFormat::Json && apply_to_database {
+            return Err(anyhow!(
+                "Cannot specify both --format json and --apply-to-database"
+            ));
+        }
+
+        if online && data_migrations_file.is_some() {
+            return Err(anyhow!(
+                "Cannot specify both --online and --data-migrations"
+            ));
+        }
+
+        let system = util::create_system(&model, None, use_ir).await?;
+        let database = util::postgres_database(&system)?;
 
         let transaction_mode = {
             let read_write_mode = crate::commands::util::read_write_mode(
@@ -126,13 +187,83 @@ impl CommandDefinition for MigrateCommandDefinition {
 
         let scope = compute_migration_scope(scope);
 
+        if let Some(data_migrations_file) = data_migrations_file {
+            let data_migrations =
+                DataMigration::from_file(&data_migrations_file).map_err(|e| anyhow!(e))?;
+            let migration = StagedMigration::from_db_and_model(
+                &db_client,
+                &database,
+                &scope,
+                mode,
+                data_migrations,
+            )
+            .await?;
+
+            if format == PlanFormat::Json {
+                let mut buffer: Box<dyn io::Write> = open_file_for_output(output.as_deref(), yes)?;
+                serde_json::to_writer_pretty(&mut buffer, &migration)?;
+                writeln!(buffer)?;
+                return Ok(());
+            }
+
+            if migration.is_empty() {
+                println!(
+                    "{}",
+                    "The schema is up to date. No migrations needed.".yellow()
+                );
+                return Ok(());
+            }
+
+            for issue in migration
+                .before
+                .issues
+                .iter()
+                .chain(&migration.after.issues)
+            {
+                eprintln!("{}", issue.to_string().yellow());
+            }
+
+            if apply_to_database {
+                apply_staged_migration(
+                    db_client,
+                    &migration,
+                    allow_destructive_changes,
+                    system,
+                    database_url.as_deref(),
+                    env,
+                )
+                .await?;
+                println!("{}", "Migration applied successfully.".green());
+            } else {
+                let mut buffer: Box<dyn io::Write> = open_file_for_output(output.as_deref(), yes)?;
+                migration.write(&mut buffer, allow_destructive_changes)?;
+                println!("{}", "Migration script written to file.".green());
+            }
+
+            return Ok(());
+        }
+
         let migrations = if non_interactive {
-            Migration::from_db_and_model(&db_client, &database, &scope).await?
+            Migration::from_db_and_model(&db_client, &database, &scope, mode).await?
         } else {
-            migrate_interactively_from_db_and_model(&db_client, &database, &scope, interaction_file)
-                .await?
+            migrate_interactively_from_db_and_model(
+                &db_client,
+                &database,
+                &scope,
+                interaction_file,
+                mode,
+            )
+            .await?
         };
 
+        if format == PlanFormat::Json {
+            // Destructive changes are marked as such (instead of commented out as in the SQL output)
+            let mut buffer: Box<dyn io::Write> = open_file_for_output(output.as_deref(), yes)?;
+            serde_json::to_writer_pretty(&mut buffer, &migrations)?;
+            writeln!(buffer)?;
+            return Ok(());
+        }
+
         if migrations.is_empty() {
             println!(
                 "{}",
@@ -141,10 +272,29 @@ impl CommandDefinition for MigrateCommandDefinition {
             return Ok(());
         }
 
+        for issue in &migrations.issues {
+            eprintln!("{}", issue.to_string().yellow());
+        }
+
+        if online {
+            report_locks(&migrations);
+        }
+
         if apply_to_database {
-            migrations
-                .apply(&mut db_client, allow_destructive_changes)
+            if online {
+                apply_online(
+                    &mut db_client,
+                    &migrations,
+                    allow_destructive_changes,
+                    lock_timeout,
+                    lock_retries,
+                )
                 .await?;
+            } else {
+                migrations
+                    .apply(&mut db_client, allow_destructive_changes)
+                    .await?;
+            }
             println!("{}", "Migration applied successfully.".green());
         } else {
             let mut buffer: Box<dyn io::Write> = open_file_for_output(output.as_deref(), yes)?;
@@ -161,6 +311,7 @@ pub async fn migrate_interactively_from_db_and_model(
     database: &Database,
     scope: &MigrationScope,
     interaction_file: Option<String>,
+    mode: MigrationMode,
 ) -> Result<Migration> {
     let new_db_spec = DatabaseSpec::from_database(database);
 
@@ -171,10 +322,114 @@ pub async fn migrate_interactively_from_db_and_model(
         new_db_spec,
         scope,
         &UserMigrationInteraction::from_file(interaction_file.map(PathBuf::from))?,
+        mode,
     )
     .await?)
 }
 
+const DEFAULT_LOCK_TIMEOUT_MS: u64 = 2000;
+const DEFAULT_LOCK_RETRIES: u32 = 5;
+const LOCK_RETRY_BACKOFF_MS: u64 = 500;
+
+// SQLSTATE for "lock_not_available", which Postgres reports when `lock_timeout` expires
+const LOCK_NOT_AVAILABLE: &str = "55P03";
+
+fn report_locks(migration: &Migration) {
+    let mut locking_statements = migration.locking_statements().peekable();
+
+    if locking_statements.peek().is_none() {
+        println!(
+            "{}",
+            "No statement locks existing tables against reads or writes.".green()
+        );
+        return;
+    }
+
+    println!("{}", "Statements that lock existing tables:".yellow());
+    for statement in locking_statements {
+        println!("  {}", statement.statement);
+        for lock in &statement.locks {
+            println!("    {}", lock.to_string().yellow());
+        }
+    }
+}
+
+/// Apply each statement on its own (instead of in a single transaction), so that indexes can be
+/// built concurrently and locks are held only for the duration of a statement. A statement that
+/// can't acquire its locks within `lock_timeout_ms` is retried (with backoff), so that it doesn't
+/// queue up the application's queries behind it.
+async fn apply_online(
+    db_client: &mut DatabaseClient,
+    migration: &Migration,
+    allow_destructive_changes: bool,
+    lock_timeout_ms: u64,
+    lock_retries: u32,
+) -> Result<()> {
+    // Each statement commits on its own, so reject a destructive plan before running any of it
+    migration.check_allowed(allow_destructive_changes)?;
+
+    let applied =
+        apply_statements_online(db_client, migration, lock_timeout_ms, lock_retries).await;
+
+    // Reset the timeout even if a statement failed, so that it doesn't apply to the rest of the
+    // session
+    let reset = db_client.batch_execute("RESET lock_timeout").await;
+
+    applied?;
+    reset?;
+
+    Ok(())
+}
+
+/// Apply the statements of an online migration, setting `lock_timeout` for each (see
+/// [apply_online])
+async fn apply_statements_online(
+    db_client: &mut DatabaseClient,
+    migration: &Migration,
+    lock_timeout_ms: u64,
+    lock_retries: u32,
+) -> Result<()> {
+    for statement in migration.statements.iter() {
+        // Concurrent statements wait for existing transactions without blocking new ones, so
+        // there is no need to give up on them
+        let lock_timeout = if statement.concurrent {
+            0
+        } else {
+            lock_timeout_ms
+        };
+        db_client
+            .batch_execute(&format!("SET lock_timeout = {lock_timeout}"))
+            .await?;
+
+        let mut attempt = 0;
+        loop {
+            match db_client.batch_execute(&statement.statement).await {
+                Ok(()) => break,
+                Err(e)
+                    if e.code().map(|code| code.code()) == Some(LOCK_NOT_AVAILABLE)
+                        && attempt < lock_retries =>
+                {
+                    attempt += 1;
+                    let backoff = Duration::from_millis(LOCK_RETRY_BACKOFF_MS * 2u64.pow(attempt));
+                    eprintln!(
+                        "{}",
+                        format!(
+                            "Timed out waiting for a lock (attempt {attempt} of {lock_retries}), retrying in {}ms: {}",
+                            backoff.as_millis(),
+                            statement.statement
+                        )
+                        .yellow()
+                    );
+                    tokio::time::sleep(backoff).await;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    Ok(())
+}
+
 const MANUAL_HANDLE: &str = "Let me handle this manually";
 const RENAME_HANDLE: &str = "Rename it";
 const DELETE_HANDLE: &str = "Delete it";
@@ -195,15 +450,17 @@ impl UserMigrationInteraction {
     }
 }
 
+// The prompts (which `inquire` renders on stderr) and their messages go to stderr, so that they
+// don't mix with a migration plan written to stdout (such as with `--format json`)
 impl MigrationInteraction for UserMigrationInteraction {
     fn handle_start(&self) {
         if self.predefined_interaction.is_some() {
-            println!(
-                "The database has a few tables that the new schema doesn't need. Trying to handle them as according to the specified interactions."
+            eprintln!(
+                "The database has a few tables, columns, or enum values that the new schema doesn't need. Trying to handle them as according to the specified interactions."
             );
         } else {
-            println!(
-                "The database has a few tables that the new schema doesn't need. Please choose how to handle them."
+            eprintln!(
+                "The database has a few tables, columns, or enum values that the new schema doesn't need. Please choose how to handle them."
             );
         }
     }
@@ -226,7 +483,7 @@ impl MigrationInteraction for UserMigrationInteraction {
             return Ok(action);
         }
 
-        println!(
+        eprintln!(
             "\nThe database has the {} table that doesn't exist in the new schema.",
             deleted_table.fully_qualified_name_with_sep(".").red()
         );
@@ -266,6 +523,121 @@ impl MigrationInteraction for UserMigrationInteraction {
             _ => unreachable!(),
         }
     }
+
+    fn handle_column_delete(
+        &self,
+        table: &SchemaObjectName,
+        deleted_column: &str,
+        create_columns: &[&str],
+    ) -> Result<ColumnAction, InteractionError> {
+        if let Some(predefined_interaction) = &self.predefined_interaction
+            && let Ok(action) =
+                predefined_interaction.handle_column_delete(table, deleted_column, create_columns)
+        {
+            return Ok(action);
+        }
+
+        eprintln!(
+            "\nThe {} table has the {} column that doesn't exist in the new schema.",
+            table.fully_qualified_name_with_sep(".").red(),
+            deleted_column.red()
+        );
+        let ans = inquire::Select::new(
+            "How would you like to handle this column?",
+            vec![MANUAL_HANDLE, RENAME_HANDLE, DELETE_HANDLE],
+        )
+        .prompt()
+        .map_err(|e| InteractionError::Generic(e.to_string()))?;
+
+        match ans {
+            MANUAL_HANDLE => Ok(ColumnAction::Defer {
+                table: table.clone(),
+                column: deleted_column.to_string(),
+            }),
+            RENAME_HANDLE => {
+                let mut create_columns = create_columns.to_vec();
+
+                create_columns.sort_by_key(|column| {
+                    // Same scaling as for tables (see `handle_table_delete`)
+                    -(strsim::jaro(column, deleted_column) * i32::MAX as f64) as i32
+                });
+
+                let new_name = inquire::Select::new("What should the new name be?", create_columns)
+                    .prompt()
+                    .map_err(|e| InteractionError::Generic(e.to_string()))?;
+                Ok(ColumnAction::Rename {
+                    table: table.clone(),
+                    old_column: deleted_column.to_string(),
+                    new_column: new_name.to_string(),
+                })
+            }
+            DELETE_HANDLE => Ok(ColumnAction::Delete {
+                table: table.clone(),
+                column: deleted_column.to_string(),
+            }),
+            _ => unreachable!(),
+        }
+    }
+
+    fn handle_enum_value_delete(
+        &self,
+        enum_name: &SchemaObjectName,
+        deleted_value: &str,
+        create_values: &[&str],
+    ) -> Result<EnumValueAction, InteractionError> {
+        if let Some(predefined_interaction) = &self.predefined_interaction
+            && let Ok(action) = predefined_interaction.handle_enum_value_delete(
+                enum_name,
+                deleted_value,
+                create_values,
+            )
+        {
+            return Ok(action);
+        }
+
+        eprintln!(
+            "\nThe {} enum has the {} value that doesn't exist in the new schema.",
+            enum_name.fully_qualified_name_with_sep(".").red(),
+            deleted_value.red()
+        );
+        let ans = inquire::Select::new(
+            "How would you like to handle this value?",
+            vec![MANUAL_HANDLE, RENAME_HANDLE, DELETE_HANDLE],
+        )
+        .prompt()
+        .map_err(|e| InteractionError::Generic(e.to_string()))?;
+
+        match ans {
+            MANUAL_HANDLE => Ok(EnumValueAction::Defer {
+                enum_name: enum_name.clone(),
+                value: deleted_value.to_string(),
+            }),
+            RENAME_HANDLE => {
+                let mut create_values = create_values.to_vec();
+
+                create_values.sort_by_key(|value| {
+                    // Offer the added values most similar to the removed one first (a renamed
+                    // value usually keeps most of its spelling, such as `MEDIUM` to `MED`)
+                    -(strsim::jaro(value, deleted_value) * i32::MAX as f64) as i32
+                });
+
+                let new_value =
+                    inquire::Select::new("What should the new value be?", create_values)
+                        .prompt()
+                        .map_err(|e| InteractionError::Generic(e.to_string()))?;
+                Ok(EnumValueAction::Rename {
+                    enum_name: enum_name.clone(),
+                    old_value: deleted_value.to_string(),
+                    new_value: new_value.to_string(),
+                })
+            }
+            DELETE_HANDLE => Ok(EnumValueAction::Delete {
+                enum_name: enum_name.clone(),
+                value: deleted_value.to_string(),
+            }),
+            _ => unreachable!(),
+        }
+    }
 }
 
 struct TableDisplay<'a>(&'a SchemaObjectName);
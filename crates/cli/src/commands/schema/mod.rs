@@ -15,6 +15,7 @@ use self::{
 use super::command::SubcommandDefinition;
 
 pub(crate) mod create;
+mod data_migration;
 pub(crate) mod import;
 pub(crate) mod migrate;
 pub(crate) mod util;
@@ -12,6 +12,7 @@ use std::io::BufReader;
 use std::path::{Path, PathBuf};
 
 use anyhow::anyhow;
+use clap::{Arg, ValueEnum, builder::PossibleValue};
 
 use core_plugin_shared::{
     serializable_system::SerializableSystem, system_serializer::SystemSerializer,
@@ -95,15 +96,50 @@ pub(crate) async fn extract_postgres_database(
 ) -> Result<Database, anyhow::Error> {
     let serialized_system = create_system(model_file, trusted_documents_dir, use_ir).await?;
 
-    let postgres_subsystem = serialized_system
+    postgres_database(&serialized_system)
+}
+
+pub(crate) fn postgres_database(system: &SerializableSystem) -> Result<Database, anyhow::Error> {
+    let postgres_subsystem = system
         .subsystems
-        .into_iter()
+        .iter()
         .find(|subsystem| subsystem.id == "postgres");
 
     let database = match postgres_subsystem {
-        Some(subsystem) => PostgresCoreSubsystem::deserialize(subsystem.core.0)?.database,
+        Some(subsystem) => {
+            PostgresCoreSubsystem::deserialize_reader(subsystem.core.0.as_slice())?.database
+        }
         None => Database::default(),
     };
 
     Ok(database)
 }
+
+pub(crate) fn plan_format_arg() -> Arg {
+    Arg::new("format")
+        .help("Output format")
+        .long_help("Output format. `json` produces a machine-readable report with each schema change, whether it is destructive, the objects it affects, and its SQL.")
+        .long("format")
+        .value_parser(clap::builder::EnumValueParser::<PlanFormat>::new())
+        .default_value("text")
+}
+
+/// Output format for the `schema migrate` and `schema verify` commands
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum PlanFormat {
+    Text,
+    Json,
+}
+
+impl ValueEnum for PlanFormat {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Self::Text, Self::Json]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        match self {
+            Self::Text => Some(PossibleValue::new("text")),
+            Self::Json => Some(PossibleValue::new("json")),
+        }
+    }
+}
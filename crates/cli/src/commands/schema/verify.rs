@@ -12,18 +12,19 @@ use async_trait::async_trait;
 use clap::Command;
 use exo_env::Environment;
 use exo_sql_pg_connect::TransactionMode;
-use exo_sql_pg_schema::{Migration, VerificationErrors};
+use exo_sql_pg_schema::{Issue, Migration, MigrationOperation, VerificationErrors};
+use serde::Serialize;
 use std::path::PathBuf;
 use std::sync::Arc;
 
 use crate::commands::command::{
-    CommandDefinition, database_arg, database_value, default_model_file, migration_scope_arg,
+    CommandDefinition, database_arg, database_value, default_model_file, get, migration_scope_arg,
     migration_scope_value, yes_arg,
 };
 use crate::commands::util::{compute_migration_scope, use_ir_arg};
 use crate::config::Config;
 
-use super::util;
+use super::util::{self, PlanFormat, plan_format_arg};
 
 pub(super) struct VerifyCommandDefinition {}
 
@@ -35,6 +36,7 @@ impl CommandDefinition for VerifyCommandDefinition {
             .arg(database_arg())
             .arg(use_ir_arg())
             .arg(migration_scope_arg())
+            .arg(plan_format_arg())
             .arg(yes_arg())
     }
 
@@ -49,6 +51,7 @@ impl CommandDefinition for VerifyCommandDefinition {
         let database_url = database_value(matches);
         let use_ir: bool = matches.get_flag("use-ir");
         let scope: Option<String> = migration_scope_value(matches);
+        let format: PlanFormat = get(matches, "format").unwrap_or(PlanFormat::Text);
         let db_client = util::open_database(
             database_url.as_deref(),
             TransactionMode::ReadOnly,
@@ -58,6 +61,30 @@ impl CommandDefinition for VerifyCommandDefinition {
 
         let database = util::extract_postgres_database(&model, None, use_ir).await?;
         let db_client = db_client.get_client().await?;
+
+        if format == PlanFormat::Json {
+            let incompatibilities = Migration::incompatibilities(
+                &db_client,
+                &database,
+                &compute_migration_scope(scope),
+            )
+            .await?;
+            let compatible = incompatibilities.value.is_empty();
+
+            let report = VerificationReport {
+                compatible,
+                incompatibilities: &incompatibilities.value,
+                issues: &incompatibilities.issues,
+            };
+            println!("{}", serde_json::to_string_pretty(&report)?);
+
+            return if compatible {
+                Ok(())
+            } else {
+                Err(anyhow!("Incompatible model."))
+            };
+        }
+
         let verification_result =
             Migration::verify(&db_client, &database, &compute_migration_scope(scope)).await;
 
@@ -76,3 +103,10 @@ impl CommandDefinition for VerifyCommandDefinition {
         verification_result.map_err(|_| anyhow!("Incompatible model."))
     }
 }
+
+#[derive(Serialize)]
+struct VerificationReport<'a> {
+    compatible: bool,
+    incompatibilities: &'a [MigrationOperation],
+    issues: &'a [Issue],
+}
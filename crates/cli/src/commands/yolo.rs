@@ -16,7 +16,7 @@ use common::env_const::{DATABASE_URL, EXO_POSTGRES_READ_WRITE};
 use common::env_processing::EnvProcessing;
 use exo_env::{Environment, MapEnvironment};
 use exo_sql_pg_connect::TransactionMode;
-use exo_sql_pg_schema::Migration;
+use exo_sql_pg_schema::{Migration, MigrationMode};
 use std::{
     path::PathBuf,
     sync::{Arc, atomic::Ordering},
@@ -178,8 +178,13 @@ async fn setup_database(
 
     let database = util::extract_postgres_database(&model, None, false).await?;
 
-    let migrations =
-        Migration::from_db_and_model(&db_client, &database, &MigrationScope::FromNewSpec).await?;
+    let migrations = Migration::from_db_and_model(
+        &db_client,
+        &database,
+        &MigrationScope::FromNewSpec,
+        MigrationMode::Standard,
+    )
+    .await?;
 
     println!("{}", "Applying migration...".blue().bold());
 
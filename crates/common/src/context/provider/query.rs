@@ -38,6 +38,7 @@ impl<'request> ContextExtractor for QueryExtractor {
             query: Some(query),
             variables: None,
             query_hash: None,
+            uploads: vec![],
         };
 
         let graphql_path = get_graphql_http_path(request_context.system_context.env);
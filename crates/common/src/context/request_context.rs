@@ -12,8 +12,9 @@ use tokio::sync::Mutex;
 
 use async_recursion::async_recursion;
 use exo_env::Environment;
+use futures::future::BoxFuture;
 
-use crate::http::{RequestHead, RequestPayload, ResponsePayload};
+use crate::http::{RequestBodyStream, RequestHead, RequestPayload, ResponsePayload};
 use crate::router::PlainRequestPayload;
 use crate::{router::Router, value::Val};
 use exo_sql_pg_connect::TransactionHolder;
@@ -37,6 +38,8 @@ pub struct SystemRequestContext<'a> {
     pub jwt_authenticator: &'a Option<JwtAuthenticator>,
     pub system_router: &'a dyn for<'request> Router<PlainRequestPayload<'request>>,
     pub transaction_holder: Arc<Mutex<TransactionHolder>>,
+    /// The work to run once the transaction commits (see [RequestContext::after_commit])
+    pub after_commit: Arc<std::sync::Mutex<Vec<BoxFuture<'static, ()>>>>,
 }
 
 impl<'a> RequestContext<'a> {
@@ -54,6 +57,7 @@ impl<'a> RequestContext<'a> {
                 jwt_authenticator,
                 system_router,
                 transaction_holder: Arc::new(Mutex::new(TransactionHolder::new())),
+                after_commit: Arc::new(std::sync::Mutex::new(vec![])),
             },
         }
     }
@@ -91,19 +95,73 @@ impl<'a> RequestContext<'a> {
             .ensure_transaction();
     }
 
+    /// Whether the work so far may have changes that other requests don't see yet (see
+    /// [TransactionHolder::may_see_uncommitted_changes])
+    pub async fn may_see_uncommitted_changes(&self) -> bool {
+        self.system_context
+            .transaction_holder
+            .as_ref()
+            .lock()
+            .await
+            .may_see_uncommitted_changes()
+            .await
+    }
+
+    /// Run the work once the transaction commits (for example, to invalidate the cached results
+    /// that the changes made stale). The work is dropped if the transaction rolls back.
+    pub fn after_commit(&self, work: BoxFuture<'static, ()>) {
+        self.system_context.after_commit.lock().unwrap().push(work);
+    }
+
     pub async fn finalize_transaction(&self, commit: bool) -> Result<(), tokio_postgres::Error> {
         // Do not finalize internal requests (currently made through the QueryExtractor)
         if self.is_internal() {
             return Ok(());
         }
 
-        self.system_context
+        let finalized = self
+            .system_context
             .transaction_holder
             .as_ref()
             .lock()
             .await
             .finalize(commit)
-            .await
+            .await;
+
+        self.run_after_commit(commit && finalized.is_ok()).await;
+        finalized
+    }
+
+    /// Finalize the transaction and start afresh for the work that follows (for example, between
+    /// the events of a subscription, so that a long-lived request doesn't hold on to a connection)
+    pub async fn reset_transaction(&self, commit: bool) -> Result<(), tokio_postgres::Error> {
+        if self.is_internal() {
+            return Ok(());
+        }
+
+        let finalized = {
+            let mut transaction_holder =
+                self.system_context.transaction_holder.as_ref().lock().await;
+            let finalized = transaction_holder.finalize(commit).await;
+            if finalized.is_ok() {
+                *transaction_holder = TransactionHolder::new();
+            }
+            finalized
+        };
+
+        self.run_after_commit(commit && finalized.is_ok()).await;
+        finalized
+    }
+
+    /// Run the work waiting for the transaction to commit (or, if it didn't commit, drop it)
+    async fn run_after_commit(&self, committed: bool) {
+        let after_commit = std::mem::take(&mut *self.system_context.after_commit.lock().unwrap());
+
+        if committed {
+            for work in after_commit {
+                work.await;
+            }
+        }
     }
 
     pub fn with_override(&'a self, context_override: Value) -> RequestContext<'a> {
@@ -157,6 +215,10 @@ impl<'a> RequestPayload for RequestContext<'a> {
     fn take_body(&self) -> Value {
         self.core.take_body()
     }
+
+    fn take_body_stream(&self) -> Option<RequestBodyStream> {
+        self.core.take_body_stream()
+    }
 }
 
 pub(super) enum CoreRequestContext<'a> {
@@ -264,4 +326,11 @@ impl<'a> RequestPayload for CoreRequestContext<'a> {
             _ => self.get_base_context().get_request().take_body(),
         }
     }
+
+    fn take_body_stream(&self) -> Option<RequestBodyStream> {
+        match self {
+            Self::InternalRequest(_, request) => request.take_body_stream(),
+            _ => self.get_base_context().get_request().take_body_stream(),
+        }
+    }
 }
@@ -139,6 +139,31 @@ impl CorsConfig {
             None => CorsResponse::NoCorsHeaders,
         }
     }
+
+    /// Determine if a WebSocket connection may be opened from the given origin
+    ///
+    /// Browsers don't apply CORS to WebSocket connections (and send cookies with them), so the
+    /// server must check the `Origin` header itself. We allow connections without an `Origin`
+    /// header (non-browser clients), same-origin connections (where the origin's host matches the
+    /// `Host` header), and connections from the origins allowed by the CORS config.
+    pub fn allow_websocket_origin(&self, origin: Option<&str>, host: Option<&str>) -> bool {
+        let Some(origin) = origin else {
+            return true;
+        };
+
+        let same_origin = host.is_some_and(|host| {
+            origin
+                .split_once("://")
+                .is_some_and(|(_, origin_host)| origin_host.eq_ignore_ascii_case(host))
+        });
+
+        same_origin
+            || match &self.allow_origin {
+                CorsAllowOrigin::All => true,
+                CorsAllowOrigin::Specific(allowed_origins) => allowed_origins.contains(origin),
+                CorsAllowOrigin::None => false,
+            }
+    }
 }
 
 #[cfg(test)]
@@ -266,4 +291,34 @@ pub(crate) mod tests {
         let config = CorsConfig::from_env(None);
         assert_cors_enforcement(&config, None, Some("https://example.com"));
     }
+
+    #[test]
+    fn cors_config_allow_websocket_origin() {
+        let config = CorsConfig::from_env(Some("https://example.com".to_string()));
+
+        assert!(config.allow_websocket_origin(None, Some("api.example.com")));
+        assert!(
+            config.allow_websocket_origin(Some("https://example.com"), Some("api.example.com"))
+        );
+        assert!(
+            config.allow_websocket_origin(Some("https://api.example.com"), Some("api.example.com"))
+        );
+        assert!(
+            config.allow_websocket_origin(Some("http://localhost:9876"), Some("localhost:9876"))
+        );
+        assert!(
+            !config
+                .allow_websocket_origin(Some("https://not-example.com"), Some("api.example.com"))
+        );
+        assert!(!config.allow_websocket_origin(Some("http://localhost:9876"), Some("localhost")));
+        assert!(!config.allow_websocket_origin(Some("https://not-example.com"), None));
+
+        let config = CorsConfig::from_env(Some("*".to_string()));
+        assert!(config.allow_websocket_origin(Some("https://not-example.com"), None));
+
+        let config = CorsConfig::from_env(None);
+        assert!(config.allow_websocket_origin(None, None));
+        assert!(config.allow_websocket_origin(Some("https://example.com"), Some("example.com")));
+        assert!(!config.allow_websocket_origin(Some("https://example.com"), Some("example.org")));
+    }
 }
@@ -27,19 +27,29 @@ impl<Rtr> CorsRouter<Rtr> {
     pub fn new(underlying: Rtr, config: CorsConfig) -> Self {
         Self { underlying, config }
     }
-}
 
-#[async_trait::async_trait]
-impl<RQ: RequestPayload + Send + Sync, Rtr: Router<RQ>> Router<RQ> for CorsRouter<Rtr> {
-    /// Route a request applying CORS rules.
+    pub fn config(&self) -> &CorsConfig {
+        &self.config
+    }
+
+    /// Apply CORS rules to a request with the given origin and method, using `route` (instead of
+    /// the underlying router) to produce the response for an allowed request. This is useful for
+    /// routes that need to own the request (for example, to stream responses for a long time).
     ///
     /// For a denied cross-site request, we return 403 (Forbidden), since there is no
     /// specified standard, but https://github.com/whatwg/fetch/issues/172 makes sense.
     /// It suggests the possibility of adding more details in the body, but also cautions
     /// to not reveal too much information. Therefore, we don't add a body.
-    async fn route(&self, request: &RQ) -> Option<ResponsePayload> {
-        let origin_header = request.get_head().get_header(http::header::ORIGIN.as_str());
-
+    pub async fn route_with<F, Fut>(
+        &self,
+        origin: Option<&str>,
+        method: &Method,
+        route: F,
+    ) -> Option<ResponsePayload>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Option<ResponsePayload>>,
+    {
         let add_cors_headers = |response: &mut ResponsePayload, origin: &str| {
             let headers = &mut response.headers;
             headers.insert(
@@ -75,11 +85,9 @@ impl<RQ: RequestPayload + Send + Sync, Rtr: Router<RQ>> Router<RQ> for CorsRoute
             status_code: StatusCode::FORBIDDEN,
         };
 
-        let cors_response = self
-            .config
-            .allow_origin(origin_header.as_deref(), &request.get_head().get_method());
+        let cors_response = self.config.allow_origin(origin, method);
 
-        if request.get_head().get_method() == Method::OPTIONS {
+        if *method == Method::OPTIONS {
             match cors_response {
                 CorsResponse::Allow(origin) => {
                     let mut response = ResponsePayload {
@@ -96,7 +104,7 @@ impl<RQ: RequestPayload + Send + Sync, Rtr: Router<RQ>> Router<RQ> for CorsRoute
         } else {
             match cors_response {
                 CorsResponse::Allow(origin) => {
-                    let mut response = self.underlying.route(request).await;
+                    let mut response = route().await;
 
                     if let Some(ref mut response) = response {
                         add_cors_headers(response, origin);
@@ -104,13 +112,27 @@ impl<RQ: RequestPayload + Send + Sync, Rtr: Router<RQ>> Router<RQ> for CorsRoute
 
                     response
                 }
-                CorsResponse::NoCorsHeaders => self.underlying.route(request).await,
+                CorsResponse::NoCorsHeaders => route().await,
                 CorsResponse::Deny => Some(forbidden_response()),
             }
         }
     }
 }
 
+#[async_trait::async_trait]
+impl<RQ: RequestPayload + Send + Sync, Rtr: Router<RQ>> Router<RQ> for CorsRouter<Rtr> {
+    /// Route a request applying CORS rules (see [CorsRouter::route_with]).
+    async fn route(&self, request: &RQ) -> Option<ResponsePayload> {
+        let origin_header = request.get_head().get_header(http::header::ORIGIN.as_str());
+        let method = request.get_head().get_method();
+
+        self.route_with(origin_header.as_deref(), &method, || {
+            self.underlying.route(request)
+        })
+        .await
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
@@ -12,9 +12,17 @@ pub const EXO_JWT_SOURCE_COOKIE: &str = "EXO_JWT_SOURCE_COOKIE";
 
 pub const EXO_POSTGRES_URL: &str = "EXO_POSTGRES_URL";
 pub const EXO_POSTGRES_READ_WRITE: &str = "EXO_POSTGRES_READ_WRITE";
+pub const EXO_POSTGRES_READ_URL: &str = "EXO_POSTGRES_READ_URL";
 pub const DATABASE_URL: &str = "DATABASE_URL";
 pub const EXO_CONNECTION_POOL_SIZE: &str = "EXO_CONNECTION_POOL_SIZE";
+pub const EXO_CONNECTION_POOL_MIN_IDLE: &str = "EXO_CONNECTION_POOL_MIN_IDLE";
+pub const EXO_CONNECTION_POOL_IDLE_TIMEOUT: &str = "EXO_CONNECTION_POOL_IDLE_TIMEOUT";
+pub const EXO_CONNECTION_POOL_MAX_LIFETIME: &str = "EXO_CONNECTION_POOL_MAX_LIFETIME";
+pub const EXO_CONNECTION_POOL_ACQUIRE_TIMEOUT: &str = "EXO_CONNECTION_POOL_ACQUIRE_TIMEOUT";
+pub const EXO_CONNECTION_POOL_TEST_QUERY: &str = "EXO_CONNECTION_POOL_TEST_QUERY";
 pub const EXO_CHECK_CONNECTION_ON_STARTUP: &str = "EXO_CHECK_CONNECTION_ON_STARTUP";
+pub const EXO_CONNECTION_STARTUP_RETRIES: &str = "EXO_CONNECTION_STARTUP_RETRIES";
+pub const EXO_STATEMENT_TIMEOUT: &str = "EXO_STATEMENT_TIMEOUT";
 
 pub const EXO_SERVER_PORT: &str = "EXO_SERVER_PORT";
 
@@ -33,11 +41,26 @@ pub const EXO_MCP_HTTP_PATH: &str = "EXO_MCP_HTTP_PATH";
 
 pub const EXO_GRAPHQL_ALLOW_MUTATIONS: &str = "EXO_GRAPHQL_ALLOW_MUTATIONS";
 
+pub const EXO_PERSISTED_QUERY_STORE: &str = "EXO_PERSISTED_QUERY_STORE"; // "memory" (default) or "postgres"
+pub const EXO_PERSISTED_QUERY_CACHE_SIZE: &str = "EXO_PERSISTED_QUERY_CACHE_SIZE";
+
+pub const EXO_RESOLVER_CACHE_SIZE: &str = "EXO_RESOLVER_CACHE_SIZE"; // 0 disables the resolver cache
+
+pub const EXO_MAX_QUERY_COST: &str = "EXO_MAX_QUERY_COST";
+pub const EXO_MAX_BATCH_SIZE: &str = "EXO_MAX_BATCH_SIZE";
+pub const EXO_MAX_UPLOAD_FILE_SIZE: &str = "EXO_MAX_UPLOAD_FILE_SIZE"; // in bytes
+pub const EXO_MAX_UPLOAD_FILES: &str = "EXO_MAX_UPLOAD_FILES";
+pub const EXO_QUERY_COST_DEFAULT_LIST_SIZE: &str = "EXO_QUERY_COST_DEFAULT_LIST_SIZE";
+
 pub const EXO_UNSTABLE_ENABLE_RPC_API: &str = "EXO_UNSTABLE_ENABLE_RPC_API";
 pub const EXO_ENABLE_MCP: &str = "EXO_ENABLE_MCP";
 
 pub const EXO_WWW_AUTHENTICATE_HEADER: &str = "EXO_WWW_AUTHENTICATE_HEADER";
 
+// Set to "true" while `exo schema migrate` runs data migrations (so that their mutations can be
+// restricted to migrations with an `@env` context)
+pub const EXO_DATA_MIGRATION: &str = "EXO_DATA_MIGRATION";
+
 #[derive(Debug)]
 pub enum DeploymentMode {
     Yolo,               // Corresponds to "exo yolo"
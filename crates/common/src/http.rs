@@ -23,10 +23,20 @@ pub trait RequestPayload {
     ///
     /// Except for the first call, the return value will be `Value::Null`
     fn take_body(&self) -> Value;
+
+    /// Take away the raw body stream for requests whose body isn't JSON (such as a
+    /// `multipart/form-data` request with file uploads)
+    ///
+    /// Returns `None` if the request has no such body or if it has already been taken
+    fn take_body_stream(&self) -> Option<RequestBodyStream> {
+        None
+    }
 }
 
 type PinnedStream<E> = Pin<Box<dyn Stream<Item = Result<Bytes, E>> + Send>>;
 
+pub type RequestBodyStream = PinnedStream<std::io::Error>;
+
 #[derive(Debug, Clone)]
 pub struct Headers {
     inner: Vec<(String, String)>,
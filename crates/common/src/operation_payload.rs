@@ -7,6 +7,7 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use bytes::Bytes;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 
@@ -16,6 +17,18 @@ pub struct OperationsPayload {
     pub query: Option<String>,
     pub variables: Option<Map<String, Value>>,
     pub query_hash: Option<String>,
+    /// Files uploaded along with the operation (through a `multipart/form-data` request)
+    pub uploads: Vec<Upload>,
+}
+
+/// A file uploaded through a multipart request
+///
+/// The content takes the place of the variable value at `path` (for example, `["files", "0"]`
+/// for the first element of the `files` variable).
+#[derive(Debug, Clone)]
+pub struct Upload {
+    pub path: Vec<String>,
+    pub content: Bytes,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -35,11 +48,9 @@ impl OperationsPayload {
             let query_hash = raw_payload.extensions.as_ref().and_then(|extensions| {
                 extensions
                     .get("persistedQuery")
-                    .and_then(|persisted_query| {
-                        persisted_query
-                            .get("sha256Hash")
-                            .map(|hash| hash.as_str().unwrap().to_string())
-                    })
+                    .and_then(|persisted_query| persisted_query.get("sha256Hash"))
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
             });
 
             OperationsPayload {
@@ -47,10 +58,31 @@ impl OperationsPayload {
                 query: raw_payload.query,
                 variables: raw_payload.variables,
                 query_hash,
+                uploads: vec![],
             }
         })
     }
 
+    /// Create a payload from the URL parameters of a GET request: `query`, `operationName`, and
+    /// the JSON-encoded `variables` and `extensions`
+    pub fn from_query_params(params: &Value) -> Result<Self, serde_json::Error> {
+        let param = |name: &str| params.get(name).and_then(Value::as_str);
+
+        let mut payload = Map::new();
+        for name in ["query", "operationName"] {
+            if let Some(value) = param(name) {
+                payload.insert(name.to_string(), Value::String(value.to_string()));
+            }
+        }
+        for name in ["variables", "extensions"] {
+            if let Some(value) = param(name) {
+                payload.insert(name.to_string(), serde_json::from_str(value)?);
+            }
+        }
+
+        Self::from_json(Value::Object(payload))
+    }
+
     pub fn to_json(&self) -> Result<Value, serde_json::Error> {
         let raw_payload = RawOperationsPayload {
             operation_name: self.operation_name.clone(),
@@ -68,3 +100,65 @@ impl OperationsPayload {
         serde_json::to_value(&raw_payload)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn from_query_params() {
+        let payload = OperationsPayload::from_query_params(&json!({
+            "query": "query($id: Int!) { concert(id: $id) { id } }",
+            "operationName": "Concert",
+            "variables": r#"{"id": 1}"#,
+            "extensions": r#"{"persistedQuery": {"version": 1, "sha256Hash": "abc"}}"#,
+        }))
+        .unwrap();
+
+        assert_eq!(
+            payload.query.as_deref(),
+            Some("query($id: Int!) { concert(id: $id) { id } }")
+        );
+        assert_eq!(payload.operation_name.as_deref(), Some("Concert"));
+        assert_eq!(
+            payload.variables,
+            Some(Map::from_iter([("id".to_string(), json!(1))]))
+        );
+        assert_eq!(payload.query_hash.as_deref(), Some("abc"));
+    }
+
+    #[test]
+    fn from_query_params_with_hash_only() {
+        let payload = OperationsPayload::from_query_params(&json!({
+            "extensions": r#"{"persistedQuery": {"version": 1, "sha256Hash": "abc"}}"#,
+        }))
+        .unwrap();
+
+        assert_eq!(payload.query, None);
+        assert_eq!(payload.operation_name, None);
+        assert_eq!(payload.variables, None);
+        assert_eq!(payload.query_hash.as_deref(), Some("abc"));
+    }
+
+    #[test]
+    fn from_query_params_invalid() {
+        // Variables and extensions must be JSON-encoded objects
+        for params in [
+            json!({ "query": "{ concerts { id } }", "variables": "{id: 1}" }),
+            json!({ "query": "{ concerts { id } }", "variables": "[1]" }),
+            json!({ "query": "{ concerts { id } }", "extensions": "persistedQuery" }),
+        ] {
+            assert!(OperationsPayload::from_query_params(&params).is_err());
+        }
+
+        // A hash that isn't a string is ignored
+        let payload = OperationsPayload::from_query_params(&json!({
+            "query": "{ concerts { id } }",
+            "extensions": r#"{"persistedQuery": {"sha256Hash": 1}}"#,
+        }))
+        .unwrap();
+        assert_eq!(payload.query_hash, None);
+    }
+}
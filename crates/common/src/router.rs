@@ -9,7 +9,9 @@
 
 use crate::{
     context::RequestContext,
-    http::{Headers, RequestHead, RequestPayload, ResponseBody, ResponsePayload},
+    http::{
+        Headers, RequestBodyStream, RequestHead, RequestPayload, ResponseBody, ResponsePayload,
+    },
 };
 use async_trait::async_trait;
 use http::StatusCode;
@@ -56,6 +58,13 @@ impl RequestPayload for PlainRequestPayload<'_> {
             Self::Internal(request) => request.take_body(),
         }
     }
+
+    fn take_body_stream(&self) -> Option<RequestBodyStream> {
+        match self {
+            Self::External(request) => request.take_body_stream(),
+            Self::Internal(request) => request.take_body_stream(),
+        }
+    }
 }
 
 pub struct CompositeRouter<Rtr> {
@@ -166,6 +166,7 @@ pub struct AstMethod<T: NodeTypedness> {
 pub enum AstMethodType {
     Query,
     Mutation,
+    Subscription,
 }
 
 impl TryFrom<&str> for AstMethodType {
@@ -174,6 +175,7 @@ impl TryFrom<&str> for AstMethodType {
         match s {
             "query" => Ok(AstMethodType::Query),
             "mutation" => Ok(AstMethodType::Mutation),
+            "subscription" => Ok(AstMethodType::Subscription),
             _ => Err(()),
         }
     }
@@ -0,0 +1,89 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use codemap::Span;
+use codemap_diagnostic::{Diagnostic, Level, SpanLabel, SpanStyle};
+use core_model::types::{CacheHint, CacheScope};
+
+use crate::{
+    ast::ast_types::{AstAnnotationParam, AstAnnotationParams, AstLiteral},
+    typechecker::AnnotationMap,
+};
+
+use super::resolved_builder::AnnotationMapHelper;
+
+/// The cache hint from `@cache(<max age>)` or `@cache(maxAge=<seconds>, scope="PUBLIC" |
+/// "PRIVATE", ttl=<seconds>)` on a type or a query (the scope defaults to `PUBLIC`). At least one of
+/// `maxAge` and `ttl` must be specified (`maxAge` defaults to 0 when only `ttl` is).
+pub fn build_cache_hint(
+    annotations: &AnnotationMap,
+    target_name: &str,
+    span: Span,
+) -> Result<Option<CacheHint>, Diagnostic> {
+    let Some(params) = annotations.get("cache") else {
+        return Ok(None);
+    };
+
+    let (max_age, scope, ttl) = match params {
+        AstAnnotationParams::Single(max_age, _) => (Some(max_age), None, None),
+        AstAnnotationParams::Map(params, _) => {
+            (params.get("maxAge"), params.get("scope"), params.get("ttl"))
+        }
+        AstAnnotationParams::None => (None, None, None),
+    };
+
+    let seconds = |param: Option<&AstAnnotationParam<_>>, name: &str| match param {
+        None => Ok(None),
+        Some(AstAnnotationParam::Literal(AstLiteral::Number(value, _))) => value
+            .parse::<u32>()
+            .map(Some)
+            .map_err(|_| cache_hint_error(target_name, name, span)),
+        Some(_) => Err(cache_hint_error(target_name, name, span)),
+    };
+
+    let ttl = seconds(ttl, "ttl")?;
+    let max_age = match (seconds(max_age, "maxAge")?, ttl) {
+        (Some(max_age), _) => max_age,
+        (None, Some(_)) => 0,
+        (None, None) => return Err(cache_hint_error(target_name, "maxAge", span)),
+    };
+
+    let scope = match scope {
+        None => CacheScope::Public,
+        Some(AstAnnotationParam::Literal(AstLiteral::String(value, _))) => {
+            CacheScope::parse(value).ok_or_else(|| cache_hint_error(target_name, "scope", span))?
+        }
+        Some(_) => return Err(cache_hint_error(target_name, "scope", span)),
+    };
+
+    Ok(Some(CacheHint {
+        max_age,
+        scope,
+        ttl,
+    }))
+}
+
+fn cache_hint_error(target_name: &str, param: &str, span: Span) -> Diagnostic {
+    let expected = if param == "scope" {
+        r#"either "PUBLIC" or "PRIVATE""#
+    } else {
+        "a non-negative number of seconds"
+    };
+
+    Diagnostic {
+        level: Level::Error,
+        message: format!("The {param} of @cache for {target_name} must be {expected}"),
+        code: Some("C000".to_string()),
+        spans: vec![SpanLabel {
+            span,
+            style: SpanStyle::Primary,
+            label: None,
+        }],
+    }
+}
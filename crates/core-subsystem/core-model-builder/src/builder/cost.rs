@@ -0,0 +1,54 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use codemap::Span;
+use codemap_diagnostic::{Diagnostic, Level, SpanLabel, SpanStyle};
+use core_model::types::Cost;
+
+use crate::{
+    ast::ast_types::{AstAnnotationParam, AstAnnotationParams, AstLiteral},
+    typechecker::AnnotationMap,
+};
+
+use super::resolved_builder::AnnotationMapHelper;
+
+/// The cost from `@cost(<weight>)` on a type, a query, or a mutation
+pub fn build_cost(
+    annotations: &AnnotationMap,
+    target_name: &str,
+    span: Span,
+) -> Result<Option<Cost>, Diagnostic> {
+    let Some(params) = annotations.get("cost") else {
+        return Ok(None);
+    };
+
+    match params {
+        AstAnnotationParams::Single(
+            AstAnnotationParam::Literal(AstLiteral::Number(value, _)),
+            _,
+        ) => value
+            .parse::<u32>()
+            .map(|weight| Some(Cost { weight }))
+            .map_err(|_| cost_error(target_name, span)),
+        _ => Err(cost_error(target_name, span)),
+    }
+}
+
+fn cost_error(target_name: &str, span: Span) -> Diagnostic {
+    Diagnostic {
+        level: Level::Error,
+        message: format!("The weight of @cost for {target_name} must be a non-negative integer"),
+        code: Some("C000".to_string()),
+        spans: vec![SpanLabel {
+            span,
+            style: SpanStyle::Primary,
+            label: None,
+        }],
+    }
+}
@@ -7,6 +7,8 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+pub mod cache_hint;
+pub mod cost;
 pub mod resolved_builder;
 pub mod system_builder;
 
@@ -46,6 +46,35 @@ where
     BooleanLiteral(bool),
 }
 
+impl<PrimExpr> AccessPredicateExpression<PrimExpr>
+where
+    PrimExpr: Send + Sync,
+{
+    /// Visit each primitive expression (such as `AuthContext.role` and `"admin"` in
+    /// `AuthContext.role == "admin" || self.published`). Any expressions nested in a primitive
+    /// expression (such as the arguments of a function call) are left to the visitor.
+    pub fn visit_primitives<'a>(&'a self, visitor: &mut impl FnMut(&'a PrimExpr)) {
+        match self {
+            AccessPredicateExpression::LogicalOp(AccessLogicalExpression::Not(expr)) => {
+                expr.visit_primitives(visitor)
+            }
+            AccessPredicateExpression::LogicalOp(
+                AccessLogicalExpression::And(left, right)
+                | AccessLogicalExpression::Or(left, right),
+            ) => {
+                left.visit_primitives(visitor);
+                right.visit_primitives(visitor);
+            }
+            AccessPredicateExpression::RelationalOp(op) => {
+                let (left, right) = op.sides();
+                visitor(left);
+                visitor(right);
+            }
+            AccessPredicateExpression::BooleanLiteral(_) => {}
+        }
+    }
+}
+
 /// A logical expression created from other [`AccessPredicateExpression`]s
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum AccessLogicalExpression<PrimExpr>
@@ -10,8 +10,8 @@
 //! Common support for representing GraphQL types such as `Int`, `List<Int>`, `Optional<Int>`, `Optional<List<Int>>`, etc.
 //!
 
-use async_graphql_parser::types::ConstDirective;
-use async_graphql_value::ConstValue;
+use async_graphql_parser::{Positioned, types::ConstDirective};
+use async_graphql_value::{ConstValue, Name};
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -281,6 +281,173 @@ impl DirectivesProvider for TypeValidation {
     }
 }
 
+/// Who may cache a response (following the `public` and `private` directives of `Cache-Control`)
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheScope {
+    /// Any cache (including shared caches such as CDNs)
+    Public,
+    /// Only the client's own cache (the response depends on who asked)
+    Private,
+}
+
+impl CacheScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CacheScope::Public => "PUBLIC",
+            CacheScope::Private => "PRIVATE",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_uppercase().as_str() {
+            "PUBLIC" => Some(CacheScope::Public),
+            "PRIVATE" => Some(CacheScope::Private),
+            _ => None,
+        }
+    }
+}
+
+/// How long (and by whom) the result of a query or a type may be cached (from the `@cache`
+/// annotation)
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheHint {
+    /// In seconds
+    pub max_age: u32,
+    pub scope: CacheScope,
+    /// How long (in seconds) the server may reuse the result from its resolver cache (`None` if the
+    /// result must not be cached by the server)
+    pub ttl: Option<u32>,
+}
+
+pub const CACHE_DIRECTIVE_NAME: &str = "cache";
+
+impl CacheHint {
+    /// Combine with another hint that applies to the same response: the shorter maximum age (and
+    /// ttl) wins and a private hint makes the whole response private. The response may be cached
+    /// by the server only if both hints have a ttl.
+    pub fn restrict(self, other: CacheHint) -> CacheHint {
+        CacheHint {
+            max_age: self.max_age.min(other.max_age),
+            scope: if self.scope == CacheScope::Private || other.scope == CacheScope::Private {
+                CacheScope::Private
+            } else {
+                CacheScope::Public
+            },
+            ttl: self
+                .ttl
+                .zip(other.ttl)
+                .map(|(ttl, other_ttl)| ttl.min(other_ttl)),
+        }
+    }
+
+    /// The hint expressed by a `@cache(maxAge: ..., scope: ..., ttl: ...)` directive (see
+    /// [DirectivesProvider] for [CacheHint]), if any
+    pub fn from_directives(directives: &[Positioned<ConstDirective>]) -> Option<CacheHint> {
+        let directive = directives
+            .iter()
+            .find(|directive| directive.node.name.node == CACHE_DIRECTIVE_NAME)?;
+
+        let argument = |name: &str| {
+            directive
+                .node
+                .arguments
+                .iter()
+                .find(|(argument_name, _)| argument_name.node == name)
+                .map(|(_, value)| &value.node)
+        };
+
+        let max_age = match argument("maxAge")? {
+            ConstValue::Number(number) => u32::try_from(number.as_u64()?).ok()?,
+            _ => return None,
+        };
+        let scope = match argument("scope") {
+            Some(ConstValue::Enum(scope)) => CacheScope::parse(scope)?,
+            _ => CacheScope::Public,
+        };
+        let ttl = match argument("ttl") {
+            Some(ConstValue::Number(number)) => Some(u32::try_from(number.as_u64()?).ok()?),
+            _ => None,
+        };
+
+        Some(CacheHint {
+            max_age,
+            scope,
+            ttl,
+        })
+    }
+}
+
+impl DirectivesProvider for CacheHint {
+    fn get_directives(&self) -> Vec<ConstDirective> {
+        let mut arguments = vec![
+            (
+                default_positioned_name("maxAge"),
+                default_positioned(ConstValue::Number(self.max_age.into())),
+            ),
+            (
+                default_positioned_name("scope"),
+                default_positioned(ConstValue::Enum(Name::new(self.scope.as_str()))),
+            ),
+        ];
+        if let Some(ttl) = self.ttl {
+            arguments.push((
+                default_positioned_name("ttl"),
+                default_positioned(ConstValue::Number(ttl.into())),
+            ));
+        }
+
+        vec![ConstDirective {
+            name: default_positioned_name(CACHE_DIRECTIVE_NAME),
+            arguments,
+        }]
+    }
+}
+
+/// The cost of resolving a field of a type or a query (or a mutation), overriding the default cost
+/// in the query cost analysis (from the `@cost` annotation)
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cost {
+    pub weight: u32,
+}
+
+pub const COST_DIRECTIVE_NAME: &str = "cost";
+
+impl Cost {
+    /// The cost expressed by a `@cost(weight: ...)` directive (see [DirectivesProvider] for
+    /// [Cost]), if any
+    pub fn from_directives(directives: &[Positioned<ConstDirective>]) -> Option<Cost> {
+        let directive = directives
+            .iter()
+            .find(|directive| directive.node.name.node == COST_DIRECTIVE_NAME)?;
+
+        let weight = directive
+            .node
+            .arguments
+            .iter()
+            .find(|(argument_name, _)| argument_name.node == "weight")
+            .map(|(_, value)| &value.node)?;
+
+        match weight {
+            ConstValue::Number(number) => u32::try_from(number.as_u64()?)
+                .ok()
+                .map(|weight| Cost { weight }),
+            _ => None,
+        }
+    }
+}
+
+impl DirectivesProvider for Cost {
+    fn get_directives(&self) -> Vec<ConstDirective> {
+        vec![ConstDirective {
+            name: default_positioned_name(COST_DIRECTIVE_NAME),
+            arguments: vec![(
+                default_positioned_name("weight"),
+                default_positioned(ConstValue::Number(self.weight.into())),
+            )],
+        }]
+    }
+}
+
 fn get_range_directive<T: Into<ConstValue>>(min: T, max: T) -> ConstDirective {
     ConstDirective {
         name: default_positioned_name("range"),
@@ -23,6 +23,7 @@ use core_model_builder::{
 };
 use core_plugin_shared::error::ModelSerializationError;
 use core_plugin_shared::serializable_system::SerializableSubsystem;
+use core_resolver::persisted_queries::PersistedQueryStore;
 use core_resolver::plugin::SubsystemGraphQLResolver;
 use core_resolver::plugin::SubsystemRpcResolver;
 use thiserror::Error;
@@ -119,6 +120,9 @@ pub trait GraphQLSubsystemBuilder {
 pub struct SubsystemResolver {
     pub graphql: Option<Arc<dyn SubsystemGraphQLResolver + Send + Sync>>,
     pub rpc: Option<Box<dyn SubsystemRpcResolver + Send + Sync>>,
+    /// A store for automatic persisted queries backed by the subsystem (used when configured
+    /// through `EXO_PERSISTED_QUERY_STORE`)
+    pub persisted_query_store: Option<Arc<dyn PersistedQueryStore>>,
 }
 
 impl SubsystemResolver {
@@ -126,7 +130,21 @@ impl SubsystemResolver {
         graphql: Option<Arc<dyn SubsystemGraphQLResolver + Send + Sync>>,
         rpc: Option<Box<dyn SubsystemRpcResolver + Send + Sync>>,
     ) -> Self {
-        Self { graphql, rpc }
+        Self {
+            graphql,
+            rpc,
+            persisted_query_store: None,
+        }
+    }
+
+    pub fn with_persisted_query_store(
+        self,
+        persisted_query_store: Option<Arc<dyn PersistedQueryStore>>,
+    ) -> Self {
+        Self {
+            persisted_query_store,
+            ..self
+        }
     }
 }
 
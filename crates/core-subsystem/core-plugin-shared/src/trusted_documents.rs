@@ -12,7 +12,7 @@ pub enum TrustedDocuments {
     MatchingOnly(HashMap<String, String>),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum TrustedDocumentEnforcement {
     Enforce,
     DoNotEnforce,
@@ -59,18 +59,38 @@ impl TrustedDocuments {
                 }
             }
             (None, Some(query_hash)) => self.get(query_hash).ok_or(if allow_untrusted {
-                TrustedDocumentResolutionError::NotFound
+                TrustedDocumentResolutionError::PersistedQueryNotFound
             } else {
                 TrustedDocumentResolutionError::NotTrusted {
                     hash: Some(query_hash.to_string()),
                     query: None,
                 }
             }),
-            (Some(_), Some(_)) => Err(TrustedDocumentResolutionError::BothPresent),
+            // A client registering a persisted query sends both (see [TrustedDocuments::contains])
+            (Some(query), Some(query_hash)) => {
+                if Self::sha256(query) != query_hash {
+                    Err(TrustedDocumentResolutionError::HashMismatch)
+                } else if allow_untrusted {
+                    Ok(query)
+                } else {
+                    self.get(query_hash)
+                        .ok_or(TrustedDocumentResolutionError::NotTrusted {
+                            hash: Some(query_hash.to_string()),
+                            query: Some(query.to_string()),
+                        })
+                }
+            }
             (None, None) => Err(TrustedDocumentResolutionError::NonePresent),
         }
     }
 
+    /// Is the document with the given hash known at build time? Documents not known at build time
+    /// may be registered by clients through the automatic persisted queries protocol (when
+    /// untrusted documents are allowed).
+    pub fn contains(&self, query_hash: &str) -> bool {
+        self.get(query_hash).is_some()
+    }
+
     fn get<'a>(&'a self, key: &str) -> Option<&'a str> {
         match self {
             TrustedDocuments::All(mapping) => mapping.get(key),
@@ -79,7 +99,7 @@ impl TrustedDocuments {
         .map(|s| s.as_str())
     }
 
-    fn sha256(query: &str) -> String {
+    pub fn sha256(query: &str) -> String {
         let query_hash = sha2::Sha256::digest(query.as_bytes());
         base16ct::lower::encode_string(&query_hash)
     }
@@ -93,11 +113,11 @@ pub enum TrustedDocumentResolutionError {
         query: Option<String>,
     },
 
-    #[error("The hash present in the query does not match any trusted document")]
-    NotFound,
+    #[error("PersistedQueryNotFound")]
+    PersistedQueryNotFound,
 
-    #[error("Both query and query_hash present in the payload, only one should be")]
-    BothPresent,
+    #[error("provided sha does not match query")]
+    HashMismatch,
 
     #[error("Neither query not query_hash are in the payload")]
     NonePresent,
@@ -163,14 +183,14 @@ mod tests {
                     Ok(query.as_str())
                 );
 
-                // Should not be able to resolve both by query and hash
+                // Should be able to resolve when both query and hash are present (as when registering a persisted query)
                 assert_eq!(
                     trusted_documents.resolve(
                         Some(&query),
                         Some(&hash),
                         TrustedDocumentEnforcement::Enforce
                     ),
-                    Err(TrustedDocumentResolutionError::BothPresent)
+                    Ok(query.as_str())
                 );
                 assert_eq!(
                     trusted_documents.resolve(
@@ -178,7 +198,17 @@ mod tests {
                         Some(&hash),
                         TrustedDocumentEnforcement::DoNotEnforce
                     ),
-                    Err(TrustedDocumentResolutionError::BothPresent)
+                    Ok(query.as_str())
+                );
+
+                // Should not be able to resolve if the hash doesn't match the query
+                assert_eq!(
+                    trusted_documents.resolve(
+                        Some(&query),
+                        Some("hash3"),
+                        TrustedDocumentEnforcement::DoNotEnforce
+                    ),
+                    Err(TrustedDocumentResolutionError::HashMismatch)
                 );
 
                 // In unenforced mode, should be able to resolve by query, but not by hash (there would be no mapping for it)
@@ -196,7 +226,7 @@ mod tests {
                         Some("hash3"),
                         TrustedDocumentEnforcement::DoNotEnforce
                     ),
-                    Err(TrustedDocumentResolutionError::NotFound)
+                    Err(TrustedDocumentResolutionError::PersistedQueryNotFound)
                 );
 
                 // At least one of query or hash should be present
@@ -235,6 +265,31 @@ mod tests {
             })
         );
 
+        // In enforced mode with matching only, registering an unknown query should result in "not trusted"
+        let query3_hash = TrustedDocuments::sha256("query3");
+        assert_eq!(
+            trusted_documents_matching_only.resolve(
+                Some("query3"),
+                Some(&query3_hash),
+                TrustedDocumentEnforcement::Enforce
+            ),
+            Err(TrustedDocumentResolutionError::NotTrusted {
+                hash: Some(query3_hash.clone()),
+                query: Some("query3".to_string())
+            })
+        );
+
+        // In enforced mode with all, should be able to register an unknown query
+        assert_eq!(
+            trusted_documents_all.resolve(
+                Some("query3"),
+                Some(&query3_hash),
+                TrustedDocumentEnforcement::Enforce
+            ),
+            Ok("query3")
+        );
+        assert!(!trusted_documents_all.contains(&query3_hash));
+
         // In enforced mode with all, should be able to resolve by query, but not by hash (there would be no mapping for it)
         assert_eq!(
             trusted_documents_all.resolve(
@@ -246,7 +301,7 @@ mod tests {
         );
         assert_eq!(
             trusted_documents_all.resolve(None, Some("hash3"), TrustedDocumentEnforcement::Enforce),
-            Err(TrustedDocumentResolutionError::NotFound)
+            Err(TrustedDocumentResolutionError::PersistedQueryNotFound)
         );
     }
 }
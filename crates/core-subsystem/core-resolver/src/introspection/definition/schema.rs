@@ -111,10 +111,38 @@ impl Schema {
             mutations
         };
 
+        // Subscriptions only read data, so they follow the profile for queries
+        let subscriptions = {
+            let mut subscriptions = subsystem_resolvers
+                .iter()
+                .fold(vec![], |mut acc, resolver| {
+                    acc.extend(
+                        resolver
+                            .schema_subscriptions()
+                            .into_iter()
+                            .filter(|field_defn| {
+                                let field_return_type = underlying_type(&field_defn.ty.node);
+                                profile.query_matches(
+                                    field_return_type,
+                                    &field_defn.name.node,
+                                    primitive_type::PrimitiveType::is_primitive,
+                                )
+                            })
+                            .collect::<Vec<FieldDefinition>>(),
+                    );
+                    acc
+                });
+
+            // ensure introspection outputs subscriptions in a stable order
+            subscriptions.sort_by_key(|s| s.name.clone());
+            subscriptions
+        };
+
         Self::new(
             type_definitions,
             queries,
             mutations,
+            subscriptions,
             declaration_doc_comments,
         )
     }
@@ -123,6 +151,7 @@ impl Schema {
         type_definitions: Vec<TypeDefinition>,
         queries: Vec<FieldDefinition>,
         mutations: Vec<FieldDefinition>,
+        subscriptions: Vec<FieldDefinition>,
         declaration_doc_comments: Arc<Option<String>>,
     ) -> Schema {
         let mut type_definitions = type_definitions;
@@ -162,6 +191,19 @@ impl Schema {
             });
         };
 
+        if !subscriptions.is_empty() {
+            type_definitions.push(TypeDefinition {
+                extend: false,
+                description: None,
+                name: default_positioned_name(SUBSCRIPTION_ROOT_TYPENAME),
+                directives: vec![],
+                kind: TypeKind::Object(ObjectType {
+                    implements: vec![],
+                    fields: subscriptions.into_iter().map(default_positioned).collect(),
+                }),
+            });
+        };
+
         type_definitions.push(Self::create_schema_type_definition());
         type_definitions.push(Self::create_type_definition());
         type_definitions.push(Self::create_field_definition());
@@ -11,7 +11,9 @@ pub mod access_solver;
 pub mod context_extractor;
 pub mod introspection;
 pub mod operation_resolver;
+pub mod persisted_queries;
 pub mod plugin;
+pub mod resolver_cache;
 pub mod system_resolver;
 pub mod system_rpc_resolver;
 pub mod validation;
@@ -21,7 +21,8 @@ use crate::{FieldResolver, QueryResponseBody};
 use common::context::RequestContext;
 /// Resolver for the root operation.
 ///
-/// The operation may be a query or a mutation and may be for data or for introspection.
+/// The operation may be a query or a mutation and may be for data or for introspection (a
+/// subscription is resolved through [GraphQLSystemResolver::resolve_subscription]).
 ///
 #[async_trait]
 impl FieldResolver<QueryResponse, SystemResolutionError, GraphQLSystemResolver>
@@ -48,31 +49,42 @@ impl FieldResolver<QueryResponse, SystemResolutionError, GraphQLSystemResolver>
         let intercepted_operation =
             InterceptedOperation::new(Some(interception_tree), self.typ, field, system_resolver);
 
-        let QueryResponse { body, headers } =
-            intercepted_operation.resolve(request_context).await?;
+        let response = intercepted_operation.resolve(request_context).await?;
 
-        // A proceed call in an around interceptor or a module call may have returned more fields
-        // that necessary, so we need to filter out the fields that are not needed.
-        // TODO: Validate that all requested fields are present in the response.
-        let field_selected_response_body = match body {
-            QueryResponseBody::Json(value @ serde_json::Value::Object(_)) => {
-                let resolved_set = value
-                    .resolve_fields(&field.subfields, &(), request_context)
-                    .await?;
-                QueryResponseBody::Json(serde_json::Value::Object(
-                    resolved_set.into_iter().collect(),
-                ))
-            }
-            _ => body,
-        };
-
-        Ok(QueryResponse {
-            body: field_selected_response_body,
-            headers,
-        })
+        select_subfields(response, field, request_context).await
     }
 }
 
+/// Keep only the fields selected by the operation in the response.
+///
+/// A proceed call in an around interceptor, a module call, or a subscription event may have
+/// returned more fields that necessary, so we need to filter out the fields that are not needed.
+// TODO: Validate that all requested fields are present in the response.
+pub(crate) async fn select_subfields(
+    response: QueryResponse,
+    field: &ValidatedField,
+    request_context: &RequestContext<'_>,
+) -> Result<QueryResponse, SystemResolutionError> {
+    let QueryResponse { body, headers } = response;
+
+    let field_selected_response_body = match body {
+        QueryResponseBody::Json(value @ serde_json::Value::Object(_)) => {
+            let resolved_set = value
+                .resolve_fields(&field.subfields, &(), request_context)
+                .await?;
+            QueryResponseBody::Json(serde_json::Value::Object(
+                resolved_set.into_iter().collect(),
+            ))
+        }
+        _ => body,
+    };
+
+    Ok(QueryResponse {
+        body: field_selected_response_body,
+        headers,
+    })
+}
+
 #[async_trait]
 impl FieldResolver<Value, SystemResolutionError, ()> for Value {
     async fn resolve_field<'a>(
@@ -0,0 +1,127 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Storage for the queries registered through the automatic persisted queries (APQ) protocol.
+//!
+//! A client first sends only the hash of a query. If the hash isn't known (neither as a trusted
+//! document nor as a registered query), the client gets a `PersistedQueryNotFound` error and
+//! retries with both the query and its hash, which registers the query for subsequent requests.
+
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use indexmap::IndexMap;
+use thiserror::Error;
+
+/// The number of queries held by [InMemoryPersistedQueryStore] unless configured otherwise
+pub const DEFAULT_PERSISTED_QUERY_CACHE_SIZE: usize = 1000;
+
+#[async_trait]
+pub trait PersistedQueryStore: Send + Sync {
+    /// Get the query registered with the given hash (the hex-encoded SHA-256 of the query)
+    async fn get(&self, query_hash: &str) -> Result<Option<String>, PersistedQueryStoreError>;
+
+    /// Register a query with its hash. The caller must have verified that the hash matches the
+    /// query.
+    async fn put(&self, query_hash: &str, query: &str) -> Result<(), PersistedQueryStoreError>;
+}
+
+/// A store that keeps the most recently used queries in memory (evicting the least recently used
+/// query when full)
+pub struct InMemoryPersistedQueryStore {
+    capacity: usize,
+    queries: Mutex<IndexMap<String, String>>,
+}
+
+impl InMemoryPersistedQueryStore {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            queries: Mutex::new(IndexMap::new()),
+        }
+    }
+}
+
+impl Default for InMemoryPersistedQueryStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_PERSISTED_QUERY_CACHE_SIZE)
+    }
+}
+
+#[async_trait]
+impl PersistedQueryStore for InMemoryPersistedQueryStore {
+    async fn get(&self, query_hash: &str) -> Result<Option<String>, PersistedQueryStoreError> {
+        let mut queries = self.queries.lock().unwrap();
+
+        // Move the query to the end to mark it as the most recently used
+        Ok(queries.shift_remove(query_hash).map(|query| {
+            queries.insert(query_hash.to_string(), query.clone());
+            query
+        }))
+    }
+
+    async fn put(&self, query_hash: &str, query: &str) -> Result<(), PersistedQueryStoreError> {
+        if self.capacity == 0 {
+            return Ok(());
+        }
+
+        let mut queries = self.queries.lock().unwrap();
+
+        queries.shift_remove(query_hash);
+        while queries.len() >= self.capacity {
+            queries.shift_remove_index(0);
+        }
+        queries.insert(query_hash.to_string(), query.to_string());
+
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("{0}")]
+pub struct PersistedQueryStoreError(#[source] pub Box<dyn std::error::Error + Send + Sync>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn in_memory_store_evicts_least_recently_used() {
+        let store = InMemoryPersistedQueryStore::new(2);
+
+        store.put("hash1", "query1").await.unwrap();
+        store.put("hash2", "query2").await.unwrap();
+
+        // Using the first query makes the second one the least recently used
+        assert_eq!(
+            store.get("hash1").await.unwrap(),
+            Some("query1".to_string())
+        );
+        store.put("hash3", "query3").await.unwrap();
+
+        assert_eq!(
+            store.get("hash1").await.unwrap(),
+            Some("query1".to_string())
+        );
+        assert_eq!(store.get("hash2").await.unwrap(), None);
+        assert_eq!(
+            store.get("hash3").await.unwrap(),
+            Some("query3".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn in_memory_store_without_capacity() {
+        let store = InMemoryPersistedQueryStore::new(0);
+
+        store.put("hash1", "query1").await.unwrap();
+
+        assert_eq!(store.get("hash1").await.unwrap(), None);
+    }
+}
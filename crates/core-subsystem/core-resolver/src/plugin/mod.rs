@@ -9,5 +9,7 @@
 
 pub mod subsystem_graphql_resolver;
 pub mod subsystem_rpc_resolver;
-pub use subsystem_graphql_resolver::{SubsystemGraphQLResolver, SubsystemResolutionError};
+pub use subsystem_graphql_resolver::{
+    SubscriptionStream, SubsystemGraphQLResolver, SubsystemResolutionError,
+};
 pub use subsystem_rpc_resolver::SubsystemRpcResolver;
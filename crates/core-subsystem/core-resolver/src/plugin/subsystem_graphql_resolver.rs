@@ -7,14 +7,17 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::collections::BTreeSet;
+
 use crate::{
-    InterceptedOperation, QueryResponse, system_resolver::GraphQLSystemResolver,
-    validation::field::ValidatedField,
+    InterceptedOperation, QueryResponse, resolver_cache::QueryDependencies,
+    system_resolver::GraphQLSystemResolver, validation::field::ValidatedField,
 };
 use async_graphql_parser::types::{FieldDefinition, OperationType, TypeDefinition};
 use async_trait::async_trait;
 use common::context::{ContextExtractionError, RequestContext};
 use core_plugin_shared::interception::InterceptorIndex;
+use futures::stream::BoxStream;
 use thiserror::Error;
 use tokio::runtime::Handle;
 
@@ -81,6 +84,41 @@ pub trait SubsystemGraphQLResolver: Sync {
         system_resolver: &'a GraphQLSystemResolver,
     ) -> Result<Option<QueryResponse>, SubsystemResolutionError>;
 
+    /// Resolve a subscription operation into a stream of responses (one per event)
+    ///
+    /// Returns `None` if the operation is not handled by this subsystem
+    async fn resolve_subscription<'a>(
+        &'a self,
+        _operation: &'a ValidatedField,
+        _request_context: &'a RequestContext<'a>,
+        _system_resolver: &'a GraphQLSystemResolver,
+    ) -> Result<Option<SubscriptionStream<'a>>, SubsystemResolutionError> {
+        Ok(None)
+    }
+
+    /// What the result of a query depends on besides the query itself: the values of the context
+    /// fields referenced by the applicable access rules and the tables read (see
+    /// [ResolverCache](crate::resolver_cache::ResolverCache))
+    ///
+    /// Returns `None` if the query is not handled by this subsystem or its result may not be
+    /// cached
+    async fn query_dependencies<'a>(
+        &'a self,
+        _operation: &'a ValidatedField,
+        _request_context: &'a RequestContext<'a>,
+    ) -> Result<Option<QueryDependencies>, SubsystemResolutionError> {
+        Ok(None)
+    }
+
+    /// The tables that a mutation writes to (to invalidate the cached results of the queries that
+    /// read them)
+    ///
+    /// Returns `None` if the mutation is not handled by this subsystem or doesn't write to any
+    /// table
+    fn mutation_tables(&self, _operation: &ValidatedField) -> Option<BTreeSet<String>> {
+        None
+    }
+
     /// Involves an interceptor
     ///
     /// Returns `None` for `QueryResponse` if the interceptor is of before/after type (which is not
@@ -99,11 +137,18 @@ pub trait SubsystemGraphQLResolver: Sync {
     fn schema_queries(&self) -> Vec<FieldDefinition>;
     /// Mutations supported by this subsystem
     fn schema_mutations(&self) -> Vec<FieldDefinition>;
+    /// Subscriptions supported by this subsystem
+    fn schema_subscriptions(&self) -> Vec<FieldDefinition> {
+        vec![]
+    }
     /// Types supported by this subsystem. This includes types explicitly defined by user types as
     /// well as types derived from user types (such as for predicates)
     fn schema_types(&self) -> Vec<TypeDefinition>;
 }
 
+/// The responses to a subscription (one for each event)
+pub type SubscriptionStream<'a> = BoxStream<'a, Result<QueryResponse, SubsystemResolutionError>>;
+
 #[derive(Error, Debug)]
 pub enum SubsystemResolutionError {
     #[error("Invalid field {0} for {1}")]
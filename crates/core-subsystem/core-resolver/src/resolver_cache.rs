@@ -0,0 +1,347 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Server-side caching of query results (for queries whose `@cache` hints specify a `ttl`).
+//!
+//! A result is cached under a key computed from the query's normalized fields (whose arguments
+//! hold the values of any variables) and the values of the context fields referenced by the access
+//! rules that apply to the query. Requests that the access rules can't tell apart share a cached
+//! result, while the others get their own. An entry expires after its ttl or when a mutation
+//! writes to any of the tables the query read.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+#[cfg(not(target_family = "wasm"))]
+use std::{sync::Mutex, time::Instant};
+
+use async_trait::async_trait;
+#[cfg(not(target_family = "wasm"))]
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use common::value::Val;
+
+use crate::{QueryResponse, QueryResponseBody, validation::field::ValidatedField};
+
+/// The number of results held by [InMemoryResolverCache] unless configured otherwise
+pub const DEFAULT_RESOLVER_CACHE_SIZE: usize = 1000;
+
+/// What the result of a query depends on besides the query itself (see
+/// [SubsystemGraphQLResolver::query_dependencies](crate::plugin::SubsystemGraphQLResolver::query_dependencies))
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct QueryDependencies {
+    /// The values of the context fields referenced by the access rules that apply to the query
+    /// (keyed by names such as `AuthContext.id`, with `Val::Null` for an absent value)
+    pub context: BTreeMap<String, Val>,
+    /// The tables that the query reads
+    pub tables: BTreeSet<String>,
+}
+
+impl QueryDependencies {
+    /// Combine with the dependencies of another field of the same query
+    pub fn merge(&mut self, other: QueryDependencies) {
+        self.context.extend(other.context);
+        self.tables.extend(other.tables);
+    }
+}
+
+/// The result of a query as held in a [ResolverCache]: the JSON of each root field (along with its
+/// output name)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub fields: Vec<(String, String)>,
+}
+
+impl CachedResponse {
+    /// The cacheable form of the responses to a query's fields (`None` if a response sets headers
+    /// such as cookies, which are specific to the request)
+    pub fn from_responses(responses: &[(String, QueryResponse)]) -> Option<Self> {
+        let fields = responses
+            .iter()
+            .map(|(output_name, response)| {
+                if !response.headers.is_empty() {
+                    return None;
+                }
+
+                let json = match &response.body {
+                    QueryResponseBody::Json(value) => value.to_string(),
+                    QueryResponseBody::Raw(Some(raw)) => raw.clone(),
+                    QueryResponseBody::Raw(None) => "null".to_string(),
+                };
+                Some((output_name.clone(), json))
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Self { fields })
+    }
+
+    pub fn into_responses(self) -> Vec<(String, QueryResponse)> {
+        self.fields
+            .into_iter()
+            .map(|(output_name, json)| {
+                (
+                    output_name,
+                    QueryResponse {
+                        body: QueryResponseBody::Raw(Some(json)),
+                        headers: vec![],
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+pub trait ResolverCache: Send + Sync {
+    /// Get the (unexpired) result cached with the given key
+    async fn get(&self, key: &str) -> Result<Option<CachedResponse>, ResolverCacheError>;
+
+    /// Cache the result of a query for `ttl`. A write to any of `tables` (the tables read by the
+    /// query) invalidates the result (see [ResolverCache::invalidate]).
+    async fn put(
+        &self,
+        key: &str,
+        response: CachedResponse,
+        ttl: Duration,
+        tables: &BTreeSet<String>,
+    ) -> Result<(), ResolverCacheError>;
+
+    /// Drop the results of the queries that read any of the given tables (since a mutation wrote
+    /// to them)
+    async fn invalidate(&self, tables: &BTreeSet<String>) -> Result<(), ResolverCacheError>;
+}
+
+/// The key for the result of a query: the (hex-encoded) SHA-256 of the query's fields along with
+/// the context values it depends on
+pub fn cache_key(
+    fields: &[ValidatedField],
+    dependencies: &QueryDependencies,
+) -> Result<String, ResolverCacheError> {
+    let mut key = serde_json::to_value((fields, &dependencies.context))
+        .map_err(|e| ResolverCacheError(Box::new(e)))?;
+    // Objects (such as the arguments and `Val::Object`s) don't have a stable order of keys
+    key.sort_all_objects();
+
+    Ok(base16ct::lower::encode_string(&Sha256::digest(
+        key.to_string().as_bytes(),
+    )))
+}
+
+/// A cache that keeps the most recently used results in memory (evicting the least recently used
+/// result when full)
+#[cfg(not(target_family = "wasm"))]
+pub struct InMemoryResolverCache {
+    capacity: usize,
+    entries: Mutex<IndexMap<String, CacheEntry>>,
+}
+
+#[cfg(not(target_family = "wasm"))]
+struct CacheEntry {
+    response: CachedResponse,
+    expires_at: Instant,
+    tables: BTreeSet<String>,
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl InMemoryResolverCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(IndexMap::new()),
+        }
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl Default for InMemoryResolverCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_RESOLVER_CACHE_SIZE)
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+#[async_trait]
+impl ResolverCache for InMemoryResolverCache {
+    async fn get(&self, key: &str) -> Result<Option<CachedResponse>, ResolverCacheError> {
+        let mut entries = self.entries.lock().unwrap();
+
+        // Move the entry to the end to mark it as the most recently used (and drop it if expired)
+        Ok(entries
+            .shift_remove(key)
+            .filter(|entry| Instant::now() < entry.expires_at)
+            .map(|entry| {
+                let response = entry.response.clone();
+                entries.insert(key.to_string(), entry);
+                response
+            }))
+    }
+
+    async fn put(
+        &self,
+        key: &str,
+        response: CachedResponse,
+        ttl: Duration,
+        tables: &BTreeSet<String>,
+    ) -> Result<(), ResolverCacheError> {
+        if self.capacity == 0 {
+            return Ok(());
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+
+        entries.shift_remove(key);
+        while entries.len() >= self.capacity {
+            entries.shift_remove_index(0);
+        }
+        entries.insert(
+            key.to_string(),
+            CacheEntry {
+                response,
+                expires_at: Instant::now() + ttl,
+                tables: tables.clone(),
+            },
+        );
+
+        Ok(())
+    }
+
+    async fn invalidate(&self, tables: &BTreeSet<String>) -> Result<(), ResolverCacheError> {
+        self.entries
+            .lock()
+            .unwrap()
+            .retain(|_, entry| entry.tables.is_disjoint(tables));
+
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("{0}")]
+pub struct ResolverCacheError(#[source] pub Box<dyn std::error::Error + Send + Sync>);
+
+#[cfg(all(test, not(target_family = "wasm")))]
+mod tests {
+    use async_graphql_value::{Name, indexmap::IndexMap};
+
+    use super::*;
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    fn response(value: &str) -> CachedResponse {
+        CachedResponse {
+            fields: vec![("concerts".to_string(), value.to_string())],
+        }
+    }
+
+    fn tables(names: &[&str]) -> BTreeSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn in_memory_cache_evicts_least_recently_used() {
+        let cache = InMemoryResolverCache::new(2);
+
+        cache
+            .put("key1", response("1"), TTL, &tables(&[]))
+            .await
+            .unwrap();
+        cache
+            .put("key2", response("2"), TTL, &tables(&[]))
+            .await
+            .unwrap();
+
+        // Using the first result makes the second one the least recently used
+        assert_eq!(cache.get("key1").await.unwrap(), Some(response("1")));
+        cache
+            .put("key3", response("3"), TTL, &tables(&[]))
+            .await
+            .unwrap();
+
+        assert_eq!(cache.get("key1").await.unwrap(), Some(response("1")));
+        assert_eq!(cache.get("key2").await.unwrap(), None);
+        assert_eq!(cache.get("key3").await.unwrap(), Some(response("3")));
+    }
+
+    #[tokio::test]
+    async fn in_memory_cache_expires_entries() {
+        let cache = InMemoryResolverCache::default();
+
+        cache
+            .put("key1", response("1"), Duration::ZERO, &tables(&[]))
+            .await
+            .unwrap();
+
+        assert_eq!(cache.get("key1").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn in_memory_cache_invalidates_by_table() {
+        let cache = InMemoryResolverCache::default();
+
+        cache
+            .put("key1", response("1"), TTL, &tables(&["concerts", "venues"]))
+            .await
+            .unwrap();
+        cache
+            .put("key2", response("2"), TTL, &tables(&["artists"]))
+            .await
+            .unwrap();
+
+        cache.invalidate(&tables(&["venues"])).await.unwrap();
+
+        assert_eq!(cache.get("key1").await.unwrap(), None);
+        assert_eq!(cache.get("key2").await.unwrap(), Some(response("2")));
+    }
+
+    #[tokio::test]
+    async fn in_memory_cache_without_capacity() {
+        let cache = InMemoryResolverCache::new(0);
+
+        cache
+            .put("key1", response("1"), TTL, &tables(&[]))
+            .await
+            .unwrap();
+
+        assert_eq!(cache.get("key1").await.unwrap(), None);
+    }
+
+    #[test]
+    fn cache_key_depends_on_context_values() {
+        let field = ValidatedField {
+            alias: None,
+            name: Name::new("concerts"),
+            arguments: IndexMap::new(),
+            subfields: vec![],
+        };
+
+        let dependencies = |id: i64, role: &str| QueryDependencies {
+            context: BTreeMap::from([(
+                "AuthContext".to_string(),
+                Val::Object(
+                    [
+                        ("id".to_string(), Val::Number(id.into())),
+                        ("role".to_string(), Val::String(role.to_string())),
+                    ]
+                    .into_iter()
+                    .collect(),
+                ),
+            )]),
+            tables: tables(&["concerts"]),
+        };
+
+        let key =
+            |id, role| cache_key(std::slice::from_ref(&field), &dependencies(id, role)).unwrap();
+
+        assert_eq!(key(1, "admin"), key(1, "admin"));
+        assert_ne!(key(1, "admin"), key(2, "admin"));
+        assert_ne!(key(1, "admin"), key(1, "user"));
+    }
+}
@@ -7,8 +7,11 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::borrow::Cow;
+use std::collections::BTreeSet;
 use std::pin::pin;
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_graphql_parser::{
     Pos,
@@ -22,7 +25,7 @@ use core_plugin_shared::{
         TrustedDocumentEnforcement, TrustedDocumentResolutionError, TrustedDocuments,
     },
 };
-use futures::{StreamExt, future::BoxFuture};
+use futures::{Stream, StreamExt, future::BoxFuture, stream::BoxStream};
 use serde_json::{Map, Value};
 use thiserror::Error;
 use tokio::runtime::Handle;
@@ -31,15 +34,24 @@ use tracing::{error, instrument, warn};
 use exo_env::Environment;
 
 use common::context::RequestContext;
-use common::operation_payload::OperationsPayload;
+use common::operation_payload::{OperationsPayload, Upload};
+use core_model::types::{CacheHint, CacheScope};
 
 use crate::{
-    FieldResolver, InterceptedOperation, QueryResponse,
+    FieldResolver, InterceptedOperation, QueryResponse, QueryResponseBody,
     introspection::definition::schema::Schema,
-    plugin::{SubsystemResolutionError, subsystem_graphql_resolver::SubsystemGraphQLResolver},
+    operation_resolver::select_subfields,
+    persisted_queries::{InMemoryPersistedQueryStore, PersistedQueryStore},
+    plugin::{
+        SubscriptionStream, SubsystemResolutionError,
+        subsystem_graphql_resolver::SubsystemGraphQLResolver,
+    },
+    resolver_cache::{CachedResponse, QueryDependencies, ResolverCache, cache_key},
     validation::{
-        document_validator::DocumentValidator, field::ValidatedField,
-        operation::ValidatedOperation, validation_error::ValidationError,
+        document_validator::{DocumentValidator, QueryCostLimit},
+        field::ValidatedField,
+        operation::ValidatedOperation,
+        validation_error::ValidationError,
     },
 };
 
@@ -53,6 +65,39 @@ pub type ExographExecuteQueryFn<'a> = dyn Fn(
     + Send
     + Sync;
 
+/// The results of an operation as they become available (see
+/// [GraphQLSystemResolver::resolve_subscription])
+pub type OperationsStream<'a> = BoxStream<'a, StreamedResponse>;
+
+/// One of the responses to an operation resolved as a stream
+#[derive(Debug)]
+pub struct StreamedResponse {
+    pub kind: StreamedResponseKind,
+    pub result: Result<Vec<(String, QueryResponse)>, SystemResolutionError>,
+    /// For an operation resolved incrementally (see [ValidatedOperation::is_incremental]),
+    /// whether more responses follow. `None` otherwise.
+    pub has_next: Option<bool>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum StreamedResponseKind {
+    /// The result of an operation or of a subscription event. For an operation resolved
+    /// incrementally, the initial result (without the deferred fields).
+    Data,
+    /// The fields of a `@defer` fragment
+    Deferred { label: Option<String> },
+}
+
+impl StreamedResponse {
+    fn data(result: Result<Vec<(String, QueryResponse)>, SystemResolutionError>) -> Self {
+        Self {
+            kind: StreamedResponseKind::Data,
+            result,
+            has_next: None,
+        }
+    }
+}
+
 /// The top-level system resolver.
 ///
 /// Delegates to subsystem resolvers to resolve individual operations.
@@ -61,9 +106,16 @@ pub struct GraphQLSystemResolver {
     query_interception_map: Arc<InterceptionMap>,
     mutation_interception_map: Arc<InterceptionMap>,
     trusted_documents: TrustedDocuments,
+    /// The queries registered through the automatic persisted queries protocol
+    persisted_query_store: Arc<dyn PersistedQueryStore>,
     pub schema: Arc<Schema>,
     normal_query_depth_limit: usize,
     introspection_query_depth_limit: usize,
+    /// The maximum cost of an operation (no limit if `None`)
+    query_cost_limit: Option<QueryCostLimit>,
+    /// The cache for the results of queries with a `ttl` in their `@cache` hints (no caching if
+    /// `None`)
+    resolver_cache: Option<Arc<dyn ResolverCache>>,
 }
 
 impl GraphQLSystemResolver {
@@ -95,9 +147,36 @@ impl GraphQLSystemResolver {
             query_interception_map,
             mutation_interception_map,
             trusted_documents,
+            persisted_query_store: Arc::new(InMemoryPersistedQueryStore::default()),
             schema,
             normal_query_depth_limit,
             introspection_query_depth_limit,
+            query_cost_limit: None,
+            resolver_cache: None,
+        }
+    }
+
+    pub fn with_persisted_query_store(
+        self,
+        persisted_query_store: Arc<dyn PersistedQueryStore>,
+    ) -> Self {
+        Self {
+            persisted_query_store,
+            ..self
+        }
+    }
+
+    pub fn with_query_cost_limit(self, query_cost_limit: Option<QueryCostLimit>) -> Self {
+        Self {
+            query_cost_limit,
+            ..self
+        }
+    }
+
+    pub fn with_resolver_cache(self, resolver_cache: Option<Arc<dyn ResolverCache>>) -> Self {
+        Self {
+            resolver_cache,
+            ..self
         }
     }
 
@@ -133,18 +212,433 @@ impl GraphQLSystemResolver {
         request_context: &RequestContext<'a>,
         trusted_document_enforcement: TrustedDocumentEnforcement,
     ) -> Result<Vec<(String, QueryResponse)>, SystemResolutionError> {
-        let query = self.trusted_documents.resolve(
-            operations_payload.query.as_deref(),
-            operations_payload.query_hash.as_deref(),
-            trusted_document_enforcement,
-        );
+        let operation = self
+            .validate_operations_payload(operations_payload, trusted_document_enforcement)
+            .await?;
+
+        self.resolve_validated_operation(&operation, request_context)
+            .await
+    }
+
+    /// Resolve a query sent with HTTP GET. Since a GET request must not have side effects, any
+    /// other kind of operation is rejected. Along with the result, returns how long (and by
+    /// whom) the result may be cached (see [ValidatedOperation::cache_hint]). A public hint is
+    /// made private if the result may depend on who is asking (see
+    /// [GraphQLSystemResolver::depends_on_context]).
+    #[instrument(
+        name = "SystemResolver::resolve_query_operations"
+        skip_all
+        )]
+    pub async fn resolve_query_operations<'a>(
+        &self,
+        operations_payload: OperationsPayload,
+        request_context: &RequestContext<'a>,
+        trusted_document_enforcement: TrustedDocumentEnforcement,
+    ) -> Result<(Vec<(String, QueryResponse)>, Option<CacheHint>), SystemResolutionError> {
+        let operation = self
+            .validate_operations_payload(operations_payload, trusted_document_enforcement)
+            .await?;
+
+        if operation.typ != OperationType::Query {
+            return Err(SystemResolutionError::NonQueryOperation);
+        }
 
-        let operation = match query {
-            Ok(query) => self.validate_operation(
-                query,
+        let cache_hint = match operation.cache_hint(&self.schema) {
+            Some(cache_hint)
+                if cache_hint.scope == CacheScope::Public
+                    && self.depends_on_context(&operation, request_context).await =>
+            {
+                Some(CacheHint {
+                    scope: CacheScope::Private,
+                    ..cache_hint
+                })
+            }
+            cache_hint => cache_hint,
+        };
+
+        self.resolve_validated_operation(&operation, request_context)
+            .await
+            .map(|response| (response, cache_hint))
+    }
+
+    /// Resolve the provided top-level operation into a stream of results.
+    ///
+    /// A subscription yields a result for each event (until the subscribed source ends). An error
+    /// in resolving an event is yielded in place of that event's result without ending the
+    /// stream. A query with `@defer` directives yields its initial result followed by a result for
+    /// each deferred fragment. Any other operation yields a single result.
+    ///
+    /// Since a subscription may stay open for a long time (and the parts of an incrementally
+    /// resolved query are delivered as soon as they are ready), the transaction (and with it, any
+    /// database connection) is finalized after each result.
+    #[instrument(
+        name = "SystemResolver::resolve_subscription"
+        skip_all
+        )]
+    pub fn resolve_subscription<'a>(
+        &'a self,
+        operations_payload: OperationsPayload,
+        request_context: &'a RequestContext<'a>,
+        trusted_document_enforcement: TrustedDocumentEnforcement,
+    ) -> OperationsStream<'a> {
+        Box::pin(async_stream::stream! {
+            let operation = match self
+                .validate_operations_payload(operations_payload, trusted_document_enforcement)
+                .await
+            {
+                Ok(operation) => operation,
+                Err(e) => {
+                    yield StreamedResponse::data(Err(e));
+                    return;
+                }
+            };
+
+            if operation.typ != OperationType::Subscription {
+                if operation.is_incremental() {
+                    let mut responses = pin!(self.resolve_incrementally(&operation, request_context));
+                    while let Some(response) = responses.next().await {
+                        yield response;
+                    }
+                } else {
+                    yield StreamedResponse::data(
+                        self.resolve_validated_operation(&operation, request_context).await,
+                    );
+                }
+                return;
+            }
+
+            // The validator ensures that a subscription has exactly one root field
+            let field = &operation.fields[0];
+
+            let mut events = match self.subscribe(field, request_context).await {
+                Ok(events) => events,
+                Err(e) => {
+                    yield StreamedResponse::data(Err(e));
+                    return;
+                }
+            };
+
+            while let Some(event) = events.next().await {
+                let response = match event {
+                    Ok(response) => select_subfields(response, field, request_context).await,
+                    Err(e) => Err(e.into()),
+                };
+
+                if let Err(e) = request_context.reset_transaction(response.is_ok()).await {
+                    yield StreamedResponse::data(Err(SystemResolutionError::Generic(format!(
+                        "Error while finalizing transaction: {e}"
+                    ))));
+                    return;
+                }
+
+                yield StreamedResponse::data(
+                    response.map(|response| vec![(field.output_name(), response)]),
+                );
+            }
+        })
+    }
+
+    /// Resolve a query with `@defer` directives: first the fields outside any deferred fragment,
+    /// and then the fields of each deferred fragment.
+    fn resolve_incrementally<'b>(
+        &'b self,
+        operation: &'b ValidatedOperation,
+        request_context: &'b RequestContext<'b>,
+    ) -> impl Stream<Item = StreamedResponse> + Send + 'b {
+        async_stream::stream! {
+            let initial_fields: Vec<&ValidatedField> = operation
+                .fields
+                .iter()
+                .filter(|field| {
+                    let output_name = field.output_name();
+                    !operation
+                        .deferred
+                        .iter()
+                        .any(|deferred| deferred.fields.contains(&output_name))
+                })
+                .collect();
+
+            let initial = match self
+                .resolve_fields_of(operation, &initial_fields, request_context)
+                .await
+            {
+                Ok(initial) => initial,
+                Err(e) => {
+                    yield StreamedResponse {
+                        kind: StreamedResponseKind::Data,
+                        result: Err(e),
+                        has_next: Some(false),
+                    };
+                    return;
+                }
+            };
+
+            let mut remaining = operation.deferred.len();
+
+            yield StreamedResponse {
+                kind: StreamedResponseKind::Data,
+                result: Ok(initial),
+                has_next: Some(remaining > 0),
+            };
+
+            for deferred in &operation.deferred {
+                remaining -= 1;
+
+                let fields: Vec<&ValidatedField> = operation
+                    .fields
+                    .iter()
+                    .filter(|field| deferred.fields.contains(&field.output_name()))
+                    .collect();
+
+                yield StreamedResponse {
+                    kind: StreamedResponseKind::Deferred {
+                        label: deferred.label.clone(),
+                    },
+                    result: self
+                        .resolve_fields_of(operation, &fields, request_context)
+                        .await,
+                    has_next: Some(remaining > 0),
+                };
+            }
+        }
+    }
+
+    /// Resolve some of the operation's fields (finalizing the transaction afterwards)
+    async fn resolve_fields_of(
+        &self,
+        operation: &ValidatedOperation,
+        fields: &[&ValidatedField],
+        request_context: &RequestContext<'_>,
+    ) -> Result<Vec<(String, QueryResponse)>, SystemResolutionError> {
+        if fields.len() > 1 {
+            request_context.ensure_transaction().await;
+        }
+
+        let responses = async {
+            let mut responses = vec![];
+            for field in fields {
+                let response = operation
+                    .resolve_field(field, self, request_context)
+                    .await?;
+                responses.push((field.output_name(), response));
+            }
+            Ok::<_, SystemResolutionError>(responses)
+        }
+        .await;
+
+        request_context
+            .reset_transaction(responses.is_ok())
+            .await
+            .map_err(|e| {
+                SystemResolutionError::Generic(format!("Error while finalizing transaction: {e}"))
+            })
+            .and(responses)
+    }
+
+    /// Resolve the operation, going through the resolver cache (if any): a cacheable query may be
+    /// answered from the cache and a mutation invalidates the cached results of the queries that
+    /// read the tables it writes to (once its changes commit).
+    ///
+    /// A query that may see changes other requests don't see yet (after a mutation in the same
+    /// request or in a transaction shared with other operations, such as in a batch with
+    /// `_exo_batch_transaction`) bypasses the cache.
+    async fn resolve_validated_operation(
+        &self,
+        operation: &ValidatedOperation,
+        request_context: &RequestContext<'_>,
+    ) -> Result<Vec<(String, QueryResponse)>, SystemResolutionError> {
+        let Some(resolver_cache) = &self.resolver_cache else {
+            return self.resolve_uncached(operation, request_context).await;
+        };
+
+        match operation.typ {
+            OperationType::Query => match self.cacheable_query(operation, request_context).await {
+                Some((key, ttl, tables)) => {
+                    self.resolve_cached(
+                        resolver_cache.as_ref(),
+                        operation,
+                        (&key, ttl, &tables),
+                        request_context,
+                    )
+                    .await
+                }
+                None => self.resolve_uncached(operation, request_context).await,
+            },
+            OperationType::Mutation => {
+                let responses = self.resolve_uncached(operation, request_context).await?;
+
+                let tables: BTreeSet<String> = operation
+                    .fields
+                    .iter()
+                    .flat_map(|field| {
+                        self.subsystem_resolvers
+                            .iter()
+                            .find_map(|resolver| resolver.mutation_tables(field))
+                            .unwrap_or_default()
+                    })
+                    .collect();
+
+                // Invalidating before the changes commit would let a concurrent query cache the
+                // results from before them. Failing to invalidate shouldn't fail the mutation
+                // (the cached results will still expire after their ttl).
+                if !tables.is_empty() {
+                    let resolver_cache = resolver_cache.clone();
+                    request_context.after_commit(Box::pin(async move {
+                        if let Err(e) = resolver_cache.invalidate(&tables).await {
+                            warn!("Error while invalidating cached results: {e}");
+                        }
+                    }));
+                }
+
+                Ok(responses)
+            }
+            OperationType::Subscription => self.resolve_uncached(operation, request_context).await,
+        }
+    }
+
+    async fn resolve_uncached(
+        &self,
+        operation: &ValidatedOperation,
+        request_context: &RequestContext<'_>,
+    ) -> Result<Vec<(String, QueryResponse)>, SystemResolutionError> {
+        // If multiple operations are present, we need to ensure that we have a transaction
+        if operation.fields.len() > 1 {
+            request_context.ensure_transaction().await;
+        }
+        operation
+            .resolve_fields(&operation.fields, self, request_context)
+            .await
+    }
+
+    /// Resolve a cacheable query from the cache (or resolve it and cache the result)
+    async fn resolve_cached(
+        &self,
+        resolver_cache: &dyn ResolverCache,
+        operation: &ValidatedOperation,
+        (key, ttl, tables): (&str, Duration, &BTreeSet<String>),
+        request_context: &RequestContext<'_>,
+    ) -> Result<Vec<(String, QueryResponse)>, SystemResolutionError> {
+        match resolver_cache.get(key).await {
+            Ok(Some(cached)) => return Ok(cached.into_responses()),
+            Ok(None) => {}
+            Err(e) => warn!("Error while looking up a cached result: {e}"),
+        }
+
+        let responses = self.resolve_uncached(operation, request_context).await?;
+
+        if let Some(cached) = CachedResponse::from_responses(&responses)
+            && let Err(e) = resolver_cache.put(key, cached, ttl, tables).await
+        {
+            warn!("Error while caching a result: {e}");
+        }
+
+        Ok(responses)
+    }
+
+    /// The cache key, the ttl, and the tables read for a query whose result may be cached: one
+    /// whose `@cache` hints specify a ttl, without interceptors, whose fields are all handled by
+    /// subsystems that can tell what the fields depend on, and that can't see uncommitted changes
+    async fn cacheable_query(
+        &self,
+        operation: &ValidatedOperation,
+        request_context: &RequestContext<'_>,
+    ) -> Option<(String, Duration, BTreeSet<String>)> {
+        if request_context.may_see_uncommitted_changes().await {
+            return None;
+        }
+
+        let ttl = operation
+            .cache_hint(&self.schema)?
+            .ttl
+            .filter(|ttl| *ttl > 0)?;
+
+        let mut dependencies = QueryDependencies::default();
+
+        for field in operation
+            .fields
+            .iter()
+            .filter(|field| field.name != "__typename")
+        {
+            // An interceptor may have side effects (or return a different result each time)
+            if self
+                .applicable_interception_tree(&field.name, operation.typ)
+                .is_some()
+            {
+                return None;
+            }
+
+            dependencies.merge(self.query_dependencies(field, request_context).await?);
+        }
+
+        match cache_key(&operation.fields, &dependencies) {
+            Ok(key) => Some((key, Duration::from_secs(ttl.into()), dependencies.tables)),
+            Err(e) => {
+                warn!("Error while computing the cache key: {e}");
+                None
+            }
+        }
+    }
+
+    /// Whether the result of the query may depend on the context (and so differ between users):
+    /// the access rules that apply to some field reference context fields, or the subsystem
+    /// resolving the field can't tell
+    async fn depends_on_context(
+        &self,
+        operation: &ValidatedOperation,
+        request_context: &RequestContext<'_>,
+    ) -> bool {
+        for field in operation
+            .fields
+            .iter()
+            .filter(|field| field.name != "__typename")
+        {
+            match self.query_dependencies(field, request_context).await {
+                Some(dependencies) if dependencies.context.is_empty() => {}
+                _ => return true,
+            }
+        }
+
+        false
+    }
+
+    async fn query_dependencies(
+        &self,
+        field: &ValidatedField,
+        request_context: &RequestContext<'_>,
+    ) -> Option<QueryDependencies> {
+        for resolver in self.subsystem_resolvers.iter() {
+            match resolver.query_dependencies(field, request_context).await {
+                Ok(Some(dependencies)) => return Some(dependencies),
+                Ok(None) => {}
+                // Resolving the field will report the error (such as a missing context value)
+                Err(_) => return None,
+            }
+        }
+
+        None
+    }
+
+    /// Resolve the query in the payload to a validated operation (enforcing trusted documents)
+    async fn validate_operations_payload(
+        &self,
+        operations_payload: OperationsPayload,
+        trusted_document_enforcement: TrustedDocumentEnforcement,
+    ) -> Result<ValidatedOperation, SystemResolutionError> {
+        let query = self
+            .resolve_document(
+                operations_payload.query.as_deref(),
+                operations_payload.query_hash.as_deref(),
+                trusted_document_enforcement,
+            )
+            .await;
+
+        match query {
+            Ok(query) => Ok(self.validate_operation(
+                &query,
                 operations_payload.operation_name,
                 operations_payload.variables,
-            )?,
+                operations_payload.uploads,
+            )?),
             // Special handing on introspection queries made by tools to be implicitly trusted
             // Introspection queries made by the playground and tools such as graphql-codegen send queries as a string
             // and have top-level field `__schema` (but we also allow `__type` and `__typename` to be more widely useful).
@@ -156,6 +650,7 @@ impl GraphQLSystemResolver {
                     &query,
                     operations_payload.operation_name,
                     operations_payload.variables,
+                    operations_payload.uploads,
                 )?;
 
                 for field in &operation.fields {
@@ -172,20 +667,50 @@ impl GraphQLSystemResolver {
                     .into());
                 }
 
-                operation
-            }
-            Err(e) => {
-                return Err(e.into());
+                Ok(operation)
             }
-        };
+            Err(e) => Err(e.into()),
+        }
+    }
 
-        // If multiple operations are present, we need to ensure that we have a transaction
-        if operation.fields.len() > 1 {
-            request_context.ensure_transaction().await;
+    /// Resolve the document for the query or query hash in the payload. A hash unknown at build
+    /// time may refer to a query registered through the automatic persisted queries protocol (if
+    /// untrusted documents are allowed), and a payload with both the query and its hash
+    /// registers the query.
+    async fn resolve_document<'a>(
+        &'a self,
+        query: Option<&'a str>,
+        query_hash: Option<&str>,
+        trusted_document_enforcement: TrustedDocumentEnforcement,
+    ) -> Result<Cow<'a, str>, TrustedDocumentResolutionError> {
+        let query_sent = query.is_some();
+        let query = self
+            .trusted_documents
+            .resolve(query, query_hash, trusted_document_enforcement);
+
+        match (query, query_hash) {
+            (Err(TrustedDocumentResolutionError::PersistedQueryNotFound), Some(query_hash)) => {
+                match self.persisted_query_store.get(query_hash).await {
+                    Ok(Some(query)) => Ok(Cow::Owned(query)),
+                    Ok(None) => Err(TrustedDocumentResolutionError::PersistedQueryNotFound),
+                    Err(e) => {
+                        warn!("Error while looking up a persisted query: {e}");
+                        Err(TrustedDocumentResolutionError::PersistedQueryNotFound)
+                    }
+                }
+            }
+            (Ok(query), Some(query_hash))
+                if query_sent && !self.trusted_documents.contains(query_hash) =>
+            {
+                // Failing to register the query shouldn't fail the request (the client will
+                // send the query again after getting `PersistedQueryNotFound`)
+                if let Err(e) = self.persisted_query_store.put(query_hash, query).await {
+                    warn!("Error while registering a persisted query: {e}");
+                }
+                Ok(Cow::Borrowed(query))
+            }
+            (query, _) => query.map(Cow::Borrowed),
         }
-        operation
-            .resolve_fields(&operation.fields, self, request_context)
-            .await
     }
 
     /// Obtain the interception tree associated with the given operation
@@ -234,6 +759,23 @@ impl GraphQLSystemResolver {
         Err(SystemResolutionError::NoResolverFound)
     }
 
+    async fn subscribe<'a>(
+        &'a self,
+        operation: &'a ValidatedField,
+        request_context: &'a RequestContext<'a>,
+    ) -> Result<SubscriptionStream<'a>, SystemResolutionError> {
+        for resolver in self.subsystem_resolvers.iter() {
+            if let Some(events) = resolver
+                .resolve_subscription(operation, request_context, self)
+                .await?
+            {
+                return Ok(events);
+            }
+        }
+
+        Err(SystemResolutionError::NoResolverFound)
+    }
+
     pub(super) async fn invoke_interceptor(
         &self,
         interceptor: &InterceptorIndexWithSubsystemIndex,
@@ -269,6 +811,7 @@ impl GraphQLSystemResolver {
         query: &str,
         operation_name: Option<String>,
         variables: Option<Map<String, Value>>,
+        uploads: Vec<Upload>,
     ) -> Result<ValidatedOperation, ValidationError> {
         let document = parse_query(query)?;
 
@@ -278,7 +821,9 @@ impl GraphQLSystemResolver {
             variables,
             self.normal_query_depth_limit,
             self.introspection_query_depth_limit,
-        );
+        )
+        .with_cost_limit(self.query_cost_limit)
+        .with_uploads(uploads);
 
         document_validator.validate(document)
     }
@@ -305,6 +850,7 @@ macro_rules! exograph_execute_query {
                             query: Some(query_string),
                             variables,
                             query_hash: None,
+                            uploads: vec![],
                         },
                         &new_request_context,
                         enforce_trusted_documents,
@@ -392,6 +938,10 @@ fn parse_query(query: &str) -> Result<ExecutableDocument, ValidationError> {
 pub enum RequestError {
     #[error("Invalid body JSON {0}")]
     InvalidBodyJson(serde_json::Error),
+    #[error("Invalid query parameters {0}")]
+    InvalidQueryParameters(serde_json::Error),
+    #[error("Invalid multipart body: {0}")]
+    InvalidMultipartBody(String),
 }
 
 #[derive(Error, Debug)]
@@ -422,6 +972,15 @@ pub enum SystemResolutionError {
 
     #[error("Invalid request {0}")]
     RequestError(#[from] RequestError),
+
+    #[error("Only queries may be sent with an HTTP GET request")]
+    NonQueryOperation,
+
+    #[error("Rolled back since another operation in the batch failed")]
+    BatchRolledBack,
+
+    #[error("Skipped since an earlier operation in the batch failed")]
+    BatchSkipped,
 }
 
 impl SystemResolutionError {
@@ -437,6 +996,11 @@ impl SystemResolutionError {
         match self {
             SystemResolutionError::Validation(error) => Some(error.to_string()),
             SystemResolutionError::SubsystemResolutionError(error) => error.user_error_message(),
+            // The automatic persisted queries protocol expects these messages as is
+            SystemResolutionError::TrustedDocumentResolution(
+                e @ (TrustedDocumentResolutionError::PersistedQueryNotFound
+                | TrustedDocumentResolutionError::HashMismatch),
+            ) => Some(e.to_string()),
             SystemResolutionError::TrustedDocumentResolution(e) => {
                 warn!("Error executing: {e}");
                 Some("Operation not allowed".to_string())
@@ -444,7 +1008,398 @@ impl SystemResolutionError {
             SystemResolutionError::Delegate(error) => error
                 .downcast_ref::<SystemResolutionError>()
                 .map(|error| error.user_error_message()),
+            SystemResolutionError::NonQueryOperation
+            | SystemResolutionError::BatchRolledBack
+            | SystemResolutionError::BatchSkipped => Some(self.to_string()),
             _ => None,
         }
     }
+
+    /// The `extensions` to report with the error (if any): its `code` and any details for the
+    /// client (such as the cost of an operation that exceeds the cost limit)
+    pub fn error_extensions(&self) -> Option<Map<String, Value>> {
+        match self {
+            SystemResolutionError::TrustedDocumentResolution(
+                TrustedDocumentResolutionError::PersistedQueryNotFound,
+            ) => Some(Map::from_iter([(
+                "code".to_string(),
+                Value::from("PERSISTED_QUERY_NOT_FOUND"),
+            )])),
+            SystemResolutionError::Validation(ValidationError::CostTooHigh { cost, max_cost }) => {
+                Some(Map::from_iter([
+                    ("code".to_string(), Value::from("COST_LIMIT_EXCEEDED")),
+                    ("cost".to_string(), Value::from(*cost)),
+                    ("maxCost".to_string(), Value::from(*max_cost)),
+                ]))
+            }
+            SystemResolutionError::Delegate(error) => error
+                .downcast_ref::<SystemResolutionError>()
+                .and_then(|error| error.error_extensions()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use async_graphql_parser::types::{FieldDefinition, TypeDefinition};
+    use common::{
+        http::{RequestHead, RequestPayload, ResponsePayload},
+        router::{PlainRequestPayload, Router},
+    };
+    use core_plugin_shared::interception::InterceptorIndex;
+    use exo_env::MapEnvironment;
+    use serde_json::json;
+
+    use super::*;
+    use crate::validation::document_validator::tests::create_test_schema;
+
+    /// Resolves `concerts` to three concerts, `venue` to a venue, and fails `venues` (recording
+    /// the fields it resolves)
+    struct TestResolver {
+        resolved: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl SubsystemGraphQLResolver for TestResolver {
+        fn id(&self) -> &'static str {
+            "test"
+        }
+
+        async fn resolve<'a>(
+            &'a self,
+            operation: &'a ValidatedField,
+            _operation_type: OperationType,
+            _request_context: &'a RequestContext,
+            _system_resolver: &'a GraphQLSystemResolver,
+        ) -> Result<Option<QueryResponse>, SubsystemResolutionError> {
+            self.resolved.lock().unwrap().push(operation.output_name());
+
+            let body = match operation.name.as_str() {
+                "concerts" => json!([{ "id": 1 }, { "id": 2 }, { "id": 3 }]),
+                "venue" => json!({ "name": "Hall" }),
+                "venues" => {
+                    return Err(SubsystemResolutionError::UserDisplayError(
+                        "Failed".to_string(),
+                    ));
+                }
+                _ => return Ok(None),
+            };
+
+            Ok(Some(QueryResponse {
+                body: QueryResponseBody::Json(body),
+                headers: vec![],
+            }))
+        }
+
+        async fn invoke_interceptor<'a>(
+            &'a self,
+            _interceptor_index: InterceptorIndex,
+            _intercepted_operation: &'a InterceptedOperation,
+            _request_context: &'a RequestContext<'a>,
+            _system_resolver: &'a GraphQLSystemResolver,
+        ) -> Result<Option<QueryResponse>, SubsystemResolutionError> {
+            Err(SubsystemResolutionError::NoInterceptorFound)
+        }
+
+        fn schema_queries(&self) -> Vec<FieldDefinition> {
+            vec![]
+        }
+
+        fn schema_mutations(&self) -> Vec<FieldDefinition> {
+            vec![]
+        }
+
+        fn schema_types(&self) -> Vec<TypeDefinition> {
+            vec![]
+        }
+    }
+
+    struct TestRequest {}
+
+    impl RequestPayload for TestRequest {
+        fn get_head(&self) -> &(dyn RequestHead + Send + Sync) {
+            self
+        }
+
+        fn take_body(&self) -> Value {
+            Value::Null
+        }
+    }
+
+    impl RequestHead for TestRequest {
+        fn get_headers(&self, _key: &str) -> Vec<String> {
+            vec![]
+        }
+
+        fn get_ip(&self) -> Option<std::net::IpAddr> {
+            None
+        }
+
+        fn get_method(&self) -> http::Method {
+            http::Method::POST
+        }
+
+        fn get_path(&self) -> String {
+            "".to_string()
+        }
+
+        fn get_query(&self) -> Value {
+            Value::Null
+        }
+    }
+
+    struct TestRouter {}
+
+    #[async_trait::async_trait]
+    impl<'request> Router<PlainRequestPayload<'request>> for TestRouter {
+        async fn route(&self, _request: &PlainRequestPayload<'request>) -> Option<ResponsePayload> {
+            None
+        }
+    }
+
+    async fn test_system_resolver(resolved: Arc<Mutex<Vec<String>>>) -> GraphQLSystemResolver {
+        GraphQLSystemResolver::new(
+            vec![Arc::new(TestResolver { resolved })],
+            Arc::new(InterceptionMap {
+                map: Default::default(),
+            }),
+            Arc::new(InterceptionMap {
+                map: Default::default(),
+            }),
+            TrustedDocuments::All(Default::default()),
+            Arc::new(create_test_schema().await),
+            Arc::new(MapEnvironment::new()),
+            10,
+            10,
+        )
+    }
+
+    /// Resolve the query as a stream, returning the responses (as the kind, the JSON of the
+    /// result or the error message, and `has_next`) and the fields resolved (in order)
+    async fn resolve_streamed(
+        query: &str,
+    ) -> (
+        Vec<(StreamedResponseKind, Value, Option<bool>)>,
+        Vec<String>,
+    ) {
+        let resolved = Arc::new(Mutex::new(vec![]));
+        let system_resolver = test_system_resolver(resolved.clone()).await;
+
+        let env = MapEnvironment::new();
+        let request = TestRequest {};
+        let router = TestRouter {};
+        let request_context = RequestContext::new(&request, vec![], &router, &None, &env);
+
+        let responses: Vec<StreamedResponse> = system_resolver
+            .resolve_subscription(
+                OperationsPayload {
+                    operation_name: None,
+                    query: Some(query.to_string()),
+                    variables: None,
+                    query_hash: None,
+                    uploads: vec![],
+                },
+                &request_context,
+                TrustedDocumentEnforcement::DoNotEnforce,
+            )
+            .collect()
+            .await;
+
+        let responses = responses
+            .into_iter()
+            .map(|response| {
+                let result = match response.result {
+                    Ok(parts) => Value::Object(
+                        parts
+                            .into_iter()
+                            .map(|(name, response)| (name, response.body.to_json().unwrap()))
+                            .collect(),
+                    ),
+                    Err(e) => Value::String(e.user_error_message()),
+                };
+                (response.kind, result, response.has_next)
+            })
+            .collect();
+
+        let resolved = resolved.lock().unwrap().clone();
+        (responses, resolved)
+    }
+
+    #[tokio::test]
+    async fn incremental_responses_in_order() {
+        let (responses, resolved) = resolve_streamed(
+            r#"
+            query {
+                ... @defer(label: "venue") {
+                    venue(id: 1) {
+                        name
+                    }
+                }
+                concerts {
+                    id
+                }
+                ... @defer {
+                    hall: venue(id: 2) {
+                        name
+                    }
+                }
+            }
+        "#,
+        )
+        .await;
+
+        assert_eq!(
+            responses,
+            vec![
+                (
+                    StreamedResponseKind::Data,
+                    json!({ "concerts": [{ "id": 1 }, { "id": 2 }, { "id": 3 }] }),
+                    Some(true)
+                ),
+                (
+                    StreamedResponseKind::Deferred {
+                        label: Some("venue".to_string())
+                    },
+                    json!({ "venue": { "name": "Hall" } }),
+                    Some(true)
+                ),
+                (
+                    StreamedResponseKind::Deferred { label: None },
+                    json!({ "hall": { "name": "Hall" } }),
+                    Some(false)
+                ),
+            ]
+        );
+
+        // The initial fields are resolved before the deferred ones
+        assert_eq!(resolved, vec!["concerts", "venue", "hall"]);
+    }
+
+    #[tokio::test]
+    async fn incremental_responses_with_failures() {
+        // A failure in the initial fields ends the stream
+        let (responses, resolved) = resolve_streamed(
+            r#"
+            query {
+                venues {
+                    name
+                }
+                ... @defer {
+                    venue(id: 1) {
+                        name
+                    }
+                }
+            }
+        "#,
+        )
+        .await;
+
+        assert_eq!(
+            responses,
+            vec![(StreamedResponseKind::Data, json!("Failed"), Some(false))]
+        );
+        assert_eq!(resolved, vec!["venues"]);
+
+        // A failure in a deferred fragment is reported in its part
+        let (responses, _) = resolve_streamed(
+            r#"
+            query {
+                venue(id: 1) {
+                    name
+                }
+                ... @defer(label: "venues") {
+                    venues {
+                        name
+                    }
+                }
+            }
+        "#,
+        )
+        .await;
+
+        assert_eq!(
+            responses,
+            vec![
+                (
+                    StreamedResponseKind::Data,
+                    json!({ "venue": { "name": "Hall" } }),
+                    Some(true)
+                ),
+                (
+                    StreamedResponseKind::Deferred {
+                        label: Some("venues".to_string())
+                    },
+                    json!("Failed"),
+                    Some(false)
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn persisted_query_registration() {
+        let system_resolver = test_system_resolver(Default::default()).await;
+
+        let query = "query { concerts { id } }";
+        let query_hash = TrustedDocuments::sha256(query);
+
+        // Unknown until registered
+        assert_eq!(
+            system_resolver
+                .resolve_document(
+                    None,
+                    Some(&query_hash),
+                    TrustedDocumentEnforcement::DoNotEnforce
+                )
+                .await,
+            Err(TrustedDocumentResolutionError::PersistedQueryNotFound)
+        );
+
+        // A query with a mismatched hash isn't registered
+        assert_eq!(
+            system_resolver
+                .resolve_document(
+                    Some("query { venues { id } }"),
+                    Some(&query_hash),
+                    TrustedDocumentEnforcement::DoNotEnforce
+                )
+                .await,
+            Err(TrustedDocumentResolutionError::HashMismatch)
+        );
+        assert_eq!(
+            system_resolver
+                .resolve_document(
+                    None,
+                    Some(&query_hash),
+                    TrustedDocumentEnforcement::DoNotEnforce
+                )
+                .await,
+            Err(TrustedDocumentResolutionError::PersistedQueryNotFound)
+        );
+
+        // Sending both registers the query for requests with just the hash
+        assert_eq!(
+            system_resolver
+                .resolve_document(
+                    Some(query),
+                    Some(&query_hash),
+                    TrustedDocumentEnforcement::DoNotEnforce
+                )
+                .await,
+            Ok(Cow::Borrowed(query))
+        );
+        assert_eq!(
+            system_resolver
+                .resolve_document(
+                    None,
+                    Some(&query_hash),
+                    TrustedDocumentEnforcement::DoNotEnforce
+                )
+                .await,
+            Ok(Cow::Owned(query.to_string()))
+        );
+    }
 }
@@ -298,7 +298,7 @@ impl<'a> ArgumentValidator<'a> {
     ) -> Result<Val, ValidationError> {
         self.validate_scalar_argument(
             "Binary",
-            &["Binary"],
+            &["Binary", primitive_type::BlobType::NAME],
             || Ok(Val::Binary(bytes.clone())),
             argument_definition,
             pos,
@@ -10,6 +10,7 @@
 use crate::validation::validation_error::ValidationError;
 use async_graphql_parser::types::{DocumentOperations, ExecutableDocument};
 use async_graphql_value::Name;
+use common::operation_payload::Upload;
 use serde_json::{Map, Value};
 use tracing::instrument;
 
@@ -17,13 +18,26 @@ use crate::introspection::definition::schema::Schema;
 
 use super::{operation::ValidatedOperation, operation_validator::OperationValidator};
 
+/// The number of elements assumed for a list field without a `limit` (or `first`) argument when
+/// computing the cost of an operation
+pub const DEFAULT_COST_LIST_SIZE: usize = 10;
+
+/// The maximum cost of an operation (see [ValidatedOperation::cost])
+#[derive(Debug, Clone, Copy)]
+pub struct QueryCostLimit {
+    pub max_cost: usize,
+    pub default_list_size: usize,
+}
+
 /// Context for validating a document.
 pub struct DocumentValidator<'a> {
     schema: &'a Schema,
     operation_name: Option<String>,
     variables: Option<Map<String, Value>>,
+    uploads: Vec<Upload>,
     normal_query_depth_limit: usize,
     introspection_query_depth_limit: usize,
+    cost_limit: Option<QueryCostLimit>,
 }
 
 impl<'a> DocumentValidator<'a> {
@@ -38,17 +52,28 @@ impl<'a> DocumentValidator<'a> {
             schema,
             operation_name,
             variables,
+            uploads: vec![],
             normal_query_depth_limit,
             introspection_query_depth_limit,
+            cost_limit: None,
         }
     }
 
+    pub fn with_cost_limit(self, cost_limit: Option<QueryCostLimit>) -> Self {
+        Self { cost_limit, ..self }
+    }
+
+    pub fn with_uploads(self, uploads: Vec<Upload>) -> Self {
+        Self { uploads, ..self }
+    }
+
     /// Validate the query payload.
     ///
     /// Validations performed:
     /// - Validate that either there is only one operation or the operation name specified matches one of the operations in the document
     /// - Validate that there is at least one operation
     /// - Other validations are delegated to the operation validator
+    /// - Validate that the cost of the operation is within the cost limit (if any)
     #[instrument(
         name = "DocumentValidator::validate"
         skip(self, document)
@@ -97,22 +122,38 @@ impl<'a> DocumentValidator<'a> {
             self.schema,
             operation_name,
             self.variables,
+            self.uploads,
             document.fragments,
             self.normal_query_depth_limit,
             self.introspection_query_depth_limit,
         );
 
-        operation_validator.validate(raw_operation)
+        let operation = operation_validator.validate(raw_operation)?;
+
+        if let Some(cost_limit) = self.cost_limit {
+            let cost = operation.cost(self.schema, cost_limit.default_list_size);
+            if cost > cost_limit.max_cost {
+                return Err(ValidationError::CostTooHigh {
+                    cost,
+                    max_cost: cost_limit.max_cost,
+                });
+            }
+        }
+
+        Ok(operation)
     }
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use std::sync::Arc;
 
     use super::*;
 
     use async_graphql_parser::parse_query;
+    use bytes::Bytes;
+    use common::value::Val;
+    use core_model::types::{CacheHint, CacheScope};
     use core_model_builder::plugin::BuildMode;
     use exo_env::MapEnvironment;
     use exo_sql_pg_connect::DatabaseClientManager;
@@ -794,11 +835,456 @@ mod tests {
         );
     }
 
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn defer_valid() {
+        let schema = create_test_schema().await;
+
+        let query = r#"
+            query {
+                concert(id: 1) {
+                    id
+                }
+                ... @defer(label: "venues") {
+                    venues {
+                        id
+                        name
+                    }
+                }
+            }
+        "#;
+
+        let validator = DocumentValidator::new(&schema, None, None, 10, 10);
+        let operation = validator.validate(create_query_document(query)).unwrap();
+
+        assert_eq!(operation.fields.len(), 2);
+
+        assert_eq!(operation.deferred.len(), 1);
+        assert_eq!(operation.deferred[0].label.as_deref(), Some("venues"));
+        assert_eq!(operation.deferred[0].fields, vec!["venues"]);
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn defer_disabled_or_overlapping() {
+        let schema = create_test_schema().await;
+
+        let variables = create_variables(
+            r#"
+            {
+                "deferConcerts": false
+            }"#,
+        );
+
+        let query = r#"
+            query($deferConcerts: Boolean!) {
+                venues {
+                    id
+                }
+                ...deferredInfo @defer
+                ... @defer(if: $deferConcerts) {
+                    concerts {
+                        id
+                    }
+                }
+            }
+
+            fragment deferredInfo on Query {
+                venues {
+                    id
+                }
+                concert(id: 1) {
+                    id
+                }
+            }
+        "#;
+
+        let validator = DocumentValidator::new(&schema, None, Some(variables), 10, 10);
+        let operation = validator.validate(create_query_document(query)).unwrap();
+
+        assert_eq!(operation.fields.len(), 3);
+
+        // `venues` is also selected outside the deferred fragment and the `concerts` fragment isn't deferred
+        assert_eq!(operation.deferred.len(), 1);
+        assert_eq!(operation.deferred[0].label, None);
+        assert_eq!(operation.deferred[0].fields, vec!["concert"]);
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn stream_invalid() {
+        let schema = create_test_schema().await;
+
+        let validator = DocumentValidator::new(&schema, None, None, 10, 10);
+
+        for query in [
+            "query { concerts @stream(initialCount: 1) { id } }",
+            "query { ... @defer { concerts @stream { id } } }",
+        ] {
+            assert!(matches!(
+                validator.validate(create_query_document(query)),
+                Err(ValidationError::StreamNotSupported(_))
+            ));
+        }
+
+        // A disabled `@stream` is accepted
+        assert!(
+            validator
+                .validate(create_query_document(
+                    "query { concerts @stream(if: false) { id } }"
+                ))
+                .is_ok()
+        );
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn nested_defer_invalid() {
+        let schema = create_test_schema().await;
+
+        let query = r#"
+            query {
+                concerts {
+                    id
+                    ...ConcertVenue
+                }
+            }
+
+            fragment ConcertVenue on Concert {
+                ... @defer {
+                    venue {
+                        name
+                    }
+                }
+            }
+        "#;
+
+        let validator = DocumentValidator::new(&schema, None, None, 10, 10);
+
+        assert!(matches!(
+            validator.validate(create_query_document(query)),
+            Err(ValidationError::NestedDefer(_))
+        ));
+
+        // A `@defer` nested in a top-level `@defer` fragment is delivered with the outer one
+        let query = r#"
+            query {
+                ... @defer(label: "outer") {
+                    concerts {
+                        id
+                    }
+                    ... @defer(label: "inner") {
+                        venues {
+                            id
+                        }
+                    }
+                }
+            }
+        "#;
+
+        let operation = validator.validate(create_query_document(query)).unwrap();
+
+        assert_eq!(operation.deferred.len(), 1);
+        assert_eq!(operation.deferred[0].fields, vec!["concerts", "venues"]);
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn duplicate_incremental_label_invalid() {
+        let schema = create_test_schema().await;
+
+        let query = r#"
+            query {
+                ... @defer(label: "slow") {
+                    concerts {
+                        id
+                    }
+                }
+                ... @defer(label: "slow") {
+                    venues {
+                        id
+                    }
+                }
+            }
+        "#;
+
+        let validator = DocumentValidator::new(&schema, None, None, 10, 10);
+
+        assert!(matches!(
+            validator.validate(create_query_document(query)),
+            Err(ValidationError::DuplicateIncrementalLabel(label, _)) if label == "slow"
+        ));
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn cache_hint_combines_selected_types() {
+        let schema = create_cache_hint_test_schema().await;
+
+        let cache_hint = |query: &str| {
+            DocumentValidator::new(&schema, None, None, 10, 10)
+                .validate(create_query_document(query))
+                .unwrap()
+                .cache_hint(&schema)
+        };
+
+        assert_eq!(
+            cache_hint("query { concerts { id title } }"),
+            Some(CacheHint {
+                max_age: 60,
+                scope: CacheScope::Public,
+                ttl: Some(120)
+            })
+        );
+
+        // The venue's hint is shorter and private (and without a ttl, the result may not be cached
+        // by the server)
+        assert_eq!(
+            cache_hint("query { concerts { id venue { name } } }"),
+            Some(CacheHint {
+                max_age: 30,
+                scope: CacheScope::Private,
+                ttl: None
+            })
+        );
+
+        // A ttl without a maxAge doesn't let HTTP caches store the result
+        assert_eq!(
+            cache_hint("query { artists { id } }"),
+            Some(CacheHint {
+                max_age: 0,
+                scope: CacheScope::Public,
+                ttl: Some(10)
+            })
+        );
+        assert_eq!(
+            cache_hint("query { concerts { id } artists { id } }"),
+            Some(CacheHint {
+                max_age: 0,
+                scope: CacheScope::Public,
+                ttl: Some(10)
+            })
+        );
+
+        // Aggregates follow the hint of the aggregated type
+        assert_eq!(
+            cache_hint("query { concertsAgg { id { count } } }"),
+            Some(CacheHint {
+                max_age: 60,
+                scope: CacheScope::Public,
+                ttl: Some(120)
+            })
+        );
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn cache_hint_requires_hints_on_all_root_fields() {
+        let schema = create_cache_hint_test_schema().await;
+
+        let cache_hint = |query: &str| {
+            DocumentValidator::new(&schema, None, None, 10, 10)
+                .validate(create_query_document(query))
+                .unwrap()
+                .cache_hint(&schema)
+        };
+
+        assert_eq!(cache_hint("query { performers { id } }"), None);
+        assert_eq!(
+            cache_hint("query { concerts { id } performers { id } }"),
+            None
+        );
+        assert_eq!(
+            cache_hint(r#"mutation { createConcert(data: { title: "A" }) { id } }"#),
+            None
+        );
+    }
+
+    async fn create_cache_hint_test_schema() -> Schema {
+        let test_exo = r#"
+            @postgres
+            module ConcertModule {
+                @access(true)
+                @cache(maxAge=60, ttl=120)
+                type Concert {
+                    @pk id: Int = autoIncrement()
+                    title: String
+                    venue: Venue?
+                }
+
+                @access(true)
+                @cache(maxAge=30, scope="PRIVATE")
+                type Venue {
+                    @pk id: Int = autoIncrement()
+                    name: String
+                    concerts: Set<Concert>?
+                }
+
+                @access(true)
+                @cache(ttl=10)
+                type Artist {
+                    @pk id: Int = autoIncrement()
+                    name: String
+                }
+
+                @access(true)
+                type Performer {
+                    @pk id: Int = autoIncrement()
+                    name: String
+                }
+            }
+        "#;
+        create_schema(test_exo).await
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn cost_multiplies_list_fields() {
+        let schema = create_test_schema().await;
+
+        let cost = |query: &str| {
+            DocumentValidator::new(&schema, None, None, 10, 10)
+                .validate(create_query_document(query))
+                .unwrap()
+                .cost(&schema, 10)
+        };
+
+        assert_eq!(cost("query { concert(id: 1) { id title } }"), 1);
+        // Without a limit, a list is assumed to have the default list size
+        assert_eq!(cost("query { concerts { id title } }"), 10);
+        assert_eq!(
+            cost("query { concerts(limit: 5) { id venue { name } } }"),
+            10
+        );
+        assert_eq!(
+            cost("query { venues(limit: 2) { id concerts(limit: 3) { id } } }"),
+            8
+        );
+        assert_eq!(cost("query { __typename concert(id: 1) { id } }"), 1);
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn cost_annotation_overrides_default_weight() {
+        let schema = create_cost_test_schema().await;
+
+        let cost = |query: &str| {
+            DocumentValidator::new(&schema, None, None, 10, 10)
+                .validate(create_query_document(query))
+                .unwrap()
+                .cost(&schema, 10)
+        };
+
+        assert_eq!(cost("query { venue(id: 1) { id name } }"), 5);
+        assert_eq!(cost("query { concerts(limit: 2) { id venue { id } } }"), 12);
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn cost_over_limit_invalid() {
+        let schema = create_cost_test_schema().await;
+
+        let validate = |query: &str| {
+            DocumentValidator::new(&schema, None, None, 10, 10)
+                .with_cost_limit(Some(QueryCostLimit {
+                    max_cost: 100,
+                    default_list_size: 10,
+                }))
+                .validate(create_query_document(query))
+        };
+
+        assert!(validate("query { venues { id concerts(limit: 5) { id } } }").is_ok());
+        assert!(matches!(
+            validate("query { venues { id concerts { id } } }"),
+            Err(ValidationError::CostTooHigh {
+                cost: 150,
+                max_cost: 100
+            })
+        ));
+    }
+
+    async fn create_cost_test_schema() -> Schema {
+        let test_exo = r#"
+            @postgres
+            module ConcertModule {
+                @access(true)
+                type Concert {
+                    @pk id: Int = autoIncrement()
+                    title: String
+                    venue: Venue
+                }
+
+                @access(true)
+                @cost(5)
+                type Venue {
+                    @pk id: Int = autoIncrement()
+                    name: String
+                    concerts: Set<Concert>
+                }
+            }
+        "#;
+        create_schema(test_exo).await
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn upload_replaces_variable_value() {
+        let schema = create_schema(
+            r#"
+            @postgres
+            module DocumentModule {
+                @access(true)
+                type Document {
+                    @pk id: Int = autoIncrement()
+                    content: Blob
+                }
+            }
+        "#,
+        )
+        .await;
+
+        let validate = |path: &[&str]| {
+            let query =
+                "mutation($data: DocumentCreationInput!) { createDocument(data: $data) { id } }";
+
+            DocumentValidator::new(
+                &schema,
+                None,
+                Some(create_variables(r#"{ "data": { "content": null } }"#)),
+                10,
+                10,
+            )
+            .with_uploads(vec![Upload {
+                path: path.iter().map(|segment| segment.to_string()).collect(),
+                content: Bytes::from_static(b"file content"),
+            }])
+            .validate(create_query_document(query))
+        };
+
+        let operation = validate(&["data", "content"]).unwrap();
+        let Some(Val::Object(data)) = operation.fields[0].arguments.get("data") else {
+            panic!("expected an object argument");
+        };
+        assert_eq!(
+            data.get("content"),
+            Some(&Val::Binary(Bytes::from_static(b"file content")))
+        );
+
+        assert!(matches!(
+            validate(&["data", "title"]),
+            Err(ValidationError::InvalidUploadPath(path, _)) if path == "data.title"
+        ));
+        assert!(matches!(
+            validate(&["file"]),
+            Err(ValidationError::InvalidUploadPath(path, _)) if path == "file"
+        ));
+    }
+
     fn create_variables(variables: &str) -> Map<String, Value> {
         serde_json::from_str(variables).unwrap()
     }
 
-    async fn create_test_schema() -> Schema {
+    pub(crate) async fn create_test_schema() -> Schema {
         let test_exo = r#"
             @postgres
             module LogModule {
@@ -818,6 +1304,10 @@ mod tests {
                 }
             }
         "#;
+        create_schema(test_exo).await
+    }
+
+    async fn create_schema(test_exo: &str) -> Schema {
         let postgres_subsystem =
             create_postgres_system_from_str(test_exo, "test.exo".to_string()).await;
 
@@ -833,6 +1323,11 @@ mod tests {
                 .as_ref()
                 .unwrap()
                 .schema_mutations(),
+            postgres_subsystem
+                .graphql
+                .as_ref()
+                .unwrap()
+                .schema_subscriptions(),
             Arc::new(None),
         )
     }
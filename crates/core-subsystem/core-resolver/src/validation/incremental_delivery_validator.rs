@@ -0,0 +1,368 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::{HashMap, HashSet};
+
+use async_graphql_parser::{
+    Pos, Positioned,
+    types::{Directive, FragmentDefinition, Selection, SelectionSet},
+};
+use async_graphql_value::{ConstValue, Name};
+
+use crate::validation::validation_error::ValidationError;
+
+use super::operation::DeferredFields;
+
+/// Context for validating the `@defer` and `@stream` directives of an operation.
+///
+/// Since subsystems resolve each top-level field as a whole, only a `@defer` fragment in the
+/// top-level selection set (possibly through other fragments) can be delivered later. A `@defer`
+/// fragment below a field, as well as any `@stream`, is rejected rather than silently delivered
+/// with the rest.
+pub struct IncrementalDeliveryValidator<'a> {
+    variables: &'a HashMap<Name, ConstValue>,
+    fragment_definitions: &'a HashMap<Name, Positioned<FragmentDefinition>>,
+}
+
+/// The directive arguments that decide how to deliver a fragment
+struct IncrementalDirective {
+    label: Option<String>,
+}
+
+#[derive(Default)]
+struct Collected {
+    /// Output names of the fields selected outside any `@defer` fragment
+    immediate: HashSet<String>,
+    deferred: Vec<DeferredFields>,
+    labels: HashSet<String>,
+}
+
+impl<'a> IncrementalDeliveryValidator<'a> {
+    #[must_use]
+    pub fn new(
+        variables: &'a HashMap<Name, ConstValue>,
+        fragment_definitions: &'a HashMap<Name, Positioned<FragmentDefinition>>,
+    ) -> Self {
+        Self {
+            variables,
+            fragment_definitions,
+        }
+    }
+
+    /// Validate the `@defer` and `@stream` directives in the selection set.
+    ///
+    /// Validations performed:
+    /// - The `if` and `label` arguments are of the expected types
+    /// - Each label is unique
+    /// - `@defer` is used only on fragments in the top-level selection set
+    /// - `@stream` is not used
+    ///
+    /// Assumes that the selection set is otherwise valid (see
+    /// [super::selection_set_validator::SelectionSetValidator]).
+    ///
+    /// # Returns
+    ///   The deferred fields (leaving out any field also selected outside a `@defer` fragment)
+    pub(super) fn validate(
+        &self,
+        selection_set: &Positioned<SelectionSet>,
+    ) -> Result<Vec<DeferredFields>, ValidationError> {
+        let mut collected = Collected::default();
+
+        self.collect(selection_set, None, &mut HashSet::new(), &mut collected)?;
+
+        let Collected {
+            immediate,
+            deferred,
+            ..
+        } = collected;
+
+        Ok(deferred
+            .into_iter()
+            .filter_map(|DeferredFields { label, fields }| {
+                let fields: Vec<_> = fields
+                    .into_iter()
+                    .filter(|field| !immediate.contains(field))
+                    .collect();
+
+                (!fields.is_empty()).then_some(DeferredFields { label, fields })
+            })
+            .collect())
+    }
+
+    fn collect(
+        &self,
+        selection_set: &Positioned<SelectionSet>,
+        deferred_index: Option<usize>,
+        fragment_trail: &mut HashSet<Name>,
+        collected: &mut Collected,
+    ) -> Result<(), ValidationError> {
+        for selection in &selection_set.node.items {
+            match &selection.node {
+                Selection::Field(field) => {
+                    let output_name = field
+                        .node
+                        .alias
+                        .as_ref()
+                        .unwrap_or(&field.node.name)
+                        .node
+                        .to_string();
+
+                    self.reject_stream(&field.node.directives, field.pos, collected)?;
+                    self.reject_nested(&field.node.selection_set, fragment_trail, collected)?;
+
+                    match deferred_index {
+                        Some(index) => {
+                            let already_deferred = collected
+                                .deferred
+                                .iter()
+                                .any(|deferred| deferred.fields.contains(&output_name));
+
+                            if !already_deferred {
+                                collected.deferred[index].fields.push(output_name);
+                            }
+                        }
+                        None => {
+                            collected.immediate.insert(output_name);
+                        }
+                    }
+                }
+                Selection::FragmentSpread(fragment_spread) => {
+                    let fragment_name = &fragment_spread.node.fragment_name.node;
+
+                    // Cycles (and missing definitions) are reported by the selection set validator
+                    let Some(fragment_definition) = self.fragment_definitions.get(fragment_name)
+                    else {
+                        continue;
+                    };
+                    if !fragment_trail.insert(fragment_name.clone()) {
+                        continue;
+                    }
+
+                    let deferred_index = self.deferred_index(
+                        &fragment_spread.node.directives,
+                        deferred_index,
+                        collected,
+                    )?;
+                    self.collect(
+                        &fragment_definition.node.selection_set,
+                        deferred_index,
+                        fragment_trail,
+                        collected,
+                    )?;
+
+                    fragment_trail.remove(fragment_name);
+                }
+                Selection::InlineFragment(inline_fragment) => {
+                    let deferred_index = self.deferred_index(
+                        &inline_fragment.node.directives,
+                        deferred_index,
+                        collected,
+                    )?;
+                    self.collect(
+                        &inline_fragment.node.selection_set,
+                        deferred_index,
+                        fragment_trail,
+                        collected,
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reject any `@defer` or `@stream` in the selection set of a field (whose value its subsystem
+    /// resolves as a whole)
+    fn reject_nested(
+        &self,
+        selection_set: &Positioned<SelectionSet>,
+        fragment_trail: &mut HashSet<Name>,
+        collected: &mut Collected,
+    ) -> Result<(), ValidationError> {
+        for selection in &selection_set.node.items {
+            match &selection.node {
+                Selection::Field(field) => {
+                    self.reject_stream(&field.node.directives, field.pos, collected)?;
+                    self.reject_nested(&field.node.selection_set, fragment_trail, collected)?;
+                }
+                Selection::FragmentSpread(fragment_spread) => {
+                    self.reject_nested_defer(
+                        &fragment_spread.node.directives,
+                        fragment_spread.pos,
+                        collected,
+                    )?;
+
+                    let fragment_name = &fragment_spread.node.fragment_name.node;
+                    let Some(fragment_definition) = self.fragment_definitions.get(fragment_name)
+                    else {
+                        continue;
+                    };
+                    if !fragment_trail.insert(fragment_name.clone()) {
+                        continue;
+                    }
+
+                    self.reject_nested(
+                        &fragment_definition.node.selection_set,
+                        fragment_trail,
+                        collected,
+                    )?;
+
+                    fragment_trail.remove(fragment_name);
+                }
+                Selection::InlineFragment(inline_fragment) => {
+                    self.reject_nested_defer(
+                        &inline_fragment.node.directives,
+                        inline_fragment.pos,
+                        collected,
+                    )?;
+                    self.reject_nested(
+                        &inline_fragment.node.selection_set,
+                        fragment_trail,
+                        collected,
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn reject_stream(
+        &self,
+        directives: &[Positioned<Directive>],
+        pos: Pos,
+        collected: &mut Collected,
+    ) -> Result<(), ValidationError> {
+        match self.directive(directives, "stream", collected)? {
+            Some(_) => Err(ValidationError::StreamNotSupported(pos)),
+            None => Ok(()),
+        }
+    }
+
+    fn reject_nested_defer(
+        &self,
+        directives: &[Positioned<Directive>],
+        pos: Pos,
+        collected: &mut Collected,
+    ) -> Result<(), ValidationError> {
+        match self.directive(directives, "defer", collected)? {
+            Some(_) => Err(ValidationError::NestedDefer(pos)),
+            None => Ok(()),
+        }
+    }
+
+    /// The index (in `collected.deferred`) of the fragment to deliver the fields under a fragment
+    /// with the given directives in. A `@defer` fragment nested in another is delivered along
+    /// with the outer one.
+    fn deferred_index(
+        &self,
+        directives: &[Positioned<Directive>],
+        enclosing_index: Option<usize>,
+        collected: &mut Collected,
+    ) -> Result<Option<usize>, ValidationError> {
+        let defer = self.directive(directives, "defer", collected)?;
+
+        Ok(match (enclosing_index, defer) {
+            (Some(index), _) => Some(index),
+            (None, Some(defer)) => {
+                collected.deferred.push(DeferredFields {
+                    label: defer.label,
+                    fields: vec![],
+                });
+                Some(collected.deferred.len() - 1)
+            }
+            (None, None) => None,
+        })
+    }
+
+    /// The arguments of the named directive, if present and not disabled (through `if: false`)
+    fn directive(
+        &self,
+        directives: &[Positioned<Directive>],
+        name: &str,
+        collected: &mut Collected,
+    ) -> Result<Option<IncrementalDirective>, ValidationError> {
+        let Some(directive) = directives
+            .iter()
+            .find(|directive| directive.node.name.node == name)
+        else {
+            return Ok(None);
+        };
+
+        let enabled = match self.argument(directive, "if")? {
+            None => true,
+            Some((ConstValue::Boolean(enabled), _)) => enabled,
+            Some((value, pos)) => return Err(invalid_argument("if", "Boolean", &value, pos)),
+        };
+
+        let label = match self.argument(directive, "label")? {
+            None => None,
+            Some((ConstValue::String(label), pos)) => {
+                if !collected.labels.insert(label.clone()) {
+                    return Err(ValidationError::DuplicateIncrementalLabel(label, pos));
+                }
+                Some(label)
+            }
+            Some((value, pos)) => return Err(invalid_argument("label", "String", &value, pos)),
+        };
+
+        Ok(enabled.then_some(IncrementalDirective { label }))
+    }
+
+    /// The value of the argument (resolving any variable), unless absent or null
+    fn argument(
+        &self,
+        directive: &Positioned<Directive>,
+        name: &str,
+    ) -> Result<Option<(ConstValue, Pos)>, ValidationError> {
+        let Some(value) = directive.node.get_argument(name) else {
+            return Ok(None);
+        };
+
+        let resolved = value.node.clone().into_const_with(|variable_name| {
+            // An absent (nullable) variable makes the argument take its default value
+            Ok::<_, ValidationError>(
+                self.variables
+                    .get(&variable_name)
+                    .cloned()
+                    .unwrap_or(ConstValue::Null),
+            )
+        })?;
+
+        Ok(match resolved {
+            ConstValue::Null => None,
+            resolved => Some((resolved, value.pos)),
+        })
+    }
+}
+
+fn invalid_argument(
+    argument_name: &str,
+    expected_type: &str,
+    value: &ConstValue,
+    pos: Pos,
+) -> ValidationError {
+    let actual_type = match value {
+        ConstValue::Null => "null",
+        ConstValue::Number(_) => "Number",
+        ConstValue::String(_) => "String",
+        ConstValue::Boolean(_) => "Boolean",
+        ConstValue::Binary(_) => "Binary",
+        ConstValue::Enum(_) => "Enum",
+        ConstValue::List(_) => "List",
+        ConstValue::Object(_) => "Object",
+    };
+
+    ValidationError::InvalidArgumentType {
+        argument_name: argument_name.to_string(),
+        expected_type: expected_type.to_string(),
+        actual_type: actual_type.to_string(),
+        pos,
+    }
+}
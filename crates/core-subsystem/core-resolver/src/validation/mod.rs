@@ -19,6 +19,7 @@ pub mod operation;
 pub mod document_validator;
 
 mod arguments_validator;
+mod incremental_delivery_validator;
 mod operation_validator;
 mod selection_set_validator;
 
@@ -7,9 +7,16 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use async_graphql_parser::types::OperationType;
+use async_graphql_parser::types::{BaseType, FieldDefinition, OperationType, TypeKind};
+use common::value::Val;
+use core_model::types::{CacheHint, Cost};
 
-use crate::validation::field::ValidatedField;
+use crate::{
+    introspection::definition::schema::{
+        MUTATION_ROOT_TYPENAME, QUERY_ROOT_TYPENAME, SUBSCRIPTION_ROOT_TYPENAME, Schema,
+    },
+    validation::{field::ValidatedField, underlying_type},
+};
 
 // Validated operation.
 #[derive(Debug)]
@@ -19,4 +26,167 @@ pub struct ValidatedOperation {
     pub typ: OperationType,
     /// The operation's fields (individual queries or mutations).
     pub fields: Vec<ValidatedField>,
+    /// The top-level fields selected only through `@defer` fragments (to deliver after the rest
+    /// of the fields when resolving incrementally).
+    pub deferred: Vec<DeferredFields>,
+}
+
+impl ValidatedOperation {
+    /// Whether the operation has any part to deliver incrementally
+    pub fn is_incremental(&self) -> bool {
+        !self.deferred.is_empty()
+    }
+
+    /// How long (and by whom) the result of the operation may be cached, combining the `@cache`
+    /// hints of the selected fields and their types. Only a query whose every root field has a
+    /// hint (on the field or on its type) may be cached. Nested fields without a hint inherit the
+    /// hint of the enclosing field.
+    pub fn cache_hint(&self, schema: &Schema) -> Option<CacheHint> {
+        if self.typ != OperationType::Query {
+            return None;
+        }
+
+        let mut combined: Option<CacheHint> = None;
+
+        for field in self
+            .fields
+            .iter()
+            .filter(|field| field.name != "__typename")
+        {
+            let field_definition = object_field(schema, QUERY_ROOT_TYPENAME, &field.name)?;
+            let hint = restrict_by_subfields(
+                field_cache_hint(schema, field_definition)?,
+                field,
+                field_definition,
+                schema,
+            );
+
+            combined = Some(combined.map_or(hint, |combined| combined.restrict(hint)));
+        }
+
+        combined
+    }
+
+    /// An estimate of the work needed to resolve the operation. Each selected field costs its
+    /// `@cost` weight (on the field or on its type), which defaults to 1 for fields of an object
+    /// type and 0 for others, plus the cost of its subfields. The cost of a list field is then
+    /// multiplied by the number of elements it may return: its `limit` (or `first`) argument, or
+    /// `default_list_size` if there is none.
+    pub fn cost(&self, schema: &Schema, default_list_size: usize) -> usize {
+        let root_type_name = match self.typ {
+            OperationType::Query => QUERY_ROOT_TYPENAME,
+            OperationType::Mutation => MUTATION_ROOT_TYPENAME,
+            OperationType::Subscription => SUBSCRIPTION_ROOT_TYPENAME,
+        };
+
+        self.fields.iter().fold(0, |cost, field| {
+            cost.saturating_add(field_cost(schema, root_type_name, field, default_list_size))
+        })
+    }
+}
+
+/// The hint on the field itself or, failing that, on its type
+fn field_cache_hint(schema: &Schema, field_definition: &FieldDefinition) -> Option<CacheHint> {
+    CacheHint::from_directives(&field_definition.directives).or_else(|| {
+        schema
+            .get_type_definition(underlying_type(&field_definition.ty.node))
+            .and_then(|type_definition| CacheHint::from_directives(&type_definition.directives))
+    })
+}
+
+fn restrict_by_subfields(
+    hint: CacheHint,
+    field: &ValidatedField,
+    field_definition: &FieldDefinition,
+    schema: &Schema,
+) -> CacheHint {
+    let field_type_name = underlying_type(&field_definition.ty.node);
+
+    field.subfields.iter().fold(hint, |hint, subfield| {
+        match object_field(schema, field_type_name, &subfield.name) {
+            Some(subfield_definition) => {
+                let hint = match field_cache_hint(schema, subfield_definition) {
+                    Some(subfield_hint) => hint.restrict(subfield_hint),
+                    None => hint,
+                };
+                restrict_by_subfields(hint, subfield, subfield_definition, schema)
+            }
+            // Such as `__typename`
+            None => hint,
+        }
+    })
+}
+
+fn field_cost(
+    schema: &Schema,
+    container_type_name: &str,
+    field: &ValidatedField,
+    default_list_size: usize,
+) -> usize {
+    // Such as `__typename` (and the introspection fields, which are limited by the depth instead)
+    let Some(field_definition) = object_field(schema, container_type_name, &field.name) else {
+        return 0;
+    };
+
+    let field_type_name = underlying_type(&field_definition.ty.node);
+    let field_type = schema.get_type_definition(field_type_name);
+
+    let weight = Cost::from_directives(&field_definition.directives)
+        .or_else(|| field_type.and_then(|typ| Cost::from_directives(&typ.directives)))
+        .map(|cost| cost.weight as usize)
+        .unwrap_or_else(|| match field_type.map(|typ| &typ.kind) {
+            Some(TypeKind::Object(_)) => 1,
+            _ => 0,
+        });
+
+    let cost = field.subfields.iter().fold(weight, |cost, subfield| {
+        cost.saturating_add(field_cost(
+            schema,
+            field_type_name,
+            subfield,
+            default_list_size,
+        ))
+    });
+
+    if matches!(field_definition.ty.node.base, BaseType::List(_)) {
+        cost.saturating_mul(list_size(field, default_list_size))
+    } else {
+        cost
+    }
+}
+
+/// The number of elements a list field may return
+fn list_size(field: &ValidatedField, default_list_size: usize) -> usize {
+    ["limit", "first"]
+        .iter()
+        .find_map(|argument_name| match field.arguments.get(*argument_name) {
+            Some(Val::Number(number)) => number
+                .as_i64()
+                .map(|size| usize::try_from(size).unwrap_or(0)),
+            _ => None,
+        })
+        .unwrap_or(default_list_size)
+}
+
+fn object_field<'a>(
+    schema: &'a Schema,
+    type_name: &str,
+    field_name: &str,
+) -> Option<&'a FieldDefinition> {
+    match &schema.get_type_definition(type_name)?.kind {
+        TypeKind::Object(object_type) => object_type
+            .fields
+            .iter()
+            .find(|field| field.node.name.node == field_name)
+            .map(|field| &field.node),
+        _ => None,
+    }
+}
+
+/// The fields of a `@defer` fragment
+#[derive(Debug)]
+pub struct DeferredFields {
+    pub label: Option<String>,
+    /// The output names of the fields (each a member of [ValidatedOperation::fields])
+    pub fields: Vec<String>,
 }
@@ -14,20 +14,28 @@ use async_graphql_parser::{
     types::{FragmentDefinition, OperationDefinition, OperationType, VariableDefinition},
 };
 use async_graphql_value::{ConstValue, Name};
+use bytes::Bytes;
+use common::operation_payload::Upload;
 use serde_json::{Map, Value};
 
 use crate::{
-    introspection::definition::schema::{MUTATION_ROOT_TYPENAME, QUERY_ROOT_TYPENAME, Schema},
+    introspection::definition::schema::{
+        MUTATION_ROOT_TYPENAME, QUERY_ROOT_TYPENAME, SUBSCRIPTION_ROOT_TYPENAME, Schema,
+    },
     validation::validation_error::ValidationError,
 };
 
-use super::{operation::ValidatedOperation, selection_set_validator::SelectionSetValidator};
+use super::{
+    incremental_delivery_validator::IncrementalDeliveryValidator, operation::ValidatedOperation,
+    selection_set_validator::SelectionSetValidator,
+};
 
 /// Context for validating an operation.
 pub struct OperationValidator<'a> {
     schema: &'a Schema,
     operation_name: Option<String>,
     variables: Option<Map<String, Value>>,
+    uploads: Vec<Upload>,
     fragment_definitions: HashMap<Name, Positioned<FragmentDefinition>>,
     normal_query_depth_limit: usize,
     introspection_query_depth_limit: usize,
@@ -39,6 +47,7 @@ impl<'a> OperationValidator<'a> {
         schema: &'a Schema,
         operation_name: Option<String>,
         variables: Option<Map<String, Value>>,
+        uploads: Vec<Upload>,
         fragment_definitions: HashMap<Name, Positioned<FragmentDefinition>>,
         normal_query_depth_limit: usize,
         introspection_query_depth_limit: usize,
@@ -47,6 +56,7 @@ impl<'a> OperationValidator<'a> {
             schema,
             operation_name,
             variables,
+            uploads,
             fragment_definitions,
             normal_query_depth_limit,
             introspection_query_depth_limit,
@@ -94,6 +104,10 @@ impl<'a> OperationValidator<'a> {
     /// - Each variables in [OperationDefinition.variable_definitions] is
     ///   available (see [`validate_variables`] for details)
     /// - The selected fields are valid (see [SelectionSetValidator] for details)])
+    /// - A subscription selects exactly one top-level field
+    /// - The `@defer` and `@stream` directives are valid (see [IncrementalDeliveryValidator] for
+    ///   details). Since mutations must run in order and each subscription event is a single
+    ///   response, these directives take effect only in queries.
     ///
     /// # Returns
     ///   A validated operation with all variables and fields resolved and normalized.
@@ -104,7 +118,7 @@ impl<'a> OperationValidator<'a> {
         let operation_type_name = match operation.node.ty {
             OperationType::Query => QUERY_ROOT_TYPENAME,
             OperationType::Mutation => MUTATION_ROOT_TYPENAME,
-            OperationType::Subscription => todo!(),
+            OperationType::Subscription => SUBSCRIPTION_ROOT_TYPENAME,
         };
 
         let container_type = match self.schema.get_type_definition(operation_type_name) {
@@ -132,10 +146,23 @@ impl<'a> OperationValidator<'a> {
             &self.selection_depth_check(),
         )?;
 
+        // Each event of a subscription is for a single source stream (GraphQL spec 5.2.3.1)
+        if operation.node.ty == OperationType::Subscription && fields.len() != 1 {
+            return Err(ValidationError::SubscriptionRootFieldCount(operation.pos));
+        }
+
+        let deferred = if operation.node.ty == OperationType::Query {
+            IncrementalDeliveryValidator::new(&variables, &self.fragment_definitions)
+                .validate(&operation.node.selection_set)?
+        } else {
+            vec![]
+        };
+
         Ok(ValidatedOperation {
             name: self.operation_name,
             typ: operation.node.ty,
             fields,
+            deferred,
         })
     }
 
@@ -144,6 +171,7 @@ impl<'a> OperationValidator<'a> {
     /// Validations performed:
     /// - All variables in [OperationDefinition.variable_definitions] are
     ///   available
+    /// - Each upload refers to a declared variable
     /// - (TODO) All variables are of the correct type. This is currently not
     ///   possible because we don't have enough information (such as the
     ///   `DateTime` type or the range of values for `Int`) in the schema yet.
@@ -155,6 +183,17 @@ impl<'a> OperationValidator<'a> {
         &'a self,
         variable_definitions: Vec<Positioned<VariableDefinition>>,
     ) -> Result<HashMap<Name, ConstValue>, ValidationError> {
+        if let Some(upload) = self.uploads.iter().find(|upload| {
+            !variable_definitions.iter().any(|vd| {
+                upload.path.first().map(String::as_str) == Some(vd.node.name.node.as_str())
+            })
+        }) {
+            return Err(ValidationError::InvalidUploadPath(
+                upload.path.join("."),
+                Pos::default(),
+            ));
+        }
+
         variable_definitions
             .into_iter()
             .filter_map(|vd| {
@@ -172,6 +211,9 @@ impl<'a> OperationValidator<'a> {
     /// error. Returning `Ok(None)` here lets callers (such as the arguments
     /// validator) apply the same "absent nullable" behavior as for omitted
     /// arguments.
+    ///
+    /// The content of any file uploaded for the variable (see [Upload]) replaces the value at
+    /// the upload's path (the client sends `null` as a placeholder there).
     fn var_value(
         &self,
         variable_definition: &VariableDefinition,
@@ -184,10 +226,18 @@ impl<'a> OperationValidator<'a> {
             .as_ref()
             .and_then(|variables| variables.get(name_str));
 
-        match provided {
+        let mut uploads = self
+            .uploads
+            .iter()
+            .filter(|upload| upload.path.first().map(String::as_str) == Some(name_str))
+            .peekable();
+
+        let value = match provided {
             Some(value) => ConstValue::from_json(value.to_owned())
                 .map(Some)
                 .map_err(|e| ValidationError::MalformedVariable(name_str.to_string(), name.pos, e)),
+            // A client may omit the placeholder for an uploaded variable
+            None if uploads.peek().is_some() => Ok(Some(ConstValue::Null)),
             None => match (
                 &variable_definition.default_value,
                 variable_definition.var_type.node.nullable,
@@ -199,6 +249,40 @@ impl<'a> OperationValidator<'a> {
                     name.pos,
                 )),
             },
+        }?;
+
+        uploads.try_fold(value, |value, upload| {
+            let mut value = value.unwrap_or(ConstValue::Null);
+            if set_upload(&mut value, &upload.path[1..], &upload.content) {
+                Ok(Some(value))
+            } else {
+                Err(ValidationError::InvalidUploadPath(
+                    upload.path.join("."),
+                    name.pos,
+                ))
+            }
+        })
+    }
+}
+
+/// Replace the value at `path` (object field names and list indices) with the uploaded content.
+/// Returns false if the path doesn't exist in the value.
+fn set_upload(value: &mut ConstValue, path: &[String], content: &Bytes) -> bool {
+    match path.split_first() {
+        None => {
+            *value = ConstValue::Binary(content.clone());
+            true
+        }
+        Some((key, rest)) => {
+            let nested = match value {
+                ConstValue::Object(fields) => fields.get_mut(key.as_str()),
+                ConstValue::List(elements) => key
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|index| elements.get_mut(index)),
+                _ => None,
+            };
+            nested.is_some_and(|nested| set_upload(nested, rest, content))
         }
     }
 }
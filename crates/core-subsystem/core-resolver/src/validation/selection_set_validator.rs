@@ -197,9 +197,31 @@ impl<'a> SelectionSetValidator<'a> {
                         )
                     })
             }
-            Selection::InlineFragment(inline_fragment) => Err(
-                ValidationError::InlineFragmentNotSupported(inline_fragment.pos),
-            ),
+            // Without interfaces or unions, an inline fragment may only select from the
+            // container type (typically to apply a directive such as `@defer` to its fields)
+            Selection::InlineFragment(inline_fragment) => {
+                let same_type =
+                    inline_fragment
+                        .node
+                        .type_condition
+                        .as_ref()
+                        .is_none_or(|condition| {
+                            condition.node.on.node == self.container_type.name.node
+                        });
+
+                if !same_type {
+                    return Err(ValidationError::InlineFragmentNotSupported(
+                        inline_fragment.pos,
+                    ));
+                }
+
+                self.validate_selection_set(
+                    &inline_fragment.node.selection_set,
+                    fragment_trail,
+                    selection_depth,
+                    selection_depth_check,
+                )
+            }
         }
     }
 
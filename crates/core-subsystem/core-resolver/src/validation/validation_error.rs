@@ -21,10 +21,15 @@ pub enum ValidationError {
     #[error("Variable '{0}' could not be deserialized: {2}")]
     MalformedVariable(String, Pos, serde_json::Error),
 
+    #[error("Upload path '{0}' doesn't refer to a variable value")]
+    InvalidUploadPath(String, Pos),
+
     #[error("Fragment definition '{0}' not found")]
     FragmentDefinitionNotFound(String, Pos),
 
-    #[error("Inline fragments are not supported")]
+    #[error(
+        "Inline fragments with a type condition other than the enclosing type are not supported"
+    )]
     InlineFragmentNotSupported(Pos),
 
     #[error("No such operation '{0}'")]
@@ -75,6 +80,21 @@ pub enum ValidationError {
     #[error("Selection set too deep")]
     SelectionSetTooDeep(Pos),
 
+    #[error("Operation cost {cost} exceeds the maximum allowed cost of {max_cost}")]
+    CostTooHigh { cost: usize, max_cost: usize },
+
+    #[error("A subscription must select exactly one top-level field")]
+    SubscriptionRootFieldCount(Pos),
+
+    #[error("'@stream' is not supported, since each list field is resolved as a whole")]
+    StreamNotSupported(Pos),
+
+    #[error("'@defer' is supported only on fragments in the operation's top-level selection set")]
+    NestedDefer(Pos),
+
+    #[error("Label '{0}' is used by more than one '@defer' or '@stream' directive")]
+    DuplicateIncrementalLabel(String, Pos),
+
     #[error("Invalid value for '{value_name}': {range_detail}, {value_detail}")]
     ValueOutOfRange {
         value_name: String,
@@ -92,6 +112,7 @@ impl ValidationError {
             }
             ValidationError::VariableNotFound(_, pos) => vec![*pos],
             ValidationError::MalformedVariable(_, pos, _) => vec![*pos],
+            ValidationError::InvalidUploadPath(_, pos) => vec![*pos],
             ValidationError::FragmentDefinitionNotFound(_, pos) => vec![*pos],
             ValidationError::InlineFragmentNotSupported(pos) => vec![*pos],
             ValidationError::OperationNotFound(_, pos) => vec![*pos],
@@ -107,6 +128,11 @@ impl ValidationError {
             ValidationError::InvalidArgumentType { pos, .. } => vec![*pos],
             ValidationError::FragmentCycle(_, pos) => vec![*pos],
             ValidationError::SelectionSetTooDeep(pos) => vec![*pos],
+            ValidationError::CostTooHigh { .. } => vec![],
+            ValidationError::SubscriptionRootFieldCount(pos) => vec![*pos],
+            ValidationError::StreamNotSupported(pos) => vec![*pos],
+            ValidationError::NestedDefer(pos) => vec![*pos],
+            ValidationError::DuplicateIncrementalLabel(_, pos) => vec![*pos],
             ValidationError::ValueOutOfRange { pos, .. } => vec![*pos],
         }
     }
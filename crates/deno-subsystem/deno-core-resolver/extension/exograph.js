@@ -14,6 +14,7 @@ const {
     op_operation_name,
     op_operation_query,
     op_operation_proceed,
+    op_exograph_subscription_next,
 } = Deno.core.ops;
 
 export function exograph_version() {
@@ -37,6 +38,10 @@ globalThis.ExographExtension = ({
         const result = await op_exograph_execute_query_priv(query_string, variables, context_override);
         return result;
     },
+
+    subscriptionNext: async function (value) {
+        return await op_exograph_subscription_next(value);
+    },
 })
 
 globalThis.ExographOperation = ({
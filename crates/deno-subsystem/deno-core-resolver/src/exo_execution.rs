@@ -28,7 +28,7 @@ use exo_deno::{
     deno_module::DenoModule,
 };
 
-use crate::exograph_ops::InterceptedOperationInfo;
+use crate::exograph_ops::DenoCallContext;
 
 #[derive(Default, Debug)]
 pub struct ExographMethodResponse {
@@ -108,16 +108,24 @@ const SHIMS: [(&str, &[&str]); 3] = {
     ]
 };
 
-const ADDITIONAL_CODE: &[&str] = &[include_str!("./exograph_error.js")];
+const ADDITIONAL_CODE: &[&str] = &[
+    include_str!("./exograph_error.js"),
+    include_str!("./subscription_methods.js"),
+];
 const EXPLICIT_ERROR_CLASS_NAME: Option<&'static str> = Some("ExographError");
 
-pub fn process_call_context(
-    deno_module: &mut DenoModule,
-    call_context: Option<InterceptedOperationInfo>,
-) {
+pub fn process_call_context(deno_module: &mut DenoModule, call_context: DenoCallContext) {
+    let DenoCallContext {
+        intercepted_operation,
+        subscription_sender,
+    } = call_context;
+
     deno_module
-        .put(call_context)
+        .put(intercepted_operation)
         .unwrap_or_else(|_| panic!("Failed to setup interceptor"));
+    deno_module
+        .put(subscription_sender)
+        .unwrap_or_else(|_| panic!("Failed to setup subscription"));
 }
 
 // We provide a set of Exograph functionality accessible via this Deno extension.
@@ -140,6 +148,7 @@ deno_core::extension!(
         crate::exograph_ops::op_operation_name,
         crate::exograph_ops::op_operation_query,
         crate::exograph_ops::op_operation_proceed,
+        crate::exograph_ops::op_exograph_subscription_next,
     ],
     esm_entry_point = "ext:exograph/__init.js",
     customizer = |ext: &mut deno_core::Extension| {
@@ -160,9 +169,7 @@ deno_core::extension!(
     }
 );
 
-pub fn exo_config(
-    env: Arc<dyn Environment>,
-) -> DenoExecutorConfig<Option<InterceptedOperationInfo>> {
+pub fn exo_config(env: Arc<dyn Environment>) -> DenoExecutorConfig<DenoCallContext> {
     fn create_extensions() -> Vec<Extension> {
         vec![exograph::init()]
     }
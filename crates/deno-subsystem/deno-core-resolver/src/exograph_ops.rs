@@ -28,6 +28,18 @@ pub struct InterceptedOperationInfo {
     pub query: Value,
 }
 
+/// Channel on which a subscription method publishes each value its async iterator yields
+pub type SubscriptionSender = Sender<Value>;
+
+/// Per-call state made available to the ops while a method executes
+#[derive(Debug, Default)]
+pub struct DenoCallContext {
+    /// Set when executing an interceptor
+    pub intercepted_operation: Option<InterceptedOperationInfo>,
+    /// Set when executing a subscription method
+    pub subscription_sender: Option<SubscriptionSender>,
+}
+
 pub async fn op_exograph_execute_query_helper(
     state: Rc<RefCell<OpState>>,
     query_string: Value,
@@ -196,6 +208,30 @@ pub async fn op_operation_proceed(
     }
 }
 
+#[op2(async(lazy))]
+pub async fn op_exograph_subscription_next(
+    state: Rc<RefCell<OpState>>,
+    #[serde] value: serde_json::Value,
+) -> Result<(), DenoExecutionError> {
+    let sender = {
+        let state = state.borrow();
+        state
+            .try_borrow::<Option<SubscriptionSender>>()
+            .cloned()
+            .flatten()
+    };
+
+    match sender {
+        Some(sender) => sender
+            .send(value)
+            .await
+            .map_err(|_| DenoExecutionError::Generic("The subscription has ended".to_string())),
+        None => Err(DenoExecutionError::Generic(
+            "Only subscription methods may return an async iterator".to_string(),
+        )),
+    }
+}
+
 // add a header to ExographMethodResponse;
 // this is eventually returned to Exograph through execute_and_get_r
 pub fn add_header(state: &mut OpState, header: String, value: String) {
@@ -10,6 +10,7 @@
 pub mod deno_execution_error;
 pub mod exo_execution;
 pub mod exograph_ops;
+pub mod module_arg;
 
 pub use exo_execution::exo_config;
 
@@ -17,7 +18,7 @@ use exo_deno::DenoExecutorPool;
 
 /// The Deno executor pool type parameterized with Exograph's callback types.
 pub type ExoDenoExecutorPool = DenoExecutorPool<
-    Option<exograph_ops::InterceptedOperationInfo>,
+    exograph_ops::DenoCallContext,
     exo_execution::RequestFromDenoMessage,
     exo_execution::ExographMethodResponse,
 >;
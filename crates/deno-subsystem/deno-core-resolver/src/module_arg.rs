@@ -0,0 +1,219 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use base64::Engine;
+use common::value::Val;
+use core_model::mapped_arena::{SerializableSlab, SerializableSlabIndex};
+use exo_deno::{Arg, ArgValue};
+use serde::de::Error;
+use subsystem_model_util::types::{ModuleType, ModuleTypeKind};
+
+/// Convert the value of a module method's argument to pass to the method. Each `Blob` in the
+/// value becomes a `Uint8Array`, whether the client uploaded it as a file (in a multipart request)
+/// or sent it as a base64-encoded string.
+///
+/// `type_id` is the argument's type without any list or optional wrapping.
+pub fn module_arg(
+    value: &Val,
+    type_id: SerializableSlabIndex<ModuleType>,
+    module_types: &SerializableSlab<ModuleType>,
+) -> Result<Arg, serde_json::Error> {
+    Ok(match arg_value(value, type_id, module_types)? {
+        ArgValue::Json(value) => Arg::Serde(value),
+        value => Arg::Value(value),
+    })
+}
+
+fn arg_value(
+    value: &Val,
+    type_id: SerializableSlabIndex<ModuleType>,
+    module_types: &SerializableSlab<ModuleType>,
+) -> Result<ArgValue, serde_json::Error> {
+    let module_type = &module_types[type_id];
+
+    match (value, &module_type.kind) {
+        (Val::Binary(bytes), _) => Ok(ArgValue::Bytes(bytes.to_vec())),
+        (Val::String(encoded), _) if module_type.name == "Blob" => {
+            base64::engine::general_purpose::STANDARD
+                .decode(encoded)
+                .map(ArgValue::Bytes)
+                .map_err(|e| serde_json::Error::custom(format!("Invalid Blob: {e}")))
+        }
+        (Val::List(values), _) => values
+            .iter()
+            .map(|value| arg_value(value, type_id, module_types))
+            .collect::<Result<_, _>>()
+            .map(ArgValue::List),
+        (Val::Object(entries), ModuleTypeKind::Composite(composite)) => entries
+            .iter()
+            .map(|(name, value)| {
+                let value = match composite.fields.iter().find(|field| &field.name == name) {
+                    Some(field) => arg_value(value, field.typ.innermost().type_id, module_types)?,
+                    None => ArgValue::Json(value.clone().try_into()?),
+                };
+                Ok((name.clone(), value))
+            })
+            .collect::<Result<_, _>>()
+            .map(ArgValue::Object),
+        _ => Ok(ArgValue::Json(value.clone().try_into()?)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use core_model::{mapped_arena::MappedArena, types::FieldType};
+    use serde_json::json;
+    use subsystem_model_util::{
+        access::Access,
+        types::{ModuleCompositeType, ModuleField, ModuleFieldType},
+    };
+
+    use super::*;
+
+    fn module_type(name: &str, kind: ModuleTypeKind) -> ModuleType {
+        ModuleType {
+            name: name.to_string(),
+            kind,
+            is_input: true,
+            doc_comments: None,
+        }
+    }
+
+    fn module_field(
+        name: &str,
+        typ: FieldType<SerializableSlabIndex<ModuleType>>,
+        module_types: &MappedArena<ModuleType>,
+    ) -> ModuleField {
+        ModuleField {
+            name: name.to_string(),
+            typ: typ.wrap(ModuleFieldType {
+                type_id: *typ.innermost(),
+                type_name: module_types[*typ.innermost()].name.clone(),
+            }),
+            has_default_value: false,
+            doc_comments: None,
+        }
+    }
+
+    /// The module types with a `DocumentInput { name: String, pages: [Blob] }` type (and the ids
+    /// of the `Blob` and the `DocumentInput` types)
+    fn module_types() -> (
+        SerializableSlab<ModuleType>,
+        SerializableSlabIndex<ModuleType>,
+        SerializableSlabIndex<ModuleType>,
+    ) {
+        let mut module_types = MappedArena::default();
+        let string_type =
+            module_types.add("String", module_type("String", ModuleTypeKind::Primitive));
+        let blob_type = module_types.add("Blob", module_type("Blob", ModuleTypeKind::Primitive));
+
+        let fields = vec![
+            module_field("name", FieldType::Plain(string_type), &module_types),
+            module_field(
+                "pages",
+                FieldType::List(Box::new(FieldType::Plain(blob_type))),
+                &module_types,
+            ),
+        ];
+        let document_type = module_types.add(
+            "DocumentInput",
+            module_type(
+                "DocumentInput",
+                ModuleTypeKind::Composite(ModuleCompositeType {
+                    fields,
+                    is_input: true,
+                    access: Access::restrictive(),
+                    doc_comments: None,
+                    cache_hint: None,
+                    cost: None,
+                }),
+            ),
+        );
+
+        (module_types.values(), blob_type, document_type)
+    }
+
+    fn arg_value_of(arg: Arg) -> ArgValue {
+        match arg {
+            Arg::Value(value) => value,
+            arg => panic!("Expected a value, got {arg:?}"),
+        }
+    }
+
+    #[test]
+    fn blobs_become_bytes() {
+        let (module_types, blob_type, document_type) = module_types();
+
+        // Sent as a base64-encoded string or uploaded as a file
+        assert_eq!(
+            arg_value_of(
+                module_arg(&Val::String("aGk=".to_string()), blob_type, &module_types).unwrap()
+            ),
+            ArgValue::Bytes(b"hi".to_vec())
+        );
+        assert_eq!(
+            arg_value_of(
+                module_arg(
+                    &Val::Binary(b"hi".to_vec().into()),
+                    blob_type,
+                    &module_types
+                )
+                .unwrap()
+            ),
+            ArgValue::Bytes(b"hi".to_vec())
+        );
+
+        let document = Val::Object(HashMap::from([
+            ("name".to_string(), Val::String("Report".to_string())),
+            (
+                "pages".to_string(),
+                Val::List(vec![
+                    Val::String("aGk=".to_string()),
+                    Val::Binary(b"there".to_vec().into()),
+                ]),
+            ),
+        ]));
+        let mut document =
+            match arg_value_of(module_arg(&document, document_type, &module_types).unwrap()) {
+                ArgValue::Object(entries) => entries,
+                value => panic!("Expected an object, got {value:?}"),
+            };
+        document.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        assert_eq!(
+            document,
+            vec![
+                ("name".to_string(), ArgValue::Json(json!("Report"))),
+                (
+                    "pages".to_string(),
+                    ArgValue::List(vec![
+                        ArgValue::Bytes(b"hi".to_vec()),
+                        ArgValue::Bytes(b"there".to_vec()),
+                    ])
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_blob() {
+        let (module_types, blob_type, _) = module_types();
+
+        assert!(
+            module_arg(
+                &Val::String("not base64!".to_string()),
+                blob_type,
+                &module_types
+            )
+            .is_err()
+        );
+    }
+}
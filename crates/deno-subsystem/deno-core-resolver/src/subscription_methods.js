@@ -0,0 +1,40 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+// Subscription methods are async generators. Wrap every exported function so that, when it returns
+// an async iterator, we drain it here and publish each yielded value (see
+// `op_exograph_subscription_next` in exograph_ops.rs) instead of returning the iterator itself, which
+// can't be serialized.
+//
+// The proxy target is a plain object, since proxying the module namespace itself would subject `get`
+// to the invariants of its non-configurable properties.
+const userModule = globalThis.mod;
+
+globalThis.mod = new Proxy({}, {
+    get(_target, property) {
+        const value = userModule[property];
+
+        if (typeof value !== "function") {
+            return value;
+        }
+
+        return async function (...args) {
+            const result = await value.apply(userModule, args);
+
+            if (result != null && typeof result[Symbol.asyncIterator] === "function") {
+                for await (const item of result) {
+                    await ExographExtension.subscriptionNext(item);
+                }
+                return null;
+            }
+
+            return result;
+        };
+    },
+});
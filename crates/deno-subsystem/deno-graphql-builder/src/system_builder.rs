@@ -12,7 +12,7 @@ use core_model_builder::{ast::ast_types::AstAccessExpr, typechecker::Typed};
 
 use deno_graphql_model::{
     interceptor::Interceptor,
-    operation::{DenoMutation, DenoQuery},
+    operation::{DenoMutation, DenoQuery, DenoSubscription},
     subsystem::DenoSubsystem,
 };
 use subsystem_model_builder_util::ModuleSubsystemWithInterceptors;
@@ -38,12 +38,18 @@ pub fn build_from_module_system(
         mutations.add(&mutation.name.clone(), DenoMutation(mutation));
     }
 
+    let mut subscriptions = MappedArena::default();
+    for subscription in underlying_module_system.subscriptions.values().into_iter() {
+        subscriptions.add(&subscription.name.clone(), DenoSubscription(subscription));
+    }
+
     ModelDenoSystemWithInterceptors {
         underlying: DenoSubsystem {
             contexts: underlying_module_system.contexts,
             module_types: underlying_module_system.module_types,
             queries,
             mutations,
+            subscriptions,
             methods: underlying_module_system.methods,
             scripts: underlying_module_system.scripts,
             interceptors: underlying_module_system.interceptors,
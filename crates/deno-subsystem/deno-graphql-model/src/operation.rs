@@ -10,7 +10,7 @@
 use std::ops::Deref;
 
 use serde::{Deserialize, Serialize};
-use subsystem_model_util::operation::{ModuleMutation, ModuleQuery};
+use subsystem_model_util::operation::{ModuleMutation, ModuleQuery, ModuleSubscription};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct DenoQuery(pub ModuleQuery);
@@ -33,3 +33,14 @@ impl Deref for DenoMutation {
         &self.0
     }
 }
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DenoSubscription(pub ModuleSubscription);
+
+impl Deref for DenoSubscription {
+    type Target = ModuleSubscription;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
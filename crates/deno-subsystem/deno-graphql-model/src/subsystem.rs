@@ -24,7 +24,7 @@ use super::module::Script;
 use crate::{
     interceptor::Interceptor,
     module::ModuleMethod,
-    operation::{DenoMutation, DenoQuery},
+    operation::{DenoMutation, DenoQuery, DenoSubscription},
     types::ModuleType,
 };
 
@@ -39,6 +39,9 @@ pub struct DenoSubsystem {
     // mutation related
     pub mutations: MappedArena<DenoMutation>,
 
+    // subscription related
+    pub subscriptions: MappedArena<DenoSubscription>,
+
     // module related
     pub methods: SerializableSlab<ModuleMethod>,
     pub scripts: SerializableSlab<Script>,
@@ -60,6 +63,13 @@ impl DenoSubsystem {
             .collect()
     }
 
+    pub fn schema_subscriptions(&self) -> Vec<FieldDefinition> {
+        self.subscriptions
+            .iter()
+            .map(|(_, subscription)| subscription.field_definition(self))
+            .collect()
+    }
+
     pub fn schema_types(&self) -> Vec<TypeDefinition> {
         self.module_types
             .iter()
@@ -0,0 +1,110 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! What the result of a query depends on for the resolver cache (see
+//! [core_resolver::resolver_cache]).
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use common::{
+    context::{ContextExtractionError, RequestContext},
+    value::Val,
+};
+use core_model::{access::CommonAccessPrimitiveExpression, context_type::ContextSelection};
+use core_resolver::{context_extractor::ContextExtractor, resolver_cache::QueryDependencies};
+use deno_graphql_model::{
+    access::{Access, ModuleAccessPrimitiveExpression},
+    module::ModuleMethod,
+    subsystem::DenoSubsystem,
+    types::{ModuleCompositeType, ModuleOperationReturnType, ModuleTypeKind},
+};
+
+/// The dependencies of a query implemented by the given method: the values of the context fields
+/// referenced by the access rules of the method (and its return type) along with the values of the
+/// contexts injected into the method. Since Exograph can't tell which tables the method reads (if
+/// any), the cached result only expires after its ttl.
+///
+/// Returns `None` if a shim (such as `Exograph`) is injected into the method, since the method may
+/// then execute queries that depend on more than we can tell.
+pub(crate) async fn query_dependencies<'a>(
+    system: &DenoSubsystem,
+    method: &ModuleMethod,
+    request_context: &'a RequestContext<'a>,
+) -> Result<Option<QueryDependencies>, ContextExtractionError> {
+    let mut context_selections = vec![];
+    let mut add_access = |access: &Access| {
+        access.value.visit_primitives(&mut |primitive| {
+            let ModuleAccessPrimitiveExpression::Common(
+                CommonAccessPrimitiveExpression::ContextSelection(selection),
+            ) = primitive
+            else {
+                return;
+            };
+            context_selections.push((selection.context_name.clone(), selection.path.0.clone()));
+        })
+    };
+
+    add_access(&method.access);
+    if let ModuleOperationReturnType::Own(return_type) = &method.return_type
+        && let ModuleTypeKind::Composite(ModuleCompositeType { access, .. }) =
+            &return_type.typ(&system.module_types).kind
+    {
+        add_access(access);
+    }
+
+    let mut injected_contexts = BTreeSet::new();
+    for argument in method
+        .arguments
+        .iter()
+        .filter(|argument| argument.is_injected)
+    {
+        let arg_type = &system.module_types[*argument.type_id.innermost()];
+
+        if system
+            .contexts
+            .iter()
+            .any(|(_, context)| context.name == arg_type.name)
+        {
+            injected_contexts.insert(arg_type.name.clone());
+        } else {
+            return Ok(None);
+        }
+    }
+
+    let mut context = BTreeMap::new();
+
+    for context_name in injected_contexts {
+        let value = system
+            .extract_context(request_context, &context_name)
+            .await?;
+        context.insert(context_name, value.unwrap_or(Val::Null));
+    }
+
+    // We depend on the whole field even if the selection picks a part of it (such as
+    // `AuthContext.roles.contains("admin")`)
+    for (context_name, field_name) in context_selections.into_iter().collect::<BTreeSet<_>>() {
+        let selection = ContextSelection {
+            context_name: context_name.clone(),
+            path: (field_name.clone(), vec![]),
+        };
+        let value = system
+            .extract_context_selection(request_context, &selection)
+            .await?;
+
+        context.insert(
+            format!("{context_name}.{field_name}"),
+            value.cloned().unwrap_or(Val::Null),
+        );
+    }
+
+    Ok(Some(QueryDependencies {
+        context,
+        tables: BTreeSet::new(),
+    }))
+}
@@ -32,10 +32,10 @@ use futures::StreamExt;
 use crate::{
     deno_execution_error::DenoExecutionError,
     exo_execution::{ExoCallbackProcessor, ExographMethodResponse},
-    exograph_ops::InterceptedOperationInfo,
+    exograph_ops::{DenoCallContext, SubscriptionSender},
     resolver::DenoSubsystemGraphQLResolver,
 };
-use std::collections::HashMap;
+use deno_core_resolver::module_arg::module_arg;
 
 pub struct DenoOperation<'a> {
     pub method: &'a ModuleMethod,
@@ -53,7 +53,26 @@ impl DenoOperation<'_> {
             return Err(DenoExecutionError::Authorization);
         }
 
-        self.resolve_deno().await
+        self.resolve_deno(DenoCallContext::default()).await
+    }
+
+    /// Execute a subscription method, publishing each value it yields to `sender`. Completes when
+    /// the method's async iterator is exhausted (or the subscriber goes away).
+    pub async fn execute_subscription(
+        &self,
+        sender: SubscriptionSender,
+    ) -> Result<QueryResponse, DenoExecutionError> {
+        let access_predicate = self.compute_module_access_predicate().await?;
+
+        if !access_predicate {
+            return Err(DenoExecutionError::Authorization);
+        }
+
+        self.resolve_deno(DenoCallContext {
+            subscription_sender: Some(sender),
+            ..Default::default()
+        })
+        .await
     }
 
     async fn compute_module_access_predicate(&self) -> Result<bool, AccessSolverError> {
@@ -90,7 +109,10 @@ impl DenoOperation<'_> {
         }
     }
 
-    async fn resolve_deno(&self) -> Result<QueryResponse, DenoExecutionError> {
+    async fn resolve_deno(
+        &self,
+        call_context: DenoCallContext,
+    ) -> Result<QueryResponse, DenoExecutionError> {
         let subsystem = &self.subsystem();
         let script = &subsystem.scripts[self.method.script];
 
@@ -106,8 +128,6 @@ impl DenoOperation<'_> {
 
         let deserialized: DenoScriptDefn = serde_json::from_slice(&script.script).unwrap();
 
-        let call_context: Option<InterceptedOperationInfo> = None;
-
         let (result, response) = self
             .subsystem_resolver
             .executor
@@ -151,16 +171,6 @@ pub async fn construct_arg_sequence<'a>(
     system: &'a DenoSubsystem,
     request_context: &'a RequestContext<'a>,
 ) -> Result<Vec<Arg>, DenoExecutionError> {
-    let mapped_args: HashMap<String, serde_json::Value> = field_args
-        .iter()
-        .map(|(module_name, module_value)| {
-            (
-                module_name.as_str().to_owned(),
-                module_value.clone().try_into().unwrap(),
-            )
-        })
-        .collect::<HashMap<_, _>>();
-
     futures::stream::iter(args.iter())
         .then(|arg| async {
             if arg.is_injected {
@@ -191,9 +201,10 @@ pub async fn construct_arg_sequence<'a>(
                     // not a context, assume it is a provided shim by the Deno executor
                     Ok(Arg::Shim(arg_type.name.clone()))
                 }
-            } else if let Some(val) = mapped_args.get(&arg.name) {
+            } else if let Some(val) = field_args.get(&arg.name) {
                 // regular argument
-                Ok(Arg::Serde(val.clone()))
+                module_arg(val, *arg.type_id.innermost(), &system.module_types)
+                    .map_err(|_| DenoExecutionError::InvalidArgument(arg.name.clone()))
             } else {
                 // Per GraphQL spec 6.4.1 (Coercing Field Arguments), if a
                 // nullable argument with no provided value and no default is
@@ -21,7 +21,7 @@ use crate::{
     deno_execution_error::DenoExecutionError,
     deno_operation::construct_arg_sequence,
     exo_execution::{ExoCallbackProcessor, ExographMethodResponse},
-    exograph_ops::InterceptedOperationInfo,
+    exograph_ops::{DenoCallContext, InterceptedOperationInfo},
     resolver::DenoSubsystemGraphQLResolver,
 };
 
@@ -58,10 +58,13 @@ pub async fn execute_interceptor<'a>(
             deserialized,
             &interceptor.method_name,
             arg_sequence,
-            Some(InterceptedOperationInfo {
-                name: intercepted_operation.operation().name.to_string(),
-                query: operation_to_value(intercepted_operation.operation()),
-            }),
+            DenoCallContext {
+                intercepted_operation: Some(InterceptedOperationInfo {
+                    name: intercepted_operation.operation().name.to_string(),
+                    query: operation_to_value(intercepted_operation.operation()),
+                }),
+                ..Default::default()
+            },
             callback_processor,
         )
         .await
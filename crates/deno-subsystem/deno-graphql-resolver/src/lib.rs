@@ -15,6 +15,7 @@ pub use deno_core_resolver::{ExoDenoExecutorPool, exo_config};
 pub use resolver::DenoSubsystemGraphQLResolver;
 
 mod access_solver;
+mod cache_dependencies;
 mod deno_operation;
 mod interceptor_execution;
 pub mod resolver;
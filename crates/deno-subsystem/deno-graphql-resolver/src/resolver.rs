@@ -8,14 +8,17 @@
 // by the Apache License, Version 2.0.
 
 use async_graphql_parser::types::{FieldDefinition, OperationType, TypeDefinition};
+use async_stream::stream;
 use async_trait::async_trait;
+use futures::{FutureExt, future::Either};
 
 use common::context::RequestContext;
 use core_model::mapped_arena::SerializableSlabIndex;
 use core_plugin_shared::interception::InterceptorIndex;
 use core_resolver::{
     InterceptedOperation, QueryResponse, QueryResponseBody, exograph_execute_query,
-    plugin::{SubsystemGraphQLResolver, SubsystemResolutionError},
+    plugin::{SubscriptionStream, SubsystemGraphQLResolver, SubsystemResolutionError},
+    resolver_cache::QueryDependencies,
     system_resolver::GraphQLSystemResolver,
     validation::field::ValidatedField,
 };
@@ -23,6 +26,7 @@ use deno_graphql_model::{module::ModuleMethod, subsystem::DenoSubsystem};
 
 use super::deno_operation::DenoOperation;
 use crate::ExoDenoExecutorPool;
+use crate::cache_dependencies::query_dependencies;
 use crate::deno_execution_error::DenoExecutionError;
 
 pub struct DenoSubsystemGraphQLResolver {
@@ -81,9 +85,8 @@ impl SubsystemGraphQLResolver for DenoSubsystemGraphQLResolver {
                     })
                 })
             }
-            OperationType::Subscription => Some(Err(DenoExecutionError::Generic(
-                "Subscriptions are not supported".to_string(),
-            ))),
+            // Subscriptions go through `resolve_subscription`
+            OperationType::Subscription => None,
         };
 
         match deno_operation {
@@ -93,6 +96,94 @@ impl SubsystemGraphQLResolver for DenoSubsystemGraphQLResolver {
         }
     }
 
+    async fn resolve_subscription<'a>(
+        &'a self,
+        field: &'a ValidatedField,
+        request_context: &'a RequestContext<'a>,
+        system_resolver: &'a GraphQLSystemResolver,
+    ) -> Result<Option<SubscriptionStream<'a>>, SubsystemResolutionError> {
+        let Some(method_id) = self
+            .subsystem
+            .subscriptions
+            .get_by_key(&field.name)
+            .and_then(|subscription| subscription.method_id)
+        else {
+            return Ok(None);
+        };
+
+        // Unlike queries and mutations, we don't ensure a transaction here, since a subscription
+        // may run for as long as the client stays subscribed.
+        let deno_operation = create_deno_operation(
+            &self.subsystem,
+            method_id,
+            field,
+            request_context,
+            self,
+            system_resolver,
+        )?;
+
+        let responses = stream! {
+            let (sender, mut receiver) = tokio::sync::mpsc::channel(1);
+            let mut execution = deno_operation.execute_subscription(sender).boxed();
+
+            loop {
+                let next = tokio::select! {
+                    Some(value) = receiver.recv() => Either::Left(value),
+                    result = &mut execution => Either::Right(result),
+                };
+
+                match next {
+                    Either::Left(value) => {
+                        yield Ok(QueryResponse {
+                            body: QueryResponseBody::Json(value),
+                            headers: vec![],
+                        });
+                    }
+                    Either::Right(result) => {
+                        // The method has returned, but may have published values we haven't
+                        // delivered yet
+                        while let Ok(value) = receiver.try_recv() {
+                            yield Ok(QueryResponse {
+                                body: QueryResponseBody::Json(value),
+                                headers: vec![],
+                            });
+                        }
+
+                        if let Err(e) = result {
+                            yield Err(e.into());
+                        }
+                        break;
+                    }
+                }
+            }
+        };
+
+        Ok(Some(Box::pin(responses)))
+    }
+
+    async fn query_dependencies<'a>(
+        &'a self,
+        operation: &'a ValidatedField,
+        request_context: &'a RequestContext<'a>,
+    ) -> Result<Option<QueryDependencies>, SubsystemResolutionError> {
+        let Some(method_id) = self
+            .subsystem
+            .queries
+            .get_by_key(&operation.name)
+            .and_then(|query| query.method_id)
+        else {
+            return Ok(None);
+        };
+
+        query_dependencies(
+            &self.subsystem,
+            &self.subsystem.methods[method_id],
+            request_context,
+        )
+        .await
+        .map_err(SubsystemResolutionError::ContextExtraction)
+    }
+
     async fn invoke_interceptor<'a>(
         &'a self,
         interceptor_index: InterceptorIndex,
@@ -132,6 +223,10 @@ impl SubsystemGraphQLResolver for DenoSubsystemGraphQLResolver {
         self.subsystem.schema_mutations()
     }
 
+    fn schema_subscriptions(&self) -> Vec<FieldDefinition> {
+        self.subsystem.schema_subscriptions()
+    }
+
     fn schema_types(&self) -> Vec<TypeDefinition> {
         self.subsystem.schema_types()
     }
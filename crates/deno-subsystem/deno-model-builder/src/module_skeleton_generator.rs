@@ -17,7 +17,7 @@ use core_model_builder::builder::resolved_builder::compute_fragment_fields;
 use core_model_builder::builder::system_builder::BaseModelSystem;
 use core_model_builder::typechecker::typ::TypecheckedSystem;
 use core_model_builder::{
-    ast::ast_types::{AstArgument, AstFieldType, AstModel, AstModule},
+    ast::ast_types::{AstArgument, AstFieldType, AstMethodType, AstModel, AstModule},
     error::ModelBuildingError,
     typechecker::Typed,
 };
@@ -127,6 +127,7 @@ pub fn generate_module_skeleton(
             &method.name,
             &method.arguments,
             Some(&method.return_type),
+            method.typ == AstMethodType::Subscription,
             &mut file,
             is_typescript,
         )?;
@@ -137,6 +138,7 @@ pub fn generate_module_skeleton(
             &interceptor.name,
             &interceptor.arguments,
             None,
+            false,
             &mut file,
             is_typescript,
         )?;
@@ -470,12 +472,18 @@ fn generate_method_skeleton(
     name: &str,
     arguments: &[AstArgument<Typed>],
     return_type: Option<&dyn TypeScriptType>,
+    is_subscription: bool,
     out_file: &mut File,
     is_typescript: bool,
 ) -> Result<(), ModelBuildingError> {
     out_file.write_all(" \n".as_bytes())?;
     // We put `async` in a comment as an indication to the user that it is okay to have async functions
-    out_file.write_all("export async function ".as_bytes())?;
+    // (subscriptions yield their values, so they are async generator functions)
+    if is_subscription {
+        out_file.write_all("export async function* ".as_bytes())?;
+    } else {
+        out_file.write_all("export async function ".as_bytes())?;
+    }
     out_file.write_all(name.as_bytes())?;
     out_file.write_all("(".as_bytes())?;
 
@@ -484,7 +492,11 @@ fn generate_method_skeleton(
     out_file.write_all(")".as_bytes())?;
 
     if is_typescript && let Some(return_type) = return_type {
-        out_file.write_all(": Promise<".as_bytes())?;
+        if is_subscription {
+            out_file.write_all(": AsyncGenerator<".as_bytes())?;
+        } else {
+            out_file.write_all(": Promise<".as_bytes())?;
+        }
         out_file.write_all(return_type.typescript_type().as_bytes())?;
         out_file.write_all(">".as_bytes())?;
     }
@@ -596,6 +608,8 @@ fn typescript_base_type(exo_type_name: &str) -> String {
         "Boolean" => "boolean".to_string(),
         "DateTime" => "Date".to_string(),
         "Uuid" => "string".to_string(),
+        // Decoded from base64 in JSON requests, or read from a file uploaded with a multipart request
+        "Blob" => "Uint8Array".to_string(),
         "Exograph" => "Exograph".to_string(),
         "ExographPriv" => "ExographPriv".to_string(),
         t => t.to_string(),
@@ -977,6 +991,7 @@ mod tests {
             &mocked_method.name,
             &mocked_method.arguments,
             Some(&mocked_method.return_type),
+            false,
             &mut temp_file,
             true,
         )
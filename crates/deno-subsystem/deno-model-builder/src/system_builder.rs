@@ -218,6 +218,7 @@ pub async fn build(
 
     if underlying.queries.is_empty()
         && underlying.mutations.is_empty()
+        && underlying.subscriptions.is_empty()
         && underlying.interceptors.is_empty()
     {
         return Ok(None);
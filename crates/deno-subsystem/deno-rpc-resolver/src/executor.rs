@@ -12,6 +12,7 @@
 use async_trait::async_trait;
 
 use common::context::RequestContext;
+use common::value::Val;
 use core_resolver::exograph_execute_query;
 use core_resolver::system_resolver::{ExographExecuteQueryFn, GraphQLSystemResolver};
 
@@ -31,7 +32,8 @@ use {
 // Re-use the executor pool type and config from deno-core-resolver
 pub use deno_core_resolver::ExoDenoExecutorPool;
 use deno_core_resolver::exo_execution::{ExoCallbackProcessor, ExographMethodResponse};
-use deno_core_resolver::exograph_ops::InterceptedOperationInfo;
+use deno_core_resolver::exograph_ops::DenoCallContext;
+use deno_core_resolver::module_arg::module_arg;
 
 /// Deno executor for RPC method calls.
 pub struct DenoRpcExecutor {
@@ -53,14 +55,26 @@ impl ModuleRpcExecutor for DenoRpcExecutor {
             ModuleRpcExecutionError::Internal(format!("Script deserialization: {e}"))
         })?;
 
-        // Convert ModuleArg to exo-deno Arg
+        // Convert ModuleArg to exo-deno Arg (with a `Uint8Array` for each `Blob`, as in GraphQL)
         let deno_args: Vec<Arg> = args
             .into_iter()
-            .map(|arg| match arg {
-                ModuleArg::Value(v) => Arg::Serde(v),
-                ModuleArg::Shim(name) => Arg::Shim(name),
+            .zip(&method.arguments)
+            .map(|(arg, argument)| match arg {
+                ModuleArg::Value(v) if !argument.is_injected => module_arg(
+                    &Val::from(v),
+                    *argument.type_id.innermost(),
+                    &subsystem.module_types,
+                )
+                .map_err(|_| {
+                    ModuleRpcExecutionError::UserDisplayError(format!(
+                        "Invalid argument {}",
+                        argument.name
+                    ))
+                }),
+                ModuleArg::Value(v) => Ok(Arg::Serde(v)),
+                ModuleArg::Shim(name) => Ok(Arg::Shim(name)),
             })
-            .collect();
+            .collect::<Result<_, _>>()?;
 
         // Use the same exograph_execute_query! macro as the GraphQL resolver
         let exograph_execute_query: &ExographExecuteQueryFn =
@@ -71,7 +85,7 @@ impl ModuleRpcExecutor for DenoRpcExecutor {
             exograph_proceed: None,
         };
 
-        let call_context: Option<InterceptedOperationInfo> = None;
+        let call_context = DenoCallContext::default();
 
         let (result, response) = self
             .executor
@@ -15,11 +15,13 @@ use common::env_const::get_graphql_http_path;
 use common::env_const::is_production;
 use common::http::{Headers, RequestHead, RequestPayload, ResponseBody, ResponsePayload};
 use common::router::Router;
+use core_model::types::{CacheHint, CacheScope};
 use core_plugin_shared::interception::InterceptionMap;
 use core_plugin_shared::trusted_documents::TrustedDocumentEnforcement;
 use core_plugin_shared::trusted_documents::TrustedDocuments;
 use core_resolver::QueryResponse;
 use core_resolver::introspection::definition::schema::Schema;
+use core_resolver::persisted_queries::PersistedQueryStore;
 use core_resolver::plugin::SubsystemGraphQLResolver;
 use core_router::SystemLoadingError;
 use http::StatusCode;
@@ -33,14 +35,25 @@ use common::operation_payload::OperationsPayload;
 use core_resolver::QueryResponseBody;
 use core_resolver::system_resolver::GraphQLSystemResolver;
 use core_resolver::system_resolver::{RequestError, SystemResolutionError};
+use futures::{Stream, StreamExt};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
 
 use exo_env::Environment;
 
-use crate::system_loader::SystemLoader;
+use crate::multipart::{self, UploadLimits};
+use crate::system_loader::{DEFAULT_MAX_BATCH_SIZE, SystemLoader, max_batch_size, upload_limits};
+
+/// The header to run the operations of a batch in one transaction (see [GraphQLRouter::route_batch])
+const BATCH_TRANSACTION_HEADER: &str = "_exo_batch_transaction";
 
 pub struct GraphQLRouter {
     resolver: Arc<GraphQLSystemResolver>,
     env: Arc<dyn Environment>,
+    /// The maximum number of operations in a batch (see [GraphQLRouter::route_batch])
+    max_batch_size: usize,
+    /// The limits on files uploaded with a multipart request (see [multipart])
+    upload_limits: UploadLimits,
 }
 
 impl GraphQLRouter {
@@ -48,14 +61,34 @@ impl GraphQLRouter {
         Self {
             resolver: Arc::new(resolver),
             env,
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+            upload_limits: UploadLimits::default(),
+        }
+    }
+
+    pub fn with_max_batch_size(self, max_batch_size: usize) -> Self {
+        Self {
+            max_batch_size,
+            ..self
+        }
+    }
+
+    pub fn with_upload_limits(self, upload_limits: UploadLimits) -> Self {
+        Self {
+            upload_limits,
+            ..self
         }
     }
 
     fn suitable(&self, request_head: &(dyn RequestHead + Sync)) -> bool {
         request_head.get_path() == get_graphql_http_path(self.env.as_ref())
-            && request_head.get_method() == http::Method::POST
+            && matches!(
+                request_head.get_method(),
+                http::Method::POST | http::Method::GET
+            )
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn from_resolvers(
         graphql_resolvers: Vec<Arc<dyn SubsystemGraphQLResolver + Send + Sync>>,
         introspection_resolver: Option<Arc<dyn SubsystemGraphQLResolver + Send + Sync>>,
@@ -63,6 +96,7 @@ impl GraphQLRouter {
         query_interception_map: Arc<InterceptionMap>,
         mutation_interception_map: Arc<InterceptionMap>,
         trusted_documents: TrustedDocuments,
+        persisted_query_store: Option<Arc<dyn PersistedQueryStore>>,
         env: Arc<dyn Environment>,
     ) -> Result<Self, SystemLoadingError> {
         let graphql_resolver = SystemLoader::create_system_resolver(
@@ -71,16 +105,215 @@ impl GraphQLRouter {
             query_interception_map,
             mutation_interception_map,
             trusted_documents,
+            persisted_query_store,
             env.clone(),
             schema,
         )?;
 
-        Ok(Self::new(graphql_resolver, env))
+        let max_batch_size = max_batch_size(env.as_ref())?;
+        let upload_limits = upload_limits(env.as_ref())?;
+
+        Ok(Self::new(graphql_resolver, env)
+            .with_max_batch_size(max_batch_size)
+            .with_upload_limits(upload_limits))
     }
 
     pub fn resolver(&self) -> Arc<GraphQLSystemResolver> {
         self.resolver.clone()
     }
+
+    /// Resolve a query sent with GET (with the operation in the URL parameters). Unlike a POST
+    /// request, the response may be cached (see [query_response]).
+    async fn route_query(
+        &self,
+        request_context: &RequestContext<'_>,
+        trusted_document_enforcement: TrustedDocumentEnforcement,
+    ) -> ResponsePayload {
+        let response = resolve_query_in_memory(
+            &self.resolver,
+            trusted_document_enforcement,
+            request_context,
+        )
+        .await;
+
+        query_response(response, request_context.get_head()).await
+    }
+
+    /// Resolve a batch of operations sent as a JSON array (as Apollo's batch link does), responding
+    /// with the array of their responses. The operations share the request context, so, for
+    /// example, the JWT is validated only once for the whole batch.
+    ///
+    /// Each operation runs in its own transaction, unless the request has the
+    /// `_exo_batch_transaction: true` header, in which case all operations run in one transaction
+    /// that commits only if every operation succeeds (see [resolve_batch_in_memory]).
+    ///
+    /// Since each operation is limited on its own (by depth and cost), a batch may have at most
+    /// `max_batch_size` operations.
+    async fn route_batch(
+        &self,
+        operations_payloads: Vec<OperationsPayload>,
+        request_context: &RequestContext<'_>,
+        trusted_document_enforcement: TrustedDocumentEnforcement,
+    ) -> ResponsePayload {
+        if operations_payloads.is_empty() {
+            tracing::error!("Invalid batch request: no operations");
+            return bad_request();
+        }
+
+        if operations_payloads.len() > self.max_batch_size {
+            tracing::error!(
+                "Invalid batch request: {} operations (the limit is {})",
+                operations_payloads.len(),
+                self.max_batch_size
+            );
+            return bad_request();
+        }
+
+        let single_transaction = request_context
+            .get_head()
+            .get_header(BATCH_TRANSACTION_HEADER)
+            .is_some_and(|value| value == "true");
+
+        let responses = resolve_batch_in_memory(
+            operations_payloads,
+            &self.resolver,
+            trusted_document_enforcement,
+            request_context,
+            single_transaction,
+        )
+        .await;
+
+        let mut headers = Headers::from_vec(
+            responses
+                .iter()
+                .flatten()
+                .flat_map(|response| response.iter().flat_map(|(_, qr)| qr.headers.clone()))
+                .collect(),
+        );
+        headers.insert("content-type".into(), "application/json".into());
+
+        let mut responses_json = Vec::with_capacity(responses.len());
+        for response in responses {
+            responses_json.push(response_json(response).await);
+        }
+
+        ResponsePayload {
+            body: ResponseBody::Bytes(format!("[{}]", responses_json.join(", ")).into_bytes()),
+            headers,
+            status_code: StatusCode::OK,
+        }
+    }
+}
+
+/// The response to a query sent with GET. It carries a `Cache-Control` header following the
+/// `@cache` hints of the query (made `private` for a request with credentials, since the result
+/// may be specific to the user) and an `ETag`, and a request with a matching `If-None-Match` gets
+/// a `304 Not Modified` response. Since the credentials may change the result, every response
+/// varies by them.
+async fn query_response(
+    response: Result<(Vec<(String, QueryResponse)>, Option<CacheHint>), SystemResolutionError>,
+    request_head: &(dyn RequestHead + Send + Sync),
+) -> ResponsePayload {
+    let mut headers = Headers::new();
+    headers.insert("vary".into(), "Authorization, Cookie".into());
+
+    let (response, cache_hint) = match response {
+        Ok((response, cache_hint)) => (Ok(response), cache_hint),
+        Err(SystemResolutionError::RequestError(e)) => {
+            tracing::error!("Error while resolving request: {:?}", e);
+            return ResponsePayload {
+                body: ResponseBody::None,
+                headers,
+                status_code: StatusCode::BAD_REQUEST,
+            };
+        }
+        Err(e @ SystemResolutionError::NonQueryOperation) => {
+            headers.insert("content-type".into(), "application/json".into());
+            headers.insert("allow".into(), "POST".into());
+
+            return ResponsePayload {
+                body: ResponseBody::Bytes(response_json(Err(e)).await.into_bytes()),
+                headers,
+                status_code: StatusCode::METHOD_NOT_ALLOWED,
+            };
+        }
+        Err(e) => (Err(e), None),
+    };
+
+    if let Ok(ref response) = response {
+        for (key, value) in response.iter().flat_map(|(_, qr)| qr.headers.clone()) {
+            headers.insert(key, value);
+        }
+    }
+    headers.insert("content-type".into(), "application/json".into());
+
+    let cacheable = response.is_ok();
+    let body = response_json(response).await;
+
+    if !cacheable {
+        headers.insert("cache-control".into(), "no-store".into());
+
+        return ResponsePayload {
+            body: ResponseBody::Bytes(body.into_bytes()),
+            headers,
+            status_code: StatusCode::OK,
+        };
+    }
+
+    let has_credentials = [http::header::AUTHORIZATION, http::header::COOKIE]
+        .iter()
+        .any(|header| {
+            request_head
+                .get_header(header.as_str())
+                .is_some_and(|value| !value.trim().is_empty())
+        });
+
+    // Without a hint, the response may still be stored, but must be revalidated (which the
+    // ETag makes cheap)
+    let cache_control = match cache_hint {
+        Some(CacheHint { max_age, scope, .. }) => {
+            let scope = if has_credentials {
+                CacheScope::Private
+            } else {
+                scope
+            };
+            format!("{}, max-age={max_age}", scope.as_str().to_ascii_lowercase())
+        }
+        None => "no-cache".to_string(),
+    };
+    let etag = format!(
+        "\"{}\"",
+        base16ct::lower::encode_string(&Sha256::digest(body.as_bytes()))
+    );
+
+    headers.insert("cache-control".into(), cache_control);
+    headers.insert("etag".into(), etag.clone());
+
+    let not_modified = request_head
+        .get_header(http::header::IF_NONE_MATCH.as_str())
+        .is_some_and(|if_none_match| etag_matches(&if_none_match, &etag));
+
+    if not_modified {
+        ResponsePayload {
+            body: ResponseBody::None,
+            headers,
+            status_code: StatusCode::NOT_MODIFIED,
+        }
+    } else {
+        ResponsePayload {
+            body: ResponseBody::Bytes(body.into_bytes()),
+            headers,
+            status_code: StatusCode::OK,
+        }
+    }
+}
+
+/// Whether an `If-None-Match` header (`*` or a list of entity tags) matches the entity tag. As
+/// required for `If-None-Match`, the comparison ignores the weak marker.
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+    })
 }
 
 #[async_trait]
@@ -102,23 +335,36 @@ impl<'a> Router<RequestContext<'a>> for GraphQLRouter {
             return None;
         }
 
-        let playground_request = request_head
-            .get_header("_exo_playground")
-            .map(|value| value == "true")
-            .unwrap_or(false);
+        let trusted_document_enforcement =
+            trusted_document_enforcement(request_context, self.env.as_ref());
 
-        let is_production = is_production(self.env.as_ref());
-        let is_internal = request_context.is_internal();
+        if request_head.get_method() == http::Method::GET {
+            return Some(
+                self.route_query(request_context, trusted_document_enforcement)
+                    .await,
+            );
+        }
 
-        let should_enforce = !is_internal && (is_production || !playground_request);
-        let trusted_document_enforcement = if should_enforce {
-            TrustedDocumentEnforcement::Enforce
-        } else {
-            TrustedDocumentEnforcement::DoNotEnforce
+        let operations_payload = match read_operations(request_context, self.upload_limits).await {
+            Ok(Operations::Single(operations_payload)) => operations_payload,
+            Ok(Operations::Batch(operations_payloads)) => {
+                return Some(
+                    self.route_batch(
+                        operations_payloads,
+                        request_context,
+                        trusted_document_enforcement,
+                    )
+                    .await,
+                );
+            }
+            Err(e) => {
+                tracing::error!("Error while reading request: {:?}", e);
+                return Some(bad_request());
+            }
         };
 
-        let response = resolve_in_memory(
-            request_context,
+        let response = resolve_in_memory_for_payload(
+            operations_payload,
             &self.resolver,
             trusted_document_enforcement,
             request_context,
@@ -127,11 +373,7 @@ impl<'a> Router<RequestContext<'a>> for GraphQLRouter {
 
         if let Err(SystemResolutionError::RequestError(e)) = response {
             tracing::error!("Error while resolving request: {:?}", e);
-            return Some(ResponsePayload {
-                body: ResponseBody::None,
-                headers: Headers::new(),
-                status_code: StatusCode::BAD_REQUEST,
-            });
+            return Some(bad_request());
         }
 
         let mut headers = if let Ok(ref response) = response {
@@ -147,69 +389,7 @@ impl<'a> Router<RequestContext<'a>> for GraphQLRouter {
 
         headers.insert("content-type".into(), "application/json".into());
 
-        let stream = try_stream! {
-            macro_rules! report_position {
-                ($position:expr) => {
-                    let p: Pos = $position;
-
-                    yield Bytes::from_static(br#"{"line": "#);
-                    yield Bytes::from(p.line.to_string());
-                    yield Bytes::from_static(br#", "column": "#);
-                    yield Bytes::from(p.column.to_string());
-                    yield Bytes::from_static(br#"}"#);
-                };
-            }
-
-            macro_rules! report_positions {
-                ($positions:expr) => {
-                    let mut first = true;
-                    for p in $positions {
-                        if !first {
-                            yield Bytes::from_static(b", ");
-                        }
-                        first = false;
-                        report_position!(p);
-                    }
-                };
-            }
-
-            match response {
-                Ok(parts) => {
-                    let parts_len = parts.len();
-                    yield Bytes::from_static(br#"{"data": {"#);
-                    for (index, part) in parts.into_iter().enumerate() {
-                        yield Bytes::from_static(b"\"");
-                        yield Bytes::from(part.0);
-                        yield Bytes::from_static(br#"":"#);
-                        match part.1.body {
-                            QueryResponseBody::Json(value) => yield Bytes::from(value.to_string()),
-                            QueryResponseBody::Raw(Some(value)) => yield Bytes::from(value),
-                            QueryResponseBody::Raw(None) => yield Bytes::from_static(b"null"),
-                        };
-                        if index != parts_len - 1 {
-                            yield Bytes::from_static(b", ");
-                        }
-                    };
-                    yield Bytes::from_static(b"}}");
-                },
-                Err(err) => {
-                    yield Bytes::from_static(br#"{"errors": [{"message":""#);
-                    yield Bytes::from(
-                        err.user_error_message().to_string()
-                            .replace('\"', "")
-                            .replace('\n', "; ")
-                    );
-                    yield Bytes::from_static(br#"""#);
-                    if let SystemResolutionError::Validation(err) = err {
-                        yield Bytes::from_static(br#", "locations": ["#);
-                        report_positions!(err.positions());
-                        yield Bytes::from_static(br#"]"#);
-                    };
-                    yield Bytes::from_static(br#"}"#);
-                    yield Bytes::from_static(b"]}");
-                },
-            }
-        };
+        let stream = response_stream(response);
 
         Some(ResponsePayload {
             body: ResponseBody::Stream(Box::pin(stream)),
@@ -219,6 +399,165 @@ impl<'a> Router<RequestContext<'a>> for GraphQLRouter {
     }
 }
 
+/// The operations in the body of a POST request
+pub(crate) enum Operations {
+    Single(OperationsPayload),
+    /// A batch of operations (see [GraphQLRouter::route_batch])
+    Batch(Vec<OperationsPayload>),
+}
+
+impl Operations {
+    pub(crate) fn from_json(body: Value) -> Result<Self, serde_json::Error> {
+        match body {
+            Value::Array(operations) => operations
+                .into_iter()
+                .map(OperationsPayload::from_json)
+                .collect::<Result<Vec<_>, _>>()
+                .map(Operations::Batch),
+            body => OperationsPayload::from_json(body).map(Operations::Single),
+        }
+    }
+}
+
+/// Read the operations from a JSON body or, if the request uploads files, a multipart body (see
+/// [multipart])
+async fn read_operations(
+    request_context: &RequestContext<'_>,
+    upload_limits: UploadLimits,
+) -> Result<Operations, RequestError> {
+    let content_type = request_context.get_head().get_header("content-type");
+
+    match content_type {
+        Some(content_type) if multipart::is_multipart(&content_type) => {
+            multipart::check_preflight(request_context.get_head())?;
+
+            let body = request_context.take_body_stream().ok_or_else(|| {
+                RequestError::InvalidMultipartBody("Missing request body".to_string())
+            })?;
+            multipart::read_operations(&content_type, body, upload_limits).await
+        }
+        _ => Operations::from_json(request_context.take_body())
+            .map_err(RequestError::InvalidBodyJson),
+    }
+}
+
+fn bad_request() -> ResponsePayload {
+    ResponsePayload {
+        body: ResponseBody::None,
+        headers: Headers::new(),
+        status_code: StatusCode::BAD_REQUEST,
+    }
+}
+
+/// Whether to enforce trusted documents for the request. Requests from the playground (outside
+/// production) and internal requests may use any document.
+pub fn trusted_document_enforcement(
+    request_context: &RequestContext<'_>,
+    env: &dyn Environment,
+) -> TrustedDocumentEnforcement {
+    let playground_request = request_context
+        .get_head()
+        .get_header("_exo_playground")
+        .map(|value| value == "true")
+        .unwrap_or(false);
+
+    let is_production = is_production(env);
+    let is_internal = request_context.is_internal();
+
+    let should_enforce = !is_internal && (is_production || !playground_request);
+    if should_enforce {
+        TrustedDocumentEnforcement::Enforce
+    } else {
+        TrustedDocumentEnforcement::DoNotEnforce
+    }
+}
+
+/// Write the result of resolving operations as a GraphQL response (`{"data": ...}` or
+/// `{"errors": [...]}`)
+pub fn response_stream(
+    response: Result<Vec<(String, QueryResponse)>, SystemResolutionError>,
+) -> impl Stream<Item = Result<Bytes, std::io::Error>> + Send {
+    try_stream! {
+        macro_rules! report_position {
+            ($position:expr) => {
+                let p: Pos = $position;
+
+                yield Bytes::from_static(br#"{"line": "#);
+                yield Bytes::from(p.line.to_string());
+                yield Bytes::from_static(br#", "column": "#);
+                yield Bytes::from(p.column.to_string());
+                yield Bytes::from_static(br#"}"#);
+            };
+        }
+
+        macro_rules! report_positions {
+            ($positions:expr) => {
+                let mut first = true;
+                for p in $positions {
+                    if !first {
+                        yield Bytes::from_static(b", ");
+                    }
+                    first = false;
+                    report_position!(p);
+                }
+            };
+        }
+
+        match response {
+            Ok(parts) => {
+                let parts_len = parts.len();
+                yield Bytes::from_static(br#"{"data": {"#);
+                for (index, part) in parts.into_iter().enumerate() {
+                    yield Bytes::from_static(b"\"");
+                    yield Bytes::from(part.0);
+                    yield Bytes::from_static(br#"":"#);
+                    match part.1.body {
+                        QueryResponseBody::Json(value) => yield Bytes::from(value.to_string()),
+                        QueryResponseBody::Raw(Some(value)) => yield Bytes::from(value),
+                        QueryResponseBody::Raw(None) => yield Bytes::from_static(b"null"),
+                    };
+                    if index != parts_len - 1 {
+                        yield Bytes::from_static(b", ");
+                    }
+                };
+                yield Bytes::from_static(b"}}");
+            },
+            Err(err) => {
+                yield Bytes::from_static(br#"{"errors": [{"message":""#);
+                yield Bytes::from(
+                    err.user_error_message().to_string()
+                        .replace('\"', "")
+                        .replace('\n', "; ")
+                );
+                yield Bytes::from_static(br#"""#);
+                if let Some(extensions) = err.error_extensions() {
+                    yield Bytes::from_static(br#", "extensions": "#);
+                    yield Bytes::from(Value::Object(extensions).to_string());
+                }
+                if let SystemResolutionError::Validation(err) = err {
+                    yield Bytes::from_static(br#", "locations": ["#);
+                    report_positions!(err.positions());
+                    yield Bytes::from_static(br#"]"#);
+                };
+                yield Bytes::from_static(br#"}"#);
+                yield Bytes::from_static(b"]}");
+            },
+        }
+    }
+}
+
+/// Same as [response_stream], but collected into a string
+pub async fn response_json(
+    response: Result<Vec<(String, QueryResponse)>, SystemResolutionError>,
+) -> String {
+    let bytes: Vec<Bytes> = response_stream(response)
+        .filter_map(|chunk| async move { chunk.ok() })
+        .collect()
+        .await;
+
+    String::from_utf8_lossy(&bytes.concat()).into_owned()
+}
+
 #[instrument(
     name = "resolver::resolve_in_memory"
     skip(system_resolver, request, request_context)
@@ -243,6 +582,34 @@ pub async fn resolve_in_memory<'a>(
     .await
 }
 
+/// Resolve a query sent with GET (see [GraphQLSystemResolver::resolve_query_operations])
+pub async fn resolve_query_in_memory(
+    system_resolver: &GraphQLSystemResolver,
+    trusted_document_enforcement: TrustedDocumentEnforcement,
+    request_context: &RequestContext<'_>,
+) -> Result<(Vec<(String, QueryResponse)>, Option<CacheHint>), SystemResolutionError> {
+    let operations_payload = OperationsPayload::from_query_params(
+        &request_context.get_head().get_query(),
+    )
+    .map_err(|e| SystemResolutionError::RequestError(RequestError::InvalidQueryParameters(e)))?;
+
+    let response = system_resolver
+        .resolve_query_operations(
+            operations_payload,
+            request_context,
+            trusted_document_enforcement,
+        )
+        .await;
+
+    request_context
+        .finalize_transaction(response.is_ok())
+        .await
+        .map_err(|e| {
+            SystemResolutionError::Generic(format!("Error while finalizing transaction: {e}"))
+        })
+        .and(response)
+}
+
 pub async fn resolve_in_memory_for_payload(
     operations_payload: OperationsPayload,
     system_resolver: &GraphQLSystemResolver,
@@ -265,3 +632,553 @@ pub async fn resolve_in_memory_for_payload(
         })
         .and(response)
 }
+
+/// Resolve the operations of a batch in order (see [GraphQLRouter::route_batch]). With
+/// `single_transaction`, the operations run in one transaction: the first failure rolls it back
+/// (along with the operations that succeeded) and skips the rest of the batch.
+pub async fn resolve_batch_in_memory(
+    operations_payloads: Vec<OperationsPayload>,
+    system_resolver: &GraphQLSystemResolver,
+    trusted_document_enforcement: TrustedDocumentEnforcement,
+    request_context: &RequestContext<'_>,
+    single_transaction: bool,
+) -> Vec<Result<Vec<(String, QueryResponse)>, SystemResolutionError>> {
+    let finalization_error =
+        |e| SystemResolutionError::Generic(format!("Error while finalizing transaction: {e}"));
+
+    if single_transaction {
+        request_context.ensure_transaction().await;
+    }
+
+    let mut responses = Vec::with_capacity(operations_payloads.len());
+
+    for operations_payload in operations_payloads {
+        // The transaction will roll back anyway
+        if single_transaction && responses.iter().any(Result::is_err) {
+            responses.push(Err(SystemResolutionError::BatchSkipped));
+            continue;
+        }
+
+        let response = system_resolver
+            .resolve_operations(
+                operations_payload,
+                request_context,
+                trusted_document_enforcement,
+            )
+            .await;
+
+        let response = if single_transaction {
+            response
+        } else {
+            // Start afresh for the next operation
+            request_context
+                .reset_transaction(response.is_ok())
+                .await
+                .map_err(finalization_error)
+                .and(response)
+        };
+
+        responses.push(response);
+    }
+
+    if single_transaction {
+        let all_succeeded = responses.iter().all(Result::is_ok);
+        let finalized = request_context.finalize_transaction(all_succeeded).await;
+
+        // The operations that succeeded didn't commit either
+        if finalized.is_err() || !all_succeeded {
+            let finalization_message = finalized
+                .err()
+                .map(|e| format!("Error while finalizing transaction: {e}"));
+
+            for response in responses.iter_mut().filter(|response| response.is_ok()) {
+                *response = Err(match &finalization_message {
+                    Some(message) => SystemResolutionError::Generic(message.clone()),
+                    None => SystemResolutionError::BatchRolledBack,
+                });
+            }
+        }
+    }
+
+    responses
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use async_graphql_parser::{
+        parse_schema,
+        types::{FieldDefinition, OperationType, TypeDefinition, TypeKind, TypeSystemDefinition},
+    };
+    use common::http::{MemoryRequestHead, MemoryRequestPayload};
+    use common::router::PlainRequestPayload;
+    use core_plugin_shared::interception::InterceptorIndex;
+    use core_plugin_shared::profile::SchemaProfile;
+    use core_resolver::InterceptedOperation;
+    use core_resolver::plugin::SubsystemResolutionError;
+    use core_resolver::validation::field::ValidatedField;
+    use exo_env::MapEnvironment;
+    use serde_json::json;
+
+    use super::*;
+
+    fn request_head(method: http::Method, headers: &[(&str, &str)]) -> MemoryRequestHead {
+        MemoryRequestHead::new(
+            headers
+                .iter()
+                .map(|(key, value)| (key.to_string(), vec![value.to_string()]))
+                .collect(),
+            HashMap::new(),
+            method,
+            "/graphql".to_string(),
+            Value::Null,
+            None,
+        )
+    }
+
+    fn query_result(
+        cache_hint: Option<CacheHint>,
+    ) -> Result<(Vec<(String, QueryResponse)>, Option<CacheHint>), SystemResolutionError> {
+        Ok((
+            vec![(
+                "concerts".to_string(),
+                QueryResponse {
+                    body: QueryResponseBody::Json(json!([{ "id": 1 }])),
+                    headers: vec![],
+                },
+            )],
+            cache_hint,
+        ))
+    }
+
+    const PUBLIC_HINT: CacheHint = CacheHint {
+        max_age: 60,
+        scope: CacheScope::Public,
+        ttl: None,
+    };
+
+    fn body(response: &ResponsePayload) -> Option<&str> {
+        match &response.body {
+            ResponseBody::Bytes(bytes) => Some(std::str::from_utf8(bytes).unwrap()),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn cacheable_query_response() {
+        let response = query_response(
+            query_result(Some(PUBLIC_HINT)),
+            &request_head(http::Method::GET, &[]),
+        )
+        .await;
+
+        assert_eq!(response.status_code, StatusCode::OK);
+        assert_eq!(
+            body(&response),
+            Some(r#"{"data": {"concerts":[{"id":1}]}}"#)
+        );
+        assert_eq!(
+            response.headers.get("cache-control").as_deref(),
+            Some("public, max-age=60")
+        );
+        assert_eq!(
+            response.headers.get("vary").as_deref(),
+            Some("Authorization, Cookie")
+        );
+        assert!(response.headers.get("etag").is_some());
+
+        // Without a hint, the response must be revalidated
+        let response =
+            query_response(query_result(None), &request_head(http::Method::GET, &[])).await;
+        assert_eq!(
+            response.headers.get("cache-control").as_deref(),
+            Some("no-cache")
+        );
+    }
+
+    #[tokio::test]
+    async fn query_response_with_credentials_is_private() {
+        for header in [("Authorization", "Bearer token"), ("Cookie", "session=abc")] {
+            let response = query_response(
+                query_result(Some(PUBLIC_HINT)),
+                &request_head(http::Method::GET, &[header]),
+            )
+            .await;
+
+            assert_eq!(
+                response.headers.get("cache-control").as_deref(),
+                Some("private, max-age=60")
+            );
+            assert_eq!(
+                response.headers.get("vary").as_deref(),
+                Some("Authorization, Cookie")
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn query_response_not_modified() {
+        let response = query_response(
+            query_result(Some(PUBLIC_HINT)),
+            &request_head(http::Method::GET, &[]),
+        )
+        .await;
+        let etag = response.headers.get("etag").unwrap();
+
+        for if_none_match in [
+            etag.clone(),
+            format!("W/{etag}"),
+            format!("\"other\", {etag}"),
+        ] {
+            let response = query_response(
+                query_result(Some(PUBLIC_HINT)),
+                &request_head(
+                    http::Method::GET,
+                    &[("If-None-Match", if_none_match.as_str())],
+                ),
+            )
+            .await;
+
+            assert_eq!(response.status_code, StatusCode::NOT_MODIFIED);
+            assert_eq!(body(&response), None);
+            assert_eq!(response.headers.get("etag"), Some(etag.clone()));
+            assert_eq!(
+                response.headers.get("vary").as_deref(),
+                Some("Authorization, Cookie")
+            );
+        }
+
+        let response = query_response(
+            query_result(Some(PUBLIC_HINT)),
+            &request_head(http::Method::GET, &[("If-None-Match", "\"other\"")]),
+        )
+        .await;
+        assert_eq!(response.status_code, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn mutation_query_response() {
+        let response = query_response(
+            Err(SystemResolutionError::NonQueryOperation),
+            &request_head(http::Method::GET, &[]),
+        )
+        .await;
+
+        assert_eq!(response.status_code, StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(response.headers.get("allow").as_deref(), Some("POST"));
+        assert_eq!(
+            response.headers.get("vary").as_deref(),
+            Some("Authorization, Cookie")
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_query_response() {
+        let response = query_response(
+            Err(SystemResolutionError::Generic("Failed".to_string())),
+            &request_head(http::Method::GET, &[]),
+        )
+        .await;
+
+        assert_eq!(response.status_code, StatusCode::OK);
+        assert_eq!(
+            response.headers.get("cache-control").as_deref(),
+            Some("no-store")
+        );
+        assert!(response.headers.get("etag").is_none());
+    }
+
+    const TEST_SCHEMA: &str = r#"
+        scalar Int
+        scalar String
+
+        type Concert {
+            id: Int!
+            title: String!
+        }
+
+        type Query {
+            concerts: [Concert!]!
+        }
+
+        type Mutation {
+            createConcert(title: String!): Concert!
+            failConcert: Concert
+        }
+    "#;
+
+    /// Resolves the operations of [TEST_SCHEMA] (failing `failConcert`), recording the fields it
+    /// resolves
+    struct TestResolver {
+        resolved: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl TestResolver {
+        fn definitions() -> (
+            Vec<TypeDefinition>,
+            Vec<FieldDefinition>,
+            Vec<FieldDefinition>,
+        ) {
+            let mut types = vec![];
+            let mut queries = vec![];
+            let mut mutations = vec![];
+
+            for definition in parse_schema(TEST_SCHEMA).unwrap().definitions {
+                let TypeSystemDefinition::Type(type_definition) = definition else {
+                    continue;
+                };
+                let type_definition = type_definition.node;
+
+                let root_fields = match type_definition.name.node.as_str() {
+                    "Query" => &mut queries,
+                    "Mutation" => &mut mutations,
+                    _ => {
+                        types.push(type_definition);
+                        continue;
+                    }
+                };
+                if let TypeKind::Object(object) = type_definition.kind {
+                    root_fields.extend(object.fields.into_iter().map(|field| field.node));
+                }
+            }
+
+            (types, queries, mutations)
+        }
+    }
+
+    #[async_trait]
+    impl SubsystemGraphQLResolver for TestResolver {
+        fn id(&self) -> &'static str {
+            "test"
+        }
+
+        async fn resolve<'a>(
+            &'a self,
+            operation: &'a ValidatedField,
+            _operation_type: OperationType,
+            _request_context: &'a RequestContext,
+            _system_resolver: &'a GraphQLSystemResolver,
+        ) -> Result<Option<QueryResponse>, SubsystemResolutionError> {
+            self.resolved
+                .lock()
+                .unwrap()
+                .push(operation.name.to_string());
+
+            let body = match operation.name.as_str() {
+                "concerts" => json!([{ "id": 1 }]),
+                "createConcert" => json!({ "id": 2 }),
+                "failConcert" => {
+                    return Err(SubsystemResolutionError::UserDisplayError(
+                        "Failed".to_string(),
+                    ));
+                }
+                _ => return Ok(None),
+            };
+
+            Ok(Some(QueryResponse {
+                body: QueryResponseBody::Json(body),
+                headers: vec![],
+            }))
+        }
+
+        async fn invoke_interceptor<'a>(
+            &'a self,
+            _interceptor_index: InterceptorIndex,
+            _intercepted_operation: &'a InterceptedOperation,
+            _request_context: &'a RequestContext<'a>,
+            _system_resolver: &'a GraphQLSystemResolver,
+        ) -> Result<Option<QueryResponse>, SubsystemResolutionError> {
+            Err(SubsystemResolutionError::NoInterceptorFound)
+        }
+
+        fn schema_queries(&self) -> Vec<FieldDefinition> {
+            Self::definitions().1
+        }
+
+        fn schema_mutations(&self) -> Vec<FieldDefinition> {
+            Self::definitions().2
+        }
+
+        fn schema_types(&self) -> Vec<TypeDefinition> {
+            Self::definitions().0
+        }
+    }
+
+    struct TestRouter {}
+
+    #[async_trait]
+    impl<'request> Router<PlainRequestPayload<'request>> for TestRouter {
+        async fn route(&self, _request: &PlainRequestPayload<'request>) -> Option<ResponsePayload> {
+            None
+        }
+    }
+
+    /// Route a POST request with the body (and headers) through a [GraphQLRouter] for
+    /// [TEST_SCHEMA], returning the response (as its status code and JSON body) and the fields
+    /// resolved (in order)
+    async fn route_post(
+        body: Value,
+        headers: &[(&str, &str)],
+    ) -> (StatusCode, Option<Value>, Vec<String>) {
+        let resolved = Arc::new(Mutex::new(vec![]));
+        let resolvers: Vec<Arc<dyn SubsystemGraphQLResolver + Send + Sync>> =
+            vec![Arc::new(TestResolver {
+                resolved: resolved.clone(),
+            })];
+        let schema = Schema::new_from_resolvers(&resolvers, &SchemaProfile::all(), Arc::new(None));
+
+        let env = Arc::new(MapEnvironment::new());
+        let graphql_router = GraphQLRouter::new(
+            GraphQLSystemResolver::new(
+                resolvers,
+                Arc::new(InterceptionMap {
+                    map: Default::default(),
+                }),
+                Arc::new(InterceptionMap {
+                    map: Default::default(),
+                }),
+                TrustedDocuments::All(Default::default()),
+                Arc::new(schema),
+                env.clone(),
+                10,
+                10,
+            ),
+            env.clone(),
+        )
+        .with_max_batch_size(3);
+
+        let request = MemoryRequestPayload::new(body, request_head(http::Method::POST, headers));
+        let router = TestRouter {};
+        let request_context = RequestContext::new(&request, vec![], &router, &None, env.as_ref());
+
+        let response = graphql_router.route(&request_context).await.unwrap();
+        let body = match response.body {
+            ResponseBody::Bytes(bytes) => Some(serde_json::from_slice(&bytes).unwrap()),
+            _ => None,
+        };
+
+        let resolved = resolved.lock().unwrap().clone();
+        (response.status_code, body, resolved)
+    }
+
+    fn operation(query: &str) -> Value {
+        json!({ "query": query })
+    }
+
+    #[tokio::test]
+    async fn batch_responses() {
+        let (status_code, body, resolved) = route_post(
+            json!([
+                operation("{ concerts { id } }"),
+                operation(r#"mutation { createConcert(title: "A") { id } }"#),
+            ]),
+            &[],
+        )
+        .await;
+
+        assert_eq!(status_code, StatusCode::OK);
+        assert_eq!(
+            body,
+            Some(json!([
+                { "data": { "concerts": [{ "id": 1 }] } },
+                { "data": { "createConcert": { "id": 2 } } },
+            ]))
+        );
+        assert_eq!(resolved, vec!["concerts", "createConcert"]);
+    }
+
+    #[tokio::test]
+    async fn batch_with_per_operation_transactions() {
+        // A failure affects only its own operation
+        let (status_code, body, resolved) = route_post(
+            json!([
+                operation(r#"mutation { createConcert(title: "A") { id } }"#),
+                operation("mutation { failConcert { id } }"),
+                operation(r#"mutation { createConcert(title: "B") { id } }"#),
+            ]),
+            &[],
+        )
+        .await;
+
+        let body = body.unwrap();
+        assert_eq!(status_code, StatusCode::OK);
+        assert_eq!(body[0], json!({ "data": { "createConcert": { "id": 2 } } }));
+        assert_eq!(body[1]["errors"][0]["message"], json!("Failed"));
+        assert_eq!(body[2], json!({ "data": { "createConcert": { "id": 2 } } }));
+        assert_eq!(
+            resolved,
+            vec!["createConcert", "failConcert", "createConcert"]
+        );
+    }
+
+    #[tokio::test]
+    async fn batch_in_single_transaction() {
+        // The first failure rolls back the earlier operations and skips the rest
+        let (status_code, body, resolved) = route_post(
+            json!([
+                operation(r#"mutation { createConcert(title: "A") { id } }"#),
+                operation("mutation { failConcert { id } }"),
+                operation(r#"mutation { createConcert(title: "B") { id } }"#),
+            ]),
+            &[(BATCH_TRANSACTION_HEADER, "true")],
+        )
+        .await;
+
+        let body = body.unwrap();
+        assert_eq!(status_code, StatusCode::OK);
+        assert_eq!(
+            body[0]["errors"][0]["message"],
+            json!(SystemResolutionError::BatchRolledBack.to_string())
+        );
+        assert_eq!(body[1]["errors"][0]["message"], json!("Failed"));
+        assert_eq!(
+            body[2]["errors"][0]["message"],
+            json!(SystemResolutionError::BatchSkipped.to_string())
+        );
+        assert_eq!(resolved, vec!["createConcert", "failConcert"]);
+
+        // Without failures, every operation succeeds
+        let (_, body, _) = route_post(
+            json!([
+                operation(r#"mutation { createConcert(title: "A") { id } }"#),
+                operation(r#"mutation { createConcert(title: "B") { id } }"#),
+            ]),
+            &[(BATCH_TRANSACTION_HEADER, "true")],
+        )
+        .await;
+
+        assert_eq!(
+            body,
+            Some(json!([
+                { "data": { "createConcert": { "id": 2 } } },
+                { "data": { "createConcert": { "id": 2 } } },
+            ]))
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_batches() {
+        let (status_code, _, _) = route_post(json!([]), &[]).await;
+        assert_eq!(status_code, StatusCode::BAD_REQUEST);
+
+        // Over the limit of 3 operations
+        let (status_code, _, resolved) = route_post(
+            json!([
+                operation("{ concerts { id } }"),
+                operation("{ concerts { id } }"),
+                operation("{ concerts { id } }"),
+                operation("{ concerts { id } }"),
+            ]),
+            &[],
+        )
+        .await;
+        assert_eq!(status_code, StatusCode::BAD_REQUEST);
+        assert!(resolved.is_empty());
+
+        let (status_code, _, _) =
+            route_post(json!([operation("{ concerts { id } }"), 1]), &[]).await;
+        assert_eq!(status_code, StatusCode::BAD_REQUEST);
+    }
+}
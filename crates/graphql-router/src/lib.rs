@@ -13,8 +13,16 @@
 /// The `resolve` function is responsible for doing the work, using information
 /// extracted from an incoming request, and returning the response as a stream.
 mod graphql_router;
+mod multipart;
+mod subscription;
 mod system_loader;
+pub mod websocket;
 
 pub use graphql_router::GraphQLRouter;
 pub use graphql_router::resolve_in_memory_for_payload;
 pub use introspection_resolver::IntrospectionResolver;
+pub use multipart::UploadLimits;
+pub use subscription::{
+    MULTIPART_MIXED_CONTENT_TYPE, StreamingOperation, SubscriptionResponse, event_stream,
+    multipart_mixed, resolve_subscription, streaming_operation,
+};
@@ -0,0 +1,451 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Support for the GraphQL multipart request spec
+//! (https://github.com/jaydenseric/graphql-multipart-request-spec), which sends files along with
+//! operations in a `multipart/form-data` body.
+//!
+//! The body has the following parts (in this order):
+//! - `operations`: The operation (or a batch of operations) as JSON, with `null` in place of each file
+//! - `map`: A JSON object mapping the name of each file part to the paths of the variables it
+//!   takes the place of (such as `variables.file` or, in a batch, `0.variables.file`)
+//! - The file parts
+//!
+//! Since browsers send a `multipart/form-data` request across origins without a preflight, a
+//! request must have a header that a cross-origin form can't set (`Apollo-Require-Preflight` or
+//! `X-Apollo-Operation-Name`, as Apollo Server requires) to prevent cross-site request forgery.
+
+use std::collections::HashMap;
+
+use bytes::Bytes;
+use common::http::{RequestBodyStream, RequestHead};
+use common::operation_payload::Upload;
+use core_resolver::system_resolver::RequestError;
+use serde_json::Value;
+
+use crate::graphql_router::Operations;
+
+/// The headers (any of which) a multipart request must have
+const PREFLIGHT_HEADERS: [&str; 2] = ["apollo-require-preflight", "x-apollo-operation-name"];
+
+/// The size of the `operations` and `map` parts allowed in addition to the files
+const MAX_OPERATIONS_SIZE: u64 = 1024 * 1024;
+
+/// The limits on the files uploaded with a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UploadLimits {
+    /// The maximum size of a file (in bytes)
+    pub max_file_size: u64,
+    /// The maximum number of files
+    pub max_files: usize,
+}
+
+impl Default for UploadLimits {
+    fn default() -> Self {
+        Self {
+            max_file_size: 10 * 1024 * 1024,
+            max_files: 10,
+        }
+    }
+}
+
+pub(crate) fn is_multipart(content_type: &str) -> bool {
+    content_type.starts_with("multipart/form-data")
+}
+
+/// Check that the request couldn't have come from a cross-origin form (see the module docs)
+pub(crate) fn check_preflight(
+    request_head: &(dyn RequestHead + Send + Sync),
+) -> Result<(), RequestError> {
+    let has_preflight_header = PREFLIGHT_HEADERS.iter().any(|header| {
+        request_head
+            .get_header(header)
+            .is_some_and(|value| !value.is_empty())
+    });
+
+    if has_preflight_header {
+        Ok(())
+    } else {
+        Err(RequestError::InvalidMultipartBody(
+            "A multipart request must have the 'Apollo-Require-Preflight' header".to_string(),
+        ))
+    }
+}
+
+/// Read the operations in a multipart body, attaching the files to them as [Upload]s. Each file
+/// is attached as soon as its part is read, and a part that isn't in the map (or exceeds the
+/// limits) fails the request without being read.
+pub(crate) async fn read_operations(
+    content_type: &str,
+    body: RequestBodyStream,
+    limits: UploadLimits,
+) -> Result<Operations, RequestError> {
+    let boundary = multer::parse_boundary(content_type).map_err(invalid_body)?;
+    let constraints = multer::Constraints::new().size_limit(
+        multer::SizeLimit::new()
+            .per_field(limits.max_file_size)
+            .for_field("operations", MAX_OPERATIONS_SIZE)
+            .for_field("map", MAX_OPERATIONS_SIZE)
+            .whole_stream(
+                (limits.max_files as u64)
+                    .saturating_mul(limits.max_file_size)
+                    .saturating_add(2 * MAX_OPERATIONS_SIZE),
+            ),
+    );
+    let mut multipart = multer::Multipart::with_constraints(body, boundary, constraints);
+
+    // The `operations` and `map` parts come first
+    let mut operations = match multipart.next_field().await.map_err(invalid_body)? {
+        Some(field) if field.name() == Some("operations") => {
+            let content = field.bytes().await.map_err(invalid_body)?;
+            let operations =
+                serde_json::from_slice::<Value>(&content).map_err(RequestError::InvalidBodyJson)?;
+            Operations::from_json(operations).map_err(RequestError::InvalidBodyJson)?
+        }
+        _ => return Err(missing_part("operations")),
+    };
+
+    let mut map = match multipart.next_field().await.map_err(invalid_body)? {
+        Some(field) if field.name() == Some("map") => {
+            let content = field.bytes().await.map_err(invalid_body)?;
+            serde_json::from_slice::<HashMap<String, Vec<String>>>(&content)
+                .map_err(RequestError::InvalidBodyJson)?
+        }
+        _ => return Err(missing_part("map")),
+    };
+
+    if map.len() > limits.max_files {
+        return Err(RequestError::InvalidMultipartBody(format!(
+            "Too many files (the limit is {})",
+            limits.max_files
+        )));
+    }
+
+    while let Some(field) = multipart.next_field().await.map_err(invalid_body)? {
+        let paths = field
+            .name()
+            .and_then(|name| map.remove(name))
+            .ok_or_else(|| {
+                RequestError::InvalidMultipartBody(format!(
+                    "Unexpected part '{}'",
+                    field.name().unwrap_or_default()
+                ))
+            })?;
+
+        let content = field.bytes().await.map_err(invalid_body)?;
+
+        for path in paths {
+            attach_upload(&mut operations, &path, content.clone())?;
+        }
+    }
+
+    if let Some(file_name) = map.keys().next() {
+        return Err(missing_part(file_name));
+    }
+
+    Ok(operations)
+}
+
+/// Attach the file to the operation referred to by the path (such as `variables.file` or
+/// `0.variables.file`)
+fn attach_upload(
+    operations: &mut Operations,
+    path: &str,
+    content: Bytes,
+) -> Result<(), RequestError> {
+    let invalid_path = || RequestError::InvalidMultipartBody(format!("Invalid file path '{path}'"));
+
+    let mut segments = path.split('.');
+
+    let payload = match operations {
+        Operations::Single(payload) => payload,
+        Operations::Batch(payloads) => segments
+            .next()
+            .and_then(|index| index.parse::<usize>().ok())
+            .and_then(|index| payloads.get_mut(index))
+            .ok_or_else(invalid_path)?,
+    };
+
+    if segments.next() != Some("variables") {
+        return Err(invalid_path());
+    }
+
+    let path: Vec<String> = segments.map(str::to_string).collect();
+    if path.is_empty() {
+        return Err(invalid_path());
+    }
+
+    payload.uploads.push(Upload { path, content });
+
+    Ok(())
+}
+
+fn missing_part(name: &str) -> RequestError {
+    RequestError::InvalidMultipartBody(format!("Missing part '{name}'"))
+}
+
+fn invalid_body(error: multer::Error) -> RequestError {
+    RequestError::InvalidMultipartBody(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use common::http::MemoryRequestHead;
+    use serde_json::json;
+
+    use super::*;
+
+    const CONTENT_TYPE: &str = "multipart/form-data; boundary=boundary";
+
+    /// A multipart body with the parts (as name and content)
+    fn body(parts: &[(&str, &str)]) -> RequestBodyStream {
+        let mut body = String::new();
+        for (name, content) in parts {
+            body.push_str(&format!(
+                "--boundary\r\nContent-Disposition: form-data; name=\"{name}\"\r\n\r\n{content}\r\n"
+            ));
+        }
+        body.push_str("--boundary--\r\n");
+
+        Box::pin(futures::stream::iter(vec![Ok::<_, std::io::Error>(
+            Bytes::from(body),
+        )]))
+    }
+
+    /// The uploads of each operation (as the path and content)
+    fn uploads(operations: &Operations) -> Vec<Vec<(Vec<String>, Bytes)>> {
+        let payloads = match operations {
+            Operations::Single(payload) => vec![payload],
+            Operations::Batch(payloads) => payloads.iter().collect(),
+        };
+
+        payloads
+            .into_iter()
+            .map(|payload| {
+                payload
+                    .uploads
+                    .iter()
+                    .map(|upload| (upload.path.clone(), upload.content.clone()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn path(segments: &[&str]) -> Vec<String> {
+        segments.iter().map(|segment| segment.to_string()).collect()
+    }
+
+    fn error_message(result: Result<Operations, RequestError>) -> String {
+        match result {
+            Ok(_) => panic!("Expected an error"),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn single_operation() {
+        let operations = read_operations(
+            CONTENT_TYPE,
+            body(&[
+                (
+                    "operations",
+                    r#"{ "query": "mutation($files: [Blob!]!) { upload(files: $files) }", "variables": { "files": [null, null] } }"#,
+                ),
+                ("map", r#"{ "0": ["variables.files.0"], "1": ["variables.files.1"] }"#),
+                ("0", "first"),
+                ("1", "second"),
+            ]),
+            UploadLimits::default(),
+        )
+        .await
+        .unwrap();
+
+        assert!(matches!(operations, Operations::Single(_)));
+        assert_eq!(
+            uploads(&operations),
+            vec![vec![
+                (path(&["files", "0"]), Bytes::from("first")),
+                (path(&["files", "1"]), Bytes::from("second")),
+            ]]
+        );
+    }
+
+    #[tokio::test]
+    async fn batch_operations() {
+        // A file may take the place of variables in several operations
+        let operations = read_operations(
+            CONTENT_TYPE,
+            body(&[
+                (
+                    "operations",
+                    r#"[{ "query": "mutation($file: Blob!) { upload(file: $file) }", "variables": { "file": null } }, { "query": "mutation($data: Input!) { create(data: $data) }", "variables": { "data": { "content": null } } }]"#,
+                ),
+                ("map", r#"{ "0": ["0.variables.file", "1.variables.data.content"] }"#),
+                ("0", "content"),
+            ]),
+            UploadLimits::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            uploads(&operations),
+            vec![
+                vec![(path(&["file"]), Bytes::from("content"))],
+                vec![(path(&["data", "content"]), Bytes::from("content"))],
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_parts() {
+        let operations = r#"{ "query": "mutation($file: Blob!) { upload(file: $file) }", "variables": { "file": null } }"#;
+
+        let result = read_operations(
+            CONTENT_TYPE,
+            body(&[("map", r#"{ "0": ["variables.file"] }"#), ("0", "content")]),
+            UploadLimits::default(),
+        )
+        .await;
+        assert_eq!(
+            error_message(result),
+            "Invalid multipart body: Missing part 'operations'"
+        );
+
+        let result = read_operations(
+            CONTENT_TYPE,
+            body(&[("operations", operations), ("0", "content")]),
+            UploadLimits::default(),
+        )
+        .await;
+        assert_eq!(
+            error_message(result),
+            "Invalid multipart body: Missing part 'map'"
+        );
+
+        let result = read_operations(
+            CONTENT_TYPE,
+            body(&[
+                ("operations", operations),
+                ("map", r#"{ "0": ["variables.file"] }"#),
+            ]),
+            UploadLimits::default(),
+        )
+        .await;
+        assert_eq!(
+            error_message(result),
+            "Invalid multipart body: Missing part '0'"
+        );
+
+        let result = read_operations(
+            CONTENT_TYPE,
+            body(&[
+                ("operations", operations),
+                ("map", r#"{ "0": ["variables.file"] }"#),
+                ("0", "content"),
+                ("1", "content"),
+            ]),
+            UploadLimits::default(),
+        )
+        .await;
+        assert_eq!(
+            error_message(result),
+            "Invalid multipart body: Unexpected part '1'"
+        );
+    }
+
+    #[tokio::test]
+    async fn upload_limits() {
+        let operations = r#"{ "query": "mutation($files: [Blob!]!) { upload(files: $files) }", "variables": { "files": [null, null] } }"#;
+        let map = r#"{ "0": ["variables.files.0"], "1": ["variables.files.1"] }"#;
+        let limits = UploadLimits {
+            max_file_size: 4,
+            max_files: 1,
+        };
+
+        let result = read_operations(
+            CONTENT_TYPE,
+            body(&[
+                ("operations", operations),
+                ("map", map),
+                ("0", "a"),
+                ("1", "b"),
+            ]),
+            limits,
+        )
+        .await;
+        assert_eq!(
+            error_message(result),
+            "Invalid multipart body: Too many files (the limit is 1)"
+        );
+
+        let result = read_operations(
+            CONTENT_TYPE,
+            body(&[
+                ("operations", operations),
+                ("map", r#"{ "0": ["variables.files.0"] }"#),
+                ("0", "content"),
+            ]),
+            limits,
+        )
+        .await;
+        assert!(error_message(result).starts_with("Invalid multipart body: "));
+    }
+
+    #[test]
+    fn invalid_paths() {
+        let mut single = Operations::from_json(json!({ "query": "{ a }" })).unwrap();
+        let mut batch = Operations::from_json(json!([{ "query": "{ a }" }])).unwrap();
+
+        for path in ["file", "variables", "0.variables.file", "extensions.file"] {
+            assert!(
+                attach_upload(&mut single, path, Bytes::new()).is_err(),
+                "{path}"
+            );
+        }
+        for path in [
+            "variables.file",
+            "1.variables.file",
+            "a.variables.file",
+            "0.file",
+        ] {
+            assert!(
+                attach_upload(&mut batch, path, Bytes::new()).is_err(),
+                "{path}"
+            );
+        }
+
+        assert_eq!(uploads(&single), vec![vec![]]);
+        assert_eq!(uploads(&batch), vec![vec![]]);
+    }
+
+    #[test]
+    fn preflight_header() {
+        let request_head = |headers: &[(&str, &str)]| {
+            MemoryRequestHead::new(
+                headers
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), vec![value.to_string()]))
+                    .collect(),
+                HashMap::new(),
+                http::Method::POST,
+                "/graphql".to_string(),
+                Value::Null,
+                None,
+            )
+        };
+
+        assert!(check_preflight(&request_head(&[("Apollo-Require-Preflight", "true")])).is_ok());
+        assert!(check_preflight(&request_head(&[("X-Apollo-Operation-Name", "upload")])).is_ok());
+        assert!(check_preflight(&request_head(&[])).is_err());
+        assert!(check_preflight(&request_head(&[("Apollo-Require-Preflight", "")])).is_err());
+    }
+}
@@ -0,0 +1,483 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::collections::HashSet;
+
+use async_graphql_parser::{
+    parse_query,
+    types::{DocumentOperations, ExecutableDocument, OperationType, Selection, SelectionSet},
+};
+use async_stream::stream;
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use serde_json::{Map, Value, json};
+
+use common::context::RequestContext;
+use common::http::RequestPayload;
+use common::operation_payload::OperationsPayload;
+use core_resolver::QueryResponse;
+use core_resolver::system_resolver::{
+    GraphQLSystemResolver, RequestError, StreamedResponse, StreamedResponseKind,
+    SystemResolutionError,
+};
+use exo_env::Environment;
+
+use crate::graphql_router::{response_json, trusted_document_enforcement};
+
+/// One of the responses of [resolve_subscription]
+pub struct SubscriptionResponse {
+    /// The GraphQL response (a JSON document such as `{"data": ...}`)
+    pub body: String,
+    /// The headers that resolving the response asked for (such as `Set-Cookie` from a Deno module)
+    pub headers: Vec<(String, String)>,
+}
+
+/// An operation to resolve as a stream (see [resolve_subscription])
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamingOperation {
+    Subscription,
+    /// A query with `@defer` or `@stream` directives (the validator rejects any `@stream` and
+    /// any `@defer` it can't deliver later, which is then reported in the same format)
+    Incremental,
+}
+
+/// The kind of the operation in the body of a GraphQL request, if it is to be resolved as a stream.
+///
+/// Clients such as Apollo Client accept `multipart/mixed` responses for every operation, so the
+/// `Accept` header alone doesn't tell. Any other body (such as a batch, a persisted query sent only
+/// by its hash, or an invalid one) is left to [crate::GraphQLRouter].
+pub fn streaming_operation(body: &Value) -> Option<StreamingOperation> {
+    let query = body.get("query").and_then(Value::as_str)?;
+    let document = parse_query(query).ok()?;
+
+    let operation = match &document.operations {
+        DocumentOperations::Single(operation) => operation,
+        DocumentOperations::Multiple(operations) => {
+            match body.get("operationName").and_then(Value::as_str) {
+                // The parser reports a named operation as one of multiple operations
+                None if operations.len() == 1 => operations.values().next()?,
+                None => return None,
+                Some(operation_name) => operations
+                    .iter()
+                    .find(|(name, _)| name.as_str() == operation_name)
+                    .map(|(_, operation)| operation)?,
+            }
+        }
+    };
+
+    if operation.node.ty == OperationType::Subscription {
+        Some(StreamingOperation::Subscription)
+    } else if has_incremental_directive(
+        &operation.node.selection_set.node,
+        &document,
+        &mut HashSet::new(),
+    ) {
+        Some(StreamingOperation::Incremental)
+    } else {
+        None
+    }
+}
+
+fn has_incremental_directive(
+    selection_set: &SelectionSet,
+    document: &ExecutableDocument,
+    visited_fragments: &mut HashSet<String>,
+) -> bool {
+    selection_set.items.iter().any(|selection| {
+        let directives = match &selection.node {
+            Selection::Field(field) => &field.node.directives,
+            Selection::FragmentSpread(fragment_spread) => &fragment_spread.node.directives,
+            Selection::InlineFragment(inline_fragment) => &inline_fragment.node.directives,
+        };
+
+        if directives
+            .iter()
+            .any(|directive| matches!(directive.node.name.node.as_str(), "defer" | "stream"))
+        {
+            return true;
+        }
+
+        match &selection.node {
+            Selection::Field(field) => has_incremental_directive(
+                &field.node.selection_set.node,
+                document,
+                visited_fragments,
+            ),
+            Selection::FragmentSpread(fragment_spread) => {
+                let fragment_name = &fragment_spread.node.fragment_name.node;
+                visited_fragments.insert(fragment_name.to_string())
+                    && document
+                        .fragments
+                        .get(fragment_name)
+                        .is_some_and(|fragment| {
+                            has_incremental_directive(
+                                &fragment.node.selection_set.node,
+                                document,
+                                visited_fragments,
+                            )
+                        })
+            }
+            Selection::InlineFragment(inline_fragment) => has_incremental_directive(
+                &inline_fragment.node.selection_set.node,
+                document,
+                visited_fragments,
+            ),
+        }
+    })
+}
+
+/// Resolve the GraphQL request into a stream of responses (each a JSON document such as
+/// `{"data": ...}`): one for each event of a subscription, the initial one followed by one for
+/// each deferred fragment of a query with `@defer` directives (with `hasNext` telling if more
+/// follow), and a single one for any other operation.
+///
+/// Unlike [crate::GraphQLRouter], which responds with a single document, this is meant for
+/// transports that can deliver multiple responses (such as WebSocket and server-sent events).
+pub fn resolve_subscription<'a>(
+    request: &'a (dyn RequestPayload + Send + Sync),
+    system_resolver: &'a GraphQLSystemResolver,
+    request_context: &'a RequestContext<'a>,
+    env: &'a dyn Environment,
+) -> impl Stream<Item = SubscriptionResponse> + Send + 'a {
+    stream! {
+        let operations_payload = match OperationsPayload::from_json(request.take_body()) {
+            Ok(operations_payload) => operations_payload,
+            Err(e) => {
+                yield SubscriptionResponse {
+                    body: response_json(Err(SystemResolutionError::RequestError(
+                        RequestError::InvalidBodyJson(e),
+                    )))
+                    .await,
+                    headers: vec![],
+                };
+                return;
+            }
+        };
+
+        let trusted_document_enforcement = trusted_document_enforcement(request_context, env);
+
+        let mut succeeded = true;
+        {
+            let mut responses = system_resolver.resolve_subscription(
+                operations_payload,
+                request_context,
+                trusted_document_enforcement,
+            );
+
+            while let Some(response) = responses.next().await {
+                succeeded &= response.result.is_ok();
+
+                let headers = match &response.result {
+                    Ok(parts) => parts
+                        .iter()
+                        .flat_map(|(_, response)| response.headers.clone())
+                        .collect(),
+                    Err(_) => vec![],
+                };
+                yield SubscriptionResponse {
+                    body: streamed_response_json(response).await,
+                    headers,
+                };
+            }
+        }
+
+        if let Err(e) = request_context.finalize_transaction(succeeded).await {
+            tracing::error!("Error while finalizing transaction: {e}");
+        }
+    }
+}
+
+/// The content type of a response framed by [multipart_mixed]
+pub const MULTIPART_MIXED_CONTENT_TYPE: &str =
+    r#"multipart/mixed; boundary="-"; deferSpec=20220824"#;
+
+/// Frame the responses as server-sent events per the "distinct connections mode" of the GraphQL
+/// over SSE protocol: a `next` event for each response, followed by a `complete` event
+pub fn event_stream(responses: impl Stream<Item = String>) -> impl Stream<Item = Bytes> {
+    responses
+        .map(|response| {
+            // A line break ends an event's data, so each line needs a field of its own
+            let data: String = response
+                .lines()
+                .map(|line| format!("data: {line}\n"))
+                .collect();
+            Bytes::from(format!("event: next\n{data}\n"))
+        })
+        .chain(futures::stream::once(async {
+            Bytes::from_static(b"event: complete\ndata:\n\n")
+        }))
+}
+
+/// Frame the responses as `multipart/mixed` parts per the incremental delivery over HTTP proposal
+/// (with [MULTIPART_MIXED_CONTENT_TYPE] as the content type of the response)
+pub fn multipart_mixed(responses: impl Stream<Item = String>) -> impl Stream<Item = Bytes> {
+    responses
+        .map(|response| {
+            Bytes::from(format!(
+                "\r\n---\r\ncontent-type: application/json; charset=utf-8\r\n\r\n{response}"
+            ))
+        })
+        .chain(futures::stream::once(async {
+            Bytes::from_static(b"\r\n-----\r\n")
+        }))
+}
+
+/// Write a response in the format of the incremental delivery proposal (as of the
+/// `deferSpec=20220824` version supported by clients such as Apollo Client) when part of an
+/// incrementally resolved query, and as a regular response otherwise.
+async fn streamed_response_json(response: StreamedResponse) -> String {
+    let StreamedResponse {
+        kind,
+        result,
+        has_next,
+    } = response;
+
+    let Some(has_next) = has_next else {
+        return response_json(result).await;
+    };
+
+    let payload = match kind {
+        StreamedResponseKind::Data => match result {
+            Ok(parts) => json!({ "data": data_json(parts), "hasNext": has_next }),
+            Err(err) => json!({ "errors": [error_json(&err)], "hasNext": has_next }),
+        },
+        StreamedResponseKind::Deferred { label } => {
+            let mut incremental = match result {
+                Ok(parts) => json!({ "data": data_json(parts), "path": [] }),
+                Err(err) => json!({ "data": null, "path": [], "errors": [error_json(&err)] }),
+            };
+            if let Some(label) = label {
+                incremental["label"] = Value::String(label);
+            }
+            json!({ "incremental": [incremental], "hasNext": has_next })
+        }
+    };
+
+    payload.to_string()
+}
+
+fn data_json(parts: Vec<(String, QueryResponse)>) -> Value {
+    Value::Object(
+        parts
+            .into_iter()
+            .map(|(name, response)| (name, response.body.to_json().unwrap_or(Value::Null)))
+            .collect::<Map<_, _>>(),
+    )
+}
+
+fn error_json(err: &SystemResolutionError) -> Value {
+    let mut error = json!({ "message": err.user_error_message() });
+    if let Some(extensions) = err.error_extensions() {
+        error["extensions"] = Value::Object(extensions);
+    }
+    if let SystemResolutionError::Validation(err) = err {
+        error["locations"] = err
+            .positions()
+            .iter()
+            .map(|pos| json!({ "line": pos.line, "column": pos.column }))
+            .collect();
+    }
+    error
+}
+
+#[cfg(test)]
+mod tests {
+    use core_resolver::QueryResponseBody;
+    use futures::executor::block_on;
+
+    use super::*;
+
+    fn operation_of(query: &str) -> Option<StreamingOperation> {
+        streaming_operation(&json!({ "query": query }))
+    }
+
+    #[test]
+    fn streaming_operations() {
+        assert_eq!(
+            operation_of("subscription { concertUpdated { id } }"),
+            Some(StreamingOperation::Subscription)
+        );
+        assert_eq!(
+            operation_of(
+                "query { concerts { id ... @defer(label: \"venue\") { venue { name } } } }"
+            ),
+            Some(StreamingOperation::Incremental)
+        );
+        assert_eq!(
+            operation_of("query { concerts @stream(initialCount: 1) { id } }"),
+            Some(StreamingOperation::Incremental)
+        );
+        assert_eq!(
+            operation_of(
+                "query { concerts { ...ConcertVenue } } fragment ConcertVenue on Concert { venue @defer { name } }"
+            ),
+            Some(StreamingOperation::Incremental)
+        );
+    }
+
+    #[test]
+    fn non_streaming_operations() {
+        assert_eq!(operation_of("query { concerts { id } }"), None);
+        assert_eq!(
+            operation_of("mutation { deleteConcert(id: 1) { id } }"),
+            None
+        );
+        assert_eq!(
+            operation_of(
+                "query { concerts { ...Details } } fragment Details on Concert { id ...Details }"
+            ),
+            None
+        );
+
+        // Invalid queries and bodies are reported by the regular router
+        assert_eq!(operation_of("subscription {"), None);
+        assert_eq!(streaming_operation(&Value::Null), None);
+        assert_eq!(
+            streaming_operation(&json!([{ "query": "subscription { concertUpdated { id } }" }])),
+            None
+        );
+        assert_eq!(
+            streaming_operation(&json!({
+                "extensions": { "persistedQuery": { "version": 1, "sha256Hash": "abc" } }
+            })),
+            None
+        );
+    }
+
+    #[test]
+    fn streaming_operation_by_name() {
+        let query =
+            "query Concerts { concerts { id } } subscription Updates { concertUpdated { id } }";
+
+        assert_eq!(
+            streaming_operation(&json!({ "query": query, "operationName": "Updates" })),
+            Some(StreamingOperation::Subscription)
+        );
+        assert_eq!(
+            streaming_operation(&json!({ "query": query, "operationName": "Concerts" })),
+            None
+        );
+        assert_eq!(
+            streaming_operation(&json!({ "query": query, "operationName": "Unknown" })),
+            None
+        );
+        assert_eq!(streaming_operation(&json!({ "query": query })), None);
+        assert_eq!(
+            operation_of("subscription Updates { concertUpdated { id } }"),
+            Some(StreamingOperation::Subscription)
+        );
+    }
+
+    fn framed(frame: impl Stream<Item = Bytes>) -> String {
+        block_on(frame.collect::<Vec<_>>())
+            .into_iter()
+            .map(|bytes| String::from_utf8(bytes.to_vec()).unwrap())
+            .collect()
+    }
+
+    fn responses(responses: &[&str]) -> impl Stream<Item = String> {
+        futures::stream::iter(
+            responses
+                .iter()
+                .map(|response| response.to_string())
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn event_stream_framing() {
+        assert_eq!(
+            framed(event_stream(responses(&[
+                r#"{"data":{"a":1},"hasNext":true}"#,
+                "{\n\"hasNext\":false\n}"
+            ]))),
+            "event: next\ndata: {\"data\":{\"a\":1},\"hasNext\":true}\n\n\
+             event: next\ndata: {\ndata: \"hasNext\":false\ndata: }\n\n\
+             event: complete\ndata:\n\n"
+        );
+
+        assert_eq!(
+            framed(event_stream(responses(&[]))),
+            "event: complete\ndata:\n\n"
+        );
+    }
+
+    #[test]
+    fn multipart_mixed_framing() {
+        assert_eq!(
+            framed(multipart_mixed(responses(&[
+                r#"{"data":{"a":1},"hasNext":true}"#,
+                r#"{"incremental":[{"data":{"b":2},"path":[]}],"hasNext":false}"#
+            ]))),
+            "\r\n---\r\ncontent-type: application/json; charset=utf-8\r\n\r\n{\"data\":{\"a\":1},\"hasNext\":true}\
+             \r\n---\r\ncontent-type: application/json; charset=utf-8\r\n\r\n{\"incremental\":[{\"data\":{\"b\":2},\"path\":[]}],\"hasNext\":false}\
+             \r\n-----\r\n"
+        );
+
+        assert_eq!(framed(multipart_mixed(responses(&[]))), "\r\n-----\r\n");
+    }
+
+    fn part(name: &str, value: Value) -> (String, QueryResponse) {
+        (
+            name.to_string(),
+            QueryResponse {
+                body: QueryResponseBody::Json(value),
+                headers: vec![],
+            },
+        )
+    }
+
+    fn streamed_json(
+        kind: StreamedResponseKind,
+        parts: Vec<(String, QueryResponse)>,
+        has_next: bool,
+    ) -> Value {
+        let response = StreamedResponse {
+            kind,
+            result: Ok(parts),
+            has_next: Some(has_next),
+        };
+        serde_json::from_str(&block_on(streamed_response_json(response))).unwrap()
+    }
+
+    #[test]
+    fn incremental_response_formats() {
+        assert_eq!(
+            streamed_json(
+                StreamedResponseKind::Data,
+                vec![part("concerts", json!([{ "id": 1 }]))],
+                true
+            ),
+            json!({ "data": { "concerts": [{ "id": 1 }] }, "hasNext": true })
+        );
+
+        assert_eq!(
+            streamed_json(
+                StreamedResponseKind::Deferred {
+                    label: Some("venue".to_string())
+                },
+                vec![part("venue", json!({ "name": "Hall" }))],
+                true
+            ),
+            json!({
+                "incremental": [{ "data": { "venue": { "name": "Hall" } }, "path": [], "label": "venue" }],
+                "hasNext": true
+            })
+        );
+
+        assert_eq!(
+            streamed_json(
+                StreamedResponseKind::Deferred { label: None },
+                vec![part("venue", json!(null))],
+                false
+            ),
+            json!({ "incremental": [{ "data": { "venue": null }, "path": [] }], "hasNext": false })
+        );
+    }
+}
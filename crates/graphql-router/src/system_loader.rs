@@ -13,23 +13,45 @@ use core_plugin_shared::interception::InterceptionMap;
 use core_plugin_shared::trusted_documents::TrustedDocuments;
 use core_router::SystemLoadingError;
 
+#[cfg(not(target_family = "wasm"))]
+use common::env_const::EXO_RESOLVER_CACHE_SIZE;
+use common::env_const::{
+    EXO_MAX_BATCH_SIZE, EXO_MAX_QUERY_COST, EXO_MAX_UPLOAD_FILE_SIZE, EXO_MAX_UPLOAD_FILES,
+    EXO_PERSISTED_QUERY_CACHE_SIZE, EXO_PERSISTED_QUERY_STORE, EXO_QUERY_COST_DEFAULT_LIST_SIZE,
+};
+use core_resolver::persisted_queries::{
+    DEFAULT_PERSISTED_QUERY_CACHE_SIZE, InMemoryPersistedQueryStore, PersistedQueryStore,
+};
 use core_resolver::plugin::SubsystemGraphQLResolver;
+use core_resolver::resolver_cache::ResolverCache;
+#[cfg(not(target_family = "wasm"))]
+use core_resolver::resolver_cache::{DEFAULT_RESOLVER_CACHE_SIZE, InMemoryResolverCache};
 use core_resolver::{
-    introspection::definition::schema::Schema, system_resolver::GraphQLSystemResolver,
+    introspection::definition::schema::Schema,
+    system_resolver::GraphQLSystemResolver,
+    validation::document_validator::{DEFAULT_COST_LIST_SIZE, QueryCostLimit},
 };
 use exo_env::Environment;
 
+use crate::multipart::UploadLimits;
+
 pub struct SystemLoader;
 
 const EXO_MAX_SELECTION_DEPTH: &str = "EXO_MAX_SELECTION_DEPTH";
 
+/// The number of operations allowed in a batch unless configured otherwise (the default batch size
+/// of Apollo's batch link)
+pub const DEFAULT_MAX_BATCH_SIZE: usize = 10;
+
 impl SystemLoader {
+    #[allow(clippy::too_many_arguments)]
     pub fn create_system_resolver(
         mut subsystem_resolvers: Vec<Arc<dyn SubsystemGraphQLResolver + Send + Sync>>,
         introspection_resolver: Option<Arc<dyn SubsystemGraphQLResolver + Send + Sync>>,
         query_interception_map: Arc<InterceptionMap>,
         mutation_interception_map: Arc<InterceptionMap>,
         trusted_documents: TrustedDocuments,
+        subsystem_persisted_query_store: Option<Arc<dyn PersistedQueryStore>>,
         env: Arc<dyn Environment>,
         schema: Arc<Schema>,
     ) -> Result<GraphQLSystemResolver, SystemLoadingError> {
@@ -40,6 +62,13 @@ impl SystemLoader {
         let (normal_query_depth_limit, introspection_query_depth_limit) =
             query_depth_limits(env.as_ref())?;
 
+        let query_cost_limit = query_cost_limit(env.as_ref())?;
+
+        let persisted_query_store =
+            persisted_query_store(subsystem_persisted_query_store, env.as_ref())?;
+
+        let resolver_cache = resolver_cache(env.as_ref())?;
+
         Ok(GraphQLSystemResolver::new(
             subsystem_resolvers,
             query_interception_map,
@@ -49,7 +78,67 @@ impl SystemLoader {
             env,
             normal_query_depth_limit,
             introspection_query_depth_limit,
-        ))
+        )
+        .with_persisted_query_store(persisted_query_store)
+        .with_query_cost_limit(query_cost_limit)
+        .with_resolver_cache(resolver_cache))
+    }
+}
+
+/// Returns the cache for the results of queries with a `ttl` in their `@cache` hints: an in-memory
+/// cache holding up to `EXO_RESOLVER_CACHE_SIZE` results (or no cache if the size is 0)
+#[cfg(not(target_family = "wasm"))]
+fn resolver_cache(
+    env: &dyn Environment,
+) -> Result<Option<Arc<dyn ResolverCache>>, SystemLoadingError> {
+    let cache_size = match env.get(EXO_RESOLVER_CACHE_SIZE) {
+        Some(e) => e.parse::<usize>().map_err(|_| {
+            SystemLoadingError::Config(format!(
+                "{EXO_RESOLVER_CACHE_SIZE} env var must be set to a non-negative integer"
+            ))
+        })?,
+        None => DEFAULT_RESOLVER_CACHE_SIZE,
+    };
+
+    Ok((cache_size > 0)
+        .then(|| Arc::new(InMemoryResolverCache::new(cache_size)) as Arc<dyn ResolverCache>))
+}
+
+/// The in-memory cache relies on `Instant` (to expire the results), which isn't available in
+/// WebAssembly
+#[cfg(target_family = "wasm")]
+fn resolver_cache(
+    _env: &dyn Environment,
+) -> Result<Option<Arc<dyn ResolverCache>>, SystemLoadingError> {
+    Ok(None)
+}
+
+/// Returns the store for automatic persisted queries: in memory (the default) or the one provided by
+/// a subsystem (for `EXO_PERSISTED_QUERY_STORE=postgres`). Either holds at most
+/// `EXO_PERSISTED_QUERY_CACHE_SIZE` queries.
+fn persisted_query_store(
+    subsystem_persisted_query_store: Option<Arc<dyn PersistedQueryStore>>,
+    env: &dyn Environment,
+) -> Result<Arc<dyn PersistedQueryStore>, SystemLoadingError> {
+    let cache_size = match env.get(EXO_PERSISTED_QUERY_CACHE_SIZE) {
+        Some(e) => e.parse::<usize>().map_err(|_| {
+            SystemLoadingError::Config(format!(
+                "{EXO_PERSISTED_QUERY_CACHE_SIZE} env var must be set to a non-negative integer"
+            ))
+        })?,
+        None => DEFAULT_PERSISTED_QUERY_CACHE_SIZE,
+    };
+
+    match env.get(EXO_PERSISTED_QUERY_STORE).as_deref() {
+        None | Some("memory") => Ok(Arc::new(InMemoryPersistedQueryStore::new(cache_size))),
+        Some("postgres") => subsystem_persisted_query_store.ok_or_else(|| {
+            SystemLoadingError::Config(format!(
+                "{EXO_PERSISTED_QUERY_STORE}=postgres requires a Postgres subsystem"
+            ))
+        }),
+        Some(other) => Err(SystemLoadingError::Config(format!(
+            "{EXO_PERSISTED_QUERY_STORE} env var must be 'memory' or 'postgres' (got '{other}')"
+        ))),
     }
 }
 
@@ -72,3 +161,69 @@ pub fn query_depth_limits(env: &dyn Environment) -> Result<(usize, usize), Syste
 
     Ok((query_depth, DEFAULT_INTROSPECTION_QUERY_DEPTH))
 }
+
+/// Returns the maximum number of operations in a batch (from `EXO_MAX_BATCH_SIZE`)
+pub fn max_batch_size(env: &dyn Environment) -> Result<usize, SystemLoadingError> {
+    match env.get(EXO_MAX_BATCH_SIZE) {
+        Some(e) => e.parse::<usize>().map_err(|_| {
+            SystemLoadingError::Config(format!(
+                "{EXO_MAX_BATCH_SIZE} env var must be set to a non-negative integer"
+            ))
+        }),
+        None => Ok(DEFAULT_MAX_BATCH_SIZE),
+    }
+}
+
+/// Returns the limits on the files uploaded with a request (from `EXO_MAX_UPLOAD_FILE_SIZE` and
+/// `EXO_MAX_UPLOAD_FILES`)
+pub fn upload_limits(env: &dyn Environment) -> Result<UploadLimits, SystemLoadingError> {
+    let parse = |name: &str| {
+        env.get(name)
+            .map(|value| {
+                value.parse::<usize>().map_err(|_| {
+                    SystemLoadingError::Config(format!(
+                        "{name} env var must be set to a non-negative integer"
+                    ))
+                })
+            })
+            .transpose()
+    };
+
+    let default_limits = UploadLimits::default();
+
+    Ok(UploadLimits {
+        max_file_size: parse(EXO_MAX_UPLOAD_FILE_SIZE)?
+            .map_or(default_limits.max_file_size, |size| size as u64),
+        max_files: parse(EXO_MAX_UPLOAD_FILES)?.unwrap_or(default_limits.max_files),
+    })
+}
+
+/// Returns the maximum cost of an operation (from `EXO_MAX_QUERY_COST`) along with the number of
+/// elements to assume for list fields without a `limit` (from `EXO_QUERY_COST_DEFAULT_LIST_SIZE`).
+/// Operations aren't limited by cost unless `EXO_MAX_QUERY_COST` is set.
+pub fn query_cost_limit(
+    env: &dyn Environment,
+) -> Result<Option<QueryCostLimit>, SystemLoadingError> {
+    let parse = |name: &str| {
+        env.get(name)
+            .map(|value| {
+                value.parse::<usize>().map_err(|_| {
+                    SystemLoadingError::Config(format!(
+                        "{name} env var must be set to a non-negative integer"
+                    ))
+                })
+            })
+            .transpose()
+    };
+
+    let Some(max_cost) = parse(EXO_MAX_QUERY_COST)? else {
+        return Ok(None);
+    };
+    let default_list_size =
+        parse(EXO_QUERY_COST_DEFAULT_LIST_SIZE)?.unwrap_or(DEFAULT_COST_LIST_SIZE);
+
+    Ok(Some(QueryCostLimit {
+        max_cost,
+        default_list_size,
+    }))
+}
@@ -0,0 +1,496 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! The server side of the `graphql-transport-ws` protocol
+//! (https://github.com/enisdenjo/graphql-ws/blob/master/PROTOCOL.md), independent of any specific
+//! WebSocket implementation. A server feeds each text message to [WebSocketConnection] and carries
+//! out the resulting [WebSocketAction].
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+
+use common::http::{RequestHead, RequestPayload};
+
+pub const GRAPHQL_TRANSPORT_WS_PROTOCOL: &str = "graphql-transport-ws";
+
+/// How long to wait for the `connection_init` message before closing the connection
+pub const CONNECTION_INIT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How many subscriptions a connection may have running at a time
+pub const MAX_SUBSCRIPTIONS_PER_CONNECTION: usize = 100;
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    ConnectionInit { payload: Option<Map<String, Value>> },
+    Ping {},
+    Pong {},
+    Subscribe { id: String, payload: Value },
+    Complete { id: String },
+}
+
+/// The reason to close the connection for (when the client violates the protocol)
+#[derive(Debug, PartialEq, Eq)]
+pub struct CloseReason {
+    pub code: u16,
+    pub description: String,
+}
+
+impl CloseReason {
+    fn new(code: u16, description: impl Into<String>) -> Self {
+        Self {
+            code,
+            description: description.into(),
+        }
+    }
+}
+
+pub enum WebSocketAction<H> {
+    /// Nothing to do
+    None,
+    /// Send the message to the client
+    Send(String),
+    /// Start a subscription, registering it with [WebSocketConnection::add_subscription]
+    Subscribe {
+        id: String,
+        request: WebSocketRequestPayload,
+    },
+    /// Stop the subscription (that the client completed)
+    Stop(H),
+}
+
+/// The state of a connection: whether the client has initialized it and the running subscriptions
+/// (with a handle, such as a task's, of type `H` to stop each)
+pub struct WebSocketConnection<H> {
+    head: Arc<dyn RequestHead + Send + Sync>,
+    /// The headers from the `connection_init` message (`None` until the connection is acknowledged)
+    init_headers: Option<Arc<HashMap<String, String>>>,
+    subscriptions: HashMap<String, H>,
+    max_subscriptions: usize,
+}
+
+impl<H> WebSocketConnection<H> {
+    /// Create a connection for the upgrade request with the given head
+    pub fn new(head: Arc<dyn RequestHead + Send + Sync>) -> Self {
+        Self {
+            head,
+            init_headers: None,
+            subscriptions: HashMap::new(),
+            max_subscriptions: MAX_SUBSCRIPTIONS_PER_CONNECTION,
+        }
+    }
+
+    pub fn with_max_subscriptions(mut self, max_subscriptions: usize) -> Self {
+        self.max_subscriptions = max_subscriptions;
+        self
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.init_headers.is_some()
+    }
+
+    /// Handle a text message from the client, returning the reason to close the connection for if
+    /// the message violates the protocol
+    pub fn handle_message(&mut self, text: &str) -> Result<WebSocketAction<H>, CloseReason> {
+        let message: ClientMessage = serde_json::from_str(text)
+            .map_err(|e| CloseReason::new(4400, format!("Invalid message received: {e}")))?;
+
+        match message {
+            ClientMessage::ConnectionInit { payload } => {
+                if self.init_headers.is_some() {
+                    return Err(CloseReason::new(4429, "Too many initialisation requests"));
+                }
+
+                self.init_headers = Some(Arc::new(init_headers(payload.unwrap_or_default())));
+                Ok(WebSocketAction::Send(
+                    json!({ "type": "connection_ack" }).to_string(),
+                ))
+            }
+            ClientMessage::Ping {} => {
+                Ok(WebSocketAction::Send(json!({ "type": "pong" }).to_string()))
+            }
+            ClientMessage::Pong {} => Ok(WebSocketAction::None),
+            ClientMessage::Subscribe { id, payload } => {
+                let Some(init_headers) = &self.init_headers else {
+                    return Err(CloseReason::new(4401, "Unauthorized"));
+                };
+
+                if self.subscriptions.contains_key(&id) {
+                    return Err(CloseReason::new(
+                        4409,
+                        format!("Subscriber for {id} already exists"),
+                    ));
+                }
+
+                if self.subscriptions.len() >= self.max_subscriptions {
+                    return Ok(WebSocketAction::Send(error_message(
+                        &id,
+                        &format!(
+                            "Too many subscriptions (a connection may have at most {} running)",
+                            self.max_subscriptions
+                        ),
+                    )));
+                }
+
+                let request = WebSocketRequestPayload {
+                    head: WebSocketRequestHead {
+                        head: self.head.clone(),
+                        init_headers: init_headers.clone(),
+                    },
+                    body: Mutex::new(payload),
+                };
+
+                Ok(WebSocketAction::Subscribe { id, request })
+            }
+            ClientMessage::Complete { id } => Ok(match self.subscriptions.remove(&id) {
+                Some(handle) => WebSocketAction::Stop(handle),
+                None => WebSocketAction::None,
+            }),
+        }
+    }
+
+    /// Register a subscription started for [WebSocketAction::Subscribe]
+    pub fn add_subscription(&mut self, id: String, handle: H) {
+        self.subscriptions.insert(id, handle);
+    }
+
+    /// Forget a subscription that has sent all its responses (so that the client may reuse its id)
+    pub fn remove_subscription(&mut self, id: &str) -> Option<H> {
+        self.subscriptions.remove(id)
+    }
+
+    /// Remove all subscriptions (to stop them once the connection closes)
+    pub fn take_subscriptions(&mut self) -> Vec<H> {
+        self.subscriptions
+            .drain()
+            .map(|(_, handle)| handle)
+            .collect()
+    }
+}
+
+/// The message with a response of a subscription
+pub fn next_message(id: &str, response: &str) -> String {
+    // The response is already a JSON document, so we splice it in (instead of parsing it again)
+    format!(
+        r#"{{"id":{},"type":"next","payload":{response}}}"#,
+        Value::String(id.to_string())
+    )
+}
+
+/// The message that a subscription has sent all its responses
+pub fn complete_message(id: &str) -> String {
+    json!({ "id": id, "type": "complete" }).to_string()
+}
+
+/// The message that a subscription couldn't start
+pub fn error_message(id: &str, message: &str) -> String {
+    json!({ "id": id, "type": "error", "payload": [{ "message": message }] }).to_string()
+}
+
+/// Clients can't set headers on a WebSocket connection from a browser, so they send them (such as
+/// `Authorization`) in the `connection_init` payload instead. Only the string values count.
+fn init_headers(payload: Map<String, Value>) -> HashMap<String, String> {
+    payload
+        .into_iter()
+        .filter_map(|(key, value)| match value {
+            Value::String(value) => Some((key.to_lowercase(), value)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The head of the upgrade request, with the headers from the `connection_init` payload taking the
+/// place of the ones with the same name
+pub struct WebSocketRequestHead {
+    head: Arc<dyn RequestHead + Send + Sync>,
+    init_headers: Arc<HashMap<String, String>>,
+}
+
+impl RequestHead for WebSocketRequestHead {
+    fn get_headers(&self, key: &str) -> Vec<String> {
+        match self.init_headers.get(&key.to_lowercase()) {
+            Some(value) => vec![value.clone()],
+            None => self.head.get_headers(key),
+        }
+    }
+
+    fn get_ip(&self) -> Option<std::net::IpAddr> {
+        self.head.get_ip()
+    }
+
+    fn get_method(&self) -> http::Method {
+        self.head.get_method()
+    }
+
+    fn get_path(&self) -> String {
+        self.head.get_path()
+    }
+
+    fn get_query(&self) -> Value {
+        self.head.get_query()
+    }
+}
+
+/// The request for a subscription (with the `subscribe` message's payload as the body)
+pub struct WebSocketRequestPayload {
+    head: WebSocketRequestHead,
+    body: Mutex<Value>,
+}
+
+impl RequestPayload for WebSocketRequestPayload {
+    fn get_head(&self) -> &(dyn RequestHead + Send + Sync) {
+        &self.head
+    }
+
+    fn take_body(&self) -> Value {
+        self.body.lock().unwrap().take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct UpgradeRequestHead {
+        headers: HashMap<String, String>,
+    }
+
+    impl RequestHead for UpgradeRequestHead {
+        fn get_headers(&self, key: &str) -> Vec<String> {
+            self.headers.get(key).cloned().into_iter().collect()
+        }
+
+        fn get_ip(&self) -> Option<std::net::IpAddr> {
+            None
+        }
+
+        fn get_method(&self) -> http::Method {
+            http::Method::GET
+        }
+
+        fn get_path(&self) -> String {
+            "/graphql".to_string()
+        }
+
+        fn get_query(&self) -> Value {
+            Value::Null
+        }
+    }
+
+    fn connection() -> WebSocketConnection<()> {
+        WebSocketConnection::new(Arc::new(UpgradeRequestHead {
+            headers: HashMap::from([
+                ("authorization".to_string(), "Bearer upgrade".to_string()),
+                ("cookie".to_string(), "session=upgrade".to_string()),
+            ]),
+        }))
+    }
+
+    fn initialized_connection() -> WebSocketConnection<()> {
+        let mut connection = connection();
+        assert_sent(
+            connection.handle_message(r#"{"type":"connection_init"}"#),
+            r#"{"type":"connection_ack"}"#,
+        );
+        connection
+    }
+
+    fn subscribe_message(id: &str) -> String {
+        json!({ "id": id, "type": "subscribe", "payload": { "query": "subscription { todoChanged { id } }" } })
+            .to_string()
+    }
+
+    fn assert_sent(action: Result<WebSocketAction<()>, CloseReason>, expected: &str) {
+        match action {
+            Ok(WebSocketAction::Send(message)) => assert_eq!(message, expected),
+            _ => panic!("Expected to send {expected}"),
+        }
+    }
+
+    fn assert_closed(action: Result<WebSocketAction<()>, CloseReason>, code: u16) {
+        match action {
+            Err(reason) => assert_eq!(reason.code, code, "{}", reason.description),
+            Ok(_) => panic!("Expected the connection to close with {code}"),
+        }
+    }
+
+    /// Subscribe and register the subscription, returning its request
+    fn subscribe(connection: &mut WebSocketConnection<()>, id: &str) -> WebSocketRequestPayload {
+        match connection.handle_message(&subscribe_message(id)) {
+            Ok(WebSocketAction::Subscribe {
+                id: subscription_id,
+                request,
+            }) => {
+                assert_eq!(subscription_id, id);
+                connection.add_subscription(subscription_id, ());
+                request
+            }
+            _ => panic!("Expected to subscribe {id}"),
+        }
+    }
+
+    #[test]
+    fn connection_init_headers() {
+        let mut connection = connection();
+        assert!(!connection.is_initialized());
+
+        assert_sent(
+            connection.handle_message(
+                r#"{"type":"connection_init","payload":{"Authorization":"Bearer init","retries":3}}"#,
+            ),
+            r#"{"type":"connection_ack"}"#,
+        );
+        assert!(connection.is_initialized());
+
+        let request = subscribe(&mut connection, "1");
+        let head = request.get_head();
+
+        // The `connection_init` payload takes the place of the upgrade request's headers...
+        assert_eq!(
+            head.get_header("Authorization"),
+            Some("Bearer init".to_string())
+        );
+        // ...but only for the string entries in it
+        assert_eq!(head.get_header("retries"), None);
+        assert_eq!(
+            head.get_header("cookie"),
+            Some("session=upgrade".to_string())
+        );
+
+        assert_eq!(
+            request.take_body(),
+            json!({ "query": "subscription { todoChanged { id } }" })
+        );
+    }
+
+    #[test]
+    fn connection_init_without_payload() {
+        let mut connection = initialized_connection();
+
+        let request = subscribe(&mut connection, "1");
+        assert_eq!(
+            request.get_head().get_header("authorization"),
+            Some("Bearer upgrade".to_string())
+        );
+    }
+
+    #[test]
+    fn repeated_connection_init() {
+        let mut connection = initialized_connection();
+
+        assert_closed(
+            connection.handle_message(r#"{"type":"connection_init"}"#),
+            4429,
+        );
+    }
+
+    #[test]
+    fn subscribe_before_connection_init() {
+        let mut connection = connection();
+
+        assert_closed(connection.handle_message(&subscribe_message("1")), 4401);
+    }
+
+    #[test]
+    fn duplicate_subscription_id() {
+        let mut connection = initialized_connection();
+        subscribe(&mut connection, "1");
+
+        assert_closed(connection.handle_message(&subscribe_message("1")), 4409);
+    }
+
+    #[test]
+    fn reuse_subscription_id() {
+        let mut connection = initialized_connection();
+
+        // Once completed by the client...
+        subscribe(&mut connection, "1");
+        assert!(matches!(
+            connection.handle_message(r#"{"type":"complete","id":"1"}"#),
+            Ok(WebSocketAction::Stop(()))
+        ));
+        subscribe(&mut connection, "1");
+
+        // ...or by the server
+        assert_eq!(connection.remove_subscription("1"), Some(()));
+        subscribe(&mut connection, "1");
+
+        // Completing an unknown subscription is a no-op
+        assert!(matches!(
+            connection.handle_message(r#"{"type":"complete","id":"2"}"#),
+            Ok(WebSocketAction::None)
+        ));
+    }
+
+    #[test]
+    fn subscription_limit() {
+        let mut connection = initialized_connection().with_max_subscriptions(2);
+        subscribe(&mut connection, "1");
+        subscribe(&mut connection, "2");
+
+        assert_sent(
+            connection.handle_message(&subscribe_message("3")),
+            r#"{"id":"3","type":"error","payload":[{"message":"Too many subscriptions (a connection may have at most 2 running)"}]}"#,
+        );
+
+        connection.remove_subscription("1");
+        subscribe(&mut connection, "3");
+
+        assert_eq!(connection.take_subscriptions().len(), 2);
+    }
+
+    #[test]
+    fn ping_pong() {
+        let mut connection = connection();
+
+        // Allowed before `connection_init`
+        assert_sent(
+            connection.handle_message(r#"{"type":"ping"}"#),
+            r#"{"type":"pong"}"#,
+        );
+        assert!(matches!(
+            connection.handle_message(r#"{"type":"pong"}"#),
+            Ok(WebSocketAction::None)
+        ));
+    }
+
+    #[test]
+    fn invalid_messages() {
+        for message in [
+            "not json",
+            r#"{"type":"unknown"}"#,
+            r#"{"type":"subscribe","payload":{}}"#,
+            r#"{"type":"complete"}"#,
+        ] {
+            let mut connection = initialized_connection();
+            assert_closed(connection.handle_message(message), 4400);
+        }
+    }
+
+    #[test]
+    fn framing() {
+        assert_eq!(
+            next_message("1", r#"{"data":{"todoChanged":{"id":1}}}"#),
+            r#"{"id":"1","type":"next","payload":{"data":{"todoChanged":{"id":1}}}}"#
+        );
+        assert_eq!(
+            next_message(r#"a"b"#, r#"{"data":null}"#),
+            r#"{"id":"a\"b","type":"next","payload":{"data":null}}"#
+        );
+        assert_eq!(complete_message("1"), r#"{"id":"1","type":"complete"}"#);
+        assert_eq!(
+            error_message("1", "Failed"),
+            r#"{"id":"1","type":"error","payload":[{"message":"Failed"}]}"#
+        );
+    }
+}
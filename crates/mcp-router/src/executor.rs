@@ -37,6 +37,7 @@ impl Executor for GraphQLSystemResolver {
             variables,
             operation_name: None,
             query_hash: None,
+            uploads: vec![],
         };
 
         resolve_in_memory_for_payload(
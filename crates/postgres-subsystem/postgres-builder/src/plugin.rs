@@ -60,6 +60,18 @@ impl SubsystemBuilder for PostgresSubsystemBuilder {
                             name: "managed",
                             optional: true,
                         },
+                        MappedAnnotationParamSpec {
+                            name: "validationChecks",
+                            optional: true,
+                        },
+                        MappedAnnotationParamSpec {
+                            name: "changeFeed",
+                            optional: true,
+                        },
+                        MappedAnnotationParamSpec {
+                            name: "sessionContext",
+                            optional: true,
+                        },
                     ]),
                 },
             ),
@@ -69,10 +81,20 @@ impl SubsystemBuilder for PostgresSubsystemBuilder {
                     targets: &[AnnotationTarget::Field],
                     no_params: false,
                     single_params: true,
-                    mapped_params: MappedAnnotationParams::Specific(&[MappedAnnotationParamSpec {
-                        name: "mapping",
-                        optional: true,
-                    }]),
+                    mapped_params: MappedAnnotationParams::Specific(&[
+                        MappedAnnotationParamSpec {
+                            name: "name",
+                            optional: true,
+                        },
+                        MappedAnnotationParamSpec {
+                            name: "mapping",
+                            optional: true,
+                        },
+                        MappedAnnotationParamSpec {
+                            name: "previously",
+                            optional: true,
+                        },
+                    ]),
                 },
             ),
             (
@@ -129,6 +151,24 @@ impl SubsystemBuilder for PostgresSubsystemBuilder {
                     mapped_params: MappedAnnotationParams::None,
                 },
             ),
+            (
+                "statementTimeout",
+                AnnotationSpec {
+                    targets: &[AnnotationTarget::Type],
+                    no_params: false,
+                    single_params: true,
+                    mapped_params: MappedAnnotationParams::Specific(&[
+                        MappedAnnotationParamSpec {
+                            name: "query",
+                            optional: true,
+                        },
+                        MappedAnnotationParamSpec {
+                            name: "mutation",
+                            optional: true,
+                        },
+                    ]),
+                },
+            ),
             (
                 "table",
                 AnnotationSpec {
@@ -151,6 +191,15 @@ impl SubsystemBuilder for PostgresSubsystemBuilder {
                     ]),
                 },
             ),
+            (
+                "check",
+                AnnotationSpec {
+                    targets: &[AnnotationTarget::Type],
+                    no_params: false,
+                    single_params: true,
+                    mapped_params: MappedAnnotationParams::None,
+                },
+            ),
             (
                 "unique",
                 AnnotationSpec {
@@ -166,7 +215,24 @@ impl SubsystemBuilder for PostgresSubsystemBuilder {
                     targets: &[AnnotationTarget::Field],
                     no_params: true,
                     single_params: true,
-                    mapped_params: MappedAnnotationParams::None,
+                    mapped_params: MappedAnnotationParams::Specific(&[
+                        MappedAnnotationParamSpec {
+                            name: "name",
+                            optional: true,
+                        },
+                        MappedAnnotationParamSpec {
+                            name: "order",
+                            optional: true,
+                        },
+                        MappedAnnotationParamSpec {
+                            name: "where",
+                            optional: true,
+                        },
+                        MappedAnnotationParamSpec {
+                            name: "expression",
+                            optional: true,
+                        },
+                    ]),
                 },
             ),
             (
@@ -35,8 +35,8 @@ use core_model_builder::{
 use exo_sql_pg::column_default::{ColumnAutoincrement, ColumnDefault, UuidGenerationMethod};
 use exo_sql_pg::{
     ArrayColumnType, BooleanColumnType, ColumnId, ColumnReference, Database, EnumColumnType,
-    IndexKind, JsonColumnType, ManyToOne, PhysicalColumn, PhysicalColumnType, PhysicalEnum,
-    PhysicalIndex, PhysicalTable, RelationColumnPair, TableId,
+    IndexColumn, IndexKind, IndexTarget, JsonColumnType, ManyToOne, PhysicalCheck, PhysicalColumn,
+    PhysicalColumnType, PhysicalEnum, PhysicalIndex, PhysicalTable, RelationColumnPair, TableId,
 };
 
 use heck::ToSnakeCase;
@@ -119,7 +119,16 @@ fn expand_database_info(
         name: resolved_type.table_name.clone(),
         columns: vec![],
         indices: vec![],
+        checks: resolved_type
+            .checks
+            .iter()
+            .map(|check| PhysicalCheck {
+                name: check.name.clone(),
+                expression: check.expression.clone(),
+            })
+            .collect(),
         managed: resolved_type.representation == EntityRepresentation::Managed,
+        change_feed: resolved_type.change_feed,
     };
 
     let table_id = building.database.insert_table(table);
@@ -142,17 +151,59 @@ fn expand_database_info(
 
     {
         let mut indices: Vec<PhysicalIndex> = vec![];
-        resolved_type.fields.iter().for_each(|field| {
-            field.indices.iter().for_each(|index_name| {
-                let existing_index = indices.iter_mut().find(|i| &i.name == index_name);
+        for field in resolved_type.fields.iter() {
+            for field_index in field.indices.iter() {
+                // Index the expression, if specified, instead of the field's columns
+                let index_columns: Vec<IndexColumn> = match &field_index.expression {
+                    Some(expression) => vec![IndexColumn {
+                        target: IndexTarget::Expression(expression.clone()),
+                        ordering: field_index.ordering,
+                    }],
+                    None => field
+                        .column_names
+                        .iter()
+                        .map(|column_name| IndexColumn {
+                            target: IndexTarget::Column(column_name.clone()),
+                            ordering: field_index.ordering,
+                        })
+                        .collect(),
+                };
+
+                let existing_index = indices.iter_mut().find(|i| i.name == field_index.name);
 
                 match existing_index {
                     Some(existing_index) => {
-                        existing_index.columns.extend(field.column_names.clone());
+                        // Columns of a multi-column index follow the order of fields in the type
+                        existing_index.columns.extend(index_columns);
+
+                        match (&existing_index.predicate, &field_index.predicate) {
+                            (Some(existing_predicate), Some(predicate))
+                                if existing_predicate != predicate =>
+                            {
+                                return Err(ModelBuildingError::Diagnosis(vec![Diagnostic {
+                                    level: Level::Error,
+                                    message: format!(
+                                        "Conflicting 'where' clauses for index '{}'",
+                                        field_index.name
+                                    ),
+                                    code: Some("C000".to_string()),
+                                    spans: vec![SpanLabel {
+                                        span: field.span,
+                                        style: SpanStyle::Primary,
+                                        label: None,
+                                    }],
+                                }]));
+                            }
+                            (None, Some(predicate)) => {
+                                existing_index.predicate = Some(predicate.clone());
+                            }
+                            _ => {}
+                        }
                     }
                     None => indices.push(PhysicalIndex {
-                        name: index_name.clone(),
-                        columns: HashSet::from_iter(field.column_names.clone()),
+                        name: field_index.name.clone(),
+                        columns: index_columns,
+                        predicate: field_index.predicate.clone(),
                         index_kind: if field.typ.innermost().type_name
                             == primitive_type::VectorType::NAME
                         {
@@ -175,8 +226,8 @@ fn expand_database_info(
                         },
                     }),
                 }
-            })
-        });
+            }
+        }
         building.database.get_table_mut(table_id).indices = indices;
     }
 
@@ -417,6 +468,7 @@ fn create_columns(
                                         default_value: default_value.clone(),
                                         update_sync,
                                         column_references: None,
+                                        previous_name: field.previous_column_name.clone(),
                                     },
                                 );
                             }
@@ -465,6 +517,7 @@ fn create_columns(
                                         default_value: default_value.clone(),
                                         update_sync,
                                         column_references: None,
+                                        previous_name: field.previous_column_name.clone(),
                                     },
                                 );
                             }
@@ -495,6 +548,7 @@ fn create_columns(
                                         default_value: default_value.clone(),
                                         update_sync,
                                         column_references: None,
+                                        previous_name: field.previous_column_name.clone(),
                                     },
                                 );
                             }
@@ -557,6 +611,7 @@ fn create_columns(
                                     default_value: default_value.clone(),
                                     update_sync,
                                     column_references: None,
+                                    previous_name: field.previous_column_name.clone(),
                                 },
                             );
                         }
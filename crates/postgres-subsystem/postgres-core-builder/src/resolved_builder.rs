@@ -16,7 +16,10 @@
 use std::collections::{HashMap, HashSet};
 
 use codemap_diagnostic::{Diagnostic, Level, SpanLabel, SpanStyle};
-use postgres_core_model::types::EntityRepresentation;
+use postgres_core_model::{
+    subsystem::SessionVariable,
+    types::{EntityRepresentation, StatementTimeout},
+};
 use serde::{Deserialize, Serialize};
 
 use super::{
@@ -25,15 +28,18 @@ use super::{
 };
 use crate::{
     resolved_type::{
-        ExplicitTypeHint, ResolvedCompositeType, ResolvedEnumType, ResolvedField,
-        ResolvedFieldDefault, ResolvedFieldType, ResolvedType, SerializableTypeHint,
+        ExplicitTypeHint, ResolvedCheck, ResolvedCompositeType, ResolvedEnumType, ResolvedField,
+        ResolvedFieldDefault, ResolvedFieldIndex, ResolvedFieldType, ResolvedType,
+        SerializableTypeHint,
     },
     type_provider::{PRIMITIVE_TYPE_PROVIDER_REGISTRY, validate_hint_annotations},
 };
+use codemap::Span;
 use core_model::{
+    context_type::ContextSelection,
     mapped_arena::MappedArena,
     primitive_type::{self},
-    types::{FieldType, Named},
+    types::{FieldType, Named, TypeValidation, TypeValidationProvider},
 };
 use core_model_builder::{
     ast::ast_types::{
@@ -41,14 +47,18 @@ use core_model_builder::{
         AstFieldDefault, AstFieldDefaultKind, AstFieldType, AstLiteral, AstModel, AstModelKind,
         AstProjectionExpr, default_span,
     },
-    builder::resolved_builder::{AnnotationMapHelper, compute_fragment_fields},
+    builder::{
+        cache_hint::build_cache_hint,
+        cost::build_cost,
+        resolved_builder::{AnnotationMapHelper, compute_fragment_fields},
+    },
     error::ModelBuildingError,
     typechecker::{
         Typed,
         typ::{Module, Type, TypecheckedSystem},
     },
 };
-use exo_sql_pg::SchemaObjectName;
+use exo_sql_pg::{Ordering, SchemaObjectName};
 
 use heck::ToSnakeCase;
 
@@ -135,12 +145,40 @@ fn resolve(
             }
         });
 
+        // Should field validations (such as `@range`) also be enforced through `CHECK` constraints?
+        let validation_checks = module_annotation
+            .and_then(|a| {
+                if let AstAnnotationParams::Map(map, _) = a {
+                    map.get("validationChecks").map(|s| s.as_boolean())
+                } else {
+                    None
+                }
+            })
+            .unwrap_or(false);
+
+        // Should the (managed) tables publish their row changes to the change feed?
+        let change_feed = module_annotation
+            .and_then(|a| {
+                if let AstAnnotationParams::Map(map, _) = a {
+                    map.get("changeFeed").map(|s| s.as_boolean())
+                } else {
+                    None
+                }
+            })
+            .unwrap_or(false);
+
+        let session_variables =
+            extract_session_variables(module_annotation, typechecked_system, errors);
+
         for typ in module.types.iter() {
             if let Some(Type::Composite(ct)) = typechecked_system.types.get_by_key(&typ.name) {
                 resolve_composite_type(
                     ct,
                     &module_schema_name,
                     module_managed,
+                    validation_checks,
+                    change_feed,
+                    &session_variables,
                     typechecked_system,
                     &mut resolved_postgres_types,
                     errors,
@@ -175,6 +213,9 @@ fn resolve_composite_type(
     ct: &AstModel<Typed>,
     module_schema_name: &Option<String>,
     module_managed: Option<bool>,
+    validation_checks: bool,
+    change_feed: bool,
+    session_variables: &[SessionVariable],
     typechecked_system: &TypecheckedSystem,
     resolved_postgres_types: &mut MappedArena<ResolvedType>,
     errors: &mut Vec<Diagnostic>,
@@ -253,6 +294,18 @@ fn resolve_composite_type(
 
         let projection_exprs = extract_projection_exprs(ct);
 
+        let statement_timeout = extract_statement_timeout(ct)?;
+
+        let cache_hint = build_cache_hint(&ct.annotations, &ct.name, ct.span)?;
+
+        let cost = build_cost(&ct.annotations, &ct.name, ct.span)?;
+
+        let checks = if is_json {
+            vec![]
+        } else {
+            compute_checks(ct, &table_name, &resolved_fields, validation_checks)?
+        };
+
         resolved_postgres_types.add(
             &ct.name,
             ResolvedType::Composite(ResolvedCompositeType {
@@ -264,9 +317,15 @@ fn resolve_composite_type(
                     name: table_name,
                     schema: schema_name,
                 },
+                checks,
                 access: access.clone(),
                 projection_exprs,
                 doc_comments: ct.doc_comments.clone(),
+                statement_timeout,
+                cache_hint,
+                cost,
+                change_feed: change_feed && representation == EntityRepresentation::Managed,
+                session_variables: session_variables.to_vec(),
                 span: ct.span,
             }),
         );
@@ -275,6 +334,139 @@ fn resolve_composite_type(
     Ok(())
 }
 
+/// The session variables from the module's `sessionContext` option such as
+/// `@postgres(sessionContext=[AuthContext.id, AuthContext.tenantId])`. Each context field sets a
+/// variable named after it (here, `exo.auth_context_id` and `exo.auth_context_tenant_id`).
+fn extract_session_variables(
+    module_annotation: Option<&AstAnnotationParams<Typed>>,
+    typechecked_system: &TypecheckedSystem,
+    errors: &mut Vec<Diagnostic>,
+) -> Vec<SessionVariable> {
+    let Some(AstAnnotationParams::Map(map, spans)) = module_annotation else {
+        return vec![];
+    };
+    let Some(param) = map.get("sessionContext") else {
+        return vec![];
+    };
+
+    let session_variable_error = |message: String, span: Span| Diagnostic {
+        level: Level::Error,
+        message,
+        code: Some("C000".to_string()),
+        spans: vec![SpanLabel {
+            span,
+            style: SpanStyle::Primary,
+            label: None,
+        }],
+    };
+
+    let AstAnnotationParam::StringList(selections, selection_spans) = param else {
+        let span = spans
+            .get("sessionContext")
+            .and_then(|spans| spans.first().copied())
+            .unwrap_or_else(default_span);
+        errors.push(session_variable_error(
+            "sessionContext must be a list of context fields such as [AuthContext.id]".to_string(),
+            span,
+        ));
+        return vec![];
+    };
+
+    selections
+        .iter()
+        .zip(selection_spans)
+        .filter_map(|(selection, span)| {
+            let Some((context_name, field_name)) = selection.split_once('.') else {
+                errors.push(session_variable_error(
+                    format!("'{selection}' in sessionContext must be a context field such as AuthContext.id"),
+                    *span,
+                ));
+                return None;
+            };
+
+            let context_field = match typechecked_system.types.get_by_key(context_name) {
+                Some(Type::Composite(context)) if context.kind == AstModelKind::Context => {
+                    context.fields.iter().find(|field| field.name == field_name)
+                }
+                _ => {
+                    errors.push(session_variable_error(
+                        format!("Context '{context_name}' in sessionContext not found"),
+                        *span,
+                    ));
+                    return None;
+                }
+            };
+
+            if context_field.is_none() {
+                errors.push(session_variable_error(
+                    format!("Field '{field_name}' of context '{context_name}' in sessionContext not found"),
+                    *span,
+                ));
+                return None;
+            }
+
+            Some(SessionVariable {
+                name: format!(
+                    "exo.{}_{}",
+                    context_name.to_snake_case(),
+                    field_name.to_snake_case()
+                ),
+                selection: ContextSelection {
+                    context_name: context_name.to_string(),
+                    path: (field_name.to_string(), vec![]),
+                },
+            })
+        })
+        .collect()
+}
+
+/// The timeouts from `@statementTimeout(<milliseconds>)` (for all operations) or
+/// `@statementTimeout(query=<milliseconds>, mutation=<milliseconds>)`
+fn extract_statement_timeout(ct: &AstModel<Typed>) -> Result<StatementTimeout, Diagnostic> {
+    let Some(annotation) = ct.annotations.get("statementTimeout") else {
+        return Ok(StatementTimeout::default());
+    };
+
+    let parse_timeout = |param: &AstAnnotationParam<Typed>| match param {
+        AstAnnotationParam::Literal(AstLiteral::Number(value, _)) => match value.parse::<u64>() {
+            Ok(timeout) if timeout > 0 => Ok(timeout),
+            _ => Err(statement_timeout_error(ct)),
+        },
+        _ => Err(statement_timeout_error(ct)),
+    };
+
+    match &annotation.params {
+        AstAnnotationParams::Single(param, _) => {
+            let timeout = parse_timeout(param)?;
+            Ok(StatementTimeout {
+                query: Some(timeout),
+                mutation: Some(timeout),
+            })
+        }
+        AstAnnotationParams::Map(params, _) => Ok(StatementTimeout {
+            query: params.get("query").map(parse_timeout).transpose()?,
+            mutation: params.get("mutation").map(parse_timeout).transpose()?,
+        }),
+        AstAnnotationParams::None => Err(statement_timeout_error(ct)),
+    }
+}
+
+fn statement_timeout_error(ct: &AstModel<Typed>) -> Diagnostic {
+    Diagnostic {
+        level: Level::Error,
+        message: format!(
+            "@statementTimeout for type {} must be a positive number of milliseconds",
+            ct.name
+        ),
+        code: Some("C000".to_string()),
+        spans: vec![SpanLabel {
+            span: ct.span,
+            style: SpanStyle::Primary,
+            label: None,
+        }],
+    }
+}
+
 fn resolve_composite_type_fields(
     ct: &AstModel<Typed>,
     is_json: bool,
@@ -340,6 +532,24 @@ fn resolve_composite_type_fields(
                         .as_ref()
                         .map(|v| resolve_field_default_type(v, &typ, errors));
 
+                    let previous_column_name = column_annotation_previous_name(field);
+
+                    if previous_column_name.is_some() && column_names.len() != 1 {
+                        errors.push(Diagnostic {
+                            level: Level::Error,
+                            message: format!(
+                                "Cannot use 'previously' in @column for field '{}' that maps to multiple columns",
+                                field.name
+                            ),
+                            code: Some("C000".to_string()),
+                            spans: vec![SpanLabel {
+                                span: field.span,
+                                style: SpanStyle::Primary,
+                                label: None,
+                            }],
+                        });
+                    }
+
                     Some(ResolvedField {
                         name: field.name.clone(),
                         typ,
@@ -355,6 +565,7 @@ fn resolve_composite_type_fields(
                         update_sync,
                         readonly,
                         doc_comments: field.doc_comments.clone(),
+                        previous_column_name,
                         span: field.span,
                     })
                 }
@@ -551,7 +762,7 @@ struct ColumnInfo {
     names: Vec<String>,
     self_column: bool,
     unique_constraints: Vec<String>,
-    indices: Vec<String>,
+    indices: Vec<ResolvedFieldIndex>,
     cardinality: Option<Cardinality>,
 }
 
@@ -593,45 +804,186 @@ fn compute_unique_constraints(field: &AstField<Typed>) -> Result<Vec<String>, Di
 fn compute_indices(
     field: &AstField<Typed>,
     enclosing_type: &AstModel<Typed>,
-) -> Result<Vec<String>, Diagnostic> {
+) -> Result<Vec<ResolvedFieldIndex>, Diagnostic> {
     let index_annotation = field.annotations.get("index");
 
+    let index_error = |message: String| Diagnostic {
+        level: Level::Error,
+        message,
+        code: Some("C000".to_string()),
+        spans: vec![SpanLabel {
+            span: field.span,
+            style: SpanStyle::Primary,
+            label: None,
+        }],
+    };
+
+    let index_computed_name =
+        || format!("{}_{}_idx", enclosing_type.name, field.name).to_ascii_lowercase();
+
     match index_annotation {
         None => Ok(vec![]),
         Some(p) => match p {
             AstAnnotationParams::Single(expr, _) => match expr {
                 AstAnnotationParam::Literal(AstLiteral::String(string, _)) => {
-                    Ok(vec![string.clone()])
+                    Ok(vec![ResolvedFieldIndex::named(string.clone())])
                 }
-                AstAnnotationParam::StringList(string_list, _) => Ok(string_list.clone()),
-                _ => Err(Diagnostic {
+                AstAnnotationParam::StringList(string_list, _) => Ok(string_list
+                    .iter()
+                    .map(|name| ResolvedFieldIndex::named(name.clone()))
+                    .collect()),
+                _ => Err(index_error(
+                    "Not a string nor a string list when specifying index".to_string(),
+                )),
+            },
+            AstAnnotationParams::None => Ok(vec![ResolvedFieldIndex::named(index_computed_name())]),
+            // @index(name="person_active_email", order="desc", where="deleted_at IS NULL", expression="lower(email)")
+            AstAnnotationParams::Map(params, _) => {
+                let string_param = |param_name: &str| match params.get(param_name) {
+                    None => Ok(None),
+                    Some(AstAnnotationParam::Literal(AstLiteral::String(value, _))) => {
+                        Ok(Some(unescape_sql_string(value)))
+                    }
+                    Some(_) => Err(index_error(format!(
+                        "The '{param_name}' parameter of @index must be a string"
+                    ))),
+                };
+
+                let ordering = match string_param("order")?
+                    .map(|order| order.to_ascii_lowercase())
+                    .as_deref()
+                {
+                    None | Some("asc") => Ordering::Asc,
+                    Some("desc") => Ordering::Desc,
+                    Some(order) => {
+                        return Err(index_error(format!(
+                            "Invalid index order '{order}'. Must be 'asc' or 'desc'"
+                        )));
+                    }
+                };
+
+                Ok(vec![ResolvedFieldIndex {
+                    name: string_param("name")?.unwrap_or_else(index_computed_name),
+                    ordering,
+                    expression: string_param("expression")?,
+                    predicate: string_param("where")?,
+                }])
+            }
+        },
+    }
+}
+
+/// Compute the `CHECK` constraints of a type. Constraints from `@check("start_at < end_at")` (or
+/// `@check("...", "...")` for multiple constraints) are named the way Postgres names table
+/// constraints (`<table>_check`, `<table>_check1`, ...). If `validation_checks` is set, `@range`
+/// validations become constraints named the way Postgres names column constraints
+/// (`<table>_<column>_check`). A `@maxLength` also becomes a `char_length` constraint, so that the
+/// limit holds even if the column's type is later widened outside Exograph.
+fn compute_checks(
+    ct: &AstModel<Typed>,
+    table_name: &str,
+    resolved_fields: &[ResolvedField],
+    validation_checks: bool,
+) -> Result<Vec<ResolvedCheck>, Diagnostic> {
+    let expressions = match ct.annotations.get("check") {
+        None => vec![],
+        Some(AstAnnotationParams::Single(expr, _)) => match expr {
+            AstAnnotationParam::Literal(AstLiteral::String(string, _)) => vec![string.clone()],
+            AstAnnotationParam::StringList(string_list, _) => string_list.clone(),
+            _ => {
+                return Err(Diagnostic {
                     level: Level::Error,
-                    message: "Not a string nor a string list when specifying index".to_string(),
+                    message: "Not a string nor a string list when specifying check".to_string(),
                     code: Some("C000".to_string()),
                     spans: vec![SpanLabel {
-                        span: field.span,
+                        span: ct.span,
                         style: SpanStyle::Primary,
                         label: None,
                     }],
-                }),
-            },
-            AstAnnotationParams::None => {
-                let index_computed_name =
-                    format!("{}_{}_idx", enclosing_type.name, field.name).to_ascii_lowercase();
-                Ok(vec![index_computed_name.clone()])
+                });
             }
-            AstAnnotationParams::Map(_, _) => Err(Diagnostic {
-                level: Level::Error,
-                message: "Cannot specify a map when specifying index".to_string(),
-                code: Some("C000".to_string()),
-                spans: vec![SpanLabel {
-                    span: field.span,
-                    style: SpanStyle::Primary,
-                    label: None,
-                }],
-            }),
         },
+        Some(_) => vec![],
+    };
+
+    let mut checks: Vec<ResolvedCheck> = expressions
+        .into_iter()
+        .enumerate()
+        .map(|(index, expression)| ResolvedCheck {
+            name: if index == 0 {
+                format!("{table_name}_check")
+            } else {
+                format!("{table_name}_check{index}")
+            },
+            expression: unescape_sql_string(&expression),
+        })
+        .collect();
+
+    if validation_checks {
+        for field in resolved_fields {
+            let (Some(type_hint), [column_name]) = (&field.type_hint, &field.column_names[..])
+            else {
+                continue;
+            };
+
+            let conditions: Vec<String> = match type_hint.get_type_validation() {
+                Some(TypeValidation::Int(constraints)) => range_conditions(
+                    column_name,
+                    constraints.min.map(|min| min.to_string()),
+                    constraints.max.map(|max| max.to_string()),
+                ),
+                Some(TypeValidation::Float(constraints)) => range_conditions(
+                    column_name,
+                    constraints.min.map(|min| min.to_string()),
+                    constraints.max.map(|max| max.to_string()),
+                ),
+                Some(TypeValidation::String(constraints)) => constraints
+                    .min_length
+                    .map(|min_length| format!("char_length(\"{column_name}\") >= {min_length}"))
+                    .into_iter()
+                    .chain(constraints.max_length.map(|max_length| {
+                        format!("char_length(\"{column_name}\") <= {max_length}")
+                    }))
+                    .collect(),
+                _ => continue,
+            };
+
+            if !conditions.is_empty() {
+                checks.push(ResolvedCheck {
+                    name: format!("{table_name}_{column_name}_check"),
+                    expression: conditions.join(" AND "),
+                });
+            }
+        }
+    }
+
+    Ok(checks)
+}
+
+/// Strings in annotations keep their escapes, but SQL needs the plain characters (for example, the
+/// quotes in `\"userId\"` or the backslash in `'\\'`)
+fn unescape_sql_string(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some(next @ ('"' | '\\'))) => {
+                unescaped.push(next);
+                chars.next();
+            }
+            _ => unescaped.push(c),
+        }
     }
+
+    unescaped
+}
+
+fn range_conditions(column_name: &str, min: Option<String>, max: Option<String>) -> Vec<String> {
+    min.map(|min| format!("\"{column_name}\" >= {min}"))
+        .into_iter()
+        .chain(max.map(|max| format!("\"{column_name}\" <= {max}")))
+        .collect()
 }
 
 fn compute_column_info(
@@ -1035,6 +1387,11 @@ fn column_annotation_mapping(field: &AstField<Typed>) -> Option<ColumnMapping> {
                     } else {
                         Some(ColumnMapping::Map(result))
                     }
+                } else if let Some(AstAnnotationParam::Literal(AstLiteral::String(s, _))) =
+                    map.get("name")
+                {
+                    // Handle the named syntax: @column(name="zip")
+                    Some(ColumnMapping::Single(s.clone()))
                 } else {
                     None
                 }
@@ -1048,6 +1405,17 @@ fn column_annotation_mapping(field: &AstField<Typed>) -> Option<ColumnMapping> {
     })
 }
 
+// The column name prior to a rename, specified as @column(previously="old_name")
+fn column_annotation_previous_name(field: &AstField<Typed>) -> Option<String> {
+    match field.annotations.get("column") {
+        Some(AstAnnotationParams::Map(map, _)) => match map.get("previously") {
+            Some(AstAnnotationParam::Literal(AstLiteral::String(s, _))) => Some(s.clone()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 fn get_matching_field<'a>(
     field: &AstField<Typed>,
     enclosing_type: &AstModel<Typed>,
@@ -1288,7 +1656,11 @@ fn extract_projection_exprs(ct: &AstModel<Typed>) -> Vec<(String, AstProjectionE
 
 #[cfg(test)]
 mod tests {
-    use crate::test_util::create_resolved_system_from_src;
+    use core_model_builder::error::ModelBuildingError;
+    use postgres_core_model::types::StatementTimeout;
+
+    use super::unescape_sql_string;
+    use crate::{resolved_type::ResolvedType, test_util::create_resolved_system_from_src};
 
     use multiplatform_test::multiplatform_test;
     use std::fs::File;
@@ -1816,4 +2188,207 @@ mod tests {
             "column_mapping_validation"
         );
     }
+
+    #[test]
+    fn unescapes_sql_strings() {
+        assert_eq!(unescape_sql_string(r#"\"userId\" > 0"#), r#""userId" > 0"#);
+        assert_eq!(unescape_sql_string(r"slug <> '\\'"), r"slug <> '\'");
+        // Other escapes stay as written (for SQL such as regular expressions)
+        assert_eq!(unescape_sql_string(r"code ~ '^\d+$'"), r"code ~ '^\d+$'");
+    }
+
+    fn statement_timeout(src: &str, type_name: &str) -> StatementTimeout {
+        let resolved = create_resolved_system_from_src(src).unwrap();
+        match resolved.get_by_key(type_name) {
+            Some(ResolvedType::Composite(composite)) => composite.statement_timeout,
+            _ => panic!("{type_name} is not a composite type"),
+        }
+    }
+
+    #[multiplatform_test]
+    fn statement_timeouts() {
+        let src = r#"
+        @postgres
+        module ReportModule {
+            @statementTimeout(30000)
+            type Report {
+                @pk id: Int = autoIncrement()
+            }
+
+            @statementTimeout(query=60000, mutation=5000)
+            type Event {
+                @pk id: Int = autoIncrement()
+            }
+
+            @statementTimeout(query=60000)
+            type Log {
+                @pk id: Int = autoIncrement()
+            }
+
+            type Venue {
+                @pk id: Int = autoIncrement()
+            }
+        }
+        "#;
+
+        assert_eq!(
+            statement_timeout(src, "Report"),
+            StatementTimeout {
+                query: Some(30000),
+                mutation: Some(30000)
+            }
+        );
+        assert_eq!(
+            statement_timeout(src, "Event"),
+            StatementTimeout {
+                query: Some(60000),
+                mutation: Some(5000)
+            }
+        );
+        assert_eq!(
+            statement_timeout(src, "Log"),
+            StatementTimeout {
+                query: Some(60000),
+                mutation: None
+            }
+        );
+        assert_eq!(statement_timeout(src, "Venue"), StatementTimeout::default());
+    }
+
+    #[multiplatform_test]
+    fn invalid_statement_timeouts() {
+        assert_resolved_err!(
+            r#"
+        @postgres
+        module ReportModule {
+            @statementTimeout(0)
+            type Report {
+                @pk id: Int = autoIncrement()
+            }
+        }
+        "#,
+            "A zero timeout must be rejected"
+        );
+
+        assert_resolved_err!(
+            r#"
+        @postgres
+        module ReportModule {
+            @statementTimeout(mutation="slow")
+            type Report {
+                @pk id: Int = autoIncrement()
+            }
+        }
+        "#,
+            "A non-numeric timeout must be rejected"
+        );
+    }
+
+    /// The session variables of the `Todo` type in the source (or the error messages)
+    fn session_variables(src: &str) -> Result<Vec<(String, String)>, String> {
+        match create_resolved_system_from_src(src) {
+            Ok(resolved) => match resolved.get_by_key("Todo") {
+                Some(ResolvedType::Composite(todo)) => Ok(todo
+                    .session_variables
+                    .iter()
+                    .map(|variable| {
+                        (
+                            variable.name.clone(),
+                            format!(
+                                "{}.{}",
+                                variable.selection.context_name, variable.selection.path.0
+                            ),
+                        )
+                    })
+                    .collect()),
+                _ => panic!("Todo is not a composite type"),
+            },
+            Err(ModelBuildingError::Diagnosis(diagnostics)) => Err(diagnostics
+                .into_iter()
+                .map(|diagnostic| diagnostic.message)
+                .collect::<Vec<_>>()
+                .join("\n")),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    fn session_context_src(session_context: &str) -> String {
+        format!(
+            r#"
+        context AuthContext {{
+            @jwt("sub") id: Int
+            @jwt tenantId: String
+        }}
+
+        @postgres({session_context})
+        module TodoDatabase {{
+            type Todo {{
+                @pk id: Int = autoIncrement()
+                title: String
+            }}
+        }}
+        "#
+        )
+    }
+
+    #[multiplatform_test]
+    fn session_context() {
+        assert_eq!(
+            session_variables(&session_context_src(
+                "sessionContext=[AuthContext.id, AuthContext.tenantId]"
+            )),
+            Ok(vec![
+                (
+                    "exo.auth_context_id".to_string(),
+                    "AuthContext.id".to_string()
+                ),
+                (
+                    "exo.auth_context_tenant_id".to_string(),
+                    "AuthContext.tenantId".to_string()
+                ),
+            ])
+        );
+
+        assert_eq!(
+            session_variables(&session_context_src("schema=\"todos\"")),
+            Ok(vec![])
+        );
+    }
+
+    #[multiplatform_test]
+    fn invalid_session_context() {
+        let error = |session_context: &str| {
+            session_variables(&session_context_src(session_context)).unwrap_err()
+        };
+
+        assert!(
+            error("sessionContext=[AuthContext.id, tenantId]")
+                .contains("'tenantId' in sessionContext must be a context field")
+        );
+        assert!(
+            error("sessionContext=[UserContext.id]")
+                .contains("Context 'UserContext' in sessionContext not found")
+        );
+        assert!(
+            error("sessionContext=[AuthContext.email]")
+                .contains("Field 'email' of context 'AuthContext' in sessionContext not found")
+        );
+        // Only the invalid entries are reported
+        assert_eq!(
+            error("sessionContext=[AuthContext.id, AuthContext.email]")
+                .lines()
+                .count(),
+            1
+        );
+
+        for not_a_list in [
+            "sessionContext=\"AuthContext.id\"",
+            "sessionContext=[AuthContext]",
+        ] {
+            assert!(
+                error(not_a_list).contains("sessionContext must be a list of context fields"),
+                "{not_a_list}"
+            );
+        }
+    }
 }
@@ -1,8 +1,11 @@
 use std::collections::HashMap;
 
 use codemap::Span;
-use exo_sql_pg::SchemaObjectName;
-use postgres_core_model::types::EntityRepresentation;
+use exo_sql_pg::{Ordering, SchemaObjectName};
+use postgres_core_model::{
+    subsystem::SessionVariable,
+    types::{EntityRepresentation, StatementTimeout},
+};
 use serde::{Deserialize, Serialize};
 
 use core_model::{
@@ -11,8 +14,8 @@ use core_model::{
     mapped_arena::MappedArena,
     primitive_type::PrimitiveType,
     types::{
-        FieldType, FloatConstraints, IntConstraints, Named, StringConstraints, TypeValidation,
-        TypeValidationProvider,
+        CacheHint, Cost, FieldType, FloatConstraints, IntConstraints, Named, StringConstraints,
+        TypeValidation, TypeValidationProvider,
     },
 };
 use core_model_builder::{
@@ -63,11 +66,23 @@ pub struct ResolvedCompositeType {
 
     pub fields: Vec<ResolvedField>,
     pub table_name: SchemaObjectName,
+    /// `CHECK` constraints from the `@check` annotation and (if enabled) from field validations
+    pub checks: Vec<ResolvedCheck>,
     pub access: ResolvedAccess,
     /// User-defined projection expressions from `@projection` annotation.
     /// Each entry is (projection_name, expression).
     pub projection_exprs: Vec<(String, AstProjectionExpr)>,
     pub doc_comments: Option<String>,
+    /// The statement timeouts from the `@statementTimeout` annotation
+    pub statement_timeout: StatementTimeout,
+    /// How long query results of this type may be cached (from the `@cache` annotation)
+    pub cache_hint: Option<CacheHint>,
+    /// The cost of resolving a field of this type (from the `@cost` annotation)
+    pub cost: Option<Cost>,
+    /// Publish row changes to the change feed (from the module's `changeFeed` option)
+    pub change_feed: bool,
+    /// The session variables to set from the context (from the module's `sessionContext` option)
+    pub session_variables: Vec<SessionVariable>,
     #[serde(skip_serializing)]
     #[serde(skip_deserializing)]
     #[serde(default = "default_span")]
@@ -94,12 +109,13 @@ pub struct ResolvedField {
     pub access: ResolvedAccess,
     pub type_hint: Option<SerializableTypeHint>,
     pub unique_constraints: Vec<String>,
-    pub indices: Vec<String>,
+    pub indices: Vec<ResolvedFieldIndex>,
     pub cardinality: Option<Cardinality>,
     pub default_value: Option<ResolvedFieldDefault>,
     pub update_sync: bool,
     pub readonly: bool,
     pub doc_comments: Option<String>,
+    pub previous_column_name: Option<String>, // the column name before a rename (from `@column(previously=...)`)
     #[serde(skip_serializing)]
     #[serde(skip_deserializing)]
     #[serde(default = "default_span")]
@@ -107,6 +123,33 @@ pub struct ResolvedField {
     pub span: Span,
 }
 
+/// A `CHECK` constraint on the type's table
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ResolvedCheck {
+    pub name: String,
+    pub expression: String,
+}
+
+/// An index that a field participates in (from its `@index` annotation)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ResolvedFieldIndex {
+    pub name: String,
+    pub ordering: Ordering,
+    pub expression: Option<String>, // an expression to index instead of the field's column (such as `lower(email)`)
+    pub predicate: Option<String>,  // the `WHERE` clause of a partial index
+}
+
+impl ResolvedFieldIndex {
+    pub fn named(name: String) -> Self {
+        Self {
+            name,
+            ordering: Ordering::Asc,
+            expression: None,
+            predicate: None,
+        }
+    }
+}
+
 // TODO: dedup?
 impl ResolvedField {
     pub fn get_is_auto_increment(&self) -> bool {
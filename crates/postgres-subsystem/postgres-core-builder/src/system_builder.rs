@@ -20,7 +20,7 @@ use postgres_core_model::{
     aggregate::AggregateType,
     order::OrderByParameterType,
     predicate::PredicateParameterType,
-    subsystem::PostgresCoreSubsystem,
+    subsystem::{PostgresCoreSubsystem, SessionVariable},
     types::{EntityType, PostgresPrimitiveType},
     vector_distance::VectorDistanceType,
 };
@@ -33,11 +33,12 @@ use crate::{
     aggregate_type_builder, database_builder, order_by_builder, predicate_builder, type_builder,
 };
 
-use crate::resolved_type::ResolvedTypeEnv;
+use crate::resolved_type::{ResolvedType, ResolvedTypeEnv};
 
 pub fn build(resolved_env: &ResolvedTypeEnv) -> Result<SystemContextBuilding, ModelBuildingError> {
     let mut building = SystemContextBuilding {
         database: database_builder::build(resolved_env)?,
+        session_variables: build_session_variables(resolved_env),
         ..SystemContextBuilding::default()
     };
 
@@ -59,6 +60,23 @@ fn build_shallow(resolved_env: &ResolvedTypeEnv, building: &mut SystemContextBui
     order_by_builder::build_shallow(resolved_env, building);
 }
 
+/// The session variables of all modules (a transaction may span types from multiple modules)
+fn build_session_variables(resolved_env: &ResolvedTypeEnv) -> Vec<SessionVariable> {
+    let mut session_variables: Vec<SessionVariable> = vec![];
+
+    for (_, typ) in resolved_env.resolved_types.iter() {
+        if let ResolvedType::Composite(composite) = typ {
+            for variable in &composite.session_variables {
+                if !session_variables.contains(variable) {
+                    session_variables.push(variable.clone());
+                }
+            }
+        }
+    }
+
+    session_variables
+}
+
 fn build_expanded(
     resolved_env: &ResolvedTypeEnv,
     building: &mut SystemContextBuilding,
@@ -90,6 +108,8 @@ pub struct SystemContextBuilding {
         Mutex<AccessExpressionsBuilding<PrecheckAccessPrimitiveExpression>>,
 
     pub database: Database,
+
+    pub session_variables: Vec<SessionVariable>,
 }
 
 impl SystemContextBuilding {
@@ -103,6 +123,7 @@ impl SystemContextBuilding {
             order_by_types: self.order_by_types.values(),
 
             database: self.database,
+            session_variables: self.session_variables,
 
             database_access_expressions: self
                 .database_access_expressions
@@ -151,6 +151,9 @@ fn create_shallow_type(
                 access: restrictive_access(),
                 projections: vec![],
                 doc_comments: composite.doc_comments.clone(),
+                statement_timeout: composite.statement_timeout,
+                cache_hint: composite.cache_hint,
+                cost: composite.cost,
             };
 
             building.entity_types.add(&resolved_type.name(), typ);
@@ -61,3 +61,9 @@ pub fn single_create_description(entity_name: &str) -> String {
 pub fn collection_create_description(entity_name: &str) -> String {
     format!("Create multiple `{entity_name}`s with the provided data")
 }
+
+pub fn change_subscription_description(entity_name: &str) -> String {
+    format!(
+        "Get each `{entity_name}` that is created or updated and matches the provided `where` filter"
+    )
+}
@@ -0,0 +1,7 @@
+DROP TRIGGER exograph_notify_on_insert_todos on "todos";
+
+DROP TRIGGER exograph_notify_on_update_todos on "todos";
+
+DROP TRIGGER exograph_notify_on_delete_todos on "todos";
+
+DROP FUNCTION exograph_notify_todos;
@@ -0,0 +1,7 @@
+CREATE FUNCTION exograph_notify_todos() RETURNS TRIGGER AS $$ DECLARE changed RECORD; BEGIN IF TG_OP = 'DELETE' THEN changed := OLD; ELSE changed := NEW; END IF; PERFORM pg_notify('exograph_changes', json_build_object('schema', TG_TABLE_SCHEMA, 'table', TG_TABLE_NAME, 'op', TG_OP, 'pk', json_build_object('id', changed."id"))::text); RETURN NULL; END; $$ language 'plpgsql';
+
+CREATE TRIGGER exograph_notify_on_insert_todos AFTER INSERT ON todos FOR EACH ROW EXECUTE FUNCTION exograph_notify_todos();
+
+CREATE TRIGGER exograph_notify_on_update_todos AFTER UPDATE ON todos FOR EACH ROW EXECUTE FUNCTION exograph_notify_todos();
+
+CREATE TRIGGER exograph_notify_on_delete_todos AFTER DELETE ON todos FOR EACH ROW EXECUTE FUNCTION exograph_notify_todos();
@@ -0,0 +1,3 @@
+ALTER TYPE "priority" ADD VALUE 'MEDIUM' AFTER 'LOW';
+
+ALTER TYPE "priority" ADD VALUE 'URGENT' AFTER 'HIGH';
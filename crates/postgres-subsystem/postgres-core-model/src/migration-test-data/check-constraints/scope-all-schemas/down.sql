@@ -0,0 +1,7 @@
+ALTER TABLE "events" DROP CONSTRAINT IF EXISTS "events_check";
+
+ALTER TABLE "events" DROP CONSTRAINT IF EXISTS "events_title_check";
+
+ALTER TABLE "events" DROP CONSTRAINT IF EXISTS "events_temperature_check";
+
+ALTER TABLE "events" DROP CONSTRAINT IF EXISTS "events_price_check";
\ No newline at end of file
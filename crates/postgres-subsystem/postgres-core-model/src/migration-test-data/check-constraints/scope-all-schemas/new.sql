@@ -0,0 +1,16 @@
+CREATE TABLE "events" (
+    "id" SERIAL PRIMARY KEY,
+    "title" VARCHAR(100) NOT NULL,
+    "start_at" INT NOT NULL,
+    "end_at" INT NOT NULL,
+    "temperature" SMALLINT NOT NULL,
+    "price" DOUBLE PRECISION NOT NULL
+);
+
+ALTER TABLE "events" ADD CONSTRAINT "events_check" CHECK (start_at < end_at);
+
+ALTER TABLE "events" ADD CONSTRAINT "events_title_check" CHECK (char_length("title") <= 100);
+
+ALTER TABLE "events" ADD CONSTRAINT "events_temperature_check" CHECK ("temperature" >= -10 AND "temperature" <= 40);
+
+ALTER TABLE "events" ADD CONSTRAINT "events_price_check" CHECK ("price" >= 0 AND "price" <= 99.5);
\ No newline at end of file
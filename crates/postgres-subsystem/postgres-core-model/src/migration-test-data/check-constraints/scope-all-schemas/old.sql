@@ -0,0 +1,8 @@
+CREATE TABLE "events" (
+    "id" SERIAL PRIMARY KEY,
+    "title" VARCHAR(100) NOT NULL,
+    "start_at" INT NOT NULL,
+    "end_at" INT NOT NULL,
+    "temperature" SMALLINT NOT NULL,
+    "price" DOUBLE PRECISION NOT NULL
+);
\ No newline at end of file
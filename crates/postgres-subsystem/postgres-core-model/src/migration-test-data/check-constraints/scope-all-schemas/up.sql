@@ -0,0 +1,7 @@
+ALTER TABLE "events" ADD CONSTRAINT "events_check" CHECK (start_at < end_at);
+
+ALTER TABLE "events" ADD CONSTRAINT "events_title_check" CHECK (char_length("title") <= 100);
+
+ALTER TABLE "events" ADD CONSTRAINT "events_temperature_check" CHECK ("temperature" >= -10 AND "temperature" <= 40);
+
+ALTER TABLE "events" ADD CONSTRAINT "events_price_check" CHECK ("price" >= 0 AND "price" <= 99.5);
\ No newline at end of file
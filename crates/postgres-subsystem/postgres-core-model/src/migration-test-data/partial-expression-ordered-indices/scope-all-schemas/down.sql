@@ -0,0 +1,5 @@
+DROP INDEX "users_active_email";
+
+DROP INDEX "users_name";
+
+DROP INDEX "user_createdat_idx";
\ No newline at end of file
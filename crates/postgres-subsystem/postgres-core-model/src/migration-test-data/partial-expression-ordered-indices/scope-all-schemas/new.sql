@@ -0,0 +1,14 @@
+CREATE TABLE "users" (
+    "id" SERIAL PRIMARY KEY,
+    "email" TEXT NOT NULL,
+    "last_name" TEXT NOT NULL,
+    "first_name" TEXT NOT NULL,
+    "created_at" TIMESTAMP WITH TIME ZONE NOT NULL,
+    "deleted_at" TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX "users_active_email" ON "users" ((lower(email))) WHERE deleted_at IS NULL;
+
+CREATE INDEX "users_name" ON "users" ("last_name", "first_name");
+
+CREATE INDEX "user_createdat_idx" ON "users" ("created_at" DESC);
\ No newline at end of file
@@ -0,0 +1,8 @@
+CREATE TABLE "users" (
+    "id" SERIAL PRIMARY KEY,
+    "email" TEXT NOT NULL,
+    "last_name" TEXT NOT NULL,
+    "first_name" TEXT NOT NULL,
+    "created_at" TIMESTAMP WITH TIME ZONE NOT NULL,
+    "deleted_at" TIMESTAMP WITH TIME ZONE
+);
\ No newline at end of file
@@ -0,0 +1,5 @@
+CREATE INDEX "users_active_email" ON "users" ((lower(email))) WHERE deleted_at IS NULL;
+
+CREATE INDEX "users_name" ON "users" ("last_name", "first_name");
+
+CREATE INDEX "user_createdat_idx" ON "users" ("created_at" DESC);
\ No newline at end of file
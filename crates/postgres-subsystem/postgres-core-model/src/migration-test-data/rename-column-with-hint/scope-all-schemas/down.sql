@@ -0,0 +1,3 @@
+-- ALTER TABLE "concerts" DROP COLUMN "name";
+
+ALTER TABLE "concerts" ADD "title" TEXT NOT NULL;
\ No newline at end of file
@@ -0,0 +1,4 @@
+CREATE TABLE "concerts" (
+    "id" SERIAL PRIMARY KEY,
+    "title" TEXT NOT NULL
+);
\ No newline at end of file
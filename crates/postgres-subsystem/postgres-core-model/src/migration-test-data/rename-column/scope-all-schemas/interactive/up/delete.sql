@@ -0,0 +1,3 @@
+ALTER TABLE "concerts" DROP COLUMN "title";
+
+ALTER TABLE "concerts" ADD "name" TEXT NOT NULL;
@@ -0,0 +1 @@
+ALTER TABLE "concerts" RENAME COLUMN "title" TO "name";
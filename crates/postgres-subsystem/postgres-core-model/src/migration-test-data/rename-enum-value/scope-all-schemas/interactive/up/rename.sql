@@ -0,0 +1 @@
+ALTER TYPE "priority" RENAME VALUE 'MEDIUM' TO 'NORMAL';
@@ -0,0 +1,7 @@
+-- ALTER TYPE "priority" RENAME TO "priority_previous";
+-- CREATE TYPE "priority" AS ENUM ('LOW', 'NORMAL', 'HIGH');
+-- ALTER TABLE "todos" ALTER COLUMN "priority" TYPE "priority" USING "priority"::text::"priority";
+-- ALTER TABLE "todos" ALTER COLUMN "priority_with_default" DROP DEFAULT;
+-- ALTER TABLE "todos" ALTER COLUMN "priority_with_default" TYPE "priority" USING "priority_with_default"::text::"priority";
+-- ALTER TABLE "todos" ALTER COLUMN "priority_with_default" SET DEFAULT 'LOW';
+-- DROP TYPE "priority_previous";
@@ -3,8 +3,8 @@ use std::path::{Path, PathBuf};
 use crate::subsystem::PostgresCoreSubsystem;
 use exo_sql_pg_connect::testing::with_client;
 use exo_sql_pg_schema::{
-    DatabaseSpec, Migration, MigrationScope, MigrationScopeMatches, MigrationStatement,
-    PredefinedMigrationInteraction, migrate_interactively,
+    DatabaseSpec, Migration, MigrationMode, MigrationScope, MigrationScopeMatches,
+    MigrationStatement, PredefinedMigrationInteraction, migrate_interactively,
 };
 use sqlparser::dialect::PostgreSqlDialect;
 use sqlparser::parser::Parser;
@@ -352,9 +352,23 @@ async fn assert_interactive_migration(
         print!("\t\t\t\t{}:", interaction_name);
 
         let migration = if kind.kind_str() == "up" {
-            migrate_interactively(old_system.clone(), new_system.clone(), scope, &interaction).await
+            migrate_interactively(
+                old_system.clone(),
+                new_system.clone(),
+                scope,
+                &interaction,
+                MigrationMode::Standard,
+            )
+            .await
         } else {
-            migrate_interactively(new_system.clone(), old_system.clone(), scope, &interaction).await
+            migrate_interactively(
+                new_system.clone(),
+                old_system.clone(),
+                scope,
+                &interaction,
+                MigrationMode::Standard,
+            )
+            .await
         }
         .map_err(|e| format!("Failed to migrate: {} {}", interaction_name, e))?;
 
@@ -426,11 +440,10 @@ fn assert_sql_eq(
             statements: actual
                 .statements
                 .iter()
-                .map(|stmt| MigrationStatement {
-                    statement: stmt.statement.clone(),
-                    is_destructive: false,
-                })
+                .map(|stmt| MigrationStatement::new(stmt.statement.clone(), false))
                 .collect::<Vec<_>>(),
+            operations: vec![],
+            issues: vec![],
         };
 
         let mut buffer = std::io::Cursor::new(vec![]);
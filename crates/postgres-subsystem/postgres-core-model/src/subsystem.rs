@@ -9,7 +9,7 @@
 
 use core_model::{
     access::AccessPredicateExpression,
-    context_type::{ContextContainer, ContextType},
+    context_type::{ContextContainer, ContextSelection, ContextType},
     mapped_arena::{MappedArena, SerializableSlab},
 };
 use core_plugin_shared::system_serializer::{
@@ -42,6 +42,18 @@ pub struct PostgresCoreSubsystem {
         SerializableSlab<AccessPredicateExpression<PrecheckAccessPrimitiveExpression>>,
 
     pub database: Database,
+
+    /// The session variables to set in each transaction (from the `sessionContext` option of the
+    /// modules)
+    pub session_variables: Vec<SessionVariable>,
+}
+
+/// A setting such as `exo.auth_context_id` set (with `SET LOCAL`) to a context value, so that
+/// column defaults and triggers can read it through `current_setting`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SessionVariable {
+    pub name: String,
+    pub selection: ContextSelection,
 }
 
 impl SystemSerializer for PostgresCoreSubsystem {
@@ -72,6 +84,8 @@ impl Default for PostgresCoreSubsystem {
             precheck_expressions: SerializableSlab::new(),
 
             database: Database::default(),
+
+            session_variables: vec![],
         }
     }
 }
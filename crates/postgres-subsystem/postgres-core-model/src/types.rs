@@ -14,7 +14,7 @@ use crate::vector_distance::VectorDistanceField;
 
 use common::value::Val;
 use core_model::context_type::ContextSelection;
-use core_model::types::TypeValidation;
+use core_model::types::{CacheHint, Cost, TypeValidation};
 use core_model::{
     mapped_arena::{SerializableSlab, SerializableSlabIndex},
     types::{FieldType, Named},
@@ -92,6 +92,20 @@ pub struct EntityType {
     pub access: Access,
     pub projections: Vec<ResolvedProjection>,
     pub doc_comments: Option<String>,
+    /// The statement timeouts for operations on this type (from `@statementTimeout`)
+    pub statement_timeout: StatementTimeout,
+    /// How long query results of this type may be cached (from `@cache`)
+    pub cache_hint: Option<CacheHint>,
+    /// The cost of resolving a field of this type (from `@cost`)
+    pub cost: Option<Cost>,
+}
+
+/// The statement timeouts (in milliseconds) for the operations that return an entity type
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StatementTimeout {
+    /// For queries (and subscriptions)
+    pub query: Option<u64>,
+    pub mutation: Option<u64>,
 }
 
 /// Encapsulates a field on an entity type (mirros how `ColumnId` is structured)
@@ -1,3 +1,6 @@
+use std::time::Duration;
+
+use common::env_const::EXO_STATEMENT_TIMEOUT;
 use exo_env::Environment;
 use exo_sql_pg_connect::{DatabaseClientManager, PgBackend, TransactionMode};
 use thiserror::Error;
@@ -12,22 +15,45 @@ pub async fn create_database_executor(
         #[cfg(feature = "network")]
         {
             use common::env_const::{
-                DATABASE_URL, EXO_CHECK_CONNECTION_ON_STARTUP, EXO_CONNECTION_POOL_SIZE,
-                EXO_POSTGRES_READ_WRITE, EXO_POSTGRES_URL,
+                EXO_CHECK_CONNECTION_ON_STARTUP, EXO_CONNECTION_POOL_ACQUIRE_TIMEOUT,
+                EXO_CONNECTION_POOL_IDLE_TIMEOUT, EXO_CONNECTION_POOL_MAX_LIFETIME,
+                EXO_CONNECTION_POOL_MIN_IDLE, EXO_CONNECTION_POOL_SIZE,
+                EXO_CONNECTION_POOL_TEST_QUERY, EXO_CONNECTION_STARTUP_RETRIES,
+                EXO_POSTGRES_READ_URL, EXO_POSTGRES_READ_WRITE,
             };
+            use exo_sql_pg_connect::PoolOptions;
 
-            let url = env
-                .get(EXO_POSTGRES_URL)
-                .or(env.get(DATABASE_URL))
-                .ok_or_else(|| {
-                    DatabaseHelperError::Config("Env EXO_POSTGRES_URL not set".to_string())
-                })?;
-            let pool_size: Option<usize> = env
-                .get(EXO_CONNECTION_POOL_SIZE)
-                .and_then(|s| s.parse().ok());
+            let url = postgres_url(env)?;
+            let pool_options = PoolOptions {
+                max_size: parse_env(env, EXO_CONNECTION_POOL_SIZE, "a number")?,
+                min_idle: parse_env(env, EXO_CONNECTION_POOL_MIN_IDLE, "a number")?,
+                idle_timeout: parse_env(
+                    env,
+                    EXO_CONNECTION_POOL_IDLE_TIMEOUT,
+                    "a number of seconds",
+                )?
+                .map(Duration::from_secs),
+                max_lifetime: parse_env(
+                    env,
+                    EXO_CONNECTION_POOL_MAX_LIFETIME,
+                    "a number of seconds",
+                )?
+                .map(Duration::from_secs),
+                acquire_timeout: parse_env(
+                    env,
+                    EXO_CONNECTION_POOL_ACQUIRE_TIMEOUT,
+                    "a number of seconds",
+                )?
+                .map(Duration::from_secs),
+                test_query: env
+                    .get(EXO_CONNECTION_POOL_TEST_QUERY)
+                    .filter(|query| !query.trim().is_empty()),
+            };
             let check_connection = env
                 .enabled(EXO_CHECK_CONNECTION_ON_STARTUP, true)
                 .map_err(|e| DatabaseHelperError::BoxedError(Box::new(e)))?;
+            let startup_retries: u32 =
+                parse_env(env, EXO_CONNECTION_STARTUP_RETRIES, "a number")?.unwrap_or(0);
             let transaction_mode = if env
                 .enabled(EXO_POSTGRES_READ_WRITE, false)
                 .map_err(|e| DatabaseHelperError::BoxedError(Box::new(e)))?
@@ -37,9 +63,40 @@ pub async fn create_database_executor(
                 TransactionMode::ReadOnly
             };
 
-            DatabaseClientManager::from_url(&url, check_connection, pool_size, transaction_mode)
-                .await
-                .map_err(|e| DatabaseHelperError::BoxedError(Box::new(e)))?
+            let primary = DatabaseClientManager::from_url_with_pool_options(
+                &url,
+                false,
+                &pool_options,
+                transaction_mode,
+            )
+            .await
+            .map_err(|e| DatabaseHelperError::BoxedError(Box::new(e)))?;
+
+            // Retry the startup check (for example, while the database is still starting up)
+            if check_connection {
+                primary
+                    .check_connection(startup_retries)
+                    .await
+                    .map_err(|e| DatabaseHelperError::BoxedError(Box::new(e)))?;
+            }
+
+            // Don't check the replica connections on startup: an unavailable replica shouldn't
+            // prevent the server from starting (its reads go to the primary instead)
+            let mut replicas = vec![];
+            for read_url in split_read_urls(&env.get(EXO_POSTGRES_READ_URL).unwrap_or_default()) {
+                replicas.push(
+                    DatabaseClientManager::from_url_with_pool_options(
+                        &read_url,
+                        false,
+                        &pool_options,
+                        TransactionMode::ReadOnly,
+                    )
+                    .await
+                    .map_err(|e| DatabaseHelperError::BoxedError(Box::new(e)))?,
+                );
+            }
+
+            primary.with_read_replicas(replicas)
         }
 
         #[cfg(not(feature = "network"))]
@@ -48,7 +105,75 @@ pub async fn create_database_executor(
             panic!("Postgres URL feature is not enabled");
         }
     };
-    Ok(PgBackend::new(database_client))
+
+    let statement_timeout = parse_env(env, EXO_STATEMENT_TIMEOUT, "a number of milliseconds")?
+        .map(Duration::from_millis);
+
+    Ok(PgBackend::new(database_client).with_statement_timeout(statement_timeout))
+}
+
+/// Start listening for the changes published by the tables with a change feed (see
+/// [`ChangeFeed`](exo_sql_pg_connect::ChangeFeed))
+#[cfg(feature = "network")]
+pub fn start_change_feed(
+    env: &dyn Environment,
+) -> Result<exo_sql_pg_connect::ChangeFeed, DatabaseHelperError> {
+    let url = postgres_url(env)?;
+
+    exo_sql_pg_connect::ChangeFeed::start(&url)
+        .map_err(|e| DatabaseHelperError::BoxedError(Box::new(e)))
+}
+
+#[cfg(feature = "network")]
+fn postgres_url(env: &dyn Environment) -> Result<String, DatabaseHelperError> {
+    use common::env_const::{DATABASE_URL, EXO_POSTGRES_URL};
+
+    env.get(EXO_POSTGRES_URL)
+        .or(env.get(DATABASE_URL))
+        .ok_or_else(|| DatabaseHelperError::Config("Env EXO_POSTGRES_URL not set".to_string()))
+}
+
+/// Parse an optional numeric environment variable (`expected` describes the value for the error
+/// message)
+fn parse_env<T: std::str::FromStr>(
+    env: &dyn Environment,
+    name: &str,
+    expected: &str,
+) -> Result<Option<T>, DatabaseHelperError> {
+    env.get(name)
+        .map(|value| {
+            value.trim().parse::<T>().map_err(|_| {
+                DatabaseHelperError::Config(format!(
+                    "Env {name} must be {expected} (got '{value}')"
+                ))
+            })
+        })
+        .transpose()
+}
+
+/// Split a comma-separated list of URLs. A comma that doesn't start a new URL is part of the
+/// previous one (as in a multi-host URL such as `postgres://host1,host2/db`).
+#[cfg(feature = "network")]
+fn split_read_urls(value: &str) -> Vec<String> {
+    let mut urls: Vec<String> = vec![];
+
+    for part in value.split(',') {
+        let trimmed = part.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let starts_url = trimmed.starts_with("postgres://") || trimmed.starts_with("postgresql://");
+
+        match urls.last_mut() {
+            Some(url) if !starts_url => {
+                url.push(',');
+                url.push_str(trimmed);
+            }
+            _ => urls.push(trimmed.to_string()),
+        }
+    }
+
+    urls
 }
 
 #[derive(Error, Debug)]
@@ -59,3 +184,44 @@ pub enum DatabaseHelperError {
     #[error("Boxed error: {0}")]
     BoxedError(#[from] Box<dyn std::error::Error + Send + Sync + 'static>),
 }
+
+#[cfg(all(test, feature = "network"))]
+mod tests {
+    use common::env_const::EXO_CONNECTION_POOL_SIZE;
+    use exo_env::MapEnvironment;
+
+    use super::{parse_env, split_read_urls};
+
+    #[test]
+    fn read_urls() {
+        assert_eq!(
+            split_read_urls("postgres://replica1/db, postgresql://host2,host3:5433/db"),
+            vec!["postgres://replica1/db", "postgresql://host2,host3:5433/db"]
+        );
+        assert!(split_read_urls("").is_empty());
+    }
+
+    #[test]
+    fn pool_size() {
+        let pool_size = |value: &str| {
+            parse_env::<usize>(
+                &MapEnvironment::from([(EXO_CONNECTION_POOL_SIZE, value)]),
+                EXO_CONNECTION_POOL_SIZE,
+                "a number",
+            )
+        };
+
+        assert_eq!(pool_size(" 10 ").unwrap(), Some(10));
+        // A typo is an error rather than falling back to the default
+        assert!(pool_size("1O").is_err());
+        assert_eq!(
+            parse_env::<usize>(
+                &MapEnvironment::default(),
+                EXO_CONNECTION_POOL_SIZE,
+                "a number"
+            )
+            .unwrap(),
+            None
+        );
+    }
+}
@@ -3,6 +3,8 @@ pub mod cast;
 pub mod column_path_util;
 pub mod database_helper;
 pub mod order_by_mapper;
+pub mod persisted_query_store;
 pub mod postgres_execution_error;
 pub mod predicate_mapper;
 pub mod predicate_util;
+pub mod session_variables;
@@ -0,0 +1,103 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! A [PersistedQueryStore] that keeps the registered queries in a Postgres table (so they are
+//! shared between server instances and survive restarts).
+//!
+//! The server manages the table itself (schema migrations leave it alone).
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use core_resolver::persisted_queries::{PersistedQueryStore, PersistedQueryStoreError};
+use exo_sql_pg_connect::{DatabaseClient, PgBackend};
+
+const CREATE_TABLE: &str = r#"CREATE TABLE IF NOT EXISTS "exograph_persisted_queries" ("hash" TEXT PRIMARY KEY, "query" TEXT NOT NULL, "last_used" TIMESTAMPTZ NOT NULL DEFAULT now())"#;
+const SELECT_QUERY: &str = r#"UPDATE "exograph_persisted_queries" SET "last_used" = now() WHERE "hash" = $1 RETURNING "query""#;
+const INSERT_QUERY: &str = r#"INSERT INTO "exograph_persisted_queries" ("hash", "query") VALUES ($1, $2) ON CONFLICT ("hash") DO UPDATE SET "last_used" = now()"#;
+const EVICT_QUERIES: &str = r#"DELETE FROM "exograph_persisted_queries" WHERE "hash" IN (SELECT "hash" FROM "exograph_persisted_queries" ORDER BY "last_used" DESC OFFSET $1)"#;
+
+/// A store that keeps the most recently used queries (evicting the least recently used ones when
+/// full)
+pub struct PostgresPersistedQueryStore {
+    executor: Arc<PgBackend>,
+    capacity: usize,
+}
+
+impl PostgresPersistedQueryStore {
+    /// Create the store (and the table, unless the store is to hold no queries)
+    pub async fn create(
+        executor: Arc<PgBackend>,
+        capacity: usize,
+    ) -> Result<Self, PersistedQueryStoreError> {
+        let store = Self { executor, capacity };
+
+        if capacity > 0 {
+            store
+                .client()
+                .await?
+                .execute(CREATE_TABLE, &[])
+                .await
+                .map_err(|e| PersistedQueryStoreError(Box::new(e)))?;
+        }
+
+        Ok(store)
+    }
+
+    async fn client(&self) -> Result<DatabaseClient, PersistedQueryStoreError> {
+        self.executor
+            .database_client()
+            .get_client()
+            .await
+            .map_err(|e| PersistedQueryStoreError(Box::new(e)))
+    }
+}
+
+#[async_trait]
+impl PersistedQueryStore for PostgresPersistedQueryStore {
+    async fn get(&self, query_hash: &str) -> Result<Option<String>, PersistedQueryStoreError> {
+        if self.capacity == 0 {
+            return Ok(None);
+        }
+
+        // Mark the query as the most recently used while fetching it
+        let rows = self
+            .client()
+            .await?
+            .query(SELECT_QUERY, &[&query_hash])
+            .await
+            .map_err(|e| PersistedQueryStoreError(Box::new(e)))?;
+
+        rows.first()
+            .map(|row| row.try_get::<_, String>(0))
+            .transpose()
+            .map_err(|e| PersistedQueryStoreError(Box::new(e)))
+    }
+
+    async fn put(&self, query_hash: &str, query: &str) -> Result<(), PersistedQueryStoreError> {
+        if self.capacity == 0 {
+            return Ok(());
+        }
+
+        let client = self.client().await?;
+
+        client
+            .execute(INSERT_QUERY, &[&query_hash, &query])
+            .await
+            .map_err(|e| PersistedQueryStoreError(Box::new(e)))?;
+
+        let capacity = i64::try_from(self.capacity).unwrap_or(i64::MAX);
+        client
+            .execute(EVICT_QUERIES, &[&capacity])
+            .await
+            .map_err(|e| PersistedQueryStoreError(Box::new(e)))?;
+
+        Ok(())
+    }
+}
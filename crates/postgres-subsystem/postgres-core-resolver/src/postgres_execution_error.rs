@@ -35,6 +35,9 @@ pub enum PostgresExecutionError {
     #[error("Not authorized")]
     Authorization,
 
+    #[error("Operation timed out or was canceled")]
+    Canceled,
+
     #[error("{0} {1}")]
     WithContext(String, #[source] Box<PostgresExecutionError>),
 
@@ -53,6 +56,7 @@ impl PostgresExecutionError {
     pub fn user_error_message(&self) -> String {
         match self {
             PostgresExecutionError::Authorization => "Not authorized".to_string(),
+            PostgresExecutionError::Canceled => self.to_string(),
             PostgresExecutionError::Validation(_, _) => self.to_string(),
             PostgresExecutionError::CastError(e) => {
                 error!("Cast error: {}", e);
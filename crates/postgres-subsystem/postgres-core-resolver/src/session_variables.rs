@@ -0,0 +1,50 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! Shared utilities for setting the session variables (from the `sessionContext` option of the
+//! modules) between GraphQL and RPC resolvers.
+
+use common::{context::RequestContext, value::Val};
+use core_resolver::context_extractor::ContextExtractor;
+use postgres_core_model::subsystem::PostgresCoreSubsystem;
+
+use crate::postgres_execution_error::PostgresExecutionError;
+
+/// Compute the values of the session variables for the request (as pairs of the variable name and
+/// its value). A variable whose context value is absent is left unset (so `current_setting(name,
+/// true)` returns null or an empty string).
+pub async fn compute_session_variables<'a>(
+    subsystem: &PostgresCoreSubsystem,
+    request_context: &'a RequestContext<'a>,
+) -> Result<Vec<(String, String)>, PostgresExecutionError> {
+    let mut session_variables = vec![];
+
+    for variable in &subsystem.session_variables {
+        let value = subsystem
+            .extract_context_selection(request_context, &variable.selection)
+            .await?;
+
+        let value = match value {
+            None | Some(Val::Null) => continue,
+            Some(Val::String(value) | Val::Enum(value)) => value.clone(),
+            Some(Val::Bool(value)) => value.to_string(),
+            Some(Val::Number(value)) => value.to_string(),
+            Some(_) => {
+                return Err(PostgresExecutionError::Generic(format!(
+                    "The context value for the session variable '{}' must be a scalar",
+                    variable.name
+                )));
+            }
+        };
+
+        session_variables.push((variable.name.clone(), value));
+    }
+
+    Ok(session_variables)
+}
@@ -17,6 +17,7 @@ mod naming;
 mod plugin;
 mod query_builder;
 mod reference_input_type_builder;
+mod subscription_builder;
 mod system_builder;
 mod type_builder;
 mod update_mutation_builder;
@@ -91,6 +91,18 @@ impl<T: ToPlural> ToPostgresQueryName for T {
     }
 }
 
+/// A type that can generate GraphQL subscription names.
+pub(super) trait ToPostgresSubscriptionName {
+    /// Change subscription name (e.g. `concertChanged`)
+    fn change_subscription(&self) -> String;
+}
+
+impl<T: ToPlural> ToPostgresSubscriptionName for T {
+    fn change_subscription(&self) -> String {
+        format!("{}Changed", self.pk_query())
+    }
+}
+
 fn to_create(name: &str) -> String {
     format!("create{name}")
 }
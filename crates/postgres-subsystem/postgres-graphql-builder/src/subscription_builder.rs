@@ -0,0 +1,89 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use core_model::{
+    access::AccessPredicateExpression,
+    mapped_arena::MappedArena,
+    types::{BaseOperationReturnType, OperationReturnType},
+};
+
+use postgres_core_builder::resolved_type::ResolvedType;
+use postgres_core_model::{
+    doc_comments,
+    predicate::PredicateParameter,
+    types::{EntityRepresentation, EntityType},
+};
+use postgres_graphql_model::subscription::{ChangeSubscription, ChangeSubscriptionParameters};
+
+use postgres_core_builder::shallow::Shallow;
+
+use super::naming::ToPostgresSubscriptionName;
+use super::query_builder::collection_predicate_param;
+use super::system_builder::SystemContextBuilding;
+
+/// Build a change subscription (such as `todoChanged`) for each type whose module enables the
+/// change feed. Since the subscription re-reads the changed row by its primary key, types without
+/// one don't get a subscription.
+pub fn build_shallow(types: &MappedArena<ResolvedType>, building: &mut SystemContextBuilding) {
+    for (_, typ) in types.iter() {
+        if let ResolvedType::Composite(c) = &typ {
+            if !c.change_feed
+                || c.representation != EntityRepresentation::Managed
+                || c.pk_fields().is_empty()
+            {
+                continue;
+            }
+
+            let entity_type_id = building.get_entity_type_id(c.name.as_str()).unwrap();
+            let entity_type = &building.core_subsystem.entity_types[entity_type_id];
+            if read_access_is_false(entity_type, building) {
+                continue;
+            }
+
+            let subscription = ChangeSubscription {
+                name: c.change_subscription(),
+                parameters: ChangeSubscriptionParameters {
+                    predicate_param: PredicateParameter::shallow(),
+                },
+                return_type: OperationReturnType::Plain(BaseOperationReturnType {
+                    associated_type_id: entity_type_id,
+                    type_name: c.name.clone(),
+                }),
+                doc_comments: Some(doc_comments::change_subscription_description(&c.name)),
+            };
+
+            building
+                .subscriptions
+                .add(&subscription.name.to_owned(), subscription);
+        }
+    }
+}
+
+pub fn build_expanded(building: &mut SystemContextBuilding) {
+    for (_, entity_type) in building.core_subsystem.entity_types.iter() {
+        let operation_name = entity_type.change_subscription();
+
+        if let Some(existing_subscription) = building.subscriptions.get_by_key_mut(&operation_name)
+        {
+            existing_subscription.parameters.predicate_param =
+                collection_predicate_param(entity_type, &building.core_subsystem.predicate_types);
+        }
+    }
+}
+
+fn read_access_is_false(entity_type: &EntityType, building: &SystemContextBuilding) -> bool {
+    matches!(
+        building
+            .core_subsystem
+            .database_access_expressions
+            .lock()
+            .unwrap()[entity_type.access.read],
+        AccessPredicateExpression::BooleanLiteral(false)
+    )
+}
@@ -15,13 +15,14 @@ use core_model_builder::error::ModelBuildingError;
 use postgres_graphql_model::{
     mutation::PostgresMutation,
     query::{AggregateQuery, CollectionQuery, UniqueQuery},
+    subscription::ChangeSubscription,
     subsystem::PostgresGraphQLSubsystem,
     types::MutationType,
 };
 
 use postgres_core_model::types::EntityType;
 
-use super::{mutation_builder, query_builder, type_builder};
+use super::{mutation_builder, query_builder, subscription_builder, type_builder};
 use postgres_core_builder::resolved_type::ResolvedTypeEnv;
 
 pub fn build(
@@ -44,6 +45,7 @@ pub fn build(
             unique_queries: building.unique_queries,
             mutation_types: building.mutation_types.values(),
             mutations: building.mutations,
+            subscriptions: building.subscriptions,
 
             pk_queries_map: building.pk_queries_map,
             collection_queries_map: building.collection_queries_map,
@@ -73,6 +75,7 @@ fn build_shallow(resolved_env: &ResolvedTypeEnv, building: &mut SystemContextBui
     // type_builder::build_shallow to have run
     query_builder::build_shallow(&resolved_env.resolved_types, building);
     mutation_builder::build_shallow(&resolved_env.resolved_types, building);
+    subscription_builder::build_shallow(&resolved_env.resolved_types, building);
 }
 
 fn build_expanded(
@@ -82,9 +85,10 @@ fn build_expanded(
     // First fully build the types.
     type_builder::build_expanded(resolved_env, building)?;
 
-    // Finally expand queries, mutations, and module methods
+    // Finally expand queries, mutations, subscriptions, and module methods
     query_builder::build_expanded(resolved_env, building);
     mutation_builder::build_expanded(building)?;
+    subscription_builder::build_expanded(building);
 
     Ok(())
 }
@@ -106,6 +110,8 @@ pub struct SystemContextBuilding {
     pub mutation_types: MappedArena<MutationType>,
     pub mutations: MappedArena<PostgresMutation>,
 
+    pub subscriptions: MappedArena<ChangeSubscription>,
+
     pub core_subsystem: Arc<postgres_core_builder::SystemContextBuilding>,
 }
 
@@ -19,10 +19,13 @@ use core_model::type_normalization::{
     FieldDefinitionProvider, InputValueProvider, TypeDefinitionProvider, default_positioned,
     default_positioned_name,
 };
+use core_model::types::DirectivesProvider;
 use postgres_core_model::relation::{OneToManyRelation, PostgresRelation};
 
 impl TypeDefinitionProvider<PostgresGraphQLSubsystem> for AggregateType {
     fn type_definition(&self, system: &PostgresGraphQLSubsystem) -> TypeDefinition {
+        let entity_type = system.core_subsystem.entity_types.get(self.underlying_type);
+
         let kind = {
             let fields: Vec<_> = self
                 .fields
@@ -37,15 +40,17 @@ impl TypeDefinitionProvider<PostgresGraphQLSubsystem> for AggregateType {
         };
         TypeDefinition {
             extend: false,
-            description: system
-                .core_subsystem
-                .entity_types
-                .get(self.underlying_type)
-                .map(|entity_type| {
-                    default_positioned(format!("An aggregate for the `{}` type.", entity_type.name))
-                }),
+            description: entity_type.map(|entity_type| {
+                default_positioned(format!("An aggregate for the `{}` type.", entity_type.name))
+            }),
             name: default_positioned_name(&self.name),
-            directives: vec![],
+            // Aggregates may be cached as long as the entities they aggregate
+            directives: entity_type
+                .and_then(|entity_type| entity_type.cache_hint)
+                .iter()
+                .flat_map(|cache_hint| cache_hint.get_directives())
+                .map(default_positioned)
+                .collect(),
             kind,
         }
     }
@@ -14,6 +14,7 @@ pub mod operation;
 pub mod order;
 pub mod predicate;
 pub mod query;
+pub mod subscription;
 pub mod subsystem;
 pub mod types;
 pub mod vector_distance;
@@ -24,7 +24,8 @@
 //! ```
 //!
 //! Queries like `todos`, `todo`, and `todoAgg` as well as mutations like `createTodo`, `updateTodo`, and `deleteTodo` will be
-//! generated by the postgres subsystem builder.
+//! generated by the postgres subsystem builder. If the module enables `changeFeed`, a subscription like `todoChanged` will be
+//! generated as well.
 
 use std::fmt::Debug;
 
@@ -37,7 +38,7 @@ use postgres_core_model::types::EntityType;
 
 use crate::types::Operation;
 
-/// An operation such as a query or mutation.
+/// An operation such as a query, mutation, or subscription.
 ///
 /// * `P` - This parameter allows differentiating between, for example,
 ///   [`UniqueQuery`](`super::query::UniqueQuery`), [`AggregateQuery`](super::query::AggregateQuery), and
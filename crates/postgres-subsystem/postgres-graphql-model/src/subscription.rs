@@ -0,0 +1,32 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use postgres_core_model::predicate::PredicateParameter;
+use serde::{Deserialize, Serialize};
+
+use core_model::type_normalization::Parameter;
+
+use super::operation::{OperationParameters, PostgresOperation};
+
+/// Subscription that delivers an entity each time it is created or updated, such as
+/// `todoChanged(where: { completed: { eq: false } })`
+pub type ChangeSubscription = PostgresOperation<ChangeSubscriptionParameters>;
+
+/// Change subscription parameters
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ChangeSubscriptionParameters {
+    /// The predicate parameter such as `where: { completed: { eq: false } }`
+    pub predicate_param: PredicateParameter,
+}
+
+impl OperationParameters for ChangeSubscriptionParameters {
+    fn introspect(&self) -> Vec<&dyn Parameter> {
+        vec![&self.predicate_param]
+    }
+}
@@ -14,6 +14,7 @@ use async_graphql_parser::types::{FieldDefinition, TypeDefinition};
 use super::mutation::PostgresMutation;
 use crate::{
     query::{AggregateQuery, CollectionQuery, UniqueQuery},
+    subscription::ChangeSubscription,
     types::MutationType,
 };
 use core_model::{
@@ -48,6 +49,9 @@ pub struct PostgresGraphQLSubsystem {
     pub mutation_types: SerializableSlab<MutationType>, // create, update, delete input types such as `PersonUpdateInput`
     pub mutations: MappedArena<PostgresMutation>,
 
+    // subscription related
+    pub subscriptions: MappedArena<ChangeSubscription>,
+
     #[serde(skip)]
     pub core_subsystem: Arc<PostgresCoreSubsystem>,
 }
@@ -88,6 +92,13 @@ impl PostgresGraphQLSubsystem {
             .collect()
     }
 
+    pub fn schema_subscriptions(&self) -> Vec<FieldDefinition> {
+        self.subscriptions
+            .iter()
+            .map(|(_, subscription)| subscription.field_definition(self))
+            .collect()
+    }
+
     pub fn schema_types(&self) -> Vec<TypeDefinition> {
         let mut all_type_definitions = vec![];
 
@@ -158,6 +169,7 @@ impl Default for PostgresGraphQLSubsystem {
             unique_queries: MappedArena::default(),
             mutation_types: SerializableSlab::new(),
             mutations: MappedArena::default(),
+            subscriptions: MappedArena::default(),
 
             pk_queries_map: HashMap::new(),
             collection_queries_map: HashMap::new(),
@@ -116,7 +116,13 @@ impl TypeDefinitionProvider<PostgresGraphQLSubsystem> for EntityType {
             extend: false,
             description: self.doc_comments.clone().map(default_positioned),
             name: default_positioned_name(&self.name),
-            directives: vec![],
+            directives: self
+                .cache_hint
+                .iter()
+                .flat_map(|cache_hint| cache_hint.get_directives())
+                .chain(self.cost.iter().flat_map(|cost| cost.get_directives()))
+                .map(default_positioned)
+                .collect(),
             kind,
         }
     }
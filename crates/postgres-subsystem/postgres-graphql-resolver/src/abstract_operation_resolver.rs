@@ -7,26 +7,42 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::time::Duration;
+
 use exo_sql_pg::{DatabaseBackend, PgAbstractOperation, database_error::DatabaseError};
 
 use common::context::RequestContext;
 use core_resolver::{QueryResponse, QueryResponseBody};
 
-use postgres_core_resolver::postgres_execution_error::PostgresExecutionError;
+use postgres_core_resolver::{
+    postgres_execution_error::PostgresExecutionError, session_variables::compute_session_variables,
+};
 
 use super::PostgresSubsystemResolver;
 
 pub async fn resolve_operation<'e>(
     op: PgAbstractOperation,
+    statement_timeout: Option<Duration>,
     subsystem_resolver: &'e PostgresSubsystemResolver,
     request_context: &'e RequestContext<'e>,
 ) -> Result<QueryResponse, PostgresExecutionError> {
+    let session_variables = compute_session_variables(
+        &subsystem_resolver.subsystem.core_subsystem,
+        request_context,
+    )
+    .await?;
+
     let mut tx = request_context
         .system_context
         .transaction_holder
         .try_lock()
         .unwrap();
 
+    if let Some(statement_timeout) = statement_timeout {
+        tx.override_statement_timeout(statement_timeout);
+    }
+    tx.set_session_variables(session_variables);
+
     let result = subsystem_resolver
         .executor
         .execute(
@@ -36,8 +52,10 @@ pub async fn resolve_operation<'e>(
         )
         .await;
 
-    if let Err(DatabaseError::Precheck(_)) = result {
-        return Err(PostgresExecutionError::Authorization);
+    match result {
+        Err(DatabaseError::Precheck(_)) => return Err(PostgresExecutionError::Authorization),
+        Err(DatabaseError::Canceled(_)) => return Err(PostgresExecutionError::Canceled),
+        _ => {}
     }
 
     let mut result = result.map_err(PostgresExecutionError::Postgres)?;
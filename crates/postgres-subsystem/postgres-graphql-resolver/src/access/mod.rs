@@ -2,4 +2,4 @@ mod database_solver;
 mod precheck_solver;
 
 #[cfg(test)]
-mod test_util;
+pub(crate) mod test_util;
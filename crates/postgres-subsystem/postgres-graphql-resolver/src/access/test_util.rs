@@ -43,7 +43,7 @@ impl RequestHead for TestRequest {
     }
 }
 
-pub(super) struct TestRouter {}
+pub(crate) struct TestRouter {}
 
 #[async_trait::async_trait]
 impl<'request> Router<PlainRequestPayload<'request>> for TestRouter {
@@ -57,7 +57,7 @@ impl<'request> Router<PlainRequestPayload<'request>> for TestRouter {
 
 const REQUEST: TestRequest = TestRequest {};
 
-pub(super) fn test_request_context<'a>(
+pub(crate) fn test_request_context<'a>(
     test_values: Value,
     system_router: &'a (dyn for<'request> Router<PlainRequestPayload<'request>> + Send + Sync),
     env: &'a dyn Environment,
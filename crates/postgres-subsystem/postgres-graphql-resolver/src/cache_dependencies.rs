@@ -0,0 +1,593 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! What the result of a query depends on (and which tables a mutation writes to) for the resolver
+//! cache (see [core_resolver::resolver_cache]).
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use common::{
+    context::{ContextExtractionError, RequestContext},
+    value::Val,
+};
+use core_model::{
+    access::{AccessPredicateExpression, CommonAccessPrimitiveExpression},
+    context_type::ContextSelection,
+    mapped_arena::SerializableSlabIndex,
+};
+use core_resolver::{
+    context_extractor::ContextExtractor, resolver_cache::QueryDependencies,
+    validation::field::ValidatedField,
+};
+use exo_sql_pg::{PhysicalColumnPath, TableId};
+use postgres_core_model::{
+    access::DatabaseAccessPrimitiveExpression, relation::PostgresRelation,
+    subsystem::PostgresCoreSubsystem, types::EntityType,
+};
+
+/// The dependencies of a query on the given entity type: the tables of the entity types it
+/// selects or filters by (directly or through the access rules) and the values of the context
+/// fields referenced by the access rules of those types (and by the session variables)
+pub(crate) async fn query_dependencies<'a>(
+    entity_id: SerializableSlabIndex<EntityType>,
+    field: &ValidatedField,
+    subsystem: &PostgresCoreSubsystem,
+    request_context: &'a RequestContext<'a>,
+) -> Result<QueryDependencies, ContextExtractionError> {
+    let mut collector = DependencyCollector::new(subsystem);
+    collector.add_selection(entity_id, field);
+
+    let entity_ids: Vec<_> = collector.entity_ids.iter().copied().collect();
+    for entity_id in entity_ids {
+        let entity = &subsystem.entity_types[entity_id];
+
+        collector.add_access_expression(&subsystem.database_access_expressions[entity.access.read]);
+        for entity_field in &entity.fields {
+            collector.add_access_expression(
+                &subsystem.database_access_expressions[entity_field.access.read],
+            );
+        }
+    }
+
+    // Session variables may be used by row-level security policies (and views)
+    for variable in &subsystem.session_variables {
+        collector.add_context_selection(&variable.selection);
+    }
+
+    let mut context = BTreeMap::new();
+    for (context_name, field_name) in &collector.context_fields {
+        let selection = ContextSelection {
+            context_name: context_name.clone(),
+            path: (field_name.clone(), vec![]),
+        };
+        let value = subsystem
+            .extract_context_selection(request_context, &selection)
+            .await?;
+
+        context.insert(
+            format!("{context_name}.{field_name}"),
+            value.cloned().unwrap_or(Val::Null),
+        );
+    }
+
+    Ok(QueryDependencies {
+        context,
+        tables: collector.table_names(),
+    })
+}
+
+/// The tables that a mutation on the given entity type writes to: the entity's table, the tables
+/// of any nested creates and updates (in the `data` argument), and for a delete, the tables whose
+/// rows refer to the deleted rows, directly or through other such rows (since the database may
+/// cascade the delete to them)
+pub(crate) fn mutation_tables(
+    entity_id: SerializableSlabIndex<EntityType>,
+    field: &ValidatedField,
+    is_delete: bool,
+    subsystem: &PostgresCoreSubsystem,
+) -> BTreeSet<String> {
+    let mut collector = DependencyCollector::new(subsystem);
+    collector.entity_ids.insert(entity_id);
+
+    if let Some(data) = field.arguments.get("data") {
+        collector.add_argument(entity_id, data);
+    }
+
+    if is_delete {
+        collector.add_referencing_entities(entity_id);
+    }
+
+    collector.table_names()
+}
+
+struct DependencyCollector<'a> {
+    subsystem: &'a PostgresCoreSubsystem,
+    entity_ids: HashSet<SerializableSlabIndex<EntityType>>,
+    /// Tables referenced by the access rules (other than those of the entity types)
+    table_ids: HashSet<TableId>,
+    /// Pairs of the context name and the field name such as `("AuthContext", "id")`
+    context_fields: BTreeSet<(String, String)>,
+}
+
+impl<'a> DependencyCollector<'a> {
+    fn new(subsystem: &'a PostgresCoreSubsystem) -> Self {
+        Self {
+            subsystem,
+            entity_ids: HashSet::new(),
+            table_ids: HashSet::new(),
+            context_fields: BTreeSet::new(),
+        }
+    }
+
+    /// Add the entity type of a field along with those of its subfields (and of its arguments)
+    fn add_selection(
+        &mut self,
+        entity_id: SerializableSlabIndex<EntityType>,
+        field: &ValidatedField,
+    ) {
+        self.entity_ids.insert(entity_id);
+
+        for argument in field.arguments.values() {
+            self.add_argument(entity_id, argument);
+        }
+
+        for subfield in &field.subfields {
+            if let Some(foreign_entity_id) = self.relation_target(entity_id, &subfield.name) {
+                self.add_selection(foreign_entity_id, subfield);
+            }
+        }
+    }
+
+    /// Add the entity types whose rows refer to those of the given entity type (through a
+    /// one-to-many relation), along with the ones referring to them, and so on
+    fn add_referencing_entities(&mut self, entity_id: SerializableSlabIndex<EntityType>) {
+        let subsystem = self.subsystem;
+
+        for entity_field in &subsystem.entity_types[entity_id].fields {
+            if let PostgresRelation::OneToMany(relation) = &entity_field.relation
+                && self.entity_ids.insert(relation.foreign_entity_id)
+            {
+                self.add_referencing_entities(relation.foreign_entity_id);
+            }
+        }
+    }
+
+    /// Add the entity types referred to by an argument. Predicates, orderings, and data refer to
+    /// related entities by the field names, such as `venue` in `where: {venue: {name: {eq:
+    /// "..."}}}`. Any other object (such as `{eq: "..."}` or the elements of `and: [...]`) stays
+    /// with the same entity.
+    fn add_argument(&mut self, entity_id: SerializableSlabIndex<EntityType>, argument: &Val) {
+        match argument {
+            Val::Object(fields) => {
+                for (name, value) in fields {
+                    match self.relation_target(entity_id, name) {
+                        Some(foreign_entity_id) => {
+                            self.entity_ids.insert(foreign_entity_id);
+                            self.add_argument(foreign_entity_id, value);
+                        }
+                        None => self.add_argument(entity_id, value),
+                    }
+                }
+            }
+            Val::List(values) => {
+                for value in values {
+                    self.add_argument(entity_id, value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn add_access_expression(
+        &mut self,
+        expression: &'a AccessPredicateExpression<DatabaseAccessPrimitiveExpression>,
+    ) {
+        expression.visit_primitives(&mut |primitive| match primitive {
+            DatabaseAccessPrimitiveExpression::Column(column_path, _) => {
+                self.add_column_path(column_path)
+            }
+            DatabaseAccessPrimitiveExpression::Function(column_path, function_call) => {
+                self.add_column_path(column_path);
+                self.add_access_expression(&function_call.expr);
+            }
+            DatabaseAccessPrimitiveExpression::Common(
+                CommonAccessPrimitiveExpression::ContextSelection(selection),
+            ) => self.add_context_selection(selection),
+            DatabaseAccessPrimitiveExpression::Common(_) => {}
+        });
+    }
+
+    /// Add the tables along a column path such as `self.venue.published`
+    fn add_column_path(&mut self, column_path: &PhysicalColumnPath) {
+        let (head, tail) = column_path.split_head();
+        self.table_ids.insert(head.self_table_id());
+
+        if let Some(tail) = tail {
+            self.add_column_path(&tail);
+        }
+    }
+
+    /// Add the context field of a selection. We depend on the whole field even if the selection
+    /// picks a part of it (such as `AuthContext.roles.contains("admin")`).
+    fn add_context_selection(&mut self, selection: &ContextSelection) {
+        self.context_fields
+            .insert((selection.context_name.clone(), selection.path.0.clone()));
+    }
+
+    /// The entity type of a relation field (including an aggregate field such as `concertsAgg`)
+    fn relation_target(
+        &self,
+        entity_id: SerializableSlabIndex<EntityType>,
+        field_name: &str,
+    ) -> Option<SerializableSlabIndex<EntityType>> {
+        let entity = &self.subsystem.entity_types[entity_id];
+
+        let relation = entity
+            .fields
+            .iter()
+            .find(|field| field.name == field_name)
+            .map(|field| &field.relation)
+            .or_else(|| {
+                entity
+                    .agg_fields
+                    .iter()
+                    .find(|field| field.name == field_name)
+                    .and_then(|field| field.relation.as_ref())
+            })?;
+
+        match relation {
+            PostgresRelation::ManyToOne { relation, .. } => Some(relation.foreign_entity_id),
+            PostgresRelation::OneToMany(relation) => Some(relation.foreign_entity_id),
+            PostgresRelation::Scalar { .. } | PostgresRelation::Embedded => None,
+        }
+    }
+
+    fn table_names(&self) -> BTreeSet<String> {
+        let entity_table_ids = self
+            .entity_ids
+            .iter()
+            .map(|entity_id| self.subsystem.entity_types[*entity_id].table_id);
+
+        entity_table_ids
+            .chain(self.table_ids.iter().copied())
+            .map(|table_id| {
+                self.subsystem
+                    .database
+                    .get_table(table_id)
+                    .name
+                    .fully_qualified_name()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql_value::{Name, indexmap::IndexMap};
+    use exo_env::MapEnvironment;
+    use postgres_graphql_model::subsystem::PostgresGraphQLSubsystem;
+    use serde_json::{Value, json};
+
+    use crate::access::test_util::{TestRouter, test_request_context};
+
+    use super::*;
+
+    const CONCERT_MODEL: &str = r#"
+        context AuthContext {
+            @test("id") id: Int
+            @test("role") role: String
+        }
+
+        @postgres
+        module ConcertModule {
+            @access(query=self.published || AuthContext.role == "admin", mutation=true)
+            type Venue {
+                @pk id: Int = autoIncrement()
+                name: String
+                published: Boolean
+                concerts: Set<Concert>?
+            }
+
+            @access(query=self.tickets.some(t => t.ownerId == AuthContext.id), mutation=true)
+            type Concert {
+                @pk id: Int = autoIncrement()
+                title: String
+                venue: Venue
+                tickets: Set<Ticket>?
+            }
+
+            @access(true)
+            type Ticket {
+                @pk id: Int = autoIncrement()
+                ownerId: Int
+                concert: Concert
+            }
+        }
+    "#;
+
+    async fn create_system(model: &str) -> PostgresGraphQLSubsystem {
+        crate::test_utils::create_postgres_system_from_str(model, "test.exo".to_string())
+            .await
+            .unwrap()
+    }
+
+    fn entity_id(
+        subsystem: &PostgresCoreSubsystem,
+        name: &str,
+    ) -> SerializableSlabIndex<EntityType> {
+        subsystem
+            .entity_types
+            .iter()
+            .find(|(_, entity)| entity.name == name)
+            .map(|(entity_id, _)| entity_id)
+            .unwrap()
+    }
+
+    fn field(name: &str, arguments: Value, subfields: Vec<ValidatedField>) -> ValidatedField {
+        let Value::Object(arguments) = arguments else {
+            panic!("Arguments must be an object");
+        };
+
+        ValidatedField {
+            alias: None,
+            name: Name::new(name),
+            arguments: arguments
+                .into_iter()
+                .map(|(name, value)| (name, Val::from(value)))
+                .collect::<IndexMap<_, _>>(),
+            subfields,
+        }
+    }
+
+    fn tables(names: &[&str]) -> BTreeSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn context(values: &[(&str, Value)]) -> BTreeMap<String, Val> {
+        values
+            .iter()
+            .map(|(name, value)| (name.to_string(), Val::from(value.clone())))
+            .collect()
+    }
+
+    async fn dependencies_of(
+        subsystem: &PostgresGraphQLSubsystem,
+        entity_name: &str,
+        field: &ValidatedField,
+        test_values: Value,
+    ) -> QueryDependencies {
+        let core_subsystem = &subsystem.core_subsystem;
+        let env = MapEnvironment::default();
+        let router = TestRouter {};
+        let request_context = test_request_context(test_values, &router, &env);
+
+        query_dependencies(
+            entity_id(core_subsystem, entity_name),
+            field,
+            core_subsystem,
+            &request_context,
+        )
+        .await
+        .unwrap()
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn function_call_access() {
+        let subsystem = create_system(CONCERT_MODEL).await;
+
+        // The access rule of `Concert` reads the tickets (and the user's id)
+        let dependencies = dependencies_of(
+            &subsystem,
+            "Concert",
+            &field("concerts", json!({}), vec![field("id", json!({}), vec![])]),
+            json!({"id": 1, "role": "user"}),
+        )
+        .await;
+
+        assert_eq!(dependencies.tables, tables(&["concerts", "tickets"]));
+        assert_eq!(
+            dependencies.context,
+            context(&[("AuthContext.id", json!(1))])
+        );
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn nested_relation() {
+        let subsystem = create_system(CONCERT_MODEL).await;
+
+        let dependencies = dependencies_of(
+            &subsystem,
+            "Venue",
+            &field(
+                "venues",
+                json!({}),
+                vec![
+                    field("id", json!({}), vec![]),
+                    field(
+                        "concerts",
+                        json!({}),
+                        vec![field("title", json!({}), vec![])],
+                    ),
+                ],
+            ),
+            json!({"id": 1, "role": "admin"}),
+        )
+        .await;
+
+        assert_eq!(
+            dependencies.tables,
+            tables(&["concerts", "tickets", "venues"])
+        );
+        assert_eq!(
+            dependencies.context,
+            context(&[
+                ("AuthContext.id", json!(1)),
+                ("AuthContext.role", json!("admin"))
+            ])
+        );
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn where_on_related_entity() {
+        let subsystem = create_system(CONCERT_MODEL).await;
+
+        // Filtering by the venue makes the result depend on the venues (and their access rule)
+        let dependencies = dependencies_of(
+            &subsystem,
+            "Concert",
+            &field(
+                "concerts",
+                json!({"where": {"venue": {"name": {"eq": "Hall"}}}}),
+                vec![field("id", json!({}), vec![])],
+            ),
+            json!({"id": 1, "role": "admin"}),
+        )
+        .await;
+
+        assert_eq!(
+            dependencies.tables,
+            tables(&["concerts", "tickets", "venues"])
+        );
+        assert_eq!(
+            dependencies.context,
+            context(&[
+                ("AuthContext.id", json!(1)),
+                ("AuthContext.role", json!("admin"))
+            ])
+        );
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn session_variables() {
+        let subsystem = create_system(
+            r#"
+                context AuthContext {
+                    @test("tenant") tenant: String
+                }
+
+                @postgres(sessionContext=[AuthContext.tenant])
+                module NoteModule {
+                    @access(true)
+                    type Note {
+                        @pk id: Int = autoIncrement()
+                        title: String
+                    }
+                }
+            "#,
+        )
+        .await;
+
+        // Row-level security policies may use the tenant, even if the access rules don't
+        let dependencies = dependencies_of(
+            &subsystem,
+            "Note",
+            &field("notes", json!({}), vec![field("title", json!({}), vec![])]),
+            json!({"tenant": "acme"}),
+        )
+        .await;
+
+        assert_eq!(dependencies.tables, tables(&["notes"]));
+        assert_eq!(
+            dependencies.context,
+            context(&[("AuthContext.tenant", json!("acme"))])
+        );
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn nested_create() {
+        let subsystem = create_system(CONCERT_MODEL).await;
+        let core_subsystem = &subsystem.core_subsystem;
+
+        let create_venue = field(
+            "createVenue",
+            json!({
+                "data": {
+                    "name": "Hall",
+                    "published": true,
+                    "concerts": [{"title": "Encore", "tickets": [{"ownerId": 1}]}]
+                }
+            }),
+            vec![field("id", json!({}), vec![])],
+        );
+
+        assert_eq!(
+            mutation_tables(
+                entity_id(core_subsystem, "Venue"),
+                &create_venue,
+                false,
+                core_subsystem
+            ),
+            tables(&["concerts", "tickets", "venues"])
+        );
+
+        let create_concert = field(
+            "createConcert",
+            json!({"data": {"title": "Encore", "venue": {"id": 1}}}),
+            vec![field("id", json!({}), vec![])],
+        );
+
+        // Referring to an existing venue still counts (even though the venue doesn't change)
+        assert_eq!(
+            mutation_tables(
+                entity_id(core_subsystem, "Concert"),
+                &create_concert,
+                false,
+                core_subsystem
+            ),
+            tables(&["concerts", "venues"])
+        );
+    }
+
+    #[cfg_attr(not(target_family = "wasm"), tokio::test)]
+    #[cfg_attr(target_family = "wasm", wasm_bindgen_test::wasm_bindgen_test)]
+    async fn delete() {
+        let subsystem = create_system(CONCERT_MODEL).await;
+        let core_subsystem = &subsystem.core_subsystem;
+
+        let delete_field =
+            |name: &str| field(name, json!({"id": 1}), vec![field("id", json!({}), vec![])]);
+
+        // Deleting a venue may delete its concerts and, in turn, their tickets
+        assert_eq!(
+            mutation_tables(
+                entity_id(core_subsystem, "Venue"),
+                &delete_field("deleteVenue"),
+                true,
+                core_subsystem
+            ),
+            tables(&["concerts", "tickets", "venues"])
+        );
+
+        assert_eq!(
+            mutation_tables(
+                entity_id(core_subsystem, "Concert"),
+                &delete_field("deleteConcert"),
+                true,
+                core_subsystem
+            ),
+            tables(&["concerts", "tickets"])
+        );
+
+        // Nothing refers to tickets
+        assert_eq!(
+            mutation_tables(
+                entity_id(core_subsystem, "Ticket"),
+                &delete_field("deleteTicket"),
+                true,
+                core_subsystem
+            ),
+            tables(&["tickets"])
+        );
+    }
+}
@@ -7,18 +7,20 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-pub use resolver::PostgresSubsystemResolver;
+pub use resolver::{ChangeEvents, PostgresSubsystemResolver};
 
 mod abstract_operation_resolver;
 mod access;
 mod aggregate_query;
 mod auth_util;
+mod cache_dependencies;
 mod create_data_param_mapper;
 mod limit_offset_mapper;
 mod operation_resolver;
 mod order_by_mapper;
 mod postgres_mutation;
 mod postgres_query;
+mod postgres_subscription;
 mod predicate_mapper;
 mod resolver;
 mod sql_mapper;
@@ -0,0 +1,89 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use common::context::RequestContext;
+use core_resolver::validation::field::ValidatedField;
+use exo_sql_pg::{
+    AbstractPredicate, ColumnPath, PgAbstractPredicate, PgAbstractSelect, PhysicalColumnPath,
+};
+use exo_sql_pg_connect::ChangeEvent;
+use postgres_core_resolver::{
+    cast::literal_column_path, postgres_execution_error::PostgresExecutionError,
+    predicate_util::json_to_val,
+};
+use postgres_graphql_model::{
+    subscription::ChangeSubscription, subsystem::PostgresGraphQLSubsystem,
+};
+
+use crate::{postgres_query::compute_select, predicate_mapper::compute_predicate};
+
+/// Compute the select for the row changed by the event. The select combines the subscription's
+/// `where` argument and the entity's read access with the row's primary key, so it yields no row
+/// if the changed row doesn't match the subscription or isn't accessible to the subscriber.
+pub(crate) async fn compute_change_select<'a>(
+    subscription: &'a ChangeSubscription,
+    event: &ChangeEvent,
+    field: &'a ValidatedField,
+    request_context: &'a RequestContext<'a>,
+    subsystem: &'a PostgresGraphQLSubsystem,
+) -> Result<PgAbstractSelect, PostgresExecutionError> {
+    let predicate = compute_predicate(
+        &[&subscription.parameters.predicate_param],
+        &field.arguments,
+        subsystem,
+        request_context,
+    )
+    .await?;
+
+    compute_select(
+        AbstractPredicate::and(predicate, pk_predicate(subscription, event, subsystem)?),
+        None,
+        None,
+        None,
+        &subscription.return_type,
+        &field.subfields,
+        subsystem,
+        request_context,
+    )
+    .await
+}
+
+fn pk_predicate(
+    subscription: &ChangeSubscription,
+    event: &ChangeEvent,
+    subsystem: &PostgresGraphQLSubsystem,
+) -> Result<PgAbstractPredicate, PostgresExecutionError> {
+    let database = &subsystem.core_subsystem.database;
+    let entity_type = subscription
+        .return_type
+        .typ(&subsystem.core_subsystem.entity_types);
+
+    database
+        .get_pk_column_ids(entity_type.table_id)
+        .into_iter()
+        .try_fold(AbstractPredicate::True, |acc, pk_column_id| {
+            let pk_column = pk_column_id.get_column(database);
+            let value = event.pk.get(&pk_column.name).ok_or_else(|| {
+                PostgresExecutionError::Generic(format!(
+                    "The change event for '{}' is missing the primary key column '{}'",
+                    event.table.fully_qualified_name(),
+                    pk_column.name
+                ))
+            })?;
+            let value = literal_column_path(&json_to_val(value), pk_column.typ.inner(), false)?;
+
+            Ok(AbstractPredicate::and(
+                acc,
+                AbstractPredicate::eq(
+                    ColumnPath::Physical(PhysicalColumnPath::leaf(pk_column_id)),
+                    value,
+                ),
+            ))
+        })
+}
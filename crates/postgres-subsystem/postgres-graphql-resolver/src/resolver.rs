@@ -7,29 +7,91 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use std::sync::Arc;
+use std::{collections::BTreeSet, sync::Arc, time::Duration};
 
 use crate::{
-    abstract_operation_resolver::resolve_operation, operation_resolver::OperationResolver,
+    abstract_operation_resolver::resolve_operation,
+    cache_dependencies::{mutation_tables, query_dependencies},
+    operation_resolver::OperationResolver,
+    postgres_subscription::compute_change_select,
 };
 use async_graphql_parser::types::{FieldDefinition, OperationType, TypeDefinition};
 use async_trait::async_trait;
 use common::context::RequestContext;
+use core_model::mapped_arena::SerializableSlabIndex;
 use core_plugin_shared::interception::InterceptorIndex;
 use core_resolver::{
-    InterceptedOperation, QueryResponse,
-    plugin::{SubsystemGraphQLResolver, SubsystemResolutionError},
+    InterceptedOperation, QueryResponse, QueryResponseBody,
+    plugin::{SubscriptionStream, SubsystemGraphQLResolver, SubsystemResolutionError},
+    resolver_cache::QueryDependencies,
     system_resolver::GraphQLSystemResolver,
     validation::field::ValidatedField,
 };
-use exo_sql_pg_connect::PgBackend;
+use exo_sql_pg::AbstractOperation;
+use exo_sql_pg_connect::{ChangeEvent, ChangeOperation, PgBackend};
+use futures::{StreamExt, stream::BoxStream};
+use postgres_core_model::types::EntityType;
 use postgres_core_resolver::postgres_execution_error::PostgresExecutionError;
-use postgres_graphql_model::subsystem::PostgresGraphQLSubsystem;
+use postgres_graphql_model::{
+    mutation::PostgresMutationParameters,
+    operation::{OperationParameters, PostgresOperation},
+    subsystem::PostgresGraphQLSubsystem,
+};
+
+/// Starts a stream of the changes from the change feed (one for each subscription)
+pub type ChangeEvents = Arc<dyn Fn() -> BoxStream<'static, ChangeEvent> + Send + Sync>;
 
 pub struct PostgresSubsystemResolver {
     pub id: &'static str,
     pub subsystem: PostgresGraphQLSubsystem,
     pub executor: Arc<PgBackend>,
+    /// The change feed for subscriptions (`None` if the subsystem has no subscriptions or the
+    /// deployment can't listen for changes)
+    pub change_events: Option<ChangeEvents>,
+}
+
+impl PostgresSubsystemResolver {
+    /// The statement timeout of the operation's return type for the kind of operation (if it
+    /// specifies one through `@statementTimeout`)
+    fn statement_timeout<P: OperationParameters>(
+        &self,
+        operation: &PostgresOperation<P>,
+        operation_type: OperationType,
+    ) -> Option<Duration> {
+        let statement_timeout = operation
+            .return_type
+            .typ(&self.subsystem.core_subsystem.entity_types)
+            .statement_timeout;
+
+        match operation_type {
+            OperationType::Query | OperationType::Subscription => statement_timeout.query,
+            OperationType::Mutation => statement_timeout.mutation,
+        }
+        .map(Duration::from_millis)
+    }
+
+    /// The entity type returned by the query with the given name (if it is one of ours)
+    fn query_entity_id(&self, name: &str) -> Option<SerializableSlabIndex<EntityType>> {
+        let subsystem = &self.subsystem;
+
+        subsystem
+            .pk_queries
+            .get_by_key(name)
+            .or_else(|| subsystem.unique_queries.get_by_key(name))
+            .map(|query| query.return_type.typ_id())
+            .or_else(|| {
+                subsystem
+                    .collection_queries
+                    .get_by_key(name)
+                    .map(|query| query.return_type.typ_id())
+            })
+            .or_else(|| {
+                subsystem
+                    .aggregate_queries
+                    .get_by_key(name)
+                    .map(|query| query.return_type.typ_id())
+            })
+    }
 }
 
 #[async_trait]
@@ -49,19 +111,25 @@ impl SubsystemGraphQLResolver for PostgresSubsystemResolver {
 
         let operation = match operation_type {
             OperationType::Query => match self.subsystem.pk_queries.get_by_key(operation_name) {
-                Some(query) => Some(query.resolve(field, request_context, &self.subsystem).await),
+                Some(query) => Some((
+                    query.resolve(field, request_context, &self.subsystem).await,
+                    self.statement_timeout(query, operation_type),
+                )),
                 None => match self.subsystem.collection_queries.get_by_key(operation_name) {
-                    Some(query) => {
-                        Some(query.resolve(field, request_context, &self.subsystem).await)
-                    }
+                    Some(query) => Some((
+                        query.resolve(field, request_context, &self.subsystem).await,
+                        self.statement_timeout(query, operation_type),
+                    )),
                     None => match self.subsystem.unique_queries.get_by_key(operation_name) {
-                        Some(query) => {
-                            Some(query.resolve(field, request_context, &self.subsystem).await)
-                        }
+                        Some(query) => Some((
+                            query.resolve(field, request_context, &self.subsystem).await,
+                            self.statement_timeout(query, operation_type),
+                        )),
                         None => match self.subsystem.aggregate_queries.get_by_key(operation_name) {
-                            Some(query) => {
-                                Some(query.resolve(field, request_context, &self.subsystem).await)
-                            }
+                            Some(query) => Some((
+                                query.resolve(field, request_context, &self.subsystem).await,
+                                self.statement_timeout(query, operation_type),
+                            )),
                             None => None,
                         },
                     },
@@ -71,28 +139,132 @@ impl SubsystemGraphQLResolver for PostgresSubsystemResolver {
                 let mutation = self.subsystem.mutations.get_by_key(operation_name);
 
                 match mutation {
-                    Some(mutation) => Some(
+                    Some(mutation) => Some((
                         mutation
                             .resolve(field, request_context, &self.subsystem)
                             .await,
-                    ),
+                        self.statement_timeout(mutation, operation_type),
+                    )),
                     None => None,
                 }
             }
-            OperationType::Subscription => Some(Err(PostgresExecutionError::Generic(
-                "Subscriptions are not supported".to_string(),
-            ))),
+            // Subscriptions go through `resolve_subscription`
+            OperationType::Subscription => None,
         };
 
         match operation {
-            Some(Ok(operation)) => Ok(Some(
-                resolve_operation(operation, self, request_context).await?,
+            Some((Ok(operation), statement_timeout)) => Ok(Some(
+                resolve_operation(operation, statement_timeout, self, request_context).await?,
             )),
-            Some(Err(e)) => Err(e.into()),
+            Some((Err(e), _)) => Err(e.into()),
             None => Ok(None),
         }
     }
 
+    async fn resolve_subscription<'a>(
+        &'a self,
+        field: &'a ValidatedField,
+        request_context: &'a RequestContext<'a>,
+        _system_resolver: &'a GraphQLSystemResolver,
+    ) -> Result<Option<SubscriptionStream<'a>>, SubsystemResolutionError> {
+        let Some(subscription) = self.subsystem.subscriptions.get_by_key(&field.name) else {
+            return Ok(None);
+        };
+
+        let Some(change_events) = &self.change_events else {
+            return Err(PostgresExecutionError::Generic(
+                "Subscriptions require a change feed, which is not available in this deployment"
+                    .to_string(),
+            )
+            .into());
+        };
+
+        let entity_type = subscription
+            .return_type
+            .typ(&self.subsystem.core_subsystem.entity_types);
+        let table_name = self
+            .subsystem
+            .core_subsystem
+            .database
+            .get_table(entity_type.table_id)
+            .name
+            .clone();
+        let statement_timeout = self.statement_timeout(subscription, OperationType::Subscription);
+
+        // A deleted row can no longer be read (to apply the `where` filter and access control),
+        // so subscribers only see the rows that are created or updated
+        let events = change_events().filter(move |event| {
+            futures::future::ready(
+                event.table == table_name && event.operation != ChangeOperation::Delete,
+            )
+        });
+
+        let responses = events.filter_map(move |event| async move {
+            let response = async {
+                let select = compute_change_select(
+                    subscription,
+                    &event,
+                    field,
+                    request_context,
+                    &self.subsystem,
+                )
+                .await?;
+
+                resolve_operation(
+                    AbstractOperation::Select(select),
+                    statement_timeout,
+                    self,
+                    request_context,
+                )
+                .await
+            }
+            .await;
+
+            match response {
+                // The changed row doesn't match the `where` filter or isn't accessible
+                Ok(QueryResponse {
+                    body: QueryResponseBody::Raw(None),
+                    ..
+                }) => None,
+                response => Some(response.map_err(SubsystemResolutionError::from)),
+            }
+        });
+
+        Ok(Some(responses.boxed()))
+    }
+
+    async fn query_dependencies<'a>(
+        &'a self,
+        field: &'a ValidatedField,
+        request_context: &'a RequestContext<'a>,
+    ) -> Result<Option<QueryDependencies>, SubsystemResolutionError> {
+        let Some(entity_id) = self.query_entity_id(&field.name) else {
+            return Ok(None);
+        };
+
+        query_dependencies(
+            entity_id,
+            field,
+            &self.subsystem.core_subsystem,
+            request_context,
+        )
+        .await
+        .map(Some)
+        .map_err(SubsystemResolutionError::ContextExtraction)
+    }
+
+    fn mutation_tables(&self, field: &ValidatedField) -> Option<BTreeSet<String>> {
+        let mutation = self.subsystem.mutations.get_by_key(&field.name)?;
+        let is_delete = matches!(mutation.parameters, PostgresMutationParameters::Delete(_));
+
+        Some(mutation_tables(
+            mutation.return_type.typ_id(),
+            field,
+            is_delete,
+            &self.subsystem.core_subsystem,
+        ))
+    }
+
     async fn invoke_interceptor<'a>(
         &'a self,
         _interceptor_index: InterceptorIndex,
@@ -111,6 +283,10 @@ impl SubsystemGraphQLResolver for PostgresSubsystemResolver {
         self.subsystem.schema_mutations()
     }
 
+    fn schema_subscriptions(&self) -> Vec<FieldDefinition> {
+        self.subsystem.schema_subscriptions()
+    }
+
     fn schema_types(&self) -> Vec<TypeDefinition> {
         self.subsystem.schema_types()
     }
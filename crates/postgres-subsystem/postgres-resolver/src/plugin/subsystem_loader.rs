@@ -11,18 +11,22 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 
-use common::env_const::get_rpc_http_path;
+use common::env_const::{
+    EXO_PERSISTED_QUERY_CACHE_SIZE, EXO_PERSISTED_QUERY_STORE, get_rpc_http_path,
+};
 use postgres_core_model::subsystem::PostgresCoreSubsystem;
-use postgres_graphql_resolver::PostgresSubsystemResolver;
+use postgres_graphql_resolver::{ChangeEvents, PostgresSubsystemResolver};
 
 use core_plugin_interface::interface::{SubsystemLoader, SubsystemLoadingError, SubsystemResolver};
 use core_plugin_shared::{
     serializable_system::SerializableSubsystem, system_serializer::SystemSerializer,
 };
+use core_resolver::persisted_queries::{DEFAULT_PERSISTED_QUERY_CACHE_SIZE, PersistedQueryStore};
 use core_resolver::plugin::{SubsystemGraphQLResolver, SubsystemRpcResolver};
 use exo_env::Environment;
-use exo_sql_pg_connect::DatabaseClientManager;
+use exo_sql_pg_connect::{DatabaseClientManager, PgBackend};
 use postgres_core_resolver::database_helper::create_database_executor;
+use postgres_core_resolver::persisted_query_store::PostgresPersistedQueryStore;
 use postgres_graphql_model::subsystem::PostgresGraphQLSubsystem;
 use postgres_rpc_model::subsystem::PostgresRpcSubsystem;
 use postgres_rpc_resolver::PostgresSubsystemRpcResolver;
@@ -60,10 +64,16 @@ impl SubsystemLoader for PostgresSubsystemLoader {
             .map(|graphql| {
                 let mut subsystem = PostgresGraphQLSubsystem::deserialize(graphql.0)?;
                 subsystem.core_subsystem = core_subsystem.clone();
+                let change_events = if subsystem.subscriptions.is_empty() {
+                    None
+                } else {
+                    change_events(env.as_ref())
+                };
                 Ok::<_, SubsystemLoadingError>(Arc::new(PostgresSubsystemResolver {
                     id: self.id(),
                     subsystem,
                     executor: executor.clone(),
+                    change_events,
                 })
                     as Arc<dyn SubsystemGraphQLResolver + Send + Sync>)
             })
@@ -84,6 +94,61 @@ impl SubsystemLoader for PostgresSubsystemLoader {
             })
             .transpose()?;
 
-        Ok(Box::new(SubsystemResolver::new(graphql_system, rpc_system)))
+        let persisted_query_store = persisted_query_store(&executor, env.as_ref()).await?;
+
+        Ok(Box::new(
+            SubsystemResolver::new(graphql_system, rpc_system)
+                .with_persisted_query_store(persisted_query_store),
+        ))
+    }
+}
+
+/// Keep the automatic persisted queries in the database if so configured (otherwise, the system
+/// router keeps them in memory). The store holds as many queries as the in-memory one would (the
+/// system router reports an invalid `EXO_PERSISTED_QUERY_CACHE_SIZE`).
+async fn persisted_query_store(
+    executor: &Arc<PgBackend>,
+    env: &dyn Environment,
+) -> Result<Option<Arc<dyn PersistedQueryStore>>, SubsystemLoadingError> {
+    if env.get(EXO_PERSISTED_QUERY_STORE).as_deref() != Some("postgres") {
+        return Ok(None);
+    }
+
+    let capacity = env
+        .get(EXO_PERSISTED_QUERY_CACHE_SIZE)
+        .and_then(|cache_size| cache_size.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_PERSISTED_QUERY_CACHE_SIZE);
+
+    let store = PostgresPersistedQueryStore::create(executor.clone(), capacity)
+        .await
+        .map_err(|e| SubsystemLoadingError::BoxedError(Box::new(e)))?;
+
+    Ok(Some(Arc::new(store)))
+}
+
+/// Listen for changes to serve subscriptions. Failing to do so isn't fatal: the rest of the API still
+/// works, and subscribing reports that the change feed isn't available.
+fn change_events(env: &dyn Environment) -> Option<ChangeEvents> {
+    #[cfg(feature = "network")]
+    {
+        match postgres_core_resolver::database_helper::start_change_feed(env) {
+            Ok(change_feed) => {
+                use futures::StreamExt;
+
+                let change_feed = Arc::new(change_feed);
+                let change_events: ChangeEvents = Arc::new(move || change_feed.events().boxed());
+                Some(change_events)
+            }
+            Err(e) => {
+                tracing::warn!("Subscriptions are unavailable: {e}");
+                None
+            }
+        }
+    }
+
+    #[cfg(not(feature = "network"))]
+    {
+        let _ = env;
+        None
     }
 }
@@ -45,6 +45,7 @@ use postgres_core_resolver::order_by_mapper::compute_order_by;
 use postgres_core_resolver::postgres_execution_error::PostgresExecutionError;
 use postgres_core_resolver::predicate_mapper::compute_predicate;
 use postgres_core_resolver::predicate_util::get_argument_field;
+use postgres_core_resolver::session_variables::compute_session_variables;
 use postgres_rpc_model::operation::{
     CollectionDelete, CollectionQuery, CollectionUpdate, Create, PkDelete, PkQuery, PkUpdate,
     UniqueDelete, UniqueQuery, UniqueUpdate,
@@ -175,11 +176,17 @@ impl SubsystemRpcResolver for PostgresSubsystemRpcResolver {
                 .resolve(&mut validated_params, request_context, &self.subsystem)
                 .await?;
 
+            let session_variables =
+                compute_session_variables(&self.subsystem.core_subsystem, request_context)
+                    .await
+                    .map_err(from_postgres_error)?;
+
             let mut tx = request_context
                 .system_context
                 .transaction_holder
                 .try_lock()
                 .unwrap();
+            tx.set_session_variables(session_variables);
 
             let mut result = self
                 .executor
@@ -1888,6 +1895,11 @@ fn from_postgres_error(e: PostgresExecutionError) -> SubsystemRpcError {
         PostgresExecutionError::Postgres(exo_sql_pg::database_error::DatabaseError::Precheck(
             _,
         )) => SubsystemRpcError::Authorization,
+        PostgresExecutionError::Postgres(exo_sql_pg::database_error::DatabaseError::Canceled(
+            _,
+        )) => SubsystemRpcError::UserDisplayError(
+            PostgresExecutionError::Canceled.user_error_message(),
+        ),
         _ => SubsystemRpcError::UserDisplayError(e.user_error_message()),
     }
 }
@@ -8,25 +8,27 @@
 // by the Apache License, Version 2.0.
 
 mod request;
+mod websocket;
 
 use std::sync::Arc;
 use std::sync::Mutex;
 
 use actix_web::{
-    HttpRequest, HttpResponse, HttpResponseBuilder, Responder,
+    HttpRequest, HttpResponse, HttpResponseBuilder, Responder, guard,
     web::{self, ServiceConfig},
 };
 use exo_env::Environment;
+use futures::{SinkExt, StreamExt, channel::mpsc};
 use reqwest::StatusCode;
 use system_router::SystemRouter;
 use url::Url;
 
 use common::{
-    env_const::{DeploymentMode, get_deployment_mode},
+    env_const::{DeploymentMode, get_deployment_mode, get_graphql_http_path},
     router::Router,
 };
 use common::{
-    http::{RequestHead, RequestPayload, ResponseBody, ResponsePayload},
+    http::{RequestBodyStream, RequestHead, RequestPayload, ResponseBody, ResponsePayload},
     router::PlainRequestPayload,
 };
 use request::ActixRequestHead;
@@ -49,7 +51,33 @@ pub fn configure_router(
         _ => None,
     };
 
+    let graphql_path = get_graphql_http_path(env.as_ref());
+
     move |app| {
+        // In the playground mode, operations go to the upstream endpoint, so there are no
+        // subscriptions to serve locally
+        if endpoint_url.is_none() {
+            app.route(
+                &graphql_path,
+                web::get()
+                    .guard(guard::fn_guard(|ctx| ctx.head().upgrade()))
+                    .to(websocket::subscribe),
+            );
+
+            // Operations with file uploads (which aren't forwarded in the playground mode)
+            app.route(
+                &graphql_path,
+                web::post()
+                    .guard(guard::fn_guard(|ctx| {
+                        ctx.head()
+                            .headers()
+                            .get("content-type")
+                            .is_some_and(|v| v.as_bytes().starts_with(b"multipart/form-data"))
+                    }))
+                    .to(resolve_multipart),
+            );
+        }
+
         app.app_data(system_router)
             .app_data(web::Data::new(endpoint_url))
             .default_service(web::to(resolve));
@@ -94,9 +122,44 @@ async fn resolve(
     }
 }
 
+/// Resolve a GraphQL request with a `multipart/form-data` body, streaming the body to the router
+async fn resolve_multipart(
+    http_request: HttpRequest,
+    payload: web::Payload,
+    query: web::Query<Value>,
+    system_router: web::Data<SystemRouter>,
+) -> HttpResponse {
+    let request = ActixRequestPayload {
+        head: ActixRequestHead::from_request(http_request, query.into_inner()),
+        body: Mutex::new(Value::Null),
+        body_stream: Mutex::new(Some(body_stream(payload))),
+    };
+
+    route_request(request, system_router).await
+}
+
+/// Convert the payload into a stream that may be sent across threads (which `web::Payload` can't)
+/// by forwarding its chunks through a channel
+fn body_stream(mut payload: web::Payload) -> RequestBodyStream {
+    let (mut sender, receiver) = mpsc::channel(16);
+
+    actix_web::rt::spawn(async move {
+        while let Some(chunk) = payload.next().await {
+            let chunk = chunk.map_err(|e| std::io::Error::other(e.to_string()));
+            if sender.send(chunk).await.is_err() {
+                // The receiver is gone (for example, the router rejected the request)
+                break;
+            }
+        }
+    });
+
+    Box::pin(receiver)
+}
+
 struct ActixRequestPayload {
     head: ActixRequestHead,
     body: Mutex<Value>,
+    body_stream: Mutex<Option<RequestBodyStream>>,
 }
 
 impl RequestPayload for ActixRequestPayload {
@@ -107,6 +170,10 @@ impl RequestPayload for ActixRequestPayload {
     fn take_body(&self) -> Value {
         self.body.lock().unwrap().take()
     }
+
+    fn take_body_stream(&self) -> Option<RequestBodyStream> {
+        self.body_stream.lock().unwrap().take()
+    }
 }
 
 async fn resolve_locally(
@@ -118,11 +185,36 @@ async fn resolve_locally(
     let request = ActixRequestPayload {
         head: ActixRequestHead::from_request(req, query),
         body: Mutex::new(body.map(|b| b.into_inner()).unwrap_or(Value::Null)),
+        body_stream: Mutex::new(None),
     };
 
-    let response = system_router
-        .route(&PlainRequestPayload::external(Box::new(request)))
-        .await;
+    route_request(request, system_router).await
+}
+
+async fn route_request(
+    request: ActixRequestPayload,
+    system_router: web::Data<SystemRouter>,
+) -> HttpResponse {
+    // Subscriptions and incrementally delivered queries with responses as server-sent events or
+    // multipart parts
+    let streaming_operation =
+        system_router.graphql_streaming_operation(&request.head, &request.body.lock().unwrap());
+
+    let response = match streaming_operation {
+        Some(operation) => {
+            SystemRouter::route_graphql_stream(
+                system_router.into_inner(),
+                Box::new(request),
+                operation,
+            )
+            .await
+        }
+        None => {
+            system_router
+                .route(&PlainRequestPayload::external(Box::new(request)))
+                .await
+        }
+    };
 
     match response {
         Some(ResponsePayload {
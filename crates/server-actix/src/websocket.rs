@@ -0,0 +1,187 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+//! GraphQL over WebSocket using the `graphql-transport-ws` protocol
+//! (https://github.com/enisdenjo/graphql-ws/blob/master/PROTOCOL.md)
+
+use std::pin::pin;
+use std::sync::Arc;
+
+use actix_web::{
+    HttpRequest, HttpResponse,
+    http::header::{HeaderValue, SEC_WEBSOCKET_PROTOCOL},
+    web,
+};
+use actix_ws::{CloseCode, Message, Session};
+use futures::StreamExt;
+use serde_json::Value;
+use tokio::{sync::mpsc, task::JoinHandle};
+
+use graphql_router::websocket::{
+    CONNECTION_INIT_TIMEOUT, CloseReason, GRAPHQL_TRANSPORT_WS_PROTOCOL as PROTOCOL,
+    WebSocketAction, WebSocketConnection, complete_message, next_message,
+};
+use system_router::SystemRouter;
+
+use crate::request::ActixRequestHead;
+
+/// Upgrade the request to a WebSocket connection and serve GraphQL operations over it
+pub(crate) async fn subscribe(
+    http_request: HttpRequest,
+    body: web::Payload,
+    query: web::Query<Value>,
+    system_router: web::Data<SystemRouter>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let head = ActixRequestHead::from_request(http_request.clone(), query.into_inner());
+
+    // Browsers don't apply CORS to WebSocket connections, so check the origin here
+    if !system_router.is_websocket_origin_allowed(&head) {
+        return Ok(HttpResponse::Forbidden().finish());
+    }
+
+    let requested_protocols = http_request
+        .headers()
+        .get(SEC_WEBSOCKET_PROTOCOL)
+        .and_then(|protocols| protocols.to_str().ok())
+        .map(|protocols| protocols.split(',').map(str::trim).collect::<Vec<_>>());
+
+    if let Some(requested_protocols) = &requested_protocols
+        && !requested_protocols.contains(&PROTOCOL)
+    {
+        return Ok(HttpResponse::BadRequest().body(format!(
+            "Unsupported WebSocket subprotocol (only '{PROTOCOL}' is supported)"
+        )));
+    }
+
+    let (mut response, session, messages) = actix_ws::handle(&http_request, body)?;
+
+    if requested_protocols.is_some() {
+        response
+            .headers_mut()
+            .insert(SEC_WEBSOCKET_PROTOCOL, HeaderValue::from_static(PROTOCOL));
+    }
+
+    let connection = Connection {
+        system_router: system_router.into_inner(),
+        session,
+        state: WebSocketConnection::new(Arc::new(head)),
+    };
+    actix_web::rt::spawn(connection.run(messages));
+
+    Ok(response)
+}
+
+struct Connection {
+    system_router: Arc<SystemRouter>,
+    session: Session,
+    state: WebSocketConnection<JoinHandle<()>>,
+}
+
+impl Connection {
+    async fn run(mut self, messages: actix_ws::MessageStream) {
+        let mut messages = pin!(messages);
+        let mut init_timeout = pin!(tokio::time::sleep(CONNECTION_INIT_TIMEOUT));
+
+        // Subscriptions report their id here once they complete
+        let (completed_sender, mut completed) = mpsc::unbounded_channel();
+
+        let reason = loop {
+            tokio::select! {
+                // Forget completed subscriptions first, so that the client may reuse their ids
+                // as soon as it receives the `complete` message
+                biased;
+
+                Some(id) = completed.recv() => {
+                    self.state.remove_subscription(&id);
+                }
+                _ = &mut init_timeout, if !self.state.is_initialized() => {
+                    break Some(close_reason(4408, "Connection initialisation timeout"));
+                }
+                message = messages.next() => match message {
+                    Some(Ok(Message::Text(text))) => {
+                        if let Err(reason) = self.handle_message(&text, &completed_sender).await {
+                            break Some(close_reason(reason.code, &reason.description));
+                        }
+                    }
+                    Some(Ok(Message::Ping(bytes))) => {
+                        if self.session.pong(&bytes).await.is_err() {
+                            break None;
+                        }
+                    }
+                    Some(Ok(Message::Binary(_) | Message::Continuation(_))) => {
+                        break Some(close_reason(4400, "Only text messages are supported"));
+                    }
+                    Some(Ok(Message::Pong(_) | Message::Nop)) => {}
+                    Some(Ok(Message::Close(_)) | Err(_)) | None => break None,
+                },
+            }
+        };
+
+        for subscription in self.state.take_subscriptions() {
+            subscription.abort();
+        }
+
+        let _ = self.session.close(reason).await;
+    }
+
+    /// Handle a message from the client, returning the reason to close the connection for if the
+    /// message violates the protocol
+    async fn handle_message(
+        &mut self,
+        text: &str,
+        completed_sender: &mpsc::UnboundedSender<String>,
+    ) -> Result<(), CloseReason> {
+        match self.state.handle_message(text)? {
+            WebSocketAction::None => {}
+            WebSocketAction::Send(message) => {
+                // A failure means the connection is closed, which the message stream reports as well
+                let _ = self.session.text(message).await;
+            }
+            WebSocketAction::Subscribe { id, request } => {
+                let responses = SystemRouter::resolve_graphql_stream(
+                    self.system_router.clone(),
+                    Box::new(request),
+                );
+
+                let mut session = self.session.clone();
+                let completed_sender = completed_sender.clone();
+                let subscription_id = id.clone();
+
+                let subscription = actix_web::rt::spawn(async move {
+                    let mut responses = responses;
+
+                    while let Some(response) = responses.next().await {
+                        if session
+                            .text(next_message(&subscription_id, &response.body))
+                            .await
+                            .is_err()
+                        {
+                            return;
+                        }
+                    }
+
+                    let _ = completed_sender.send(subscription_id.clone());
+                    let _ = session.text(complete_message(&subscription_id)).await;
+                });
+
+                self.state.add_subscription(id, subscription);
+            }
+            WebSocketAction::Stop(subscription) => subscription.abort(),
+        }
+
+        Ok(())
+    }
+}
+
+fn close_reason(code: u16, description: &str) -> actix_ws::CloseReason {
+    actix_ws::CloseReason {
+        code: CloseCode::Other(code),
+        description: Some(description.to_string()),
+    }
+}
@@ -16,6 +16,7 @@ use worker::{Request as WorkerRequest, Response as WorkerResponse};
 
 use common::http::{Headers, RequestHead, RequestPayload, ResponseBody, ResponsePayload};
 use common::router::{PlainRequestPayload, Router};
+use system_router::SystemRouter;
 
 use wasm_bindgen::prelude::*;
 
@@ -101,9 +102,22 @@ pub async fn resolve(raw_request: web_sys::Request) -> Result<web_sys::Response,
     };
 
     let system_router = crate::init::get_system_router()?;
-    let response_payload = system_router
-        .route(&PlainRequestPayload::external(Box::new(request)))
-        .await;
+
+    // Subscriptions and incrementally delivered queries with responses as server-sent events or
+    // multipart parts
+    let streaming_operation =
+        system_router.graphql_streaming_operation(&request.head, &request.body.lock().unwrap());
+
+    let response_payload = match streaming_operation {
+        Some(operation) => {
+            SystemRouter::route_graphql_stream(system_router, Box::new(request), operation).await
+        }
+        None => {
+            system_router
+                .route(&PlainRequestPayload::external(Box::new(request)))
+                .await
+        }
+    };
 
     let response = match response_payload {
         Some(ResponsePayload {
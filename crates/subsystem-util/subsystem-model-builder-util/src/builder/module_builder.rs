@@ -19,7 +19,7 @@ use subsystem_model_util::{
     argument::{ArgumentParameter, ArgumentParameterType},
     interceptor::Interceptor,
     module::{Argument, ModuleMethod, ModuleMethodType, Script},
-    operation::{ModuleMutation, ModuleQuery},
+    operation::{ModuleMutation, ModuleQuery, ModuleSubscription},
     types::{ForeignModuleType, ModuleOperationReturnType, ModuleType},
 };
 
@@ -71,6 +71,11 @@ pub fn build_expanded(building: &mut SystemContextBuilding) {
                 let query = &mut building.queries[query_id];
                 query.method_id = Some(id)
             }
+
+            ModuleMethodType::Subscription(subscription_id) => {
+                let subscription = &mut building.subscriptions[subscription_id];
+                subscription.method_id = Some(id)
+            }
         }
     }
 }
@@ -128,6 +133,14 @@ fn create_shallow_module(
                     let mutation_id = building.mutations.add(&resolved_method.name, mutation);
                     ModuleMethodType::Mutation(mutation_id)
                 }
+                ResolvedMethodType::Subscription => {
+                    let subscription =
+                        shallow_module_subscription(resolved_method, &building.types, building);
+                    let subscription_id = building
+                        .subscriptions
+                        .add(&resolved_method.name, subscription);
+                    ModuleMethodType::Subscription(subscription_id)
+                }
             },
             is_exported: resolved_method.is_exported,
             arguments: resolved_method
@@ -180,6 +193,8 @@ fn shallow_module_query(
         argument_param: argument_param(method, building),
         return_type: compute_shallow_return_type(&method.return_type, module_types),
         doc_comments: method.doc_comments.clone(),
+        cache_hint: method.cache_hint,
+        cost: method.cost,
     }
 }
 
@@ -194,6 +209,22 @@ fn shallow_module_mutation(
         argument_param: argument_param(method, building),
         return_type: compute_shallow_return_type(&method.return_type, module_types),
         doc_comments: method.doc_comments.clone(),
+        cost: method.cost,
+    }
+}
+
+fn shallow_module_subscription(
+    method: &ResolvedMethod,
+    module_types: &MappedArena<ModuleType>,
+    building: &SystemContextBuilding,
+) -> ModuleSubscription {
+    ModuleSubscription {
+        name: method.name.clone(),
+        method_id: None,
+        argument_param: argument_param(method, building),
+        return_type: compute_shallow_return_type(&method.return_type, module_types),
+        doc_comments: method.doc_comments.clone(),
+        cost: method.cost,
     }
 }
 
@@ -223,7 +254,7 @@ fn compute_shallow_return_type(
     }
 }
 
-// Generate parameters for the method's query or mutation.
+// Generate parameters for the method's query, mutation, or subscription.
 fn argument_param(
     method: &ResolvedMethod,
     building: &SystemContextBuilding,
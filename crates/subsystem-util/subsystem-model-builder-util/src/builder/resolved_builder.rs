@@ -13,9 +13,11 @@ use codemap::Span;
 use codemap_diagnostic::{Diagnostic, Level, SpanLabel, SpanStyle};
 
 use core_model::primitive_type::InjectedType;
-use core_model::types::{FieldType, Named};
+use core_model::types::{CacheHint, Cost, FieldType, Named};
 use core_model::{mapped_arena::MappedArena, primitive_type::PrimitiveType};
 use core_model_builder::ast::ast_types::AstFieldType;
+use core_model_builder::builder::cache_hint::build_cache_hint;
+use core_model_builder::builder::cost::build_cost;
 use core_model_builder::builder::resolved_builder::{AnnotationMapHelper, compute_fragment_fields};
 use core_model_builder::builder::system_builder::BaseModelSystem;
 use core_model_builder::typechecker::AnnotationMap;
@@ -64,6 +66,10 @@ pub struct ResolvedCompositeType {
     pub is_input: bool,
     pub access: ResolvedAccess,
     pub doc_comments: Option<String>,
+    /// From the `@cache` annotation
+    pub cache_hint: Option<CacheHint>,
+    /// From the `@cost` annotation
+    pub cost: Option<Cost>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -107,12 +113,17 @@ pub struct ResolvedMethod {
     pub arguments: Vec<ResolvedArgument>,
     pub return_type: FieldType<ResolvedFieldType>,
     pub doc_comments: Option<String>,
+    /// From the `@cache` annotation (only for queries)
+    pub cache_hint: Option<CacheHint>,
+    /// From the `@cost` annotation
+    pub cost: Option<Cost>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum ResolvedMethodType {
     Query,
     Mutation,
+    Subscription,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -292,11 +303,39 @@ async fn resolve_module(
                         .iter()
                         .map(|m| {
                             let access = build_access(m.annotations.get("access"));
+                            let cache_hint = match build_cache_hint(&m.annotations, &m.name, m.span) {
+                                Ok(Some(_)) if m.typ != AstMethodType::Query => {
+                                    errors.push(Diagnostic {
+                                        level: Level::Error,
+                                        message: format!("@cache applies only to queries, but {} is not a query", m.name),
+                                        code: Some("C000".to_string()),
+                                        spans: vec![SpanLabel {
+                                            span: m.span,
+                                            style: SpanStyle::Primary,
+                                            label: None,
+                                        }],
+                                    });
+                                    None
+                                }
+                                Ok(cache_hint) => cache_hint,
+                                Err(diagnostic) => {
+                                    errors.push(diagnostic);
+                                    None
+                                }
+                            };
+                            let cost = match build_cost(&m.annotations, &m.name, m.span) {
+                                Ok(cost) => cost,
+                                Err(diagnostic) => {
+                                    errors.push(diagnostic);
+                                    None
+                                }
+                            };
                             ResolvedMethod {
                                 name: m.name.clone(),
                                 operation_kind: match m.typ {
                                     AstMethodType::Query   => ResolvedMethodType::Query,
                                     AstMethodType::Mutation => ResolvedMethodType::Mutation,
+                                    AstMethodType::Subscription => ResolvedMethodType::Subscription,
                                 },
                                 is_exported: m.is_exported,
                                 access,
@@ -307,6 +346,8 @@ async fn resolve_module(
                                     .collect(),
                                 return_type: resolve_field_type(&m.return_type.to_typ(types), m.return_type.module_name(), types),
                                 doc_comments: m.doc_comments.clone(),
+                                cache_hint,
+                                cost,
                             }
                         })
                         .collect(),
@@ -545,6 +586,22 @@ fn resolve_module_types(
             Type::Composite(ct) if ct.kind == AstModelKind::Type && is_relevant(typ) => {
                 let access = build_access(ct.annotations.get("access"));
 
+                let cache_hint = match build_cache_hint(&ct.annotations, &ct.name, ct.span) {
+                    Ok(cache_hint) => cache_hint,
+                    Err(diagnostic) => {
+                        errors.push(diagnostic);
+                        None
+                    }
+                };
+
+                let cost = match build_cost(&ct.annotations, &ct.name, ct.span) {
+                    Ok(cost) => cost,
+                    Err(diagnostic) => {
+                        errors.push(diagnostic);
+                        None
+                    }
+                };
+
                 let fragment_fields = compute_fragment_fields(ct, errors, typechecked_system);
 
                 let resolved_fields = ct
@@ -571,6 +628,8 @@ fn resolve_module_types(
                         is_input: input_types.contains(&ct.name),
                         access,
                         doc_comments: ct.doc_comments.clone(),
+                        cache_hint,
+                        cost,
                     }),
                 );
             }
@@ -20,7 +20,7 @@ use core_model_builder::{
 use subsystem_model_util::{
     interceptor::Interceptor,
     module::{ModuleMethod, Script},
-    operation::{ModuleMutation, ModuleQuery},
+    operation::{ModuleMutation, ModuleQuery, ModuleSubscription},
     subsystem::ModuleSubsystem,
     types::ModuleType,
 };
@@ -49,6 +49,7 @@ pub struct SystemContextBuilding {
     pub queries: MappedArena<ModuleQuery>,
 
     pub mutations: MappedArena<ModuleMutation>,
+    pub subscriptions: MappedArena<ModuleSubscription>,
     pub methods: MappedArena<ModuleMethod>,
     pub interceptors: SerializableSlab<Interceptor>, // Don't use MappedArena because we use a composite key (module name + method name) here
     pub scripts: MappedArena<Script>,
@@ -60,6 +61,7 @@ impl Default for SystemContextBuilding {
             types: MappedArena::default(),
             queries: MappedArena::default(),
             mutations: MappedArena::default(),
+            subscriptions: MappedArena::default(),
             methods: MappedArena::default(),
             interceptors: SerializableSlab::new(),
             scripts: MappedArena::default(),
@@ -143,6 +145,7 @@ pub async fn build_with_selection(
             module_types: building.types.values(),
             queries: building.queries,
             mutations: building.mutations,
+            subscriptions: building.subscriptions,
             methods: building.methods.values(),
             scripts: building.scripts.values(),
             contexts: base_system.contexts.clone(),
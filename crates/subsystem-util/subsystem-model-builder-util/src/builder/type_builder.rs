@@ -96,6 +96,8 @@ fn expand_module_type_no_fields(
             access: Access::restrictive(),
             is_input: false,
             doc_comments: resolved_type.doc_comments.clone(),
+            cache_hint: resolved_type.cache_hint,
+            cost: resolved_type.cost,
         });
     building
         .types
@@ -120,6 +122,8 @@ fn expand_module_type_fields(
         is_input: resolved_type.is_input,
         access: Access::restrictive(),
         doc_comments: resolved_type.doc_comments.clone(),
+        cache_hint: resolved_type.cache_hint,
+        cost: resolved_type.cost,
     });
 
     building.types.get_by_id_mut(existing_type_id).kind = kind
@@ -202,6 +206,8 @@ fn expand_type_access(
             is_input: self_type_info.is_input,
             access: expr,
             doc_comments: self_type_info.doc_comments.clone(),
+            cache_hint: self_type_info.cache_hint,
+            cost: self_type_info.cost,
         });
 
         building.types.get_by_id_mut(existing_type_id).kind = kind
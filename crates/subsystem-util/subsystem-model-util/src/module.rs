@@ -12,7 +12,7 @@ use serde::{Deserialize, Serialize};
 use crate::{access::Access, types::ModuleOperationReturnType};
 
 use super::{
-    operation::{ModuleMutation, ModuleQuery},
+    operation::{ModuleMutation, ModuleQuery, ModuleSubscription},
     types::ModuleType,
 };
 use core_model::{mapped_arena::SerializableSlabIndex, types::FieldType};
@@ -46,4 +46,5 @@ pub struct Argument {
 pub enum ModuleMethodType {
     Query(SerializableSlabIndex<ModuleQuery>),
     Mutation(SerializableSlabIndex<ModuleMutation>),
+    Subscription(SerializableSlabIndex<ModuleSubscription>),
 }
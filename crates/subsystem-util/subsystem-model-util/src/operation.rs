@@ -14,7 +14,9 @@ use core_model::type_normalization::{
     InputValueProvider, Parameter, Type, default_positioned, default_positioned_name,
 };
 use core_model::{
-    mapped_arena::SerializableSlabIndex, type_normalization::FieldDefinitionProvider,
+    mapped_arena::SerializableSlabIndex,
+    type_normalization::FieldDefinitionProvider,
+    types::{CacheHint, Cost, DirectivesProvider},
 };
 use serde::{Deserialize, Serialize};
 
@@ -29,6 +31,10 @@ pub struct ModuleQuery {
     pub argument_param: Vec<ArgumentParameter>,
     pub return_type: ModuleOperationReturnType,
     pub doc_comments: Option<String>,
+    /// How long the results may be cached (from the method's `@cache` annotation)
+    pub cache_hint: Option<CacheHint>,
+    /// From the method's `@cost` annotation
+    pub cost: Option<Cost>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -38,6 +44,21 @@ pub struct ModuleMutation {
     pub argument_param: Vec<ArgumentParameter>,
     pub return_type: ModuleOperationReturnType,
     pub doc_comments: Option<String>,
+    /// From the method's `@cost` annotation
+    pub cost: Option<Cost>,
+}
+
+/// A subscription such as `priceChanged(symbol: "ACME")`, whose method returns an async iterator
+/// of the values to deliver to the subscriber
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ModuleSubscription {
+    pub name: String,
+    pub method_id: Option<SerializableSlabIndex<ModuleMethod>>,
+    pub argument_param: Vec<ArgumentParameter>,
+    pub return_type: ModuleOperationReturnType,
+    pub doc_comments: Option<String>,
+    /// From the method's `@cost` annotation
+    pub cost: Option<Cost>,
 }
 
 impl Operation for ModuleQuery {
@@ -88,6 +109,30 @@ impl Operation for ModuleMutation {
     }
 }
 
+impl Operation for ModuleSubscription {
+    fn name(&self) -> &String {
+        &self.name
+    }
+
+    fn parameters(&self) -> Vec<&dyn Parameter> {
+        self.argument_param
+            .iter()
+            .map(|param| {
+                let param: &dyn Parameter = param;
+                param
+            })
+            .collect()
+    }
+
+    fn return_type(&self) -> Type {
+        return_type(&self.return_type)
+    }
+
+    fn doc_comments(&self) -> Option<String> {
+        self.doc_comments.clone()
+    }
+}
+
 fn return_type(module_return_type: &ModuleOperationReturnType) -> Type {
     match module_return_type {
         ModuleOperationReturnType::Own(return_type) => return_type.into(),
@@ -114,7 +159,13 @@ impl<S> FieldDefinitionProvider<S> for ModuleQuery {
             description: self.doc_comments().map(default_positioned),
             name: default_positioned_name(self.name()),
             arguments: fields,
-            directives: vec![],
+            directives: self
+                .cache_hint
+                .iter()
+                .flat_map(|cache_hint| cache_hint.get_directives())
+                .chain(self.cost.iter().flat_map(|cost| cost.get_directives()))
+                .map(default_positioned)
+                .collect(),
             ty: default_positioned(self.return_type().to_graphql_type()),
         }
     }
@@ -132,7 +183,35 @@ impl<S> FieldDefinitionProvider<S> for ModuleMutation {
             description: self.doc_comments().map(default_positioned),
             name: default_positioned_name(self.name()),
             arguments: fields,
-            directives: vec![],
+            directives: self
+                .cost
+                .iter()
+                .flat_map(|cost| cost.get_directives())
+                .map(default_positioned)
+                .collect(),
+            ty: default_positioned(self.return_type().to_graphql_type()),
+        }
+    }
+}
+
+impl<S> FieldDefinitionProvider<S> for ModuleSubscription {
+    fn field_definition(&self, _system: &S) -> FieldDefinition {
+        let fields = self
+            .parameters()
+            .iter()
+            .map(|parameter| default_positioned(parameter.input_value()))
+            .collect();
+
+        FieldDefinition {
+            description: self.doc_comments().map(default_positioned),
+            name: default_positioned_name(self.name()),
+            arguments: fields,
+            directives: self
+                .cost
+                .iter()
+                .flat_map(|cost| cost.get_directives())
+                .map(default_positioned)
+                .collect(),
             ty: default_positioned(self.return_type().to_graphql_type()),
         }
     }
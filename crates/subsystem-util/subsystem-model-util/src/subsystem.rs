@@ -24,7 +24,7 @@ use serde::{Deserialize, Serialize};
 use crate::{
     interceptor::Interceptor,
     module::ModuleMethod,
-    operation::{ModuleMutation, ModuleQuery},
+    operation::{ModuleMutation, ModuleQuery, ModuleSubscription},
     types::ModuleType,
 };
 
@@ -41,6 +41,9 @@ pub struct ModuleSubsystem {
     // mutation related
     pub mutations: MappedArena<ModuleMutation>,
 
+    // subscription related
+    pub subscriptions: MappedArena<ModuleSubscription>,
+
     // module related
     pub methods: SerializableSlab<ModuleMethod>,
     pub scripts: SerializableSlab<Script>,
@@ -62,6 +65,13 @@ impl ModuleSubsystem {
             .collect()
     }
 
+    pub fn schema_subscriptions(&self) -> Vec<Positioned<FieldDefinition>> {
+        self.subscriptions
+            .iter()
+            .map(|subscription| default_positioned(subscription.1.field_definition(self)))
+            .collect()
+    }
+
     pub fn schema_types(&self) -> Vec<TypeDefinition> {
         self.module_types
             .iter()
@@ -17,7 +17,9 @@ use core_model::{
         FieldDefinitionProvider, InputValueProvider, Parameter, Type, TypeDefinitionProvider,
         default_positioned, default_positioned_name,
     },
-    types::{FieldType, Named, OperationReturnType, TypeValidation},
+    types::{
+        CacheHint, Cost, DirectivesProvider, FieldType, Named, OperationReturnType, TypeValidation,
+    },
 };
 
 use serde::{Deserialize, Serialize};
@@ -46,6 +48,8 @@ pub struct ModuleCompositeType {
     pub is_input: bool,
     pub access: Access,
     pub doc_comments: Option<String>,
+    pub cache_hint: Option<CacheHint>,
+    pub cost: Option<Cost>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -80,6 +84,8 @@ impl TypeDefinitionProvider<SerializableSlab<ModuleType>> for ModuleType {
             },
             ModuleTypeKind::Composite(ModuleCompositeType {
                 fields: model_fields,
+                cache_hint,
+                cost,
                 ..
             }) => {
                 let kind = if self.is_input {
@@ -105,7 +111,12 @@ impl TypeDefinitionProvider<SerializableSlab<ModuleType>> for ModuleType {
                     extend: false,
                     description: self.doc_comments.clone().map(default_positioned),
                     name: default_positioned_name(&self.name),
-                    directives: vec![],
+                    directives: cache_hint
+                        .iter()
+                        .flat_map(|cache_hint| cache_hint.get_directives())
+                        .chain(cost.iter().flat_map(|cost| cost.get_directives()))
+                        .map(default_positioned)
+                        .collect(),
                     kind,
                 }
             }
@@ -7,15 +7,22 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use std::{fs::File, io::BufReader, path::Path, sync::Arc};
+use std::{fs::File, io::BufReader, ops::Deref, path::Path, sync::Arc};
 
-use common::env_const::{EXO_ENABLE_MCP, EXO_UNSTABLE_ENABLE_RPC_API};
+use bytes::Bytes;
+use futures::{StreamExt, stream::BoxStream};
+use http::StatusCode;
+use serde_json::Value;
+
+use common::env_const::{EXO_ENABLE_MCP, EXO_UNSTABLE_ENABLE_RPC_API, get_graphql_http_path};
 use common::introspection::{IntrospectionMode, introspection_mode};
 use common::router::PlainRequestPayload;
 use core_plugin_shared::profile::{SchemaProfile, SchemaProfiles};
 use core_resolver::introspection::definition::schema::Schema;
+use core_resolver::persisted_queries::PersistedQueryStore;
 use core_resolver::plugin::SubsystemGraphQLResolver;
 use core_resolver::plugin::SubsystemRpcResolver;
+use core_resolver::system_resolver::GraphQLSystemResolver;
 use core_resolver::system_rpc_resolver::SystemRpcResolver;
 use rpc_introspection::RpcSchema;
 
@@ -28,7 +35,7 @@ use common::context::{JwtAuthenticator, RequestContext};
 use common::{
     cors::{CorsConfig, CorsRouter},
     env_const::{EXO_CORS_DOMAINS, EXO_GRAPHQL_ALLOW_MUTATIONS},
-    http::ResponsePayload,
+    http::{Headers, RequestHead, RequestPayload, ResponseBody, ResponsePayload},
     router::{CompositeRouter, Router},
 };
 use core_plugin_interface::interface::{SubsystemLoader, SubsystemResolver};
@@ -38,7 +45,9 @@ use core_plugin_shared::{
 };
 use core_router::SystemLoadingError;
 use exo_env::Environment;
-use graphql_router::{GraphQLRouter, IntrospectionResolver};
+use graphql_router::{
+    GraphQLRouter, IntrospectionResolver, StreamingOperation, SubscriptionResponse,
+};
 
 #[cfg(not(target_family = "wasm"))]
 use playground_router::PlaygroundRouter;
@@ -91,9 +100,18 @@ pub async fn create_system_router_from_system(
 
     let mut graphql_resolvers: Vec<Arc<dyn SubsystemGraphQLResolver + Send + Sync>> = vec![];
     let mut rpc_resolvers: Vec<Box<dyn SubsystemRpcResolver + Send + Sync>> = vec![];
+    let mut persisted_query_store: Option<Arc<dyn PersistedQueryStore>> = None;
 
     for resolver in subsystem_resolvers {
-        let SubsystemResolver { graphql, rpc } = *resolver;
+        let SubsystemResolver {
+            graphql,
+            rpc,
+            persisted_query_store: subsystem_persisted_query_store,
+        } = *resolver;
+
+        if persisted_query_store.is_none() {
+            persisted_query_store = subsystem_persisted_query_store;
+        }
 
         if let Some(graphql) = graphql {
             graphql_resolvers.push(graphql);
@@ -144,6 +162,7 @@ pub async fn create_system_router_from_system(
             query_interception_map.clone(),
             mutation_interception_map.clone(),
             trusted_documents,
+            persisted_query_store.clone(),
             env.clone(),
         )?
     };
@@ -163,6 +182,7 @@ pub async fn create_system_router_from_system(
         declaration_doc_comments,
         query_interception_map,
         mutation_interception_map,
+        persisted_query_store,
         schema_profiles,
     )
     .await?;
@@ -185,6 +205,7 @@ async fn create_mcp_router(
     declaration_doc_comments: Arc<Option<String>>,
     query_interception_map: Arc<InterceptionMap>,
     mutation_interception_map: Arc<InterceptionMap>,
+    persisted_query_store: Option<Arc<dyn PersistedQueryStore>>,
     schema_profiles: Option<SchemaProfiles>,
 ) -> Result<McpRouter, SystemLoadingError> {
     let env_clone = env.clone();
@@ -207,6 +228,7 @@ async fn create_mcp_router(
             query_interception_map.clone(),
             mutation_interception_map.clone(),
             TrustedDocuments::all(),
+            persisted_query_store.clone(),
             env.clone(),
         )?;
 
@@ -308,6 +330,8 @@ async fn create_system_router(
     #[cfg(not(target_family = "wasm"))] mcp_router: McpRouter,
     env: Arc<dyn Environment>,
 ) -> Result<SystemRouter, SystemLoadingError> {
+    let graphql_resolver = graphql_router.resolver();
+
     let mut routers: Vec<Box<dyn for<'a> Router<RequestContext<'a>> + Send + Sync>> =
         vec![Box::new(graphql_router)];
 
@@ -324,7 +348,7 @@ async fn create_system_router(
 
     #[cfg(target_family = "wasm")]
     {
-        SystemRouter::new(routers, env.clone()).await
+        SystemRouter::new(routers, graphql_resolver, env.clone()).await
     }
 
     #[cfg(not(target_family = "wasm"))]
@@ -333,7 +357,13 @@ async fn create_system_router(
 
         routers.push(Box::new(PlaygroundRouter::new(playground_config.clone())));
 
-        SystemRouter::new(routers, env.clone(), Some(playground_config)).await
+        SystemRouter::new(
+            routers,
+            graphql_resolver,
+            env.clone(),
+            Some(playground_config),
+        )
+        .await
     }
 }
 
@@ -341,6 +371,8 @@ type RequestContextRouter = Box<dyn for<'b> Router<RequestContext<'b>> + Send +
 
 pub struct SystemRouter {
     underlying: CorsRouter<CompositeRouter<RequestContextRouter>>,
+    /// The resolver behind the GraphQL router (for the responses streamed outside of `route`)
+    graphql_resolver: Arc<GraphQLSystemResolver>,
     env: Arc<dyn Environment>,
     authenticator: Arc<Option<JwtAuthenticator>>,
     #[cfg(not(target_family = "wasm"))]
@@ -350,6 +382,7 @@ pub struct SystemRouter {
 impl SystemRouter {
     pub async fn new(
         routers: Vec<RequestContextRouter>,
+        graphql_resolver: Arc<GraphQLSystemResolver>,
         env: Arc<dyn Environment>,
         #[cfg(not(target_family = "wasm"))] playground_config: Option<Arc<PlaygroundRouterConfig>>,
     ) -> Result<Self, SystemLoadingError> {
@@ -364,6 +397,7 @@ impl SystemRouter {
                 CompositeRouter::new(routers),
                 CorsConfig::from_env(cors_domains),
             ),
+            graphql_resolver,
             env,
             authenticator: Arc::new(authenticator),
             #[cfg(not(target_family = "wasm"))]
@@ -390,6 +424,153 @@ impl SystemRouter {
             }
         }
     }
+
+    pub fn is_graphql_path(&self, request_path: &str) -> bool {
+        request_path == get_graphql_http_path(self.env.as_ref())
+    }
+
+    /// Whether a WebSocket connection may be opened for the request (see
+    /// [CorsConfig::allow_websocket_origin])
+    pub fn is_websocket_origin_allowed(&self, request_head: &(dyn RequestHead + Sync)) -> bool {
+        self.underlying.config().allow_websocket_origin(
+            request_head
+                .get_header(http::header::ORIGIN.as_str())
+                .as_deref(),
+            request_head
+                .get_header(http::header::HOST.as_str())
+                .as_deref(),
+        )
+    }
+
+    /// The operation of a GraphQL request to route through [SystemRouter::route_graphql_stream]:
+    /// one that accepts its responses as a stream (of server-sent events or of `multipart/mixed`
+    /// parts) and is a subscription or uses `@defer` or `@stream` (see
+    /// [graphql_router::streaming_operation]). `None` for any other request (to route as usual).
+    pub fn graphql_streaming_operation(
+        &self,
+        request_head: &(dyn RequestHead + Sync),
+        body: &Value,
+    ) -> Option<StreamingOperation> {
+        let accepts_stream = self.is_graphql_path(&request_head.get_path())
+            && request_head.get_method() == http::Method::POST
+            && request_head
+                .get_header(http::header::ACCEPT.as_str())
+                .is_some_and(|accept| {
+                    accept.contains("text/event-stream") || accept.contains("multipart/mixed")
+                });
+
+        if accepts_stream {
+            graphql_router::streaming_operation(body)
+        } else {
+            None
+        }
+    }
+
+    /// Resolve a GraphQL request into a stream of responses (each a JSON document): one for each
+    /// event of a subscription, one for each part of a query with `@defer` directives, and a
+    /// single one for any other operation.
+    ///
+    /// Since a subscription may last long after the caller returns (for example, over a WebSocket
+    /// connection), the stream owns the router (typically an `Arc` or a `'static` reference) and
+    /// the request.
+    pub fn resolve_graphql_stream<R>(
+        router: R,
+        request: Box<dyn RequestPayload + Send + Sync>,
+    ) -> BoxStream<'static, SubscriptionResponse>
+    where
+        R: Deref<Target = SystemRouter> + Send + 'static,
+    {
+        Box::pin(async_stream::stream! {
+            let router: &SystemRouter = &router;
+
+            let request_context = RequestContext::new(
+                request.as_ref(),
+                vec![],
+                router,
+                &router.authenticator,
+                router.env.as_ref(),
+            );
+
+            let responses = graphql_router::resolve_subscription(
+                request.as_ref(),
+                &router.graphql_resolver,
+                &request_context,
+                router.env.as_ref(),
+            );
+            futures::pin_mut!(responses);
+
+            while let Some(response) = responses.next().await {
+                yield response;
+            }
+        })
+    }
+
+    /// Route a GraphQL request with its responses as a stream, either of server-sent events (per
+    /// the "distinct connections mode" of the GraphQL over SSE protocol: a `next` event for each
+    /// response, followed by a `complete` event) or, for a client that doesn't accept server-sent
+    /// events, of `multipart/mixed` parts (per the incremental delivery over HTTP proposal).
+    ///
+    /// The headers that resolving the operation asks for (such as `Set-Cookie`) must go out before
+    /// the body, so for an incrementally delivered query, the response waits for the initial
+    /// part and carries its headers. A subscription's response starts right away (its events may
+    /// be far apart), so it carries none.
+    pub async fn route_graphql_stream<R>(
+        router: R,
+        request: Box<dyn RequestPayload + Send + Sync>,
+        operation: StreamingOperation,
+    ) -> Option<ResponsePayload>
+    where
+        R: Deref<Target = SystemRouter> + Clone + Send + 'static,
+    {
+        let origin = request.get_head().get_header(http::header::ORIGIN.as_str());
+        let method = request.get_head().get_method();
+        let event_stream = request
+            .get_head()
+            .get_header(http::header::ACCEPT.as_str())
+            .is_some_and(|accept| accept.contains("text/event-stream"));
+
+        let cors_router = router.clone();
+        cors_router
+            .underlying
+            .route_with(origin.as_deref(), &method, || async move {
+                let mut responses = Self::resolve_graphql_stream(router, request);
+
+                let initial = match operation {
+                    StreamingOperation::Incremental => responses.next().await,
+                    StreamingOperation::Subscription => None,
+                };
+
+                let mut headers = Headers::from_vec(
+                    initial
+                        .as_ref()
+                        .map(|initial| initial.headers.clone())
+                        .unwrap_or_default(),
+                );
+                headers.insert("cache-control".into(), "no-cache".into());
+
+                let responses = futures::stream::iter(initial)
+                    .chain(responses)
+                    .map(|response| response.body);
+
+                let body: BoxStream<'static, Result<Bytes, std::io::Error>> = if event_stream {
+                    headers.insert("content-type".into(), "text/event-stream".into());
+                    Box::pin(graphql_router::event_stream(responses).map(Ok))
+                } else {
+                    headers.insert(
+                        "content-type".into(),
+                        graphql_router::MULTIPART_MIXED_CONTENT_TYPE.into(),
+                    );
+                    Box::pin(graphql_router::multipart_mixed(responses).map(Ok))
+                };
+
+                Some(ResponsePayload {
+                    body: ResponseBody::Stream(body),
+                    headers,
+                    status_code: StatusCode::OK,
+                })
+            })
+            .await
+    }
 }
 
 #[async_trait::async_trait]
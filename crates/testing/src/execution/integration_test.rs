@@ -552,6 +552,7 @@ async fn execute_graphql_operation(
         query: Some(query),
         variables: Some(variables_map),
         query_hash: None,
+        uploads: vec![],
     };
 
     let request = MemoryRequestPayload::new(operations_payload.to_json()?, request_head);
@@ -99,6 +99,7 @@ async fn create_introspection_request() -> Result<MemoryRequestPayload> {
         },
         variables: None,
         query_hash: None,
+        uploads: vec![],
     };
 
     Ok(MemoryRequestPayload::new(
@@ -45,6 +45,13 @@ pub async fn build(
 
     let underlying_module_system = module_system.underlying;
 
+    if let Some((_, subscription)) = underlying_module_system.subscriptions.iter().next() {
+        return Err(ModelBuildingError::Generic(format!(
+            "Subscription '{}' is not supported: Wasm modules can only declare queries and mutations",
+            subscription.name
+        )));
+    }
+
     if underlying_module_system.queries.is_empty()
         && underlying_module_system.mutations.is_empty()
         && underlying_module_system.interceptors.is_empty()
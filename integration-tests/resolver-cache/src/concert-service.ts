@@ -0,0 +1,18 @@
+import type { Exograph } from '../generated/exograph.d.ts';
+
+export function greeting(authContext: { id: number }): string {
+  return `Hello, user ${authContext.id}`;
+}
+
+export async function concertCount(exograph: Exograph): Promise<number> {
+  const { concertsAgg } = await exograph.executeQuery(`
+    query {
+      concertsAgg {
+        id {
+          count
+        }
+      }
+    }
+  `);
+  return concertsAgg.id.count;
+}
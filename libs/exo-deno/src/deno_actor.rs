@@ -171,10 +171,9 @@ where
                     // take R from GothamStorage
                     let r: Option<R> = deno_module.take().expect("take() should not have failed");
 
-                    // send result of the Deno function back to call_method
-                    final_response_sender
-                        .send(result.map(|result| (result, r)))
-                        .expect("Could not send result in DenoActor thread");
+                    // send result of the Deno function back to call_method (ignoring the error if the
+                    // caller has gone away, for example, a subscriber that has unsubscribed)
+                    let _ = final_response_sender.send(result.map(|result| (result, r)));
 
                     busy_clone.store(false, Ordering::Relaxed); // unmark DenoActor as busy
                 }
@@ -34,6 +34,7 @@ use std::cell::RefCell;
 use std::path::PathBuf;
 use tracing::instrument;
 
+use serde::{Serialize, Serializer};
 use serde_json::Value;
 
 use std::collections::HashMap;
@@ -374,6 +375,9 @@ impl DenoModule {
                         .to_object(scope)
                         .unwrap()
                         .into()),
+                    Arg::Value(v) => {
+                        Ok(serde_v8::to_v8(scope, v).map_err(DenoInternalError::Serde)?)
+                    }
                 })
                 .collect::<Result<Vec<_>, DenoError>>()?;
 
@@ -486,6 +490,34 @@ pub enum Arg {
     /// (the first part of each tuple) provided to the `shims` argument to the
     /// `DenoModule::new` function.
     Shim(String),
+    /// A value that may hold bytes (such as an uploaded file), which JSON can't represent.
+    Value(ArgValue),
+}
+
+/// A value (or a part of one) to pass to a DenoModule function through [Arg::Value].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgValue {
+    Json(serde_json::Value),
+    /// Bytes to be passed as a `Uint8Array`.
+    Bytes(Vec<u8>),
+    List(Vec<ArgValue>),
+    Object(Vec<(String, ArgValue)>),
+}
+
+impl Serialize for ArgValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ArgValue::Json(value) => value.serialize(serializer),
+            // serde_v8 turns a `ToJsBuffer` into a `Uint8Array`
+            ArgValue::Bytes(bytes) => {
+                deno_core::ToJsBuffer::from(bytes.clone()).serialize(serializer)
+            }
+            ArgValue::List(values) => serializer.collect_seq(values),
+            ArgValue::Object(entries) => {
+                serializer.collect_map(entries.iter().map(|(key, value)| (key, value)))
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -573,6 +605,55 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_bytes() {
+        let mut deno_module = DenoModule::new(
+            UserCode::LoadFromFs(
+                Path::new("src")
+                    .join("test_js")
+                    .join("direct.js")
+                    .to_owned(),
+            ),
+            vec![],
+            vec![],
+            vec![],
+            None,
+            None,
+            None,
+            Arc::new(MapEnvironment::default()),
+        )
+        .await
+        .unwrap();
+
+        let ret_value = deno_module
+            .execute_function(
+                "describeBytes",
+                vec![
+                    Arg::Value(ArgValue::Bytes(b"hi".to_vec())),
+                    Arg::Value(ArgValue::Object(vec![
+                        ("name".to_string(), ArgValue::Json(json!("report"))),
+                        (
+                            "pages".to_string(),
+                            ArgValue::List(vec![
+                                ArgValue::Bytes(vec![1, 2]),
+                                ArgValue::Bytes(vec![]),
+                            ]),
+                        ),
+                    ])),
+                ],
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            ret_value,
+            json!([
+                { "bytes": [104, 105] },
+                { "name": "report", "pages": [{ "bytes": [1, 2] }, { "bytes": [] }] }
+            ])
+        );
+    }
+
     #[tokio::test]
     async fn test_shim_sync() {
         static GET_JSON_SHIM: (&str, &[&str]) = ("__shim", &[include_str!("./test_js/shim.js")]);
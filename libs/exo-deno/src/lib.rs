@@ -21,7 +21,7 @@ use std::sync::atomic::{AtomicBool, Ordering};
 
 pub use deno_error;
 pub use deno_executor_pool::DenoExecutorPool;
-pub use deno_module::{Arg, DenoModule, UserCode};
+pub use deno_module::{Arg, ArgValue, DenoModule, UserCode};
 
 mod deno_actor;
 mod embedded_module_loader;
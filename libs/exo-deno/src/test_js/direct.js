@@ -17,4 +17,18 @@ export async function getJson(id) {
   return await r.json();
 }
 
-
+export function describeBytes(...values) {
+  const describe = (value) => {
+    if (value instanceof Uint8Array) {
+      return { bytes: Array.from(value) };
+    } else if (Array.isArray(value)) {
+      return value.map(describe);
+    } else if (value !== null && typeof value === "object") {
+      return Object.fromEntries(
+        Object.entries(value).map(([key, value]) => [key, describe(value)]),
+      );
+    }
+    return value;
+  };
+  return values.map(describe);
+}
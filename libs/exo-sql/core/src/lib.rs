@@ -44,8 +44,8 @@ pub use physical_column::ColumnId;
 pub use physical_column::ColumnReference;
 pub use physical_column::PhysicalColumn;
 pub use physical_table::PhysicalEnum;
-pub use physical_table::PhysicalIndex;
 pub use physical_table::PhysicalTable;
+pub use physical_table::{IndexColumn, IndexTarget, PhysicalCheck, PhysicalIndex};
 pub use relation::{
     ManyToOne, ManyToOneId, OneToMany, OneToManyId, RelationColumnPair, RelationId,
 };
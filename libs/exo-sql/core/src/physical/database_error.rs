@@ -53,6 +53,10 @@ pub enum DatabaseError {
     #[error("Precheck: {0}")]
     Precheck(String),
 
+    /// The database canceled the query (for example, because it exceeded the statement timeout)
+    #[error("Query canceled: {0}")]
+    Canceled(String),
+
     #[error("{0}")]
     Generic(String),
 }
@@ -40,6 +40,10 @@ pub struct PhysicalColumn {
 
     /// references to other foreign columns. A column can have multiple references if it point to multiple tables.
     pub column_references: Option<Vec<ColumnReference>>,
+
+    /// The name this column had before being renamed (through `@column(previously=...)`), so that
+    /// migrations can rename the column instead of dropping and recreating it.
+    pub previous_name: Option<String>,
 }
 
 impl Clone for PhysicalColumn {
@@ -54,6 +58,7 @@ impl Clone for PhysicalColumn {
             default_value: self.default_value.clone(),
             update_sync: self.update_sync,
             column_references: self.column_references.clone(),
+            previous_name: self.previous_name.clone(),
         }
     }
 }
@@ -69,6 +74,7 @@ impl PartialEq for PhysicalColumn {
             && self.default_value == other.default_value
             && self.update_sync == other.update_sync
             && self.column_references == other.column_references
+            && self.previous_name == other.previous_name
     }
 }
 
@@ -7,9 +7,8 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use std::collections::HashSet;
-
 use crate::index_kind::PhysicalIndexKind;
+use crate::order::Ordering;
 use crate::physical_column::PhysicalColumn;
 use crate::schema_object::SchemaObjectName;
 
@@ -25,7 +24,15 @@ pub struct PhysicalTable {
 
     pub indices: Vec<PhysicalIndex>,
 
+    /// The `CHECK` constraints of the table.
+    pub checks: Vec<PhysicalCheck>,
+
     pub managed: bool,
+
+    /// Should changes to the rows be published to the change feed (through triggers that
+    /// `pg_notify` each inserted, updated, or deleted row)?
+    #[serde(default)]
+    pub change_feed: bool,
 }
 
 /// A physical enum in the database such as "Priority" with variants "LOW", "MEDIUM", "HIGH".
@@ -38,7 +45,10 @@ pub struct PhysicalEnum {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PhysicalIndex {
     pub name: String,
-    pub columns: HashSet<String>,
+    /// The index columns in order (the order matters for multi-column indexes)
+    pub columns: Vec<IndexColumn>,
+    /// The predicate of a partial index (the SQL for its `WHERE` clause)
+    pub predicate: Option<String>,
     pub index_kind: Box<dyn PhysicalIndexKind>,
 }
 
@@ -46,12 +56,60 @@ impl PartialEq for PhysicalIndex {
     fn eq(&self, other: &Self) -> bool {
         self.name == other.name
             && self.columns == other.columns
+            && self.predicate == other.predicate
             && self.index_kind.equals(other.index_kind.as_ref())
     }
 }
 
 impl Eq for PhysicalIndex {}
 
+impl PhysicalIndex {
+    pub fn column_names(&self) -> impl Iterator<Item = &str> {
+        self.columns
+            .iter()
+            .filter_map(|column| column.column_name())
+    }
+}
+
+/// A column of an index along with its sort order
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct IndexColumn {
+    pub target: IndexTarget,
+    pub ordering: Ordering,
+}
+
+/// What an index column covers: a table column or an expression (such as `lower("email")`)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub enum IndexTarget {
+    Column(String),
+    Expression(String),
+}
+
+impl IndexColumn {
+    /// An ascending index column for the given table column (the most common case)
+    pub fn column(name: impl Into<String>) -> Self {
+        Self {
+            target: IndexTarget::Column(name.into()),
+            ordering: Ordering::Asc,
+        }
+    }
+
+    pub fn column_name(&self) -> Option<&str> {
+        match &self.target {
+            IndexTarget::Column(name) => Some(name),
+            IndexTarget::Expression(_) => None,
+        }
+    }
+}
+
+/// A `CHECK` constraint such as `"start_at" < "end_at"`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct PhysicalCheck {
+    pub name: String,
+    /// The SQL expression (without the surrounding `CHECK (...)`)
+    pub expression: String,
+}
+
 /// The derived implementation of `Debug` is quite verbose, so we implement it manually
 /// to print the table and columns names only.
 impl std::fmt::Debug for PhysicalTable {
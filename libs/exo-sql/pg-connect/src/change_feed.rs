@@ -0,0 +1,319 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use exo_sql_core::{SchemaObjectName, database_error::DatabaseError};
+
+/// The channel on which the triggers of the tables with a change feed `pg_notify` row changes
+pub const CHANGE_FEED_CHANNEL: &str = "exograph_changes";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ChangeOperation {
+    Insert,
+    Update,
+    Delete,
+}
+
+/// A change to a row of a table
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeEvent {
+    pub table: SchemaObjectName,
+    pub operation: ChangeOperation,
+    /// The primary key of the changed row (the values keyed by the column names)
+    pub pk: Map<String, Value>,
+}
+
+/// The notification payload (see `DatabaseSpec::change_feed_triggers` in pg-schema)
+#[derive(Deserialize)]
+struct ChangePayload {
+    schema: String,
+    table: String,
+    op: ChangeOperation,
+    pk: Map<String, Value>,
+}
+
+impl ChangeEvent {
+    #[cfg_attr(not(feature = "postgres-url"), allow(dead_code))]
+    pub(crate) fn from_payload(payload: &str) -> Result<Self, DatabaseError> {
+        let payload: ChangePayload = serde_json::from_str(payload)
+            .map_err(|e| DatabaseError::Generic(format!("Invalid change payload: {e}")))?;
+
+        Ok(Self {
+            table: SchemaObjectName::new_with_schema_name(payload.table, payload.schema),
+            operation: payload.op,
+            pk: payload.pk,
+        })
+    }
+}
+
+#[cfg(feature = "postgres-url")]
+pub use listener::ChangeFeed;
+
+#[cfg(feature = "postgres-url")]
+mod listener {
+    use std::time::Duration;
+
+    use futures::Stream;
+    use tokio::{
+        sync::{broadcast, mpsc},
+        task::JoinHandle,
+    };
+
+    use exo_sql_core::database_error::DatabaseError;
+
+    use super::{CHANGE_FEED_CHANNEL, ChangeEvent};
+    use crate::connect::{DatabaseCreation, PgBouncerMode, TransactionMode};
+
+    /// The number of events to hold for a subscriber that falls behind (beyond which it misses events)
+    const EVENT_BUFFER_SIZE: usize = 1024;
+
+    const INITIAL_RETRY_DELAY: Duration = Duration::from_millis(500);
+    const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+    /// Listens for the row changes published by the change feed triggers over a dedicated
+    /// connection (reconnecting if the connection fails) and hands them to the subscribers (see
+    /// `events`).
+    ///
+    /// Postgres delivers a notification only to the connections listening at the time, so the
+    /// changes made while the feed is reconnecting are lost.
+    pub struct ChangeFeed {
+        sender: broadcast::Sender<ChangeEvent>,
+        listener: JoinHandle<()>,
+    }
+
+    impl ChangeFeed {
+        /// Start listening to the database at the URL. Since `LISTEN` needs a session of its own,
+        /// the URL must point to the primary directly (not through PgBouncer in transaction pooling
+        /// mode and not to a read replica).
+        pub fn start(url: &str) -> Result<Self, DatabaseError> {
+            let (_, pgbouncer) = PgBouncerMode::from_url(url, TransactionMode::ReadWrite)?;
+            if pgbouncer.is_some() {
+                return Err(DatabaseError::Config(
+                    "The change feed requires a direct connection to Postgres (LISTEN doesn't work through PgBouncer in transaction pooling mode)".into(),
+                ));
+            }
+
+            let (sender, _) = broadcast::channel(EVENT_BUFFER_SIZE);
+            let listener = tokio::spawn(listen(url.to_string(), sender.clone()));
+
+            Ok(Self { sender, listener })
+        }
+
+        /// A stream of the changes from now on
+        pub fn events(&self) -> impl Stream<Item = ChangeEvent> + Send + 'static {
+            futures::stream::unfold(self.sender.subscribe(), |mut receiver| async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(event) => return Some((event, receiver)),
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            tracing::warn!(
+                                "A change feed subscriber fell behind and missed {skipped} changes"
+                            );
+                        }
+                        Err(broadcast::error::RecvError::Closed) => return None,
+                    }
+                }
+            })
+        }
+    }
+
+    impl Drop for ChangeFeed {
+        fn drop(&mut self) {
+            self.listener.abort();
+        }
+    }
+
+    async fn listen(url: String, sender: broadcast::Sender<ChangeEvent>) {
+        let mut delay = INITIAL_RETRY_DELAY;
+
+        loop {
+            match listen_until_disconnected(&url, &sender).await {
+                Ok(()) => {
+                    delay = INITIAL_RETRY_DELAY;
+                    tracing::warn!("The change feed connection closed. Reconnecting in {delay:?}");
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to listen for changes: {e}. Retrying in {delay:?}");
+                }
+            }
+
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(MAX_RETRY_DELAY);
+        }
+    }
+
+    async fn listen_until_disconnected(
+        url: &str,
+        sender: &broadcast::Sender<ChangeEvent>,
+    ) -> Result<(), DatabaseError> {
+        let (notifications_sender, mut notifications) = mpsc::unbounded_channel();
+
+        let client = DatabaseCreation::connect_url(
+            url,
+            TransactionMode::ReadWrite,
+            Some(notifications_sender),
+        )
+        .await?;
+        client
+            .batch_execute(&format!("LISTEN {CHANGE_FEED_CHANNEL}"))
+            .await
+            .map_err(DatabaseError::driver)?;
+
+        tracing::info!("Listening for changes on the '{CHANGE_FEED_CHANNEL}' channel");
+
+        // The connection task drops the sender (ending this loop) once the connection closes
+        while let Some(notification) = notifications.recv().await {
+            match ChangeEvent::from_payload(notification.payload()) {
+                // Without any subscribers, there is no one to hand the event to
+                Ok(event) => {
+                    let _ = sender.send(event);
+                }
+                Err(e) => tracing::warn!("Ignoring a change notification: {e}"),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn change_payload() {
+        let event = ChangeEvent::from_payload(
+            r#"{"schema": "public", "table": "todos", "op": "UPDATE", "pk": {"id": 5}}"#,
+        )
+        .unwrap();
+        assert_eq!(event.table, SchemaObjectName::new("todos", None));
+        assert_eq!(event.operation, ChangeOperation::Update);
+        assert_eq!(Value::Object(event.pk), json!({"id": 5}));
+
+        let event = ChangeEvent::from_payload(
+            r#"{"schema": "auth", "table": "memberships", "op": "DELETE", "pk": {"user_id": 1, "group_id": "admins"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            event.table,
+            SchemaObjectName::new("memberships", Some("auth"))
+        );
+        assert_eq!(event.operation, ChangeOperation::Delete);
+        assert_eq!(
+            Value::Object(event.pk),
+            json!({"user_id": 1, "group_id": "admins"})
+        );
+
+        assert!(ChangeEvent::from_payload(r#"{"table": "todos"}"#).is_err());
+        assert!(
+            ChangeEvent::from_payload(
+                r#"{"schema": "public", "table": "todos", "op": "TRUNCATE", "pk": {}}"#
+            )
+            .is_err()
+        );
+    }
+
+    #[cfg(feature = "postgres-url")]
+    #[test]
+    fn change_feed_rejects_pgbouncer() {
+        assert!(ChangeFeed::start("postgres://user@host/db?pgbouncer=true").is_err());
+    }
+}
+
+#[cfg(all(test, feature = "test-support"))]
+mod database_tests {
+    use std::time::Duration;
+
+    use futures::{StreamExt, pin_mut};
+    use serde_json::json;
+
+    use crate::testing::with_db_url;
+    use crate::{DatabaseClientManager, TransactionMode};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_change_feed() {
+        with_db_url(|url| async move {
+            let client_manager =
+                DatabaseClientManager::from_url_direct(&url, false, TransactionMode::ReadWrite)
+                    .await
+                    .unwrap();
+            let client = client_manager.get_client().await.unwrap();
+
+            client
+                .batch_execute(&format!(
+                    "CREATE TABLE todos (id SERIAL PRIMARY KEY, title TEXT NOT NULL);
+                     CREATE FUNCTION notify_todos() RETURNS TRIGGER AS $$
+                     DECLARE changed RECORD;
+                     BEGIN
+                        IF TG_OP = 'DELETE' THEN changed := OLD; ELSE changed := NEW; END IF;
+                        PERFORM pg_notify('{CHANGE_FEED_CHANNEL}', json_build_object('schema', TG_TABLE_SCHEMA, 'table', TG_TABLE_NAME, 'op', TG_OP, 'pk', json_build_object('id', changed.id))::text);
+                        RETURN NULL;
+                     END;
+                     $$ language 'plpgsql';
+                     CREATE TRIGGER notify_todos AFTER INSERT OR UPDATE OR DELETE ON todos FOR EACH ROW EXECUTE FUNCTION notify_todos();"
+                ))
+                .await
+                .unwrap();
+
+            let feed = ChangeFeed::start(&url).unwrap();
+            let events = feed.events();
+            pin_mut!(events);
+
+            // The feed starts listening in the background, so keep inserting until it sees a row
+            let first_event = loop {
+                client
+                    .batch_execute("INSERT INTO todos (title) VALUES ('first')")
+                    .await
+                    .unwrap();
+
+                if let Ok(event) =
+                    tokio::time::timeout(Duration::from_millis(200), events.next()).await
+                {
+                    break event.unwrap();
+                }
+            };
+            assert_eq!(first_event.table, SchemaObjectName::new("todos", None));
+            assert_eq!(first_event.operation, ChangeOperation::Insert);
+
+            // Skip the events for the rows inserted after the first one the feed saw
+            let first_id = first_event.pk["id"].as_i64().unwrap();
+            let last_id = client
+                .query("SELECT MAX(id)::bigint FROM todos", &[])
+                .await
+                .unwrap()[0]
+                .get::<_, i64>(0);
+            for _ in first_id..last_id {
+                events.next().await.unwrap();
+            }
+
+            client
+                .batch_execute(&format!(
+                    "UPDATE todos SET title = 'updated' WHERE id = {first_id}; DELETE FROM todos WHERE id = {first_id}"
+                ))
+                .await
+                .unwrap();
+
+            let event = events.next().await.unwrap();
+            assert_eq!(event.operation, ChangeOperation::Update);
+            assert_eq!(Value::Object(event.pk), json!({"id": first_id}));
+
+            let event = events.next().await.unwrap();
+            assert_eq!(event.operation, ChangeOperation::Delete);
+            assert_eq!(Value::Object(event.pk), json!({"id": first_id}));
+        })
+        .await;
+    }
+}
@@ -0,0 +1,104 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::sync::Arc;
+
+use tokio_postgres::CancelToken;
+
+use exo_sql_core::database_error::DatabaseError;
+
+use super::database_client::DatabaseClient;
+
+/// Cancels queries running on connections to a database (using the same TLS settings as the
+/// connections themselves)
+#[derive(Clone)]
+pub(crate) struct QueryCanceller {
+    #[cfg_attr(not(feature = "postgres-url"), allow(dead_code))]
+    url: Arc<str>,
+}
+
+impl QueryCanceller {
+    #[cfg(feature = "postgres-url")]
+    pub(crate) fn new(url: &str) -> Self {
+        Self { url: url.into() }
+    }
+
+    async fn cancel(&self, token: CancelToken) -> Result<(), DatabaseError> {
+        #[cfg(feature = "postgres-url")]
+        {
+            use std::str::FromStr;
+
+            use super::{
+                creation::{PgBouncerMode, TransactionMode},
+                ssl_config::SslConfig,
+            };
+
+            // The transaction mode doesn't matter for cancelling; only the cleaned URL does
+            let (url, _) = PgBouncerMode::from_url(&self.url, TransactionMode::ReadWrite)?;
+            let (url, ssl_config) = SslConfig::from_url(&url)?;
+            let config = tokio_postgres::Config::from_str(&url).map_err(DatabaseError::driver)?;
+
+            // As with the connections, use TLS only for TCP hosts
+            let has_tcp_hosts = config
+                .get_hosts()
+                .iter()
+                .any(|host| matches!(host, tokio_postgres::config::Host::Tcp(_)));
+
+            match ssl_config {
+                Some(ssl_config) if has_tcp_hosts => {
+                    let (_, tls) = ssl_config.updated_config(config)?;
+                    token.cancel_query(tls).await
+                }
+                _ => token.cancel_query(tokio_postgres::NoTls).await,
+            }
+            .map_err(DatabaseError::driver)
+        }
+
+        #[cfg(not(feature = "postgres-url"))]
+        {
+            let _ = token;
+            Ok(())
+        }
+    }
+}
+
+/// Cancels the query running on a client if dropped before `disarm` is called. Create one before
+/// running a query and disarm it once the query finishes; if the future running the query is
+/// dropped instead (for example, because the HTTP client disconnected), the server stops
+/// executing the query (instead of running it to completion with no one to receive the result).
+pub(crate) struct CancelOnDrop {
+    cancellation: Option<(CancelToken, QueryCanceller)>,
+}
+
+impl CancelOnDrop {
+    pub(crate) fn new(client: &DatabaseClient, canceller: Option<QueryCanceller>) -> Self {
+        Self {
+            cancellation: canceller.map(|canceller| (client.cancel_token(), canceller)),
+        }
+    }
+
+    pub(crate) fn disarm(mut self) {
+        self.cancellation = None;
+    }
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        if let Some((token, canceller)) = self.cancellation.take()
+            && let Ok(runtime) = tokio::runtime::Handle::try_current()
+        {
+            tracing::info!("Request dropped while running a query. Cancelling the query.");
+            runtime.spawn(async move {
+                if let Err(e) = canceller.cancel(token).await {
+                    tracing::warn!("Failed to cancel the query: {e}");
+                }
+            });
+        }
+    }
+}
@@ -8,12 +8,16 @@
 // by the Apache License, Version 2.0.
 
 use futures::future::BoxFuture;
+#[cfg(feature = "postgres-url")]
+use tokio::sync::mpsc::UnboundedSender;
 use tokio::task::JoinHandle;
 use tokio_postgres::Config;
+#[cfg(feature = "postgres-url")]
+use tokio_postgres::Notification;
 
 use exo_sql_core::database_error::DatabaseError;
 
-use super::database_client::DatabaseClient;
+use super::{cancellation::QueryCanceller, database_client::DatabaseClient};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TransactionMode {
@@ -38,6 +42,64 @@ impl TransactionMode {
     }
 }
 
+/// How to work with PgBouncer in transaction pooling mode, which assigns a server connection to a
+/// client only for the duration of a transaction. Consecutive statements outside a transaction may
+/// run on different server connections, so we can't rely on any session state: named prepared
+/// statements (prepared on one connection, but executed on another) or session settings (such as
+/// `default_transaction_read_only`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PgBouncerMode {
+    /// Enforce read-only mode by starting each transaction with `BEGIN READ ONLY` (and running all
+    /// writes in a transaction) instead of through the session setting
+    pub(crate) read_only: bool,
+}
+
+impl PgBouncerMode {
+    /// Remove the `pgbouncer` parameter (which the driver doesn't know about) from the URL. If it
+    /// is `true`, return the mode to connect in.
+    #[cfg(feature = "postgres-url")]
+    pub(crate) fn from_url(
+        url: &str,
+        transaction_mode: TransactionMode,
+    ) -> Result<(String, Option<PgBouncerMode>), DatabaseError> {
+        let parsed_url = url::Url::parse(url)
+            .map_err(|_| DatabaseError::Config("Invalid database URL".into()))?;
+
+        let Some((_, pgbouncer_param)) = parsed_url
+            .query_pairs()
+            .find(|(name, _)| name == "pgbouncer")
+        else {
+            return Ok((url.to_string(), None));
+        };
+
+        let pgbouncer: bool = pgbouncer_param.parse().map_err(|_| {
+            DatabaseError::Config(format!(
+                "Invalid pgbouncer parameter value '{pgbouncer_param}' (expected 'true' or 'false')"
+            ))
+        })?;
+
+        let mut cleaned_url = parsed_url.clone();
+        cleaned_url.query_pairs_mut().clear().extend_pairs(
+            parsed_url
+                .query_pairs()
+                .filter(|(name, _)| name != "pgbouncer"),
+        );
+        // Don't leave a dangling '?' if `pgbouncer` was the only parameter
+        if cleaned_url.query() == Some("") {
+            cleaned_url.set_query(None);
+        }
+
+        let mode = pgbouncer.then(|| {
+            tracing::info!("Connecting through PgBouncer (in transaction pooling mode)");
+            PgBouncerMode {
+                read_only: transaction_mode == TransactionMode::ReadOnly,
+            }
+        });
+
+        Ok((cleaned_url.to_string(), mode))
+    }
+}
+
 pub enum DatabaseCreation {
     #[cfg(feature = "postgres-url")]
     Url {
@@ -51,6 +113,14 @@ pub enum DatabaseCreation {
 }
 
 impl DatabaseCreation {
+    pub(crate) fn canceller(&self) -> Option<QueryCanceller> {
+        match self {
+            #[cfg(feature = "postgres-url")]
+            DatabaseCreation::Url { url, .. } => Some(QueryCanceller::new(url)),
+            DatabaseCreation::Connect { .. } => None,
+        }
+    }
+
     pub async fn get_client(&self) -> Result<DatabaseClient, DatabaseError> {
         match self {
             DatabaseCreation::Connect {
@@ -60,7 +130,7 @@ impl DatabaseCreation {
                     .connect(config)
                     .await
                     .map_err(DatabaseError::driver)?;
-                Ok(DatabaseClient::Direct(client))
+                Ok(DatabaseClient::direct(client))
             }
             #[cfg(feature = "postgres-url")]
             DatabaseCreation::Url {
@@ -74,18 +144,33 @@ impl DatabaseCreation {
     async fn from_url(
         url: &str,
         transaction_mode: TransactionMode,
+    ) -> Result<DatabaseClient, DatabaseError> {
+        Self::connect_url(url, transaction_mode, None).await
+    }
+
+    /// Connect to the database at the URL. If `notifications` is set, forward the notifications
+    /// (for the channels the client `LISTEN`s to) to it.
+    #[cfg(feature = "postgres-url")]
+    pub(crate) async fn connect_url(
+        url: &str,
+        transaction_mode: TransactionMode,
+        notifications: Option<UnboundedSender<Notification>>,
     ) -> Result<DatabaseClient, DatabaseError> {
         use std::str::FromStr;
 
         use super::ssl_config::SslConfig;
 
-        let (url, ssl_config) = SslConfig::from_url(url)?;
+        let (url, pgbouncer) = PgBouncerMode::from_url(url, transaction_mode)?;
+        let (url, ssl_config) = SslConfig::from_url(&url)?;
 
         let mut config = Config::from_str(&url).map_err(|e| {
             DatabaseError::driver(e)
                 .with_context("Failed to parse PostgreSQL connection string".into())
         })?;
-        transaction_mode.update_config(&mut config);
+        // PgBouncer doesn't support startup options, so enforce the transaction mode per transaction
+        if pgbouncer.is_none() {
+            transaction_mode.update_config(&mut config);
+        }
 
         // Only if there is any TCP host, use the TLS connector (with the new Rustls version, SSL over unix sockets errors out)
         let has_tcp_hosts = config
@@ -100,31 +185,59 @@ impl DatabaseCreation {
                 let (client, connection) =
                     config.connect(tls).await.map_err(DatabaseError::driver)?;
 
-                tokio::spawn(async move {
-                    if let Err(e) = connection.await {
-                        tracing::error!("connection error: {}", e);
-                    }
-                });
+                spawn_connection(connection, notifications);
 
-                Ok(DatabaseClient::Direct(client))
+                Ok(DatabaseClient::direct(client).with_pgbouncer(pgbouncer))
             }
             _ => {
                 let tls = tokio_postgres::NoTls;
                 let (client, connection) =
                     config.connect(tls).await.map_err(DatabaseError::driver)?;
 
-                tokio::spawn(async move {
-                    if let Err(e) = connection.await {
-                        tracing::error!("connection error: {}", e);
-                    }
-                });
+                spawn_connection(connection, notifications);
 
-                Ok(DatabaseClient::Direct(client))
+                Ok(DatabaseClient::direct(client).with_pgbouncer(pgbouncer))
             }
         }
     }
 }
 
+/// Drive the connection in the background (until it closes), forwarding its notifications to
+/// `notifications` (if set)
+#[cfg(feature = "postgres-url")]
+fn spawn_connection<S, T>(
+    mut connection: tokio_postgres::Connection<S, T>,
+    notifications: Option<UnboundedSender<Notification>>,
+) where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static,
+    T: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static,
+{
+    use futures::StreamExt;
+    use tokio_postgres::AsyncMessage;
+
+    tokio::spawn(async move {
+        let mut messages = futures::stream::poll_fn(move |cx| connection.poll_message(cx));
+
+        while let Some(message) = messages.next().await {
+            match message {
+                Ok(AsyncMessage::Notification(notification)) => {
+                    if let Some(notifications) = &notifications {
+                        let _ = notifications.send(notification);
+                    }
+                }
+                Ok(AsyncMessage::Notice(notice)) => {
+                    tracing::info!("{}: {}", notice.severity(), notice.message());
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::error!("connection error: {}", e);
+                    break;
+                }
+            }
+        }
+    });
+}
+
 /// A trait for connecting to a database.
 ///
 /// Implementation note: This is the same as deadpool_postgres::Connect, but allows to be used even
@@ -135,3 +248,40 @@ pub trait Connect: Sync + Send {
         pg_config: &tokio_postgres::Config,
     ) -> BoxFuture<'_, Result<(tokio_postgres::Client, JoinHandle<()>), tokio_postgres::Error>>;
 }
+
+#[cfg(all(test, feature = "postgres-url"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pgbouncer_param() {
+        let (url, mode) = PgBouncerMode::from_url(
+            "postgres://user@host/db?pgbouncer=true&sslmode=require",
+            TransactionMode::ReadOnly,
+        )
+        .unwrap();
+        assert_eq!(url, "postgres://user@host/db?sslmode=require");
+        assert_eq!(mode, Some(PgBouncerMode { read_only: true }));
+
+        let (url, mode) = PgBouncerMode::from_url(
+            "postgres://user@host/db?pgbouncer=false",
+            TransactionMode::ReadWrite,
+        )
+        .unwrap();
+        assert_eq!(url, "postgres://user@host/db");
+        assert_eq!(mode, None);
+
+        let (url, mode) =
+            PgBouncerMode::from_url("postgres://user@host/db", TransactionMode::ReadWrite).unwrap();
+        assert_eq!(url, "postgres://user@host/db");
+        assert_eq!(mode, None);
+
+        assert!(
+            PgBouncerMode::from_url(
+                "postgres://user@host/db?pgbouncer=yes",
+                TransactionMode::ReadWrite
+            )
+            .is_err()
+        );
+    }
+}
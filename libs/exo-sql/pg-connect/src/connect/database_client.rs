@@ -9,9 +9,18 @@
 
 use std::ops::{Deref, DerefMut};
 
-use tokio_postgres::ToStatement;
+use futures::{StreamExt, pin_mut};
+use tokio_postgres::{ToStatement, types::Type};
 
-pub enum DatabaseClient {
+use super::creation::PgBouncerMode;
+
+pub struct DatabaseClient {
+    client: ClientKind,
+    /// Connected through PgBouncer in transaction pooling mode (see `PgBouncerMode`)
+    pgbouncer: Option<PgBouncerMode>,
+}
+
+enum ClientKind {
     #[cfg(feature = "pool")]
     Pooled(deadpool_postgres::Client),
     Direct(tokio_postgres::Client),
@@ -21,52 +30,214 @@ impl Deref for DatabaseClient {
     type Target = tokio_postgres::Client;
 
     fn deref(&self) -> &Self::Target {
-        match self {
+        match &self.client {
             #[cfg(feature = "pool")]
-            DatabaseClient::Pooled(client) => client,
-            DatabaseClient::Direct(client) => client,
+            ClientKind::Pooled(client) => client,
+            ClientKind::Direct(client) => client,
         }
     }
 }
 
 impl DerefMut for DatabaseClient {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        match self {
+        match &mut self.client {
             #[cfg(feature = "pool")]
-            DatabaseClient::Pooled(client) => client,
-            DatabaseClient::Direct(client) => client,
+            ClientKind::Pooled(client) => client,
+            ClientKind::Direct(client) => client,
         }
     }
 }
 
 impl DatabaseClient {
+    #[cfg(feature = "pool")]
+    pub(crate) fn pooled(client: deadpool_postgres::Client) -> Self {
+        Self {
+            client: ClientKind::Pooled(client),
+            pgbouncer: None,
+        }
+    }
+
+    pub(crate) fn direct(client: tokio_postgres::Client) -> Self {
+        Self {
+            client: ClientKind::Direct(client),
+            pgbouncer: None,
+        }
+    }
+
+    pub(crate) fn with_pgbouncer(self, pgbouncer: Option<PgBouncerMode>) -> Self {
+        Self { pgbouncer, ..self }
+    }
+
+    /// Should every write run in a (read-only) transaction?
+    pub(crate) fn requires_read_only_transactions(&self) -> bool {
+        self.pgbouncer.is_some_and(|mode| mode.read_only)
+    }
+
+    /// Can settings be set for the session (instead of only for a transaction)? Through PgBouncer,
+    /// the next transaction may run on another server connection.
+    pub(crate) fn supports_session_settings(&self) -> bool {
+        self.pgbouncer.is_none()
+    }
+
+    /// Will the connection go back to a pool (and serve another request) once dropped?
+    pub(crate) fn is_pooled(&self) -> bool {
+        match &self.client {
+            #[cfg(feature = "pool")]
+            ClientKind::Pooled(_) => true,
+            ClientKind::Direct(_) => false,
+        }
+    }
+
+    /// Close the connection instead of returning it to the pool (for example, because it may be in
+    /// the middle of a transaction)
+    pub(crate) fn discard(self) {
+        match self.client {
+            #[cfg(feature = "pool")]
+            ClientKind::Pooled(client) => drop(deadpool_postgres::Object::take(client)),
+            ClientKind::Direct(client) => drop(client),
+        }
+    }
+
+    /// The statement to begin a transaction
+    pub(crate) fn begin_statement(&self) -> &'static str {
+        if self.requires_read_only_transactions() {
+            "BEGIN READ ONLY"
+        } else {
+            "BEGIN"
+        }
+    }
+
+    /// Run a query. Through PgBouncer, the query uses an unnamed statement (and leaves the
+    /// parameter types for Postgres to infer, which works for text parameters).
     pub async fn query<T>(
         &self,
         query: &T,
         params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
     ) -> Result<Vec<tokio_postgres::Row>, tokio_postgres::Error>
     where
-        T: ?Sized + ToStatement,
+        T: ?Sized + ToStatement + AsRef<str>,
     {
-        match self {
+        if self.pgbouncer.is_some() {
+            return self
+                .deref()
+                .query_typed(query.as_ref(), &untyped_params(params))
+                .await;
+        }
+
+        match &self.client {
             #[cfg(feature = "pool")]
-            DatabaseClient::Pooled(client) => client.query(query, params).await,
-            DatabaseClient::Direct(client) => client.query(query, params).await,
+            ClientKind::Pooled(client) => client.query(query, params).await,
+            ClientKind::Direct(client) => client.query(query, params).await,
         }
     }
 
+    /// Execute a statement and return the number of rows it affected (see `query` for how it
+    /// works through PgBouncer)
     pub async fn execute<T>(
         &self,
         query: &T,
         params: &[&(dyn tokio_postgres::types::ToSql + Sync)],
     ) -> Result<u64, tokio_postgres::Error>
     where
-        T: ?Sized + ToStatement,
+        T: ?Sized + ToStatement + AsRef<str>,
     {
-        match self {
+        if self.pgbouncer.is_some() {
+            let rows = self
+                .deref()
+                .query_typed_raw(query.as_ref(), untyped_params(params))
+                .await?;
+            pin_mut!(rows);
+            while let Some(row) = rows.next().await {
+                row?;
+            }
+            return Ok(rows.rows_affected().unwrap_or(0));
+        }
+
+        match &self.client {
             #[cfg(feature = "pool")]
-            DatabaseClient::Pooled(client) => client.execute(query, params).await,
-            DatabaseClient::Direct(client) => client.execute(query, params).await,
+            ClientKind::Pooled(client) => client.execute(query, params).await,
+            ClientKind::Direct(client) => client.execute(query, params).await,
         }
     }
 }
+
+fn untyped_params<'a>(
+    params: &[&'a (dyn tokio_postgres::types::ToSql + Sync)],
+) -> Vec<(&'a (dyn tokio_postgres::types::ToSql + Sync), Type)> {
+    params.iter().map(|param| (*param, Type::UNKNOWN)).collect()
+}
+
+#[cfg(all(test, feature = "test-support"))]
+mod database_tests {
+    use exo_sql_core::database_error::DatabaseError;
+
+    use crate::testing::{PgBouncerStandIn, with_db_url};
+    use crate::{DatabaseClientManager, TransactionMode};
+
+    use super::*;
+
+    async fn client(
+        url: &str,
+        transaction_mode: TransactionMode,
+    ) -> Result<DatabaseClient, DatabaseError> {
+        DatabaseClientManager::from_url_direct(url, false, transaction_mode)
+            .await
+            .unwrap()
+            .get_client()
+            .await
+    }
+
+    /// Without the `pgbouncer` option, the connection relies on session state
+    #[tokio::test]
+    async fn test_session_state_without_pgbouncer_mode() {
+        with_db_url(|url| async move {
+            let stand_in = PgBouncerStandIn::start(&url).await;
+
+            // Read-only mode sets `default_transaction_read_only` through a startup option
+            assert!(
+                client(&stand_in.url(false), TransactionMode::ReadOnly)
+                    .await
+                    .is_err()
+            );
+
+            let client = client(&stand_in.url(false), TransactionMode::ReadWrite)
+                .await
+                .unwrap();
+            client.query("SELECT $1::text", &[&"value"]).await.unwrap();
+
+            assert!(!stand_in.violations().is_empty());
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_pgbouncer_mode() {
+        with_db_url(|url| async move {
+            let stand_in = PgBouncerStandIn::start(&url).await;
+
+            let client = client(&stand_in.url(true), TransactionMode::ReadWrite)
+                .await
+                .unwrap();
+            client
+                .batch_execute("CREATE TABLE items (name TEXT NOT NULL)")
+                .await
+                .unwrap();
+
+            let inserted = client
+                .execute("INSERT INTO items (name) VALUES ($1)", &[&"first"])
+                .await
+                .unwrap();
+            assert_eq!(inserted, 1);
+
+            let rows = client
+                .query("SELECT name FROM items WHERE name = $1", &[&"first"])
+                .await
+                .unwrap();
+            assert_eq!(rows.len(), 1);
+            assert_eq!(rows[0].get::<_, String>(0), "first");
+
+            assert_eq!(stand_in.violations(), Vec::<String>::new());
+        })
+        .await;
+    }
+}
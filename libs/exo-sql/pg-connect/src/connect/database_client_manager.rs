@@ -10,20 +10,35 @@
 use exo_sql_core::database_error::DatabaseError;
 
 use super::{
+    cancellation::QueryCanceller,
     creation::{Connect, DatabaseCreation},
     database_client::DatabaseClient,
+    read_replicas::{ReadReplicas, ReplicaClient},
 };
 
 #[cfg(feature = "postgres-url")]
 use super::creation::TransactionMode;
 
+#[cfg(any(feature = "pool", feature = "postgres-url"))]
+use super::pool_options::PoolOptions;
+
 #[cfg(feature = "pool")]
 use super::database_pool::DatabasePool;
 
+#[cfg(feature = "postgres-url")]
+const INITIAL_RETRY_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+#[cfg(feature = "postgres-url")]
+const MAX_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(30);
+
 pub enum DatabaseClientManager {
     #[cfg(feature = "pool")]
     Pooled(DatabasePool),
     Direct(DatabaseCreation),
+    /// Routes read-only work (see `get_read_client`) to the replicas and everything else to the primary
+    WithReadReplicas {
+        primary: Box<DatabaseClientManager>,
+        replicas: ReadReplicas,
+    },
 }
 
 impl DatabaseClientManager {
@@ -61,7 +76,7 @@ impl DatabaseClientManager {
         };
 
         let res = Ok(Self::Pooled(
-            DatabasePool::create(creation, Some(pool_size)).await?,
+            DatabasePool::create(creation, &PoolOptions::with_max_size(Some(pool_size))).await?,
         ));
 
         if let Ok(ref res) = res
@@ -78,6 +93,47 @@ impl DatabaseClientManager {
             #[cfg(feature = "pool")]
             DatabaseClientManager::Pooled(pool) => pool.get_client().await,
             DatabaseClientManager::Direct(creation) => creation.get_client().await,
+            DatabaseClientManager::WithReadReplicas { primary, .. } => {
+                Box::pin(primary.get_client()).await
+            }
+        }
+    }
+
+    /// Route read-only work to the replicas (if any)
+    pub fn with_read_replicas(self, replicas: Vec<DatabaseClientManager>) -> Self {
+        if replicas.is_empty() {
+            self
+        } else {
+            Self::WithReadReplicas {
+                primary: Box::new(self),
+                replicas: ReadReplicas::new(replicas),
+            }
+        }
+    }
+
+    /// Get a client for a read replica. Returns `None` if there are no replicas or none is healthy,
+    /// in which case the caller should use `get_client` (the primary).
+    pub(crate) async fn get_read_client(&self) -> Option<ReplicaClient> {
+        match self {
+            DatabaseClientManager::WithReadReplicas { replicas, .. } => replicas.get_client().await,
+            _ => None,
+        }
+    }
+
+    /// The canceller for queries running on the clients from `get_client`
+    pub(crate) fn canceller(&self) -> Option<QueryCanceller> {
+        match self {
+            #[cfg(feature = "pool")]
+            DatabaseClientManager::Pooled(pool) => pool.canceller(),
+            DatabaseClientManager::Direct(creation) => creation.canceller(),
+            DatabaseClientManager::WithReadReplicas { primary, .. } => primary.canceller(),
+        }
+    }
+
+    /// Stop routing work to the replica (for a while) after it failed
+    pub(crate) fn mark_read_replica_unhealthy(&self, replica_index: usize) {
+        if let DatabaseClientManager::WithReadReplicas { replicas, .. } = self {
+            replicas.mark_unhealthy(replica_index);
         }
     }
 }
@@ -87,12 +143,27 @@ impl DatabaseClientManager {
     pub async fn from_url(
         url: &str,
         check_connection: bool,
-        #[allow(unused_variables)] pool_size: Option<usize>,
+        pool_size: Option<usize>,
+        transaction_mode: TransactionMode,
+    ) -> Result<Self, DatabaseError> {
+        Self::from_url_with_pool_options(
+            url,
+            check_connection,
+            &PoolOptions::with_max_size(pool_size),
+            transaction_mode,
+        )
+        .await
+    }
+
+    pub async fn from_url_with_pool_options(
+        url: &str,
+        check_connection: bool,
+        #[allow(unused_variables)] pool_options: &PoolOptions,
         transaction_mode: TransactionMode,
     ) -> Result<Self, DatabaseError> {
         #[cfg(feature = "pool")]
         {
-            Self::from_url_pooled(url, check_connection, pool_size, transaction_mode).await
+            Self::from_url_pooled(url, check_connection, pool_options, transaction_mode).await
         }
         #[cfg(not(feature = "pool"))]
         {
@@ -124,7 +195,7 @@ impl DatabaseClientManager {
     pub async fn from_url_pooled(
         url: &str,
         check_connection: bool,
-        pool_size: Option<usize>,
+        pool_options: &PoolOptions,
         transaction_mode: TransactionMode,
     ) -> Result<Self, DatabaseError> {
         let creation = DatabaseCreation::Url {
@@ -132,7 +203,7 @@ impl DatabaseClientManager {
             transaction_mode,
         };
         let res = Ok(Self::Pooled(
-            DatabasePool::create(creation, pool_size).await?,
+            DatabasePool::create(creation, pool_options).await?,
         ));
 
         if let Ok(ref res) = res
@@ -143,4 +214,80 @@ impl DatabaseClientManager {
 
         res
     }
+
+    /// Check that we can connect to the database. If not, retry up to `retries` times with an
+    /// exponential backoff (for example, to wait for a database that starts along with the server).
+    pub async fn check_connection(&self, retries: u32) -> Result<(), DatabaseError> {
+        let mut delay = INITIAL_RETRY_DELAY;
+        let mut attempt = 0;
+
+        loop {
+            attempt += 1;
+
+            match self.get_client().await {
+                Ok(_) => return Ok(()),
+                Err(e) if attempt <= retries => {
+                    tracing::warn!(
+                        "Failed to connect to the database (attempt {attempt} of {}): {e}. Retrying in {delay:?}",
+                        retries + 1
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay = (delay * 2).min(MAX_RETRY_DELAY);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "test-support"))]
+mod database_tests {
+    use std::time::{Duration, Instant};
+
+    use crate::testing::with_db_url;
+
+    use super::*;
+
+    /// A URL on which nothing listens
+    async fn unreachable_url() -> String {
+        let port = {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            listener.local_addr().unwrap().port()
+        };
+        format!("postgres://exo@127.0.0.1:{port}/exo?sslmode=disable")
+    }
+
+    #[tokio::test]
+    async fn test_check_connection() {
+        with_db_url(|url| async move {
+            let manager =
+                DatabaseClientManager::from_url_direct(&url, false, TransactionMode::ReadWrite)
+                    .await
+                    .unwrap();
+            manager.check_connection(0).await.unwrap();
+        })
+        .await;
+    }
+
+    /// An unreachable database fails the check after the retries (with an increasing delay)
+    #[tokio::test]
+    async fn test_check_connection_retries() {
+        let manager = DatabaseClientManager::from_url_direct(
+            &unreachable_url().await,
+            false,
+            TransactionMode::ReadWrite,
+        )
+        .await
+        .unwrap();
+
+        let start = Instant::now();
+        assert!(manager.check_connection(0).await.is_err());
+        assert!(start.elapsed() < INITIAL_RETRY_DELAY);
+
+        let start = Instant::now();
+        assert!(manager.check_connection(2).await.is_err());
+        // 500ms + 1s
+        assert!(start.elapsed() >= INITIAL_RETRY_DELAY * 3);
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
 }
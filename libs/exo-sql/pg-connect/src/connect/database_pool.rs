@@ -9,76 +9,119 @@
 
 #![cfg(feature = "pool")]
 
+use std::time::{Duration, Instant};
+
 #[cfg(feature = "postgres-url")]
 use deadpool_postgres::ConfigConnectImpl;
-use deadpool_postgres::{Connect, Manager, ManagerConfig, Pool, RecyclingMethod};
+use deadpool_postgres::{
+    Connect, Hook, HookError, Manager, ManagerConfig, Metrics, Pool, RecyclingMethod, Runtime,
+    Timeouts, WeakPool,
+};
 
 use tokio_postgres::Config;
 
 use crate::TransactionMode;
 use exo_sql_core::database_error::DatabaseError;
 
-use super::{creation::DatabaseCreation, database_client::DatabaseClient};
+use super::{
+    cancellation::QueryCanceller,
+    creation::{DatabaseCreation, PgBouncerMode},
+    database_client::DatabaseClient,
+    pool_options::PoolOptions,
+};
+
+/// How often to close expired connections, open idle connections (up to `min_idle`), and report the
+/// pool status
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(30);
 
 pub struct DatabasePool {
     pool: Pool,
+    canceller: Option<QueryCanceller>,
+    pgbouncer: Option<PgBouncerMode>,
 }
 
 impl DatabasePool {
     pub async fn create(
         creation: DatabaseCreation,
-        pool_size: Option<usize>,
+        options: &PoolOptions,
     ) -> Result<Self, DatabaseError> {
         match creation {
             #[cfg(feature = "postgres-url")]
             DatabaseCreation::Url {
                 url,
                 transaction_mode,
-            } => Self::from_db_url(&url, pool_size, transaction_mode).await,
+            } => {
+                let pool = Self::from_db_url(&url, options, transaction_mode).await?;
+                Ok(Self {
+                    canceller: Some(QueryCanceller::new(&url)),
+                    ..pool
+                })
+            }
             DatabaseCreation::Connect { config, connect } => {
-                Self::from_connect(pool_size, *config, ConnectBridge(connect)).await
+                Self::from_connect(options, *config, ConnectBridge(connect)).await
             }
         }
     }
 
     pub async fn get_client(&self) -> Result<DatabaseClient, DatabaseError> {
-        Ok(DatabaseClient::Pooled(
-            self.pool
-                .get()
-                .await
-                .map_err(|e| DatabaseError::BoxedError(Box::new(e)))?,
-        ))
+        let start = Instant::now();
+
+        let client = self.pool.get().await.map_err(|e| {
+            let status = self.pool.status();
+            tracing::warn!(
+                pool.size = status.size,
+                pool.max_size = status.max_size,
+                pool.waiting = status.waiting,
+                "Failed to get a database connection: {e}"
+            );
+            DatabaseError::BoxedError(Box::new(e))
+        })?;
+
+        tracing::trace!(
+            acquire_ms = start.elapsed().as_millis() as u64,
+            "Got a database connection"
+        );
+
+        Ok(DatabaseClient::pooled(client).with_pgbouncer(self.pgbouncer))
+    }
+
+    pub(crate) fn canceller(&self) -> Option<QueryCanceller> {
+        self.canceller.clone()
     }
 
     #[cfg(feature = "postgres-url")]
     async fn from_db_url(
         url: &str,
-        pool_size: Option<usize>,
+        options: &PoolOptions,
         transaction_mode: TransactionMode,
     ) -> Result<Self, DatabaseError> {
-        Self::from_helper(url, pool_size, transaction_mode).await
+        Self::from_helper(url, options, transaction_mode).await
     }
 
     #[cfg(feature = "postgres-url")]
     async fn from_helper(
         url: &str,
-        pool_size: Option<usize>,
+        options: &PoolOptions,
         transaction_mode: TransactionMode,
     ) -> Result<Self, DatabaseError> {
         use std::str::FromStr;
 
         use super::ssl_config::SslConfig;
 
-        let (url, ssl_config) = SslConfig::from_url(url)?;
+        let (url, pgbouncer) = PgBouncerMode::from_url(url, transaction_mode)?;
+        let (url, ssl_config) = SslConfig::from_url(&url)?;
 
         let mut config = Config::from_str(&url).map_err(|e| {
             DatabaseError::driver(e)
                 .with_context("Failed to parse PostgreSQL connection string".into())
         })?;
 
-        transaction_mode.update_config(&mut config);
+        // PgBouncer doesn't support startup options, so enforce the transaction mode per transaction
+        if pgbouncer.is_none() {
+            transaction_mode.update_config(&mut config);
+        }
 
-        match ssl_config {
+        let pool = match ssl_config {
             Some(ssl_config) => {
                 let (config, tls) = ssl_config.updated_config(config)?;
 
@@ -89,10 +132,10 @@ impl DatabasePool {
                     .any(|host| matches!(host, tokio_postgres::config::Host::Tcp(_)));
 
                 if has_tcp_hosts {
-                    Self::from_connect(pool_size, config, ConfigConnectImpl { tls }).await
+                    Self::from_connect(options, config, ConfigConnectImpl { tls }).await
                 } else {
                     Self::from_connect(
-                        pool_size,
+                        options,
                         config,
                         ConfigConnectImpl {
                             tls: tokio_postgres::NoTls,
@@ -103,7 +146,7 @@ impl DatabasePool {
             }
             None => {
                 Self::from_connect(
-                    pool_size,
+                    options,
                     config,
                     ConfigConnectImpl {
                         tls: tokio_postgres::NoTls,
@@ -111,35 +154,138 @@ impl DatabasePool {
                 )
                 .await
             }
-        }
+        }?;
+
+        Ok(Self { pgbouncer, ..pool })
     }
 
     pub async fn from_connect(
-        pool_size: Option<usize>,
+        options: &PoolOptions,
         config: Config,
         connect: impl Connect + 'static,
     ) -> Result<Self, DatabaseError> {
+        // A test query weeds out the connections that look open, but no longer work (for example,
+        // after a failover)
         let manager_config = ManagerConfig {
-            recycling_method: RecyclingMethod::Fast,
+            recycling_method: match &options.test_query {
+                Some(test_query) => RecyclingMethod::Custom(test_query.clone()),
+                None => RecyclingMethod::Fast,
+            },
         };
 
         let manager = Manager::from_connect(config, connect, manager_config);
 
-        let pool = Pool::builder(manager);
+        let (idle_timeout, max_lifetime) = (options.idle_timeout, options.max_lifetime);
+        let pool = Pool::builder(manager)
+            .runtime(Runtime::Tokio1)
+            .wait_timeout(options.acquire_timeout)
+            // Replace an expired connection instead of handing it out
+            .pre_recycle(Hook::sync_fn(move |_, metrics| {
+                if is_expired(metrics, idle_timeout, max_lifetime) {
+                    Err(HookError::message("Connection expired"))
+                } else {
+                    Ok(())
+                }
+            }));
 
-        let pool = match pool_size {
-            Some(pool_size) => pool.max_size(pool_size),
+        let pool = match options.max_size {
+            Some(max_size) => pool.max_size(max_size),
             None => pool,
         }
         .build()
         .expect("Failed to create DB pool");
 
-        let db = Self { pool };
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(maintain(pool.weak(), options.clone()));
+        }
+
+        let db = Self {
+            pool,
+            canceller: None,
+            pgbouncer: None,
+        };
 
         Ok(db)
     }
 }
 
+fn is_expired(
+    metrics: &Metrics,
+    idle_timeout: Option<Duration>,
+    max_lifetime: Option<Duration>,
+) -> bool {
+    idle_timeout.is_some_and(|idle_timeout| metrics.last_used() > idle_timeout)
+        || max_lifetime.is_some_and(|max_lifetime| metrics.age() > max_lifetime)
+}
+
+/// Periodically close expired connections, open idle connections (up to `min_idle`), and report the
+/// pool status (until the pool is dropped)
+async fn maintain(pool: WeakPool, options: PoolOptions) {
+    let mut interval = tokio::time::interval(MAINTENANCE_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let Some(pool) = pool.upgrade() else {
+            return;
+        };
+
+        let expired = maintain_once(&pool, &options).await;
+
+        let status = pool.status();
+        tracing::debug!(
+            pool.size = status.size,
+            pool.max_size = status.max_size,
+            pool.available = status.available,
+            pool.waiting = status.waiting,
+            pool.expired = expired,
+            "Database connection pool status"
+        );
+    }
+}
+
+/// Close expired connections and open idle ones. Returns the number of closed connections.
+async fn maintain_once(pool: &Pool, options: &PoolOptions) -> usize {
+    let expired = pool
+        .retain(|_, metrics| !is_expired(&metrics, options.idle_timeout, options.max_lifetime))
+        .removed
+        .len();
+
+    if let Some(min_idle) = options.min_idle {
+        open_idle_connections(pool, min_idle).await;
+    }
+
+    expired
+}
+
+/// Open connections until the pool has `min_idle` idle ones (or reaches its maximum size)
+async fn open_idle_connections(pool: &Pool, min_idle: usize) {
+    let status = pool.status();
+    let in_use = status.size - status.available;
+    let target_idle = min_idle.min(status.max_size.saturating_sub(in_use));
+
+    if status.available >= target_idle {
+        return;
+    }
+
+    // Hold on to the connections (existing idle ones first) until there are enough, so that the
+    // pool must open new ones. Don't wait for a connection that others are using.
+    let timeouts = Timeouts {
+        wait: Some(Duration::ZERO),
+        ..pool.timeouts()
+    };
+    let mut connections = Vec::with_capacity(target_idle);
+    while connections.len() < target_idle {
+        match pool.timeout_get(&timeouts).await {
+            Ok(connection) => connections.push(connection),
+            Err(e) => {
+                tracing::warn!("Failed to open an idle database connection: {e}");
+                break;
+            }
+        }
+    }
+}
+
 struct ConnectBridge(Box<dyn super::creation::Connect>);
 
 impl Connect for ConnectBridge {
@@ -153,3 +299,156 @@ impl Connect for ConnectBridge {
         self.0.connect(pg_config)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(age: Duration, idle: Option<Duration>) -> Metrics {
+        let now = Instant::now();
+        Metrics {
+            created: now.checked_sub(age).unwrap(),
+            recycled: idle.map(|idle| now.checked_sub(idle).unwrap()),
+            recycle_count: usize::from(idle.is_some()),
+        }
+    }
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn expiry() {
+        // Without limits, connections never expire
+        assert!(!is_expired(&metrics(60 * MINUTE, None), None, None));
+
+        // Lifetime counts from the creation
+        assert!(is_expired(
+            &metrics(10 * MINUTE, Some(MINUTE)),
+            None,
+            Some(5 * MINUTE)
+        ));
+        assert!(!is_expired(
+            &metrics(2 * MINUTE, Some(MINUTE)),
+            None,
+            Some(5 * MINUTE)
+        ));
+
+        // Idle time counts from the last use (or the creation, if never used)
+        assert!(is_expired(
+            &metrics(10 * MINUTE, Some(6 * MINUTE)),
+            Some(5 * MINUTE),
+            None
+        ));
+        assert!(!is_expired(
+            &metrics(10 * MINUTE, Some(MINUTE)),
+            Some(5 * MINUTE),
+            None
+        ));
+        assert!(is_expired(
+            &metrics(6 * MINUTE, None),
+            Some(5 * MINUTE),
+            None
+        ));
+    }
+}
+
+#[cfg(all(test, feature = "test-support"))]
+mod database_tests {
+    use std::str::FromStr;
+
+    use crate::testing::with_db_url;
+
+    use super::*;
+
+    async fn pool(url: &str, options: PoolOptions) -> DatabasePool {
+        // Leave opening idle connections to the test (instead of the background maintenance)
+        let options = PoolOptions {
+            min_idle: None,
+            ..options
+        };
+        DatabasePool::from_connect(
+            &options,
+            Config::from_str(url).unwrap(),
+            ConfigConnectImpl {
+                tls: tokio_postgres::NoTls,
+            },
+        )
+        .await
+        .unwrap()
+    }
+
+    async fn backend_pid(client: &DatabaseClient) -> i32 {
+        client.query("SELECT pg_backend_pid()", &[]).await.unwrap()[0].get::<_, i32>(0)
+    }
+
+    /// Maintenance opens connections up to the minimum number of idle ones (within the maximum
+    /// size of the pool)
+    #[tokio::test]
+    async fn test_keeps_min_idle_connections() {
+        with_db_url(|url| async move {
+            let options = PoolOptions {
+                max_size: Some(3),
+                min_idle: Some(2),
+                ..Default::default()
+            };
+            let db = pool(&url, options.clone()).await;
+            assert_eq!(db.pool.status().size, 0);
+
+            assert_eq!(maintain_once(&db.pool, &options).await, 0);
+            assert_eq!(db.pool.status().available, 2);
+
+            // Connections in use count toward the maximum size
+            let in_use = db.get_client().await.unwrap();
+            let in_use_too = db.get_client().await.unwrap();
+            open_idle_connections(&db.pool, 2).await;
+            let status = db.pool.status();
+            assert_eq!((status.size, status.available), (3, 1));
+            drop((in_use, in_use_too));
+
+            // Asking for more than the maximum size opens only up to it
+            open_idle_connections(&db.pool, 5).await;
+            let status = db.pool.status();
+            assert_eq!((status.size, status.available), (3, 3));
+        })
+        .await;
+    }
+
+    /// Maintenance closes the connections past their lifetime (and replaces them to keep the
+    /// minimum number of idle ones), and the pool doesn't hand out expired connections
+    #[tokio::test]
+    async fn test_replaces_expired_connections() {
+        with_db_url(|url| async move {
+            let options = PoolOptions {
+                max_size: Some(2),
+                min_idle: Some(1),
+                max_lifetime: Some(Duration::from_millis(200)),
+                ..Default::default()
+            };
+            let db = pool(&url, options.clone()).await;
+
+            assert_eq!(maintain_once(&db.pool, &options).await, 0);
+            assert_eq!(db.pool.status().available, 1);
+
+            let client = db.get_client().await.unwrap();
+            let pid = backend_pid(&client).await;
+            drop(client);
+
+            tokio::time::sleep(Duration::from_millis(300)).await;
+
+            // A borrowed connection is fresh even before maintenance runs
+            let client = db.get_client().await.unwrap();
+            let new_pid = backend_pid(&client).await;
+            assert_ne!(new_pid, pid);
+            drop(client);
+
+            tokio::time::sleep(Duration::from_millis(300)).await;
+
+            assert_eq!(maintain_once(&db.pool, &options).await, 1);
+            let status = db.pool.status();
+            assert_eq!((status.size, status.available), (1, 1));
+
+            let client = db.get_client().await.unwrap();
+            assert_ne!(backend_pid(&client).await, new_pid);
+        })
+        .await;
+    }
+}
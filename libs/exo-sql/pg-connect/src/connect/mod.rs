@@ -7,12 +7,20 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+mod cancellation;
 mod creation;
 mod database_client;
 mod database_client_manager;
 mod database_pool;
+mod pool_options;
+mod read_replicas;
 mod ssl_config;
 
+pub(crate) use cancellation::{CancelOnDrop, QueryCanceller};
 pub use creation::{Connect, TransactionMode};
+#[cfg(feature = "postgres-url")]
+pub(crate) use creation::{DatabaseCreation, PgBouncerMode};
 pub use database_client::DatabaseClient;
 pub use database_client_manager::DatabaseClientManager;
+pub use pool_options::PoolOptions;
+pub(crate) use read_replicas::ReplicaClient;
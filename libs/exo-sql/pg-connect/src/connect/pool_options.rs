@@ -0,0 +1,41 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::time::Duration;
+
+/// How to size and maintain a connection pool (applies only with the "pool" feature; without it,
+/// each client gets its own connection)
+#[derive(Debug, Clone, Default)]
+pub struct PoolOptions {
+    /// The maximum number of connections (defaults to four times the number of CPUs)
+    pub max_size: Option<usize>,
+    /// The number of idle connections to keep open (so that a burst of requests doesn't have to
+    /// wait for new connections)
+    pub min_idle: Option<usize>,
+    /// Close connections that have been idle for longer
+    pub idle_timeout: Option<Duration>,
+    /// Close connections that have been open for longer (for example, to move connections to a new
+    /// primary after a failover or to spread them across the servers behind a load balancer)
+    pub max_lifetime: Option<Duration>,
+    /// Fail getting a connection if none becomes available within this time (instead of waiting
+    /// indefinitely)
+    pub acquire_timeout: Option<Duration>,
+    /// A query to run on an existing connection before handing it out (to weed out the connections
+    /// that look open, but no longer work)
+    pub test_query: Option<String>,
+}
+
+impl PoolOptions {
+    pub fn with_max_size(max_size: Option<usize>) -> Self {
+        Self {
+            max_size,
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,109 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::{
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use super::{
+    cancellation::QueryCanceller, database_client::DatabaseClient,
+    database_client_manager::DatabaseClientManager,
+};
+
+/// How long to route reads to the primary after a replica fails
+const UNHEALTHY_PERIOD: Duration = Duration::from_secs(30);
+
+/// Read replicas to route read-only work to (in a round-robin fashion)
+pub struct ReadReplicas {
+    replicas: Vec<ReadReplica>,
+    next: AtomicUsize,
+}
+
+struct ReadReplica {
+    client_manager: DatabaseClientManager,
+    unhealthy_until: Mutex<Option<Instant>>,
+}
+
+/// A client connected to a read replica
+pub(crate) struct ReplicaClient {
+    pub(crate) client: DatabaseClient,
+    pub(crate) replica_index: usize,
+    pub(crate) canceller: Option<QueryCanceller>,
+}
+
+impl ReadReplicas {
+    pub(super) fn new(client_managers: Vec<DatabaseClientManager>) -> Self {
+        Self {
+            replicas: client_managers
+                .into_iter()
+                .map(|client_manager| ReadReplica {
+                    client_manager,
+                    unhealthy_until: Mutex::new(None),
+                })
+                .collect(),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Get a client for the next healthy replica. Returns `None` if no replica is healthy (so the
+    /// caller should use the primary).
+    pub(super) async fn get_client(&self) -> Option<ReplicaClient> {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+
+        for offset in 0..self.replicas.len() {
+            let replica_index = (start + offset) % self.replicas.len();
+            let replica = &self.replicas[replica_index];
+
+            if !replica.is_healthy() {
+                continue;
+            }
+
+            match replica.client_manager.get_client().await {
+                Ok(client) => {
+                    return Some(ReplicaClient {
+                        client,
+                        replica_index,
+                        canceller: replica.client_manager.canceller(),
+                    });
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to connect to read replica {replica_index}: {e}");
+                    self.mark_unhealthy(replica_index);
+                }
+            }
+        }
+
+        None
+    }
+
+    pub(super) fn mark_unhealthy(&self, replica_index: usize) {
+        if let Some(replica) = self.replicas.get(replica_index) {
+            *replica.unhealthy_until.lock().unwrap() = Some(Instant::now() + UNHEALTHY_PERIOD);
+        }
+    }
+}
+
+impl ReadReplica {
+    fn is_healthy(&self) -> bool {
+        let mut unhealthy_until = self.unhealthy_until.lock().unwrap();
+        match *unhealthy_until {
+            Some(until) if Instant::now() < until => false,
+            Some(_) => {
+                // Give the replica another chance
+                *unhealthy_until = None;
+                true
+            }
+            None => true,
+        }
+    }
+}
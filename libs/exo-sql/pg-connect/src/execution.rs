@@ -7,8 +7,8 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use tokio_postgres::GenericClient;
-use tracing::{error, info, instrument};
+use tokio_postgres::{GenericClient, error::SqlState};
+use tracing::{error, info, instrument, warn};
 
 use exo_sql_core::{Database, database_error::DatabaseError};
 use exo_sql_pg::{
@@ -43,6 +43,36 @@ pub async fn execute_transaction_script(
         .ok_or_else(|| DatabaseError::Transaction("".into()))
 }
 
+/// Execute a read-only script (see `TransactionScript::is_read_only`) without consuming it, so that
+/// the caller can run it again elsewhere (for example, on the primary if a read replica fails).
+pub async fn execute_read_only_script(
+    script: &TransactionScript<'_>,
+    database: &Database,
+    client: &mut impl GenericClient,
+) -> Result<TransactionStepResult, DatabaseError> {
+    let mut result = None;
+
+    for step in script.steps() {
+        result = Some(match step {
+            TransactionStep::Concrete(step)
+                if matches!(step.operation, SQLOperation::Select(_)) =>
+            {
+                run_query_ref(&step.operation, database, client).await?
+            }
+            TransactionStep::Precheck(select) => {
+                check_precheck_result(run_query_ref(select, database, client).await?)?
+            }
+            _ => {
+                return Err(DatabaseError::Transaction(
+                    "Expected a read-only transaction script".into(),
+                ));
+            }
+        });
+    }
+
+    result.ok_or_else(|| DatabaseError::Transaction("".into()))
+}
+
 /// Execute a single transaction step.
 #[instrument(
     name = "execute_transaction_step"
@@ -84,15 +114,7 @@ pub async fn execute_transaction_step(
             execute_concrete_step(step.resolve(transaction_context), database, client).await
         }
         TransactionStep::Precheck(select) => {
-            let precheck_result = run_query(SQLOperation::Select(select), database, client).await?;
-            if precheck_result.len() != 1 {
-                return Err(DatabaseError::Precheck(format!(
-                    "Expected 1 row, got {}",
-                    precheck_result.len()
-                )));
-            }
-
-            Ok(precheck_result)
+            check_precheck_result(run_query(SQLOperation::Select(select), database, client).await?)
         }
     }
 }
@@ -114,10 +136,31 @@ pub async fn execute_concrete_step(
     run_query(step.operation, database, client).await
 }
 
+fn check_precheck_result(
+    precheck_result: TransactionStepResult,
+) -> Result<TransactionStepResult, DatabaseError> {
+    if precheck_result.len() != 1 {
+        return Err(DatabaseError::Precheck(format!(
+            "Expected 1 row, got {}",
+            precheck_result.len()
+        )));
+    }
+
+    Ok(precheck_result)
+}
+
 async fn run_query(
     operation: SQLOperation<'_>,
     database: &Database,
     client: &mut impl GenericClient,
+) -> Result<TransactionStepResult, DatabaseError> {
+    run_query_ref(&operation, database, client).await
+}
+
+async fn run_query_ref(
+    operation: &impl ExpressionBuilder,
+    database: &Database,
+    client: &mut impl GenericClient,
 ) -> Result<TransactionStepResult, DatabaseError> {
     let mut sql_builder = SQLBuilder::new();
     operation.build(database, &mut sql_builder);
@@ -131,6 +174,10 @@ async fn run_query(
     info!("Executing SQL operation: {}", stmt);
 
     client.query_typed(&stmt, &params[..]).await.map_err(|e| {
+        if e.code() == Some(&SqlState::QUERY_CANCELED) {
+            warn!("Query canceled: {e}");
+            return DatabaseError::Canceled(e.to_string());
+        }
         error!("Failed to execute query: {e:?}");
         DatabaseError::driver(e).with_context("Database operation failed".into())
     })
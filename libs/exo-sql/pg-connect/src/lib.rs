@@ -7,6 +7,7 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+mod change_feed;
 mod connect;
 mod execution;
 mod pg_backend;
@@ -16,6 +17,9 @@ mod transaction_holder;
 pub mod testing;
 
 // Re-export key types
-pub use connect::{Connect, DatabaseClient, DatabaseClientManager, TransactionMode};
+#[cfg(feature = "postgres-url")]
+pub use change_feed::ChangeFeed;
+pub use change_feed::{CHANGE_FEED_CHANNEL, ChangeEvent, ChangeOperation};
+pub use connect::{Connect, DatabaseClient, DatabaseClientManager, PoolOptions, TransactionMode};
 pub use pg_backend::PgBackend;
 pub use transaction_holder::TransactionHolder;
@@ -7,6 +7,8 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+use std::time::Duration;
+
 use async_trait::async_trait;
 use exo_sql_core::{Database, DatabaseError};
 use exo_sql_model::{AbstractOperation, DatabaseBackend};
@@ -20,11 +22,29 @@ use crate::{TransactionHolder, connect::DatabaseClientManager};
 /// executes them, and converts the result rows to strings.
 pub struct PgBackend {
     database_client: DatabaseClientManager,
+    /// The default statement timeout (an operation may override it through the transaction holder)
+    statement_timeout: Option<Duration>,
 }
 
 impl PgBackend {
     pub fn new(database_client: DatabaseClientManager) -> Self {
-        Self { database_client }
+        Self {
+            database_client,
+            statement_timeout: None,
+        }
+    }
+
+    pub fn with_statement_timeout(self, statement_timeout: Option<Duration>) -> Self {
+        Self {
+            statement_timeout,
+            ..self
+        }
+    }
+
+    /// The client manager for work outside of abstract operations (such as storing persisted
+    /// queries)
+    pub fn database_client(&self) -> &DatabaseClientManager {
+        &self.database_client
     }
 }
 
@@ -43,7 +63,12 @@ impl DatabaseBackend for PgBackend {
         let transaction_script = pg.to_transaction_script(database, operation);
 
         let rows = tx_holder
-            .with_tx(database, &self.database_client, transaction_script)
+            .with_tx(
+                database,
+                &self.database_client,
+                transaction_script,
+                self.statement_timeout,
+            )
             .await?;
 
         rows.into_iter()
@@ -14,10 +14,13 @@ mod docker;
 mod existing;
 mod local;
 
+mod pgbouncer;
 mod test_support;
 
 pub use db::{
     EXO_SQL_EPHEMERAL_DATABASE_LAUNCH_PREFERENCE, EphemeralDatabase, EphemeralDatabaseLauncher,
     EphemeralDatabaseServer,
 };
+#[cfg(all(test, not(target_family = "wasm")))]
+pub(crate) use pgbouncer::PgBouncerStandIn;
 pub use test_support::{with_client, with_db_url, with_init_script};
@@ -0,0 +1,230 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+#![cfg(all(test, not(target_family = "wasm")))]
+
+use std::{
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+use tokio_postgres::config::Host;
+
+const SSL_REQUEST_CODE: i32 = 80877103;
+const CANCEL_REQUEST_CODE: i32 = 80877102;
+
+/// A stand-in for PgBouncer in transaction pooling mode. It forwards connections to the database,
+/// but (like PgBouncer) rejects the `options` startup parameter. It also records the uses of
+/// session state that break with transaction pooling: named prepared statements and session-level
+/// `SET`s.
+pub(crate) struct PgBouncerStandIn {
+    port: u16,
+    database_url: String,
+    violations: Arc<Mutex<Vec<String>>>,
+}
+
+impl PgBouncerStandIn {
+    pub(crate) async fn start(database_url: &str) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let violations = Arc::new(Mutex::new(vec![]));
+
+        let upstream_config = tokio_postgres::Config::from_str(database_url).unwrap();
+        let connection_violations = violations.clone();
+        tokio::spawn(async move {
+            while let Ok((client, _)) = listener.accept().await {
+                tokio::spawn(serve(
+                    client,
+                    upstream_config.clone(),
+                    connection_violations.clone(),
+                ));
+            }
+        });
+
+        Self {
+            port,
+            database_url: database_url.to_string(),
+            violations,
+        }
+    }
+
+    /// The URL to connect to the database through the stand-in (with `pgbouncer=true` if
+    /// `pgbouncer_mode` is set)
+    pub(crate) fn url(&self, pgbouncer_mode: bool) -> String {
+        let config = tokio_postgres::Config::from_str(&self.database_url).unwrap();
+
+        let mut url = url::Url::parse(&format!("postgres://127.0.0.1:{}", self.port)).unwrap();
+        url.set_username(config.get_user().unwrap()).unwrap();
+        if let Some(password) = config.get_password() {
+            url.set_password(Some(std::str::from_utf8(password).unwrap()))
+                .unwrap();
+        }
+        url.set_path(config.get_dbname().unwrap());
+        url.query_pairs_mut().append_pair("sslmode", "disable");
+        if pgbouncer_mode {
+            url.query_pairs_mut().append_pair("pgbouncer", "true");
+        }
+
+        url.to_string()
+    }
+
+    pub(crate) fn violations(&self) -> Vec<String> {
+        self.violations.lock().unwrap().clone()
+    }
+}
+
+async fn serve(
+    mut client: TcpStream,
+    upstream_config: tokio_postgres::Config,
+    violations: Arc<Mutex<Vec<String>>>,
+) -> std::io::Result<()> {
+    // Decline SSL (like a PgBouncer without `client_tls_sslmode`)
+    let startup = loop {
+        let startup = read_startup_message(&mut client).await?;
+        if startup_code(&startup) == SSL_REQUEST_CODE {
+            client.write_all(b"N").await?;
+        } else {
+            break startup;
+        }
+    };
+
+    if startup_code(&startup) != CANCEL_REQUEST_CODE
+        && startup_parameters(&startup).any(|name| name == "options")
+    {
+        client
+            .write_all(&error_response("unsupported startup parameter: options"))
+            .await?;
+        return Ok(());
+    }
+
+    match upstream_config.get_hosts().first().unwrap() {
+        Host::Tcp(host) => {
+            let port = upstream_config.get_ports().first().copied().unwrap_or(5432);
+            let upstream = TcpStream::connect((host.as_str(), port)).await?;
+            forward(client, upstream, startup, violations).await
+        }
+        #[cfg(unix)]
+        Host::Unix(path) => {
+            let port = upstream_config.get_ports().first().copied().unwrap_or(5432);
+            let upstream =
+                tokio::net::UnixStream::connect(path.join(format!(".s.PGSQL.{port}"))).await?;
+            forward(client, upstream, startup, violations).await
+        }
+    }
+}
+
+async fn forward(
+    client: TcpStream,
+    mut upstream: impl AsyncRead + AsyncWrite + Unpin,
+    startup: Vec<u8>,
+    violations: Arc<Mutex<Vec<String>>>,
+) -> std::io::Result<()> {
+    upstream.write_all(&startup).await?;
+
+    let (mut client_read, mut client_write) = client.into_split();
+    let (mut upstream_read, mut upstream_write) = tokio::io::split(upstream);
+
+    let to_upstream = forward_client_messages(&mut client_read, &mut upstream_write, &violations);
+    let to_client = tokio::io::copy(&mut upstream_read, &mut client_write);
+
+    tokio::select! {
+        result = to_upstream => result,
+        result = to_client => result.map(|_| ()),
+    }
+}
+
+async fn forward_client_messages(
+    client: &mut (impl AsyncRead + Unpin),
+    upstream: &mut (impl AsyncWrite + Unpin),
+    violations: &Mutex<Vec<String>>,
+) -> std::io::Result<()> {
+    loop {
+        let mut header = [0u8; 5];
+        client.read_exact(&mut header).await?;
+        let length = i32::from_be_bytes(header[1..5].try_into().unwrap()) as usize;
+        let mut body = vec![0u8; length - 4];
+        client.read_exact(&mut body).await?;
+
+        if let Some(violation) = session_state_use(header[0], &body) {
+            violations.lock().unwrap().push(violation);
+        }
+
+        upstream.write_all(&header).await?;
+        upstream.write_all(&body).await?;
+    }
+}
+
+/// Describe how the message relies on session state (if it does)
+fn session_state_use(message_type: u8, body: &[u8]) -> Option<String> {
+    let text = |bytes: &[u8]| {
+        let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+        String::from_utf8_lossy(&bytes[..end]).to_string()
+    };
+
+    match message_type {
+        // Parse: the statement name followed by the query
+        b'P' => {
+            let name = text(body);
+            (!name.is_empty()).then(|| format!("Named prepared statement '{name}'"))
+        }
+        // Simple query
+        b'Q' => {
+            let query = text(body);
+            query
+                .split(';')
+                .map(|statement| statement.trim().to_uppercase())
+                .find(|statement| {
+                    statement.starts_with("SET ") && !statement.starts_with("SET LOCAL ")
+                })
+                .map(|statement| format!("Session-level setting '{statement}'"))
+        }
+        _ => None,
+    }
+}
+
+async fn read_startup_message(stream: &mut TcpStream) -> std::io::Result<Vec<u8>> {
+    let mut length = [0u8; 4];
+    stream.read_exact(&mut length).await?;
+    let mut message = length.to_vec();
+    message.resize(i32::from_be_bytes(length) as usize, 0);
+    stream.read_exact(&mut message[4..]).await?;
+    Ok(message)
+}
+
+fn startup_code(message: &[u8]) -> i32 {
+    i32::from_be_bytes(message[4..8].try_into().unwrap())
+}
+
+/// The names of the parameters in a startup message (pairs of null-terminated names and values)
+fn startup_parameters(message: &[u8]) -> impl Iterator<Item = String> + '_ {
+    message[8..]
+        .split(|b| *b == 0)
+        .step_by(2)
+        .filter(|name| !name.is_empty())
+        .map(|name| String::from_utf8_lossy(name).to_string())
+}
+
+fn error_response(message: &str) -> Vec<u8> {
+    let mut fields = vec![];
+    for (code, value) in [(b'S', "FATAL"), (b'C', "08P01"), (b'M', message)] {
+        fields.push(code);
+        fields.extend_from_slice(value.as_bytes());
+        fields.push(0);
+    }
+    fields.push(0);
+
+    let mut response = vec![b'E'];
+    response.extend_from_slice(&(fields.len() as i32 + 4).to_be_bytes());
+    response.extend_from_slice(&fields);
+    response
+}
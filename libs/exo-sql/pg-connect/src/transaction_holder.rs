@@ -10,16 +10,20 @@
 use std::{
     ops::DerefMut,
     sync::{LazyLock, atomic::AtomicBool},
+    time::Duration,
 };
 
 use tokio::sync::Mutex;
 
-use exo_sql_core::{Database, database_error::DatabaseError};
+use exo_sql_core::{
+    Database,
+    database_error::{DatabaseDriverError, DatabaseError},
+};
 use exo_sql_pg::transaction::{TransactionScript, TransactionStepResult};
 
 use crate::{
-    connect::{DatabaseClient, DatabaseClientManager},
-    execution::execute_transaction_script,
+    connect::{CancelOnDrop, DatabaseClient, DatabaseClientManager, ReplicaClient},
+    execution::{execute_read_only_script, execute_transaction_script},
 };
 
 /// Manages the state of a database transaction across multiple operations within a request.
@@ -45,14 +49,63 @@ use crate::{
 /// The transaction is deferred until the first database operation that requires one. This avoids
 /// unnecessary `BEGIN`/`COMMIT` round-trips for requests that don't touch the database or that
 /// only perform a single auto-committed query.
+///
+/// # Read replicas
+///
+/// If the client manager has read replicas, read-only work runs on a replica as long as the
+/// request hasn't needed a transaction (for example, due to an interceptor or multiple operations)
+/// and hasn't written anything (so that the request sees its own writes, which may not have reached
+/// the replica yet). Everything else runs on the primary.
+///
+/// # Statement timeout and cancellation
+///
+/// Work with a statement timeout outside a transaction sets the timeout for the session, which
+/// takes a single round-trip (and leaves reads on a replica). Finalizing resets the timeout on a
+/// pooled connection if the next user of the connection may not set one (that is, if there is no
+/// default timeout). Within a transaction (or through PgBouncer, where the next transaction may run
+/// on another connection), the work uses `SET LOCAL statement_timeout` instead. If the future
+/// running the work is dropped (for example, because the HTTP client disconnected), the running
+/// query is canceled.
+///
+/// # Dropped requests
+///
+/// If the holder is dropped with an open transaction, in the middle of some work (whose query may
+/// still be running or about to be canceled), or with a session timeout that needs a reset, its
+/// connections are closed instead of going back to a pool.
+///
+/// # Session variables
+///
+/// Work that writes with session variables (see `set_session_variables`) runs in a transaction
+/// with `SET LOCAL` for each variable, so that column defaults and triggers can read them through
+/// `current_setting` without the values leaking to the next user of the connection. Since only
+/// writes run defaults and triggers, reads don't set the variables (and so don't need a
+/// transaction or leave the replicas).
 pub struct TransactionHolder {
     state: LazyLock<Mutex<TransactionState>>,
     needs_transaction: AtomicBool,
+    statement_timeout_override: Option<Duration>,
+    session_variables: Vec<(String, String)>,
 }
 
 struct TransactionState {
     client: Option<DatabaseClient>,
+    read_client: Option<ReplicaClient>,
     status: TransactionStatus,
+    has_written: bool,
+    /// The statement timeout set in the current transaction
+    statement_timeout: Option<Duration>,
+    /// The session variables set in the current transaction
+    session_variables: Vec<(String, String)>,
+    /// The statement timeout when the work doesn't override it (see `release`)
+    default_statement_timeout: Option<Duration>,
+    /// The statement timeout set for the session on `client`
+    session_statement_timeout: Option<Duration>,
+    /// The statement timeout set for the session on `read_client`
+    read_session_statement_timeout: Option<Duration>,
+    /// Work is running on `client` (if still set on drop, the future running the work was dropped)
+    busy: bool,
+    /// Work is running on `read_client`
+    read_busy: bool,
 }
 
 #[derive(Debug, PartialEq)]
@@ -63,6 +116,8 @@ enum TransactionStatus {
     Active,
     /// COMMIT or ROLLBACK has been sent
     Finalized,
+    /// The caller began the transaction (see `TransactionHolder::with_transaction`) and ends it
+    Adopted,
 }
 
 impl Default for TransactionHolder {
@@ -70,6 +125,8 @@ impl Default for TransactionHolder {
         Self {
             state: LazyLock::new(|| Mutex::new(TransactionState::new())),
             needs_transaction: AtomicBool::new(false),
+            statement_timeout_override: None,
+            session_variables: vec![],
         }
     }
 }
@@ -79,6 +136,25 @@ impl TransactionHolder {
         Self::default()
     }
 
+    /// Run all work on a client on which the caller has already begun a transaction (for example,
+    /// to run GraphQL operations in the middle of a schema migration). Finalizing the holder leaves
+    /// the transaction open; the caller takes the client back with `take_client` and ends the
+    /// transaction itself.
+    pub async fn with_transaction(client: DatabaseClient) -> Self {
+        let holder = Self::default();
+        {
+            let mut state = holder.state.lock().await;
+            state.client = Some(client);
+            state.status = TransactionStatus::Adopted;
+        }
+        holder
+    }
+
+    /// Take the client out of the holder (leaving any transaction on it open)
+    pub async fn take_client(&mut self) -> Option<DatabaseClient> {
+        self.state.lock().await.client.take()
+    }
+
     /// Set the flag to indicate that a transaction must be used when executing work.
     ///
     /// Typically, a caller higher-up in the stack calls this method when it determines that a transaction
@@ -89,14 +165,46 @@ impl TransactionHolder {
             .store(true, std::sync::atomic::Ordering::SeqCst);
     }
 
+    /// Whether later work may see changes that aren't committed (or not visible to other requests)
+    /// yet: some work has written, a transaction was asked for (see `ensure_transaction`), or the
+    /// work runs in the caller's transaction
+    pub async fn may_see_uncommitted_changes(&self) -> bool {
+        if self
+            .needs_transaction
+            .load(std::sync::atomic::Ordering::SeqCst)
+        {
+            return true;
+        }
+
+        let state = self.state.lock().await;
+        state.has_written || state.status != TransactionStatus::Idle
+    }
+
+    /// Use the timeout (instead of the one passed to `with_tx`) for the next work (for example, for
+    /// an operation that specifies its own timeout)
+    pub fn override_statement_timeout(&mut self, statement_timeout: Duration) {
+        self.statement_timeout_override = Some(statement_timeout);
+    }
+
+    /// Set the session variables (pairs of a name such as `exo.auth_context_id` and its value) for
+    /// the rest of the work
+    pub fn set_session_variables(&mut self, session_variables: Vec<(String, String)>) {
+        self.session_variables = session_variables;
+    }
+
     /// Execute work within a transaction context
     pub async fn with_tx(
         &mut self,
         database: &Database,
         client_manager: &DatabaseClientManager,
         work: TransactionScript<'_>,
+        statement_timeout: Option<Duration>,
     ) -> Result<TransactionStepResult, DatabaseError> {
+        let default_statement_timeout = statement_timeout;
+        let statement_timeout = self.statement_timeout_override.take().or(statement_timeout);
+
         let mut state = self.state.lock().await;
+        state.default_statement_timeout = default_statement_timeout;
 
         if state.status == TransactionStatus::Finalized {
             return Err(DatabaseError::Transaction(
@@ -104,14 +212,54 @@ impl TransactionHolder {
             ));
         }
 
+        let needs_tx = self
+            .needs_transaction
+            .load(std::sync::atomic::Ordering::SeqCst);
+
+        // Only writes run column defaults and triggers, which the session variables are for
+        let session_variables: &[(String, String)] = if work.is_read_only() {
+            &[]
+        } else {
+            &self.session_variables
+        };
+
+        if work.is_read_only() {
+            if !needs_tx
+                && state.can_use_read_replica()
+                && state.ensure_read_client(client_manager).await
+            {
+                match state
+                    .execute_read_work(database, client_manager, &work, statement_timeout)
+                    .await
+                {
+                    // The replica went away (and is now marked unhealthy), so retry once on the
+                    // primary instead of failing the request
+                    Err(e) if state.read_client.is_none() => {
+                        tracing::warn!(
+                            "Retrying a read on the primary after a replica failure: {e}"
+                        );
+                    }
+                    result => return result,
+                }
+            }
+        } else {
+            state.has_written = true;
+        }
+
         // Ensure we have a client
         state.ensure_client(client_manager).await?;
 
         // Execute the work
-        let needs_tx = self
-            .needs_transaction
-            .load(std::sync::atomic::Ordering::SeqCst);
-        state.execute_work(database, work, needs_tx).await
+        state
+            .execute_work(
+                database,
+                client_manager,
+                work,
+                needs_tx,
+                statement_timeout,
+                session_variables,
+            )
+            .await
     }
 
     /// Finalize the transaction (commit or rollback based on parameter)
@@ -129,10 +277,106 @@ impl TransactionState {
     fn new() -> Self {
         Self {
             client: None,
+            read_client: None,
             status: TransactionStatus::Idle,
+            has_written: false,
+            statement_timeout: None,
+            session_variables: vec![],
+            default_statement_timeout: None,
+            session_statement_timeout: None,
+            read_session_statement_timeout: None,
+            busy: false,
+            read_busy: false,
         }
     }
 
+    fn can_use_read_replica(&self) -> bool {
+        self.status == TransactionStatus::Idle && !self.has_written
+    }
+
+    /// Ensure we have a client for a read replica (returns false if there is no healthy replica)
+    async fn ensure_read_client(&mut self, client_manager: &DatabaseClientManager) -> bool {
+        if let Some(read_client) = &self.read_client
+            && read_client.client.is_closed()
+        {
+            client_manager.mark_read_replica_unhealthy(read_client.replica_index);
+            self.read_client = None;
+        }
+
+        if self.read_client.is_none() {
+            self.read_client = client_manager.get_read_client().await;
+            self.read_session_statement_timeout = None;
+        }
+
+        self.read_client.is_some()
+    }
+
+    async fn execute_read_work(
+        &mut self,
+        database: &Database,
+        client_manager: &DatabaseClientManager,
+        work: &TransactionScript<'_>,
+        statement_timeout: Option<Duration>,
+    ) -> Result<TransactionStepResult, DatabaseError> {
+        let Some(read_client) = self.read_client.as_mut() else {
+            return Err(DatabaseError::Transaction(
+                "No read replica client available".to_string(),
+            ));
+        };
+
+        self.read_busy = true;
+
+        // Reads on a replica run without a transaction, except to scope the statement timeout
+        // through PgBouncer
+        let session_timeout =
+            statement_timeout.filter(|_| read_client.client.supports_session_settings());
+        let result = match session_timeout {
+            Some(statement_timeout) => {
+                match set_session_statement_timeout(
+                    &read_client.client,
+                    &mut self.read_session_statement_timeout,
+                    statement_timeout,
+                )
+                .await
+                {
+                    Ok(()) => execute_with_cancellation(read_client, database, work).await,
+                    Err(e) => Err(e),
+                }
+            }
+            None => match local_settings_sql(statement_timeout, &[]) {
+                Some(settings) => {
+                    let begin = format!("BEGIN; {settings}");
+                    match read_client.client.batch_execute(&begin).await {
+                        Ok(()) => {
+                            let result =
+                                execute_with_cancellation(read_client, database, work).await;
+                            let end = if result.is_ok() { "COMMIT" } else { "ROLLBACK" };
+                            match read_client.client.batch_execute(end).await {
+                                Ok(()) => result,
+                                Err(e) => result.and(Err(DatabaseError::driver(e))),
+                            }
+                        }
+                        Err(e) => Err(DatabaseError::driver(e)),
+                    }
+                }
+                None => execute_with_cancellation(read_client, database, work).await,
+            },
+        };
+
+        self.read_busy = false;
+
+        // If the replica went away, route this and the subsequent work to the primary
+        if let Err(e) = &result
+            && (read_client.client.is_closed() || is_connection_error(e))
+        {
+            client_manager.mark_read_replica_unhealthy(read_client.replica_index);
+            self.read_client = None;
+            self.read_session_statement_timeout = None;
+        }
+
+        result
+    }
+
     async fn ensure_client(
         &mut self,
         client_manager: &DatabaseClientManager,
@@ -148,11 +392,11 @@ impl TransactionState {
             TransactionStatus::Finalized => Err(DatabaseError::Transaction(
                 "Transaction already finalized".to_string(),
             )),
-            TransactionStatus::Active => Ok(()),
+            TransactionStatus::Active | TransactionStatus::Adopted => Ok(()),
             TransactionStatus::Idle => match self.client {
                 Some(ref client) => {
                     client
-                        .batch_execute("BEGIN")
+                        .batch_execute(client.begin_statement())
                         .await
                         .map_err(DatabaseError::driver)?;
                     self.status = TransactionStatus::Active;
@@ -165,17 +409,124 @@ impl TransactionState {
         }
     }
 
+    /// Apply the statement timeout and the session variables to the rest of the transaction, unless
+    /// already applied (`SET LOCAL` applies only within a transaction, so begin one if needed).
+    /// Outside a transaction, a statement timeout without session variables is set for the session
+    /// instead (see `release`).
+    async fn apply_local_settings(
+        &mut self,
+        statement_timeout: Option<Duration>,
+        session_variables: &[(String, String)],
+    ) -> Result<(), DatabaseError> {
+        if self.status == TransactionStatus::Idle
+            && session_variables.is_empty()
+            && let Some(statement_timeout) = statement_timeout
+            && let Some(ref client) = self.client
+            && client.supports_session_settings()
+        {
+            return set_session_statement_timeout(
+                client,
+                &mut self.session_statement_timeout,
+                statement_timeout,
+            )
+            .await;
+        }
+
+        let statement_timeout = statement_timeout.filter(|t| {
+            self.statement_timeout != Some(*t) && self.session_statement_timeout != Some(*t)
+        });
+        let session_variables: &[(String, String)] = if self.session_variables == session_variables
+        {
+            &[]
+        } else {
+            session_variables
+        };
+
+        let Some(settings) = local_settings_sql(statement_timeout, session_variables) else {
+            return Ok(());
+        };
+
+        match self.client {
+            Some(ref client) => {
+                let sql = match self.status {
+                    TransactionStatus::Idle => {
+                        format!("{}; {settings}", client.begin_statement())
+                    }
+                    TransactionStatus::Active => settings,
+                    // Leave the caller's transaction alone
+                    TransactionStatus::Adopted | TransactionStatus::Finalized => return Ok(()),
+                };
+
+                client
+                    .batch_execute(&sql)
+                    .await
+                    .map_err(DatabaseError::driver)?;
+                self.status = TransactionStatus::Active;
+                if statement_timeout.is_some() {
+                    self.statement_timeout = statement_timeout;
+                }
+                if !session_variables.is_empty() {
+                    self.session_variables = session_variables.to_vec();
+                }
+                Ok(())
+            }
+            None => Err(DatabaseError::Transaction(
+                "No database client available".to_string(),
+            )),
+        }
+    }
+
     async fn execute_work(
         &mut self,
         database: &Database,
+        client_manager: &DatabaseClientManager,
         work: TransactionScript<'_>,
         needs_tx: bool,
+        statement_timeout: Option<Duration>,
+        session_variables: &[(String, String)],
     ) -> Result<TransactionStepResult, DatabaseError> {
-        if work.needs_transaction() || needs_tx {
+        self.busy = true;
+        let result = self
+            .run_work(
+                database,
+                client_manager,
+                work,
+                needs_tx,
+                statement_timeout,
+                session_variables,
+            )
+            .await;
+        self.busy = false;
+        result
+    }
+
+    async fn run_work(
+        &mut self,
+        database: &Database,
+        client_manager: &DatabaseClientManager,
+        work: TransactionScript<'_>,
+        needs_tx: bool,
+        statement_timeout: Option<Duration>,
+        session_variables: &[(String, String)],
+    ) -> Result<TransactionStepResult, DatabaseError> {
+        // Without a session-level read-only setting, a write must run in a read-only transaction
+        // to be rejected
+        let needs_read_only_tx = !work.is_read_only()
+            && self
+                .client
+                .as_ref()
+                .is_some_and(|client| client.requires_read_only_transactions());
+
+        if work.needs_transaction() || needs_tx || needs_read_only_tx {
             self.begin_transaction().await?;
         }
+        self.apply_local_settings(statement_timeout, session_variables)
+            .await?;
         if let Some(ref mut client) = self.client {
-            execute_transaction_script(work, database, client.deref_mut()).await
+            let cancel_on_drop = CancelOnDrop::new(client, client_manager.canceller());
+            let result = execute_transaction_script(work, database, client.deref_mut()).await;
+            cancel_on_drop.disarm();
+            result
         } else {
             Err(DatabaseError::Transaction(
                 "No database client available".to_string(),
@@ -184,33 +535,157 @@ impl TransactionState {
     }
 
     async fn commit(&mut self) -> Result<(), tokio_postgres::Error> {
+        if self.status == TransactionStatus::Adopted {
+            return Ok(());
+        }
         if self.status == TransactionStatus::Active
             && let Some(ref client) = self.client
         {
             client.batch_execute("COMMIT").await?;
         }
         self.status = TransactionStatus::Finalized;
-        Ok(())
+        self.release().await
     }
 
     async fn rollback(&mut self) -> Result<(), tokio_postgres::Error> {
+        if self.status == TransactionStatus::Adopted {
+            return Ok(());
+        }
         if self.status == TransactionStatus::Active
             && let Some(ref client) = self.client
         {
             client.batch_execute("ROLLBACK").await?;
         }
         self.status = TransactionStatus::Finalized;
+        self.release().await
+    }
+
+    /// Reset the statement timeouts set for the session on pooled connections, unless every user of
+    /// the connections sets its own (default) timeout
+    async fn release(&mut self) -> Result<(), tokio_postgres::Error> {
+        if self.needs_session_reset() {
+            if let Some(ref client) = self.client {
+                client.batch_execute("RESET statement_timeout").await?;
+            }
+            self.session_statement_timeout = None;
+        }
+        if self.read_needs_session_reset() {
+            if let Some(ref read_client) = self.read_client {
+                read_client
+                    .client
+                    .batch_execute("RESET statement_timeout")
+                    .await?;
+            }
+            self.read_session_statement_timeout = None;
+        }
         Ok(())
     }
+
+    fn needs_session_reset(&self) -> bool {
+        self.default_statement_timeout.is_none()
+            && self.session_statement_timeout.is_some()
+            && self
+                .client
+                .as_ref()
+                .is_some_and(|client| client.is_pooled())
+    }
+
+    fn read_needs_session_reset(&self) -> bool {
+        self.default_statement_timeout.is_none()
+            && self.read_session_statement_timeout.is_some()
+            && self
+                .read_client
+                .as_ref()
+                .is_some_and(|read_client| read_client.client.is_pooled())
+    }
+}
+
+async fn execute_with_cancellation(
+    read_client: &mut ReplicaClient,
+    database: &Database,
+    work: &TransactionScript<'_>,
+) -> Result<TransactionStepResult, DatabaseError> {
+    let cancel_on_drop = CancelOnDrop::new(&read_client.client, read_client.canceller.clone());
+    let result = execute_read_only_script(work, database, read_client.client.deref_mut()).await;
+    cancel_on_drop.disarm();
+    result
+}
+
+/// Set the statement timeout for the session, unless already set (to the same value)
+async fn set_session_statement_timeout(
+    client: &DatabaseClient,
+    session_statement_timeout: &mut Option<Duration>,
+    statement_timeout: Duration,
+) -> Result<(), DatabaseError> {
+    if *session_statement_timeout != Some(statement_timeout) {
+        client
+            .batch_execute(&format!(
+                "SET statement_timeout = {}",
+                statement_timeout.as_millis()
+            ))
+            .await
+            .map_err(DatabaseError::driver)?;
+        *session_statement_timeout = Some(statement_timeout);
+    }
+    Ok(())
+}
+
+/// Did the error come from the connection (and not from the query)? Such errors have no SQLSTATE
+/// code: the connection was closed or an I/O operation on it failed.
+fn is_connection_error(error: &DatabaseError) -> bool {
+    match error {
+        DatabaseError::WithContext(_, error) => is_connection_error(error),
+        DatabaseError::Driver(DatabaseDriverError(error)) => error
+            .downcast_ref::<tokio_postgres::Error>()
+            .is_some_and(|error| {
+                error.is_closed()
+                    || std::error::Error::source(error)
+                        .is_some_and(|source| source.is::<std::io::Error>())
+            }),
+        _ => false,
+    }
+}
+
+/// The `SET LOCAL` statements for the settings (`None` if there is nothing to set)
+fn local_settings_sql(
+    statement_timeout: Option<Duration>,
+    session_variables: &[(String, String)],
+) -> Option<String> {
+    let statements: Vec<_> = statement_timeout
+        .map(|statement_timeout| {
+            format!(
+                "SET LOCAL statement_timeout = {}",
+                statement_timeout.as_millis()
+            )
+        })
+        .into_iter()
+        .chain(session_variables.iter().map(|(name, value)| {
+            // `SET` doesn't take bind parameters, so quote the value as a string literal
+            format!("SET LOCAL {name} = '{}'", value.replace('\'', "''"))
+        }))
+        .collect();
+
+    (!statements.is_empty()).then(|| statements.join("; "))
 }
 
 impl Drop for TransactionState {
     fn drop(&mut self) {
-        if self.status == TransactionStatus::Active {
-            tracing::warn!(
-                "TransactionState dropped with an open transaction that was not finalized. \
-                 The server will implicitly roll back the transaction when the connection closes."
-            );
+        if self.status == TransactionStatus::Active || self.busy || self.needs_session_reset() {
+            if self.status == TransactionStatus::Active {
+                tracing::warn!(
+                    "TransactionState dropped with an open transaction that was not finalized. \
+                     Closing the connection, so the server rolls back the transaction."
+                );
+            }
+            if let Some(client) = self.client.take() {
+                client.discard();
+            }
+        }
+
+        if (self.read_busy || self.read_needs_session_reset())
+            && let Some(read_client) = self.read_client.take()
+        {
+            read_client.client.discard();
         }
     }
 }
@@ -236,6 +711,19 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_may_see_uncommitted_changes() {
+        assert!(!TransactionHolder::new().may_see_uncommitted_changes().await);
+
+        let holder = TransactionHolder::new();
+        holder.ensure_transaction();
+        assert!(holder.may_see_uncommitted_changes().await);
+
+        let holder = TransactionHolder::new();
+        holder.state.lock().await.has_written = true;
+        assert!(holder.may_see_uncommitted_changes().await);
+    }
+
     #[tokio::test]
     async fn test_finalized_state_consistency() {
         let mut holder = TransactionHolder::new();
@@ -259,9 +747,21 @@ mod tests {
 /// error recovery, etc.)
 #[cfg(all(test, feature = "test-support"))]
 mod database_tests {
+    use tokio_postgres::error::SqlState;
+
+    use exo_sql_core::{
+        SchemaObjectName,
+        operation::{Column, Predicate, Select, Table},
+    };
+    use exo_sql_pg::{
+        SQLOperation,
+        test_database_builder::{DatabaseBuilder, pk, string},
+        transaction::{ConcreteTransactionStep, TransactionStep},
+    };
+
     use crate::TransactionMode;
     use crate::connect::DatabaseClientManager;
-    use crate::testing::with_db_url;
+    use crate::testing::{PgBouncerStandIn, with_db_url};
 
     use super::*;
 
@@ -293,6 +793,44 @@ mod database_tests {
         mgr
     }
 
+    /// Through PgBouncer, read-only mode is enforced per transaction (instead of per session)
+    #[tokio::test]
+    async fn test_pgbouncer_read_only_transactions() {
+        with_db_url(|url| async move {
+            setup_schema(&url).await;
+
+            let stand_in = PgBouncerStandIn::start(&url).await;
+            let mgr = DatabaseClientManager::from_url_direct(
+                &stand_in.url(true),
+                false,
+                TransactionMode::ReadOnly,
+            )
+            .await
+            .unwrap();
+
+            let mut holder = TransactionHolder::new();
+            holder.ensure_transaction();
+
+            {
+                let mut state = holder.state.lock().await;
+                state.ensure_client(&mgr).await.unwrap();
+                state.begin_transaction().await.unwrap();
+                let client = state.client.as_ref().unwrap();
+                let error = client
+                    .execute("INSERT INTO test_items (name) VALUES ($1)", &[&"rejected"])
+                    .await
+                    .unwrap_err();
+                assert_eq!(error.code(), Some(&SqlState::READ_ONLY_SQL_TRANSACTION));
+            }
+
+            holder.finalize(false).await.unwrap();
+
+            assert_eq!(count_items(&url).await, 0);
+            assert_eq!(stand_in.violations(), Vec::<String>::new());
+        })
+        .await;
+    }
+
     #[tokio::test]
     async fn test_commit_persists_data() {
         with_db_url(|url| async move {
@@ -375,6 +913,36 @@ mod database_tests {
         .await;
     }
 
+    /// Finalizing a holder that adopted the caller's transaction leaves the transaction open.
+    #[tokio::test]
+    async fn test_adopted_transaction_is_ended_by_caller() {
+        with_db_url(|url| async move {
+            let mgr = setup_schema(&url).await;
+
+            let client = mgr.get_client().await.unwrap();
+            client.batch_execute("BEGIN").await.unwrap();
+
+            let mut holder = TransactionHolder::with_transaction(client).await;
+
+            {
+                let state = holder.state.lock().await;
+                let client = state.client.as_ref().unwrap();
+                client
+                    .execute("INSERT INTO test_items (name) VALUES ($1)", &[&"adopted"])
+                    .await
+                    .unwrap();
+            }
+
+            holder.finalize(true).await.unwrap();
+
+            let client = holder.take_client().await.unwrap();
+            client.batch_execute("ROLLBACK").await.unwrap();
+
+            assert_eq!(count_items(&url).await, 0);
+        })
+        .await;
+    }
+
     /// Calling finalize twice is safe (second call is a no-op).
     #[tokio::test]
     async fn test_double_finalize_is_safe() {
@@ -476,6 +1044,48 @@ mod database_tests {
         .await;
     }
 
+    async fn current_name(client: &DatabaseClient) -> Option<String> {
+        client
+            .query("SELECT current_setting('exo.auth_context_name', true)", &[])
+            .await
+            .unwrap()[0]
+            .get::<_, Option<String>>(0)
+    }
+
+    /// Session variables are visible within the transaction (and not after it)
+    #[tokio::test]
+    async fn test_session_variables() {
+        with_db_url(|url| async move {
+            let mgr = client_manager(&url).await;
+
+            let mut holder = TransactionHolder::new();
+            holder.set_session_variables(vec![(
+                "exo.auth_context_name".to_string(),
+                "O'Brien".to_string(),
+            )]);
+
+            {
+                let mut state = holder.state.lock().await;
+                state.ensure_client(&mgr).await.unwrap();
+                state
+                    .apply_local_settings(None, &holder.session_variables)
+                    .await
+                    .unwrap();
+                assert_eq!(state.status, TransactionStatus::Active);
+
+                let client = state.client.as_ref().unwrap();
+                assert_eq!(current_name(client).await.as_deref(), Some("O'Brien"));
+            }
+
+            holder.finalize(true).await.unwrap();
+
+            let state = holder.state.lock().await;
+            let client = state.client.as_ref().unwrap();
+            assert_ne!(current_name(client).await.as_deref(), Some("O'Brien"));
+        })
+        .await;
+    }
+
     /// with_tx after finalize returns an error.
     #[tokio::test]
     async fn test_with_tx_after_finalize_errors() {
@@ -494,4 +1104,395 @@ mod database_tests {
         })
         .await;
     }
+
+    fn items_database() -> Database {
+        DatabaseBuilder::new()
+            .table("test_items", vec![pk("id"), string("name")])
+            .build()
+    }
+
+    /// A read-only script equivalent to `SELECT "name" FROM "test_items"`
+    fn select_items(database: &Database) -> TransactionScript<'static> {
+        let table_id = database
+            .get_table_id(&SchemaObjectName::new("test_items", None))
+            .unwrap();
+        let name_col_id = database.get_column_id(table_id, "name").unwrap();
+
+        let mut script = TransactionScript::default();
+        script.add_step(TransactionStep::Concrete(Box::new(
+            ConcreteTransactionStep::new(SQLOperation::Select(Select {
+                table: Table::physical(table_id, None),
+                columns: vec![Column::physical(name_col_id, None)],
+                predicate: Predicate::True,
+                order_by: None,
+                offset: None,
+                limit: None,
+                group_by: None,
+                top_level_selection: false,
+            })),
+        )));
+        script
+    }
+
+    async fn insert_item(url: &str, name: &str) {
+        let mgr = client_manager(url).await;
+        let client = mgr.get_client().await.unwrap();
+        client
+            .execute("INSERT INTO test_items (name) VALUES ($1)", &[&name])
+            .await
+            .unwrap();
+    }
+
+    /// A client manager whose replica is the same database (so that reads see the same data)
+    async fn replicated_client_manager(url: &str) -> DatabaseClientManager {
+        client_manager(url)
+            .await
+            .with_read_replicas(vec![client_manager(url).await])
+    }
+
+    /// Read-only work runs on a replica (without connecting to the primary)
+    #[tokio::test]
+    async fn test_reads_go_to_replica() {
+        with_db_url(|url| async move {
+            setup_schema(&url).await;
+            insert_item(&url, "replicated").await;
+            let mgr = replicated_client_manager(&url).await;
+            let database = items_database();
+
+            let mut holder = TransactionHolder::new();
+            let rows = holder
+                .with_tx(&database, &mgr, select_items(&database), None)
+                .await
+                .unwrap();
+            assert_eq!(rows.len(), 1);
+
+            let state = holder.state.lock().await;
+            assert!(state.read_client.is_some());
+            assert!(state.client.is_none());
+        })
+        .await;
+    }
+
+    /// Once a request has written (or needs a transaction), its reads go to the primary, so that
+    /// it sees its own writes
+    #[tokio::test]
+    async fn test_reads_after_write_go_to_primary() {
+        with_db_url(|url| async move {
+            setup_schema(&url).await;
+            let mgr = replicated_client_manager(&url).await;
+            let database = items_database();
+
+            let mut holder = TransactionHolder::new();
+            holder.state.lock().await.has_written = true;
+            holder
+                .with_tx(&database, &mgr, select_items(&database), None)
+                .await
+                .unwrap();
+            {
+                let state = holder.state.lock().await;
+                assert!(state.read_client.is_none());
+                assert!(state.client.is_some());
+            }
+
+            let mut holder = TransactionHolder::new();
+            holder.ensure_transaction();
+            holder
+                .with_tx(&database, &mgr, select_items(&database), None)
+                .await
+                .unwrap();
+            {
+                let state = holder.state.lock().await;
+                assert!(state.read_client.is_none());
+                assert_eq!(state.status, TransactionStatus::Active);
+            }
+            holder.finalize(true).await.unwrap();
+        })
+        .await;
+    }
+
+    /// If the replica goes away in the middle of a request, the read is retried on the primary and
+    /// the replica is taken out of rotation
+    #[tokio::test]
+    async fn test_reads_fall_back_to_primary_when_replica_fails() {
+        with_db_url(|url| async move {
+            setup_schema(&url).await;
+            insert_item(&url, "fallback").await;
+            let mgr = replicated_client_manager(&url).await;
+            let database = items_database();
+
+            let mut holder = TransactionHolder::new();
+            holder
+                .with_tx(&database, &mgr, select_items(&database), None)
+                .await
+                .unwrap();
+
+            let replica_pid = {
+                let state = holder.state.lock().await;
+                let read_client = state.read_client.as_ref().unwrap();
+                read_client
+                    .client
+                    .query("SELECT pg_backend_pid()", &[])
+                    .await
+                    .unwrap()[0]
+                    .get::<_, i32>(0)
+            };
+            let admin = client_manager(&url).await;
+            admin
+                .get_client()
+                .await
+                .unwrap()
+                .execute("SELECT pg_terminate_backend($1)", &[&replica_pid])
+                .await
+                .unwrap();
+
+            let rows = holder
+                .with_tx(&database, &mgr, select_items(&database), None)
+                .await
+                .unwrap();
+            assert_eq!(rows.len(), 1);
+
+            {
+                let state = holder.state.lock().await;
+                assert!(state.read_client.is_none());
+                assert!(state.client.is_some());
+            }
+            assert!(mgr.get_read_client().await.is_none());
+        })
+        .await;
+    }
+
+    /// An unreachable replica doesn't fail reads
+    #[tokio::test]
+    async fn test_reads_use_primary_when_replica_is_unreachable() {
+        with_db_url(|url| async move {
+            setup_schema(&url).await;
+            insert_item(&url, "unreachable").await;
+
+            // Nothing listens on the port once the listener is dropped
+            let port = {
+                let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+                listener.local_addr().unwrap().port()
+            };
+            let replica = client_manager(&format!(
+                "postgres://exo@127.0.0.1:{port}/exo?sslmode=disable"
+            ))
+            .await;
+            let mgr = client_manager(&url).await.with_read_replicas(vec![replica]);
+            let database = items_database();
+
+            let mut holder = TransactionHolder::new();
+            let rows = holder
+                .with_tx(&database, &mgr, select_items(&database), None)
+                .await
+                .unwrap();
+            assert_eq!(rows.len(), 1);
+
+            let state = holder.state.lock().await;
+            assert!(state.read_client.is_none());
+            assert!(state.client.is_some());
+        })
+        .await;
+    }
+
+    async fn current_statement_timeout(client: &DatabaseClient) -> String {
+        client
+            .query("SELECT current_setting('statement_timeout')", &[])
+            .await
+            .unwrap()[0]
+            .get::<_, String>(0)
+    }
+
+    async fn backend_pid(client: &DatabaseClient) -> i32 {
+        client.query("SELECT pg_backend_pid()", &[]).await.unwrap()[0].get::<_, i32>(0)
+    }
+
+    /// Outside a transaction, the statement timeout applies to the session (without a transaction)
+    #[tokio::test]
+    async fn test_statement_timeout_without_transaction() {
+        with_db_url(|url| async move {
+            let mgr = setup_schema(&url).await;
+            let database = items_database();
+
+            let mut holder = TransactionHolder::new();
+            holder
+                .with_tx(
+                    &database,
+                    &mgr,
+                    select_items(&database),
+                    Some(Duration::from_millis(100)),
+                )
+                .await
+                .unwrap();
+
+            {
+                let state = holder.state.lock().await;
+                assert_eq!(state.status, TransactionStatus::Idle);
+                let client = state.client.as_ref().unwrap();
+                assert_eq!(current_statement_timeout(client).await, "100ms");
+                let error = client.query("SELECT pg_sleep(1)", &[]).await.unwrap_err();
+                assert_eq!(error.code(), Some(&SqlState::QUERY_CANCELED));
+            }
+
+            holder.finalize(true).await.unwrap();
+        })
+        .await;
+    }
+
+    /// Through PgBouncer, the statement timeout applies only to the transaction
+    #[tokio::test]
+    async fn test_pgbouncer_statement_timeout() {
+        with_db_url(|url| async move {
+            setup_schema(&url).await;
+            let stand_in = PgBouncerStandIn::start(&url).await;
+            let mgr = client_manager(&stand_in.url(true)).await;
+            let database = items_database();
+
+            let mut holder = TransactionHolder::new();
+            holder
+                .with_tx(
+                    &database,
+                    &mgr,
+                    select_items(&database),
+                    Some(Duration::from_millis(100)),
+                )
+                .await
+                .unwrap();
+            assert_eq!(holder.state.lock().await.status, TransactionStatus::Active);
+
+            holder.finalize(true).await.unwrap();
+            assert_eq!(stand_in.violations(), Vec::<String>::new());
+        })
+        .await;
+    }
+
+    /// A pooled connection goes back to the pool without the statement timeout of the request
+    #[cfg(feature = "pool")]
+    #[tokio::test]
+    async fn test_statement_timeout_is_reset() {
+        with_db_url(|url| async move {
+            setup_schema(&url).await;
+            let mgr = pooled_client_manager(&url).await;
+            let database = items_database();
+
+            let mut holder = TransactionHolder::new();
+            holder.override_statement_timeout(Duration::from_millis(100));
+            holder
+                .with_tx(&database, &mgr, select_items(&database), None)
+                .await
+                .unwrap();
+            holder.finalize(true).await.unwrap();
+            drop(holder);
+
+            let client = mgr.get_client().await.unwrap();
+            assert_eq!(current_statement_timeout(&client).await, "0");
+        })
+        .await;
+    }
+
+    #[cfg(feature = "pool")]
+    async fn pooled_client_manager(url: &str) -> DatabaseClientManager {
+        DatabaseClientManager::from_url_pooled(
+            url,
+            false,
+            &crate::PoolOptions::with_max_size(Some(1)),
+            TransactionMode::ReadWrite,
+        )
+        .await
+        .unwrap()
+    }
+
+    /// A request dropped with an open transaction doesn't return its connection to the pool
+    #[cfg(feature = "pool")]
+    #[tokio::test]
+    async fn test_dropped_transaction_discards_pooled_connection() {
+        with_db_url(|url| async move {
+            setup_schema(&url).await;
+            let mgr = pooled_client_manager(&url).await;
+
+            let pid = {
+                let holder = TransactionHolder::new();
+                let mut state = holder.state.lock().await;
+                state.ensure_client(&mgr).await.unwrap();
+                state.begin_transaction().await.unwrap();
+                let client = state.client.as_ref().unwrap();
+                client
+                    .execute("INSERT INTO test_items (name) VALUES ($1)", &[&"orphaned"])
+                    .await
+                    .unwrap();
+                backend_pid(client).await
+            };
+
+            let client = mgr.get_client().await.unwrap();
+            assert_ne!(backend_pid(&client).await, pid);
+            drop(client);
+
+            assert_eq!(count_items(&url).await, 0);
+        })
+        .await;
+    }
+
+    /// A request dropped in the middle of a query (even outside a transaction) doesn't return its
+    /// connection to the pool (where the cancel request could hit the next query)
+    #[cfg(feature = "pool")]
+    #[tokio::test]
+    async fn test_dropped_query_discards_pooled_connection() {
+        with_db_url(|url| async move {
+            setup_schema(&url).await;
+            let mgr = pooled_client_manager(&url).await;
+            let database = items_database();
+
+            let mut holder = TransactionHolder::new();
+            holder
+                .with_tx(&database, &mgr, select_items(&database), None)
+                .await
+                .unwrap();
+            let pid = backend_pid(holder.state.lock().await.client.as_ref().unwrap()).await;
+
+            // Block reads of the table until the lock is released
+            let locker = client_manager(&url).await.get_client().await.unwrap();
+            locker
+                .batch_execute("BEGIN; LOCK TABLE test_items IN ACCESS EXCLUSIVE MODE")
+                .await
+                .unwrap();
+
+            let result = tokio::time::timeout(
+                Duration::from_millis(100),
+                holder.with_tx(&database, &mgr, select_items(&database), None),
+            )
+            .await;
+            assert!(result.is_err());
+
+            drop(holder);
+            locker.batch_execute("ROLLBACK").await.unwrap();
+
+            let client = mgr.get_client().await.unwrap();
+            assert_ne!(backend_pid(&client).await, pid);
+        })
+        .await;
+    }
+
+    /// Reads don't set the session variables (so don't need a transaction)
+    #[tokio::test]
+    async fn test_reads_skip_session_variables() {
+        with_db_url(|url| async move {
+            let mgr = setup_schema(&url).await;
+            let database = items_database();
+
+            let mut holder = TransactionHolder::new();
+            holder.set_session_variables(vec![(
+                "exo.auth_context_name".to_string(),
+                "reader".to_string(),
+            )]);
+            holder
+                .with_tx(&database, &mgr, select_items(&database), None)
+                .await
+                .unwrap();
+
+            let state = holder.state.lock().await;
+            assert_eq!(state.status, TransactionStatus::Idle);
+            let client = state.client.as_ref().unwrap();
+            assert_eq!(current_name(client).await, None);
+        })
+        .await;
+    }
 }
@@ -0,0 +1,280 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::sync::LazyLock;
+
+use exo_sql_core::SchemaObjectName;
+use regex::Regex;
+
+use crate::{
+    DebugPrintTo, op::SchemaOp, sql_expression::normalize_expression, table_spec::TableSpec,
+};
+
+/// A `CHECK` constraint on a table
+#[derive(Debug, Clone)]
+pub struct CheckSpec {
+    pub name: String,
+    /// The SQL expression (without the surrounding `CHECK (...)`)
+    pub expression: String,
+}
+
+/// A check constraint that a validation annotation (`@range` or `@maxLength`) can express
+#[derive(Debug, Clone, PartialEq)]
+pub enum SimpleCheck {
+    /// `column >= min AND column <= max` (with either bound optional)
+    Range {
+        column: String,
+        min: Option<String>,
+        max: Option<String>,
+    },
+    /// `char_length(column) <= max_length`
+    MaxLength { column: String, max_length: usize },
+}
+
+static COMPARISON_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"^"?([A-Za-z_][A-Za-z0-9_]*)"?\s*(>=|<=)\s*(.+)$"#).unwrap());
+
+static LENGTH_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"^(?i:char_length|character_length|length)\(\(?"?([A-Za-z_][A-Za-z0-9_]*)"?\)?(?:::[a-z ]+)?\)\s*<=\s*(.+)$"#)
+        .unwrap()
+});
+
+impl CheckSpec {
+    pub fn new(name: String, expression: String) -> Self {
+        Self { name, expression }
+    }
+
+    // Does the other check enforce the same condition as this check (ignoring the name)?
+    pub fn effectively_eq(&self, other: &CheckSpec) -> bool {
+        normalize_expression(&self.expression) == normalize_expression(&other.expression)
+    }
+
+    pub fn diff<'a>(
+        &'a self,
+        other: &'a CheckSpec,
+        self_table: &'a TableSpec,
+        other_table: &'a TableSpec,
+    ) -> Vec<SchemaOp<'a>> {
+        // Like indexes, a check with a different name but the same expression needs no change
+        if self_table.name == other_table.name && self.effectively_eq(other) {
+            return vec![];
+        }
+
+        vec![
+            SchemaOp::DeleteCheck {
+                table: self_table,
+                check: self,
+            },
+            SchemaOp::CreateCheck {
+                table: other_table,
+                check: other,
+            },
+        ]
+    }
+
+    /// Recognize a range (`price >= 0 AND price <= 100`) or a length (`char_length(title) <= 100`)
+    /// check on a single column. Handles the way Postgres reports expressions (with extra
+    /// parentheses, casts, and quoted negative numbers).
+    pub fn simple_check(&self) -> Option<SimpleCheck> {
+        let terms = split_conjunction(strip_parentheses(&self.expression));
+
+        if let [term] = &terms[..]
+            && let Some(captures) = LENGTH_RE.captures(term)
+        {
+            return Some(SimpleCheck::MaxLength {
+                column: captures[1].to_string(),
+                max_length: number_literal(&captures[2])?.parse().ok()?,
+            });
+        }
+
+        if terms.is_empty() || terms.len() > 2 {
+            return None;
+        }
+
+        let mut column: Option<&str> = None;
+        let (mut min, mut max) = (None, None);
+
+        for term in terms {
+            let captures = COMPARISON_RE.captures(term)?;
+            let term_column = captures.get(1)?.as_str();
+            if column.is_some_and(|column| column != term_column) {
+                return None;
+            }
+            column = Some(term_column);
+
+            let bound = if &captures[2] == ">=" {
+                &mut min
+            } else {
+                &mut max
+            };
+            if bound.is_some() {
+                return None;
+            }
+            *bound = Some(number_literal(&captures[3])?.to_string());
+        }
+
+        Some(SimpleCheck::Range {
+            column: column?.to_string(),
+            min,
+            max,
+        })
+    }
+
+    pub fn creation_sql(&self, table_name: &SchemaObjectName) -> String {
+        format!(
+            "ALTER TABLE {table_name} ADD CONSTRAINT \"{check_name}\" CHECK ({expression});",
+            table_name = table_name.sql_name(),
+            check_name = self.name,
+            expression = self.expression,
+        )
+    }
+}
+
+/// Strip the parentheses around the whole expression (`((a >= 0))` -> `a >= 0`)
+fn strip_parentheses(expression: &str) -> &str {
+    let mut expression = expression.trim();
+
+    while expression.starts_with('(')
+        && matching_parenthesis(expression) == Some(expression.len() - 1)
+    {
+        expression = expression[1..expression.len() - 1].trim();
+    }
+
+    expression
+}
+
+/// The index of the parenthesis that closes the one at the start of the expression
+fn matching_parenthesis(expression: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut in_literal = false;
+
+    for (index, c) in expression.char_indices() {
+        match c {
+            '\'' => in_literal = !in_literal,
+            '(' if !in_literal => depth += 1,
+            ')' if !in_literal => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Split the expression at the top-level `AND`s (each term without the parentheses around it)
+fn split_conjunction(expression: &str) -> Vec<&str> {
+    let mut terms = vec![];
+    let mut depth = 0;
+    let mut in_literal = false;
+    let mut term_start = 0;
+
+    for (index, c) in expression.char_indices() {
+        match c {
+            '\'' => in_literal = !in_literal,
+            '(' if !in_literal => depth += 1,
+            ')' if !in_literal => depth -= 1,
+            ' ' if !in_literal
+                && depth == 0
+                && expression[index..]
+                    .get(..5)
+                    .is_some_and(|word| word.eq_ignore_ascii_case(" AND ")) =>
+            {
+                terms.push(strip_parentheses(&expression[term_start..index]));
+                term_start = index + 5;
+            }
+            _ => {}
+        }
+    }
+    terms.push(strip_parentheses(&expression[term_start..]));
+
+    terms
+}
+
+/// The number in a literal such as `10`, `(0)::double precision`, or `'-10'::integer`
+fn number_literal(literal: &str) -> Option<&str> {
+    let literal = strip_parentheses(literal);
+    let literal = match literal.find("::") {
+        Some(cast_start) => strip_parentheses(&literal[..cast_start]),
+        None => literal,
+    };
+    let number = literal.trim_matches('\'');
+
+    number.parse::<f64>().ok().map(|_| number)
+}
+
+impl DebugPrintTo for CheckSpec {
+    fn debug_print_to<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+        indent: usize,
+    ) -> std::io::Result<()> {
+        let indent_str = " ".repeat(indent);
+        writeln!(
+            writer,
+            "{}- ({}, CHECK ({}))",
+            indent_str, self.name, self.expression
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn simple_check(expression: &str) -> Option<SimpleCheck> {
+        CheckSpec::new("check".to_string(), expression.to_string()).simple_check()
+    }
+
+    #[test]
+    fn recognizes_simple_checks_as_reported_by_postgres() {
+        assert_eq!(
+            simple_check(
+                "((price >= (0)::double precision) AND (price <= (99.5)::double precision))"
+            ),
+            Some(SimpleCheck::Range {
+                column: "price".to_string(),
+                min: Some("0".to_string()),
+                max: Some("99.5".to_string()),
+            })
+        );
+        assert_eq!(
+            simple_check("(\"temperature\" >= '-10'::integer)"),
+            Some(SimpleCheck::Range {
+                column: "temperature".to_string(),
+                min: Some("-10".to_string()),
+                max: None,
+            })
+        );
+        assert_eq!(
+            simple_check("(char_length(title) <= 100)"),
+            Some(SimpleCheck::MaxLength {
+                column: "title".to_string(),
+                max_length: 100,
+            })
+        );
+    }
+
+    #[test]
+    fn ignores_other_checks() {
+        for expression in [
+            "(start_at < end_at)",
+            "((a >= 0) AND (b <= 10))",
+            "((a >= 0) OR (a <= 10))",
+            "((a >= 0) AND (a >= 10))",
+            "(a > 0)",
+            "(status = 'active'::text)",
+        ] {
+            assert_eq!(simple_check(expression), None, "{expression}");
+        }
+    }
+}
@@ -41,6 +41,12 @@ pub struct ColumnSpec {
     pub is_nullable: bool,
     pub unique_constraints: Vec<String>,
     pub default_value: Option<ColumnDefault>,
+    /// The name of the column before a rename (see `PhysicalColumn::previous_name`). Not
+    /// considered for equality, since it is only a hint for migrations.
+    pub previous_name: Option<String>,
+    /// The comment on the column (`COMMENT ON COLUMN`). Not considered for equality, since
+    /// migrations don't manage comments.
+    pub comment: Option<String>,
 }
 
 impl PartialEq for ColumnSpec {
@@ -150,7 +156,7 @@ impl DebugPrintTo for ColumnReferenceSpec {
 }
 
 const COLUMNS_TYPE_QUERY: &str = "
-  SELECT pg_class.relname as table_name, attname as column_name, format_type(atttypid, atttypmod), attndims, attnotnull FROM pg_attribute
+  SELECT pg_class.relname as table_name, attname as column_name, format_type(atttypid, atttypmod), attndims, attnotnull, col_description(attrelid, attnum) as comment FROM pg_attribute
     LEFT JOIN pg_class ON pg_attribute.attrelid = pg_class.oid
     LEFT JOIN pg_namespace ON pg_class.relnamespace = pg_namespace.oid
   WHERE attnum > 0 AND attisdropped = false AND pg_namespace.nspname = $1";
@@ -232,6 +238,7 @@ impl ColumnSpec {
             default_value,
             not_null,
             db_type,
+            comment,
         } = table_attributes
             .get(column_name)
             .ok_or(DatabaseError::Generic(format!(
@@ -251,6 +258,8 @@ impl ColumnSpec {
                 is_nullable: !not_null,
                 unique_constraints,
                 default_value: default_value.clone(),
+                previous_name: None,
+                comment: comment.clone(),
             }),
             issues: vec![],
         })
@@ -392,6 +401,8 @@ impl ColumnSpec {
             unique_constraints: column.unique_constraints,
             default_value: column.default_value,
             reference_specs,
+            previous_name: column.previous_name,
+            comment: None,
         }
     }
 
@@ -447,6 +458,7 @@ impl ColumnSpec {
             let table_name: String = row.get("table_name");
             let column_name: String = row.get("column_name");
             let not_null: bool = row.get("attnotnull");
+            let comment: Option<String> = row.get("comment");
 
             let table_name = SchemaObjectName::new_with_schema_name(table_name, schema_name);
 
@@ -492,6 +504,7 @@ impl ColumnSpec {
                     db_type,
                     not_null,
                     default_value: None,
+                    comment,
                 },
             );
         }
@@ -574,6 +587,7 @@ pub struct ColumnAttribute {
     pub default_value: Option<ColumnDefault>,
     pub db_type: Option<Box<dyn PhysicalColumnType>>,
     pub not_null: bool,
+    pub comment: Option<String>,
 }
 
 const TEXT_TYPE_CAST_PREFIX: &str = "'::text";
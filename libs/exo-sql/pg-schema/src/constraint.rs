@@ -40,11 +40,18 @@ pub(crate) struct UniqueConstraint {
     pub(crate) columns: Vec<String>,
 }
 
+#[derive(Debug)]
+pub(crate) struct CheckConstraint {
+    pub(crate) constraint_name: String,
+    pub(crate) expression: String,
+}
+
 #[derive(Debug)]
 pub(crate) struct Constraints {
     pub(crate) primary_key: Option<PrimaryKeyConstraint>,
     pub(crate) foreign_constraints: Vec<ForeignKeyConstraint>,
     pub(crate) uniques: Vec<UniqueConstraint>,
+    pub(crate) checks: Vec<CheckConstraint>,
 }
 
 static PRIMARY_KEY_RE: LazyLock<Regex> =
@@ -53,6 +60,10 @@ static FOREIGN_KEY_RE: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"FOREIGN KEY \(([^)]+)\) REFERENCES ([^\(]+)\(([^)]+)\)").unwrap()
 });
 static UNIQUE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"UNIQUE \(([^)]+)\)").unwrap());
+// A constraint that isn't inherited by child tables (`NO INHERIT`) or that was added with `NOT
+// VALID` (but not yet validated) reports that as a suffix
+static CHECK_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)^CHECK \((.*)\)( NO INHERIT)?( NOT VALID)?$").unwrap());
 static LIST_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(\w+)").unwrap());
 
 const CONSTRAINT_QUERY: &str = "
@@ -79,7 +90,7 @@ impl Constraints {
         client: &DatabaseClient,
         table_name: &SchemaObjectName,
     ) -> Result<Constraints, DatabaseError> {
-        // Get a list of constraints in the table (primary key and foreign key constraints)
+        // Get a list of constraints in the table (primary key, foreign key, unique, and check constraints)
         let constraints = client
             .query(
                 CONSTRAINT_QUERY,
@@ -165,13 +176,39 @@ impl Constraints {
             })
             .collect();
 
+        let checks = constraints
+            .iter()
+            .filter(|(contype, _, _, _, _)| *contype == 'c')
+            .map(|(_, conname, condef, _, _)| {
+                let expression = Self::parse_check_definition(condef).ok_or_else(|| {
+                    DatabaseError::Generic(format!(
+                        "Unexpected definition of the check constraint '{conname}' on '{}': {condef}",
+                        table_name.fully_qualified_name()
+                    ))
+                })?;
+                Ok(CheckConstraint {
+                    constraint_name: conname.to_string(),
+                    expression: expression.to_string(),
+                })
+            })
+            .collect::<Result<Vec<_>, DatabaseError>>()?;
+
         Ok(Constraints {
             primary_key,
             foreign_constraints,
             uniques,
+            checks,
         })
     }
 
+    /// The expression of a check constraint definition (as reported by `pg_get_constraintdef`)
+    fn parse_check_definition(condef: &str) -> Option<&str> {
+        CHECK_RE
+            .captures(condef)
+            .and_then(|captures| captures.get(1))
+            .map(|expression| expression.as_str())
+    }
+
     fn parse_column_list(column_list: &str) -> Vec<String> {
         // Basically just split the string on commas and remove the quotes (the regex takes care of the quotes)
         LIST_RE
@@ -204,3 +241,37 @@ pub(crate) fn sorted_comma_list<T: ToString>(list: &HashSet<T>, quote_name: bool
     list.sort();
     list.join(", ")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_check_definitions() {
+        for (condef, expression) in [
+            (
+                "CHECK (price >= 0::double precision)",
+                "price >= 0::double precision",
+            ),
+            ("CHECK (start_at < end_at) NOT VALID", "start_at < end_at"),
+            ("CHECK (quantity > 0) NO INHERIT", "quantity > 0"),
+            ("CHECK (quantity > 0) NO INHERIT NOT VALID", "quantity > 0"),
+            ("CHECK ((a > 0) OR (b > 0))", "(a > 0) OR (b > 0)"),
+        ] {
+            assert_eq!(
+                Constraints::parse_check_definition(condef),
+                Some(expression),
+                "{condef}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_unexpected_check_definitions() {
+        assert_eq!(Constraints::parse_check_definition("UNIQUE (email)"), None);
+        assert_eq!(
+            Constraints::parse_check_definition("CHECK (quantity > 0) DEFERRABLE"),
+            None
+        );
+    }
+}
@@ -13,19 +13,20 @@ use crate::column_default_schema::ColumnDefaultSchema;
 use exo_sql_core::relation::RelationColumnPair;
 use exo_sql_core::{ColumnAutoincrement, ColumnDefault};
 use exo_sql_core::{
-    ColumnReference, Database, DatabaseError, ManyToOne, PhysicalColumn, PhysicalIndex,
-    PhysicalTable, SchemaObjectName, TableId,
+    ColumnReference, Database, DatabaseError, IndexTarget, ManyToOne, PhysicalCheck,
+    PhysicalColumn, PhysicalIndex, PhysicalTable, SchemaObjectName, TableId,
 };
-use exo_sql_pg_connect::DatabaseClient;
+use exo_sql_pg_connect::{CHANGE_FEED_CHANNEL, DatabaseClient};
 
 use crate::{
     DebugPrintTo,
+    check_spec::CheckSpec,
     column_spec::{ColumnReferenceSpec, ColumnSpec},
     enum_spec::EnumSpec,
     function_spec::FunctionSpec,
     index_spec::{IndexKind, IndexSpec},
     issue::WithIssues,
-    op::{RenameTableOp, SchemaOp},
+    op::{RenameColumnOp, RenameTableOp, SchemaOp},
     spec::MigrationScopeMatches,
     table_spec::TableSpec,
     trigger_spec::{TriggerEvent, TriggerOrientation, TriggerSpec, TriggerTiming},
@@ -44,6 +45,15 @@ const MATERIALIZED_VIEWS_QUERY: &str =
 
 const SEQUENCE_NAMES_QUERY: &str = "SELECT sequence_name FROM information_schema.sequences WHERE sequence_schema = $1 ORDER BY sequence_name";
 
+/// The prefix of the names of the triggers that publish row changes to the change feed
+pub(crate) const CHANGE_FEED_TRIGGER_PREFIX: &str = "exograph_notify_on_";
+
+/// The table in which the server keeps the queries registered through the automatic persisted
+/// queries protocol (with `EXO_PERSISTED_QUERY_STORE=postgres`). Since the server manages it, it
+/// is left out of the spec of a live database (so migrations don't drop it and imports don't turn
+/// it into a type).
+const PERSISTED_QUERIES_TABLE: &str = "exograph_persisted_queries";
+
 #[derive(Debug, Clone)]
 pub struct DatabaseSpec {
     pub tables: Vec<TableSpec>,
@@ -107,18 +117,18 @@ impl DatabaseSpec {
         let mut database = Database::default();
 
         // Step 1: Create tables (without columns)
-        let mut tables: Vec<(TableId, Vec<ColumnSpec>, Vec<IndexSpec>)> = self
+        let mut tables: Vec<(TableId, Vec<ColumnSpec>, Vec<IndexSpec>, Vec<CheckSpec>)> = self
             .tables
             .into_iter()
             .filter(|table_spec| table_spec.managed)
             .map(|table| {
                 let table_id = database.insert_table(table.to_column_less_table());
-                (table_id, table.columns, table.indices)
+                (table_id, table.columns, table.indices, table.checks)
             })
             .collect();
 
         // Step 2: Add columns to tables
-        for (table_id, column_specs, index_specs) in tables.iter() {
+        for (table_id, column_specs, index_specs, check_specs) in tables.iter() {
             let columns = column_specs
                 .iter()
                 .map(|column_spec| {
@@ -132,6 +142,7 @@ impl DatabaseSpec {
                         default_value: column_spec.default_value.to_owned(),
                         update_sync: false, // There is no good way to know from the database spec if a column should be updated on sync
                         column_references: None,
+                        previous_name: column_spec.previous_name.clone(),
                     }
                 })
                 .collect();
@@ -144,13 +155,21 @@ impl DatabaseSpec {
                 .map(|index_spec| PhysicalIndex {
                     name: index_spec.name.to_owned(),
                     columns: index_spec.columns.to_owned(),
+                    predicate: index_spec.predicate.to_owned(),
                     index_kind: Box::new(index_spec.index_kind.clone()),
                 })
                 .collect();
+            table.checks = check_specs
+                .iter()
+                .map(|check_spec| PhysicalCheck {
+                    name: check_spec.name.to_owned(),
+                    expression: check_spec.expression.to_owned(),
+                })
+                .collect();
         }
 
         // Step 3: Add column references to tables
-        for (table_id, column_specs, _) in tables.iter_mut() {
+        for (table_id, column_specs, _, _) in tables.iter_mut() {
             for column_spec in column_specs {
                 let column_references = column_spec.reference_specs.as_ref().map(|ref_specs| {
                     ref_specs
@@ -265,11 +284,16 @@ impl DatabaseSpec {
             .tables()
             .into_iter()
             .map(|(_, table)| {
-                let (trigger_specs, function_specs) = match Self::update_trigger(table) {
+                let (mut trigger_specs, mut function_specs) = match Self::update_trigger(table) {
                     Some((trigger, function)) => (vec![trigger], vec![function]),
                     None => (vec![], vec![]),
                 };
 
+                if let Some((triggers, function)) = Self::change_feed_triggers(table) {
+                    trigger_specs.extend(triggers);
+                    function_specs.push(function);
+                }
+
                 all_function_specs.extend(function_specs);
 
                 TableSpec::new(
@@ -286,6 +310,7 @@ impl DatabaseSpec {
                         .map(|index| IndexSpec {
                             name: index.name.clone(),
                             columns: index.columns.clone(),
+                            predicate: index.predicate.clone(),
                             index_kind: index
                                 .index_kind
                                 .as_any()
@@ -294,6 +319,11 @@ impl DatabaseSpec {
                                 .expect("PhysicalIndex should contain a Postgres IndexKind"),
                         })
                         .collect(),
+                    table
+                        .checks
+                        .iter()
+                        .map(|check| CheckSpec::new(check.name.clone(), check.expression.clone()))
+                        .collect(),
                     trigger_specs,
                     table.managed,
                 )
@@ -361,6 +391,9 @@ impl DatabaseSpec {
                 .map_err(DatabaseError::driver)?
             {
                 let table_name: String = table_row.get("table_name");
+                if table_name == PERSISTED_QUERIES_TABLE {
+                    continue;
+                }
                 let table_name = SchemaObjectName::new_with_schema_name(table_name, &schema_name);
 
                 let mut table =
@@ -460,6 +493,59 @@ impl DatabaseSpec {
         }
     }
 
+    /// Triggers (one for each kind of change) to publish the changed rows to the change feed. The
+    /// notification payload identifies the row by its primary key, for example:
+    /// `{"schema": "public", "table": "todos", "op": "UPDATE", "pk": {"id": 1}}`
+    fn change_feed_triggers(table: &PhysicalTable) -> Option<(Vec<TriggerSpec>, FunctionSpec)> {
+        if !table.change_feed {
+            return None;
+        }
+
+        let table_name = table.name.fully_qualified_name_with_sep("_");
+
+        let pk_fields = table
+            .get_pk_physical_columns()
+            .iter()
+            .map(|column| format!("'{name}', changed.\"{name}\"", name = column.name))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let function_name = format!("exograph_notify_{table_name}");
+        // For `DELETE`, `NEW` is null, so take the primary key from `OLD`
+        let function_body = format!(
+            "DECLARE changed RECORD; BEGIN IF TG_OP = 'DELETE' THEN changed := OLD; ELSE changed := NEW; END IF; \
+             PERFORM pg_notify('{CHANGE_FEED_CHANNEL}', json_build_object('schema', TG_TABLE_SCHEMA, 'table', TG_TABLE_NAME, 'op', TG_OP, 'pk', json_build_object({pk_fields}))::text); \
+             RETURN NULL; END;"
+        );
+
+        let triggers = [
+            TriggerEvent::Insert,
+            TriggerEvent::Update,
+            TriggerEvent::Delete,
+        ]
+        .into_iter()
+        .map(|event| TriggerSpec {
+            name: format!(
+                "{CHANGE_FEED_TRIGGER_PREFIX}{}_{table_name}",
+                event.as_str().to_lowercase()
+            ),
+            function: function_name.clone(),
+            timing: TriggerTiming::After,
+            orientation: TriggerOrientation::Row,
+            event,
+        })
+        .collect();
+
+        Some((
+            triggers,
+            FunctionSpec {
+                name: function_name,
+                body: function_body,
+                language: "plpgsql".into(),
+            },
+        ))
+    }
+
     pub fn with_table_renamed<'a>(
         &mut self,
         old_name: &'a SchemaObjectName,
@@ -501,6 +587,156 @@ impl DatabaseSpec {
 
         ops
     }
+
+    pub fn with_column_renamed<'a>(
+        &mut self,
+        table_name: &SchemaObjectName,
+        old_name: &str,
+        new_name: &str,
+    ) -> Vec<SchemaOp<'a>> {
+        let mut ops = vec![];
+
+        self.tables.iter_mut().for_each(|table| {
+            if &table.name == table_name {
+                table.columns.iter_mut().for_each(|column| {
+                    if column.name == old_name {
+                        column.name = new_name.to_string();
+                        ops.push(SchemaOp::RenameColumn(RenameColumnOp {
+                            table_name: table_name.clone(),
+                            old_name: old_name.to_string(),
+                            new_name: new_name.to_string(),
+                        }));
+
+                        // Postgres doesn't rename the sequence used by a serial column
+                        if let Some(ColumnDefault::Autoincrement(ColumnAutoincrement::Serial)) =
+                            &column.default_value
+                        {
+                            ops.push(SchemaOp::RenameSequence {
+                                old_name: ColumnAutoincrement::serial_sequence_name(
+                                    table_name, old_name,
+                                ),
+                                new_name: ColumnAutoincrement::serial_sequence_name(
+                                    table_name, new_name,
+                                ),
+                            });
+                        }
+                    }
+                });
+
+                table.indices.iter_mut().for_each(|index| {
+                    for index_column in index.columns.iter_mut() {
+                        if let IndexTarget::Column(name) = &mut index_column.target
+                            && name == old_name
+                        {
+                            *name = new_name.to_string();
+                        }
+                    }
+                });
+            }
+
+            // Update references to the column (if it is a primary key) from other tables
+            table.columns.iter_mut().for_each(|column| {
+                for reference_spec in column.reference_specs.iter_mut().flatten() {
+                    if &reference_spec.foreign_table_name == table_name
+                        && reference_spec.foreign_pk_column_name == old_name
+                    {
+                        reference_spec.foreign_pk_column_name = new_name.to_string();
+                    }
+                }
+            });
+        });
+
+        ops
+    }
+
+    /// Rename a value of an enum (as chosen by the user, since we don't guess renames from the
+    /// values that disappear). Defaults set to the old value follow the rename, as they do in
+    /// Postgres.
+    pub fn with_enum_value_renamed<'a>(
+        &mut self,
+        enum_name: &SchemaObjectName,
+        old_value: &str,
+        new_value: &str,
+    ) -> Vec<SchemaOp<'a>> {
+        let Some(enum_) = self.enums.iter_mut().find(|enum_| &enum_.name == enum_name) else {
+            return vec![];
+        };
+
+        let Some(variant) = enum_
+            .variants
+            .iter_mut()
+            .find(|variant| *variant == old_value)
+        else {
+            return vec![];
+        };
+        *variant = new_value.to_string();
+
+        let enum_ = enum_.clone();
+        self.tables.iter_mut().for_each(|table| {
+            table.columns.iter_mut().for_each(|column| {
+                if enum_.is_used_by(column)
+                    && let Some(ColumnDefault::Enum(value)) = &mut column.default_value
+                    && value == old_value
+                {
+                    *value = new_value.to_string();
+                }
+            })
+        });
+
+        vec![SchemaOp::RenameEnumValue {
+            enum_name: enum_name.clone(),
+            old_value: old_value.to_string(),
+            new_value: new_value.to_string(),
+        }]
+    }
+
+    /// Apply column renames requested by the `new` spec (through `@column(previously=...)`).
+    ///
+    /// A rename applies only if the table has a column with the previous name and none with the
+    /// new name, so the hint may stay in the model after the migration.
+    pub fn with_hinted_column_renames<'a>(
+        &mut self,
+        new: &DatabaseSpec,
+        scope: &MigrationScopeMatches,
+    ) -> Vec<SchemaOp<'a>> {
+        let renames = new
+            .tables
+            .iter()
+            .filter(|new_table| new_table.managed && scope.matches(&new_table.name))
+            .flat_map(|new_table| {
+                let existing_table = self
+                    .tables
+                    .iter()
+                    .find(|table| table.name == new_table.name);
+
+                new_table.columns.iter().filter_map(move |new_column| {
+                    let existing_table = existing_table?;
+                    let previous_name = new_column.previous_name.as_ref()?;
+                    let has_column = |name: &str| {
+                        existing_table
+                            .columns
+                            .iter()
+                            .any(|column| column.name == name)
+                    };
+
+                    (has_column(previous_name) && !has_column(&new_column.name)).then(|| {
+                        (
+                            new_table.name.clone(),
+                            previous_name.clone(),
+                            new_column.name.clone(),
+                        )
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+
+        renames
+            .into_iter()
+            .flat_map(|(table_name, old_name, new_name)| {
+                self.with_column_renamed(&table_name, &old_name, &new_name)
+            })
+            .collect()
+    }
 }
 
 impl DebugPrintTo for DatabaseSpec {
@@ -582,6 +818,8 @@ mod tests {
                             default_value: Some(ColumnDefault::Autoincrement(
                                 ColumnAutoincrement::Serial,
                             )),
+                            previous_name: None,
+                            comment: None,
                         },
                         ColumnSpec {
                             name: "name".into(),
@@ -593,6 +831,8 @@ mod tests {
                             is_nullable: true,
                             unique_constraints: vec![],
                             default_value: None,
+                            previous_name: None,
+                            comment: None,
                         },
                         ColumnSpec {
                             name: "email".into(),
@@ -602,10 +842,13 @@ mod tests {
                             is_nullable: true,
                             unique_constraints: vec![],
                             default_value: None,
+                            previous_name: None,
+                            comment: None,
                         },
                     ],
                     vec![],
                     vec![],
+                    vec![],
                     true,
                 )],
                 vec![],
@@ -633,9 +876,12 @@ mod tests {
                         is_nullable: true,
                         unique_constraints: vec![],
                         default_value: None,
+                        previous_name: None,
+                        comment: None,
                     }],
                     vec![],
                     vec![],
+                    vec![],
                     true,
                 )],
                 vec![],
@@ -668,6 +914,8 @@ mod tests {
                             is_nullable: true,
                             unique_constraints: vec![],
                             default_value: None,
+                            previous_name: None,
+                            comment: None,
                         },
                         ColumnSpec {
                             name: "just_precision".into(),
@@ -680,6 +928,8 @@ mod tests {
                             is_nullable: true,
                             unique_constraints: vec![],
                             default_value: None,
+                            previous_name: None,
+                            comment: None,
                         },
                         ColumnSpec {
                             name: "no_precision_and_scale".into(),
@@ -692,10 +942,13 @@ mod tests {
                             is_nullable: true,
                             unique_constraints: vec![],
                             default_value: None,
+                            previous_name: None,
+                            comment: None,
                         },
                     ],
                     vec![],
                     vec![],
+                    vec![],
                     true,
                 )],
                 vec![],
@@ -705,6 +958,64 @@ mod tests {
         .await;
     }
 
+    #[tokio::test]
+    async fn table_with_check() {
+        test_database_spec(
+            "CREATE TABLE events (start_at INT, end_at INT, CONSTRAINT events_check CHECK (start_at < end_at))",
+            DatabaseSpec::new(
+                vec![TableSpec::new(
+                    SchemaObjectName {
+                        name: "events".into(),
+                        schema: None,
+                    },
+                    vec![
+                        ColumnSpec {
+                            name: "start_at".into(),
+                            typ: Box::new(IntColumnType { bits: IntBits::_32 }),
+                            reference_specs: None,
+                            is_pk: false,
+                            is_nullable: true,
+                            unique_constraints: vec![],
+                            default_value: None,
+                            previous_name: None,
+                            comment: None,
+                        },
+                        ColumnSpec {
+                            name: "end_at".into(),
+                            typ: Box::new(IntColumnType { bits: IntBits::_32 }),
+                            reference_specs: None,
+                            is_pk: false,
+                            is_nullable: true,
+                            unique_constraints: vec![],
+                            default_value: None,
+                            previous_name: None,
+                            comment: None,
+                        },
+                    ],
+                    vec![],
+                    vec![CheckSpec::new(
+                        "events_check".into(),
+                        "start_at < end_at".into(),
+                    )],
+                    vec![],
+                    true,
+                )],
+                vec![],
+                vec![],
+            ),
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn persisted_queries_table_skipped() {
+        test_database_spec(
+            r#"CREATE TABLE "exograph_persisted_queries" ("hash" TEXT PRIMARY KEY, "query" TEXT NOT NULL)"#,
+            DatabaseSpec::new(vec![], vec![], vec![]),
+        )
+        .await;
+    }
+
     async fn test_database_spec(schema: &str, expected_database_spec: DatabaseSpec) {
         with_init_script(schema, |client| async move {
             let WithIssues {
@@ -761,6 +1072,12 @@ mod tests {
             assert_index_spec_eq(actual_index, expected_index);
         }
 
+        assert_eq!(actual.checks.len(), expected.checks.len());
+        for (actual_check, expected_check) in actual.checks.iter().zip(expected.checks.iter()) {
+            assert_eq!(actual_check.name, expected_check.name);
+            assert_eq!(actual_check.expression, expected_check.expression);
+        }
+
         assert_eq!(actual.triggers.len(), expected.triggers.len());
         for (actual_trigger, expected_trigger) in
             actual.triggers.iter().zip(expected.triggers.iter())
@@ -785,6 +1102,7 @@ mod tests {
     fn assert_index_spec_eq(actual: &IndexSpec, expected: &IndexSpec) {
         assert_eq!(actual.name, expected.name);
         assert_eq!(actual.columns, expected.columns);
+        assert_eq!(actual.predicate, expected.predicate);
         assert_eq!(actual.index_kind, expected.index_kind);
     }
 
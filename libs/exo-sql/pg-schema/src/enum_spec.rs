@@ -10,15 +10,26 @@
 use exo_sql_core::DatabaseError;
 use exo_sql_core::SchemaObjectName;
 use exo_sql_core::SchemaStatement;
+use exo_sql_pg::physical_column_type::{ArrayColumnType, EnumColumnType, PhysicalColumnType};
 use exo_sql_pg_connect::DatabaseClient;
 
 use crate::DebugPrintTo;
+use crate::column_default_schema::ColumnDefaultSchema;
+use crate::column_spec::ColumnSpec;
 use crate::issue::WithIssues;
 use crate::op::SchemaOp;
+use crate::table_spec::TableSpec;
 
 const ENUM_VARIANTS_QUERY: &str = "SELECT e.enumlabel AS enum_value FROM pg_type t JOIN pg_enum e ON t.oid = e.enumtypid JOIN pg_catalog.pg_namespace n ON n.oid = t.typnamespace
   WHERE n.nspname = $1 AND t.typname = $2 ORDER BY e.enumsortorder;";
 
+/// Where to place a value added to an existing enum (`ALTER TYPE ... ADD VALUE ... [BEFORE | AFTER] ...`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnumValuePosition {
+    Before(String),
+    After(String),
+}
+
 #[derive(Debug, Clone)]
 pub struct EnumSpec {
     pub name: SchemaObjectName,
@@ -56,19 +67,111 @@ impl EnumSpec {
         })
     }
 
-    pub fn diff<'a>(&'a self, new: &'a Self) -> Vec<SchemaOp<'a>> {
+    /// Compute the operations to migrate this enum to the `new` one.
+    ///
+    /// New values are added next to their neighbors. Postgres cannot drop or reorder enum values,
+    /// so if any value is removed (or the order changes), we recreate the type and convert the
+    /// `columns` that use it. A value that disappears is never assumed to be renamed (which would
+    /// silently remap the existing rows): renames must be explicit (see
+    /// [crate::DatabaseSpec::with_enum_value_renamed]).
+    pub fn diff<'a>(
+        &'a self,
+        new: &'a Self,
+        columns: Vec<(&'a TableSpec, &'a ColumnSpec)>,
+    ) -> Vec<SchemaOp<'a>> {
         let mut changes = Vec::new();
 
         let existing_variants = &self.variants;
         let new_variants = &new.variants;
 
-        if existing_variants != new_variants {
-            changes.push(SchemaOp::CreateEnum { enum_: self });
+        if existing_variants == new_variants {
+            return changes;
+        }
+
+        let removed_values: Vec<String> = existing_variants
+            .iter()
+            .filter(|variant| !new_variants.contains(variant))
+            .cloned()
+            .collect();
+
+        let mut current_variants: Vec<&String> = existing_variants
+            .iter()
+            .filter(|variant| new_variants.contains(variant))
+            .collect();
+
+        let retained_in_new_order: Vec<&String> = new_variants
+            .iter()
+            .filter(|variant| current_variants.contains(variant))
+            .collect();
+
+        if !removed_values.is_empty() || current_variants != retained_in_new_order {
+            changes.push(SchemaOp::RecreateEnum {
+                enum_: new,
+                removed_values,
+                columns,
+            });
+            return changes;
+        }
+
+        for (index, variant) in new_variants.iter().enumerate() {
+            if current_variants.contains(&variant) {
+                continue;
+            }
+
+            // Since we add values in the new order, the preceding value (if any) is already present
+            let position = match index.checked_sub(1) {
+                Some(previous_index) => Some(EnumValuePosition::After(
+                    new_variants[previous_index].clone(),
+                )),
+                None => current_variants
+                    .first()
+                    .map(|first| EnumValuePosition::Before(first.to_string())),
+            };
+
+            changes.push(SchemaOp::AddEnumValue {
+                enum_: new,
+                value: variant.clone(),
+                position,
+            });
+            current_variants.push(variant);
         }
 
         changes
     }
 
+    /// The values of this enum that don't exist in the `new` one along with the values that exist
+    /// only in the `new` one (candidates for renaming the former)
+    pub(crate) fn value_changes<'a>(&'a self, new: &'a Self) -> (Vec<&'a str>, Vec<&'a str>) {
+        let only_in = |variants: &'a [String], other: &[String]| {
+            variants
+                .iter()
+                .filter(|variant| !other.contains(variant))
+                .map(|variant| variant.as_str())
+                .collect::<Vec<_>>()
+        };
+
+        (
+            only_in(&self.variants, &new.variants),
+            only_in(&new.variants, &self.variants),
+        )
+    }
+
+    /// Does the column hold this enum (directly or as an array element)?
+    pub(crate) fn is_used_by(&self, column: &ColumnSpec) -> bool {
+        let is_this_enum = |typ: &dyn PhysicalColumnType| {
+            typ.as_any()
+                .downcast_ref::<EnumColumnType>()
+                .is_some_and(|enum_type| enum_type.enum_name == self.name)
+        };
+
+        is_this_enum(column.typ.as_ref())
+            || column
+                .typ
+                .as_any()
+                .downcast_ref::<ArrayColumnType>()
+                .is_some_and(|array_type| is_this_enum(array_type.typ.as_ref()))
+    }
+
     /// Converts the table specification to SQL statements.
     pub(crate) fn creation_sql(&self) -> SchemaStatement {
         let table_name = self.sql_name();
@@ -76,7 +179,7 @@ impl EnumSpec {
         let variants = self
             .variants
             .iter()
-            .map(|v| format!("'{}'", v))
+            .map(|v| quoted_literal(v))
             .collect::<Vec<String>>()
             .join(", ");
 
@@ -87,6 +190,126 @@ impl EnumSpec {
         }
     }
 
+    pub(crate) fn add_value_sql(
+        &self,
+        value: &str,
+        position: Option<&EnumValuePosition>,
+    ) -> SchemaStatement {
+        let position_part = match position {
+            Some(EnumValuePosition::Before(neighbor)) => {
+                format!(" BEFORE {}", quoted_literal(neighbor))
+            }
+            Some(EnumValuePosition::After(neighbor)) => {
+                format!(" AFTER {}", quoted_literal(neighbor))
+            }
+            None => "".to_string(),
+        };
+
+        SchemaStatement {
+            // A value added in a transaction can't be used until the transaction commits, so the
+            // migration commits these statements before running the rest (see
+            // `SchemaOp::runs_before_transaction`)
+            pre_statements: vec![format!(
+                "ALTER TYPE {} ADD VALUE {}{position_part};",
+                self.sql_name(),
+                quoted_literal(value)
+            )],
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn rename_value_sql(
+        enum_name: &SchemaObjectName,
+        old_value: &str,
+        new_value: &str,
+    ) -> SchemaStatement {
+        SchemaStatement {
+            statement: format!(
+                "ALTER TYPE {} RENAME VALUE {} TO {};",
+                enum_name.sql_name(),
+                quoted_literal(old_value),
+                quoted_literal(new_value)
+            ),
+            ..Default::default()
+        }
+    }
+
+    /// Replace the existing type with a freshly created one (with the same name) and convert the
+    /// columns using it through `text`. Rows holding a removed value make the conversion fail, so
+    /// the user must update them first.
+    pub(crate) fn recreation_sql(
+        &self,
+        removed_values: &[String],
+        columns: &[(&TableSpec, &ColumnSpec)],
+    ) -> SchemaStatement {
+        let previous_name = SchemaObjectName {
+            name: format!("{}_previous", self.name.name),
+            schema: self.name.schema.clone(),
+        };
+
+        let mut statements = vec![
+            format!(
+                "ALTER TYPE {} RENAME TO \"{}\";",
+                self.sql_name(),
+                previous_name.name
+            ),
+            self.creation_sql().statement,
+        ];
+
+        for (table, column) in columns {
+            let cast = if column.typ.as_any().is::<ArrayColumnType>() {
+                format!("text[]::{}[]", self.sql_name())
+            } else {
+                format!("text::{}", self.sql_name())
+            };
+            let typ = if column.typ.as_any().is::<ArrayColumnType>() {
+                format!("{}[]", self.sql_name())
+            } else {
+                self.sql_name()
+            };
+
+            let default_value = column
+                .default_value
+                .as_ref()
+                .and_then(|default_value| default_value.to_schema());
+
+            if default_value.is_some() {
+                statements.push(format!(
+                    "ALTER TABLE {} ALTER COLUMN \"{}\" DROP DEFAULT;",
+                    table.sql_name(),
+                    column.name
+                ));
+            }
+
+            statements.push(format!(
+                "ALTER TABLE {} ALTER COLUMN \"{}\" TYPE {typ} USING \"{}\"::{cast};",
+                table.sql_name(),
+                column.name,
+                column.name
+            ));
+
+            // A default set to a removed value would fail, so let the column diff set the new default
+            if let Some(default_value) = default_value
+                && !removed_values
+                    .iter()
+                    .any(|removed_value| default_value == format!("'{removed_value}'"))
+            {
+                statements.push(format!(
+                    "ALTER TABLE {} ALTER COLUMN \"{}\" SET DEFAULT {default_value};",
+                    table.sql_name(),
+                    column.name
+                ));
+            }
+        }
+
+        statements.push(format!("DROP TYPE {};", previous_name.sql_name()));
+
+        SchemaStatement {
+            statement: statements.join("\n"),
+            ..Default::default()
+        }
+    }
+
     pub(crate) fn deletion_sql(&self) -> SchemaStatement {
         SchemaStatement {
             statement: "".to_string(),
@@ -96,6 +319,11 @@ impl EnumSpec {
     }
 }
 
+/// An enum value as a string literal (with any single quotes doubled)
+fn quoted_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
 impl DebugPrintTo for EnumSpec {
     fn debug_print_to<W: std::io::Write>(
         &self,
@@ -118,3 +346,86 @@ impl DebugPrintTo for EnumSpec {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn priority(variants: &[&str]) -> EnumSpec {
+        EnumSpec::new(
+            SchemaObjectName::new("priority", None),
+            variants.iter().map(|variant| variant.to_string()).collect(),
+        )
+    }
+
+    #[test]
+    fn replaced_value_is_not_renamed() {
+        let old = priority(&["LOW", "MEDIUM", "HIGH"]);
+        let new = priority(&["LOW", "NORMAL", "HIGH"]);
+
+        let changes = old.diff(&new, vec![]);
+
+        assert_eq!(changes.len(), 1);
+        assert!(matches!(
+            &changes[0],
+            SchemaOp::RecreateEnum { removed_values, .. } if removed_values == &["MEDIUM"]
+        ));
+        assert!(changes[0].is_destructive());
+    }
+
+    #[test]
+    fn added_values_are_placed_next_to_neighbors() {
+        let old = priority(&["LOW", "HIGH"]);
+        let new = priority(&["LOWEST", "LOW", "MEDIUM", "HIGH"]);
+
+        let added: Vec<_> = old
+            .diff(&new, vec![])
+            .into_iter()
+            .map(|change| match change {
+                SchemaOp::AddEnumValue {
+                    value, position, ..
+                } => (value, position),
+                other => panic!("Unexpected change: {other:?}"),
+            })
+            .collect();
+
+        assert_eq!(
+            added,
+            vec![
+                (
+                    "LOWEST".to_string(),
+                    Some(EnumValuePosition::Before("LOW".to_string()))
+                ),
+                (
+                    "MEDIUM".to_string(),
+                    Some(EnumValuePosition::After("LOW".to_string()))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn values_with_quotes_are_escaped() {
+        let old = priority(&["LOW", "HIGH"]);
+        let new = priority(&["LOW", "Rock 'n' Roll", "HIGH"]);
+
+        assert_eq!(
+            new.creation_sql().statement,
+            "CREATE TYPE \"priority\" AS ENUM ('LOW', 'Rock ''n'' Roll', 'HIGH');"
+        );
+
+        assert_eq!(
+            new.add_value_sql(
+                "Rock 'n' Roll",
+                Some(&EnumValuePosition::After("LOW".to_string()))
+            )
+            .pre_statements,
+            vec!["ALTER TYPE \"priority\" ADD VALUE 'Rock ''n'' Roll' AFTER 'LOW';"]
+        );
+
+        assert_eq!(
+            EnumSpec::rename_value_sql(&old.name, "HIGH", "Top 'o' the list").statement,
+            "ALTER TYPE \"priority\" RENAME VALUE 'HIGH' TO 'Top ''o'' the list';"
+        );
+    }
+}
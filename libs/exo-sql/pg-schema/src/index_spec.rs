@@ -7,14 +7,13 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
-use std::collections::HashSet;
-
-use exo_sql_core::{DatabaseError, SchemaObjectName};
+use exo_sql_core::{DatabaseError, IndexColumn, IndexTarget, Ordering, SchemaObjectName};
 use exo_sql_pg::VectorDistanceFunction;
 use exo_sql_pg_connect::DatabaseClient;
 
 use crate::{
-    DebugPrintTo, column_spec::ColumnSpec, issue::WithIssues, op::SchemaOp, table_spec::TableSpec,
+    DebugPrintTo, column_spec::ColumnSpec, issue::WithIssues, op::SchemaOp,
+    sql_expression::normalize_expression, table_spec::TableSpec,
 };
 
 // Re-export from pg where the canonical definitions now live
@@ -24,25 +23,55 @@ pub use exo_sql_pg::{HNWSParams, IndexKind};
 #[derive(Debug, Clone)]
 pub struct IndexSpec {
     pub name: String,
-    pub columns: HashSet<String>,
+    pub columns: Vec<IndexColumn>,
+    pub predicate: Option<String>,
     pub index_kind: IndexKind,
 }
 
+// Invalid indexes (left behind by a failed `CREATE INDEX CONCURRENTLY`) are skipped, so that a
+// later migration creates them again.
+//
+// Index columns are reported by `pg_get_indexdef` (with the column number) in the index order, so
+// we get the column name (quoted, if necessary) or the expression for each. We only consider key
+// columns (and not the ones in an `INCLUDE` clause).
 const INDICES_QUERY: &str = r#"
 SELECT
    schema.nspname AS schema,
    index_info.indrelid :: regclass :: text AS table,
-   array_agg(attribute.attname) as column_names,
    index_info.indisunique AS is_unique,
    cls.relname AS index_name,
    access_method.amname AS index_method,
-   opc.operator_classes as index_opclasses
+   opc.operator_classes as index_opclasses,
+   ARRAY (
+      SELECT
+         pg_get_indexdef(index_info.indexrelid, key.position + 1, true)
+      FROM
+         generate_series(0, index_info.indnkeyatts - 1) AS key(position)
+      ORDER BY
+         key.position
+   ) AS column_definitions,
+   ARRAY (
+      SELECT
+         index_info.indkey[key.position] = 0
+      FROM
+         generate_series(0, index_info.indnkeyatts - 1) AS key(position)
+      ORDER BY
+         key.position
+   ) AS column_is_expressions,
+   ARRAY (
+      SELECT
+         (index_info.indoption[key.position] & 1) <> 0
+      FROM
+         generate_series(0, index_info.indnkeyatts - 1) AS key(position)
+      ORDER BY
+         key.position
+   ) AS column_is_descendings,
+   pg_get_expr(index_info.indpred, index_info.indrelid, true) AS predicate
 FROM
    pg_catalog.pg_namespace schema
    JOIN pg_catalog.pg_class cls ON cls.relnamespace = schema.oid
    JOIN pg_catalog.pg_index index_info ON index_info.indexrelid = cls.oid
    JOIN pg_catalog.pg_am access_method ON access_method.oid = cls.relam
-   JOIN pg_attribute attribute ON attribute.attrelid = index_info.indrelid
    CROSS JOIN LATERAL (
       SELECT
          ARRAY (
@@ -56,29 +85,56 @@ FROM
 WHERE
    index_info.indrelid :: regclass :: text = $1
    AND cls.relkind = 'i'
-   AND attribute.attnum = ANY(index_info.indkey)
-GROUP BY
-   schema.nspname,
-   index_info.indrelid,
-   cls.relname,
-   access_method.amname,
-   opc.operator_classes,
-   index_info.indisunique;
+   AND index_info.indisvalid;
 "#;
 
 impl IndexSpec {
-    pub fn new(name: String, columns: HashSet<String>, index_kind: IndexKind) -> Self {
+    pub fn new(
+        name: String,
+        columns: Vec<IndexColumn>,
+        predicate: Option<String>,
+        index_kind: IndexKind,
+    ) -> Self {
         Self {
             name,
             columns,
+            predicate,
             index_kind,
         }
     }
 
+    pub fn column_names(&self) -> impl Iterator<Item = &str> {
+        self.columns
+            .iter()
+            .filter_map(|column| column.column_name())
+    }
+
     // Does the other index serve the same purpose as this index?
     // Effectively, match ignoring the index name
     pub fn effectively_eq(&self, other: &IndexSpec) -> bool {
-        self.columns == other.columns && self.index_kind == other.index_kind
+        self.columns.len() == other.columns.len()
+            && self
+                .columns
+                .iter()
+                .zip(other.columns.iter())
+                .all(|(self_column, other_column)| {
+                    self_column.ordering == other_column.ordering
+                        && match (&self_column.target, &other_column.target) {
+                            (IndexTarget::Column(self_name), IndexTarget::Column(other_name)) => {
+                                self_name == other_name
+                            }
+                            (
+                                IndexTarget::Expression(self_expr),
+                                IndexTarget::Expression(other_expr),
+                            ) => {
+                                normalize_expression(self_expr) == normalize_expression(other_expr)
+                            }
+                            _ => false,
+                        }
+                })
+            && self.predicate.as_deref().map(normalize_expression)
+                == other.predicate.as_deref().map(normalize_expression)
+            && self.index_kind == other.index_kind
     }
 
     pub async fn from_live_db(
@@ -92,19 +148,40 @@ impl IndexSpec {
             .map_err(DatabaseError::driver)?
             .iter()
             .flat_map(|row| {
-                let column_names = row
-                    .get::<_, Vec<String>>("column_names")
+                let column_definitions: Vec<String> = row.get("column_definitions");
+                let column_is_expressions: Vec<bool> = row.get("column_is_expressions");
+                let column_is_descendings: Vec<bool> = row.get("column_is_descendings");
+
+                let index_columns = column_definitions
                     .into_iter()
-                    .collect::<HashSet<_>>();
+                    .zip(column_is_expressions)
+                    .zip(column_is_descendings)
+                    .map(|((definition, is_expression), is_descending)| IndexColumn {
+                        target: if is_expression {
+                            IndexTarget::Expression(definition)
+                        } else {
+                            IndexTarget::Column(unquote_identifier(&definition))
+                        },
+                        ordering: if is_descending {
+                            Ordering::Desc
+                        } else {
+                            Ordering::Asc
+                        },
+                    })
+                    .collect::<Vec<_>>();
+                let predicate: Option<String> = row.get("predicate");
                 let unique: bool = row.get("is_unique");
 
                 // If the columns consists only of primary key columns, then we should not
                 // explicitly create an index for it (the database will create it automatically due
                 // to the pk constraint)
                 if unique
-                    || column_names
-                        .iter()
-                        .all(|c| columns.iter().any(|col| col.name == *c && col.is_pk))
+                    || (predicate.is_none()
+                        && index_columns.iter().all(|index_column| {
+                            index_column.column_name().is_some_and(|name| {
+                                columns.iter().any(|col| col.name == name && col.is_pk)
+                            })
+                        }))
                 {
                     Ok::<_, DatabaseError>(None)
                 } else {
@@ -125,7 +202,8 @@ impl IndexSpec {
                         }?;
                     Ok(Some(IndexSpec::new(
                         row.get("index_name"),
-                        column_names,
+                        index_columns,
+                        predicate,
                         index_kind,
                     )))
                 }
@@ -146,10 +224,7 @@ impl IndexSpec {
         other_table: &'a TableSpec,
     ) -> Vec<SchemaOp<'a>> {
         // Ignore the index name, since other information is sufficient to define the index (the name is auxiliary to work with multiple columns)
-        if self.columns == other.columns
-            && self_table.name == other_table.name
-            && self.index_kind == other.index_kind
-        {
+        if self_table.name == other_table.name && self.effectively_eq(other) {
             return vec![];
         }
 
@@ -166,15 +241,29 @@ impl IndexSpec {
     }
 
     pub fn creation_sql(&self, table_name: &SchemaObjectName) -> String {
-        let sorted_columns = {
-            let mut columns = self.columns.iter().collect::<Vec<_>>();
-            columns.sort();
-            columns
-        };
+        self.creation_sql_with(table_name, false)
+    }
+
+    /// `CREATE INDEX CONCURRENTLY`, which doesn't block writes to the table while building the
+    /// index (but can't run inside a transaction)
+    pub fn concurrent_creation_sql(&self, table_name: &SchemaObjectName) -> String {
+        self.creation_sql_with(table_name, true)
+    }
 
-        let columns_str = sorted_columns
+    fn creation_sql_with(&self, table_name: &SchemaObjectName, concurrently: bool) -> String {
+        let columns_str = self
+            .columns
             .iter()
-            .map(|c| format!("\"{c}\""))
+            .map(|column| {
+                let target_str = match &column.target {
+                    IndexTarget::Column(name) => format!("\"{name}\""),
+                    IndexTarget::Expression(expression) => format!("({expression})"),
+                };
+                match column.ordering {
+                    Ordering::Asc => target_str,
+                    Ordering::Desc => format!("{target_str} DESC"),
+                }
+            })
             .collect::<Vec<_>>()
             .join(", ");
 
@@ -203,14 +292,32 @@ impl IndexSpec {
             _ => format!("({columns_str})"),
         };
 
+        let predicate_str = self
+            .predicate
+            .as_ref()
+            .map(|predicate| format!(" WHERE {predicate}"))
+            .unwrap_or_default();
+
         format!(
-            "CREATE INDEX \"{index_name}\" ON {table_name} {index_spec_str};",
+            "CREATE INDEX {concurrently_str}\"{index_name}\" ON {table_name} {index_spec_str}{predicate_str};",
+            concurrently_str = if concurrently { "CONCURRENTLY " } else { "" },
             index_name = self.name,
             table_name = table_name.sql_name(),
         )
     }
 }
 
+/// `pg_get_indexdef` quotes column names only when necessary (for example, for `"userId"`)
+fn unquote_identifier(identifier: &str) -> String {
+    match identifier
+        .strip_prefix('"')
+        .and_then(|identifier| identifier.strip_suffix('"'))
+    {
+        Some(quoted) => quoted.replace("\"\"", "\""),
+        None => identifier.to_string(),
+    }
+}
+
 impl DebugPrintTo for IndexSpec {
     fn debug_print_to<W: std::io::Write>(
         &self,
@@ -218,9 +325,21 @@ impl DebugPrintTo for IndexSpec {
         indent: usize,
     ) -> std::io::Result<()> {
         let indent_str = " ".repeat(indent);
-        let mut columns_sorted: Vec<_> = self.columns.iter().map(String::as_str).collect();
-        columns_sorted.sort_unstable();
-        let columns_str = columns_sorted.join(", ");
+        let columns_str = self
+            .columns
+            .iter()
+            .map(|column| {
+                let target_str = match &column.target {
+                    IndexTarget::Column(name) => name.clone(),
+                    IndexTarget::Expression(expression) => format!("({expression})"),
+                };
+                match column.ordering {
+                    Ordering::Asc => target_str,
+                    Ordering::Desc => format!("{target_str} DESC"),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
 
         let index_type = match &self.index_kind {
             IndexKind::HNWS {
@@ -229,10 +348,142 @@ impl DebugPrintTo for IndexSpec {
             IndexKind::DatabaseDefault => "DEFAULT".to_string(),
         };
 
+        let predicate_str = self
+            .predicate
+            .as_ref()
+            .map(|predicate| format!(" WHERE {predicate}"))
+            .unwrap_or_default();
+
         writeln!(
             writer,
-            "{}- ({}, {}, [{}])",
-            indent_str, self.name, index_type, columns_str
+            "{}- ({}, {}, [{}]){}",
+            indent_str, self.name, index_type, columns_str, predicate_str
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(indices: Vec<IndexSpec>) -> TableSpec {
+        TableSpec::new(
+            SchemaObjectName::new("people", None),
+            vec![],
+            indices,
+            vec![],
+            vec![],
+            true,
+        )
+    }
+
+    fn index(columns: Vec<IndexColumn>, predicate: Option<&str>) -> IndexSpec {
+        IndexSpec::new(
+            "people_idx".to_string(),
+            columns,
+            predicate.map(|predicate| predicate.to_string()),
+            IndexKind::DatabaseDefault,
         )
     }
+
+    fn expression(expression: &str) -> IndexColumn {
+        IndexColumn {
+            target: IndexTarget::Expression(expression.to_string()),
+            ordering: Ordering::Asc,
+        }
+    }
+
+    fn is_recreated(old: IndexSpec, new: IndexSpec) -> bool {
+        let old_table = table(vec![old]);
+        let new_table = table(vec![new]);
+
+        let changes = old_table.diff(&new_table);
+        assert!(changes.len() == 2 || changes.is_empty(), "{changes:?}");
+        changes
+            .iter()
+            .any(|change| matches!(change, SchemaOp::DeleteIndex { .. }))
+            && changes
+                .iter()
+                .any(|change| matches!(change, SchemaOp::CreateIndex { .. }))
+    }
+
+    #[test]
+    fn partial_index_matches_live_db_predicate() {
+        // As written in the model and as reported by `pg_get_expr`
+        assert!(!is_recreated(
+            index(
+                vec![IndexColumn::column("email")],
+                Some("deleted_at IS NULL AND status = 'active'")
+            ),
+            index(
+                vec![IndexColumn::column("email")],
+                Some("((deleted_at IS NULL) AND (status = 'active'::text))")
+            ),
+        ));
+    }
+
+    #[test]
+    fn partial_index_with_changed_predicate_is_recreated() {
+        let email = || vec![IndexColumn::column("email")];
+
+        for (old, new) in [
+            ("deleted_at IS NULL", "deleted_at IS NOT NULL"),
+            ("(a OR b) AND c", "a OR (b AND c)"),
+            ("status = 'active'", "status = 'Active'"),
+            ("score > 10", "score >= 10"),
+        ] {
+            assert!(
+                is_recreated(index(email(), Some(old)), index(email(), Some(new))),
+                "{old} -> {new}"
+            );
+        }
+
+        assert!(is_recreated(
+            index(email(), None),
+            index(email(), Some("deleted_at IS NULL"))
+        ));
+        assert!(is_recreated(
+            index(email(), Some("deleted_at IS NULL")),
+            index(email(), None)
+        ));
+    }
+
+    #[test]
+    fn expression_index_matches_live_db_expression() {
+        assert!(!is_recreated(
+            index(vec![expression("lower(email)")], None),
+            index(vec![expression("lower((email)::text)")], None),
+        ));
+        assert!(!is_recreated(
+            index(vec![expression("LOWER(\"email\")")], None),
+            index(vec![expression("lower(email)")], None),
+        ));
+    }
+
+    #[test]
+    fn expression_index_with_changed_expression_is_recreated() {
+        assert!(is_recreated(
+            index(vec![expression("lower(email)")], None),
+            index(vec![expression("upper(email)")], None),
+        ));
+        assert!(is_recreated(
+            index(vec![expression("(a + b) * c")], None),
+            index(vec![expression("a + b * c")], None),
+        ));
+        // An expression on a column isn't the same as an index on the column
+        assert!(is_recreated(
+            index(vec![expression("lower(email)")], None),
+            index(vec![IndexColumn::column("email")], None),
+        ));
+        assert!(is_recreated(
+            index(vec![expression("lower(email)")], None),
+            index(
+                vec![IndexColumn {
+                    target: IndexTarget::Expression("lower(email)".to_string()),
+                    ordering: Ordering::Desc,
+                }],
+                None
+            ),
+        ));
+    }
 }
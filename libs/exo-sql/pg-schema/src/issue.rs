@@ -10,10 +10,13 @@
 use core::fmt;
 use std::fmt::{Display, Formatter};
 
+use serde::Serialize;
+
 /// An issue that a user may encounter when dealing with the database schema.
 ///
-/// Used in `model import` command.
-#[derive(Debug)]
+/// Used in `model import` and `schema migrate` commands.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "lowercase")]
 pub enum Issue {
     Warning(String),
     Hint(String),
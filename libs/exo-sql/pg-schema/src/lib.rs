@@ -7,6 +7,7 @@
 // the Business Source License, use of this software will be governed
 // by the Apache License, Version 2.0.
 
+mod check_spec;
 mod column_spec;
 mod database_spec;
 mod enum_spec;
@@ -22,15 +23,18 @@ mod trigger_spec;
 mod column_default_schema;
 mod column_type_schema;
 mod constraint;
+mod sql_expression;
 
+pub use check_spec::{CheckSpec, SimpleCheck};
 pub use column_spec::{ColumnReferenceSpec, ColumnSpec, physical_column_type_from_string};
 pub use database_spec::DatabaseSpec;
 pub use enum_spec::EnumSpec;
-pub use issue::WithIssues;
+pub use issue::{Issue, WithIssues};
 pub use migration::{
-    InteractionError, Migration, MigrationError, MigrationInteraction, MigrationStatement,
-    PredefinedMigrationInteraction, TableAction, VerificationErrors, migrate_interactively,
-    wipe_database,
+    ColumnAction, DataMigration, EnumValueAction, InteractionError, LockMode, Migration,
+    MigrationError, MigrationInteraction, MigrationMode, MigrationOperation, MigrationStatement,
+    PredefinedMigrationInteraction, StagedMigration, TableAction, TableLock, VerificationErrors,
+    migrate_interactively, wipe_database,
 };
 pub use spec::{MigrationScope, MigrationScopeMatches, NameMatching};
 pub use table_spec::TableSpec;
@@ -9,13 +9,14 @@
 
 use std::fmt::Display;
 
-use exo_sql_core::{Database, DatabaseError};
+use exo_sql_core::{Database, DatabaseError, SchemaStatement};
 use exo_sql_pg_connect::DatabaseClient;
 use serde::Serialize;
 
 use crate::{
     database_spec::DatabaseSpec,
-    issue::WithIssues,
+    issue::{Issue, WithIssues},
+    migration::{MigrationMode, TableLock},
     op::SchemaOp,
     spec::{MigrationScope, MigrationScopeMatches, diff},
 };
@@ -23,12 +24,56 @@ use crate::{
 #[derive(Debug, Serialize)]
 pub struct Migration {
     pub statements: Vec<MigrationStatement>,
+    /// The operations that produced the statements
+    pub operations: Vec<MigrationOperation>,
+    /// Issues (such as removed enum values) the user should review before applying the migration
+    pub issues: Vec<Issue>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct MigrationStatement {
     pub statement: String,
     pub is_destructive: bool,
+    /// Locks on existing tables that block reads or writes while the statement runs
+    pub locks: Vec<TableLock>,
+    /// Must the statement run outside a transaction (such as `CREATE INDEX CONCURRENTLY`)?
+    pub concurrent: bool,
+    /// Must the statement be committed before the rest of the migration runs (such as `ALTER TYPE
+    /// ... ADD VALUE`, whose value can't be used in the same transaction)?
+    pub before_transaction: bool,
+}
+
+/// A change to the database schema (for machine-readable migration plans)
+#[derive(Debug, Serialize)]
+pub struct MigrationOperation {
+    /// The kind of change (such as `delete_column`)
+    pub kind: &'static str,
+    /// The objects the change affects (such as `"concerts"."title"`)
+    pub objects: Vec<String>,
+    pub is_destructive: bool,
+    /// The SQL statements that perform the change
+    pub statements: Vec<String>,
+    /// How the database doesn't match the model (if the difference makes the model incompatible)
+    pub incompatibility: Option<String>,
+}
+
+impl MigrationOperation {
+    fn new(op: &SchemaOp, is_destructive: bool, statement: &SchemaStatement) -> Self {
+        Self {
+            kind: op.kind(),
+            objects: op.affected_objects(),
+            is_destructive,
+            statements: statement
+                .pre_statements
+                .iter()
+                .chain(std::iter::once(&statement.statement))
+                .chain(statement.post_statements.iter())
+                .filter(|sql| !sql.trim().is_empty())
+                .cloned()
+                .collect(),
+            incompatibility: op.error_string(),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -85,39 +130,93 @@ impl Migration {
         new_schema_spec: &DatabaseSpec,
         scope: &MigrationScope,
     ) -> Self {
-        let diffs = diff(old_schema_spec, new_schema_spec, scope);
+        Self::from_schemas_with_mode(
+            old_schema_spec,
+            new_schema_spec,
+            scope,
+            MigrationMode::Standard,
+        )
+    }
+
+    pub fn from_schemas_with_mode(
+        old_schema_spec: &DatabaseSpec,
+        new_schema_spec: &DatabaseSpec,
+        scope: &MigrationScope,
+        mode: MigrationMode,
+    ) -> Self {
+        let scope_matches = match scope {
+            MigrationScope::Specified(scope) => scope,
+            MigrationScope::FromNewSpec => {
+                &MigrationScopeMatches::from_specs_schemas(&[new_schema_spec])
+            }
+        };
 
-        let diffs = diffs
+        // Rename columns as hinted by the model before computing the rest of the differences (which
+        // would otherwise delete the old column and create a new one)
+        let mut old_schema_spec = old_schema_spec.clone();
+        let rename_ops = old_schema_spec.with_hinted_column_renames(new_schema_spec, scope_matches);
+
+        let diffs = diff(&old_schema_spec, new_schema_spec, scope);
+
+        let diffs = rename_ops
             .into_iter()
+            .chain(diffs)
             .map(|diff| (diff, None))
             .collect::<Vec<_>>();
 
-        Self::from_diffs(&diffs)
+        Self::from_diffs_with_mode(&diffs, mode)
     }
 
     pub fn from_diffs(diffs: &[(SchemaOp, Option<bool>)]) -> Self {
+        Self::from_diffs_with_mode(diffs, MigrationMode::Standard)
+    }
+
+    pub fn from_diffs_with_mode(diffs: &[(SchemaOp, Option<bool>)], mode: MigrationMode) -> Self {
         let mut pre_statements = vec![];
         let mut statements = vec![];
         let mut post_statements = vec![];
+        let mut operations = vec![];
+        let mut issues = vec![];
 
         for (diff, is_destructive_override) in diffs.iter() {
             let is_destructive = is_destructive_override.unwrap_or(diff.is_destructive());
 
-            let statement = diff.to_sql();
+            issues.extend(diff.issue());
+
+            let statement = diff.to_sql_for_mode(mode);
+            let concurrent = diff.runs_concurrently(mode);
+            let before_transaction = diff.runs_before_transaction();
+
+            operations.push(MigrationOperation::new(diff, is_destructive, &statement));
+
+            // Attribute the locks to the main statement (or the first post statement for operations
+            // such as adding a foreign key, which only have post statements)
+            let mut locks = Some(diff.locks(mode));
+            let mut new_statement = |sql: String, take_locks: bool| MigrationStatement {
+                statement: sql,
+                is_destructive,
+                locks: if take_locks {
+                    locks.take().unwrap_or_default()
+                } else {
+                    vec![]
+                },
+                concurrent,
+                before_transaction,
+            };
 
             for constraint in statement.pre_statements.into_iter() {
                 if !constraint.trim().is_empty() {
-                    pre_statements.push(MigrationStatement::new(constraint, is_destructive));
+                    pre_statements.push(new_statement(constraint, false));
                 }
             }
 
             if !statement.statement.trim().is_empty() {
-                statements.push(MigrationStatement::new(statement.statement, is_destructive));
+                statements.push(new_statement(statement.statement, true));
             }
 
             for constraint in statement.post_statements.into_iter() {
                 if !constraint.trim().is_empty() {
-                    post_statements.push(MigrationStatement::new(constraint, is_destructive));
+                    post_statements.push(new_statement(constraint, true));
                 }
             }
         }
@@ -127,6 +226,8 @@ impl Migration {
 
         Migration {
             statements: pre_statements,
+            operations,
+            issues,
         }
     }
 
@@ -149,6 +250,7 @@ impl Migration {
         client: &DatabaseClient,
         database: &Database,
         scope: &MigrationScope,
+        mode: MigrationMode,
     ) -> Result<Self, DatabaseError> {
         let new_spec = DatabaseSpec::from_database(database);
 
@@ -158,7 +260,12 @@ impl Migration {
             eprintln!("{issue}");
         }
 
-        Ok(Migration::from_schemas(&old_schema.value, &new_spec, scope))
+        Ok(Migration::from_schemas_with_mode(
+            &old_schema.value,
+            &new_spec,
+            scope,
+            mode,
+        ))
     }
 
     pub fn has_destructive_changes(&self) -> bool {
@@ -167,11 +274,44 @@ impl Migration {
             .any(|statement| statement.is_destructive)
     }
 
+    /// Statements that take locks blocking reads or writes of existing tables
+    pub fn locking_statements(&self) -> impl Iterator<Item = &MigrationStatement> {
+        self.statements
+            .iter()
+            .filter(|statement| !statement.locks.is_empty())
+    }
+
     pub async fn verify(
         client: &DatabaseClient,
         database: &Database,
         scope: &MigrationScope,
     ) -> Result<(), VerificationErrors> {
+        let incompatibilities = Self::incompatibilities(client, database, scope).await?;
+
+        for issue in &incompatibilities.issues {
+            eprintln!("{issue}");
+        }
+
+        let errors: Vec<_> = incompatibilities
+            .value
+            .into_iter()
+            .flat_map(|operation| operation.incompatibility)
+            .collect();
+
+        if !errors.is_empty() {
+            Err(VerificationErrors::ModelNotCompatible(errors))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// The differences between the database and the model that make the model incompatible with
+    /// the database (along with issues found while reading the database schema)
+    pub async fn incompatibilities(
+        client: &DatabaseClient,
+        database: &Database,
+        scope: &MigrationScope,
+    ) -> Result<WithIssues<Vec<MigrationOperation>>, DatabaseError> {
         let new_schema = DatabaseSpec::from_database(database);
 
         let scope_matches = match scope {
@@ -183,19 +323,28 @@ impl Migration {
 
         let old_schema = extract_db_schema(client, scope_matches).await?;
 
-        for issue in &old_schema.issues {
-            eprintln!("{issue}");
-        }
-
         let diff = diff(&old_schema.value, &new_schema, scope);
 
-        let errors: Vec<_> = diff.iter().flat_map(|op| op.error_string()).collect();
+        let operations = diff
+            .iter()
+            .filter(|op| op.error_string().is_some())
+            .map(|op| MigrationOperation::new(op, op.is_destructive(), &op.to_sql()))
+            .collect();
+
+        Ok(WithIssues {
+            value: operations,
+            issues: old_schema.issues,
+        })
+    }
 
-        if !errors.is_empty() {
-            Err(VerificationErrors::ModelNotCompatible(errors))
-        } else {
-            Ok(())
-        }
+    /// Check the whole migration for destructive statements (if they aren't allowed) before any
+    /// statement runs. Statements that run outside the migration's transaction (see
+    /// [`MigrationStatement::before_transaction`] and [`MigrationStatement::concurrent`]) can't be
+    /// rolled back, so finding a destructive statement midway would leave a partial migration.
+    pub fn check_allowed(&self, allow_destructive_changes: bool) -> Result<(), MigrationError> {
+        self.statements
+            .iter()
+            .try_for_each(|statement| statement.check_allowed(allow_destructive_changes))
     }
 
     pub async fn apply(
@@ -203,24 +352,62 @@ impl Migration {
         client: &mut DatabaseClient,
         allow_destructive_changes: bool,
     ) -> Result<(), MigrationError> {
+        self.check_allowed(allow_destructive_changes)?;
+
+        self.execute_before_transaction(client, allow_destructive_changes)
+            .await?;
+
         let transaction = client.transaction().await?;
-        for MigrationStatement {
-            statement,
-            is_destructive,
-        } in self.statements.iter()
-        {
-            if !is_destructive || allow_destructive_changes {
-                transaction.execute(statement, &[]).await?;
-            } else {
-                return Err(MigrationError::Generic(format!(
-                    "Destructive change detected: {}",
-                    statement
-                )));
-            }
+        for migration_statement in self.transactional_statements() {
+            // A statement may consist of multiple SQL statements (for example, when recreating an enum)
+            transaction
+                .batch_execute(&migration_statement.statement)
+                .await?;
         }
         Ok(transaction.commit().await?)
     }
 
+    /// Execute (and commit) the statements that must be committed before the rest of the migration
+    /// runs (see [`MigrationStatement::before_transaction`]). Such statements only add to the
+    /// schema (and the next migration skips them), so they may stay even if the rest fails.
+    pub async fn execute_before_transaction(
+        &self,
+        client: &DatabaseClient,
+        allow_destructive_changes: bool,
+    ) -> Result<(), MigrationError> {
+        self.check_allowed(allow_destructive_changes)?;
+
+        for migration_statement in self
+            .statements
+            .iter()
+            .filter(|statement| statement.before_transaction)
+        {
+            client.batch_execute(&migration_statement.statement).await?;
+        }
+        Ok(())
+    }
+
+    /// Execute the statements on a client on which the caller has begun a transaction (and will
+    /// end it). Used to run parts of a [`super::StagedMigration`] in the same transaction. The
+    /// caller must run [`Migration::execute_before_transaction`] before beginning the transaction.
+    pub async fn execute_in_transaction(
+        &self,
+        client: &DatabaseClient,
+        allow_destructive_changes: bool,
+    ) -> Result<(), MigrationError> {
+        for migration_statement in self.transactional_statements() {
+            migration_statement.check_allowed(allow_destructive_changes)?;
+            client.batch_execute(&migration_statement.statement).await?;
+        }
+        Ok(())
+    }
+
+    fn transactional_statements(&self) -> impl Iterator<Item = &MigrationStatement> {
+        self.statements
+            .iter()
+            .filter(|statement| !statement.before_transaction)
+    }
+
     pub fn write(
         &self,
         writer: &mut dyn std::io::Write,
@@ -229,12 +416,18 @@ impl Migration {
         for MigrationStatement {
             statement,
             is_destructive,
+            ..
         } in self.statements.iter()
         {
             if *is_destructive && !allow_destructive_changes {
-                write!(writer, "-- ")?;
+                // Comment out every line, since a statement may span multiple lines
+                for line in statement.lines() {
+                    writeln!(writer, "-- {line}")?;
+                }
+                writeln!(writer)?;
+            } else {
+                writeln!(writer, "{statement}\n")?;
             }
-            writeln!(writer, "{statement}\n")?;
         }
         Ok(())
     }
@@ -245,6 +438,20 @@ impl MigrationStatement {
         Self {
             statement,
             is_destructive,
+            locks: vec![],
+            concurrent: false,
+            before_transaction: false,
+        }
+    }
+
+    fn check_allowed(&self, allow_destructive_changes: bool) -> Result<(), MigrationError> {
+        if self.is_destructive && !allow_destructive_changes {
+            Err(MigrationError::Generic(format!(
+                "Destructive change detected: {}",
+                self.statement
+            )))
+        } else {
+            Ok(())
         }
     }
 }
@@ -276,3 +483,167 @@ pub async fn wipe_database(client: &mut DatabaseClient) -> Result<(), DatabaseEr
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use exo_sql_core::{IndexColumn, SchemaObjectName};
+    use exo_sql_pg::IndexKind;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{index_spec::IndexSpec, table_spec::TableSpec};
+
+    fn table(name: &str) -> TableSpec {
+        TableSpec::new(
+            SchemaObjectName::new(name, None),
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            true,
+        )
+    }
+
+    #[test]
+    fn online_migration_marks_concurrent_statements() {
+        let events = table("events");
+        let index = IndexSpec::new(
+            "events_title_idx".to_string(),
+            vec![IndexColumn::column("title")],
+            None,
+            IndexKind::DatabaseDefault,
+        );
+        let diffs = vec![(
+            SchemaOp::CreateIndex {
+                table: &events,
+                index: &index,
+            },
+            None,
+        )];
+
+        let standard = Migration::from_diffs_with_mode(&diffs, MigrationMode::Standard);
+        assert_eq!(standard.statements.len(), 1);
+        assert!(!standard.statements[0].concurrent);
+        assert_eq!(standard.locking_statements().count(), 1);
+
+        let online = Migration::from_diffs_with_mode(&diffs, MigrationMode::Online);
+        let statements = online
+            .statements
+            .iter()
+            .map(|statement| (statement.statement.as_str(), statement.concurrent))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statements,
+            vec![
+                (
+                    "DROP INDEX CONCURRENTLY IF EXISTS \"events_title_idx\";",
+                    true
+                ),
+                (
+                    "CREATE INDEX CONCURRENTLY \"events_title_idx\" ON \"events\" (\"title\");",
+                    true
+                ),
+            ]
+        );
+        assert_eq!(online.locking_statements().count(), 0);
+    }
+
+    #[test]
+    fn destructive_statements_are_rejected_before_any_statement_runs() {
+        let events = table("events");
+        let venues = table("venues");
+        let index = IndexSpec::new(
+            "events_title_idx".to_string(),
+            vec![IndexColumn::column("title")],
+            None,
+            IndexKind::DatabaseDefault,
+        );
+
+        // The destructive statement comes after a concurrent one (which would commit on its own)
+        let migration = Migration::from_diffs_with_mode(
+            &[
+                (
+                    SchemaOp::CreateIndex {
+                        table: &events,
+                        index: &index,
+                    },
+                    None,
+                ),
+                (SchemaOp::DeleteTable { table: &venues }, None),
+            ],
+            MigrationMode::Online,
+        );
+
+        assert!(migration.has_destructive_changes());
+        assert!(matches!(
+            migration.check_allowed(false),
+            Err(MigrationError::Generic(message)) if message.contains("DROP TABLE")
+        ));
+        assert!(migration.check_allowed(true).is_ok());
+    }
+
+    #[test]
+    fn json_plan_describes_operations() {
+        let events = table("events");
+        let venues = table("venues");
+        let index = IndexSpec::new(
+            "events_title_idx".to_string(),
+            vec![IndexColumn::column("title")],
+            None,
+            IndexKind::DatabaseDefault,
+        );
+
+        let migration = Migration::from_diffs(&[
+            (SchemaOp::DeleteTable { table: &venues }, None),
+            (
+                SchemaOp::CreateIndex {
+                    table: &events,
+                    index: &index,
+                },
+                None,
+            ),
+        ]);
+
+        let plan = serde_json::to_value(&migration).unwrap();
+
+        let operations = plan["operations"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|operation| {
+                (
+                    operation["kind"].clone(),
+                    operation["objects"].clone(),
+                    operation["is_destructive"].clone(),
+                    operation["statements"].clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            operations,
+            vec![
+                (
+                    json!("delete_table"),
+                    json!(["\"venues\""]),
+                    json!(true),
+                    json!(["DROP TABLE \"venues\" CASCADE;"]),
+                ),
+                (
+                    json!("create_index"),
+                    json!(["\"events\".\"events_title_idx\""]),
+                    json!(false),
+                    json!(["CREATE INDEX \"events_title_idx\" ON \"events\" (\"title\");"]),
+                ),
+            ]
+        );
+        // An extra table isn't an incompatibility, but a missing index is
+        assert!(plan["operations"][0]["incompatibility"].is_null());
+        assert!(plan["operations"][1]["incompatibility"].is_string());
+
+        assert_eq!(plan["statements"][0]["is_destructive"], true);
+        assert_eq!(
+            plan["statements"][1]["locks"],
+            json!([{ "table": "\"events\"", "mode": "Share" }])
+        );
+    }
+}
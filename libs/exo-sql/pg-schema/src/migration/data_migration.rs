@@ -0,0 +1,200 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+#[cfg(feature = "interactive-migration")]
+use std::path::PathBuf;
+
+use exo_sql_core::{Database, DatabaseError, SchemaObjectName};
+use exo_sql_pg_connect::DatabaseClient;
+use serde::Serialize;
+
+use crate::{database_spec::DatabaseSpec, issue::Issue, spec::MigrationScope};
+
+use super::{Migration, MigrationMode};
+
+/// A step that populates a column between schema changes. For example, to add a non-nullable
+/// column to a table with existing rows, the migration adds the column as nullable, runs the data
+/// migration, and then makes the column non-nullable.
+#[derive(Debug, Clone, Serialize)]
+pub struct DataMigration {
+    pub table: SchemaObjectName,
+    pub column: String,
+    /// The mutation that populates the column (typically implemented by a Deno module)
+    pub mutation: String,
+}
+
+impl DataMigration {
+    #[cfg(feature = "interactive-migration")]
+    pub fn from_file(file_name: &PathBuf) -> Result<Vec<Self>, String> {
+        #[derive(serde::Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct DataMigrationsSer {
+            #[serde(rename = "backfill-column")]
+            backfill_columns: Option<Vec<BackfillColumn>>,
+        }
+
+        #[derive(serde::Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct BackfillColumn {
+            table: String,
+            column: String,
+            mutation: String,
+        }
+
+        let data_migrations = std::fs::read_to_string(file_name)
+            .map_err(|e| format!("Failed to read data migrations file: {}", e))?;
+
+        let data_migrations = toml::from_str::<DataMigrationsSer>(&data_migrations)
+            .map_err(|e| format!("Failed to parse data migrations file: {}", e))?;
+
+        Ok(data_migrations
+            .backfill_columns
+            .unwrap_or_default()
+            .into_iter()
+            .map(|backfill| DataMigration {
+                table: super::predefined_interaction::string_to_table_name(&backfill.table),
+                column: backfill.column,
+                mutation: backfill.mutation,
+            })
+            .collect())
+    }
+}
+
+/// A migration split around data migrations: `before` prepares the schema for the data migrations
+/// (adding the columns to populate as nullable), and `after` completes the schema changes that
+/// need the populated data (making those columns non-nullable).
+///
+/// All parts must run in the same transaction, so that a failed data migration leaves the
+/// database unchanged.
+#[derive(Debug, Serialize)]
+pub struct StagedMigration {
+    pub before: Migration,
+    pub data_migrations: Vec<DataMigration>,
+    pub after: Migration,
+}
+
+impl StagedMigration {
+    pub fn is_empty(&self) -> bool {
+        self.before.is_empty() && self.after.is_empty()
+    }
+
+    pub fn from_schemas(
+        old_schema_spec: &DatabaseSpec,
+        new_schema_spec: &DatabaseSpec,
+        scope: &MigrationScope,
+        mode: MigrationMode,
+        data_migrations: Vec<DataMigration>,
+    ) -> Self {
+        let mut intermediate_spec = new_schema_spec.clone();
+        let mut applicable_migrations = vec![];
+        let mut issues = vec![];
+
+        for data_migration in data_migrations {
+            let old_column_nullable = old_schema_spec
+                .tables
+                .iter()
+                .find(|table| table.name == data_migration.table)
+                .and_then(|table| {
+                    table
+                        .columns
+                        .iter()
+                        .find(|column| column.name == data_migration.column)
+                })
+                .map(|column| column.is_nullable);
+
+            let new_column = intermediate_spec
+                .tables
+                .iter_mut()
+                .find(|table| table.name == data_migration.table)
+                .and_then(|table| {
+                    table
+                        .columns
+                        .iter_mut()
+                        .find(|column| column.name == data_migration.column)
+                });
+
+            match (new_column, old_column_nullable) {
+                // The column is new or becomes non-nullable, so the data migration must populate it
+                (Some(new_column), None | Some(true)) if !new_column.is_nullable => {
+                    new_column.is_nullable = true;
+                    applicable_migrations.push(data_migration);
+                }
+                (Some(_), _) => issues.push(Issue::Hint(format!(
+                    "Skipping the data migration `{}` for the column `{}` in the table `{}`, since the migration doesn't need to populate it.",
+                    data_migration.mutation,
+                    data_migration.column,
+                    data_migration.table.sql_name()
+                ))),
+                (None, _) => issues.push(Issue::Warning(format!(
+                    "The model has no column `{}` in the table `{}` for the data migration `{}`.",
+                    data_migration.column,
+                    data_migration.table.sql_name(),
+                    data_migration.mutation,
+                ))),
+            }
+        }
+
+        let mut before =
+            Migration::from_schemas_with_mode(old_schema_spec, &intermediate_spec, scope, mode);
+        before.issues.extend(issues);
+
+        let after =
+            Migration::from_schemas_with_mode(&intermediate_spec, new_schema_spec, scope, mode);
+
+        Self {
+            before,
+            data_migrations: applicable_migrations,
+            after,
+        }
+    }
+
+    pub async fn from_db_and_model(
+        client: &DatabaseClient,
+        database: &Database,
+        scope: &MigrationScope,
+        mode: MigrationMode,
+        data_migrations: Vec<DataMigration>,
+    ) -> Result<Self, DatabaseError> {
+        let new_spec = DatabaseSpec::from_database(database);
+
+        let old_schema = Migration::extract_schema_from_db(client, &new_spec, scope).await?;
+
+        for issue in &old_schema.issues {
+            eprintln!("{issue}");
+        }
+
+        Ok(Self::from_schemas(
+            &old_schema.value,
+            &new_spec,
+            scope,
+            mode,
+            data_migrations,
+        ))
+    }
+
+    pub fn write(
+        &self,
+        writer: &mut dyn std::io::Write,
+        allow_destructive_changes: bool,
+    ) -> std::io::Result<()> {
+        self.before.write(writer, allow_destructive_changes)?;
+
+        for data_migration in &self.data_migrations {
+            writeln!(
+                writer,
+                "-- Populate {}.\"{}\" by running: mutation {{ {} }}\n",
+                data_migration.table.sql_name(),
+                data_migration.column,
+                data_migration.mutation
+            )?;
+        }
+
+        self.after.write(writer, allow_destructive_changes)
+    }
+}
@@ -3,10 +3,10 @@ use exo_sql_core::SchemaObjectName;
 use crate::{
     database_spec::DatabaseSpec,
     op::SchemaOp,
-    spec::{MigrationScope, diff},
+    spec::{MigrationScope, MigrationScopeMatches, diff},
 };
 
-use super::core::Migration;
+use super::{core::Migration, lock::MigrationMode};
 
 #[derive(Debug)]
 pub enum TableAction {
@@ -28,11 +28,77 @@ impl TableAction {
     }
 }
 
+#[derive(Debug)]
+pub enum ColumnAction {
+    Defer {
+        table: SchemaObjectName,
+        column: String,
+    },
+    Rename {
+        table: SchemaObjectName,
+        old_column: String,
+        new_column: String,
+    },
+    Delete {
+        table: SchemaObjectName,
+        column: String,
+    },
+}
+
+impl ColumnAction {
+    pub fn target_column(&self) -> (&SchemaObjectName, &str) {
+        match self {
+            ColumnAction::Defer { table, column } => (table, column),
+            ColumnAction::Rename {
+                table, old_column, ..
+            } => (table, old_column),
+            ColumnAction::Delete { table, column } => (table, column),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum EnumValueAction {
+    Defer {
+        enum_name: SchemaObjectName,
+        value: String,
+    },
+    Rename {
+        enum_name: SchemaObjectName,
+        old_value: String,
+        new_value: String,
+    },
+    Delete {
+        enum_name: SchemaObjectName,
+        value: String,
+    },
+}
+
+impl EnumValueAction {
+    pub fn target_value(&self) -> (&SchemaObjectName, &str) {
+        match self {
+            EnumValueAction::Defer { enum_name, value } => (enum_name, value),
+            EnumValueAction::Rename {
+                enum_name,
+                old_value,
+                ..
+            } => (enum_name, old_value),
+            EnumValueAction::Delete { enum_name, value } => (enum_name, value),
+        }
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum InteractionError {
     #[error("Table action for table {0:?} not found")]
     TableActionNotFound(SchemaObjectName),
 
+    #[error("Column action for column {1} in table {0:?} not found")]
+    ColumnActionNotFound(SchemaObjectName, String),
+
+    #[error("Enum value action for value {1} of enum {0:?} not found")]
+    EnumValueActionNotFound(SchemaObjectName, String),
+
     #[error("Generic error: {0}")]
     Generic(String),
 }
@@ -46,6 +112,20 @@ pub trait MigrationInteraction: Send + Sync {
         deleted_table: &SchemaObjectName,
         create_tables: &[&SchemaObjectName],
     ) -> Result<TableAction, InteractionError>;
+
+    fn handle_column_delete(
+        &self,
+        table: &SchemaObjectName,
+        deleted_column: &str,
+        create_columns: &[&str],
+    ) -> Result<ColumnAction, InteractionError>;
+
+    fn handle_enum_value_delete(
+        &self,
+        enum_name: &SchemaObjectName,
+        deleted_value: &str,
+        create_values: &[&str],
+    ) -> Result<EnumValueAction, InteractionError>;
 }
 
 async fn get_table_actions(
@@ -107,23 +187,145 @@ async fn get_table_actions(
     }
 }
 
+/// Ask how to handle columns that exist only in the old spec, when the same table has new columns
+/// (that may be the renamed versions of them). Without such columns, there is nothing to rename
+/// to, so we leave deleting the column to the usual migration.
+async fn get_column_actions(
+    old_db_spec: &DatabaseSpec,
+    new_db_spec: &DatabaseSpec,
+    scope: &MigrationScope,
+    interactions: &dyn MigrationInteraction,
+) -> Result<Vec<ColumnAction>, InteractionError> {
+    let mut column_actions: Vec<ColumnAction> = vec![];
+
+    let diffs = diff(old_db_spec, new_db_spec, scope);
+
+    let create_columns = diffs
+        .iter()
+        .filter_map(|diff| match diff {
+            SchemaOp::CreateColumn { table, column } => Some((&table.name, column.name.as_str())),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let delete_columns = diffs
+        .iter()
+        .filter_map(|diff| match diff {
+            SchemaOp::DeleteColumn { table, column } => Some((&table.name, column.name.as_str())),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    // A column whose type changes is deleted and created with the same name, which isn't a rename
+    let is_recreated = |table_name: &SchemaObjectName, column_name: &str| {
+        let matches = |&(t, c): &(&SchemaObjectName, &str)| t == table_name && c == column_name;
+        create_columns.iter().any(matches) && delete_columns.iter().any(matches)
+    };
+
+    for &(table_name, deleted_column) in delete_columns.iter() {
+        if is_recreated(table_name, deleted_column) {
+            continue;
+        }
+
+        let rename_candidates = create_columns
+            .iter()
+            .filter(|create_column| {
+                let (create_table_name, create_column_name) = **create_column;
+
+                create_table_name == table_name
+                    && !is_recreated(create_table_name, create_column_name)
+                    && column_actions.iter().all(|action| {
+                        !matches!(action, ColumnAction::Rename { table, new_column, .. } if table == create_table_name && new_column == create_column_name)
+                    })
+            })
+            .map(|(_, create_column_name)| *create_column_name)
+            .collect::<Vec<_>>();
+
+        if rename_candidates.is_empty() {
+            continue;
+        }
+
+        interactions.handle_start();
+
+        let column_action =
+            interactions.handle_column_delete(table_name, deleted_column, &rename_candidates)?;
+
+        column_actions.push(column_action);
+    }
+
+    Ok(column_actions)
+}
+
+/// Ask how to handle enum values that exist only in the old spec, when the same enum has new values
+/// (that may be the renamed versions of them). Without an explicit rename, the enum is recreated
+/// without the value (which fails if rows hold it).
+async fn get_enum_value_actions(
+    old_db_spec: &DatabaseSpec,
+    new_db_spec: &DatabaseSpec,
+    scope_matches: &MigrationScopeMatches,
+    interactions: &dyn MigrationInteraction,
+) -> Result<Vec<EnumValueAction>, InteractionError> {
+    let mut enum_value_actions: Vec<EnumValueAction> = vec![];
+
+    for old_enum in old_db_spec
+        .enums
+        .iter()
+        .filter(|enum_| scope_matches.matches(&enum_.name))
+    {
+        let Some(new_enum) = new_db_spec
+            .enums
+            .iter()
+            .find(|new_enum| new_enum.name == old_enum.name)
+        else {
+            continue;
+        };
+
+        let (deleted_values, create_values) = old_enum.value_changes(new_enum);
+
+        for deleted_value in deleted_values {
+            let rename_candidates = create_values
+                .iter()
+                .filter(|create_value| {
+                    enum_value_actions.iter().all(|action| {
+                        !matches!(action, EnumValueAction::Rename { enum_name, new_value, .. } if enum_name == &old_enum.name && new_value == *create_value)
+                    })
+                })
+                .copied()
+                .collect::<Vec<_>>();
+
+            if rename_candidates.is_empty() {
+                continue;
+            }
+
+            interactions.handle_start();
+
+            let enum_value_action = interactions.handle_enum_value_delete(
+                &old_enum.name,
+                deleted_value,
+                &rename_candidates,
+            )?;
+
+            enum_value_actions.push(enum_value_action);
+        }
+    }
+
+    Ok(enum_value_actions)
+}
+
 pub async fn migrate_interactively(
     mut old_db_spec: DatabaseSpec,
     new_db_spec: DatabaseSpec,
     scope: &MigrationScope,
     interactions: &dyn MigrationInteraction,
+    mode: MigrationMode,
 ) -> Result<Migration, InteractionError> {
     let table_actions = get_table_actions(&old_db_spec, &new_db_spec, scope, interactions).await?;
 
-    apply_table_actions(&mut old_db_spec, new_db_spec, table_actions, scope)
-}
+    let scope_matches = match scope {
+        MigrationScope::Specified(scope) => scope,
+        MigrationScope::FromNewSpec => &MigrationScopeMatches::from_specs_schemas(&[&new_db_spec]),
+    };
 
-fn apply_table_actions(
-    old_db_spec: &mut DatabaseSpec,
-    new_db_spec: DatabaseSpec,
-    table_actions: Vec<TableAction>,
-    scope: &MigrationScope,
-) -> Result<Migration, InteractionError> {
     let mut all_ops: Vec<SchemaOp> = vec![];
 
     for table_action in table_actions.iter() {
@@ -137,14 +339,55 @@ fn apply_table_actions(
         }
     }
 
-    let diffs = diff(old_db_spec, &new_db_spec, scope);
+    // Columns renamed through `@column(previously=...)` need no interaction
+    all_ops.extend(old_db_spec.with_hinted_column_renames(&new_db_spec, scope_matches));
+
+    // Ask about columns only after renaming tables, so that we compare columns of the same table
+    let column_actions =
+        get_column_actions(&old_db_spec, &new_db_spec, scope, interactions).await?;
+
+    for column_action in column_actions.iter() {
+        if let ColumnAction::Rename {
+            table,
+            old_column,
+            new_column,
+        } = column_action
+        {
+            all_ops.extend(old_db_spec.with_column_renamed(table, old_column, new_column));
+        }
+    }
+
+    let enum_value_actions =
+        get_enum_value_actions(&old_db_spec, &new_db_spec, scope_matches, interactions).await?;
+
+    for enum_value_action in enum_value_actions.iter() {
+        if let EnumValueAction::Rename {
+            enum_name,
+            old_value,
+            new_value,
+        } = enum_value_action
+        {
+            all_ops.extend(old_db_spec.with_enum_value_renamed(enum_name, old_value, new_value));
+        }
+    }
+
+    let diffs = diff(&old_db_spec, &new_db_spec, scope);
+
+    // Recreating an enum is allowed only if the user chose to delete each of the removed values
+    let is_enum_value_deleted = |enum_name: &SchemaObjectName, value: &String| {
+        enum_value_actions.iter().any(|action| {
+            matches!(action, EnumValueAction::Delete { enum_name: action_enum_name, value: action_value } if action_enum_name == enum_name && action_value == value)
+        })
+    };
 
     let diffs = diffs
         .into_iter()
         .map(|diff| {
             let allow_destructive = table_actions.iter().any(|action|
                 matches!((&diff, action), (SchemaOp::DeleteTable { table }, TableAction::Delete(table_name)) if table_name == &table.name)
-            );
+            ) || column_actions.iter().any(|action|
+                matches!((&diff, action), (SchemaOp::DeleteColumn { table, column }, ColumnAction::Delete { table: table_name, column: column_name }) if table_name == &table.name && column_name == &column.name)
+            ) || matches!(&diff, SchemaOp::RecreateEnum { enum_, removed_values, .. } if removed_values.iter().all(|value| is_enum_value_deleted(&enum_.name, value)));
 
             (diff, if allow_destructive { Some(false) } else { None })
         })
@@ -156,7 +399,7 @@ fn apply_table_actions(
         .chain(diffs)
         .collect::<Vec<_>>();
 
-    Ok(Migration::from_diffs(&all_ops))
+    Ok(Migration::from_diffs_with_mode(&all_ops, mode))
 }
 
 #[derive(Debug)]
@@ -173,4 +416,28 @@ impl MigrationInteraction for AlwaysDeferMigrationInteraction {
     ) -> Result<TableAction, InteractionError> {
         Ok(TableAction::Defer(deleted_table.clone()))
     }
+
+    fn handle_column_delete(
+        &self,
+        table: &SchemaObjectName,
+        deleted_column: &str,
+        _create_columns: &[&str],
+    ) -> Result<ColumnAction, InteractionError> {
+        Ok(ColumnAction::Defer {
+            table: table.clone(),
+            column: deleted_column.to_string(),
+        })
+    }
+
+    fn handle_enum_value_delete(
+        &self,
+        enum_name: &SchemaObjectName,
+        deleted_value: &str,
+        _create_values: &[&str],
+    ) -> Result<EnumValueAction, InteractionError> {
+        Ok(EnumValueAction::Defer {
+            enum_name: enum_name.clone(),
+            value: deleted_value.to_string(),
+        })
+    }
 }
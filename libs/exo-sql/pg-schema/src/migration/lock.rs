@@ -0,0 +1,77 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+use std::fmt::Display;
+
+use serde::Serialize;
+
+/// How to produce migration statements
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MigrationMode {
+    /// Plain statements meant to run in a single transaction
+    #[default]
+    Standard,
+    /// Statements that avoid holding locks that block reads or writes on existing tables for long
+    /// (for migrating a database that serves traffic). Indexes are built concurrently (outside a
+    /// transaction) and constraints are added as `NOT VALID` and validated separately.
+    Online,
+}
+
+/// A table-level lock (https://www.postgresql.org/docs/current/explicit-locking.html#LOCKING-TABLES).
+/// We track only the ones that block reads or writes of the table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum LockMode {
+    /// Blocks writes (`CREATE INDEX`)
+    Share,
+    /// Blocks writes (`ADD FOREIGN KEY`, `CREATE TRIGGER`)
+    ShareRowExclusive,
+    /// Blocks reads and writes (most `ALTER TABLE` forms, `DROP INDEX`)
+    AccessExclusive,
+}
+
+impl LockMode {
+    pub fn blocks_reads(&self) -> bool {
+        matches!(self, LockMode::AccessExclusive)
+    }
+}
+
+impl Display for LockMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockMode::Share => write!(f, "SHARE"),
+            LockMode::ShareRowExclusive => write!(f, "SHARE ROW EXCLUSIVE"),
+            LockMode::AccessExclusive => write!(f, "ACCESS EXCLUSIVE"),
+        }
+    }
+}
+
+/// A lock a migration statement takes on an existing table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TableLock {
+    /// The table name (as in SQL)
+    pub table: String,
+    pub mode: LockMode,
+}
+
+impl TableLock {
+    pub fn new(table: String, mode: LockMode) -> Self {
+        Self { table, mode }
+    }
+}
+
+impl Display for TableLock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let blocks = if self.mode.blocks_reads() {
+            "blocks reads and writes"
+        } else {
+            "blocks writes"
+        };
+        write!(f, "{} on {} ({blocks})", self.mode, self.table)
+    }
+}
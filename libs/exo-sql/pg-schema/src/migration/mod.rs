@@ -1,8 +1,18 @@
 mod core;
+mod data_migration;
 mod interaction;
+mod lock;
 mod predefined_interaction;
 
-pub use core::{Migration, MigrationError, MigrationStatement, VerificationErrors, wipe_database};
+pub use core::{
+    Migration, MigrationError, MigrationOperation, MigrationStatement, VerificationErrors,
+    wipe_database,
+};
 
-pub use interaction::{InteractionError, MigrationInteraction, TableAction, migrate_interactively};
+pub use data_migration::{DataMigration, StagedMigration};
+pub use interaction::{
+    ColumnAction, EnumValueAction, InteractionError, MigrationInteraction, TableAction,
+    migrate_interactively,
+};
+pub use lock::{LockMode, MigrationMode, TableLock};
 pub use predefined_interaction::PredefinedMigrationInteraction;
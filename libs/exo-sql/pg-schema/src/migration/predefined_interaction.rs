@@ -6,18 +6,26 @@ use exo_sql_core::SchemaObjectName;
 
 use super::{
     MigrationInteraction,
-    interaction::{InteractionError, TableAction},
+    interaction::{ColumnAction, EnumValueAction, InteractionError, TableAction},
 };
 
 #[derive(Debug)]
 pub struct PredefinedMigrationInteraction {
     actions: Mutex<Vec<TableAction>>,
+    column_actions: Mutex<Vec<ColumnAction>>,
+    enum_value_actions: Mutex<Vec<EnumValueAction>>,
 }
 
 impl PredefinedMigrationInteraction {
-    pub fn new(actions: Vec<TableAction>) -> Self {
+    pub fn new(
+        actions: Vec<TableAction>,
+        column_actions: Vec<ColumnAction>,
+        enum_value_actions: Vec<EnumValueAction>,
+    ) -> Self {
         Self {
             actions: Mutex::new(actions),
+            column_actions: Mutex::new(column_actions),
+            enum_value_actions: Mutex::new(enum_value_actions),
         }
     }
 
@@ -32,6 +40,18 @@ impl PredefinedMigrationInteraction {
             delete_tables: Option<Vec<String>>,
             #[serde(rename = "defer-table")]
             defer_tables: Option<Vec<String>>,
+            #[serde(rename = "rename-column")]
+            rename_columns: Option<Vec<RenameColumn>>,
+            #[serde(rename = "delete-column")]
+            delete_columns: Option<Vec<TableColumn>>,
+            #[serde(rename = "defer-column")]
+            defer_columns: Option<Vec<TableColumn>>,
+            #[serde(rename = "rename-enum-value")]
+            rename_enum_values: Option<Vec<RenameEnumValue>>,
+            #[serde(rename = "delete-enum-value")]
+            delete_enum_values: Option<Vec<EnumValue>>,
+            #[serde(rename = "defer-enum-value")]
+            defer_enum_values: Option<Vec<EnumValue>>,
         }
 
         #[derive(serde::Deserialize)]
@@ -43,6 +63,42 @@ impl PredefinedMigrationInteraction {
             new_table: String,
         }
 
+        #[derive(serde::Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct RenameColumn {
+            table: String,
+            #[serde(rename = "old-column")]
+            old_column: String,
+            #[serde(rename = "new-column")]
+            new_column: String,
+        }
+
+        #[derive(serde::Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct TableColumn {
+            table: String,
+            column: String,
+        }
+
+        #[derive(serde::Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct RenameEnumValue {
+            #[serde(rename = "enum")]
+            enum_name: String,
+            #[serde(rename = "old-value")]
+            old_value: String,
+            #[serde(rename = "new-value")]
+            new_value: String,
+        }
+
+        #[derive(serde::Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct EnumValue {
+            #[serde(rename = "enum")]
+            enum_name: String,
+            value: String,
+        }
+
         let interaction = std::fs::read_to_string(file_name)
             .map_err(|e| format!("Failed to read interaction file: {}", e))?;
 
@@ -51,24 +107,6 @@ impl PredefinedMigrationInteraction {
 
         let mut table_actions = vec![];
 
-        fn string_to_table_name(name: &str) -> SchemaObjectName {
-            let parts = name.split('.').collect::<Vec<_>>();
-
-            if parts.len() == 1 {
-                SchemaObjectName {
-                    schema: None,
-                    name: parts[0].to_string(),
-                }
-            } else if parts.len() == 2 {
-                SchemaObjectName {
-                    schema: Some(parts[0].to_string()),
-                    name: parts[1].to_string(),
-                }
-            } else {
-                panic!("Invalid table name: {}", name)
-            }
-        }
-
         if let Some(rename_tables) = interaction.rename_tables {
             for rename_table in rename_tables {
                 table_actions.push(TableAction::Rename {
@@ -90,7 +128,90 @@ impl PredefinedMigrationInteraction {
             }
         }
 
-        Ok(PredefinedMigrationInteraction::new(table_actions))
+        let mut column_actions = vec![];
+
+        if let Some(rename_columns) = interaction.rename_columns {
+            for rename_column in rename_columns {
+                column_actions.push(ColumnAction::Rename {
+                    table: string_to_table_name(&rename_column.table),
+                    old_column: rename_column.old_column,
+                    new_column: rename_column.new_column,
+                });
+            }
+        }
+
+        if let Some(delete_columns) = interaction.delete_columns {
+            for delete_column in delete_columns {
+                column_actions.push(ColumnAction::Delete {
+                    table: string_to_table_name(&delete_column.table),
+                    column: delete_column.column,
+                });
+            }
+        }
+
+        if let Some(defer_columns) = interaction.defer_columns {
+            for defer_column in defer_columns {
+                column_actions.push(ColumnAction::Defer {
+                    table: string_to_table_name(&defer_column.table),
+                    column: defer_column.column,
+                });
+            }
+        }
+
+        let mut enum_value_actions = vec![];
+
+        if let Some(rename_enum_values) = interaction.rename_enum_values {
+            for rename_enum_value in rename_enum_values {
+                enum_value_actions.push(EnumValueAction::Rename {
+                    enum_name: string_to_table_name(&rename_enum_value.enum_name),
+                    old_value: rename_enum_value.old_value,
+                    new_value: rename_enum_value.new_value,
+                });
+            }
+        }
+
+        if let Some(delete_enum_values) = interaction.delete_enum_values {
+            for delete_enum_value in delete_enum_values {
+                enum_value_actions.push(EnumValueAction::Delete {
+                    enum_name: string_to_table_name(&delete_enum_value.enum_name),
+                    value: delete_enum_value.value,
+                });
+            }
+        }
+
+        if let Some(defer_enum_values) = interaction.defer_enum_values {
+            for defer_enum_value in defer_enum_values {
+                enum_value_actions.push(EnumValueAction::Defer {
+                    enum_name: string_to_table_name(&defer_enum_value.enum_name),
+                    value: defer_enum_value.value,
+                });
+            }
+        }
+
+        Ok(PredefinedMigrationInteraction::new(
+            table_actions,
+            column_actions,
+            enum_value_actions,
+        ))
+    }
+}
+
+#[cfg(feature = "interactive-migration")]
+pub(super) fn string_to_table_name(name: &str) -> SchemaObjectName {
+    let parts = name.split('.').collect::<Vec<_>>();
+
+    if parts.len() == 1 {
+        SchemaObjectName {
+            schema: None,
+            name: parts[0].to_string(),
+        }
+    } else if parts.len() == 2 {
+        SchemaObjectName {
+            schema: Some(parts[0].to_string()),
+            name: parts[1].to_string(),
+        }
+    } else {
+        panic!("Invalid table name: {}", name)
     }
 }
 
@@ -118,4 +239,41 @@ impl MigrationInteraction for PredefinedMigrationInteraction {
         let table_action = actions.remove(action_index);
         Ok(table_action)
     }
+
+    fn handle_column_delete(
+        &self,
+        table: &SchemaObjectName,
+        deleted_column: &str,
+        _create_columns: &[&str],
+    ) -> Result<ColumnAction, InteractionError> {
+        // Same as tables: remove the action so that we don't handle the same column twice
+        let mut column_actions = self.column_actions.lock().unwrap();
+        let action_index = column_actions
+            .iter()
+            .position(|action| action.target_column() == (table, deleted_column))
+            .ok_or_else(|| {
+                InteractionError::ColumnActionNotFound(table.clone(), deleted_column.to_string())
+            })?;
+        Ok(column_actions.remove(action_index))
+    }
+
+    fn handle_enum_value_delete(
+        &self,
+        enum_name: &SchemaObjectName,
+        deleted_value: &str,
+        _create_values: &[&str],
+    ) -> Result<EnumValueAction, InteractionError> {
+        // Same as tables: remove the action so that we don't handle the same value twice
+        let mut enum_value_actions = self.enum_value_actions.lock().unwrap();
+        let action_index = enum_value_actions
+            .iter()
+            .position(|action| action.target_value() == (enum_name, deleted_value))
+            .ok_or_else(|| {
+                InteractionError::EnumValueActionNotFound(
+                    enum_name.clone(),
+                    deleted_value.to_string(),
+                )
+            })?;
+        Ok(enum_value_actions.remove(action_index))
+    }
 }
@@ -12,11 +12,14 @@ use std::collections::HashSet;
 use exo_sql_core::{SchemaObjectName, SchemaStatement};
 
 use crate::{
+    check_spec::CheckSpec,
     column_spec::{ColumnReferenceSpec, ColumnSpec},
     constraint::sorted_comma_list,
-    enum_spec::EnumSpec,
+    enum_spec::{EnumSpec, EnumValuePosition},
     function_spec::FunctionSpec,
     index_spec::IndexSpec,
+    issue::Issue,
+    migration::{LockMode, MigrationMode, TableLock},
     table_spec::TableSpec,
     trigger_spec::TriggerSpec,
 };
@@ -39,6 +42,27 @@ impl RenameTableOp {
         }
     }
 }
+#[derive(Debug, Clone)]
+pub struct RenameColumnOp {
+    pub table_name: SchemaObjectName,
+    pub old_name: String,
+    pub new_name: String,
+}
+
+impl RenameColumnOp {
+    pub fn to_sql(&self) -> SchemaStatement {
+        SchemaStatement {
+            statement: format!(
+                "ALTER TABLE {} RENAME COLUMN \"{}\" TO \"{}\";",
+                self.table_name.sql_name(),
+                self.old_name,
+                self.new_name
+            ),
+            ..Default::default()
+        }
+    }
+}
+
 /// An execution unit of SQL, representing an operation that can create or destroy resources.
 #[derive(Debug)]
 pub enum SchemaOp<'a> {
@@ -78,6 +102,22 @@ pub enum SchemaOp<'a> {
     DeleteEnum {
         enum_: &'a EnumSpec,
     },
+    AddEnumValue {
+        enum_: &'a EnumSpec,
+        value: String,
+        position: Option<EnumValuePosition>,
+    },
+    RenameEnumValue {
+        enum_name: SchemaObjectName,
+        old_value: String,
+        new_value: String,
+    },
+    /// Replace an enum whose values can't be altered in place (removed or reordered values)
+    RecreateEnum {
+        enum_: &'a EnumSpec,
+        removed_values: Vec<String>,
+        columns: Vec<(&'a TableSpec, &'a ColumnSpec)>,
+    },
 
     CreateColumn {
         table: &'a TableSpec,
@@ -87,11 +127,7 @@ pub enum SchemaOp<'a> {
         table: &'a TableSpec,
         column: &'a ColumnSpec,
     },
-    RenameColumn {
-        table: &'a TableSpec,
-        name: String,
-        new_name: String,
-    },
+    RenameColumn(RenameColumnOp),
     CreateIndex {
         table: &'a TableSpec,
         index: &'a IndexSpec,
@@ -127,6 +163,15 @@ pub enum SchemaOp<'a> {
         constraint: String,
     },
 
+    CreateCheck {
+        table: &'a TableSpec,
+        check: &'a CheckSpec,
+    },
+    DeleteCheck {
+        table: &'a TableSpec,
+        check: &'a CheckSpec,
+    },
+
     CreateForeignKeyReference {
         table: &'a TableSpec,
         name: String,
@@ -204,6 +249,21 @@ impl SchemaOp<'_> {
 
             SchemaOp::CreateEnum { enum_ } => enum_.creation_sql(),
             SchemaOp::DeleteEnum { enum_ } => enum_.deletion_sql(),
+            SchemaOp::AddEnumValue {
+                enum_,
+                value,
+                position,
+            } => enum_.add_value_sql(value, position.as_ref()),
+            SchemaOp::RenameEnumValue {
+                enum_name,
+                old_value,
+                new_value,
+            } => EnumSpec::rename_value_sql(enum_name, old_value, new_value),
+            SchemaOp::RecreateEnum {
+                enum_,
+                removed_values,
+                columns,
+            } => enum_.recreation_sql(removed_values, columns),
 
             SchemaOp::CreateColumn { table, column } => {
                 let column_stmt = column.to_sql(table.has_single_pk());
@@ -226,26 +286,14 @@ impl SchemaOp<'_> {
                 ),
                 ..Default::default()
             },
-            SchemaOp::RenameColumn {
-                table,
-                name,
-                new_name,
-            } => SchemaStatement {
-                statement: format!(
-                    "ALTER TABLE {} RENAME COLUMN \"{}\" TO \"{}\";",
-                    table.sql_name(),
-                    name,
-                    new_name
-                ),
-                ..Default::default()
-            },
+            SchemaOp::RenameColumn(op) => op.to_sql(),
 
             SchemaOp::CreateIndex { table, index } => SchemaStatement {
                 statement: index.creation_sql(&table.name),
                 ..Default::default()
             },
-            SchemaOp::DeleteIndex { index, .. } => SchemaStatement {
-                statement: format!("DROP INDEX \"{}\";", index.name),
+            SchemaOp::DeleteIndex { table, index } => SchemaStatement {
+                statement: format!("DROP INDEX {};", index_sql_name(table, index)),
                 ..Default::default()
             },
 
@@ -302,6 +350,19 @@ impl SchemaOp<'_> {
                 ..Default::default()
             },
 
+            SchemaOp::CreateCheck { table, check } => SchemaStatement {
+                statement: check.creation_sql(&table.name),
+                ..Default::default()
+            },
+            SchemaOp::DeleteCheck { table, check } => SchemaStatement {
+                statement: format!(
+                    "ALTER TABLE {} DROP CONSTRAINT IF EXISTS \"{}\";",
+                    table.sql_name(),
+                    check.name
+                ),
+                ..Default::default()
+            },
+
             SchemaOp::CreateForeignKeyReference {
                 table,
                 name,
@@ -314,11 +375,7 @@ impl SchemaOp<'_> {
                 let (self_columns, foreign_columns): (Vec<&ColumnSpec>, Vec<&ColumnReferenceSpec>) =
                     reference_columns.into_iter().unzip();
 
-                let constraint_name = format!(
-                    "{}_{}_fk",
-                    table.name.fully_qualified_name_with_sep("_"),
-                    name
-                );
+                let constraint_name = foreign_key_constraint_name(table, name);
 
                 let foreign_reference_columns = if foreign_columns.len() == 1 {
                     // If there is only one foreign column, we don't need to specify the columns in the foreign key constraint (assume it's the primary key)
@@ -413,6 +470,334 @@ impl SchemaOp<'_> {
         }
     }
 
+    /// The SQL for the operation when migrating in the given mode
+    pub fn to_sql_for_mode(&self, mode: MigrationMode) -> SchemaStatement {
+        if mode == MigrationMode::Standard {
+            return self.to_sql();
+        }
+
+        match self {
+            SchemaOp::CreateIndex { table, index } => SchemaStatement {
+                statement: index.concurrent_creation_sql(&table.name),
+                // A failed concurrent build leaves an invalid index behind, which we must drop
+                // before retrying
+                pre_statements: vec![format!(
+                    "DROP INDEX CONCURRENTLY IF EXISTS {};",
+                    index_sql_name(table, index)
+                )],
+                ..Default::default()
+            },
+            SchemaOp::DeleteIndex { table, index } => SchemaStatement {
+                statement: format!(
+                    "DROP INDEX CONCURRENTLY IF EXISTS {};",
+                    index_sql_name(table, index)
+                ),
+                ..Default::default()
+            },
+            SchemaOp::CreateCheck { table, check } => SchemaStatement {
+                statement: not_valid(check.creation_sql(&table.name)),
+                post_statements: vec![validation_sql(table, &check.name)],
+                ..Default::default()
+            },
+            SchemaOp::CreateForeignKeyReference { table, name, .. } => {
+                let SchemaStatement {
+                    post_statements, ..
+                } = self.to_sql();
+
+                SchemaStatement {
+                    post_statements: post_statements
+                        .into_iter()
+                        .map(not_valid)
+                        .chain(std::iter::once(validation_sql(
+                            table,
+                            &foreign_key_constraint_name(table, name),
+                        )))
+                        .collect(),
+                    ..Default::default()
+                }
+            }
+            _ => self.to_sql(),
+        }
+    }
+
+    /// Does the operation run outside a transaction (and therefore can't be combined with other
+    /// statements)?
+    /// Must the operation be committed before the rest of the migration runs? A value added to an
+    /// enum can't be used (for example, as a default) in the transaction that added it.
+    pub fn runs_before_transaction(&self) -> bool {
+        matches!(self, SchemaOp::AddEnumValue { .. })
+    }
+
+    pub fn runs_concurrently(&self, mode: MigrationMode) -> bool {
+        mode == MigrationMode::Online
+            && matches!(
+                self,
+                SchemaOp::CreateIndex { .. } | SchemaOp::DeleteIndex { .. }
+            )
+    }
+
+    /// Locks on existing tables (that block reads or writes) that the operation takes.
+    pub fn locks(&self, mode: MigrationMode) -> Vec<TableLock> {
+        fn lock(table: &TableSpec, mode: LockMode) -> Vec<TableLock> {
+            vec![TableLock::new(table.sql_name(), mode)]
+        }
+
+        match self {
+            SchemaOp::CreateSchema { .. }
+            | SchemaOp::DeleteSchema { .. }
+            | SchemaOp::RenameSchema { .. }
+            | SchemaOp::CreateSequence { .. }
+            | SchemaOp::DeleteSequence { .. }
+            | SchemaOp::RenameSequence { .. }
+            | SchemaOp::CreateEnum { .. }
+            | SchemaOp::DeleteEnum { .. }
+            | SchemaOp::AddEnumValue { .. }
+            | SchemaOp::RenameEnumValue { .. }
+            | SchemaOp::CreateExtension { .. }
+            | SchemaOp::RemoveExtension { .. }
+            | SchemaOp::CreateFunction { .. }
+            | SchemaOp::DeleteFunction { .. }
+            | SchemaOp::CreateOrReplaceFunction { .. } => vec![],
+
+            // The new table isn't in use yet, but its foreign keys lock the referenced tables
+            SchemaOp::CreateTable { table } => {
+                let mut referenced_tables = table
+                    .columns
+                    .iter()
+                    .flat_map(|column| column.reference_specs.iter().flatten())
+                    .map(|reference| reference.foreign_table_name.sql_name())
+                    .filter(|name| name != &table.sql_name())
+                    .collect::<Vec<_>>();
+                referenced_tables.sort();
+                referenced_tables.dedup();
+
+                referenced_tables
+                    .into_iter()
+                    .map(|name| TableLock::new(name, LockMode::ShareRowExclusive))
+                    .collect()
+            }
+            SchemaOp::DeleteTable { table } => lock(table, LockMode::AccessExclusive),
+            SchemaOp::RenameTable(op) => vec![TableLock::new(
+                op.old_name.sql_name(),
+                LockMode::AccessExclusive,
+            )],
+
+            SchemaOp::RecreateEnum { columns, .. } => {
+                let mut tables = columns
+                    .iter()
+                    .map(|(table, _)| table.sql_name())
+                    .collect::<Vec<_>>();
+                tables.sort();
+                tables.dedup();
+
+                tables
+                    .into_iter()
+                    .map(|name| TableLock::new(name, LockMode::AccessExclusive))
+                    .collect()
+            }
+
+            SchemaOp::CreateColumn { table, .. }
+            | SchemaOp::DeleteColumn { table, .. }
+            | SchemaOp::SetColumnDefaultValue { table, .. }
+            | SchemaOp::UnsetColumnDefaultValue { table, .. }
+            | SchemaOp::SetNotNull { table, .. }
+            | SchemaOp::UnsetNotNull { table, .. }
+            | SchemaOp::CreateUniqueConstraint { table, .. }
+            | SchemaOp::RemoveUniqueConstraint { table, .. }
+            | SchemaOp::CreateCheck { table, .. }
+            | SchemaOp::DeleteCheck { table, .. }
+            | SchemaOp::DeleteForeignKeyReference { table, .. } => {
+                lock(table, LockMode::AccessExclusive)
+            }
+            SchemaOp::RenameColumn(op) => vec![TableLock::new(
+                op.table_name.sql_name(),
+                LockMode::AccessExclusive,
+            )],
+
+            SchemaOp::CreateIndex { table, .. } => match mode {
+                MigrationMode::Standard => lock(table, LockMode::Share),
+                MigrationMode::Online => vec![],
+            },
+            SchemaOp::DeleteIndex { table, .. } => match mode {
+                MigrationMode::Standard => lock(table, LockMode::AccessExclusive),
+                MigrationMode::Online => vec![],
+            },
+
+            SchemaOp::CreateForeignKeyReference {
+                table,
+                reference_columns,
+                ..
+            } => {
+                let mut locks = lock(table, LockMode::ShareRowExclusive);
+                if let Some((_, reference)) = reference_columns.first()
+                    && reference.foreign_table_name != table.name
+                {
+                    locks.push(TableLock::new(
+                        reference.foreign_table_name.sql_name(),
+                        LockMode::ShareRowExclusive,
+                    ));
+                }
+                locks
+            }
+
+            SchemaOp::CreateTrigger { table_name, .. } => vec![TableLock::new(
+                table_name.sql_name(),
+                LockMode::ShareRowExclusive,
+            )],
+            SchemaOp::DeleteTrigger { table_name, .. } => vec![TableLock::new(
+                table_name.sql_name(),
+                LockMode::AccessExclusive,
+            )],
+        }
+    }
+
+    /// The kind of operation (for machine-readable migration plans)
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SchemaOp::CreateSchema { .. } => "create_schema",
+            SchemaOp::DeleteSchema { .. } => "delete_schema",
+            SchemaOp::RenameSchema { .. } => "rename_schema",
+            SchemaOp::CreateSequence { .. } => "create_sequence",
+            SchemaOp::DeleteSequence { .. } => "delete_sequence",
+            SchemaOp::RenameSequence { .. } => "rename_sequence",
+            SchemaOp::CreateTable { .. } => "create_table",
+            SchemaOp::DeleteTable { .. } => "delete_table",
+            SchemaOp::RenameTable(_) => "rename_table",
+            SchemaOp::CreateEnum { .. } => "create_enum",
+            SchemaOp::DeleteEnum { .. } => "delete_enum",
+            SchemaOp::AddEnumValue { .. } => "add_enum_value",
+            SchemaOp::RenameEnumValue { .. } => "rename_enum_value",
+            SchemaOp::RecreateEnum { .. } => "recreate_enum",
+            SchemaOp::CreateColumn { .. } => "create_column",
+            SchemaOp::DeleteColumn { .. } => "delete_column",
+            SchemaOp::RenameColumn(_) => "rename_column",
+            SchemaOp::CreateIndex { .. } => "create_index",
+            SchemaOp::DeleteIndex { .. } => "delete_index",
+            SchemaOp::SetColumnDefaultValue { .. } => "set_column_default_value",
+            SchemaOp::UnsetColumnDefaultValue { .. } => "unset_column_default_value",
+            SchemaOp::CreateExtension { .. } => "create_extension",
+            SchemaOp::RemoveExtension { .. } => "remove_extension",
+            SchemaOp::CreateUniqueConstraint { .. } => "create_unique_constraint",
+            SchemaOp::RemoveUniqueConstraint { .. } => "remove_unique_constraint",
+            SchemaOp::CreateCheck { .. } => "create_check",
+            SchemaOp::DeleteCheck { .. } => "delete_check",
+            SchemaOp::CreateForeignKeyReference { .. } => "create_foreign_key_reference",
+            SchemaOp::DeleteForeignKeyReference { .. } => "delete_foreign_key_reference",
+            SchemaOp::SetNotNull { .. } => "set_not_null",
+            SchemaOp::UnsetNotNull { .. } => "unset_not_null",
+            SchemaOp::CreateFunction { .. } => "create_function",
+            SchemaOp::DeleteFunction { .. } => "delete_function",
+            SchemaOp::CreateOrReplaceFunction { .. } => "create_or_replace_function",
+            SchemaOp::CreateTrigger { .. } => "create_trigger",
+            SchemaOp::DeleteTrigger { .. } => "delete_trigger",
+        }
+    }
+
+    /// The database objects the operation affects (as SQL names). Objects that belong to a table
+    /// (columns, indexes, constraints, and triggers) are qualified with the table name.
+    pub fn affected_objects(&self) -> Vec<String> {
+        fn table_member(table_name: &SchemaObjectName, member: &str) -> String {
+            format!("{}.\"{member}\"", table_name.sql_name())
+        }
+
+        match self {
+            SchemaOp::CreateSchema { schema } | SchemaOp::DeleteSchema { schema } => {
+                vec![format!("\"{schema}\"")]
+            }
+            SchemaOp::RenameSchema { old_name, new_name } => {
+                vec![format!("\"{old_name}\""), format!("\"{new_name}\"")]
+            }
+
+            SchemaOp::CreateSequence { sequence } | SchemaOp::DeleteSequence { sequence } => {
+                vec![sequence.sql_name()]
+            }
+            SchemaOp::RenameSequence { old_name, new_name } => {
+                vec![old_name.sql_name(), new_name.sql_name()]
+            }
+
+            SchemaOp::CreateTable { table } | SchemaOp::DeleteTable { table } => {
+                vec![table.sql_name()]
+            }
+            SchemaOp::RenameTable(op) => vec![op.old_name.sql_name(), op.new_name.sql_name()],
+
+            SchemaOp::CreateEnum { enum_ }
+            | SchemaOp::DeleteEnum { enum_ }
+            | SchemaOp::AddEnumValue { enum_, .. } => vec![enum_.sql_name()],
+            SchemaOp::RenameEnumValue { enum_name, .. } => vec![enum_name.sql_name()],
+            SchemaOp::RecreateEnum { enum_, columns, .. } => std::iter::once(enum_.sql_name())
+                .chain(
+                    columns
+                        .iter()
+                        .map(|(table, column)| table_member(&table.name, &column.name)),
+                )
+                .collect(),
+
+            SchemaOp::CreateColumn { table, column }
+            | SchemaOp::DeleteColumn { table, column }
+            | SchemaOp::SetColumnDefaultValue { table, column, .. }
+            | SchemaOp::UnsetColumnDefaultValue { table, column }
+            | SchemaOp::SetNotNull { table, column }
+            | SchemaOp::UnsetNotNull { table, column } => {
+                vec![table_member(&table.name, &column.name)]
+            }
+            SchemaOp::RenameColumn(op) => vec![
+                table_member(&op.table_name, &op.old_name),
+                table_member(&op.table_name, &op.new_name),
+            ],
+
+            SchemaOp::CreateIndex { table, index } | SchemaOp::DeleteIndex { table, index } => {
+                vec![table_member(&table.name, &index.name)]
+            }
+
+            SchemaOp::CreateExtension { extension } | SchemaOp::RemoveExtension { extension } => {
+                vec![format!("\"{extension}\"")]
+            }
+
+            SchemaOp::CreateUniqueConstraint {
+                table,
+                constraint_name: constraint,
+                ..
+            }
+            | SchemaOp::RemoveUniqueConstraint { table, constraint } => {
+                vec![table_member(&table.name, constraint)]
+            }
+            SchemaOp::CreateCheck { table, check } | SchemaOp::DeleteCheck { table, check } => {
+                vec![table_member(&table.name, &check.name)]
+            }
+
+            SchemaOp::CreateForeignKeyReference {
+                table,
+                name,
+                reference_columns,
+            } => std::iter::once(table_member(
+                &table.name,
+                &foreign_key_constraint_name(table, name),
+            ))
+            .chain(
+                reference_columns
+                    .first()
+                    .map(|(_, reference)| reference.foreign_table_name.sql_name()),
+            )
+            .collect(),
+            SchemaOp::DeleteForeignKeyReference { table, name } => {
+                vec![table_member(&table.name, name)]
+            }
+
+            SchemaOp::CreateFunction { function }
+            | SchemaOp::CreateOrReplaceFunction { function } => vec![function.name.clone()],
+            SchemaOp::DeleteFunction { name } => vec![name.to_string()],
+
+            SchemaOp::CreateTrigger {
+                trigger,
+                table_name,
+            }
+            | SchemaOp::DeleteTrigger {
+                trigger,
+                table_name,
+            } => vec![table_member(table_name, &trigger.name)],
+        }
+    }
+
     pub fn is_destructive(&self) -> bool {
         match self {
             SchemaOp::DeleteSchema { .. }
@@ -422,6 +807,9 @@ impl SchemaOp<'_> {
             | SchemaOp::DeleteEnum { .. }
             | SchemaOp::DeleteSequence { .. } => true,
 
+            // Rows holding a removed value cannot be converted to the recreated enum
+            SchemaOp::RecreateEnum { removed_values, .. } => !removed_values.is_empty(),
+
             // Explicitly matching the other cases here to ensure that we have thought about each case
             SchemaOp::CreateSchema { .. }
             | SchemaOp::RenameSchema { .. }
@@ -430,6 +818,8 @@ impl SchemaOp<'_> {
             | SchemaOp::CreateTable { .. }
             | SchemaOp::RenameTable { .. }
             | SchemaOp::CreateEnum { .. }
+            | SchemaOp::AddEnumValue { .. }
+            | SchemaOp::RenameEnumValue { .. }
             | SchemaOp::CreateColumn { .. }
             | SchemaOp::RenameColumn { .. }
             | SchemaOp::CreateIndex { .. }
@@ -437,6 +827,8 @@ impl SchemaOp<'_> {
             | SchemaOp::CreateExtension { .. }
             | SchemaOp::CreateUniqueConstraint { .. }
             | SchemaOp::RemoveUniqueConstraint { .. }
+            | SchemaOp::CreateCheck { .. }
+            | SchemaOp::DeleteCheck { .. }
             | SchemaOp::CreateForeignKeyReference { .. }
             | SchemaOp::DeleteForeignKeyReference { .. }
             | SchemaOp::SetColumnDefaultValue { .. }
@@ -478,6 +870,23 @@ impl SchemaOp<'_> {
                 enum_.sql_name()
             )),
             SchemaOp::DeleteEnum { .. } => None, // An extra enum in the database is not a problem
+            SchemaOp::AddEnumValue { enum_, value, .. } => Some(format!(
+                "The value `{value}` of the enum `{}` exists in the model, but does not exist in the database.",
+                enum_.sql_name()
+            )),
+            SchemaOp::RenameEnumValue {
+                enum_name,
+                old_value,
+                new_value,
+            } => Some(format!(
+                "The enum `{}` in the database has the value `{old_value}`, but the model expects `{new_value}`.",
+                enum_name.sql_name()
+            )),
+            SchemaOp::RecreateEnum { enum_, .. } => Some(format!(
+                "The values of the enum `{}` in the database do not match the model: [{}].",
+                enum_.sql_name(),
+                enum_.variants.join(", ")
+            )),
 
             SchemaOp::CreateColumn { table, column } => Some(format!(
                 "The column `{}` in the table `{}` exists in the model, but does not exist in the database table.",
@@ -544,6 +953,20 @@ impl SchemaOp<'_> {
                     table.sql_name()
                 ))
             }
+            SchemaOp::CreateCheck { table, check } => Some(format!(
+                "The model requires a check constraint named `{}` in table `{}`: {}",
+                check.name,
+                table.sql_name(),
+                check.expression
+            )),
+            SchemaOp::DeleteCheck { table, check } => {
+                // Extra check constraint may make inserts fail even if model allows it
+                Some(format!(
+                    "Extra check constraint `{}` in table `{}` found that is not required by the model.",
+                    check.name,
+                    table.sql_name()
+                ))
+            }
             SchemaOp::CreateForeignKeyReference {
                 table,
                 reference_columns,
@@ -602,4 +1025,210 @@ impl SchemaOp<'_> {
             )),
         }
     }
+
+    /// An issue that the user should be aware of before applying this operation.
+    pub fn issue(&self) -> Option<Issue> {
+        match self {
+            SchemaOp::RecreateEnum {
+                enum_,
+                removed_values,
+                columns,
+            } if !removed_values.is_empty() => {
+                let column_names = columns
+                    .iter()
+                    .map(|(table, column)| format!("{}.\"{}\"", table.sql_name(), column.name))
+                    .collect::<Vec<_>>();
+
+                let update_hint = if column_names.is_empty() {
+                    "".to_string()
+                } else {
+                    format!(
+                        " Update rows in {} that hold these values before applying the migration.",
+                        column_names.join(", ")
+                    )
+                };
+
+                Some(Issue::Warning(format!(
+                    "The value(s) {} of the enum `{}` are removed in the model. Postgres cannot drop enum values, so the migration recreates the type.{update_hint} If a value was renamed instead, choose to rename it when migrating interactively (or specify `rename-enum-value` in the interactions file) to keep the rows holding it.",
+                    removed_values
+                        .iter()
+                        .map(|value| format!("`{value}`"))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    enum_.sql_name(),
+                )))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn foreign_key_constraint_name(table: &TableSpec, name: &str) -> String {
+    format!(
+        "{}_{}_fk",
+        table.name.fully_qualified_name_with_sep("_"),
+        name
+    )
+}
+
+/// Add a constraint without checking existing rows (which would otherwise hold a lock on the table
+/// during the scan). The constraint still applies to new rows.
+fn not_valid(add_constraint_sql: String) -> String {
+    match add_constraint_sql.strip_suffix(';') {
+        Some(sql) => format!("{sql} NOT VALID;"),
+        None => format!("{add_constraint_sql} NOT VALID"),
+    }
+}
+
+/// The name of an index (which lives in the schema of its table) for use in SQL
+fn index_sql_name(table: &TableSpec, index: &IndexSpec) -> String {
+    SchemaObjectName::new(index.name.as_str(), table.name.schema.as_deref()).sql_name()
+}
+
+/// Check existing rows against a `NOT VALID` constraint. Validation doesn't block reads or writes.
+fn validation_sql(table: &TableSpec, constraint_name: &str) -> String {
+    format!(
+        "ALTER TABLE {} VALIDATE CONSTRAINT \"{constraint_name}\";",
+        table.sql_name()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use exo_sql_core::IndexColumn;
+    use exo_sql_pg::IndexKind;
+
+    use super::*;
+
+    fn events_table() -> TableSpec {
+        TableSpec::new(
+            SchemaObjectName::new("events", Some("log")),
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            true,
+        )
+    }
+
+    fn events_index() -> IndexSpec {
+        IndexSpec::new(
+            "events_title_idx".to_string(),
+            vec![IndexColumn::column("title")],
+            None,
+            IndexKind::DatabaseDefault,
+        )
+    }
+
+    #[test]
+    fn online_indexes_are_built_and_dropped_concurrently() {
+        let table = events_table();
+        let index = events_index();
+
+        let create = SchemaOp::CreateIndex {
+            table: &table,
+            index: &index,
+        };
+        let statement = create.to_sql_for_mode(MigrationMode::Online);
+        assert!(
+            statement.statement.starts_with(
+                "CREATE INDEX CONCURRENTLY \"events_title_idx\" ON \"log\".\"events\""
+            ),
+            "{}",
+            statement.statement
+        );
+        assert_eq!(
+            statement.pre_statements,
+            vec!["DROP INDEX CONCURRENTLY IF EXISTS \"log\".\"events_title_idx\";"]
+        );
+        assert!(create.runs_concurrently(MigrationMode::Online));
+        assert!(!create.runs_concurrently(MigrationMode::Standard));
+
+        let delete = SchemaOp::DeleteIndex {
+            table: &table,
+            index: &index,
+        };
+        assert_eq!(
+            delete.to_sql_for_mode(MigrationMode::Online).statement,
+            "DROP INDEX CONCURRENTLY IF EXISTS \"log\".\"events_title_idx\";"
+        );
+        assert_eq!(
+            delete.to_sql_for_mode(MigrationMode::Standard).statement,
+            "DROP INDEX \"log\".\"events_title_idx\";"
+        );
+        assert!(delete.runs_concurrently(MigrationMode::Online));
+    }
+
+    #[test]
+    fn online_checks_are_validated_separately() {
+        let table = events_table();
+        let check = CheckSpec::new("events_check".to_string(), "start_at < end_at".to_string());
+
+        let create = SchemaOp::CreateCheck {
+            table: &table,
+            check: &check,
+        };
+
+        let online = create.to_sql_for_mode(MigrationMode::Online);
+        assert_eq!(
+            online.statement,
+            "ALTER TABLE \"log\".\"events\" ADD CONSTRAINT \"events_check\" CHECK (start_at < end_at) NOT VALID;"
+        );
+        assert_eq!(
+            online.post_statements,
+            vec!["ALTER TABLE \"log\".\"events\" VALIDATE CONSTRAINT \"events_check\";"]
+        );
+        assert!(!create.runs_concurrently(MigrationMode::Online));
+
+        let standard = create.to_sql_for_mode(MigrationMode::Standard);
+        assert_eq!(
+            standard.statement,
+            "ALTER TABLE \"log\".\"events\" ADD CONSTRAINT \"events_check\" CHECK (start_at < end_at);"
+        );
+        assert!(standard.post_statements.is_empty());
+    }
+
+    #[test]
+    fn online_index_operations_take_no_blocking_locks() {
+        let table = events_table();
+        let index = events_index();
+
+        let create = SchemaOp::CreateIndex {
+            table: &table,
+            index: &index,
+        };
+        assert_eq!(
+            create.locks(MigrationMode::Standard),
+            vec![TableLock::new(
+                "\"log\".\"events\"".to_string(),
+                LockMode::Share
+            )]
+        );
+        assert_eq!(create.locks(MigrationMode::Online), vec![]);
+
+        let delete = SchemaOp::DeleteIndex {
+            table: &table,
+            index: &index,
+        };
+        assert_eq!(
+            delete.locks(MigrationMode::Standard),
+            vec![TableLock::new(
+                "\"log\".\"events\"".to_string(),
+                LockMode::AccessExclusive
+            )]
+        );
+        assert_eq!(delete.locks(MigrationMode::Online), vec![]);
+
+        // Online mode can't avoid blocking locks for other operations
+        let delete_table = SchemaOp::DeleteTable { table: &table };
+        for mode in [MigrationMode::Standard, MigrationMode::Online] {
+            assert_eq!(
+                delete_table.locks(mode),
+                vec![TableLock::new(
+                    "\"log\".\"events\"".to_string(),
+                    LockMode::AccessExclusive
+                )]
+            );
+        }
+    }
 }
@@ -153,7 +153,23 @@ pub fn diff<'a>(
             .iter()
             .find(|new_enum| old_enum.sql_name() == new_enum.sql_name())
         {
-            Some(new_enum) => changes.extend(old_enum.diff(new_enum)),
+            Some(new_enum) => {
+                // Recreating the enum must convert every column that uses it (even in a table
+                // outside the scope), since the old type can't be dropped otherwise
+                let columns = old
+                    .tables
+                    .iter()
+                    .flat_map(|table| {
+                        table
+                            .columns
+                            .iter()
+                            .filter(|column| old_enum.is_used_by(column))
+                            .map(move |column| (table, column))
+                    })
+                    .collect();
+
+                changes.extend(old_enum.diff(new_enum, columns))
+            }
 
             // enum does not exist, deletion
             None => changes.push(SchemaOp::DeleteEnum { enum_: old_enum }),
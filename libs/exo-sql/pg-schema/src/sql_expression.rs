@@ -0,0 +1,716 @@
+// Copyright Exograph, Inc. All rights reserved.
+//
+// Use of this software is governed by the Business Source License
+// included in the LICENSE file at the root of this repository.
+//
+// As of the Change Date specified in that file, in accordance with
+// the Business Source License, use of this software will be governed
+// by the Apache License, Version 2.0.
+
+/// Normalize an SQL expression (of an index column, a predicate, or a check constraint), so that an
+/// expression in the model compares equal to the one Postgres reports for it, while semantically
+/// different expressions don't.
+///
+/// Postgres reformats expressions: it parenthesizes each operation, adds casts to literals and
+/// columns (`'active'::text`, `(email)::text`), quotes negative numbers (`'-10'::integer`), and
+/// rewrites some operators (`IN` becomes `= ANY (ARRAY[...])`). So we parse the expression and
+/// print it back with every operation parenthesized (which keeps the grouping the expression
+/// implies), without the casts of literals and columns, and with unquoted identifiers folded to
+/// lowercase (as Postgres does). String literals and quoted identifiers stay as they are.
+///
+/// An expression we can't parse is compared as written (ignoring only whitespace), so that it is
+/// never mistaken for a different one.
+pub(crate) fn normalize_expression(expression: &str) -> String {
+    tokenize(expression)
+        .and_then(|tokens| {
+            let mut parser = Parser {
+                tokens,
+                position: 0,
+            };
+            let expr = parser.parse_expr(0)?;
+            (parser.position == parser.tokens.len()).then_some(expr)
+        })
+        .map(|expr| expr.to_string())
+        .unwrap_or_else(|| expression.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// An unquoted word (an identifier or a keyword)
+    Word(String),
+    /// A quoted identifier (without the quotes)
+    QuotedIdent(String),
+    /// A string literal (with the quotes)
+    String(String),
+    Number(String),
+    Operator(String),
+    LeftParen,
+    RightParen,
+    LeftBracket,
+    RightBracket,
+    Comma,
+    Dot,
+    DoubleColon,
+}
+
+fn tokenize(expression: &str) -> Option<Vec<Token>> {
+    const OPERATOR_CHARS: &str = "+-*/<>=~!@#%^&|`?";
+
+    let mut tokens = vec![];
+    let mut chars = expression.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '\'' | '"' => {
+                chars.next();
+                let mut content = String::new();
+                loop {
+                    let next = chars.next()?;
+                    if next == c {
+                        // A doubled quote continues the literal (or identifier)
+                        if chars.next_if_eq(&c).is_none() {
+                            break;
+                        }
+                        content.push(c);
+                    }
+                    content.push(next);
+                }
+                tokens.push(if c == '\'' {
+                    Token::String(format!("'{content}'"))
+                } else {
+                    Token::QuotedIdent(content)
+                });
+            }
+            c if c.is_ascii_digit() => {
+                let mut number = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || *c == '.') {
+                    number.push(c);
+                }
+                if let Some(e) = chars.next_if(|c| matches!(c, 'e' | 'E')) {
+                    number.push(e);
+                    if let Some(sign) = chars.next_if(|c| matches!(c, '+' | '-')) {
+                        number.push(sign);
+                    }
+                    while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+                        number.push(c);
+                    }
+                }
+                tokens.push(Token::Number(number));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut word = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || matches!(c, '_' | '$'))
+                {
+                    word.push(c);
+                }
+                tokens.push(Token::Word(word));
+            }
+            ':' => {
+                chars.next();
+                chars.next_if_eq(&':')?;
+                tokens.push(Token::DoubleColon);
+            }
+            '(' | ')' | '[' | ']' | ',' | '.' => {
+                chars.next();
+                tokens.push(match c {
+                    '(' => Token::LeftParen,
+                    ')' => Token::RightParen,
+                    '[' => Token::LeftBracket,
+                    ']' => Token::RightBracket,
+                    ',' => Token::Comma,
+                    _ => Token::Dot,
+                });
+            }
+            c if OPERATOR_CHARS.contains(c) => {
+                let mut operator: String = chars
+                    .clone()
+                    .take_while(|c| OPERATOR_CHARS.contains(*c))
+                    .collect();
+                // As in Postgres, a multi-character operator can't end in `+` or `-` unless it
+                // has one of the characters below (so `>=-10` is `>=` followed by `-10`)
+                if !operator.contains(['~', '!', '@', '#', '%', '^', '&', '|', '`', '?']) {
+                    while operator.len() > 1 && operator.ends_with(['+', '-']) {
+                        operator.pop();
+                    }
+                }
+                for _ in 0..operator.len() {
+                    chars.next();
+                }
+                tokens.push(Token::Operator(operator));
+            }
+            _ => return None,
+        }
+    }
+
+    Some(tokens)
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    /// A literal in its normalized form (such as `'active'`, `-10`, or `true`)
+    Literal(String),
+    /// A (possibly qualified) column with each part normalized
+    Column(Vec<String>),
+    Function(String, Vec<Expr>),
+    Not(Box<Expr>),
+    Negate(Box<Expr>),
+    Binary(Box<Expr>, String, Box<Expr>),
+    /// `IS [NOT] NULL` and such
+    Is(Box<Expr>, String),
+    Cast(Box<Expr>, String),
+    Array(Vec<Expr>),
+    /// `expr op ANY (array)` or `expr op ALL (array)`
+    Quantified(Box<Expr>, String, String, Box<Expr>),
+}
+
+impl std::fmt::Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fn list(exprs: &[Expr]) -> String {
+            exprs
+                .iter()
+                .map(|expr| expr.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+
+        match self {
+            Expr::Literal(literal) => write!(f, "{literal}"),
+            Expr::Column(parts) => write!(f, "{}", parts.join(".")),
+            Expr::Function(name, args) => write!(f, "{name}({})", list(args)),
+            Expr::Not(expr) => write!(f, "(NOT {expr})"),
+            Expr::Negate(expr) => write!(f, "(- {expr})"),
+            Expr::Binary(left, op, right) => write!(f, "({left} {op} {right})"),
+            Expr::Is(expr, test) => write!(f, "({expr} IS {test})"),
+            Expr::Cast(expr, typ) => write!(f, "({expr})::{typ}"),
+            Expr::Array(elements) => write!(f, "ARRAY[{}]", list(elements)),
+            Expr::Quantified(left, op, quantifier, right) => {
+                write!(f, "({left} {op} {quantifier} ({right}))")
+            }
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+// Binding powers (following the precedence of Postgres operators)
+const OR: u8 = 1;
+const AND: u8 = 2;
+const NOT: u8 = 3;
+const IS: u8 = 4;
+const COMPARISON: u8 = 5;
+const LIKE_IN_BETWEEN: u8 = 6;
+const OTHER_OPERATOR: u8 = 7;
+const ADDITIVE: u8 = 8;
+const MULTIPLICATIVE: u8 = 9;
+const EXPONENT: u8 = 10;
+const UNARY: u8 = 11;
+const CAST: u8 = 12;
+
+const NUMERIC_TYPES: [&str; 6] = [
+    "smallint",
+    "integer",
+    "bigint",
+    "numeric",
+    "real",
+    "double precision",
+];
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn peek_keyword(&self, offset: usize) -> Option<String> {
+        match self.tokens.get(self.position + offset) {
+            Some(Token::Word(word)) => Some(word.to_ascii_uppercase()),
+            _ => None,
+        }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Option<()> {
+        (self.next()? == expected).then_some(())
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Option<()> {
+        (self.peek_keyword(0)? == keyword).then(|| self.position += 1)
+    }
+
+    fn parse_expr(&mut self, min_binding_power: u8) -> Option<Expr> {
+        let mut left = self.parse_prefix()?;
+
+        loop {
+            let keyword = self.peek_keyword(0);
+            let binding_power = match (self.peek(), keyword.as_deref()) {
+                (Some(Token::DoubleColon), _) => CAST,
+                (_, Some("OR")) => OR,
+                (_, Some("AND")) => AND,
+                (_, Some("IS" | "ISNULL" | "NOTNULL")) => IS,
+                (_, Some("LIKE" | "ILIKE" | "IN" | "BETWEEN")) => LIKE_IN_BETWEEN,
+                (_, Some("NOT"))
+                    if matches!(
+                        self.peek_keyword(1).as_deref(),
+                        Some("LIKE" | "ILIKE" | "IN" | "BETWEEN")
+                    ) =>
+                {
+                    LIKE_IN_BETWEEN
+                }
+                (Some(Token::Operator(operator)), _) => match operator.as_str() {
+                    "=" | "<>" | "!=" | "<" | ">" | "<=" | ">=" => COMPARISON,
+                    "+" | "-" => ADDITIVE,
+                    "*" | "/" | "%" => MULTIPLICATIVE,
+                    "^" => EXPONENT,
+                    _ => OTHER_OPERATOR,
+                },
+                _ => break,
+            };
+
+            if binding_power < min_binding_power {
+                break;
+            }
+
+            left = match self.next()? {
+                Token::DoubleColon => {
+                    let typ = self.parse_type_name()?;
+                    cast(left, typ)
+                }
+                Token::Operator(operator) => {
+                    let operator = if operator == "!=" {
+                        "<>".to_string()
+                    } else {
+                        operator
+                    };
+
+                    match self.peek_keyword(0).as_deref() {
+                        Some(quantifier @ ("ANY" | "SOME" | "ALL"))
+                            if binding_power == COMPARISON =>
+                        {
+                            let quantifier = if quantifier == "SOME" {
+                                "ANY".to_string()
+                            } else {
+                                quantifier.to_string()
+                            };
+                            self.position += 1;
+                            self.expect(Token::LeftParen)?;
+                            let right = self.parse_expr(0)?;
+                            self.expect(Token::RightParen)?;
+                            Expr::Quantified(Box::new(left), operator, quantifier, Box::new(right))
+                        }
+                        _ => {
+                            let right = self.parse_expr(binding_power + 1)?;
+                            Expr::Binary(Box::new(left), operator, Box::new(right))
+                        }
+                    }
+                }
+                Token::Word(word) => match word.to_ascii_uppercase().as_str() {
+                    "OR" | "AND" => {
+                        let right = self.parse_expr(binding_power + 1)?;
+                        Expr::Binary(Box::new(left), word.to_ascii_uppercase(), Box::new(right))
+                    }
+                    "ISNULL" => Expr::Is(Box::new(left), "NULL".to_string()),
+                    "NOTNULL" => Expr::Is(Box::new(left), "NOT NULL".to_string()),
+                    "IS" => self.parse_is(left)?,
+                    "NOT" => {
+                        let operation = self.next_word()?;
+                        self.parse_like_in_between(left, &operation, true)?
+                    }
+                    operation => self.parse_like_in_between(left, operation, false)?,
+                },
+                _ => return None,
+            };
+        }
+
+        Some(left)
+    }
+
+    fn parse_prefix(&mut self) -> Option<Expr> {
+        match self.next()? {
+            Token::String(literal) => Some(Expr::Literal(literal)),
+            Token::Number(number) => Some(Expr::Literal(number)),
+            Token::QuotedIdent(name) => self.parse_column_or_function(quoted_identifier(&name)),
+            Token::LeftParen => {
+                let expr = self.parse_expr(0)?;
+                self.expect(Token::RightParen)?;
+                Some(expr)
+            }
+            Token::Operator(operator) if operator == "-" || operator == "+" => {
+                let expr = self.parse_expr(UNARY)?;
+                Some(match (operator.as_str(), expr) {
+                    ("+", expr) => expr,
+                    (_, Expr::Literal(number)) if is_number(&number) => {
+                        Expr::Literal(negate_number(&number))
+                    }
+                    (_, expr) => Expr::Negate(Box::new(expr)),
+                })
+            }
+            Token::Word(word) => match word.to_ascii_uppercase().as_str() {
+                "NOT" => Some(Expr::Not(Box::new(self.parse_expr(NOT)?))),
+                "TRUE" | "FALSE" | "NULL" => Some(Expr::Literal(word.to_ascii_lowercase())),
+                "ARRAY" => {
+                    self.expect(Token::LeftBracket)?;
+                    let elements = self.parse_list(Token::RightBracket)?;
+                    Some(Expr::Array(elements))
+                }
+                // Expressions we don't normalize (and so compare as written)
+                "CASE" | "EXISTS" | "SELECT" | "ANY" | "ALL" | "SOME" | "INTERVAL" => None,
+                _ => match self.peek() {
+                    // A typed literal (such as `DATE '2024-01-01'`), which Postgres reports as a cast
+                    Some(Token::String(_)) => {
+                        let Some(Token::String(literal)) = self.next() else {
+                            return None;
+                        };
+                        Some(cast(
+                            Expr::Literal(literal),
+                            normalize_type_name(&word.to_ascii_lowercase()),
+                        ))
+                    }
+                    _ => self.parse_column_or_function(word.to_ascii_lowercase()),
+                },
+            },
+            _ => None,
+        }
+    }
+
+    fn parse_column_or_function(&mut self, name: String) -> Option<Expr> {
+        let mut parts = vec![name];
+
+        while self.peek() == Some(&Token::Dot) {
+            self.position += 1;
+            parts.push(match self.next()? {
+                Token::Word(word) => word.to_ascii_lowercase(),
+                Token::QuotedIdent(name) => quoted_identifier(&name),
+                _ => return None,
+            });
+        }
+
+        if self.peek() == Some(&Token::LeftParen) {
+            self.position += 1;
+            let args = self.parse_list(Token::RightParen)?;
+            Some(Expr::Function(parts.join("."), args))
+        } else {
+            Some(Expr::Column(parts))
+        }
+    }
+
+    /// Parse a comma-separated list of expressions up to (and including) the closing token
+    fn parse_list(&mut self, closing: Token) -> Option<Vec<Expr>> {
+        let mut exprs = vec![];
+
+        if self.peek() == Some(&closing) {
+            self.position += 1;
+            return Some(exprs);
+        }
+
+        loop {
+            exprs.push(self.parse_expr(0)?);
+            match self.next()? {
+                Token::Comma => continue,
+                token if token == closing => return Some(exprs),
+                _ => return None,
+            }
+        }
+    }
+
+    fn next_word(&mut self) -> Option<String> {
+        match self.next()? {
+            Token::Word(word) => Some(word.to_ascii_uppercase()),
+            _ => None,
+        }
+    }
+
+    fn parse_is(&mut self, left: Expr) -> Option<Expr> {
+        let mut test = self.next_word()?;
+        if test == "NOT" {
+            test = format!("NOT {}", self.next_word()?);
+        }
+
+        match test.as_str() {
+            "NULL" | "NOT NULL" | "TRUE" | "NOT TRUE" | "FALSE" | "NOT FALSE" | "UNKNOWN"
+            | "NOT UNKNOWN" => Some(Expr::Is(Box::new(left), test)),
+            "DISTINCT" | "NOT DISTINCT" => {
+                self.expect_keyword("FROM")?;
+                let right = self.parse_expr(IS + 1)?;
+                Some(Expr::Binary(
+                    Box::new(left),
+                    format!("IS {test} FROM"),
+                    Box::new(right),
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Parse the rest of `LIKE`, `ILIKE`, `IN`, or `BETWEEN` (possibly negated), written the way
+    /// Postgres reports them
+    fn parse_like_in_between(
+        &mut self,
+        left: Expr,
+        operation: &str,
+        negated: bool,
+    ) -> Option<Expr> {
+        match operation {
+            "LIKE" | "ILIKE" => {
+                let operator = match (operation, negated) {
+                    ("LIKE", false) => "~~",
+                    ("LIKE", true) => "!~~",
+                    (_, false) => "~~*",
+                    (_, true) => "!~~*",
+                };
+                let right = self.parse_expr(LIKE_IN_BETWEEN + 1)?;
+                Some(Expr::Binary(
+                    Box::new(left),
+                    operator.to_string(),
+                    Box::new(right),
+                ))
+            }
+            "IN" => {
+                // `x IN (a, b)` is `x = ANY (ARRAY[a, b])` and `x NOT IN (a, b)` is
+                // `x <> ALL (ARRAY[a, b])`
+                self.expect(Token::LeftParen)?;
+                let elements = self.parse_list(Token::RightParen)?;
+                let (operator, quantifier) = if negated { ("<>", "ALL") } else { ("=", "ANY") };
+                Some(Expr::Quantified(
+                    Box::new(left),
+                    operator.to_string(),
+                    quantifier.to_string(),
+                    Box::new(Expr::Array(elements)),
+                ))
+            }
+            "BETWEEN" => {
+                // `x BETWEEN a AND b` is `x >= a AND x <= b` and `x NOT BETWEEN a AND b` is
+                // `x < a OR x > b`
+                let low = self.parse_expr(LIKE_IN_BETWEEN + 1)?;
+                self.expect_keyword("AND")?;
+                let high = self.parse_expr(LIKE_IN_BETWEEN + 1)?;
+                let (low_operator, connective, high_operator) = if negated {
+                    ("<", "OR", ">")
+                } else {
+                    (">=", "AND", "<=")
+                };
+                Some(Expr::Binary(
+                    Box::new(Expr::Binary(
+                        Box::new(left.clone()),
+                        low_operator.to_string(),
+                        Box::new(low),
+                    )),
+                    connective.to_string(),
+                    Box::new(Expr::Binary(
+                        Box::new(left),
+                        high_operator.to_string(),
+                        Box::new(high),
+                    )),
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_type_name(&mut self) -> Option<String> {
+        // Words that may follow the first word of a type name (such as `character varying`)
+        const CONTINUATIONS: [&str; 6] =
+            ["VARYING", "PRECISION", "WITH", "WITHOUT", "TIME", "ZONE"];
+
+        let mut name = match self.next()? {
+            Token::Word(word) => word.to_ascii_lowercase(),
+            Token::QuotedIdent(name) => quoted_identifier(&name),
+            _ => return None,
+        };
+
+        while self.peek() == Some(&Token::Dot) {
+            self.position += 1;
+            name.push('.');
+            name.push_str(&match self.next()? {
+                Token::Word(word) => word.to_ascii_lowercase(),
+                Token::QuotedIdent(part) => quoted_identifier(&part),
+                _ => return None,
+            });
+        }
+
+        while let Some(word) = self.peek_keyword(0)
+            && CONTINUATIONS.contains(&word.as_str())
+        {
+            self.position += 1;
+            name.push(' ');
+            name.push_str(&word.to_ascii_lowercase());
+        }
+
+        let mut name = normalize_type_name(&name);
+
+        if self.peek() == Some(&Token::LeftParen) {
+            self.position += 1;
+            let modifiers = self.parse_list(Token::RightParen)?;
+            name.push_str(&format!(
+                "({})",
+                modifiers
+                    .iter()
+                    .map(|modifier| modifier.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+
+        while self.peek() == Some(&Token::LeftBracket) {
+            self.position += 1;
+            self.expect(Token::RightBracket)?;
+            name.push_str("[]");
+        }
+
+        Some(name)
+    }
+}
+
+/// Casts of literals and columns are dropped (since Postgres adds them to match the types of
+/// operands), except that a quoted number cast to a numeric type (as Postgres reports negative
+/// numbers) becomes the number. Casts of other expressions are kept.
+fn cast(expr: Expr, typ: String) -> Expr {
+    match expr {
+        Expr::Literal(literal)
+            if NUMERIC_TYPES.contains(&typ.as_str())
+                && literal.starts_with('\'')
+                && is_number(literal.trim_matches('\'')) =>
+        {
+            Expr::Literal(literal.trim_matches('\'').to_string())
+        }
+        expr @ (Expr::Literal(_) | Expr::Column(_)) => expr,
+        expr => Expr::Cast(Box::new(expr), typ),
+    }
+}
+
+fn normalize_type_name(name: &str) -> String {
+    match name {
+        "int" | "int4" => "integer",
+        "int2" => "smallint",
+        "int8" => "bigint",
+        "float4" => "real",
+        "float8" => "double precision",
+        "decimal" => "numeric",
+        "bool" => "boolean",
+        "varchar" => "character varying",
+        "char" => "character",
+        "timestamp" => "timestamp without time zone",
+        "timestamptz" => "timestamp with time zone",
+        "time" => "time without time zone",
+        "timetz" => "time with time zone",
+        name => name,
+    }
+    .to_string()
+}
+
+/// A quoted identifier is the same as an unquoted one if it needs no quotes (all lowercase and
+/// starting with a letter)
+fn quoted_identifier(name: &str) -> String {
+    let needs_quotes = !name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_lowercase() || c == '_')
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+
+    if needs_quotes {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    } else {
+        name.to_string()
+    }
+}
+
+fn is_number(value: &str) -> bool {
+    value.parse::<f64>().is_ok()
+}
+
+fn negate_number(number: &str) -> String {
+    match number.strip_prefix('-') {
+        Some(positive) => positive.to_string(),
+        None => format!("-{number}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalized_expressions_match_postgres_formatting() {
+        // The model's expression and what `pg_get_indexdef`/`pg_get_expr` report for it
+        for (model, live) in [
+            ("lower(email)", "lower((email)::text)"),
+            ("deleted_at IS NULL", "(deleted_at IS NULL)"),
+            ("a IS NULL AND (b OR c)", "((a IS NULL) AND (b OR c))"),
+            ("a OR b AND c", "(a OR (b AND c))"),
+            (
+                "status = 'active' AND \"userId\" > 10",
+                "((status = 'active'::text) AND (\"userId\" > 10))",
+            ),
+            ("name = 'It''s'", "(name = 'It''s'::character varying(255))"),
+            (
+                "\"temperature\" >= -10 AND \"temperature\" <= 40.5",
+                "temperature >= '-10'::integer AND temperature <= 40.5::double precision",
+            ),
+            (
+                "status IN ('active', 'pending')",
+                "(status = ANY (ARRAY['active'::text, 'pending'::text]))",
+            ),
+            (
+                "price BETWEEN 0 AND 100",
+                "((price >= 0) AND (price <= 100))",
+            ),
+            ("name LIKE 'A%'", "((name)::text ~~ 'A%'::text)"),
+            (
+                "char_length(name) <= 20",
+                "(char_length((name)::text) <= 20)",
+            ),
+            (
+                "(data->>'kind')::int > 0",
+                "(((data ->> 'kind'::text))::integer > 0)",
+            ),
+        ] {
+            assert_eq!(
+                normalize_expression(model),
+                normalize_expression(live),
+                "{model} vs {live}"
+            );
+        }
+    }
+
+    #[test]
+    fn normalized_expressions_keep_differences() {
+        for (model, live) in [
+            ("lower(email)", "upper(email)"),
+            ("status = 'Active'", "status = 'active'"),
+            ("status = 'A'", "status = 'a'"),
+            ("deleted_at IS NULL", "deleted_at IS NOT NULL"),
+            ("code = '10'", "code = 10"),
+            ("(a OR b) AND c", "a OR b AND c"),
+            ("a - (b - c)", "a - b - c"),
+            ("\"userId\" > 10", "userId > 10"),
+            ("status IN ('a', 'b')", "status NOT IN ('a', 'b')"),
+            ("(data->>'kind')::int > 0", "(data->>'kind')::text > '0'"),
+            ("CASE WHEN a THEN b END", "CASE WHEN a THEN c END"),
+        ] {
+            assert_ne!(
+                normalize_expression(model),
+                normalize_expression(live),
+                "{model} vs {live}"
+            );
+        }
+    }
+
+    #[test]
+    fn unparsed_expressions_compare_as_written() {
+        assert_eq!(
+            normalize_expression("CASE  WHEN a THEN\n b END"),
+            "CASE WHEN a THEN b END"
+        );
+    }
+}
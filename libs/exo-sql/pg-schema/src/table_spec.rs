@@ -17,10 +17,12 @@ use exo_sql_pg::physical_column_type::PhysicalColumnTypeExt;
 use exo_sql_pg_connect::DatabaseClient;
 
 use crate::DebugPrintTo;
+use crate::check_spec::CheckSpec;
 use crate::column_spec::{
     ColumnAttribute, ColumnReferenceSpec, ColumnSpec, physical_column_type_from_string,
 };
 use crate::constraint::{Constraints, sorted_comma_list};
+use crate::database_spec::CHANGE_FEED_TRIGGER_PREFIX;
 use crate::enum_spec::EnumSpec;
 use crate::index_spec::IndexSpec;
 use crate::issue::WithIssues;
@@ -29,6 +31,8 @@ use crate::trigger_spec::TriggerSpec;
 
 const PHYSICAL_TABLE_COLUMNS_QUERY: &str = "SELECT column_name FROM information_schema.columns WHERE table_name = $1 AND table_schema = $2";
 
+const TABLE_COMMENT_QUERY: &str = "SELECT obj_description(pg_class.oid, 'pg_class') as comment FROM pg_class JOIN pg_namespace ON pg_class.relnamespace = pg_namespace.oid WHERE pg_class.relname = $1 AND pg_namespace.nspname = $2";
+
 const MATERIALIZED_VIEW_COLUMNS_QUERY: &str = r#"
   SELECT attribute.attname as column_name, pg_catalog.format_type(attribute.atttypid, attribute.atttypmod) as column_type, attribute.attnotnull as not_null
     FROM pg_attribute attribute JOIN pg_class t on attribute.attrelid = t.oid JOIN pg_namespace schema on t.relnamespace = schema.oid
@@ -39,8 +43,11 @@ pub struct TableSpec {
     pub name: SchemaObjectName,
     pub columns: Vec<ColumnSpec>,
     pub indices: Vec<IndexSpec>,
+    pub checks: Vec<CheckSpec>,
     pub triggers: Vec<TriggerSpec>,
     pub managed: bool,
+    /// The comment on the table (`COMMENT ON TABLE`), which migrations don't manage
+    pub comment: Option<String>,
 }
 
 impl TableSpec {
@@ -48,6 +55,7 @@ impl TableSpec {
         name: SchemaObjectName,
         columns: Vec<ColumnSpec>,
         indices: Vec<IndexSpec>,
+        checks: Vec<CheckSpec>,
         triggers: Vec<TriggerSpec>,
         managed: bool,
     ) -> Self {
@@ -55,8 +63,10 @@ impl TableSpec {
             name,
             columns,
             indices,
+            checks,
             triggers,
             managed,
+            comment: None,
         }
     }
 
@@ -69,7 +79,12 @@ impl TableSpec {
             name: self.name.clone(),
             columns: vec![],
             indices: vec![],
+            checks: vec![],
             managed: self.managed,
+            change_feed: self
+                .triggers
+                .iter()
+                .any(|trigger| trigger.name.starts_with(CHANGE_FEED_TRIGGER_PREFIX)),
         }
     }
 
@@ -100,6 +115,14 @@ impl TableSpec {
 
         let constraints = Constraints::from_live_db(client, &table_name).await?;
 
+        // Sort by name, since the query doesn't order the constraints
+        let mut checks: Vec<_> = constraints
+            .checks
+            .into_iter()
+            .map(|check| CheckSpec::new(check.constraint_name, check.expression))
+            .collect();
+        checks.sort_by(|a, b| a.name.cmp(&b.name));
+
         // Mapping from this table's column name to its reference spec
         let mut column_reference_mapping: HashMap<String, Vec<ColumnReferenceSpec>> =
             HashMap::new();
@@ -202,13 +225,24 @@ impl TableSpec {
         } = TriggerSpec::from_live_db(client, &table_name).await?;
         issues.extend(triggers_issues);
 
+        let comment = client
+            .query_opt(
+                TABLE_COMMENT_QUERY,
+                &[&table_name.name, &table_name.schema_name()],
+            )
+            .await
+            .map_err(DatabaseError::driver)?
+            .and_then(|row| row.get("comment"));
+
         Ok(WithIssues {
             value: TableSpec {
                 name: table_name,
                 columns,
                 indices,
+                checks,
                 triggers,
                 managed: true,
+                comment,
             },
             issues,
         })
@@ -246,12 +280,14 @@ impl TableSpec {
                     is_nullable: !not_null,
                     unique_constraints: vec![],
                     default_value: None,
+                    previous_name: None,
+                    comment: None,
                 }
             })
             .collect();
 
         Ok(WithIssues {
-            value: TableSpec::new(table_name, columns, vec![], vec![], true),
+            value: TableSpec::new(table_name, columns, vec![], vec![], vec![], true),
             issues,
         })
     }
@@ -278,6 +314,25 @@ impl TableSpec {
         required_extensions
     }
 
+    /// Does the name follow the way Exograph names check constraints (`<table>_check`,
+    /// `<table>_check<n>` for `@check`, and `<table>_<column>_check` for validations)?
+    fn is_exograph_check_name(&self, name: &str) -> bool {
+        let Some(suffix) = name
+            .strip_prefix(self.name.name.as_str())
+            .and_then(|suffix| suffix.strip_prefix('_'))
+        else {
+            return false;
+        };
+
+        suffix
+            .strip_prefix("check")
+            .is_some_and(|index| index.chars().all(|c| c.is_ascii_digit()))
+            || self
+                .columns
+                .iter()
+                .any(|column| suffix == format!("{}_check", column.name))
+    }
+
     pub fn diff<'a>(&'a self, new: &'a Self) -> Vec<SchemaOp<'a>> {
         // If the exograph model is not managed, we don't need to apply any changes
         if !new.managed {
@@ -358,6 +413,41 @@ impl TableSpec {
             }
         }
 
+        for existing_check in self.checks.iter() {
+            let new_check = new
+                .checks
+                .iter()
+                .find(|c| c.name == existing_check.name || c.effectively_eq(existing_check));
+
+            match new_check {
+                Some(new_check) => {
+                    changes.extend(existing_check.diff(new_check, self, new));
+                }
+                // Leave constraints that Exograph didn't create (such as ones added by hand) alone
+                None if self.is_exograph_check_name(&existing_check.name) => {
+                    changes.push(SchemaOp::DeleteCheck {
+                        table: self,
+                        check: existing_check,
+                    });
+                }
+                None => {}
+            }
+        }
+
+        for new_check in new.checks.iter() {
+            let existing_check = self
+                .checks
+                .iter()
+                .find(|c| c.name == new_check.name || c.effectively_eq(new_check));
+
+            if existing_check.is_none() {
+                changes.push(SchemaOp::CreateCheck {
+                    table: new,
+                    check: new_check,
+                });
+            }
+        }
+
         let self_unique_constraints = self.named_unique_constraints();
         let new_unique_constraints = new.named_unique_constraints();
 
@@ -524,6 +614,10 @@ impl TableSpec {
             post_statements.push(index.creation_sql(&self.name));
         }
 
+        for check in self.checks.iter() {
+            post_statements.push(check.creation_sql(&self.name));
+        }
+
         for trigger in self.triggers.iter() {
             post_statements.push(trigger.creation_sql(&self.name));
         }
@@ -625,6 +719,13 @@ impl DebugPrintTo for TableSpec {
             }
         }
 
+        if !self.checks.is_empty() {
+            writeln!(writer, "{}  - Checks:", indent_str)?;
+            for check in &self.checks {
+                check.debug_print_to(writer, indent + 4)?;
+            }
+        }
+
         if !self.triggers.is_empty() {
             writeln!(writer, "{}  - Triggers:", indent_str)?;
             for trigger in &self.triggers {
@@ -655,3 +756,78 @@ impl DebugPrintTo for TableSpec {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use exo_sql_pg::StringColumnType;
+
+    use super::*;
+
+    fn events(checks: Vec<CheckSpec>) -> TableSpec {
+        let title = ColumnSpec {
+            name: "title".to_string(),
+            typ: Box::new(StringColumnType { max_length: None }),
+            reference_specs: None,
+            is_pk: false,
+            is_nullable: false,
+            unique_constraints: vec![],
+            default_value: None,
+            previous_name: None,
+            comment: None,
+        };
+
+        TableSpec::new(
+            SchemaObjectName::new("events", None),
+            vec![title],
+            vec![],
+            checks,
+            vec![],
+            true,
+        )
+    }
+
+    fn check(name: &str, expression: &str) -> CheckSpec {
+        CheckSpec::new(name.to_string(), expression.to_string())
+    }
+
+    fn deleted_checks<'a>(changes: &'a [SchemaOp<'a>]) -> Vec<&'a str> {
+        changes
+            .iter()
+            .filter_map(|change| match change {
+                SchemaOp::DeleteCheck { check, .. } => Some(check.name.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn removed_exograph_checks_are_dropped() {
+        let old = events(vec![
+            check("events_check", "start_at < end_at"),
+            check("events_check1", "capacity > 0"),
+            check("events_title_check", "char_length(title) <= 100"),
+        ]);
+        let new = events(vec![]);
+
+        let changes = old.diff(&new);
+
+        assert_eq!(
+            deleted_checks(&changes),
+            vec!["events_check", "events_check1", "events_title_check"]
+        );
+    }
+
+    #[test]
+    fn unmanaged_checks_are_kept() {
+        let old = events(vec![
+            check("events_title_not_blank", "title <> ''"),
+            check("events_checked_at_check", "checked_at > created_at"),
+            check("venues_check", "capacity > 0"),
+        ]);
+        let new = events(vec![]);
+
+        let changes = old.diff(&new);
+
+        assert!(deleted_checks(&changes).is_empty(), "{changes:?}");
+    }
+}
@@ -113,7 +113,9 @@ impl DatabaseBuilder {
                     name: SchemaObjectName::new(&name, None),
                     columns: vec![],
                     indices: vec![],
+                    checks: vec![],
                     managed: true,
+                    change_feed: false,
                 });
                 (table_id, name, columns)
             })
@@ -137,6 +139,7 @@ impl DatabaseBuilder {
                     },
                     update_sync: false,
                     column_references: None,
+                    previous_name: None,
                 })
                 .collect();
 
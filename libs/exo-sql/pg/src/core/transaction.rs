@@ -92,6 +92,19 @@ impl<'a> TransactionScript<'a> {
         self.steps.len() > 1
     }
 
+    /// Does the script only read data (and thus may run on a read replica)?
+    pub fn is_read_only(&self) -> bool {
+        self.steps.iter().all(|step| match step {
+            TransactionStep::Concrete(step) => matches!(step.operation, SQLOperation::Select(_)),
+            TransactionStep::Precheck(_) => true,
+            _ => false,
+        })
+    }
+
+    pub fn steps(&self) -> &[TransactionStep<'a>] {
+        &self.steps
+    }
+
     /// Consume the script and return the steps
     pub fn into_steps(self) -> Vec<TransactionStep<'a>> {
         self.steps
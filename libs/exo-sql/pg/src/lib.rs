@@ -79,10 +79,10 @@ pub use core::sql_operation::SQLOperation;
 
 // Re-exports from exo-sql-core so consumers can depend only on exo-sql-pg
 pub use exo_sql_core::{
-    ColumnId, ColumnPathLink, ColumnReference, Database, DatabaseError, Limit, ManyToOne,
-    ManyToOneId, Offset, OneToMany, OneToManyId, Ordering, PhysicalColumn, PhysicalColumnPath,
-    PhysicalEnum, PhysicalIndex, PhysicalTable, RelationColumnPair, RelationId, SchemaObjectName,
-    TableId,
+    ColumnId, ColumnPathLink, ColumnReference, Database, DatabaseError, IndexColumn, IndexTarget,
+    Limit, ManyToOne, ManyToOneId, Offset, OneToMany, OneToManyId, Ordering, PhysicalCheck,
+    PhysicalColumn, PhysicalColumnPath, PhysicalEnum, PhysicalIndex, PhysicalTable,
+    RelationColumnPair, RelationId, SchemaObjectName, TableId,
     physical_column::{get_mto_relation_for_columns, get_otm_relation_for_columns},
 };
 